@@ -2,11 +2,24 @@ use std::collections::HashSet;
 use std::io::Write;
 
 const MIGRATIONS_DIR: &str = "migrations";
+const LANG_DIR: &str = "res/lang";
 const DEFAULT_LANG_FILE: &str = "res/lang/en.ftl";
+const OPENAPI_FILE: &str = "openapi/openapi.json";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let out_dir = std::env::var("OUT_DIR")?;
 
+    {
+        println!("cargo:rerun-if-changed={}", OPENAPI_FILE);
+
+        // served as-is by the API, so make sure it at least parses
+        let spec: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(OPENAPI_FILE)?)?;
+        if !spec["paths"].is_object() {
+            return Err(format!("{} is missing paths", OPENAPI_FILE).into());
+        }
+    }
+
     {
         println!("cargo:rerun-if-changed={}", MIGRATIONS_DIR);
 
@@ -112,10 +125,50 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
     }
+    {
+        println!("cargo:rerun-if-changed={}", LANG_DIR);
+
+        let default_content = std::fs::read_to_string(DEFAULT_LANG_FILE)?;
+        let default_keys: HashSet<&str> = message_ids(&default_content, DEFAULT_LANG_FILE)?;
+
+        for entry in std::fs::read_dir(LANG_DIR)? {
+            let path = entry?.path();
+            if path.extension() != Some(std::ffi::OsStr::new("ftl")) {
+                continue;
+            }
+
+            let path = path.to_str().unwrap();
+            let content = std::fs::read_to_string(path)?;
+
+            // translations can be missing messages, but shouldn't have any the code won't use
+            for key in message_ids(&content, path)? {
+                if !default_keys.contains(key) {
+                    return Err(format!("{} has unknown message {}", path, key).into());
+                }
+            }
+        }
+    }
 
     Ok(())
 }
 
+fn message_ids<'a>(
+    content: &'a str,
+    path: &str,
+) -> Result<HashSet<&'a str>, Box<dyn std::error::Error>> {
+    let ast = fluent_syntax::parser::parse_runtime(content)
+        .map_err(|(_, errors)| format!("Failed to parse {}: {:?}", path, errors))?;
+
+    Ok(ast
+        .body
+        .into_iter()
+        .filter_map(|entry| match entry {
+            fluent_syntax::ast::Entry::Message(msg) => Some(msg.id.name),
+            _ => None,
+        })
+        .collect())
+}
+
 fn discover_args_for_pattern<'a>(
     target: &mut Vec<&'a str>,
     pattern: &fluent_syntax::ast::Pattern<&'a str>,
@@ -0,0 +1,10 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN allow_dislikes;
+	ALTER TABLE site DROP COLUMN allow_dislikes;
+
+	DELETE FROM reply_like WHERE is_dislike;
+	DELETE FROM post_like WHERE is_dislike;
+
+	ALTER TABLE reply_like DROP COLUMN is_dislike;
+	ALTER TABLE post_like DROP COLUMN is_dislike;
+COMMIT;
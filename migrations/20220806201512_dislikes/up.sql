@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE post_like ADD COLUMN is_dislike BOOLEAN NOT NULL DEFAULT FALSE;
+	ALTER TABLE reply_like ADD COLUMN is_dislike BOOLEAN NOT NULL DEFAULT FALSE;
+
+	ALTER TABLE site ADD COLUMN allow_dislikes BOOLEAN NOT NULL DEFAULT FALSE;
+	ALTER TABLE community ADD COLUMN allow_dislikes BOOLEAN NOT NULL DEFAULT TRUE;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE post DROP COLUMN archived_author;
+	ALTER TABLE reply DROP COLUMN archived_author;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN archived_author TEXT;
+	ALTER TABLE reply ADD COLUMN archived_author TEXT;
+COMMIT;
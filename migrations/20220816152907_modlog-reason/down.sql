@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN modlog_reasons_public;
+	ALTER TABLE modlog_event DROP COLUMN reason;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE modlog_event ADD COLUMN reason TEXT;
+	ALTER TABLE community ADD COLUMN modlog_reasons_public BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
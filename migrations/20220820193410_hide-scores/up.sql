@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN hide_scores_minutes INTEGER;
+COMMIT;
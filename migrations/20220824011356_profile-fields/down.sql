@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE person DROP COLUMN profile_fields;
+	ALTER TABLE person DROP COLUMN display_name;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN display_name TEXT;
+	ALTER TABLE person ADD COLUMN profile_fields JSONB NOT NULL DEFAULT ('[]');
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	DROP INDEX community_followers_count;
+	DROP TRIGGER community_follow_update_count ON community_follow;
+	DROP FUNCTION community_follow_update_count();
+	ALTER TABLE community DROP COLUMN followers_count;
+COMMIT;
@@ -0,0 +1,23 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN followers_count BIGINT NOT NULL DEFAULT (0);
+	UPDATE community SET followers_count=(SELECT COUNT(*) FROM community_follow WHERE community_follow.community = community.id AND accepted);
+
+	CREATE FUNCTION community_follow_update_count() RETURNS TRIGGER AS $$
+		BEGIN
+			IF TG_OP IN ('UPDATE', 'DELETE') THEN
+				IF OLD.accepted THEN
+					UPDATE community SET followers_count = followers_count - 1 WHERE id = OLD.community;
+				END IF;
+			END IF;
+			IF TG_OP IN ('INSERT', 'UPDATE') THEN
+				IF NEW.accepted THEN
+					UPDATE community SET followers_count = followers_count + 1 WHERE id = NEW.community;
+				END IF;
+			END IF;
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER community_follow_update_count AFTER INSERT OR UPDATE OR DELETE ON community_follow FOR EACH ROW EXECUTE PROCEDURE community_follow_update_count();
+
+	CREATE INDEX community_followers_count ON community (followers_count DESC, id);
+COMMIT;
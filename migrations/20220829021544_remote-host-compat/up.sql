@@ -0,0 +1,12 @@
+BEGIN;
+	CREATE TABLE remote_host (
+		host TEXT PRIMARY KEY,
+		last_probe_at TIMESTAMPTZ NOT NULL,
+		software_name TEXT,
+		software_version TEXT,
+		no_shared_inbox BOOLEAN NOT NULL DEFAULT (FALSE),
+		inline_objects BOOLEAN NOT NULL DEFAULT (FALSE),
+		override_no_shared_inbox BOOLEAN,
+		override_inline_objects BOOLEAN
+	);
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	DROP TABLE reply_draft;
+COMMIT;
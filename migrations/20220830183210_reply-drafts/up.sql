@@ -0,0 +1,11 @@
+BEGIN;
+	CREATE TABLE reply_draft (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		parent BIGINT REFERENCES reply ON DELETE CASCADE,
+		content_text TEXT,
+		content_markdown TEXT,
+		updated_at TIMESTAMPTZ NOT NULL
+	);
+	CREATE UNIQUE INDEX reply_draft_key ON reply_draft (person, post, (COALESCE(parent, 0)));
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN;
+	CREATE TABLE person_follow (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		follower BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		ap_id TEXT UNIQUE,
+		created TIMESTAMPTZ NOT NULL,
+		PRIMARY KEY (person, follower)
+	);
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE flag DROP COLUMN category_rule;
+	ALTER TABLE flag DROP COLUMN category;
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+	ALTER TABLE flag ADD COLUMN category TEXT;
+	ALTER TABLE flag ADD COLUMN category_rule INTEGER;
+	CREATE INDEX ON flag (category);
+COMMIT;
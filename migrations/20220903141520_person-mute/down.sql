@@ -0,0 +1,3 @@
+BEGIN;
+	DROP TABLE person_mute;
+COMMIT;
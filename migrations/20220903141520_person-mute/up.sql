@@ -0,0 +1,12 @@
+BEGIN;
+	CREATE TABLE person_mute (
+		id BIGSERIAL PRIMARY KEY,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		community BIGINT REFERENCES community ON DELETE CASCADE,
+		by_person BIGINT REFERENCES person ON DELETE SET NULL,
+		created_at TIMESTAMPTZ NOT NULL,
+		expires_at TIMESTAMPTZ NOT NULL,
+		reason TEXT
+	);
+	CREATE UNIQUE INDEX ON person_mute (person, (COALESCE(community, 0)));
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN unlisted BOOLEAN NOT NULL DEFAULT (FALSE);
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+	ALTER TABLE task DROP COLUMN run_after;
+	ALTER TABLE person DROP COLUMN notify_community_announcements;
+	DROP TABLE community_announcement;
+COMMIT;
@@ -0,0 +1,11 @@
+BEGIN;
+	CREATE TABLE community_announcement (
+		id BIGSERIAL PRIMARY KEY,
+		community BIGINT NOT NULL REFERENCES community ON DELETE CASCADE,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		created_by BIGINT REFERENCES person ON DELETE SET NULL,
+		created_at TIMESTAMPTZ NOT NULL
+	);
+	ALTER TABLE person ADD COLUMN notify_community_announcements BOOLEAN NOT NULL DEFAULT TRUE;
+	ALTER TABLE task ADD COLUMN run_after TIMESTAMPTZ;
+COMMIT;
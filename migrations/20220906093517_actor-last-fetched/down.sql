@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN last_fetched_at;
+	ALTER TABLE person DROP COLUMN last_fetched_at;
+COMMIT;
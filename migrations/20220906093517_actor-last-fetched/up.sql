@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN last_fetched_at TIMESTAMPTZ;
+	ALTER TABLE community ADD COLUMN last_fetched_at TIMESTAMPTZ;
+COMMIT;
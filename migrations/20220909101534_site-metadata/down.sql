@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE site DROP COLUMN default_sort;
+	ALTER TABLE site DROP COLUMN contact;
+	ALTER TABLE site DROP COLUMN rules_html;
+	ALTER TABLE site DROP COLUMN rules_markdown;
+	ALTER TABLE site DROP COLUMN title;
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE site ADD COLUMN title TEXT;
+	ALTER TABLE site ADD COLUMN rules_markdown TEXT;
+	ALTER TABLE site ADD COLUMN rules_html TEXT;
+	ALTER TABLE site ADD COLUMN contact TEXT;
+	ALTER TABLE site ADD COLUMN default_sort TEXT NOT NULL DEFAULT ('hot') CHECK (default_sort IN ('hot', 'new', 'top'));
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	DROP INDEX notification_parent_post;
+	DROP INDEX notification_parent_reply;
+	DROP INDEX notification_reply;
+	DROP INDEX notification_to_user_created_at;
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	CREATE INDEX notification_to_user_created_at ON notification (to_user, created_at DESC, id DESC);
+	CREATE INDEX notification_reply ON notification (reply);
+	CREATE INDEX notification_parent_reply ON notification (parent_reply);
+	CREATE INDEX notification_parent_post ON notification (parent_post);
+COMMIT;
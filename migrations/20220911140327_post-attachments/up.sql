@@ -0,0 +1,11 @@
+BEGIN;
+	CREATE TABLE post_attachment (
+		id BIGSERIAL PRIMARY KEY,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		position INTEGER NOT NULL,
+		url TEXT NOT NULL,
+		media_type TEXT,
+		alt_text TEXT,
+		UNIQUE (post, position)
+	);
+COMMIT;
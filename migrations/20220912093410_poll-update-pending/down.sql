@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE poll DROP COLUMN update_pending;
+COMMIT;
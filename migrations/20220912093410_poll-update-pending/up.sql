@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE poll ADD COLUMN update_pending BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
@@ -0,0 +1,8 @@
+BEGIN;
+	DROP INDEX person_local_username_idx;
+	-- deleted accounts all share the same username, which the old index would reject
+	UPDATE person SET username=('[deleted]' || id) WHERE local AND deleted;
+	CREATE UNIQUE INDEX person_lower_idx ON person (LOWER(username)) WHERE local;
+
+	ALTER TABLE person DROP COLUMN deleted;
+COMMIT;
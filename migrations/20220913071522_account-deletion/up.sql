@@ -0,0 +1,6 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN deleted BOOLEAN NOT NULL DEFAULT FALSE;
+
+	DROP INDEX person_lower_idx;
+	CREATE UNIQUE INDEX person_local_username_idx ON person (LOWER(username)) WHERE local AND NOT deleted;
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN moved_to;
+	ALTER TABLE community DROP COLUMN also_known_as;
+
+	ALTER TABLE person DROP COLUMN moved_to;
+	ALTER TABLE person DROP COLUMN also_known_as;
+COMMIT;
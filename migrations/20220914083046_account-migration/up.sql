@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN also_known_as TEXT[] NOT NULL DEFAULT '{}';
+	ALTER TABLE person ADD COLUMN moved_to TEXT;
+
+	ALTER TABLE community ADD COLUMN also_known_as TEXT[] NOT NULL DEFAULT '{}';
+	ALTER TABLE community ADD COLUMN moved_to TEXT;
+COMMIT;
@@ -0,0 +1,10 @@
+BEGIN;
+	CREATE TABLE webhook (
+		id BIGSERIAL PRIMARY KEY,
+		url TEXT NOT NULL,
+		secret TEXT NOT NULL,
+		events TEXT[] NOT NULL,
+		created_by BIGINT REFERENCES person ON DELETE SET NULL,
+		created_at TIMESTAMPTZ NOT NULL
+	);
+COMMIT;
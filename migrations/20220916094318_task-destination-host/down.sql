@@ -0,0 +1,6 @@
+BEGIN;
+	ALTER TABLE remote_host DROP COLUMN delivery_paused;
+
+	DROP INDEX task_pending_destination_host;
+	ALTER TABLE task DROP COLUMN destination_host;
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE task ADD COLUMN destination_host TEXT;
+	UPDATE task SET destination_host=substring(params->>'inbox' FROM '^[a-z]+://([^/]+)') WHERE kind='deliver_to_inbox' AND state='pending';
+	CREATE INDEX task_pending_destination_host ON task (destination_host) WHERE state='pending';
+
+	ALTER TABLE remote_host ADD COLUMN delivery_paused BOOLEAN NOT NULL DEFAULT (FALSE);
+COMMIT;
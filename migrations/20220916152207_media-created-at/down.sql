@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE media DROP COLUMN created_at;
+COMMIT;
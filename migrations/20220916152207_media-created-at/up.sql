@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE media ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp);
+COMMIT;
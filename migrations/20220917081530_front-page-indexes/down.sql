@@ -0,0 +1,4 @@
+BEGIN;
+	DROP INDEX community_follow_follower;
+	DROP INDEX post_local_created;
+COMMIT;
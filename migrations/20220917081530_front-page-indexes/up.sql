@@ -0,0 +1,4 @@
+BEGIN;
+	CREATE INDEX post_local_created ON post (created DESC, id DESC) WHERE local AND NOT deleted;
+	CREATE INDEX community_follow_follower ON community_follow (follower) WHERE accepted;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	DROP INDEX notification_to_user_unread;
+	ALTER TABLE notification DROP COLUMN read;
+COMMIT;
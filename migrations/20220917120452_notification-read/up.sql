@@ -0,0 +1,5 @@
+BEGIN;
+	ALTER TABLE notification ADD COLUMN read BOOLEAN NOT NULL DEFAULT (FALSE);
+	UPDATE notification SET read=TRUE WHERE created_at <= (SELECT last_checked_notifications FROM person WHERE id=notification.to_user);
+	CREATE INDEX notification_to_user_unread ON notification (to_user) WHERE NOT read;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE remote_host DROP COLUMN unreachable_since;
+	ALTER TABLE remote_host DROP COLUMN blocked;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE remote_host ADD COLUMN blocked BOOLEAN NOT NULL DEFAULT (FALSE);
+	ALTER TABLE remote_host ADD COLUMN unreachable_since TIMESTAMPTZ;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	DROP TABLE post_flair;
+	DROP TABLE community_flair;
+COMMIT;
@@ -0,0 +1,17 @@
+BEGIN;
+	CREATE TABLE community_flair (
+		id BIGSERIAL PRIMARY KEY,
+		community BIGINT NOT NULL REFERENCES community ON DELETE CASCADE,
+		name TEXT NOT NULL,
+		created_at TIMESTAMPTZ NOT NULL,
+		UNIQUE (community, name)
+	);
+
+	CREATE TABLE post_flair (
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		flair BIGINT NOT NULL REFERENCES community_flair ON DELETE CASCADE,
+		PRIMARY KEY (post, flair)
+	);
+
+	CREATE INDEX post_flair_flair ON post_flair (flair);
+COMMIT;
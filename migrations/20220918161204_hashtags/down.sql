@@ -0,0 +1,5 @@
+BEGIN;
+	DROP TABLE reply_hashtag;
+	DROP TABLE post_hashtag;
+	DROP TABLE hashtag;
+COMMIT;
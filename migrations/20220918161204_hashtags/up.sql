@@ -0,0 +1,22 @@
+BEGIN;
+	CREATE TABLE hashtag (
+		id BIGSERIAL PRIMARY KEY,
+		name TEXT NOT NULL UNIQUE
+	);
+
+	CREATE TABLE post_hashtag (
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		hashtag BIGINT NOT NULL REFERENCES hashtag ON DELETE CASCADE,
+		PRIMARY KEY (post, hashtag)
+	);
+
+	CREATE INDEX post_hashtag_hashtag ON post_hashtag (hashtag);
+
+	CREATE TABLE reply_hashtag (
+		reply BIGINT NOT NULL REFERENCES reply ON DELETE CASCADE,
+		hashtag BIGINT NOT NULL REFERENCES hashtag ON DELETE CASCADE,
+		PRIMARY KEY (reply, hashtag)
+	);
+
+	CREATE INDEX reply_hashtag_hashtag ON reply_hashtag (hashtag);
+COMMIT;
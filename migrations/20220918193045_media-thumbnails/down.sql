@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE media DROP COLUMN blurhash;
+	ALTER TABLE media DROP COLUMN thumbnail_path;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE media ADD COLUMN thumbnail_path TEXT;
+	ALTER TABLE media ADD COLUMN blurhash TEXT;
+COMMIT;
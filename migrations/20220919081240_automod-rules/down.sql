@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE flag DROP COLUMN automod_rule;
+	DELETE FROM flag WHERE person IS NULL;
+	ALTER TABLE flag ALTER COLUMN person SET NOT NULL;
+
+	DROP TABLE automod_rule;
+COMMIT;
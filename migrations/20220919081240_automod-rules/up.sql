@@ -0,0 +1,16 @@
+BEGIN;
+	CREATE TABLE automod_rule (
+		id BIGSERIAL PRIMARY KEY,
+		community BIGINT REFERENCES community ON DELETE CASCADE,
+		kind TEXT NOT NULL,
+		value TEXT NOT NULL,
+		action TEXT NOT NULL,
+		created_by BIGINT REFERENCES person ON DELETE SET NULL,
+		created_at TIMESTAMPTZ NOT NULL
+	);
+
+	CREATE INDEX automod_rule_community ON automod_rule (community);
+
+	ALTER TABLE flag ALTER COLUMN person DROP NOT NULL;
+	ALTER TABLE flag ADD COLUMN automod_rule BIGINT REFERENCES automod_rule ON DELETE SET NULL;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE reply DROP COLUMN pending_approval;
+	ALTER TABLE post DROP COLUMN pending_approval;
+COMMIT;
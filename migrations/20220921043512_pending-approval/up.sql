@@ -0,0 +1,6 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN pending_approval BOOLEAN NOT NULL DEFAULT FALSE;
+	ALTER TABLE reply ADD COLUMN pending_approval BOOLEAN NOT NULL DEFAULT FALSE;
+	CREATE INDEX ON post (community) WHERE pending_approval;
+	CREATE INDEX ON reply (post) WHERE pending_approval;
+COMMIT;
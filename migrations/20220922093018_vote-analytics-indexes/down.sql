@@ -0,0 +1,6 @@
+BEGIN;
+	DROP INDEX reply_author;
+	DROP INDEX post_author;
+	DROP INDEX reply_like_person;
+	DROP INDEX post_like_person;
+COMMIT;
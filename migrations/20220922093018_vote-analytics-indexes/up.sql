@@ -0,0 +1,6 @@
+BEGIN;
+	CREATE INDEX post_like_person ON post_like (person);
+	CREATE INDEX reply_like_person ON reply_like (person);
+	CREATE INDEX post_author ON post (author);
+	CREATE INDEX reply_author ON reply (author);
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	DROP TABLE community_activity;
+COMMIT;
@@ -0,0 +1,11 @@
+BEGIN;
+	CREATE TABLE community_activity (
+		community BIGINT PRIMARY KEY REFERENCES community ON DELETE CASCADE,
+		posts_count BIGINT NOT NULL,
+		comments_count BIGINT NOT NULL,
+		participants_count BIGINT NOT NULL,
+		score BIGINT NOT NULL,
+		updated_at TIMESTAMPTZ NOT NULL
+	);
+	CREATE INDEX community_activity_score ON community_activity (score DESC, community);
+COMMIT;
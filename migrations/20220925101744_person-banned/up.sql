@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN banned BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
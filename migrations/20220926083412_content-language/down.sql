@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE person DROP COLUMN preferred_languages;
+	ALTER TABLE post DROP COLUMN language;
+COMMIT;
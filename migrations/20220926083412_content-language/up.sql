@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN language TEXT;
+	ALTER TABLE person ADD COLUMN preferred_languages TEXT[] NOT NULL DEFAULT '{}';
+COMMIT;
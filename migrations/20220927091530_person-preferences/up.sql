@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN preferences JSONB NOT NULL DEFAULT '{}';
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	DROP TABLE reply_mute;
+	DROP TABLE post_mute;
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+	CREATE TABLE post_mute (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		PRIMARY KEY (person, post)
+	);
+
+	CREATE TABLE reply_mute (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		reply BIGINT NOT NULL REFERENCES reply ON DELETE CASCADE,
+		PRIMARY KEY (person, reply)
+	);
+COMMIT;
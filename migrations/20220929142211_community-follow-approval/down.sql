@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN members_only_posting;
+	ALTER TABLE community DROP COLUMN approve_follows;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN approve_follows BOOLEAN NOT NULL DEFAULT FALSE;
+	ALTER TABLE community ADD COLUMN members_only_posting BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
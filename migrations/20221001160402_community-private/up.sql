@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN private BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE modlog_event ADD COLUMN domain TEXT;
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN href_canonical TEXT;
+	UPDATE post SET href_canonical=href WHERE href IS NOT NULL;
+	CREATE INDEX post_href_canonical ON post (href_canonical);
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE person DROP COLUMN registration_ip;
+	ALTER TABLE login DROP COLUMN ip;
+COMMIT;
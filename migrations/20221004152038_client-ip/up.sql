@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE login ADD COLUMN ip INET;
+	ALTER TABLE person ADD COLUMN registration_ip INET;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE notification DROP COLUMN login_attempt;
+	DROP TABLE login_attempt;
+COMMIT;
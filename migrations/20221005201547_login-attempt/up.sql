@@ -0,0 +1,12 @@
+BEGIN;
+	CREATE TABLE login_attempt (
+		id BIGSERIAL PRIMARY KEY,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		created TIMESTAMPTZ NOT NULL,
+		success BOOLEAN NOT NULL,
+		ip INET,
+		user_agent TEXT
+	);
+	CREATE INDEX login_attempt_person ON login_attempt (person, id DESC);
+	ALTER TABLE notification ADD COLUMN login_attempt BIGINT REFERENCES login_attempt ON DELETE CASCADE;
+COMMIT;
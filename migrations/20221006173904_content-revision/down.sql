@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE reply DROP COLUMN updated;
+	DROP TABLE content_revision;
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN;
+	CREATE TABLE content_revision (
+		id BIGSERIAL PRIMARY KEY,
+		reply BIGINT NOT NULL REFERENCES reply ON DELETE CASCADE,
+		created_at TIMESTAMPTZ NOT NULL,
+		content_text TEXT,
+		content_markdown TEXT,
+		content_html TEXT
+	);
+	CREATE INDEX content_revision_reply ON content_revision (reply, id);
+	ALTER TABLE reply ADD COLUMN updated TIMESTAMPTZ;
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN views BIGINT NOT NULL DEFAULT 0;
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	DROP TRIGGER post_like_update_score ON post_like;
+	DROP FUNCTION post_like_update_score();
+	DROP TRIGGER post_insert_score ON post;
+	DROP FUNCTION post_insert_score();
+	DROP TABLE post_score;
+COMMIT;
@@ -0,0 +1,34 @@
+BEGIN;
+	CREATE TABLE post_score (
+		post BIGINT PRIMARY KEY REFERENCES post ON DELETE CASCADE,
+		created TIMESTAMPTZ NOT NULL,
+		score BIGINT NOT NULL DEFAULT (0)
+	);
+	INSERT INTO post_score (post, created, score) SELECT id, created, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id AND post_like.person IS DISTINCT FROM post.author) FROM post;
+
+	CREATE FUNCTION post_insert_score() RETURNS TRIGGER AS $$
+		BEGIN
+			INSERT INTO post_score (post, created) VALUES (NEW.id, NEW.created);
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER post_insert_score AFTER INSERT ON post FOR EACH ROW EXECUTE PROCEDURE post_insert_score();
+
+	-- votes by the post's author are not counted, same as the previous top sort
+	CREATE FUNCTION post_like_update_score() RETURNS TRIGGER AS $$
+		BEGIN
+			IF TG_OP IN ('UPDATE', 'DELETE') THEN
+				UPDATE post_score SET score = score - (CASE WHEN OLD.is_dislike THEN -1 ELSE 1 END) WHERE post = OLD.post AND OLD.person IS DISTINCT FROM (SELECT author FROM post WHERE id = OLD.post);
+			END IF;
+			IF TG_OP IN ('INSERT', 'UPDATE') THEN
+				UPDATE post_score SET score = score + (CASE WHEN NEW.is_dislike THEN -1 ELSE 1 END) WHERE post = NEW.post AND NEW.person IS DISTINCT FROM (SELECT author FROM post WHERE id = NEW.post);
+			END IF;
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER post_like_update_score AFTER INSERT OR UPDATE OR DELETE ON post_like FOR EACH ROW EXECUTE PROCEDURE post_like_update_score();
+
+	-- all-time and long windows scan in score order, short windows scan by creation time
+	CREATE INDEX post_score_top ON post_score (score DESC, post DESC, created);
+	CREATE INDEX post_score_created ON post_score (created DESC, score, post);
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+	DROP INDEX community_remote_host;
+	DROP INDEX person_remote_host;
+	ALTER TABLE remote_host DROP COLUMN last_delivery_at;
+COMMIT;
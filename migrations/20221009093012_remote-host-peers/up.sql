@@ -0,0 +1,10 @@
+BEGIN;
+	ALTER TABLE remote_host ADD COLUMN last_delivery_at TIMESTAMPTZ;
+
+	-- hosts seen before probing was added, these will be probed the next time they are seen
+	INSERT INTO remote_host (host, last_probe_at) SELECT DISTINCT host, 'epoch' FROM (SELECT substring(ap_id FROM '^[a-z]+://([^/]+)') AS host FROM person WHERE NOT local UNION SELECT substring(ap_id FROM '^[a-z]+://([^/]+)') FROM community WHERE NOT local) AS seen WHERE host IS NOT NULL ON CONFLICT (host) DO NOTHING;
+
+	-- for counting known actors per peer
+	CREATE INDEX person_remote_host ON person ((substring(ap_id FROM '^[a-z]+://([^/]+)'))) WHERE NOT local;
+	CREATE INDEX community_remote_host ON community ((substring(ap_id FROM '^[a-z]+://([^/]+)'))) WHERE NOT local;
+COMMIT;
@@ -0,0 +1,10 @@
+BEGIN;
+	CREATE TABLE relay (
+		id BIGSERIAL PRIMARY KEY,
+		ap_id TEXT NOT NULL UNIQUE,
+		inbox TEXT NOT NULL,
+		accepted BOOLEAN NOT NULL DEFAULT FALSE,
+		created_by BIGINT REFERENCES person ON DELETE SET NULL,
+		created_at TIMESTAMPTZ NOT NULL
+	);
+COMMIT;
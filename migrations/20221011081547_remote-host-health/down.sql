@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE remote_host DROP COLUMN dead;
+	ALTER TABLE remote_host DROP COLUMN consecutive_failures;
+COMMIT;
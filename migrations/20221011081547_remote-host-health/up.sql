@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE remote_host ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT (0);
+	ALTER TABLE remote_host ADD COLUMN dead BOOLEAN NOT NULL DEFAULT (FALSE);
+
+	-- matches the previous cutoff for skipping unreachable hosts
+	UPDATE remote_host SET dead=TRUE WHERE unreachable_since < current_timestamp - INTERVAL '3 DAYS';
+COMMIT;
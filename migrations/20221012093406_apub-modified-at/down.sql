@@ -0,0 +1,12 @@
+BEGIN;
+	DROP TRIGGER post_flair_set_modified_at ON post_flair;
+	DROP FUNCTION post_flair_set_modified_at();
+	DROP TRIGGER post_modified_at ON post;
+	DROP TRIGGER community_modified_at ON community;
+	DROP TRIGGER person_modified_at ON person;
+	DROP FUNCTION set_modified_at();
+
+	ALTER TABLE post DROP COLUMN modified_at;
+	ALTER TABLE community DROP COLUMN modified_at;
+	ALTER TABLE person DROP COLUMN modified_at;
+COMMIT;
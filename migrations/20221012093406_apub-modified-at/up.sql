@@ -0,0 +1,29 @@
+BEGIN;
+	ALTER TABLE person ADD COLUMN modified_at TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp);
+	ALTER TABLE community ADD COLUMN modified_at TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp);
+	ALTER TABLE post ADD COLUMN modified_at TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp);
+
+	CREATE FUNCTION set_modified_at() RETURNS TRIGGER AS $$
+		BEGIN
+			NEW.modified_at := current_timestamp;
+			RETURN NEW;
+		END;
+	$$ LANGUAGE plpgsql;
+
+	-- only columns that appear in the ActivityPub representation are compared
+	CREATE TRIGGER person_modified_at BEFORE UPDATE ON person FOR EACH ROW WHEN ((OLD.username, OLD.public_key, OLD.description, OLD.description_html, OLD.avatar, OLD.is_bot, OLD.display_name, OLD.profile_fields, OLD.deleted, OLD.also_known_as, OLD.moved_to) IS DISTINCT FROM (NEW.username, NEW.public_key, NEW.description, NEW.description_html, NEW.avatar, NEW.is_bot, NEW.display_name, NEW.profile_fields, NEW.deleted, NEW.also_known_as, NEW.moved_to)) EXECUTE PROCEDURE set_modified_at();
+	CREATE TRIGGER community_modified_at BEFORE UPDATE ON community FOR EACH ROW WHEN ((OLD.name, OLD.public_key, OLD.description, OLD.description_html, OLD.deleted, OLD.private) IS DISTINCT FROM (NEW.name, NEW.public_key, NEW.description, NEW.description_html, NEW.deleted, NEW.private)) EXECUTE PROCEDURE set_modified_at();
+	CREATE TRIGGER post_modified_at BEFORE UPDATE ON post FOR EACH ROW WHEN ((OLD.title, OLD.href, OLD.content_text, OLD.content_markdown, OLD.content_html, OLD.sensitive, OLD.deleted, OLD.language, OLD.community) IS DISTINCT FROM (NEW.title, NEW.href, NEW.content_text, NEW.content_markdown, NEW.content_html, NEW.sensitive, NEW.deleted, NEW.language, NEW.community)) EXECUTE PROCEDURE set_modified_at();
+
+	CREATE FUNCTION post_flair_set_modified_at() RETURNS TRIGGER AS $$
+		BEGIN
+			IF TG_OP = 'DELETE' THEN
+				UPDATE post SET modified_at = current_timestamp WHERE id = OLD.post;
+			ELSE
+				UPDATE post SET modified_at = current_timestamp WHERE id = NEW.post;
+			END IF;
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER post_flair_set_modified_at AFTER INSERT OR DELETE ON post_flair FOR EACH ROW EXECUTE PROCEDURE post_flair_set_modified_at();
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	DROP TRIGGER post_modified_at ON post;
+	CREATE TRIGGER post_modified_at BEFORE UPDATE ON post FOR EACH ROW WHEN ((OLD.title, OLD.href, OLD.content_text, OLD.content_markdown, OLD.content_html, OLD.sensitive, OLD.deleted, OLD.language, OLD.community) IS DISTINCT FROM (NEW.title, NEW.href, NEW.content_text, NEW.content_markdown, NEW.content_html, NEW.sensitive, NEW.deleted, NEW.language, NEW.community)) EXECUTE PROCEDURE set_modified_at();
+
+	ALTER TABLE post DROP COLUMN locked;
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN locked BOOLEAN NOT NULL DEFAULT (FALSE);
+
+	-- sticky and locked are now part of the ActivityPub representation
+	DROP TRIGGER post_modified_at ON post;
+	CREATE TRIGGER post_modified_at BEFORE UPDATE ON post FOR EACH ROW WHEN ((OLD.title, OLD.href, OLD.content_text, OLD.content_markdown, OLD.content_html, OLD.sensitive, OLD.deleted, OLD.language, OLD.community, OLD.sticky, OLD.locked) IS DISTINCT FROM (NEW.title, NEW.href, NEW.content_text, NEW.content_markdown, NEW.content_html, NEW.sensitive, NEW.deleted, NEW.language, NEW.community, NEW.sticky, NEW.locked)) EXECUTE PROCEDURE set_modified_at();
+COMMIT;
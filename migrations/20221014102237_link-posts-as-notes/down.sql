@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community DROP COLUMN link_posts_as_notes;
+COMMIT;
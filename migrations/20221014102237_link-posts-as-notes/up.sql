@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN link_posts_as_notes BOOLEAN NOT NULL DEFAULT (FALSE);
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	DROP TABLE local_reply_reaction_undo;
+	DROP TABLE local_post_reaction_undo;
+	DROP TABLE reply_reaction;
+	DROP TABLE post_reaction;
+COMMIT;
@@ -0,0 +1,39 @@
+BEGIN;
+	CREATE TABLE post_reaction (
+		id BIGSERIAL PRIMARY KEY,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		content TEXT NOT NULL,
+		local BOOLEAN NOT NULL,
+		ap_id TEXT UNIQUE,
+		created_local TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp),
+		UNIQUE (post, person, content)
+	);
+
+	CREATE TABLE reply_reaction (
+		id BIGSERIAL PRIMARY KEY,
+		reply BIGINT NOT NULL REFERENCES reply ON DELETE CASCADE,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		content TEXT NOT NULL,
+		local BOOLEAN NOT NULL,
+		ap_id TEXT UNIQUE,
+		created_local TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp),
+		UNIQUE (reply, person, content)
+	);
+
+	CREATE TABLE local_post_reaction_undo (
+		id UUID PRIMARY KEY,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		reaction BIGINT NOT NULL,
+		content TEXT NOT NULL
+	);
+
+	CREATE TABLE local_reply_reaction_undo (
+		id UUID PRIMARY KEY,
+		reply BIGINT NOT NULL REFERENCES reply ON DELETE CASCADE,
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		reaction BIGINT NOT NULL,
+		content TEXT NOT NULL
+	);
+COMMIT;
@@ -0,0 +1,8 @@
+BEGIN;
+	CREATE TABLE post_hide (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		post BIGINT NOT NULL REFERENCES post ON DELETE CASCADE,
+		created_at TIMESTAMPTZ NOT NULL DEFAULT (current_timestamp),
+		PRIMARY KEY (person, post)
+	);
+COMMIT;
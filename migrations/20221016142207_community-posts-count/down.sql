@@ -0,0 +1,8 @@
+BEGIN;
+	DROP TRIGGER reply_update_community_activity ON reply;
+	DROP FUNCTION reply_update_community_activity();
+	DROP TRIGGER post_update_community_counts ON post;
+	DROP FUNCTION post_update_community_counts();
+	ALTER TABLE community DROP COLUMN last_activity;
+	ALTER TABLE community DROP COLUMN posts_count;
+COMMIT;
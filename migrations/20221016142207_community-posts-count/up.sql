@@ -0,0 +1,44 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN posts_count BIGINT NOT NULL DEFAULT (0);
+	ALTER TABLE community ADD COLUMN last_activity TIMESTAMPTZ;
+	UPDATE community SET posts_count=(SELECT COUNT(*) FROM post WHERE post.community = community.id AND post.approved AND NOT post.deleted);
+	UPDATE community SET last_activity=LEAST(current_timestamp, GREATEST(
+		(SELECT MAX(created) FROM post WHERE post.community = community.id AND post.approved AND NOT post.deleted),
+		(SELECT MAX(reply.created) FROM reply INNER JOIN post ON (post.id = reply.post) WHERE post.community = community.id AND NOT reply.deleted)
+	));
+
+	CREATE FUNCTION post_update_community_counts() RETURNS TRIGGER AS $$
+		BEGIN
+			IF TG_OP IN ('UPDATE', 'DELETE') THEN
+				IF OLD.approved AND NOT OLD.deleted THEN
+					UPDATE community SET posts_count = posts_count - 1 WHERE id = OLD.community;
+				END IF;
+			END IF;
+			IF TG_OP IN ('INSERT', 'UPDATE') THEN
+				IF NEW.approved AND NOT NEW.deleted THEN
+					UPDATE community SET posts_count = posts_count + 1 WHERE id = NEW.community;
+				END IF;
+			END IF;
+			-- a post counts as activity once it shows up in the community
+			IF TG_OP = 'INSERT' THEN
+				IF NEW.approved THEN
+					UPDATE community SET last_activity = GREATEST(last_activity, LEAST(NEW.created, current_timestamp)) WHERE id = NEW.community;
+				END IF;
+			ELSIF TG_OP = 'UPDATE' THEN
+				IF NEW.approved AND NOT OLD.approved THEN
+					UPDATE community SET last_activity = GREATEST(last_activity, LEAST(NEW.created, current_timestamp)) WHERE id = NEW.community;
+				END IF;
+			END IF;
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER post_update_community_counts AFTER INSERT OR UPDATE OF approved, deleted, community OR DELETE ON post FOR EACH ROW EXECUTE PROCEDURE post_update_community_counts();
+
+	CREATE FUNCTION reply_update_community_activity() RETURNS TRIGGER AS $$
+		BEGIN
+			UPDATE community SET last_activity = GREATEST(last_activity, LEAST(NEW.created, current_timestamp)) WHERE id = (SELECT community FROM post WHERE id = NEW.post);
+			RETURN NULL;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER reply_update_community_activity AFTER INSERT ON reply FOR EACH ROW EXECUTE PROCEDURE reply_update_community_activity();
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+	DROP INDEX community_follow_ap_id;
+	DROP TRIGGER community_follow_sync_accepted ON community_follow;
+	DROP FUNCTION community_follow_sync_accepted();
+	ALTER TABLE community_follow DROP COLUMN state;
+	DROP TYPE follow_state;
+COMMIT;
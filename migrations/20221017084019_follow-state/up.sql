@@ -0,0 +1,20 @@
+BEGIN;
+	CREATE TYPE follow_state AS ENUM ('pending', 'accepted', 'rejected');
+
+	ALTER TABLE community_follow ADD COLUMN state follow_state;
+	UPDATE community_follow SET state=(CASE WHEN accepted THEN 'accepted' ELSE 'pending' END)::follow_state;
+	ALTER TABLE community_follow ALTER COLUMN state SET NOT NULL;
+
+	-- accepted is derived from state, and kept for queries that only care about accepted follows
+	CREATE FUNCTION community_follow_sync_accepted() RETURNS TRIGGER AS $$
+		BEGIN
+			NEW.accepted := NEW.state = 'accepted';
+			RETURN NEW;
+		END;
+	$$ LANGUAGE plpgsql;
+	CREATE TRIGGER community_follow_sync_accepted BEFORE INSERT OR UPDATE ON community_follow FOR EACH ROW EXECUTE PROCEDURE community_follow_sync_accepted();
+
+	-- keep the ID on the best existing row if a Follow was somehow stored twice
+	UPDATE community_follow SET ap_id=NULL WHERE ap_id IS NOT NULL AND (community, follower) NOT IN (SELECT DISTINCT ON (ap_id) community, follower FROM community_follow WHERE ap_id IS NOT NULL ORDER BY ap_id, accepted DESC);
+	CREATE UNIQUE INDEX community_follow_ap_id ON community_follow (ap_id);
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE reply DROP COLUMN announced_at;
+	ALTER TABLE post DROP COLUMN announced_at;
+COMMIT;
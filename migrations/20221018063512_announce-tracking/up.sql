@@ -0,0 +1,8 @@
+BEGIN;
+	ALTER TABLE post ADD COLUMN announced_at TIMESTAMPTZ;
+	ALTER TABLE reply ADD COLUMN announced_at TIMESTAMPTZ;
+
+	-- there is no record of whether existing objects were announced, so assume they were
+	UPDATE post SET announced_at=created WHERE community IN (SELECT id FROM community WHERE local);
+	UPDATE reply SET announced_at=created WHERE post IN (SELECT post.id FROM post INNER JOIN community ON (community.id = post.community) WHERE community.local);
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	DROP INDEX task_pending_priority;
+	ALTER TABLE task DROP COLUMN priority;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE task ADD COLUMN priority SMALLINT NOT NULL DEFAULT (0);
+	CREATE INDEX task_pending_priority ON task (priority DESC, created_at) WHERE state='pending';
+COMMIT;
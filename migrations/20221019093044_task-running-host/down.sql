@@ -0,0 +1,3 @@
+BEGIN;
+	DROP INDEX task_running_destination_host;
+COMMIT;
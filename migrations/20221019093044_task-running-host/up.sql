@@ -0,0 +1,3 @@
+BEGIN;
+	CREATE INDEX task_running_destination_host ON task (destination_host) WHERE state='running';
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE task DROP COLUMN started_at;
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE task ADD COLUMN started_at TIMESTAMPTZ;
+COMMIT;
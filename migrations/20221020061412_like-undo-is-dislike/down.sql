@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE local_reply_like_undo DROP COLUMN is_dislike;
+	ALTER TABLE local_post_like_undo DROP COLUMN is_dislike;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE local_post_like_undo ADD COLUMN is_dislike BOOLEAN NOT NULL DEFAULT FALSE;
+	ALTER TABLE local_reply_like_undo ADD COLUMN is_dislike BOOLEAN NOT NULL DEFAULT FALSE;
+COMMIT;
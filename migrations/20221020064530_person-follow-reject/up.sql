@@ -0,0 +1,9 @@
+BEGIN;
+	CREATE TABLE person_follow_reject (
+		person BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		follower BIGINT NOT NULL REFERENCES person ON DELETE CASCADE,
+		ap_id TEXT NOT NULL,
+		created TIMESTAMPTZ NOT NULL,
+		PRIMARY KEY (person, follower)
+	);
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE notification DROP COLUMN announcement;
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	ALTER TABLE notification ADD COLUMN announcement BIGINT REFERENCES community_announcement ON DELETE CASCADE;
+	CREATE UNIQUE INDEX notification_announcement_to_user ON notification (announcement, to_user);
+COMMIT;
@@ -0,0 +1,4 @@
+BEGIN;
+	DELETE FROM flag WHERE kind='comment';
+	ALTER TABLE flag DROP COLUMN reply;
+COMMIT;
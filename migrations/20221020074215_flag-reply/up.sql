@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE flag ADD COLUMN reply BIGINT REFERENCES reply ON DELETE CASCADE;
+COMMIT;
@@ -0,0 +1,3 @@
+BEGIN;
+	ALTER TABLE community ADD COLUMN rules TEXT[] NOT NULL DEFAULT (ARRAY[]::TEXT[]);
+COMMIT;
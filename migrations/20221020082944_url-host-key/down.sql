@@ -0,0 +1,3 @@
+BEGIN;
+	DROP FUNCTION url_host_key (TEXT);
+COMMIT;
@@ -0,0 +1,16 @@
+BEGIN;
+	-- matches get_url_host_key: lowercase host, port kept unless it's the scheme's default
+	CREATE FUNCTION url_host_key(url TEXT) RETURNS TEXT AS $$
+		DECLARE
+			host TEXT := lower(substring(url FROM '^[A-Za-z][A-Za-z0-9+.-]*://(?:[^/?#@]*@)?([^/?#]+)'));
+		BEGIN
+			IF lower(url) LIKE 'https://%' THEN
+				RETURN regexp_replace(host, ':443$', '');
+			ELSIF lower(url) LIKE 'http://%' THEN
+				RETURN regexp_replace(host, ':80$', '');
+			ELSE
+				RETURN host;
+			END IF;
+		END;
+	$$ LANGUAGE plpgsql IMMUTABLE;
+COMMIT;
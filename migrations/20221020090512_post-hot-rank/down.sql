@@ -0,0 +1,4 @@
+BEGIN;
+	DROP INDEX post_score_hot;
+	ALTER TABLE post_score DROP COLUMN hot_rank;
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+	-- recomputed periodically by the worker, NULL until the first pass after the post is created
+	ALTER TABLE post_score ADD COLUMN hot_rank FLOAT;
+	UPDATE post_score SET hot_rank = hot_rank(score, created) WHERE created < current_timestamp;
+	CREATE INDEX post_score_hot ON post_score (hot_rank DESC NULLS FIRST, post DESC);
+COMMIT;
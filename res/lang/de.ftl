@@ -1,36 +1,129 @@
+automod_rejected = Dieser Inhalt wurde von einer automatischen Moderationsregel blockiert
+automod_rule_value_invalid = Ungültiger Wert für diese Art von Automod-Regel
+comment_attachment_not_local = Kommentaranhang muss ein lokales Medium sein
 comment_content_conflict = Genau eines von content_markdown oder content_text muss spezifiziert sein
 comment_empty = Kommentar darf nicht leer sein
 comment_not_yours = Das ist nicht dein Kommentar
+community_archive_timestamp_invalid = Ungültiger Zeitstempel im Archiv
+community_archive_version_unsupported = Nicht unterstützte Version des Community-Archivs
 community_edit_denied = Du bist nicht authorisiert diese Community zu modifizieren
 community_moderators_not_local = Community-Moderatoren können nur für lokale Communities gelistet werden
+community_moderators_remove_must_be_older = Du kannst nur Moderatoren entfernen, die neuer sind als du
 community_name_disallowed_chars = Unerlaubte Zeichen in Community-Name
+community_not_local = Keine lokale Community
+description_content_conflict = Höchstens eines von description_text, description_markdown und description_html darf spezifiziert sein
+dislikes_disabled = Dislikes sind hier nicht aktiviert
+duration_invalid = Ungültige Dauer für { $field }
 email_content_forgot_password = Hi { $username }, wenn du einen Passwort-Reset von lotide angefragt hast, nutze diesen Code: { $key }
 email_not_configured = Email ist nicht konfiguriert auf diesem Server
+flag_community_unknown = Unbekannte Community für Meldung
+flag_rule_mismatch = Eine Regelnummer muss genau dann angegeben werden, wenn die Kategorie rule ist
+flag_rule_not_found = Diese Regel gibt es in dieser Community nicht
+flags_dismissed_filter_ambiguous = Filtern nach Verwerfung ist mit mehreren Zielfiltern nicht möglich
+flags_dismissed_filter_needs_target = Filtern nach Verwerfung ist ohne Zielfilter nicht möglich
+flair_name_invalid = Flair-Namen müssen zwischen 1 und { $max } Zeichen lang sein
+flair_name_taken = In dieser Community existiert bereits ein Flair mit diesem Namen
+forwarded_for_invalid = Ungültiger X-Forwarded-For-Wert
+hashtag_invalid = Ungültiger Hashtag
+internal_error = Interner Serverfehler
+invitation_already_used = Diese Einladung wurde bereits verwendet
+invitations_disabled = Einladungen sind auf diesem Server deaktiviert
+invitations_not_allowed = Du darfst keine Einladungen erstellen
+invitations_query_required = Eine Suchanfrage ist erforderlich
+language_invalid = Ungültiger Sprachcode
+login_required = Anmeldung erforderlich
+media_upload_missing = Hochgeladenes Medium ist verschwunden
 media_upload_not_configured = Upload von Medien ist nicht konfiguriert auf diesem Server
 media_upload_not_image = Upload von Medien ist nur für Bilder verfügbar
+method_not_allowed = Methode nicht erlaubt
 missing_content_type = Fehlender Content-Type
 moderators_only_local = Nur lokale Nutzer können Community-Moderatoren sein
 must_be_moderator = Du must ein Community-Moderator sein um diese Aktion auszuführen
+mute_duration_invalid = Stummschaltungsdauer muss zwischen 1 und { $max } Sekunden liegen
 name_in_use = Der Name ist bereits vergeben
 no_password = Kein passwort für diesen Nutzer gesetzt
+no_such_attachment = Anhang existiert nicht
+no_such_automod_rule = Automod-Regel existiert nicht
 no_such_comment = Kommentar nicht verfügbar
 no_such_community = Community nicht verfügbar
+no_such_feed = Feed existiert nicht
+no_such_flair = Flair existiert nicht
 no_such_forgot_password_key = Passwort-Reset-Key nicht verfügbar oder abgelaufen
+no_such_invitation = Einladung existiert nicht
 no_such_local_user_by_email = Kein lokaler Nutzer für diese Emailadresse gefunden
 no_such_local_user_by_name = Kein lokaler Nutzer mit diesem Namen gefunden
+no_such_modlog_event = Moderationsereignis existiert nicht
+no_such_notification = Benachrichtigung existiert nicht
+no_such_poll = Umfrage existiert nicht
 no_such_post = Beitrag existiert nicht
+no_such_remote_host = Entfernter Host existiert nicht
 no_such_user = User existiert nicht
+no_such_webhook = Webhook existiert nicht
+not_acceptable = Keiner der angefragten Inhaltstypen ist verfügbar
 not_admin = Du bist kein Seitenadministrator
+not_current_user = Dieser Endpunkt ist nur für den aktuellen Nutzer verfügbar
+not_found = Nicht gefunden
+notification_subscription_type_unknown = Unbekannter Abonnementtyp
+notification_title_community_announcement = Neue Ankündigung in { $community_name }
+notification_title_post_reply = Antwort auf deinen Beitrag { $post_title }
+notification_title_reply_reply = Antwort auf deinen Kommentar zum Beitrag { $post_title }
+page_invalid = Ungültige Seite
 password_incorrect = Password nicht korrekt
+permission_denied = Dazu hast du keine Berechtigung
+permission_missing_create_community = Du darfst keine Communities erstellen
+poll_is_closed = Umfrage ist geschlossen
+poll_vote_multiple_needs_options = `option` kann bei Mehrfachauswahl-Umfragen nicht verwendet werden
+poll_vote_single_needs_option = `options` kann bei Einfachauswahl-Umfragen nicht verwendet werden
+post_attachment_url_invalid = Anhang-URL ist nicht valide
+post_attachments_too_many = Beiträge dürfen höchstens { $max ->
+        [one] einen Anhang
+       *[other] { $max } Anhänge
+    } haben
+post_conflict_href_poll = Link und Umfrage können nicht gleichzeitig angegeben werden
 post_content_conflict = content_markdown und content_text schließen einander aus
 post_href_invalid = URL ist nicht valide
 post_needs_content = Beitrag muss entweder eine href, content_text, oder content_markdown beinhalten
 post_not_in_community = Dieser Beitrag ist nicht in dieser Community
+post_not_link = Dieser Beitrag ist kein Link
 post_not_yours = Dies ist nicht dein Beitrag
+post_poll_empty = Eine Umfrage ohne Optionen kann nicht erstellt werden
+post_poll_options_conflict = Mehrere Umfrageoptionen mit demselben Namen sind nicht möglich
+ratelimit_exceeded = Zu viele Anfragen.
+reply_draft_too_long = Entwürfe dürfen höchstens { $max ->
+        [one] ein Zeichen
+       *[other] { $max } Zeichen
+    } lang sein
 root = lotide läuft. lotide selbst hat kein Frontend, du musst eines seperat starten.
-sort_relevant_not_search = Sortierung nach Relevanz ist beim Suchen nicht erlaubtSorting by relevance is only allowed when searching
+signup_not_allowed = Registrierung ist auf diesem Server deaktiviert
+sort_relevant_not_search = Sortierung nach Relevanz ist nur beim Suchen erlaubt
+timestamp_invalid = Ungültiger Zeitstempel für { $field }
+user_alias_invalid = Aliase müssen http- oder https-URLs sein
+user_aliases_too_many = Accounts dürfen höchstens { $max ->
+        [one] einen Alias
+       *[other] { $max } Aliase
+    } haben
+user_avatar_not_local = Avatar muss ein lokales Medium sein
 user_email_invalid = Emailadresse ist nicht valide
+user_move_target_invalid = Umzugsziel muss ein anderer Account sein, der diesen als Alias auflistet
+user_muted = Du bist stummgeschaltet bis { $expires_at }
 user_name_disallowed_chars = Nutzername beinhaltet unerlaubte Zeichen
 user_no_avatar = Dieser Nutzer hat keinen Avatar
+user_not_local = Kein lokaler Nutzer
+user_not_remote = Kein entfernter Nutzer
+user_preferred_languages_too_many = { $max ->
+        [one] Nur eine bevorzugte Sprache kann festgelegt werden
+       *[other] Höchstens { $max } bevorzugte Sprachen können festgelegt werden
+    }
+user_profile_fields_too_many = Profile dürfen höchstens { $max ->
+        [one] ein Feld
+       *[other] { $max } Felder
+    } haben
+user_profile_text_too_long = Anzeigename und Profilfelder dürfen höchstens { $max ->
+        [one] ein Zeichen
+       *[other] { $max } Zeichen
+    } lang sein
 user_suspended_error = Dieser Account wurde gesperrt
+users_list_filter_required = Auflisten von Nutzern ist nur mit den Filtern local=true und einem Nutzernamen erlaubt
+vote_analytics_window_invalid = Zeitfenster muss zwischen 1 und { $max } Minuten liegen
+webhook_url_invalid = Webhook-URL muss eine http- oder https-URL sein
 
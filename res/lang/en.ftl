@@ -1,53 +1,140 @@
+automod_rejected = This content was blocked by an automatic moderation rule
+automod_rule_value_invalid = Invalid value for this kind of automod rule
+comment_attachment_not_local = Comment attachment must be local media
 comment_content_conflict = Exactly one of content_markdown and content_text must be specified
+comment_edit_window_passed = This comment can no longer be edited
 comment_empty = Comment may not be empty
 comment_not_yours = That's not your comment
+community_archive_timestamp_invalid = Invalid timestamp in archive
+community_archive_version_unsupported = Unsupported community archive version
 community_edit_denied = You are not authorized to modify this community
+community_members_only = Only approved members can post in this community
 community_moderators_not_local = Community moderators can only be listed for local communities
 community_moderators_remove_must_be_older = You can only remove moderators that are newer than you
 community_name_disallowed_chars = Community name contains disallowed characters
 community_not_local = Not a local community
 description_content_conflict = At most one of description_text, description_markdown, and description_html must be specified
+dislikes_disabled = Dislikes are not enabled here
+duration_invalid = Invalid duration for { $field }
 email_content_forgot_password = Hi { $username }, if you requested a password reset from lotide, use this code: { $key }
 email_not_configured = Email is not configured on this server
+flair_name_invalid = Flair names must be between 1 and { $max } characters long
+flair_name_taken = A flair with that name already exists in this community
+flag_community_unknown = Unknown community for flag
+flag_rule_mismatch = A rule number must be given exactly when the category is rule
+flag_rule_not_found = That rule does not exist in this community
+flags_dismissed_filter_ambiguous = Cannot filter by dismissal with multiple target filters
+flags_dismissed_filter_needs_target = Cannot filter by dismissal without target filter
+forwarded_for_invalid = Invalid X-Forwarded-For value
+hashtag_invalid = Invalid hashtag
+internal_error = Internal Server Error
 invitation_already_used = That invitation has already been used
 invitations_disabled = Invitations are disabled on this server
 invitations_not_allowed = You are not allowed to create invitations
+invitations_query_required = A query is required
+language_invalid = Invalid language code
+login_required = Login Required
+lookup_uri_invalid = Lookup URI must be an http or https URL
 media_upload_missing = Uploaded media has gone missing
 media_upload_not_configured = Media Upload is not configured on this server
 media_upload_not_image = Media upload is only available for images
+method_not_allowed = Method Not Allowed
 missing_content_type = Missing Content-Type
 moderators_only_local = Only local users can be community moderators
 must_be_moderator = You must be a community moderator to perform this action
+mute_duration_invalid = Mute duration must be between 1 and { $max } seconds
 name_in_use = That name is already in use
 no_password = No password set for this user
 no_such_attachment = No such attachment
+no_such_automod_rule = No such automod rule
 no_such_comment = No such comment
+no_such_feed = No such feed
 no_such_community = No such community
+no_such_flair = No such flair
+no_such_follow_request = No such follow request
 no_such_forgot_password_key = No such password reset key, or it has expired
 no_such_invitation = No such invitation
 no_such_local_user_by_email = No local user found by that email address
 no_such_local_user_by_name = No local user found by that name
+no_such_modlog_event = No such modlog event
+no_such_notification = No such notification
+no_such_poll = No such poll
 no_such_post = No such post
+no_such_relay = No such relay
+no_such_remote_host = No such remote host
 no_such_user = No such user
+no_such_webhook = No such webhook
+not_acceptable = None of the requested content types are available
 not_admin = You are not a site admin
+not_current_user = This endpoint is only available for the current user
+not_found = Not Found
+notification_subscription_type_unknown = Unknown subscription type
+notification_title_community_announcement = New announcement in { $community_name }
+notification_title_new_login = New login to your account from an unrecognized address
 notification_title_post_reply = Reply to your post { $post_title }
 notification_title_reply_reply = Reply to your comment on post { $post_title }
+page_invalid = Invalid page
 password_incorrect = Incorrect password
+permission_denied = You do not have permission to do that
 permission_missing_create_community = You are not allowed to create communities
 poll_is_closed = Poll is closed
+poll_vote_multiple_needs_options = Cannot use `option` for multiple-choice poll
+poll_vote_single_needs_option = Cannot use `options` for single-choice poll
+post_attachment_url_invalid = Attachment URL is not valid
+post_attachments_too_many = Posts may have at most { $max ->
+        [one] one attachment
+       *[other] { $max } attachments
+    }
 post_conflict_href_poll = Cannot specify both a link and a poll
 post_content_conflict = content_markdown and content_text are mutually exclusive
 post_href_invalid = Specified URL is not valid
-post_needs_content = Post must contain one of href, content_text, or content_markdown
+post_locked = That post is locked
+post_needs_content = Post must contain one of href, content_text, content_markdown, or attachments
 post_not_in_community = That post is not in this community
 post_not_link = That post is not a link
 post_not_yours = That's not your post
 post_poll_options_conflict = Cannot have multiple poll options with the same name
 post_poll_empty = Cannot create a poll without options
+ratelimit_exceeded = Ratelimit exceeded.
+reaction_invalid = Reactions must be a single emoji or a custom emoji shortcode
+relay_invalid = Could not find a relay actor with an inbox at that address
+reply_draft_too_long = Drafts may be at most { $max ->
+        [one] one character
+       *[other] { $max } characters
+    }
+request_body_too_large = Request body is too large
 root = lotide is running. Note that lotide itself does not include a frontend, and you'll need to install one separately.
 signup_not_allowed = User registration is disabled on this server
 sort_relevant_not_search = Sorting by relevance is only allowed when searching
+sort_within_not_top = Time windows are only allowed when sorting by top
+timestamp_invalid = Invalid timestamp for { $field }
+timezone_invalid = Invalid timezone
+user_alias_invalid = Aliases must be http or https URLs
+user_aliases_too_many = Accounts may have at most { $max ->
+        [one] one alias
+       *[other] { $max } aliases
+    }
+user_avatar_not_local = Avatar must be local media
 user_email_invalid = Specified email address is invalid
+user_move_target_invalid = Move target must be another account which lists this one as an alias
+user_muted = You are muted until { $expires_at }
 user_name_disallowed_chars = Username contains disallowed characters
 user_no_avatar = That user does not have an avatar
+user_not_local = Not a local user
+user_not_remote = Not a remote user
+user_preferred_languages_too_many = { $max ->
+        [one] Only one preferred language may be set
+       *[other] At most { $max } preferred languages may be set
+    }
+user_profile_fields_too_many = Profiles may have at most { $max ->
+        [one] one field
+       *[other] { $max } fields
+    }
+user_profile_text_too_long = Display name and profile fields may be at most { $max ->
+        [one] one character
+       *[other] { $max } characters
+    }
 user_suspended_error = This account has been suspended
+users_list_filter_required = User listing is only allowed when filtering by local=true and a username
+vote_analytics_window_invalid = Window must be between 1 and { $max } minutes
+webhook_url_invalid = Webhook URL must be an http or https URL
@@ -21,6 +21,7 @@ no_such_community = Neniu tia komunumo
 no_such_forgot_password_key = Neniu tia rekomencigkodo, aŭ ĝi eksvalidiĝis
 no_such_local_user_by_email = Neniu uzanto trovita per tiu retpoŝtadreso
 no_such_local_user_by_name = Neniu uzanto trovita per tiu nomo
+no_such_modlog_event = Neniu tia moderiga evento
 no_such_post = Neniu tia poŝto
 no_such_user = Neniu tia uzanto
 not_admin = Vi ne estas administranto
@@ -51,3 +52,88 @@ invitations_not_allowed = Vi ne rajtas krei invitojn
 no_such_invitation = Neniu tia invito
 permission_missing_create_community = Vi ne rajtas krei komunumojn
 signup_not_allowed = Uzanto-registriĝo estas malebligita sur ĉi tiu servilo
+comment_attachment_not_local = Aldonaĵo de komento devas esti loka bildo
+community_archive_timestamp_invalid = Nevalida tempo en arkivo
+duration_invalid = Nevalida daŭro por { $field }
+flag_community_unknown = Nekonata komunumo por raporto
+flags_dismissed_filter_ambiguous = Ne povas filtri per malakcepto kun pluraj celaj filtriloj
+flags_dismissed_filter_needs_target = Ne povas filtri per malakcepto sen cela filtrilo
+forwarded_for_invalid = Nevalida valoro de X-Forwarded-For
+internal_error = Interna servila eraro
+invitations_query_required = Serĉo estas necesa
+login_required = Ensaluto necesas
+method_not_allowed = Metodo ne permesita
+no_such_poll = Neniu tia enketo
+not_current_user = Ĉi tiu funkcio estas nur disponebla por la nuna uzanto
+not_found = Ne trovita
+notification_subscription_type_unknown = Nekonata speco de abono
+page_invalid = Nevalida paĝo
+permission_denied = Vi ne rajtas fari tion
+poll_vote_multiple_needs_options = Ne povas uzi `option` por plurelekta enketo
+poll_vote_single_needs_option = Ne povas uzi `options` por unuelekta enketo
+ratelimit_exceeded = Tro da petoj.
+user_avatar_not_local = Profilbildo devas esti loka bildo
+users_list_filter_required = Listigo de uzantoj nur estas permesita per filtrado laŭ local=true kaj uzantnomo
+no_such_notification = Neniu tia sciigo
+timestamp_invalid = Nevalida tempo por { $field }
+not_acceptable = Neniu el la petitaj enhavtipoj disponeblas
+flair_name_invalid = Nomoj de etikedoj devas havi inter 1 kaj { $max } signojn
+flair_name_taken = Etikedo kun tiu nomo jam ekzistas en ĉi tiu komunumo
+no_such_flair = Neniu tia etikedo
+hashtag_invalid = Nevalida kradvorto
+automod_rejected = Ĉi tiu enhavo estis blokita de aŭtomata moderiga regulo
+automod_rule_value_invalid = Nevalida valoro por ĉi tiu speco de aŭtomoderiga regulo
+no_such_automod_rule = Neniu tia aŭtomoderiga regulo
+vote_analytics_window_invalid = Fenestro devas esti inter 1 kaj { $max } minutoj
+user_not_local = Ne estas loka uzanto
+user_not_remote = Ne estas fora uzanto
+language_invalid = Nevalida lingvokodo
+user_preferred_languages_too_many = { $max ->
+        [one] Oni povas elekti nur unu preferatan lingvon
+       *[other] Oni povas elekti maksimume { $max } preferatajn lingvojn
+    }
+community_archive_version_unsupported = Nesubtenata versio de komunuma arkivo
+dislikes_disabled = Malŝatoj ne estas ebligitaj ĉi tie
+flag_rule_mismatch = Regulnumero devas esti donita se kaj nur se la kategorio estas rule
+flag_rule_not_found = Tiu regulo ne ekzistas en ĉi tiu komunumo
+mute_duration_invalid = Daŭro de silentigo devas esti inter 1 kaj { $max } sekundoj
+no_such_feed = Neniu tia fluo
+no_such_remote_host = Neniu tia fora servilo
+no_such_webhook = Neniu tia retkroĉilo
+notification_title_community_announcement = Nova anonco en { $community_name }
+post_attachment_url_invalid = URL de aldonaĵo ne estas valida
+post_attachments_too_many = Afiŝoj povas havi maksimume { $max ->
+        [one] unu aldonaĵon
+       *[other] { $max } aldonaĵojn
+    }
+reply_draft_too_long = Malnetoj povas havi maksimume { $max ->
+        [one] unu signon
+       *[other] { $max } signojn
+    }
+user_alias_invalid = Kromnomoj devas esti http- aŭ https-URL-oj
+user_aliases_too_many = Kontoj povas havi maksimume { $max ->
+        [one] unu kromnomon
+       *[other] { $max } kromnomojn
+    }
+user_move_target_invalid = Celo de transloĝiĝo devas esti alia konto, kiu listigas ĉi tiun kiel kromnomon
+user_muted = Vi estas silentigita ĝis { $expires_at }
+user_profile_fields_too_many = Profiloj povas havi maksimume { $max ->
+        [one] unu kampon
+       *[other] { $max } kampojn
+    }
+user_profile_text_too_long = Montrata nomo kaj profilkampoj povas havi maksimume { $max ->
+        [one] unu signon
+       *[other] { $max } signojn
+    }
+webhook_url_invalid = URL de retkroĉilo devas esti http- aŭ https-URL
+timezone_invalid = Nevalida horzono
+community_members_only = Nur aprobitaj membroj povas afiŝi en ĉi tiu komunumo
+no_such_follow_request = Neniu tia abonpeto
+notification_title_new_login = Nova ensaluto al via konto de nekonata adreso
+request_body_too_large = La korpo de la peto estas tro granda
+comment_edit_window_passed = Tiu komento ne plu estas redaktebla
+sort_within_not_top = Tempaj fenestroj nur estas permisitaj por ordigo laŭ plej bonaj
+no_such_relay = Neniu tia relajso
+relay_invalid = Ne eblis trovi relajsan aktoron kun enirkesto ĉe tiu adreso
+post_locked = Tiu afiŝo estas ŝlosita
+reaction_invalid = Reagoj devas esti unuopa emoĝio aŭ mallongigo de propra emoĝio
@@ -1,34 +1,128 @@
+automod_rejected = Ce contenu a été bloqué par une règle de modération automatique
+automod_rule_value_invalid = Valeur invalide pour ce type de règle de modération automatique
+comment_attachment_not_local = La pièce jointe d'un commentaire doit être un média local
 comment_content_conflict = Soit content_markdown soit content_text doit être spécifié
 comment_empty = Un commentaire ne peut pas est vide
 comment_not_yours = Ce n'est pas votre commentaire
+community_archive_timestamp_invalid = Horodatage invalide dans l'archive
+community_archive_version_unsupported = Version d'archive de communauté non prise en charge
 community_edit_denied = Vous n'êtes pas autorisé à modifier cette communauté
 community_moderators_not_local = Les modérateurs ne peuvent être listés que pour les communautés locales
+community_moderators_remove_must_be_older = Vous ne pouvez retirer que les modérateurs plus récents que vous
 community_name_disallowed_chars = Le nom de la communauté contient des caractères interdits
+community_not_local = Ce n'est pas une communauté locale
+description_content_conflict = Au plus un de description_text, description_markdown et description_html peut être spécifié
+dislikes_disabled = Les votes négatifs ne sont pas activés ici
+duration_invalid = Durée invalide pour { $field }
 email_content_forgot_password = Bonjour { $username }, votre nouveau mot de passe est disponible en utilisant ce code : { $key }
 email_not_configured = Aucun e-mail configuré sur ce serveur
+flag_community_unknown = Communauté inconnue pour ce signalement
+flag_rule_mismatch = Un numéro de règle doit être donné si et seulement si la catégorie est rule
+flag_rule_not_found = Cette règle n'existe pas dans cette communauté
+flags_dismissed_filter_ambiguous = Impossible de filtrer par rejet avec plusieurs filtres de cible
+flags_dismissed_filter_needs_target = Impossible de filtrer par rejet sans filtre de cible
+flair_name_invalid = Les noms d'étiquettes doivent contenir entre 1 et { $max } caractères
+flair_name_taken = Une étiquette portant ce nom existe déjà dans cette communauté
+forwarded_for_invalid = Valeur X-Forwarded-For invalide
+hashtag_invalid = Hashtag invalide
+internal_error = Erreur interne du serveur
+invitation_already_used = Cette invitation a déjà été utilisée
+invitations_disabled = Les invitations sont désactivées sur ce serveur
+invitations_not_allowed = Vous n'êtes pas autorisé à créer des invitations
+invitations_query_required = Une recherche est requise
+language_invalid = Code de langue invalide
+login_required = Connexion requise
+media_upload_missing = Le média téléversé a disparu
 media_upload_not_configured = Le téléversement de média n'est pas configuré sur ce serveur
 media_upload_not_image = Le téléversement est possible uniquement pour une image
+method_not_allowed = Méthode non autorisée
 missing_content_type = Ce type n'est pas reconnu
 moderators_only_local = Seuls les utilisateurs locaux peuvent devenir modérateurs
 must_be_moderator = Vous devez être modérateur de cette communauté pour faire ça
+mute_duration_invalid = La durée de mise en sourdine doit être comprise entre 1 et { $max } secondes
 name_in_use = Ce pseudo est déjà utilisé
 no_password = Aucun mot de passe enregistré pour cet utilisateur
+no_such_attachment = Cette pièce jointe n'existe pas
+no_such_automod_rule = Cette règle de modération automatique n'existe pas
 no_such_comment = Ce commentaire n'existe pas
 no_such_community = Cette communauté n'existe pas
+no_such_feed = Ce flux n'existe pas
+no_such_flair = Cette étiquette n'existe pas
 no_such_forgot_password_key = Cette clé de renouvellement de mot de passe n'existe pas ou elle a expiré
+no_such_invitation = Cette invitation n'existe pas
 no_such_local_user_by_email = Il n'y a pas d'utilisateur utilisant cette adresse mail.
 no_such_local_user_by_name = Il n'y a pas d'utilisateur utilisant ce pseudo
+no_such_modlog_event = Cet événement de modération n'existe pas
+no_such_notification = Cette notification n'existe pas
+no_such_poll = Ce sondage n'existe pas
 no_such_post = Ce sujet n'existe pas
+no_such_remote_host = Ce serveur distant n'existe pas
 no_such_user = Cet utilisateur n'existe pas
+no_such_webhook = Ce webhook n'existe pas
+not_acceptable = Aucun des types de contenu demandés n'est disponible
 not_admin = Vous n'êtes pas administrateur
+not_current_user = Ce point d'accès n'est disponible que pour l'utilisateur actuel
+not_found = Introuvable
+notification_subscription_type_unknown = Type d'abonnement inconnu
+notification_title_community_announcement = Nouvelle annonce dans { $community_name }
+notification_title_post_reply = Réponse à votre sujet { $post_title }
+notification_title_reply_reply = Réponse à votre commentaire sur le sujet { $post_title }
+page_invalid = Page invalide
 password_incorrect = Mot de passe incorrect
+permission_denied = Vous n'avez pas la permission de faire ça
+permission_missing_create_community = Vous n'êtes pas autorisé à créer des communautés
+poll_is_closed = Le sondage est clos
+poll_vote_multiple_needs_options = Impossible d'utiliser `option` pour un sondage à choix multiples
+poll_vote_single_needs_option = Impossible d'utiliser `options` pour un sondage à choix unique
+post_attachment_url_invalid = L'URL de la pièce jointe n'est pas valide
+post_attachments_too_many = Un sujet peut avoir au plus { $max ->
+        [one] une pièce jointe
+       *[other] { $max } pièces jointes
+    }
+post_conflict_href_poll = Impossible de spécifier à la fois un lien et un sondage
 post_content_conflict = content_markdown et content_text sont mutuellement exclusifs
 post_href_invalid = Cette URL n'est pas valide
 post_needs_content = Un sujet doit contenir soit un lien, soit du texte ou du markdown
 post_not_in_community = Ce sujet n'existe pas dans cette communauté
+post_not_link = Ce sujet n'est pas un lien
 post_not_yours = Ce n'est pas votre sujet
+post_poll_empty = Impossible de créer un sondage sans options
+post_poll_options_conflict = Plusieurs options de sondage ne peuvent pas avoir le même nom
+ratelimit_exceeded = Trop de requêtes.
+reply_draft_too_long = Un brouillon peut contenir au plus { $max ->
+        [one] un caractère
+       *[other] { $max } caractères
+    }
 root = lotide est lancé. Lotide en lui-même ne possède pas d'interface, vous devez en installer une par vous-même.
+signup_not_allowed = L'inscription est désactivée sur ce serveur
+sort_relevant_not_search = Le tri par pertinence n'est possible que lors d'une recherche
+timestamp_invalid = Horodatage invalide pour { $field }
+user_alias_invalid = Les alias doivent être des URL http ou https
+user_aliases_too_many = Un compte peut avoir au plus { $max ->
+        [one] un alias
+       *[other] { $max } alias
+    }
+user_avatar_not_local = L'image de profil doit être un média local
 user_email_invalid = L'adresse e-mail n'est pas valide
+user_move_target_invalid = La cible du déménagement doit être un autre compte qui liste celui-ci comme alias
+user_muted = Vous êtes en sourdine jusqu'à { $expires_at }
 user_name_disallowed_chars = Le pseudo contient des caractères interdits
 user_no_avatar = Cet utilisateur n'a pas d'image de profil
+user_not_local = Ce n'est pas un utilisateur local
+user_not_remote = Ce n'est pas un utilisateur distant
+user_preferred_languages_too_many = { $max ->
+        [one] Une seule langue préférée peut être définie
+       *[other] Au plus { $max } langues préférées peuvent être définies
+    }
+user_profile_fields_too_many = Un profil peut avoir au plus { $max ->
+        [one] un champ
+       *[other] { $max } champs
+    }
+user_profile_text_too_long = Le nom affiché et les champs du profil peuvent contenir au plus { $max ->
+        [one] un caractère
+       *[other] { $max } caractères
+    }
 user_suspended_error = Ce compte a été suspendu
+users_list_filter_required = La liste des utilisateurs n'est possible qu'en filtrant par local=true et par pseudo
+vote_analytics_window_invalid = La fenêtre doit être comprise entre 1 et { $max } minutes
+webhook_url_invalid = L'URL du webhook doit être une URL http ou https
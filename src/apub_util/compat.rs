@@ -0,0 +1,146 @@
+use crate::types::HostQuirks;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const NODEINFO_SCHEMA_PREFIX: &str = "http://nodeinfo.diaspora.software/ns/schema/2.";
+
+#[derive(Deserialize)]
+struct NodeInfoLinks {
+    links: Vec<NodeInfoLink>,
+}
+
+#[derive(Deserialize)]
+struct NodeInfoLink {
+    rel: String,
+    href: String,
+}
+
+#[derive(Deserialize)]
+struct NodeInfo {
+    software: NodeInfoSoftware,
+}
+
+#[derive(Deserialize)]
+struct NodeInfoSoftware {
+    name: String,
+    version: Option<String>,
+}
+
+/// Host (and port, if any) of a URL, as used for the `remote_host` table
+pub fn get_url_host_key(url: &url::Url) -> Option<&str> {
+    if url.has_host() {
+        Some(&url[url::Position::BeforeHost..url::Position::AfterPort])
+    } else {
+        None
+    }
+}
+
+pub async fn get_host_quirks(
+    host: &str,
+    db: &tokio_postgres::Client,
+) -> Result<HostQuirks, tokio_postgres::Error> {
+    let row = db.query_opt(
+        "SELECT COALESCE(override_no_shared_inbox, no_shared_inbox), COALESCE(override_inline_objects, inline_objects) FROM remote_host WHERE host=$1",
+        &[&host],
+    ).await?;
+
+    Ok(match row {
+        None => Default::default(),
+        Some(row) => HostQuirks {
+            no_shared_inbox: row.get(0),
+            inline_objects: row.get(1),
+        },
+    })
+}
+
+pub async fn enqueue_probe_host_if_needed(
+    host: &str,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let row = db.query_opt(
+        "INSERT INTO remote_host (host, last_probe_at) VALUES ($1, current_timestamp) ON CONFLICT (host) DO UPDATE SET last_probe_at=current_timestamp WHERE remote_host.last_probe_at < current_timestamp - INTERVAL '7 DAYS' RETURNING host",
+        &[&host],
+    ).await?;
+
+    if row.is_some() {
+        ctx.enqueue_task(&crate::tasks::ProbeRemoteHost {
+            host: Cow::Borrowed(host),
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub fn spawn_probe_host_if_needed(ap_id: &url::Url, ctx: Arc<crate::BaseContext>) {
+    if let Some(host) = get_url_host_key(ap_id) {
+        let host = host.to_owned();
+        crate::spawn_task(async move { enqueue_probe_host_if_needed(&host, &ctx).await });
+    }
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    uri: &hyper::Uri,
+    ctx: &crate::BaseContext,
+) -> Result<T, crate::Error> {
+    if uri.scheme() != Some(&http::uri::Scheme::HTTPS) && !ctx.dev_mode {
+        return Err(crate::Error::InternalStrStatic(
+            "NodeInfo URLs must be HTTPS in non-dev mode",
+        ));
+    }
+
+    let res = crate::res_to_error(
+        ctx.fetch(
+            hyper::Request::get(uri)
+                .header(hyper::header::ACCEPT, "application/json")
+                .body(Default::default())?,
+        )
+        .await?,
+    )
+    .await?;
+
+    let body = ctx.read_fetched_body(res).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn probe_host(host: &str, ctx: &crate::BaseContext) -> Result<(), crate::Error> {
+    let links: NodeInfoLinks = fetch_json(
+        &format!(
+            "{}://{}/.well-known/nodeinfo",
+            if ctx.dev_mode { "http" } else { "https" },
+            host
+        )
+        .parse()?,
+        ctx,
+    )
+    .await?;
+
+    // prefer the newest schema version we know about
+    let link = links
+        .links
+        .iter()
+        .filter(|link| link.rel.starts_with(NODEINFO_SCHEMA_PREFIX))
+        .max_by(|a, b| a.rel.cmp(&b.rel))
+        .ok_or(crate::Error::InternalStrStatic(
+            "No supported NodeInfo schema",
+        ))?;
+
+    let info: NodeInfo = fetch_json(&link.href.parse()?, ctx).await?;
+
+    let software_name = info.software.name.to_lowercase();
+    let software_version = info.software.version;
+
+    log::debug!("probed {}: {} {:?}", host, software_name, software_version);
+
+    let db = ctx.db_pool.get().await?;
+    db.execute(
+        "UPDATE remote_host SET software_name=$2, software_version=$3 WHERE host=$1",
+        &[&host, &software_name, &software_version],
+    )
+    .await?;
+
+    Ok(())
+}
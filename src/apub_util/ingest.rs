@@ -1,6 +1,7 @@
 use super::{ExtendedPostlike, FollowLike, KnownObject, Verified};
 use crate::types::{
-    CommentLocalID, CommunityLocalID, PollOptionLocalID, PostLocalID, ThingLocalRef, UserLocalID,
+    AutomodAction, CommentLocalID, CommunityLocalID, FlagLocalID, FollowState, PollOptionLocalID,
+    PostLocalID, ThingLocalRef, UserLocalID, WebhookEvent,
 };
 use activitystreams::prelude::*;
 use serde::Deserialize;
@@ -91,14 +92,27 @@ pub async fn ingest_object(
                     match obj_ref {
                         Some(super::LocalObjectRef::CommunityFollow(_, follower_local_id))
                         | Some(super::LocalObjectRef::CommunityFollowJoin(_, follower_local_id)) => {
-                            db.execute(
-                                "UPDATE community_follow SET accepted=TRUE WHERE community=$1 AND follower=$2",
-                                &[&community_local_id, &follower_local_id],
-                            ).await?;
+                            crate::follows::transition_community_follow(
+                                &db,
+                                community_local_id,
+                                follower_local_id,
+                                FollowState::Accepted,
+                            )
+                            .await?;
                         }
                         _ => {}
                     }
                 }
+            } else if let Some(object_id) = activity.object().as_single_id() {
+                if let Some(super::LocalObjectRef::RelayFollow(relay)) =
+                    super::LocalObjectRef::try_from_uri(object_id, &ctx.host_url_apub)
+                {
+                    db.execute(
+                        "UPDATE relay SET accepted=TRUE WHERE id=$1 AND ap_id=$2",
+                        &[&relay, &actor_ap_id],
+                    )
+                    .await?;
+                }
             }
 
             Ok(None)
@@ -246,9 +260,31 @@ pub async fn ingest_object(
                         .await?;
                     }
                 }
+            } else {
+                let is_relay = db
+                    .query_opt(
+                        "SELECT 1 FROM relay WHERE ap_id=$1 AND accepted",
+                        &[&community_ap_id.as_str()],
+                    )
+                    .await?
+                    .is_some();
+
+                if is_relay {
+                    crate::apub_util::require_containment(activity_id, community_ap_id)?;
+
+                    // relays only pass along IDs, so the object needs to come from its origin
+                    if let Some(object_id) = object.as_single_id() {
+                        if crate::apub_util::try_strip_host(object_id, &ctx.host_url_apub).is_none()
+                        {
+                            crate::apub_util::fetch_and_ingest(object_id, FoundFrom::Other, ctx)
+                                .await?;
+                        }
+                    }
+                }
             }
             Ok(None)
         }
+        KnownObject::Application(obj) => ingest_personlike(Verified(obj), true, ctx).await,
         KnownObject::Article(obj) => {
             ingest_postlike(Verified(KnownObject::Article(obj)), found_from, ctx).await
         }
@@ -260,6 +296,14 @@ pub async fn ingest_object(
             ingest_delete(Verified(activity), ctx).await?;
             Ok(None)
         }
+        KnownObject::Dislike(activity) => {
+            ingest_dislike(Verified(activity), ctx).await?;
+            Ok(None)
+        }
+        KnownObject::EmojiReact(activity) => {
+            ingest_emoji_react(Verified(activity), ctx).await?;
+            Ok(None)
+        }
         KnownObject::Flag(activity) => {
             let activity_id = activity
                 .id_unchecked()
@@ -363,10 +407,20 @@ pub async fn ingest_object(
                             }
                         };
 
-                        db.execute(
-                            "INSERT INTO flag (kind, person, post, content_text, to_community, to_remote_site_admin, created_local, local, ap_id) VALUES ('post', $1, $2, $3, $4, TRUE, current_timestamp, FALSE, $5) ON CONFLICT (ap_id) DO UPDATE SET kind='post', person=$1, post=$2, content_text=$3, to_community=$4",
+                        let row = db.query_one(
+                            "INSERT INTO flag (kind, person, post, content_text, to_community, to_remote_site_admin, created_local, local, ap_id) VALUES ('post', $1, $2, $3, $4, TRUE, current_timestamp, FALSE, $5) ON CONFLICT (ap_id) DO UPDATE SET kind='post', person=$1, post=$2, content_text=$3, to_community=$4 RETURNING id, (xmax = 0)",
                             &[&actor_local_id, &post_id, &content, &to_community, &activity_id.as_str()],
                         ).await?;
+
+                        if row.get(1) {
+                            let flag_id = FlagLocalID(row.get(0));
+
+                            ctx.enqueue_webhook_event(
+                                WebhookEvent::NewReport,
+                                serde_json::json!({ "flag": flag_id, "post": post_id, "user": actor_local_id }),
+                            )
+                            .await?;
+                        }
                     }
                     _ => {
                         log::warn!("unsupported flag target: {:?}", target_local_id);
@@ -396,7 +450,8 @@ pub async fn ingest_object(
                 .unwrap_or("");
             let description_html = group
                 .summary()
-                .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next());
+                .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next())
+                .map(crate::clean_html);
             let inbox = group.inbox_unchecked().as_str();
             let outbox = group.outbox_unchecked();
             let followers = group.followers_unchecked().map(|x| x.as_str());
@@ -414,28 +469,31 @@ pub async fn ingest_object(
                 .public_key
                 .as_ref()
                 .and_then(|key| key.signature_algorithm.as_deref());
+            let also_known_as = group.ext_three.also_known_as_ids();
+            let moved_to = group.ext_three.moved_to_id().map(|x| x.as_str());
 
             let id = CommunityLocalID(db.query_one(
-                "INSERT INTO community (name, local, ap_id, ap_inbox, ap_shared_inbox, public_key, public_key_sigalg, description_html, created_local, ap_outbox, ap_followers) VALUES ($1, FALSE, $2, $3, $4, $5, $6, $7, current_timestamp, $8, $9) ON CONFLICT (ap_id) DO UPDATE SET ap_inbox=$3, ap_shared_inbox=$4, public_key=$5, public_key_sigalg=$6, description_html=$7, ap_outbox=$8, ap_followers=$9 RETURNING id",
-                &[&name, &ap_id.as_str(), &inbox, &shared_inbox, &public_key, &public_key_sigalg, &description_html, &outbox.map(|x| x.as_str()), &followers],
+                "INSERT INTO community (name, local, ap_id, ap_inbox, ap_shared_inbox, public_key, public_key_sigalg, description_html, created_local, ap_outbox, ap_followers, last_fetched_at, also_known_as, moved_to) VALUES ($1, FALSE, $2, $3, $4, $5, $6, $7, current_timestamp, $8, $9, current_timestamp, $10, $11) ON CONFLICT (ap_id) DO UPDATE SET name=(CASE WHEN community.deleted THEN community.name ELSE $1 END), ap_inbox=$3, ap_shared_inbox=$4, public_key=$5, public_key_sigalg=$6, description_html=$7, ap_outbox=$8, ap_followers=$9, last_fetched_at=current_timestamp, also_known_as=$10, moved_to=$11 RETURNING id",
+                &[&name, &ap_id.as_str(), &inbox, &shared_inbox, &public_key, &public_key_sigalg, &description_html, &outbox.map(|x| x.as_str()), &followers, &also_known_as, &moved_to],
             ).await?.get(0));
 
-            let outbox = outbox.map(|x| x.to_owned());
+            super::compat::spawn_probe_host_if_needed(ap_id, ctx.clone());
+
+            let info = super::ActorLocalInfo::Community {
+                id,
+                public_key: public_key.map(|key| super::PubKeyInfo {
+                    algorithm: super::get_message_digest(public_key_sigalg),
+                    key: key.to_owned(),
+                }),
+                ap_outbox: outbox.map(|x| x.to_owned()),
+            };
+            info.cache(ap_id, &ctx);
 
             if let Some(featured_url) = group.ext_two.featured {
                 crate::apub_util::spawn_enqueue_fetch_community_featured(id, featured_url, ctx);
             }
 
-            Ok(Some(IngestResult::Actor(
-                super::ActorLocalInfo::Community {
-                    id,
-                    public_key: public_key.map(|key| super::PubKeyInfo {
-                        algorithm: super::get_message_digest(public_key_sigalg),
-                        key: key.to_owned(),
-                    }),
-                    ap_outbox: outbox,
-                },
-            )))
+            Ok(Some(IngestResult::Actor(info)))
         }
         KnownObject::Image(obj) => {
             ingest_postlike(Verified(KnownObject::Image(obj)), found_from, ctx).await
@@ -487,6 +545,10 @@ pub async fn ingest_object(
             ingest_like(Verified(activity), ctx).await?;
             Ok(None)
         }
+        KnownObject::Move(activity) => {
+            ingest_move(Verified(activity), ctx).await?;
+            Ok(None)
+        }
         KnownObject::Note(obj) => {
             // try to handle poll response
             if let Some(in_reply_to) = obj.in_reply_to().and_then(|x| x.as_single_id()) {
@@ -527,16 +589,21 @@ pub async fn ingest_object(
                                         if !multiple {
                                             trans
                                                 .execute(
-                                                    "DELETE FROM poll_vote WHERE person=$1",
-                                                    &[&actor_local_id],
+                                                    "DELETE FROM poll_vote WHERE poll_id=$1 AND person=$2",
+                                                    &[&poll_id, &actor_local_id],
                                                 )
                                                 .await?;
                                         }
 
-                                        trans.execute("INSERT INTO poll_vote (poll_id, option_id, person) VALUES ($1, $2, $3)", &[&poll_id, &option_id, &actor_local_id]).await?;
+                                        trans.execute("INSERT INTO poll_vote (poll_id, option_id, person) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING", &[&poll_id, &option_id, &actor_local_id]).await?;
 
                                         trans.commit().await?;
                                     }
+
+                                    super::enqueue_poll_results_update_if_needed(
+                                        post_id, &db, &ctx,
+                                    )
+                                    .await?;
                                 }
 
                                 return Ok(None);
@@ -623,6 +690,56 @@ pub async fn ingest_object(
 
             Ok(None)
         }
+        KnownObject::Reject(activity) => {
+            let activity_id = activity
+                .id_unchecked()
+                .ok_or(crate::Error::InternalStrStatic("Missing activity ID"))?;
+
+            let actor_ap_id = activity
+                .actor_unchecked()
+                .as_single_id()
+                .ok_or(crate::Error::InternalStrStatic("Missing actor for Reject"))?;
+
+            crate::apub_util::require_containment(activity_id, actor_ap_id)?;
+
+            let community_local_id: Option<CommunityLocalID> = {
+                db.query_opt(
+                    "SELECT id FROM community WHERE ap_id=$1",
+                    &[&actor_ap_id.as_str()],
+                )
+                .await?
+                .map(|row| CommunityLocalID(row.get(0)))
+            };
+
+            if let Some(community_local_id) = community_local_id {
+                let object_id = activity
+                    .object()
+                    .as_single_id()
+                    .ok_or(crate::Error::InternalStrStatic("Missing object for Reject"))?;
+
+                match super::LocalObjectRef::try_from_uri(object_id, &ctx.host_url_apub) {
+                    Some(super::LocalObjectRef::CommunityFollow(
+                        community_id,
+                        follower_local_id,
+                    ))
+                    | Some(super::LocalObjectRef::CommunityFollowJoin(
+                        community_id,
+                        follower_local_id,
+                    )) if community_id == community_local_id => {
+                        crate::follows::transition_community_follow(
+                            &db,
+                            community_local_id,
+                            follower_local_id,
+                            FollowState::Rejected,
+                        )
+                        .await?;
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok(None)
+        }
         KnownObject::Service(obj) => ingest_personlike(Verified(obj), true, ctx).await,
         KnownObject::Undo(activity) => {
             ingest_undo(Verified(activity), ctx).await?;
@@ -648,15 +765,26 @@ pub async fn ingest_object(
             crate::spawn_task(async move {
                 let row = db
                     .query_opt(
-                        "SELECT 1 FROM community WHERE ap_id=$1 LIMIT 1",
+                        "(SELECT TRUE FROM community WHERE ap_id=$1) UNION ALL (SELECT TRUE FROM person WHERE ap_id=$1) UNION ALL (SELECT FALSE FROM post WHERE ap_id=$1) UNION ALL (SELECT FALSE FROM reply WHERE ap_id=$1) LIMIT 1",
                         &[&object_id.as_str()],
                     )
                     .await?;
-                if row.is_some() {
-                    ctx.enqueue_task(&crate::tasks::FetchActor {
-                        actor_ap_id: Cow::Owned(object_id),
-                    })
-                    .await?;
+                match row.map(|row| row.get::<_, bool>(0)) {
+                    Some(true) => {
+                        crate::apub_util::invalidate_cached_actor(&object_id, &ctx);
+
+                        ctx.enqueue_task(&crate::tasks::FetchActor {
+                            actor_ap_id: Cow::Owned(object_id),
+                        })
+                        .await?;
+                    }
+                    Some(false) => {
+                        ctx.enqueue_task(&crate::tasks::RefreshObject {
+                            object_ap_id: Cow::Owned(object_id),
+                        })
+                        .await?;
+                    }
+                    None => {}
                 }
 
                 Ok(())
@@ -667,6 +795,16 @@ pub async fn ingest_object(
     }
 }
 
+async fn is_user_banned(
+    user: UserLocalID,
+    db: &tokio_postgres::Client,
+) -> Result<bool, crate::Error> {
+    Ok(db
+        .query_opt("SELECT 1 FROM person WHERE id=$1 AND banned", &[&user])
+        .await?
+        .is_some())
+}
+
 pub fn ingest_object_boxed(
     object: Verified<KnownObject>,
     found_from: FoundFrom,
@@ -678,9 +816,34 @@ pub fn ingest_object_boxed(
 pub async fn ingest_like(
     activity: Verified<activitystreams::activity::Like>,
     ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    ingest_vote(activity, false, ctx).await
+}
+
+pub async fn ingest_dislike(
+    activity: Verified<activitystreams::activity::Dislike>,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    ingest_vote(activity, true, ctx).await
+}
+
+async fn ingest_vote<K: Clone + serde::Serialize + Send + Sync>(
+    activity: Verified<activitystreams::activity::ActorAndObject<K>>,
+    is_dislike: bool,
+    ctx: Arc<crate::RouteContext>,
 ) -> Result<(), crate::Error> {
     let db = ctx.db_pool.get().await?;
 
+    if is_dislike {
+        let allow_dislikes: bool = db
+            .query_one("SELECT allow_dislikes FROM site WHERE local", &[])
+            .await?
+            .get(0);
+        if !allow_dislikes {
+            return Ok(());
+        }
+    }
+
     let activity_id = activity
         .id_unchecked()
         .ok_or(crate::Error::InternalStrStatic("Missing activity ID"))?;
@@ -690,6 +853,10 @@ pub async fn ingest_like(
 
         let actor_local_id = super::get_or_fetch_user_local_id(actor_id, &db, &ctx).await?;
 
+        if is_user_banned(actor_local_id, &db).await? {
+            return Ok(());
+        }
+
         if let Some(object_id) = activity.object().as_single_id() {
             let thing_local_ref = if let Some(local_id) =
                 super::LocalObjectRef::try_from_uri(&object_id, &ctx.host_url_apub)
@@ -716,9 +883,19 @@ pub async fn ingest_like(
 
             match thing_local_ref {
                 Some(ThingLocalRef::Post(post_local_id)) => {
+                    if is_dislike {
+                        let row = db.query_opt(
+                            "SELECT community.allow_dislikes FROM post INNER JOIN community ON (community.id = post.community) WHERE post.id=$1",
+                            &[&post_local_id],
+                        ).await?;
+                        if !row.map_or(true, |row| row.get(0)) {
+                            return Ok(());
+                        }
+                    }
+
                     let row_count = db.execute(
-                        "INSERT INTO post_like (post, person, local, ap_id) VALUES ($1, $2, FALSE, $3) ON CONFLICT (post, person) DO NOTHING",
-                        &[&post_local_id, &actor_local_id, &activity_id.as_str()],
+                        "INSERT INTO post_like (post, person, local, ap_id, is_dislike) VALUES ($1, $2, FALSE, $3, $4) ON CONFLICT (post, person) DO UPDATE SET ap_id=$3, is_dislike=$4 WHERE post_like.is_dislike != $4",
+                        &[&post_local_id, &actor_local_id, &activity_id.as_str(), &is_dislike],
                     ).await?;
 
                     if row_count > 0 {
@@ -739,13 +916,23 @@ pub async fn ingest_like(
                     }
                 }
                 Some(ThingLocalRef::Comment(comment_local_id)) => {
+                    if is_dislike {
+                        let row = db.query_opt(
+                            "SELECT community.allow_dislikes FROM reply INNER JOIN post ON (post.id = reply.post) INNER JOIN community ON (community.id = post.community) WHERE reply.id=$1",
+                            &[&comment_local_id],
+                        ).await?;
+                        if !row.map_or(true, |row| row.get(0)) {
+                            return Ok(());
+                        }
+                    }
+
                     let row_count = db.execute(
-                        "INSERT INTO reply_like (reply, person, local, ap_id) VALUES ($1, $2, FALSE, $3) ON CONFLICT (reply, person) DO NOTHING",
-                        &[&comment_local_id, &actor_local_id, &activity_id.as_str()],
+                        "INSERT INTO reply_like (reply, person, local, ap_id, is_dislike) VALUES ($1, $2, FALSE, $3, $4) ON CONFLICT (reply, person) DO UPDATE SET ap_id=$3, is_dislike=$4 WHERE reply_like.is_dislike != $4",
+                        &[&comment_local_id, &actor_local_id, &activity_id.as_str(), &is_dislike],
                     ).await?;
 
                     if row_count > 0 {
-                        let row = db.query_opt("SELECT post.community, community.local FROM reply, post, community WHERE reply.post = post.id AND post.community = community.id AND post.id=$1", &[&comment_local_id]).await?;
+                        let row = db.query_opt("SELECT post.community, community.local FROM reply, post, community WHERE reply.post = post.id AND post.community = community.id AND reply.id=$1", &[&comment_local_id]).await?;
                         if let Some(row) = row {
                             let community_local = row.get(1);
                             if community_local {
@@ -769,6 +956,112 @@ pub async fn ingest_like(
     Ok(())
 }
 
+pub async fn ingest_emoji_react(
+    activity: Verified<super::EmojiReact>,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let activity_id = activity
+        .id_unchecked()
+        .ok_or(crate::Error::InternalStrStatic("Missing activity ID"))?;
+
+    let actor_id =
+        activity
+            .actor_unchecked()
+            .as_single_id()
+            .ok_or(crate::Error::InternalStrStatic(
+                "Missing actor for activity",
+            ))?;
+
+    let object_id = activity
+        .object()
+        .as_single_id()
+        .ok_or(crate::Error::InternalStrStatic(
+            "Missing object for EmojiReact",
+        ))?;
+
+    super::require_containment(activity_id, actor_id)?;
+
+    let content = activity.content();
+    let content = match content
+        .as_ref()
+        .and_then(|x| x.as_single_xsd_string())
+        .and_then(crate::normalize_reaction)
+    {
+        Some(content) => content,
+        None => return Ok(()),
+    };
+
+    let db = ctx.db_pool.get().await?;
+
+    let actor_local_id = super::get_or_fetch_user_local_id(actor_id, &db, &ctx).await?;
+
+    if is_user_banned(actor_local_id, &db).await? {
+        return Ok(());
+    }
+
+    let thing_local_ref = if let Some(local_id) =
+        super::LocalObjectRef::try_from_uri(object_id, &ctx.host_url_apub)
+    {
+        match local_id {
+            super::LocalObjectRef::Post(id) => Some(ThingLocalRef::Post(id)),
+            super::LocalObjectRef::Comment(id) => Some(ThingLocalRef::Comment(id)),
+            _ => None,
+        }
+    } else {
+        let row = db.query_opt(
+            "(SELECT TRUE, id FROM post WHERE ap_id=$1) UNION ALL (SELECT FALSE, id FROM reply WHERE ap_id=$1) LIMIT 1",
+            &[&object_id.as_str()],
+        ).await?;
+
+        row.map(|row| {
+            if row.get(0) {
+                ThingLocalRef::Post(PostLocalID(row.get(1)))
+            } else {
+                ThingLocalRef::Comment(CommentLocalID(row.get(1)))
+            }
+        })
+    };
+
+    let community_row = match thing_local_ref {
+        Some(ThingLocalRef::Post(post_local_id)) => {
+            let row_count = db.execute(
+                "INSERT INTO post_reaction (post, person, content, local, ap_id) VALUES ($1, $2, $3, FALSE, $4) ON CONFLICT DO NOTHING",
+                &[&post_local_id, &actor_local_id, &content, &activity_id.as_str()],
+            ).await?;
+
+            if row_count > 0 {
+                db.query_opt("SELECT post.community, community.local FROM post, community WHERE post.community = community.id AND post.id=$1", &[&post_local_id]).await?
+            } else {
+                None
+            }
+        }
+        Some(ThingLocalRef::Comment(comment_local_id)) => {
+            let row_count = db.execute(
+                "INSERT INTO reply_reaction (reply, person, content, local, ap_id) VALUES ($1, $2, $3, FALSE, $4) ON CONFLICT DO NOTHING",
+                &[&comment_local_id, &actor_local_id, &content, &activity_id.as_str()],
+            ).await?;
+
+            if row_count > 0 {
+                db.query_opt("SELECT post.community, community.local FROM reply, post, community WHERE reply.post = post.id AND post.community = community.id AND reply.id=$1", &[&comment_local_id]).await?
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+
+    if let Some(row) = community_row {
+        let community_local: bool = row.get(1);
+        if community_local {
+            let community_id = CommunityLocalID(row.get(0));
+            let body = serde_json::to_string(&activity)?;
+            super::enqueue_forward_to_community_followers(community_id, body, ctx).await?;
+        }
+    }
+
+    Ok(())
+}
+
 pub async fn ingest_delete(
     activity: Verified<activitystreams::activity::Delete>,
     ctx: Arc<crate::RouteContext>,
@@ -787,9 +1080,14 @@ pub async fn ingest_delete(
         super::require_containment(activity_id, actor_id)?;
         super::require_containment(object_id, actor_id)?;
 
+        if object_id == actor_id {
+            // actor deleted itself
+            return ingest_remote_actor_delete(actor_id, &ctx).await;
+        }
+
         // maybe it's a post or reply
         let row = db.query_opt(
-            "WITH deleted_post AS (UPDATE post SET href=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE ap_id=$1 AND deleted=FALSE RETURNING (SELECT id FROM community WHERE community.id = post.community AND community.local)), deleted_reply AS (UPDATE reply SET content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE ap_id=$1 AND deleted=FALSE RETURNING (SELECT id FROM community WHERE community.id=(SELECT community FROM post WHERE id=reply.post) AND community.local)) (SELECT * FROM deleted_post) UNION ALL (SELECT * FROM deleted_reply) LIMIT 1",
+            "WITH deleted_post AS (UPDATE post SET href=NULL, href_canonical=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE ap_id=$1 AND deleted=FALSE RETURNING id, (SELECT id FROM community WHERE community.id = post.community AND community.local) AS community), deleted_post_attachments AS (DELETE FROM post_attachment WHERE post IN (SELECT id FROM deleted_post)), deleted_reply AS (UPDATE reply SET content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE ap_id=$1 AND deleted=FALSE RETURNING (SELECT id FROM community WHERE community.id=(SELECT community FROM post WHERE id=reply.post) AND community.local)) (SELECT community FROM deleted_post) UNION ALL (SELECT * FROM deleted_reply) LIMIT 1",
             &[&object_id.as_str()],
             ).await?;
 
@@ -810,12 +1108,248 @@ pub async fn ingest_delete(
         } else {
             // maybe it's a community
             db.execute("UPDATE community SET deleted=TRUE, old_name=name, name='[deleted]', description=NULL, description_html=NULL, description_markdown=NULL, created_by=NULL, public_key=NULL WHERE ap_id=$1", &[&object_id.as_str()]).await?;
+            crate::apub_util::invalidate_cached_actor(object_id, &ctx);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn ingest_move(
+    activity: Verified<activitystreams::activity::Move>,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let activity_id = activity
+        .id_unchecked()
+        .ok_or(crate::Error::InternalStrStatic("Missing ID for activity"))?;
+    let actor_id = activity
+        .actor_unchecked()
+        .as_single_id()
+        .ok_or(crate::Error::InternalStrStatic("Missing ID for actor"))?;
+
+    super::require_containment(activity_id, actor_id)?;
+
+    if activity.object().as_single_id() != Some(actor_id) {
+        // only actors moving themselves are supported
+        return Ok(());
+    }
+
+    let target_id = activity
+        .target()
+        .and_then(|x| x.as_single_id())
+        .ok_or(crate::Error::InternalStrStatic("Missing target for Move"))?;
+
+    let target = match super::LocalObjectRef::try_from_uri(target_id, &ctx.host_url_apub) {
+        Some(super::LocalObjectRef::User(id)) => ThingLocalRef::User(id),
+        Some(super::LocalObjectRef::Community(id)) => ThingLocalRef::Community(id),
+        Some(_) => return Ok(()),
+        None => super::fetch_actor(target_id, ctx.clone()).await?.as_ref(),
+    };
+
+    let mut db = ctx.db_pool.get().await?;
+
+    match target {
+        ThingLocalRef::User(new_id) => {
+            // the new account must list the old one as an alias
+            let row = db
+                .query_opt(
+                    "SELECT 1 FROM person WHERE id=$1 AND $2 = ANY(also_known_as)",
+                    &[&new_id, &actor_id.as_str()],
+                )
+                .await?;
+            if row.is_none() {
+                return Err(crate::Error::InternalStrStatic(
+                    "Move target does not list actor in alsoKnownAs",
+                ));
+            }
+
+            let trans = db.transaction().await?;
+
+            let row = trans
+                .query_opt(
+                    "UPDATE person SET moved_to=$2 WHERE ap_id=$1 AND NOT local RETURNING id",
+                    &[&actor_id.as_str(), &target_id.as_str()],
+                )
+                .await?;
+
+            if let Some(row) = row {
+                let old_id = UserLocalID(row.get(0));
+
+                // carry the old account's follows of local users over to the new one
+                trans.execute(
+                    "INSERT INTO person_follow (person, follower, ap_id, created) SELECT person, $2, NULL, created FROM person_follow WHERE follower=$1 ON CONFLICT (person, follower) DO NOTHING",
+                    &[&old_id, &new_id],
+                ).await?;
+                trans
+                    .execute("DELETE FROM person_follow WHERE follower=$1", &[&old_id])
+                    .await?;
+            }
+
+            trans.commit().await?;
         }
+        ThingLocalRef::Community(new_id) => {
+            let row = db
+                .query_opt(
+                    "SELECT local FROM community WHERE id=$1 AND $2 = ANY(also_known_as)",
+                    &[&new_id, &actor_id.as_str()],
+                )
+                .await?
+                .ok_or(crate::Error::InternalStrStatic(
+                    "Move target does not list actor in alsoKnownAs",
+                ))?;
+            let new_local: bool = row.get(0);
+
+            let row = db
+                .query_opt(
+                    "UPDATE community SET moved_to=$2 WHERE ap_id=$1 AND NOT local RETURNING id",
+                    &[&actor_id.as_str(), &target_id.as_str()],
+                )
+                .await?;
+
+            if let Some(row) = row {
+                let old_id = CommunityLocalID(row.get(0));
+
+                // move local follows over to the new community
+                let rows = db
+                    .query(
+                        "DELETE FROM community_follow WHERE community=$1 AND local RETURNING follower",
+                        &[&old_id],
+                    )
+                    .await?;
+
+                for row in rows {
+                    let follower = UserLocalID(row.get(0));
+
+                    let transition = crate::follows::request_community_follow(
+                        &db, new_id, follower, true, None, new_local,
+                    )
+                    .await?;
+                    if transition.previous.is_none() {
+                        super::spawn_enqueue_send_community_follow(new_id, follower, ctx.clone());
+                    }
+
+                    let undo_id = uuid::Uuid::new_v4();
+                    db.execute(
+                        "INSERT INTO local_community_follow_undo (id, community, follower) VALUES ($1, $2, $3)",
+                        &[&undo_id, &old_id, &follower],
+                    ).await?;
+                    super::spawn_enqueue_send_community_follow_undo(
+                        undo_id,
+                        old_id,
+                        follower,
+                        ctx.clone(),
+                    );
+                }
+            }
+        }
+        _ => {}
     }
 
     Ok(())
 }
 
+/// Tombstones a remote person or community, either after a self-Delete or once its ID returns 410 Gone
+pub async fn ingest_remote_actor_delete(
+    actor_id: &url::Url,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    use crate::tasks::TaskDef;
+
+    crate::apub_util::invalidate_cached_actor(actor_id, ctx);
+
+    let mut db = ctx.db_pool.get().await?;
+
+    let trans = db.transaction().await?;
+
+    let row = trans.query_opt(
+        "UPDATE person SET deleted=TRUE, username='[deleted]', description='', description_html=NULL, description_markdown=NULL, avatar=NULL, display_name=NULL, profile_fields='[]', public_key=NULL WHERE ap_id=$1 AND NOT local AND NOT deleted RETURNING id, ap_inbox",
+        &[&actor_id.as_str()],
+    ).await?;
+
+    let inbox: Option<String> = if let Some(row) = row {
+        let user_id = UserLocalID(row.get(0));
+
+        trans.execute(
+            "UPDATE post SET had_href=(href IS NOT NULL), href=NULL, href_canonical=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE author=$1 AND NOT deleted",
+            &[&user_id],
+        ).await?;
+        trans
+            .execute(
+                "DELETE FROM post_attachment WHERE post IN (SELECT id FROM post WHERE author=$1)",
+                &[&user_id],
+            )
+            .await?;
+        trans.execute(
+            "UPDATE reply SET content_text='[deleted]', content_markdown=NULL, content_html=NULL, attachment_href=NULL, deleted=TRUE WHERE author=$1 AND NOT deleted",
+            &[&user_id],
+        ).await?;
+
+        trans
+            .execute("DELETE FROM post_like WHERE person=$1", &[&user_id])
+            .await?;
+        trans
+            .execute("DELETE FROM reply_like WHERE person=$1", &[&user_id])
+            .await?;
+        trans
+            .execute("DELETE FROM poll_vote WHERE person=$1", &[&user_id])
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM community_follow WHERE follower=$1",
+                &[&user_id],
+            )
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM person_follow WHERE person=$1 OR follower=$1",
+                &[&user_id],
+            )
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM community_moderator WHERE person=$1",
+                &[&user_id],
+            )
+            .await?;
+
+        row.get(1)
+    } else {
+        let row = trans.query_opt(
+            "UPDATE community SET deleted=TRUE, old_name=name, name='[deleted]', description=NULL, description_html=NULL, description_markdown=NULL, created_by=NULL, public_key=NULL WHERE ap_id=$1 AND NOT local AND NOT deleted RETURNING id, ap_inbox",
+            &[&actor_id.as_str()],
+        ).await?;
+
+        if let Some(row) = row {
+            let community_id = CommunityLocalID(row.get(0));
+
+            trans
+                .execute(
+                    "DELETE FROM community_follow WHERE community=$1",
+                    &[&community_id],
+                )
+                .await?;
+
+            row.get(1)
+        } else {
+            None
+        }
+    };
+
+    if let Some(inbox) = inbox {
+        // nothing left to deliver to, so pending retries would only fail
+        trans
+            .execute(
+                "DELETE FROM task WHERE kind=$1 AND state='pending' AND params->>'inbox' = $2",
+                &[&crate::tasks::DeliverToInbox::KIND, &inbox],
+            )
+            .await?;
+    }
+
+    trans.commit().await?;
+
+    Ok(())
+}
+
 pub async fn ingest_undo(
     activity: Verified<activitystreams::activity::Undo>,
     ctx: Arc<crate::RouteContext>,
@@ -848,8 +1382,36 @@ pub async fn ingest_undo(
         .await?;
     db.execute("DELETE FROM reply_like WHERE ap_id=$1", &[&object_id])
         .await?;
+    db.execute("DELETE FROM post_reaction WHERE ap_id=$1", &[&object_id])
+        .await?;
+    db.execute("DELETE FROM reply_reaction WHERE ap_id=$1", &[&object_id])
+        .await?;
     db.execute("DELETE FROM community_follow WHERE ap_id=$1", &[&object_id])
         .await?;
+
+    // the Follow may have been stored under a different ID, so also match an embedded one by target
+    if let Some(follow) = activity
+        .object()
+        .as_one()
+        .filter(|x| x.kind_str() == Some("Follow"))
+    {
+        if let Some(follow) = activitystreams::activity::Follow::from_any_base(follow.clone())? {
+            if let Some(super::LocalObjectRef::Community(community_id)) = follow
+                .object()
+                .as_single_id()
+                .and_then(|target| super::LocalObjectRef::try_from_uri(target, &ctx.host_url_apub))
+            {
+                db.execute(
+                    "DELETE FROM community_follow WHERE community=$1 AND NOT local AND follower=(SELECT id FROM person WHERE ap_id=$2)",
+                    &[&community_id, &actor_id.as_str()],
+                )
+                .await?;
+            }
+        }
+    }
+
+    db.execute("DELETE FROM person_follow WHERE ap_id=$1", &[&object_id])
+        .await?;
     db.execute(
         "UPDATE post SET approved=FALSE, approved_ap_id=NULL, rejected=TRUE, rejected_ap_id=$2 WHERE approved_ap_id=$1",
         &[&object_id, &activity_id.as_str()],
@@ -864,16 +1426,35 @@ pub async fn ingest_create(
     found_from: FoundFrom,
     ctx: Arc<crate::BaseContext>,
 ) -> Result<(), crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
     for req_obj in activity.object().iter() {
         let object_id = req_obj.id();
 
         if let Some(object_id) = object_id {
+            // The same Create is often delivered more than once, either from retries or through
+            // multiple servers. Announces still need to be processed to update approval.
+            if found_from.as_announce().is_none() {
+                let row = db
+                    .query_opt(
+                        "(SELECT 1 FROM post WHERE ap_id=$1) UNION ALL (SELECT 1 FROM reply WHERE ap_id=$1) LIMIT 1",
+                        &[&object_id.as_str()],
+                    )
+                    .await?;
+                if row.is_some() {
+                    log::debug!("Skipping Create for already known object {}", object_id);
+                    continue;
+                }
+            }
+
             let obj = if if let Some(activity_id) = activity.id_unchecked() {
                 crate::apub_util::is_contained(activity_id, object_id)
             } else {
                 false
             } {
-                Verified(serde_json::from_value(serde_json::to_value(&req_obj)?)?)
+                Verified(super::jsonld::parse_known_object(serde_json::to_value(
+                    &req_obj,
+                )?)?)
             } else {
                 crate::apub_util::fetch_ap_object(object_id, &ctx).await?
             };
@@ -1105,6 +1686,7 @@ async fn ingest_postlike(
                             in_reply_to,
                             attachment_href,
                             sensitive,
+                            get_hashtags(obj.tag().into_iter().flat_map(|x| x.iter())),
                             ctx,
                         )
                         .await?
@@ -1117,8 +1699,18 @@ async fn ingest_postlike(
                         let title = name
                             .as_ref()
                             .and_then(|x| x.as_single_xsd_string())
-                            .or_else(|| summary.as_ref().and_then(|x| x.as_single_xsd_string()))
-                            .unwrap_or("");
+                            .or_else(|| summary.as_ref().and_then(|x| x.as_single_xsd_string()));
+
+                        // Mastodon notes have no title
+                        let derived_title;
+                        let title = match (title, content) {
+                            (Some(title), _) => title,
+                            (None, Some(content)) => {
+                                derived_title = title_from_content(content, media_type);
+                                &derived_title
+                            }
+                            (None, None) => "",
+                        };
 
                         // Interpret attachments (usually images) as links
                         let href = obj
@@ -1173,26 +1765,35 @@ async fn ingest_postlike(
                             })
                             .transpose()?
                             .flatten();
-                        let sensitive = obj.ext_two.sensitive;
-
-                        Ok(Some(IngestResult::Post(
-                            handle_recieved_post(
-                                object_id.clone(),
-                                title,
-                                href.as_deref(),
-                                content,
-                                media_type,
-                                created.as_ref(),
-                                author,
-                                community_local_id,
-                                community_is_local,
-                                found_from.as_announce(),
-                                poll_info,
-                                sensitive,
-                                ctx,
-                            )
-                            .await?,
-                        )))
+                        let flags = obj.ext_two.clone();
+                        let language = get_language(&obj.ext_three, obj.context());
+
+                        // the first attachment was already used as the link
+                        let attachments = get_post_attachments(
+                            obj.attachment().into_iter().flat_map(|x| x.iter()).skip(1),
+                        )?;
+                        let hashtags = get_hashtags(obj.tag().into_iter().flat_map(|x| x.iter()));
+
+                        Ok(handle_recieved_post(
+                            object_id.clone(),
+                            title,
+                            href.as_deref(),
+                            content,
+                            media_type,
+                            created.as_ref(),
+                            author,
+                            community_local_id,
+                            community_is_local,
+                            found_from.as_announce(),
+                            poll_info,
+                            flags,
+                            language.as_deref(),
+                            attachments,
+                            hashtags,
+                            ctx,
+                        )
+                        .await?
+                        .map(IngestResult::Post))
                     }
                 } else {
                     Ok(None)
@@ -1253,6 +1854,7 @@ async fn ingest_postlike(
                         in_reply_to,
                         attachment_href,
                         sensitive,
+                        get_hashtags(obj.tag().into_iter().flat_map(|x| x.iter())),
                         ctx,
                     )
                     .await?;
@@ -1291,28 +1893,96 @@ async fn ingest_followlike(
         let follower_local_id =
             crate::apub_util::get_or_fetch_user_local_id(follower_ap_id, &db, &ctx).await?;
 
+        if is_user_banned(follower_local_id, &db).await? {
+            return Ok(());
+        }
+
         if let Some(target) = target {
-            if let Some(super::LocalObjectRef::Community(community_id)) =
-                super::LocalObjectRef::try_from_uri(target, &ctx.host_url_apub)
-            {
-                let row = db
-                    .query_opt("SELECT local FROM community WHERE id=$1", &[&community_id])
-                    .await?;
-                if let Some(row) = row {
-                    let local: bool = row.get(0);
-                    if local {
-                        db.execute("INSERT INTO community_follow (community, follower, local, ap_id, accepted) VALUES ($1, $2, FALSE, $3, TRUE) ON CONFLICT (community, follower) DO UPDATE SET ap_id = $3, accepted = TRUE", &[&community_id, &follower_local_id, &activity_ap_id.as_str()]).await?;
+            match super::LocalObjectRef::try_from_uri(target, &ctx.host_url_apub) {
+                Some(super::LocalObjectRef::Community(community_id)) => {
+                    let row = db
+                        .query_opt(
+                            "SELECT local, approve_follows FROM community WHERE id=$1",
+                            &[&community_id],
+                        )
+                        .await?;
+                    if let Some(row) = row {
+                        let local: bool = row.get(0);
+                        if local {
+                            let approve_follows: bool = row.get(1);
+
+                            // follows needing approval stay pending until a moderator responds
+                            let transition = crate::follows::request_community_follow(
+                                &db,
+                                community_id,
+                                follower_local_id,
+                                false,
+                                Some(activity_ap_id.as_str()),
+                                !approve_follows,
+                            )
+                            .await?;
 
-                        crate::apub_util::spawn_enqueue_send_community_follow_accept(
-                            community_id,
-                            follower_local_id,
-                            follow.with_owned(),
-                            ctx,
-                        );
+                            match transition.current {
+                                FollowState::Accepted => {
+                                    crate::apub_util::spawn_enqueue_send_community_follow_accept(
+                                        community_id,
+                                        follower_local_id,
+                                        follow.with_owned(),
+                                        ctx,
+                                    );
+                                }
+                                FollowState::Rejected => {
+                                    crate::apub_util::spawn_enqueue_send_community_follow_response(
+                                        community_id,
+                                        follower_local_id,
+                                        activity_ap_id.clone(),
+                                        false,
+                                        ctx,
+                                    );
+                                }
+                                FollowState::Pending => {}
+                            }
+                        }
+                    } else {
+                        log::error!("Warning: recieved follow for unknown community");
+                    }
+                }
+                Some(super::LocalObjectRef::User(user_id)) => {
+                    let row = db
+                        .query_opt(
+                            "SELECT local, suspended FROM person WHERE id=$1",
+                            &[&user_id],
+                        )
+                        .await?;
+                    if let Some(row) = row {
+                        let local: bool = row.get(0);
+                        if local {
+                            let suspended: bool = row.get(1);
+
+                            if suspended {
+                                db.execute("INSERT INTO person_follow_reject (person, follower, ap_id, created) VALUES ($1, $2, $3, current_timestamp) ON CONFLICT (person, follower) DO UPDATE SET ap_id = $3, created = current_timestamp", &[&user_id, &follower_local_id, &activity_ap_id.as_str()]).await?;
+                            } else {
+                                db.execute("INSERT INTO person_follow (person, follower, ap_id, created) VALUES ($1, $2, $3, current_timestamp) ON CONFLICT (person, follower) DO UPDATE SET ap_id = $3", &[&user_id, &follower_local_id, &activity_ap_id.as_str()]).await?;
+                                db.execute(
+                                    "DELETE FROM person_follow_reject WHERE person=$1 AND follower=$2",
+                                    &[&user_id, &follower_local_id],
+                                )
+                                .await?;
+                            }
+
+                            crate::apub_util::spawn_enqueue_send_user_follow_response(
+                                user_id,
+                                follower_local_id,
+                                follow.with_owned(),
+                                !suspended,
+                                ctx,
+                            );
+                        }
+                    } else {
+                        log::error!("Warning: recieved follow for unknown user");
                     }
-                } else {
-                    log::error!("Warning: recieved follow for unknown community");
                 }
+                _ => {}
             }
         }
     }
@@ -1328,9 +1998,10 @@ async fn ingest_personlike<
         + Clone,
 >(
     person: Verified<
-        activitystreams_ext::Ext1<
+        activitystreams_ext::Ext2<
             activitystreams::actor::ApActor<K>,
             super::PublicKeyExtension<'static>,
+            super::MovedExtension,
         >,
     >,
     is_bot: bool,
@@ -1340,14 +2011,11 @@ async fn ingest_personlike<
         .id_unchecked()
         .ok_or(crate::Error::InternalStrStatic("Missing ID in Person"))?;
 
-    let username = person
-        .preferred_username()
-        .or_else(|| {
-            person
-                .name()
-                .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next())
-        })
-        .unwrap_or("");
+    let name = person
+        .name()
+        .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next());
+    let username = person.preferred_username().or(name).unwrap_or("");
+    let display_name = name.filter(|name| *name != username);
     let inbox = person.inbox_unchecked().as_str();
     let shared_inbox = person
         .endpoints_unchecked()
@@ -1365,7 +2033,8 @@ async fn ingest_personlike<
         .and_then(|key| key.signature_algorithm.as_deref());
     let description_html = person
         .summary()
-        .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next());
+        .and_then(|maybe| maybe.iter().filter_map(|x| x.as_xsd_string()).next())
+        .map(crate::clean_html);
 
     let avatar = person.icon().and_then(|icon| {
         icon.iter()
@@ -1385,21 +2054,28 @@ async fn ingest_personlike<
         .as_ref()
         .and_then(|icon| icon.url().and_then(|url| url.as_single_id()))
         .map(|x| x.as_str());
+    let also_known_as = person.ext_two.also_known_as_ids();
+    let moved_to = person.ext_two.moved_to_id().map(|x| x.as_str());
 
     let db = ctx.db_pool.get().await?;
 
     let id = UserLocalID(db.query_one(
-        "INSERT INTO person (username, local, created_local, ap_id, ap_inbox, ap_shared_inbox, public_key, public_key_sigalg, description_html, avatar, is_bot) VALUES ($1, FALSE, localtimestamp, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (ap_id) DO UPDATE SET ap_inbox=$3, ap_shared_inbox=$4, public_key=$5, public_key_sigalg=$6, description_html=$7, avatar=$8, is_bot=$9 RETURNING id",
-        &[&username, &ap_id.as_str(), &inbox, &shared_inbox, &public_key, &public_key_sigalg, &description_html, &avatar, &is_bot],
+        "INSERT INTO person (username, local, created_local, ap_id, ap_inbox, ap_shared_inbox, public_key, public_key_sigalg, description_html, avatar, is_bot, display_name, last_fetched_at, also_known_as, moved_to) VALUES ($1, FALSE, localtimestamp, $2, $3, $4, $5, $6, $7, $8, $9, $10, current_timestamp, $11, $12) ON CONFLICT (ap_id) DO UPDATE SET username=$1, ap_inbox=$3, ap_shared_inbox=$4, public_key=$5, public_key_sigalg=$6, description_html=$7, avatar=$8, is_bot=$9, display_name=$10, last_fetched_at=current_timestamp, also_known_as=$11, moved_to=$12 RETURNING id",
+        &[&username, &ap_id.as_str(), &inbox, &shared_inbox, &public_key, &public_key_sigalg, &description_html, &avatar, &is_bot, &display_name, &also_known_as, &moved_to],
     ).await?.get(0));
 
-    Ok(Some(IngestResult::Actor(super::ActorLocalInfo::User {
+    let info = super::ActorLocalInfo::User {
         id,
         public_key: public_key.map(|key| super::PubKeyInfo {
             algorithm: super::get_message_digest(public_key_sigalg),
             key: key.to_owned(),
         }),
-    })))
+    };
+    info.cache(ap_id, &ctx);
+
+    super::compat::spawn_probe_host_if_needed(ap_id, ctx);
+
+    Ok(Some(IngestResult::Actor(info)))
 }
 
 async fn handle_recieved_reply(
@@ -1411,6 +2087,7 @@ async fn handle_recieved_reply(
     in_reply_to: &activitystreams::primitives::OneOrMany<activitystreams::base::AnyBase>,
     attachment_href: Option<&str>,
     sensitive: Option<bool>,
+    hashtags: Vec<String>,
     ctx: Arc<crate::RouteContext>,
 ) -> Result<Option<CommentLocalID>, crate::Error> {
     let db = ctx.db_pool.get().await?;
@@ -1420,6 +2097,13 @@ async fn handle_recieved_reply(
         None => None,
     };
 
+    if let Some(author) = author {
+        if is_user_banned(author, &db).await? {
+            log::debug!("Ignoring comment {} from banned user", object_id);
+            return Ok(None);
+        }
+    }
+
     let last_reply_to = in_reply_to.iter().last(); // TODO maybe not this? Not sure how to interpret inReplyTo
 
     if let Some(last_reply_to) = last_reply_to {
@@ -1472,9 +2156,40 @@ async fn handle_recieved_reply(
                     ReplyTarget::Comment { id, post } => (post, Some(id)),
                 };
 
+                let row = db
+                    .query_one(
+                        "SELECT post.locked AND community.local FROM post INNER JOIN community ON (community.id = post.community) WHERE post.id=$1",
+                        &[&post],
+                    )
+                    .await?;
+                if row.get(0) {
+                    log::debug!("Ignoring comment {} on locked post", object_id);
+                    return Ok(None);
+                }
+
+                if let Some(author) = author {
+                    let row = db
+                        .query_one("SELECT community FROM post WHERE id=$1", &[&post])
+                        .await?;
+                    if !crate::can_post_in_community(&db, author, CommunityLocalID(row.get(0)))
+                        .await?
+                    {
+                        log::debug!("Ignoring comment {} from non-member", object_id);
+                        return Ok(None);
+                    }
+                }
+
+                let outcome =
+                    crate::automod::check_comment(post, author, Some(content), &db, &ctx).await?;
+                if let crate::automod::CommentOutcome::Reject = outcome {
+                    log::debug!("Ignoring comment {} blocked by automod", object_id);
+                    return Ok(None);
+                }
+                let held = matches!(outcome, crate::automod::CommentOutcome::Hold);
+
                 let content_is_html = media_type.is_none() || media_type == Some(&mime::TEXT_HTML);
                 let (content_text, content_html) = if content_is_html {
-                    (None, Some(content))
+                    (None, Some(crate::clean_html(content)))
                 } else {
                     (Some(content), None)
                 };
@@ -1482,12 +2197,19 @@ async fn handle_recieved_reply(
                 let sensitive = sensitive.unwrap_or(false);
 
                 let row = db.query_opt(
-                    "INSERT INTO reply (post, parent, author, content_text, content_html, created, local, ap_id, attachment_href, sensitive) VALUES ($1, $2, $3, $4, $5, COALESCE($6, current_timestamp), FALSE, $7, $8, $9) ON CONFLICT (ap_id) DO NOTHING RETURNING id",
-                    &[&post, &parent, &author, &content_text, &content_html, &created, &object_id.as_str(), &attachment_href, &sensitive],
+                    "INSERT INTO reply (post, parent, author, content_text, content_html, created, local, ap_id, attachment_href, sensitive, pending_approval) VALUES ($1, $2, $3, $4, $5, COALESCE($6, current_timestamp), FALSE, $7, $8, $9, $10) ON CONFLICT (ap_id) DO NOTHING RETURNING id",
+                    &[&post, &parent, &author, &content_text, &content_html, &created, &object_id.as_str(), &attachment_href, &sensitive, &held],
                     ).await?;
 
                 if let Some(row) = row {
                     let id = CommentLocalID(row.get(0));
+
+                    crate::set_comment_hashtags(id, &hashtags, &db).await?;
+
+                    if let crate::automod::CommentOutcome::Report(found) = outcome {
+                        crate::automod::report_comment(id, found, &ctx).await?;
+                    }
+
                     let info = crate::CommentInfo {
                         id,
                         author,
@@ -1495,30 +2217,41 @@ async fn handle_recieved_reply(
                         parent,
                         content_text: content_text.map(|x| Cow::Owned(x.to_owned())),
                         content_markdown: None,
-                        content_html: content_html.map(|x| Cow::Owned(x.to_owned())),
+                        content_html: content_html.map(Cow::Owned),
                         created: created.copied().unwrap_or_else(|| {
                             chrono::offset::Utc::now()
                                 .with_timezone(&chrono::offset::FixedOffset::west(0))
                         }),
+                        updated: None,
                         ap_id: crate::APIDOrLocal::APID(object_id.to_owned()),
                         attachment_href: attachment_href.map(|x| Cow::Owned(x.to_owned())),
                         sensitive,
                     };
 
-                    crate::on_post_add_comment(info, ctx);
+                    if !held {
+                        crate::on_post_add_comment(info, ctx);
+                    }
 
                     Ok(Some(id))
                 } else {
-                    // not new, try to fetch id
-                    // will probably be unnecessary when we implement comment editing
+                    // not new, update stored content in case it was edited
+                    // previous content is kept as a revision if it changed
 
-                    let row = db
-                        .query_opt(
-                            "SELECT id FROM reply WHERE ap_id=$1",
-                            &[&object_id.as_str()],
-                        )
-                        .await?;
-                    Ok(row.map(|row| CommentLocalID(row.get(0))))
+                    let row = db.query_opt(
+                        "WITH revision AS (INSERT INTO content_revision (reply, created_at, content_text, content_html) SELECT id, COALESCE(updated, created), content_text, content_html FROM reply WHERE ap_id=$1 AND NOT deleted AND (content_text IS DISTINCT FROM $2 OR content_html IS DISTINCT FROM $3) RETURNING reply) UPDATE reply SET content_text=(CASE WHEN deleted THEN content_text ELSE $2 END), content_html=(CASE WHEN deleted THEN content_html ELSE $3 END), attachment_href=(CASE WHEN deleted THEN attachment_href ELSE $4 END), sensitive=$5, updated=(CASE WHEN EXISTS(SELECT 1 FROM revision) THEN current_timestamp ELSE updated END) WHERE ap_id=$1 RETURNING id",
+                        &[&object_id.as_str(), &content_text, &content_html, &attachment_href, &sensitive],
+                    ).await?;
+
+                    match row {
+                        Some(row) => {
+                            let id = CommentLocalID(row.get(0));
+
+                            crate::set_comment_hashtags(id, &hashtags, &db).await?;
+
+                            Ok(Some(id))
+                        }
+                        None => Ok(None),
+                    }
                 }
             } else {
                 Ok(None)
@@ -1548,53 +2281,209 @@ async fn handle_received_page_for_community<Kind: Clone + std::fmt::Debug>(
         .filter_map(|maybe| maybe.as_xsd_string())
         .next()
         .unwrap_or("");
+    // Lemmy and others may send the link as a Link attachment instead of `url`
     let href = obj
         .url()
         .iter()
-        .map(|x| x.iter())
-        .flatten()
-        .filter_map(|maybe| {
-            maybe
-                .as_xsd_any_uri()
-                .map(|x| x.as_str())
-                .or_else(|| maybe.as_xsd_string())
-        })
-        .next();
+        .flat_map(|x| x.iter())
+        .find_map(href_from_any_base)
+        .or_else(|| {
+            obj.attachment()
+                .iter()
+                .flat_map(|x| x.iter())
+                .filter(|x| x.kind_str() == Some("Link"))
+                .find_map(href_from_any_base)
+        });
     let content = obj.content();
     let content = content.as_ref().and_then(|x| x.as_single_xsd_string());
     let media_type = obj.media_type();
+    let derived_title;
+    let title = match content {
+        Some(content) if title.is_empty() => {
+            derived_title = title_from_content(content, media_type);
+            &derived_title
+        }
+        _ => title,
+    };
     let created = obj.published();
     let author = obj.attributed_to().and_then(|x| x.as_single_id());
-    let sensitive = obj.ext_two.sensitive;
+    let flags = obj.ext_two.clone();
+    let language = get_language(&obj.ext_three, obj.context());
+    let attachments = get_post_attachments(obj.attachment().into_iter().flat_map(|x| x.iter()))?;
+    let hashtags = get_hashtags(obj.tag().into_iter().flat_map(|x| x.iter()));
 
     if let Some(object_id) = obj.id_unchecked() {
         if let Some(author) = author {
             super::require_containment(object_id, author)?;
         }
 
-        Ok(Some(
-            handle_recieved_post(
-                object_id.clone(),
-                title,
-                href,
-                content,
-                media_type,
-                created.as_ref(),
-                author,
-                community_local_id,
-                community_is_local,
-                is_announce,
-                poll_info,
-                sensitive,
-                ctx,
-            )
-            .await?,
-        ))
+        handle_recieved_post(
+            object_id.clone(),
+            title,
+            href.as_deref(),
+            content,
+            media_type,
+            created.as_ref(),
+            author,
+            community_local_id,
+            community_is_local,
+            is_announce,
+            poll_info,
+            flags,
+            language.as_deref(),
+            attachments,
+            hashtags,
+            ctx,
+        )
+        .await
     } else {
         Ok(None)
     }
 }
 
+/// Reads the language from `contentMap`, or from `@language` in the JSON-LD context
+fn get_language(
+    ext: &super::LanguageExtension,
+    context: Option<&activitystreams::primitives::OneOrMany<activitystreams::base::AnyBase>>,
+) -> Option<String> {
+    ext.language().or_else(|| {
+        context
+            .into_iter()
+            .flat_map(|x| x.iter())
+            .filter_map(|base| serde_json::to_value(base).ok())
+            .find_map(|value| {
+                value
+                    .get("@language")?
+                    .as_str()
+                    .and_then(crate::normalize_language)
+            })
+    })
+}
+
+fn attachment_from_object<Kind>(
+    obj: activitystreams::object::Object<Kind>,
+) -> Option<crate::PostAttachment> {
+    let url = obj
+        .url()
+        .and_then(|href| href.iter().filter_map(|x| x.as_xsd_any_uri()).next())?;
+
+    // don't let remote objects point at local media
+    if url.scheme() != "https" && url.scheme() != "http" {
+        return None;
+    }
+
+    Some(crate::PostAttachment {
+        url: url.as_str().to_owned(),
+        media_type: obj.media_type().map(|x| x.to_string()),
+        alt_text: obj
+            .name()
+            .as_ref()
+            .and_then(|x| x.as_single_xsd_string())
+            .map(ToOwned::to_owned),
+    })
+}
+
+fn get_hashtags<'a>(
+    items: impl Iterator<Item = &'a activitystreams::base::AnyBase>,
+) -> Vec<String> {
+    let mut output = Vec::new();
+
+    for base in items {
+        if base.kind_str() != Some("Hashtag") {
+            continue;
+        }
+
+        let tag = serde_json::to_value(base).ok().and_then(|value| {
+            value
+                .get("name")
+                .and_then(|name| name.as_str())
+                .and_then(crate::normalize_hashtag)
+        });
+
+        if let Some(tag) = tag {
+            if !output.contains(&tag) {
+                output.push(tag);
+            }
+        }
+    }
+
+    output
+}
+
+/// Reads a link given either directly or as a Link object
+fn href_from_any_base(base: &activitystreams::base::AnyBase) -> Option<String> {
+    if let Some(href) = base.as_xsd_any_uri() {
+        Some(href.as_str().to_owned())
+    } else if let Some(href) = base.as_xsd_string() {
+        Some(href.to_owned())
+    } else if base.kind_str() == Some("Link") {
+        activitystreams::link::Link::<activitystreams::link::kind::LinkType>::from_any_base(
+            base.clone(),
+        )
+        .ok()
+        .flatten()
+        .and_then(|mut link| link.take_href())
+        .map(|href| href.as_str().to_owned())
+    } else {
+        None
+    }
+}
+
+/// Uses the first line of content as a title, for software that doesn't have them
+fn title_from_content(content: &str, media_type: Option<&mime::Mime>) -> String {
+    lazy_static::lazy_static! {
+        static ref LINE_BREAK_REGEX: regex::Regex = regex::Regex::new(r"(?i)</p>|<br\s*/?>").unwrap();
+        static ref TAG_REGEX: regex::Regex = regex::Regex::new(r"<[^>]*>").unwrap();
+    }
+
+    const MAX_TITLE_LENGTH: usize = 100;
+
+    let text = if media_type.is_none() || media_type == Some(&mime::TEXT_HTML) {
+        let text = LINE_BREAK_REGEX.replace_all(content, "\n");
+        TAG_REGEX
+            .replace_all(&text, "")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&#39;", "'")
+            .replace("&amp;", "&")
+    } else {
+        content.to_owned()
+    };
+
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+
+    match line.char_indices().nth(MAX_TITLE_LENGTH) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => line.to_owned(),
+    }
+}
+
+fn get_post_attachments<'a>(
+    items: impl Iterator<Item = &'a activitystreams::base::AnyBase>,
+) -> Result<Vec<crate::PostAttachment>, crate::Error> {
+    Ok(items
+        .filter_map(|base| match base.kind_str() {
+            Some("Document") => Some(
+                activitystreams::object::Document::from_any_base(base.clone())
+                    .map(|obj| obj.and_then(attachment_from_object)),
+            ),
+            Some("Image") => Some(
+                activitystreams::object::Image::from_any_base(base.clone())
+                    .map(|obj| obj.and_then(attachment_from_object)),
+            ),
+            _ => None,
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
 async fn handle_recieved_post(
     object_id: url::Url,
     title: &str,
@@ -1607,34 +2496,114 @@ async fn handle_recieved_post(
     community_is_local: bool,
     is_announce: Option<&url::Url>,
     poll_info: Option<PollIngestInfo>,
-    sensitive: Option<bool>,
+    flags: super::ContentFlagsExtension,
+    language: Option<&str>,
+    attachments: Vec<crate::PostAttachment>,
+    hashtags: Vec<String>,
     ctx: Arc<crate::RouteContext>,
-) -> Result<PostIngestResult, crate::Error> {
+) -> Result<Option<PostIngestResult>, crate::Error> {
     let mut db = ctx.db_pool.get().await?;
     let author = match author {
         Some(author) => Some(super::get_or_fetch_user_local_id(author, &db, &ctx).await?),
         None => None,
     };
 
+    if let Some(author) = author {
+        if is_user_banned(author, &db).await? {
+            log::debug!("Ignoring post {} from banned user", object_id);
+            return Ok(None);
+        }
+
+        if !crate::can_post_in_community(&db, author, community_local_id).await? {
+            log::debug!("Ignoring post {} from non-member", object_id);
+            return Ok(None);
+        }
+    }
+
     let content_is_html = media_type.is_none() || media_type == Some(&mime::TEXT_HTML);
     let (content_text, content_html) = if content_is_html {
-        (None, Some(content))
+        (None, content.map(crate::clean_html))
     } else {
-        (Some(content), None)
+        (content, None)
     };
 
-    let approved = is_announce.is_some() || community_is_local;
+    let automod_match = crate::automod::check_content(
+        &crate::automod::AutomodContent {
+            community: community_local_id,
+            author,
+            title: Some(title),
+            href,
+            content,
+        },
+        &db,
+        &ctx,
+    )
+    .await?;
+
+    let held = match automod_match.map(|found| found.action) {
+        // held posts stay hidden until approved in the local modqueue
+        Some(AutomodAction::Hold) => true,
+        Some(AutomodAction::Reject) => {
+            log::debug!("Ignoring post {} blocked by automod", object_id);
+            return Ok(None);
+        }
+        Some(AutomodAction::Report) | None => false,
+    };
+
+    let approved = (is_announce.is_some() || community_is_local) && !held;
+
+    let sensitive = flags.sensitive.unwrap_or(false);
 
-    let sensitive = sensitive.unwrap_or(false);
+    let href_canonical = href.map(|href| ctx.canonicalize_href(href));
 
-    let (post_local_id, poll_output) = {
+    let (post_local_id, poll_output, is_new) = {
         let trans = db.transaction().await?;
         let row = trans.query_one(
-            "INSERT INTO post (author, href, content_text, content_html, title, created, community, local, ap_id, approved, approved_ap_id, updated_local, sensitive) VALUES ($1, $2, $3, $4, $5, COALESCE($6, current_timestamp), $7, FALSE, $8, $9, $10, current_timestamp, $11) ON CONFLICT (ap_id) DO UPDATE SET approved=($9 OR post.approved), approved_ap_id=(CASE WHEN $9 THEN $10 ELSE post.approved_ap_id END), updated_local=current_timestamp, sensitive=$11 RETURNING id, poll_id",
-            &[&author, &href, &content_text, &content_html, &title, &created, &community_local_id, &object_id.as_str(), &approved, &is_announce.map(|x| x.as_str()), &sensitive],
+            "INSERT INTO post (author, href, content_text, content_html, title, created, community, local, ap_id, approved, approved_ap_id, updated_local, sensitive, pending_approval, language, href_canonical) VALUES ($1, $2, $3, $4, $5, COALESCE($6, current_timestamp), $7, FALSE, $8, $9, $10, current_timestamp, $11, $12, $13, $14) ON CONFLICT (ap_id) DO UPDATE SET approved=($9 OR post.approved), approved_ap_id=(CASE WHEN $9 THEN $10 ELSE post.approved_ap_id END), updated_local=current_timestamp, sensitive=$11, language=$13, href=(CASE WHEN post.deleted THEN post.href ELSE $2 END), href_canonical=(CASE WHEN post.deleted THEN post.href_canonical ELSE $14 END), content_text=(CASE WHEN post.deleted THEN post.content_text ELSE $3 END), content_html=(CASE WHEN post.deleted THEN post.content_html ELSE $4 END), title=(CASE WHEN post.deleted THEN post.title ELSE $5 END) RETURNING id, poll_id, deleted, (xmax = 0)",
+            &[&author, &href, &content_text, &content_html, &title, &created, &community_local_id, &object_id.as_str(), &approved, &is_announce.map(|x| x.as_str()), &sensitive, &held, &language, &href_canonical],
         ).await?;
         let post_local_id = PostLocalID(row.get(0));
         let existing_poll_id: Option<i64> = row.get(1);
+        let deleted: bool = row.get(2);
+        let is_new: bool = row.get(3);
+
+        // only trusted when announced by the community, local communities keep their own state
+        if !community_is_local
+            && is_announce.is_some()
+            && (flags.stickied.is_some() || flags.comments_enabled.is_some())
+        {
+            trans
+                .execute(
+                    "UPDATE post SET sticky=COALESCE($2, sticky), locked=COALESCE(NOT $3, locked) WHERE id=$1",
+                    &[&post_local_id, &flags.stickied, &flags.comments_enabled],
+                )
+                .await?;
+        }
+
+        if !deleted {
+            trans
+                .execute(
+                    "DELETE FROM post_attachment WHERE post=$1",
+                    &[&post_local_id],
+                )
+                .await?;
+
+            if !attachments.is_empty() {
+                let positions: Vec<i32> = (0..(attachments.len() as i32)).collect();
+                let urls: Vec<&str> = attachments.iter().map(|x| x.url.as_str()).collect();
+                let media_types: Vec<Option<&str>> = attachments
+                    .iter()
+                    .map(|x| x.media_type.as_deref())
+                    .collect();
+                let alt_texts: Vec<Option<&str>> =
+                    attachments.iter().map(|x| x.alt_text.as_deref()).collect();
+
+                trans.execute(
+                    "INSERT INTO post_attachment (post, position, url, media_type, alt_text) SELECT $1, * FROM UNNEST($2::INTEGER[], $3::TEXT[], $4::TEXT[], $5::TEXT[])",
+                    &[&post_local_id, &positions, &urls, &media_types, &alt_texts],
+                ).await?;
+            }
+        }
 
         let poll_output = if let Some(poll_id) = existing_poll_id {
             if let Some(poll_info) = &poll_info {
@@ -1736,10 +2705,24 @@ async fn handle_recieved_post(
 
         trans.commit().await?;
 
-        (post_local_id, poll_output)
+        (post_local_id, poll_output, is_new)
     };
 
-    if community_is_local {
+    crate::set_post_hashtags(post_local_id, &hashtags, &db).await?;
+
+    if is_new {
+        if let Some(found) = automod_match {
+            if found.action == AutomodAction::Report {
+                crate::automod::report_post(post_local_id, found, &ctx).await?;
+            }
+        }
+    }
+
+    if is_new && approved {
+        crate::on_community_add_post(community_local_id, post_local_id, &ctx);
+    }
+
+    if community_is_local && !held {
         crate::on_local_community_add_post(community_local_id, post_local_id, object_id, ctx);
     }
 
@@ -1762,10 +2745,10 @@ async fn handle_recieved_post(
         }
     });
 
-    Ok(PostIngestResult {
+    Ok(Some(PostIngestResult {
         id: post_local_id,
         poll,
-    })
+    }))
 }
 
 fn try_transform_inner<T: TryInto<U>, U>(
@@ -1775,5 +2758,6 @@ fn try_transform_inner<T: TryInto<U>, U>(
         l.inner.try_into()?,
         l.ext_one,
         l.ext_two,
+        l.ext_three,
     ))
 }
@@ -0,0 +1,225 @@
+use serde_json::{Map, Value};
+
+const PUBLIC_ID: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Compact forms of the public collection, valid wherever the activitystreams context is in effect
+const PUBLIC_ALIASES: &[&str] = &["as:Public", "Public"];
+
+const AUDIENCE_PROPERTIES: &[&str] = &["to", "cc", "bto", "bcc", "audience"];
+
+/// Properties only read as a plain URI, which some implementations send as embedded objects
+const LINK_PROPERTIES: &[&str] = &["inbox", "outbox", "followers", "following", "featured"];
+
+const ACTOR_TYPES: &[&str] = &["Person", "Service", "Application", "Group", "Organization"];
+
+/// Every type `KnownObject` can hold, used to pick one out of multi-typed objects
+const KNOWN_TYPES: &[&str] = &[
+    "Accept",
+    "Add",
+    "Announce",
+    "Create",
+    "Delete",
+    "Dislike",
+    "EmojiReact",
+    "Flag",
+    "Follow",
+    "Join",
+    "Leave",
+    "Like",
+    "Move",
+    "Undo",
+    "Update",
+    "Person",
+    "Remove",
+    "Service",
+    "Application",
+    "Group",
+    "Article",
+    "Image",
+    "Page",
+    "Note",
+    "Question",
+    "Reject",
+];
+
+/// Rewrites a received document into the shapes the activitystreams types expect.
+///
+/// JSON-LD allows most properties to be either a single value or an array, and objects to be
+/// embedded or referenced by ID, so different implementations send the same data differently.
+pub fn normalize(value: &mut Value) {
+    let obj = match value {
+        Value::Object(obj) => obj,
+        Value::Array(values) => {
+            values.iter_mut().for_each(normalize);
+            return;
+        }
+        _ => return,
+    };
+
+    normalize_type(obj);
+
+    for key in AUDIENCE_PROPERTIES {
+        if let Some(audience) = obj.get_mut(*key) {
+            normalize_audience(audience);
+        }
+    }
+
+    for key in LINK_PROPERTIES {
+        if let Some(link) = obj.get_mut(*key) {
+            collapse_to_id(link);
+        }
+    }
+
+    if let Some(Value::Object(endpoints)) = obj.get_mut("endpoints") {
+        if let Some(shared_inbox) = endpoints.get_mut("sharedInbox") {
+            collapse_to_id(shared_inbox);
+        }
+    }
+
+    normalize_public_key(obj);
+    normalize_attributed_to(obj);
+    normalize_sensitive(obj);
+    normalize_content_map(obj);
+
+    if let Some(inner) = obj.get_mut("object") {
+        normalize(inner);
+    }
+}
+
+fn type_of(value: &Value) -> Option<&str> {
+    match value.get("type")? {
+        Value::String(kind) => Some(kind),
+        Value::Array(kinds) => kinds.iter().find_map(|kind| kind.as_str()),
+        _ => None,
+    }
+}
+
+fn normalize_type(obj: &mut Map<String, Value>) {
+    if let Some(Value::Array(kinds)) = obj.get("type") {
+        let kind = kinds
+            .iter()
+            .filter_map(|kind| kind.as_str())
+            .find(|kind| KNOWN_TYPES.contains(kind))
+            .or_else(|| kinds.iter().find_map(|kind| kind.as_str()))
+            .map(|kind| Value::String(kind.to_owned()));
+
+        if let Some(kind) = kind {
+            obj.insert("type".to_owned(), kind);
+        }
+    }
+}
+
+fn normalize_audience(value: &mut Value) {
+    match value {
+        Value::String(id) => {
+            if PUBLIC_ALIASES.contains(&id.as_str()) {
+                *id = PUBLIC_ID.to_owned();
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize_audience),
+        _ => {}
+    }
+}
+
+fn collapse_to_id(value: &mut Value) {
+    if let Value::Array(values) = value {
+        if values.len() == 1 {
+            *value = values.pop().unwrap();
+        }
+    }
+
+    let id = match value {
+        Value::Object(obj) => obj.get("id").filter(|id| id.is_string()).cloned(),
+        _ => None,
+    };
+
+    if let Some(id) = id {
+        *value = id;
+    }
+}
+
+/// Some actors list several keys, or only reference one by ID, which can't be used directly
+fn normalize_public_key(obj: &mut Map<String, Value>) {
+    let owner = obj.get("id").cloned();
+
+    let key = match obj.get("publicKey") {
+        Some(key) => key,
+        None => return,
+    };
+
+    if let Value::Array(keys) = key {
+        let found = keys
+            .iter()
+            .find(|key| owner.is_some() && key.get("owner") == owner.as_ref())
+            .or_else(|| keys.iter().find(|key| key.is_object()))
+            .cloned();
+
+        match found {
+            Some(found) => {
+                obj.insert("publicKey".to_owned(), found);
+            }
+            None => {
+                obj.remove("publicKey");
+            }
+        }
+    } else if !key.is_object() {
+        obj.remove("publicKey");
+    }
+}
+
+/// Posts may be attributed to both an author and a group, only the author is used
+fn normalize_attributed_to(obj: &mut Map<String, Value>) {
+    if let Some(Value::Array(values)) = obj.get_mut("attributedTo") {
+        if values.len() > 1 {
+            let idx = values
+                .iter()
+                .position(|value| match type_of(value) {
+                    Some(kind) => kind != "Group" && ACTOR_TYPES.contains(&kind),
+                    None => false,
+                })
+                .unwrap_or(0);
+
+            let value = values.swap_remove(idx);
+            obj.insert("attributedTo".to_owned(), value);
+        }
+    }
+}
+
+fn normalize_sensitive(obj: &mut Map<String, Value>) {
+    let sensitive = match obj.get("sensitive") {
+        None | Some(Value::Bool(_)) => return,
+        Some(Value::String(value)) => value.parse::<bool>().ok(),
+        Some(Value::Array(values)) => values.iter().find_map(|value| value.as_bool()),
+        Some(_) => None,
+    };
+
+    match sensitive {
+        Some(sensitive) => {
+            obj.insert("sensitive".to_owned(), Value::Bool(sensitive));
+        }
+        None => {
+            obj.remove("sensitive");
+        }
+    }
+}
+
+fn normalize_content_map(obj: &mut Map<String, Value>) {
+    if let Some(Value::Object(map)) = obj.get_mut("contentMap") {
+        map.retain(|_, value| {
+            if let Value::Array(values) = value {
+                if let Some(first) = values.iter().find(|x| x.is_string()).cloned() {
+                    *value = first;
+                }
+            }
+
+            value.is_string()
+        });
+    } else {
+        obj.remove("contentMap");
+    }
+}
+
+pub fn parse_known_object(mut value: Value) -> Result<super::KnownObject, serde_json::Error> {
+    normalize(&mut value);
+    serde_json::from_value(value)
+}
@@ -1,6 +1,7 @@
 use super::try_strip_host;
 use crate::types::{
-    CommentLocalID, CommunityLocalID, PollLocalID, PollOptionLocalID, PostLocalID, UserLocalID,
+    CommentLocalID, CommunityLocalID, PollLocalID, PollOptionLocalID, PostLocalID, RelayID,
+    UserLocalID,
 };
 use crate::BaseURL;
 
@@ -9,12 +10,15 @@ type RefRouteNode<P> = trout::Node<P, String, LocalObjectRef, ()>;
 lazy_static::lazy_static! {
     static ref LOCAL_REF_ROUTES: RefRouteNode<()> = {
         RefRouteNode::new()
+            .with_child("actor", RefRouteNode::new().with_handler((), |_, _, _| LocalObjectRef::InstanceActor))
             .with_child(
                 "comments",
                 RefRouteNode::new()
                     .with_child_parse::<CommentLocalID, _>(
                         RefRouteNode::new().with_handler((), |(comment,), _, _| LocalObjectRef::Comment(comment))
+                            .with_child("dislikes", RefRouteNode::new().with_child_parse::<UserLocalID, _>(RefRouteNode::new().with_handler((), |(comment, user), _, _| LocalObjectRef::CommentDislike(comment, user))))
                             .with_child("likes", RefRouteNode::new().with_child_parse::<UserLocalID, _>(RefRouteNode::new().with_handler((), |(comment, user), _, _| LocalObjectRef::CommentLike(comment, user))))
+                            .with_child("reactions", RefRouteNode::new().with_child_parse::<i64, _>(RefRouteNode::new().with_handler((), |(comment, reaction), _, _| LocalObjectRef::CommentReaction(comment, reaction))))
                     )
             )
             .with_child(
@@ -40,8 +44,25 @@ lazy_static::lazy_static! {
                                                 RefRouteNode::new()
                                                     .with_handler((), |(community, follower), _, _| LocalObjectRef::CommunityFollowJoin(community, follower))
                                             )
+                                            .with_child(
+                                                "reject",
+                                                RefRouteNode::new()
+                                                    .with_handler((), |(community, follower), _, _| LocalObjectRef::CommunityFollowReject(community, follower))
+                                            )
                                     )
                             )
+                            .with_child(
+                                "moderators",
+                                RefRouteNode::new()
+                                    .with_handler((), |(community,), _, _| LocalObjectRef::CommunityModerators(community))
+                            )
+                            .with_child(
+                                "modlog",
+                                RefRouteNode::new()
+                                    .with_handler((), |(community,), _, _| LocalObjectRef::CommunityModlog(community))
+                                    .with_child("events", RefRouteNode::new().with_child_parse::<i64, _>(RefRouteNode::new().with_handler((), |(community, event), _, _| LocalObjectRef::CommunityModlogEvent(community, event))))
+                                    .with_child("page", RefRouteNode::new().with_child_parse::<crate::TimestampAndIDOrLatest, _>(RefRouteNode::new().with_handler((), |(community, page), _, _| LocalObjectRef::CommunityModlogPage(community, page))))
+                            )
                             .with_child(
                                 "outbox",
                                 RefRouteNode::new()
@@ -65,15 +86,28 @@ lazy_static::lazy_static! {
                     .with_child_parse::<PostLocalID, _>(
                         RefRouteNode::new()
                             .with_handler((), |(post,), _, _| LocalObjectRef::Post(post))
+                            .with_child("dislikes", RefRouteNode::new().with_child_parse::<UserLocalID, _>(RefRouteNode::new().with_handler((), |(post, user), _, _| LocalObjectRef::PostDislike(post, user))))
                             .with_child("likes", RefRouteNode::new().with_child_parse::<UserLocalID, _>(RefRouteNode::new().with_handler((), |(post, user), _, _| LocalObjectRef::PostLike(post, user))))
+                            .with_child("reactions", RefRouteNode::new().with_child_parse::<i64, _>(RefRouteNode::new().with_handler((), |(post, reaction), _, _| LocalObjectRef::PostReaction(post, reaction))))
                     )
             )
+            .with_child("relays", RefRouteNode::new().with_child_parse::<RelayID, _>(RefRouteNode::new().with_child("follow", RefRouteNode::new().with_handler((), |(relay,), _, _| LocalObjectRef::RelayFollow(relay)))))
             .with_child(
                 "users",
                 RefRouteNode::new()
                     .with_child_parse::<UserLocalID, _>(
                         RefRouteNode::new()
                             .with_handler((), |(user,), _, _| LocalObjectRef::User(user))
+                            .with_child(
+                                "followers",
+                                RefRouteNode::new()
+                                    .with_handler((), |(user,), _, _| LocalObjectRef::UserFollowers(user))
+                                    .with_child_parse::<UserLocalID, _>(
+                                        RefRouteNode::new()
+                                            .with_child("accept", RefRouteNode::new().with_handler((), |(user, follower), _, _| LocalObjectRef::UserFollowAccept(user, follower)))
+                                            .with_child("reject", RefRouteNode::new().with_handler((), |(user, follower), _, _| LocalObjectRef::UserFollowReject(user, follower)))
+                                    )
+                            )
                             .with_child("outbox", RefRouteNode::new().with_handler((), |(user,), _, _| LocalObjectRef::UserOutbox(user)).with_child("page", RefRouteNode::new().with_child_parse::<crate::TimestampOrLatest, _>(RefRouteNode::new().with_handler((), |(user, page), _, _| LocalObjectRef::UserOutboxPage(user, page)))))
                     )
             )
@@ -83,19 +117,33 @@ lazy_static::lazy_static! {
 #[derive(Debug, Clone, Copy)]
 pub enum LocalObjectRef {
     Comment(CommentLocalID),
+    CommentDislike(CommentLocalID, UserLocalID),
     CommentLike(CommentLocalID, UserLocalID),
+    CommentReaction(CommentLocalID, i64),
     Community(CommunityLocalID),
     CommunityFeatured(CommunityLocalID),
     CommunityFollowers(CommunityLocalID),
     CommunityFollow(CommunityLocalID, UserLocalID),
     CommunityFollowJoin(CommunityLocalID, UserLocalID),
+    CommunityFollowReject(CommunityLocalID, UserLocalID),
+    CommunityModerators(CommunityLocalID),
+    CommunityModlog(CommunityLocalID),
+    CommunityModlogEvent(CommunityLocalID, i64),
+    CommunityModlogPage(CommunityLocalID, crate::TimestampAndIDOrLatest),
     CommunityOutbox(CommunityLocalID),
     CommunityOutboxPage(CommunityLocalID, crate::TimestampOrLatest),
+    InstanceActor,
     PollVote(PollLocalID, UserLocalID, PollOptionLocalID),
     Post(PostLocalID),
+    PostDislike(PostLocalID, UserLocalID),
     PostLike(PostLocalID, UserLocalID),
+    PostReaction(PostLocalID, i64),
+    RelayFollow(RelayID),
     SharedInbox,
     User(UserLocalID),
+    UserFollowers(UserLocalID),
+    UserFollowAccept(UserLocalID, UserLocalID),
+    UserFollowReject(UserLocalID, UserLocalID),
     UserOutbox(UserLocalID),
     UserOutboxPage(UserLocalID, crate::TimestampOrLatest),
 }
@@ -129,12 +177,24 @@ impl LocalObjectRef {
                     .extend(&["comments", &comment.to_string()]);
                 res
             }
+            LocalObjectRef::CommentDislike(comment, user) => {
+                let mut res = LocalObjectRef::Comment(comment).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&["dislikes", &user.to_string()]);
+                res
+            }
             LocalObjectRef::CommentLike(comment, user) => {
                 let mut res = LocalObjectRef::Comment(comment).to_local_uri(host_url_apub);
                 res.path_segments_mut()
                     .extend(&["likes", &user.to_string()]);
                 res
             }
+            LocalObjectRef::CommentReaction(comment, reaction) => {
+                let mut res = LocalObjectRef::Comment(comment).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&["reactions", &reaction.to_string()]);
+                res
+            }
             LocalObjectRef::Community(community) => {
                 let mut res = host_url_apub.clone();
                 res.path_segments_mut()
@@ -163,6 +223,35 @@ impl LocalObjectRef {
                 res.path_segments_mut().push("join");
                 res
             }
+            LocalObjectRef::CommunityFollowReject(community, follower) => {
+                let mut res = LocalObjectRef::CommunityFollow(community, follower)
+                    .to_local_uri(host_url_apub);
+                res.path_segments_mut().push("reject");
+                res
+            }
+            LocalObjectRef::CommunityModerators(community) => {
+                let mut res = LocalObjectRef::Community(community).to_local_uri(host_url_apub);
+                res.path_segments_mut().push("moderators");
+                res
+            }
+            LocalObjectRef::CommunityModlog(community) => {
+                let mut res = LocalObjectRef::Community(community).to_local_uri(host_url_apub);
+                res.path_segments_mut().push("modlog");
+                res
+            }
+            LocalObjectRef::CommunityModlogEvent(community, event) => {
+                let mut res =
+                    LocalObjectRef::CommunityModlog(community).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&["events", &event.to_string()]);
+                res
+            }
+            LocalObjectRef::CommunityModlogPage(community, page) => {
+                let mut res =
+                    LocalObjectRef::CommunityModlog(community).to_local_uri(host_url_apub);
+                res.path_segments_mut().extend(&["page", &page.to_string()]);
+                res
+            }
             LocalObjectRef::CommunityOutbox(community) => {
                 let mut res = LocalObjectRef::Community(community).to_local_uri(host_url_apub);
                 res.path_segments_mut().push("outbox");
@@ -174,6 +263,11 @@ impl LocalObjectRef {
                 res.path_segments_mut().extend(&["page", &page.to_string()]);
                 res
             }
+            LocalObjectRef::InstanceActor => {
+                let mut res = host_url_apub.clone();
+                res.path_segments_mut().push("actor");
+                res
+            }
             LocalObjectRef::PollVote(poll, user, option) => {
                 let mut res = host_url_apub.clone();
                 res.path_segments_mut().extend(&[
@@ -192,6 +286,12 @@ impl LocalObjectRef {
                     .extend(&["posts", &post.to_string()]);
                 res
             }
+            LocalObjectRef::PostDislike(post, user) => {
+                let mut res = LocalObjectRef::Post(post).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&["dislikes", &user.to_string()]);
+                res
+            }
             LocalObjectRef::PostLike(post, user) => {
                 let mut res =
                     crate::apub_util::LocalObjectRef::Post(post).to_local_uri(host_url_apub);
@@ -199,6 +299,18 @@ impl LocalObjectRef {
                     .extend(&["likes", &user.to_string()]);
                 res
             }
+            LocalObjectRef::PostReaction(post, reaction) => {
+                let mut res = LocalObjectRef::Post(post).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&["reactions", &reaction.to_string()]);
+                res
+            }
+            LocalObjectRef::RelayFollow(relay) => {
+                let mut res = host_url_apub.clone();
+                res.path_segments_mut()
+                    .extend(&["relays", &relay.to_string(), "follow"]);
+                res
+            }
             LocalObjectRef::SharedInbox => {
                 let mut res = host_url_apub.clone();
                 res.path_segments_mut().push("inbox");
@@ -210,6 +322,23 @@ impl LocalObjectRef {
                     .extend(&["users", &user.to_string()]);
                 res
             }
+            LocalObjectRef::UserFollowers(user) => {
+                let mut res = LocalObjectRef::User(user).to_local_uri(host_url_apub);
+                res.path_segments_mut().push("followers");
+                res
+            }
+            LocalObjectRef::UserFollowAccept(user, follower) => {
+                let mut res = LocalObjectRef::UserFollowers(user).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&[follower.to_string().as_str(), "accept"]);
+                res
+            }
+            LocalObjectRef::UserFollowReject(user, follower) => {
+                let mut res = LocalObjectRef::UserFollowers(user).to_local_uri(host_url_apub);
+                res.path_segments_mut()
+                    .extend(&[follower.to_string().as_str(), "reject"]);
+                res
+            }
             LocalObjectRef::UserOutbox(user) => {
                 let mut res = LocalObjectRef::User(user).to_local_uri(host_url_apub);
                 res.path_segments_mut().push("outbox");
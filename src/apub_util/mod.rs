@@ -1,28 +1,55 @@
 use crate::types::{
     ActorLocalRef, CommentLocalID, CommunityLocalID, FlagLocalID, PollLocalID, PollOptionLocalID,
-    PostLocalID, ThingLocalRef, UserLocalID,
+    PostLocalID, RelayID, ThingLocalRef, UserLocalID,
 };
 use crate::BaseURL;
 use activitystreams::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::ops::Deref;
 use std::sync::Arc;
 
+pub mod compat;
 pub mod ingest;
+pub mod jsonld;
 pub mod local_object_ref;
 
 pub use local_object_ref::LocalObjectRef;
 
 pub const ACTIVITY_TYPE: &str = "application/activity+json";
+pub const LD_JSON_TYPE: &str =
+    r#"application/ld+json; profile="https://www.w3.org/ns/activitystreams""#;
+
+const ACTIVITYSTREAMS_PROFILE: &str = "https://www.w3.org/ns/activitystreams";
+const FETCH_ACCEPT: &str = r#"application/activity+json, application/ld+json; profile="https://www.w3.org/ns/activitystreams""#;
 
 pub const SIGALG_RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
 pub const SIGALG_RSA_SHA512: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512";
 
 pub const INTERACTIVE_FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
 
+const ACTOR_CACHE_DURATION: std::time::Duration = std::time::Duration::from_secs(60 * 10);
+const ACTOR_CACHE_MAX_SIZE: usize = 4096;
+
+lazy_static::lazy_static! {
+    static ref EXTENSIONS_CONTEXT: activitystreams::base::AnyBase = activitystreams::base::AnyBase::from_arbitrary_json(serde_json::json!({
+        "sensitive": "as:sensitive",
+        "toot": "http://joinmastodon.org/ns#",
+        "votersCount": "toot:votersCount"
+    })).unwrap();
+}
+
+/// Contexts for outgoing documents, defining the extension properties they may use
+pub fn default_contexts() -> Vec<activitystreams::base::AnyBase> {
+    vec![
+        activitystreams::base::AnyBase::from_xsd_any_uri(activitystreams::context()),
+        activitystreams::base::AnyBase::from_xsd_any_uri(activitystreams::security()),
+        EXTENSIONS_CONTEXT.clone(),
+    ]
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(transparent)]
 pub struct Verified<T: Clone>(pub T);
@@ -53,6 +80,14 @@ impl<T: Clone, U1: Clone, U2: Clone> From<Verified<activitystreams_ext::Ext2<T,
     }
 }
 
+impl<T: Clone, U1: Clone, U2: Clone, U3: Clone>
+    From<Verified<activitystreams_ext::Ext3<T, U1, U2, U3>>> for Verified<T>
+{
+    fn from(src: Verified<activitystreams_ext::Ext3<T, U1, U2, U3>>) -> Self {
+        Verified(src.0.inner)
+    }
+}
+
 pub struct Contained<'a, T: activitystreams::markers::Base + Clone>(pub Cow<'a, Verified<T>>);
 impl<'a, T: activitystreams::markers::Base + Clone> std::ops::Deref for Contained<'a, T> {
     type Target = Verified<T>;
@@ -78,31 +113,45 @@ pub enum KnownObject {
     Announce(activitystreams::activity::Announce),
     Create(activitystreams::activity::Create),
     Delete(activitystreams::activity::Delete),
+    Dislike(activitystreams::activity::Dislike),
+    EmojiReact(EmojiReact),
     Flag(activitystreams::activity::Flag),
     Follow(activitystreams::activity::Follow),
     Join(activitystreams::activity::Join),
     Leave(activitystreams::activity::Leave),
     Like(activitystreams::activity::Like),
+    Move(activitystreams::activity::Move),
+    Reject(activitystreams::activity::Reject),
     Undo(activitystreams::activity::Undo),
     Update(activitystreams::activity::Update),
     Person(
-        activitystreams_ext::Ext1<
+        activitystreams_ext::Ext2<
             activitystreams::actor::ApActor<activitystreams::actor::Person>,
             PublicKeyExtension<'static>,
+            MovedExtension,
         >,
     ),
     Remove(activitystreams::activity::Remove),
     Service(
-        activitystreams_ext::Ext1<
+        activitystreams_ext::Ext2<
             activitystreams::actor::ApActor<activitystreams::actor::Service>,
             PublicKeyExtension<'static>,
+            MovedExtension,
         >,
     ),
-    Group(
+    Application(
         activitystreams_ext::Ext2<
+            activitystreams::actor::ApActor<activitystreams::actor::Application>,
+            PublicKeyExtension<'static>,
+            MovedExtension,
+        >,
+    ),
+    Group(
+        activitystreams_ext::Ext3<
             activitystreams::actor::ApActor<activitystreams::actor::Group>,
             PublicKeyExtension<'static>,
             FeaturedExtension,
+            MovedExtension,
         >,
     ),
     Article(ExtendedPostlike<activitystreams::object::Article>),
@@ -112,6 +161,20 @@ pub enum KnownObject {
     Question(ExtendedPostlike<activitystreams::activity::Question>),
 }
 
+/// Pleroma extension for reacting to an object with an emoji, which is given as `content`
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum EmojiReactType {
+    EmojiReact,
+}
+
+impl Default for EmojiReactType {
+    fn default() -> Self {
+        EmojiReactType::EmojiReact
+    }
+}
+
+pub type EmojiReact = activitystreams::activity::ActorAndObject<EmojiReactType>;
+
 #[derive(Deserialize)]
 pub struct JustMaybeAPID {
     id: Option<BaseURL>,
@@ -144,21 +207,98 @@ pub struct FeaturedExtension {
     pub featured: Option<url::Url>,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedExtension {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub also_known_as:
+        Option<activitystreams::primitives::OneOrMany<activitystreams::base::AnyBase>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<activitystreams::primitives::OneOrMany<activitystreams::base::AnyBase>>,
+}
+
+impl MovedExtension {
+    pub fn new(also_known_as: &[&str], moved_to: Option<&str>) -> Result<Self, crate::Error> {
+        let also_known_as: Vec<_> = also_known_as
+            .iter()
+            .map(|x| Ok(activitystreams::base::AnyBase::from_xsd_any_uri(x.parse()?)))
+            .collect::<Result<_, crate::Error>>()?;
+
+        Ok(Self {
+            also_known_as: if also_known_as.is_empty() {
+                None
+            } else {
+                Some(activitystreams::primitives::OneOrMany::from_many(
+                    also_known_as,
+                ))
+            },
+            moved_to: moved_to
+                .map(|x| {
+                    Ok::<_, crate::Error>(activitystreams::primitives::OneOrMany::from_xsd_any_uri(
+                        x.parse()?,
+                    ))
+                })
+                .transpose()?,
+        })
+    }
+
+    pub fn also_known_as_ids(&self) -> Vec<&str> {
+        self.also_known_as
+            .iter()
+            .flat_map(|x| x.iter())
+            .filter_map(|x| x.id())
+            .map(|x| x.as_str())
+            .collect()
+    }
+
+    pub fn moved_to_id(&self) -> Option<&url::Url> {
+        self.moved_to.as_ref().and_then(|x| x.as_single_id())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct TargetExtension {
     #[serde(skip_serializing_if = "Option::is_none")]
     target: Option<activitystreams::primitives::OneOrMany<activitystreams::base::AnyBase>>,
 }
 
+/// Post state flags, `stickied` and `commentsEnabled` being Lemmy's
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
-pub struct SensitiveExtension {
+#[serde(rename_all = "camelCase")]
+pub struct ContentFlagsExtension {
     sensitive: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stickied: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comments_enabled: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageExtension {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_map: Option<std::collections::BTreeMap<String, String>>,
 }
 
-pub type ExtendedPostlike<T> = activitystreams_ext::Ext2<T, TargetExtension, SensitiveExtension>;
+impl LanguageExtension {
+    pub fn language(&self) -> Option<String> {
+        self.content_map
+            .iter()
+            .flat_map(|map| map.keys())
+            .find_map(|key| crate::normalize_language(key))
+    }
+}
+
+pub type ExtendedPostlike<T> =
+    activitystreams_ext::Ext3<T, TargetExtension, ContentFlagsExtension, LanguageExtension>;
 
 pub fn make_extended_postlike<T>(src: T) -> ExtendedPostlike<T> {
-    ExtendedPostlike::new(src, Default::default(), Default::default())
+    ExtendedPostlike::new(
+        src,
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    )
 }
 
 #[derive(Deserialize)]
@@ -217,6 +357,13 @@ impl FollowLike {
             FollowLike::Join(join) => join.actor_unchecked(),
         }
     }
+
+    pub fn into_any_base(self) -> Result<activitystreams::base::AnyBase, serde_json::Error> {
+        match self {
+            FollowLike::Follow(follow) => activitystreams::base::AnyBase::from_extended(follow),
+            FollowLike::Join(join) => activitystreams::base::AnyBase::from_extended(join),
+        }
+    }
 }
 
 pub fn try_strip_host<'a>(url: &'a impl AsRef<str>, host_url: &url::Url) -> Option<&'a str> {
@@ -234,6 +381,12 @@ pub fn get_local_person_pubkey_apub_id(person: UserLocalID, host_url_apub: &Base
     res
 }
 
+pub fn get_instance_actor_pubkey_apub_id(host_url_apub: &BaseURL) -> BaseURL {
+    let mut res = LocalObjectRef::InstanceActor.to_local_uri(host_url_apub);
+    res.set_fragment(Some("main-key"));
+    res
+}
+
 pub fn get_local_community_pubkey_apub_id(
     community: CommunityLocalID,
     host_url_apub: &BaseURL,
@@ -243,6 +396,201 @@ pub fn get_local_community_pubkey_apub_id(
     res
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityContentType {
+    Activity,
+    LdJson,
+}
+
+impl ActivityContentType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityContentType::Activity => ACTIVITY_TYPE,
+            ActivityContentType::LdJson => LD_JSON_TYPE,
+        }
+    }
+
+    /// Picks the content type to respond with based on an Accept header, or None if the client
+    /// doesn't accept any form of ActivityStreams document
+    pub fn negotiate(accept: Option<&str>) -> Option<Self> {
+        let accept = match accept {
+            None => return Some(ActivityContentType::Activity),
+            Some(accept) if accept.trim().is_empty() => return Some(ActivityContentType::Activity),
+            Some(accept) => accept,
+        };
+
+        let mut best: Option<(f32, Self)> = None;
+
+        for entry in accept.split(',') {
+            let mut parts = entry.split(';');
+            let media_type = parts.next().unwrap().trim().to_ascii_lowercase();
+
+            let mut quality = 1.0;
+            let mut profile = None;
+            for param in parts {
+                if let Some((key, value)) = param.split_once('=') {
+                    let value = value.trim().trim_matches('"');
+                    match key.trim() {
+                        "q" => quality = value.parse().unwrap_or(0.0),
+                        "profile" => profile = Some(value.to_owned()),
+                        _ => {}
+                    }
+                }
+            }
+
+            let found = match media_type.as_str() {
+                "application/activity+json" | "application/json" | "application/*" | "*/*" => {
+                    ActivityContentType::Activity
+                }
+                "application/ld+json" => match &profile {
+                    Some(profile)
+                        if !profile
+                            .split_whitespace()
+                            .any(|profile| profile == ACTIVITYSTREAMS_PROFILE) =>
+                    {
+                        continue
+                    }
+                    _ => ActivityContentType::LdJson,
+                },
+                _ => continue,
+            };
+
+            if quality <= 0.0 {
+                continue;
+            }
+
+            match best {
+                Some((best_quality, _)) if best_quality >= quality => {}
+                _ => best = Some((quality, found)),
+            }
+        }
+
+        best.map(|(_, value)| value)
+    }
+}
+
+/// Builds a response for an ActivityStreams document. The content type is adjusted to match the
+/// request's Accept header by `negotiate_activity_response`.
+pub fn activity_response(body: impl Into<hyper::Body>) -> hyper::Response<hyper::Body> {
+    crate::common_response_builder()
+        .header(hyper::header::CONTENT_TYPE, ACTIVITY_TYPE)
+        .header(hyper::header::VARY, "Accept")
+        .body(body.into())
+        .unwrap()
+}
+
+/// How long remote servers and proxies may reuse a fetched object before revalidating
+const OBJECT_MAX_AGE_SECONDS: u32 = 300;
+
+fn format_http_date(time: chrono::DateTime<chrono::FixedOffset>) -> hyper::header::HeaderValue {
+    time.with_timezone(&chrono::offset::Utc)
+        .format("%a, %d %b %Y %T GMT")
+        .to_string()
+        .parse()
+        .unwrap()
+}
+
+/// Whether the request's If-Modified-Since shows it already has the current version
+fn is_not_modified(
+    req: &hyper::Request<hyper::Body>,
+    last_modified: chrono::DateTime<chrono::FixedOffset>,
+) -> bool {
+    req.headers()
+        .get(hyper::header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+        // Last-Modified only has second precision
+        .map(|since| last_modified.timestamp() <= since.timestamp())
+        .unwrap_or(false)
+}
+
+/// Objects from private communities are only cached by the requester, never by shared proxies
+fn set_cache_headers(
+    resp: &mut hyper::Response<hyper::Body>,
+    last_modified: Option<chrono::DateTime<chrono::FixedOffset>>,
+    private: bool,
+) {
+    let cache_control = if private {
+        "private, no-cache".to_owned()
+    } else {
+        format!("public, max-age={}", OBJECT_MAX_AGE_SECONDS)
+    };
+
+    resp.headers_mut().insert(
+        hyper::header::CACHE_CONTROL,
+        hyper::header::HeaderValue::from_str(&cache_control).unwrap(),
+    );
+
+    if let Some(last_modified) = last_modified {
+        resp.headers_mut().insert(
+            hyper::header::LAST_MODIFIED,
+            format_http_date(last_modified),
+        );
+    }
+}
+
+fn not_modified_response(
+    last_modified: chrono::DateTime<chrono::FixedOffset>,
+    private: bool,
+) -> hyper::Response<hyper::Body> {
+    let mut resp = crate::common_response_builder()
+        .status(hyper::StatusCode::NOT_MODIFIED)
+        .header(hyper::header::VARY, "Accept")
+        .body(Default::default())
+        .unwrap();
+
+    set_cache_headers(&mut resp, Some(last_modified), private);
+
+    resp
+}
+
+/// Answers with 304 Not Modified when possible, otherwise builds the full response with caching headers
+pub fn cached_activity_response(
+    req: &hyper::Request<hyper::Body>,
+    last_modified: Option<chrono::DateTime<chrono::FixedOffset>>,
+    private: bool,
+    body: impl FnOnce() -> Result<Vec<u8>, crate::Error>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    if let Some(last_modified) = last_modified {
+        if is_not_modified(req, last_modified) {
+            return Ok(not_modified_response(last_modified, private));
+        }
+    }
+
+    let mut resp = activity_response(body()?);
+    set_cache_headers(&mut resp, last_modified, private);
+
+    Ok(resp)
+}
+
+pub fn negotiate_activity_response(
+    accept: Option<&str>,
+    mut resp: hyper::Response<hyper::Body>,
+    lang: &crate::Translator,
+) -> hyper::Response<hyper::Body> {
+    if resp.headers().get(hyper::header::CONTENT_TYPE)
+        != Some(&hyper::header::HeaderValue::from_static(ACTIVITY_TYPE))
+    {
+        return resp;
+    }
+
+    match ActivityContentType::negotiate(accept) {
+        Some(ActivityContentType::Activity) => resp,
+        Some(content_type) => {
+            resp.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static(content_type.as_str()),
+            );
+            resp
+        }
+        None => crate::error_response(
+            hyper::StatusCode::NOT_ACCEPTABLE,
+            lang,
+            &crate::lang::not_acceptable(),
+        ),
+    }
+}
+
 pub fn now_http_date() -> hyper::header::HeaderValue {
     chrono::offset::Utc::now()
         .format("%a, %d %b %Y %T GMT")
@@ -288,7 +636,64 @@ pub enum ActorLocalInfo {
     },
 }
 
+#[derive(Clone)]
+pub struct CachedActor {
+    pub id: ThingLocalRef,
+    key: Option<(
+        openssl::pkey::PKey<openssl::pkey::Public>,
+        Option<openssl::hash::MessageDigest>,
+    )>,
+}
+
+/// Recently seen remote actors, keyed by ActivityPub ID
+pub type ActorCache = std::sync::Mutex<HashMap<url::Url, (std::time::Instant, CachedActor)>>;
+
+pub fn get_cached_actor(ap_id: &url::Url, ctx: &crate::BaseContext) -> Option<CachedActor> {
+    let cache = ctx.actor_cache.lock().unwrap();
+    match cache.get(ap_id) {
+        Some((time, actor)) if time.elapsed() < ACTOR_CACHE_DURATION => Some(actor.clone()),
+        _ => None,
+    }
+}
+
+fn cache_actor(ap_id: &url::Url, actor: CachedActor, ctx: &crate::BaseContext) {
+    let mut cache = ctx.actor_cache.lock().unwrap();
+    if cache.len() >= ACTOR_CACHE_MAX_SIZE {
+        cache.retain(|_, (time, _)| time.elapsed() < ACTOR_CACHE_DURATION);
+        if cache.len() >= ACTOR_CACHE_MAX_SIZE {
+            cache.clear();
+        }
+    }
+    cache.insert(ap_id.clone(), (std::time::Instant::now(), actor));
+}
+
+pub fn invalidate_cached_actor(ap_id: &url::Url, ctx: &crate::BaseContext) {
+    ctx.actor_cache.lock().unwrap().remove(ap_id);
+}
+
 impl ActorLocalInfo {
+    /// Remembers this actor's local ID and key so later lookups can skip the database
+    pub fn cache(&self, ap_id: &url::Url, ctx: &crate::BaseContext) {
+        let key = self.public_key().and_then(|key_info| {
+            match openssl::pkey::PKey::public_key_from_pem(&key_info.key) {
+                Ok(key) => Some((key, key_info.algorithm)),
+                Err(err) => {
+                    log::warn!("Failed to parse public key for {}: {:?}", ap_id, err);
+                    None
+                }
+            }
+        });
+
+        cache_actor(
+            ap_id,
+            CachedActor {
+                id: self.as_ref(),
+                key,
+            },
+            ctx,
+        );
+    }
+
     pub fn public_key(&self) -> Option<&PubKeyInfo> {
         match self {
             ActorLocalInfo::User { public_key, .. } => public_key.as_ref(),
@@ -320,6 +725,22 @@ pub fn require_containment(object_id: &url::Url, actor_id: &url::Url) -> Result<
     }
 }
 
+/// The remote server reported that an object no longer exists
+#[derive(Debug)]
+pub struct RemoteGone;
+
+impl std::fmt::Display for RemoteGone {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Remote object is gone")
+    }
+}
+
+impl std::error::Error for RemoteGone {}
+
+pub fn is_remote_gone(err: &crate::Error) -> bool {
+    matches!(err, crate::Error::Internal(err) if err.is::<RemoteGone>())
+}
+
 pub async fn fetch_ap_object_raw(
     ap_id: &url::Url,
     ctx: &crate::BaseContext,
@@ -332,18 +753,17 @@ pub async fn fetch_ap_object_raw(
             ));
         }
         // avoid infinite loop in malicious or broken cases
-        let res = crate::res_to_error(
-            ctx.http_client
-                .request(
-                    hyper::Request::get(&current_id)
-                        .header(hyper::header::ACCEPT, ACTIVITY_TYPE)
-                        .body(Default::default())?,
-                )
-                .await?,
-        )
-        .await?;
+        let req = hyper::Request::get(&current_id)
+            .header(hyper::header::ACCEPT, FETCH_ACCEPT)
+            .body(Default::default())?;
+
+        let res = ctx.fetch(req).await?;
+        if res.status() == hyper::StatusCode::GONE {
+            return Err(RemoteGone.into());
+        }
+        let res = crate::res_to_error(res).await?;
 
-        let body = hyper::body::to_bytes(res.into_body()).await?;
+        let body = ctx.read_fetched_body(res).await?;
         let body: serde_json::Value = serde_json::from_slice(&body)?;
 
         current_id = match body.get("id") {
@@ -364,12 +784,51 @@ pub async fn fetch_ap_object_raw(
     Err(crate::Error::InternalStrStatic("Recursion depth exceeded"))
 }
 
+/// Signs a GET request as the instance actor, for servers that require authorized fetch
+pub fn sign_fetch_request(
+    req: &mut hyper::Request<hyper::Body>,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let uri = req.uri().clone();
+
+    let host = uri
+        .host()
+        .ok_or(crate::Error::InternalStrStatic("Missing host in fetch URL"))?;
+    let host = match uri.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_owned(),
+    };
+
+    req.headers_mut().insert(
+        hyper::header::HOST,
+        hyper::header::HeaderValue::from_str(&host)?,
+    );
+    req.headers_mut()
+        .insert(hyper::header::DATE, now_http_date());
+
+    let path_and_query = uri.path_and_query().map(|x| x.as_str()).unwrap_or("/");
+
+    let key_id = get_instance_actor_pubkey_apub_id(&ctx.host_url_apub);
+
+    let signature = hancock::Signature::create_legacy(
+        key_id.as_str(),
+        &hyper::Method::GET,
+        path_and_query,
+        req.headers(),
+        |src| do_sign(&ctx.instance_actor_key, &src),
+    )?;
+
+    req.headers_mut().insert("Signature", signature.to_header());
+
+    Ok(())
+}
+
 pub async fn fetch_ap_object(
     ap_id: &url::Url,
     ctx: &crate::BaseContext,
 ) -> Result<Verified<KnownObject>, crate::Error> {
     let value = fetch_ap_object_raw(ap_id, ctx).await?;
-    let value: KnownObject = serde_json::from_value(value)?;
+    let value = jsonld::parse_known_object(value)?;
     Ok(Verified(value))
 }
 
@@ -383,7 +842,7 @@ pub async fn fetch_or_verify(
         .ok_or(crate::Error::InternalStrStatic("Missing ID in object"))?;
     if is_contained(object_id, sender_ap_id) {
         if let Some(base) = obj.as_base() {
-            return Ok(serde_json::from_value(serde_json::to_value(base)?).map(Verified)?);
+            return Ok(jsonld::parse_known_object(serde_json::to_value(base)?).map(Verified)?);
         }
     }
 
@@ -424,6 +883,14 @@ pub async fn get_or_fetch_user_local_id(
             )))
         }
     } else {
+        if let Some(CachedActor {
+            id: ThingLocalRef::User(id),
+            ..
+        }) = get_cached_actor(ap_id, ctx)
+        {
+            return Ok(id);
+        }
+
         match db
             .query_opt("SELECT id FROM person WHERE ap_id=$1", &[&ap_id.as_str()])
             .await?
@@ -604,7 +1071,7 @@ pub fn spawn_enqueue_send_community_follow(
         let mut follow =
             activitystreams::activity::Follow::new(person_ap_id.clone(), community_ap_id.clone());
         follow
-            .set_context(activitystreams::context())
+            .set_many_contexts(default_contexts())
             .set_id(
                 LocalObjectRef::CommunityFollow(community, local_follower)
                     .to_local_uri(&ctx.host_url_apub)
@@ -613,7 +1080,7 @@ pub fn spawn_enqueue_send_community_follow(
             .set_to(community_ap_id.clone());
 
         let mut join = activitystreams::activity::Join::new(person_ap_id, community_ap_id.clone());
-        join.set_context(activitystreams::context())
+        join.set_many_contexts(default_contexts())
             .set_id(
                 LocalObjectRef::CommunityFollowJoin(community, local_follower)
                     .to_local_uri(&ctx.host_url_apub)
@@ -709,6 +1176,7 @@ pub fn local_community_post_announce_ap(
     community_id: CommunityLocalID,
     post_local_id: PostLocalID,
     post_ap_id: url::Url,
+    private: bool,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Announce, crate::Error> {
     let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
@@ -717,7 +1185,7 @@ pub fn local_community_post_announce_ap(
         activitystreams::activity::Announce::new(community_ap_id.clone(), post_ap_id);
 
     announce
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id.clone();
             res.path_segments_mut()
@@ -728,8 +1196,11 @@ pub fn local_community_post_announce_ap(
             let mut res = community_ap_id;
             res.path_segments_mut().push("followers");
             res
-        })
-        .set_cc(activitystreams::public());
+        });
+
+    if !private {
+        announce.set_cc(activitystreams::public());
+    }
 
     Ok(announce)
 }
@@ -738,13 +1209,14 @@ pub fn local_community_post_add_ap(
     community_id: CommunityLocalID,
     post_local_id: PostLocalID,
     post_ap_id: url::Url,
+    private: bool,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Add, crate::Error> {
     let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
 
     let mut add = activitystreams::activity::Add::new(community_ap_id.clone(), post_ap_id);
 
-    add.set_context(activitystreams::context())
+    add.set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id.clone();
             res.path_segments_mut()
@@ -756,8 +1228,11 @@ pub fn local_community_post_add_ap(
             let mut res = community_ap_id;
             res.path_segments_mut().push("followers");
             res
-        })
-        .set_cc(activitystreams::public());
+        });
+
+    if !private {
+        add.set_cc(activitystreams::public());
+    }
 
     Ok(add)
 }
@@ -766,17 +1241,24 @@ pub fn local_community_post_add_undo_ap(
     community_id: CommunityLocalID,
     post_local_id: PostLocalID,
     post_ap_id: url::Url,
+    private: bool,
     uuid: &uuid::Uuid,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Undo, crate::Error> {
     let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
 
-    let add = local_community_post_add_ap(community_id, post_local_id, post_ap_id, host_url_apub)?;
+    let add = local_community_post_add_ap(
+        community_id,
+        post_local_id,
+        post_ap_id,
+        private,
+        host_url_apub,
+    )?;
 
     let mut undo =
         activitystreams::activity::Undo::new(community_ap_id.clone(), add.into_any_base()?);
 
-    undo.set_context(activitystreams::context())
+    undo.set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id.clone();
             res.path_segments_mut().extend(&[
@@ -792,8 +1274,11 @@ pub fn local_community_post_add_undo_ap(
             let mut res = community_ap_id;
             res.path_segments_mut().push("followers");
             res
-        })
-        .set_cc(activitystreams::public());
+        });
+
+    if !private {
+        undo.set_cc(activitystreams::public());
+    }
 
     Ok(undo)
 }
@@ -802,18 +1287,24 @@ pub fn local_community_post_announce_undo_ap(
     community_id: CommunityLocalID,
     post_local_id: PostLocalID,
     post_ap_id: url::Url,
+    private: bool,
     uuid: &uuid::Uuid,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Undo, crate::Error> {
     let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
 
-    let announce =
-        local_community_post_announce_ap(community_id, post_local_id, post_ap_id, host_url_apub)?;
+    let announce = local_community_post_announce_ap(
+        community_id,
+        post_local_id,
+        post_ap_id,
+        private,
+        host_url_apub,
+    )?;
 
     let mut undo =
         activitystreams::activity::Undo::new(community_ap_id.clone(), announce.into_any_base()?);
 
-    undo.set_context(activitystreams::context())
+    undo.set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id.clone();
             res.path_segments_mut().extend(&[
@@ -829,8 +1320,11 @@ pub fn local_community_post_announce_undo_ap(
             let mut res = community_ap_id;
             res.path_segments_mut().push("followers");
             res
-        })
-        .set_cc(activitystreams::public());
+        });
+
+    if !private {
+        undo.set_cc(activitystreams::public());
+    }
 
     Ok(undo)
 }
@@ -839,6 +1333,7 @@ pub fn local_community_comment_announce_ap(
     community_id: CommunityLocalID,
     comment_local_id: CommentLocalID,
     comment_ap_id: url::Url,
+    private: bool,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Announce, crate::Error> {
     let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
@@ -847,7 +1342,7 @@ pub fn local_community_comment_announce_ap(
         activitystreams::activity::Announce::new(community_ap_id.deref().clone(), comment_ap_id);
 
     announce
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id;
             res.path_segments_mut().extend(&[
@@ -857,43 +1352,67 @@ pub fn local_community_comment_announce_ap(
             ]);
             res.into()
         })
-        .set_to(LocalObjectRef::CommunityFollowers(community_id).to_local_uri(host_url_apub))
-        .set_cc(activitystreams::public());
+        .set_to(LocalObjectRef::CommunityFollowers(community_id).to_local_uri(host_url_apub));
+
+    if !private {
+        announce.set_cc(activitystreams::public());
+    }
 
     Ok(announce)
 }
 
-pub fn spawn_announce_community_post(
+pub fn spawn_enqueue_announce_community_post(
     community: CommunityLocalID,
     post_local_id: PostLocalID,
     post_ap_id: url::Url,
     ctx: Arc<crate::RouteContext>,
 ) {
-    match local_community_post_announce_ap(
+    crate::spawn_task(async move {
+        ctx.enqueue_task(&crate::tasks::AnnounceCommunityPost {
+            community,
+            post: post_local_id,
+            post_ap_id,
+        })
+        .await
+    });
+}
+
+/// Queues the Announce and Add of a post for the community's followers, then records that it
+/// was announced so `ReconcileAnnounces` won't try again
+pub async fn announce_community_post(
+    community: CommunityLocalID,
+    post_local_id: PostLocalID,
+    post_ap_id: url::Url,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let private = is_community_private(community, &ctx).await?;
+
+    let announce = local_community_post_announce_ap(
         community,
         post_local_id,
         post_ap_id.clone(),
+        private,
         &ctx.host_url_apub,
-    ) {
-        Err(err) => {
-            log::error!("Failed to create Announce: {:?}", err);
-        }
-        Ok(announce) => {
-            crate::spawn_task(enqueue_send_to_community_followers(
-                community,
-                announce,
-                ctx.clone(),
-            ));
-        }
-    }
-    match local_community_post_add_ap(community, post_local_id, post_ap_id, &ctx.host_url_apub) {
-        Err(err) => {
-            log::error!("Failed to create Add: {:?}", err);
-        }
-        Ok(add) => {
-            crate::spawn_task(enqueue_send_to_community_followers(community, add, ctx));
-        }
-    }
+    )?;
+    let add = local_community_post_add_ap(
+        community,
+        post_local_id,
+        post_ap_id,
+        private,
+        &ctx.host_url_apub,
+    )?;
+
+    enqueue_send_to_community_followers(community, announce, ctx.clone()).await?;
+    enqueue_send_to_community_followers(community, add, ctx.clone()).await?;
+
+    let db = ctx.db_pool.get().await?;
+    db.execute(
+        "UPDATE post SET announced_at=current_timestamp WHERE id=$1",
+        &[&post_local_id],
+    )
+    .await?;
+
+    Ok(())
 }
 
 pub fn spawn_enqueue_send_community_post_announce_undo(
@@ -907,10 +1426,13 @@ pub fn spawn_enqueue_send_community_post_announce_undo(
         let post_ap_id = post_ap_id.clone();
 
         crate::spawn_task(async move {
+            let private = is_community_private(community, &ctx).await?;
+
             let undo = local_community_post_announce_undo_ap(
                 community,
                 post,
                 post_ap_id,
+                private,
                 &uuid::Uuid::new_v4(),
                 &ctx.host_url_apub,
             )?;
@@ -920,10 +1442,13 @@ pub fn spawn_enqueue_send_community_post_announce_undo(
     }
 
     crate::spawn_task(async move {
+        let private = is_community_private(community, &ctx).await?;
+
         let undo = local_community_post_add_undo_ap(
             community,
             post,
             post_ap_id,
+            private,
             &uuid::Uuid::new_v4(),
             &ctx.host_url_apub,
         )?;
@@ -932,23 +1457,191 @@ pub fn spawn_enqueue_send_community_post_announce_undo(
     });
 }
 
-pub fn spawn_announce_community_comment(
+pub fn spawn_enqueue_announce_community_comment(
     community: CommunityLocalID,
     comment_local_id: CommentLocalID,
     comment_ap_id: url::Url,
     ctx: Arc<crate::RouteContext>,
 ) {
+    crate::spawn_task(async move {
+        ctx.enqueue_task(&crate::tasks::AnnounceCommunityComment {
+            community,
+            comment: comment_local_id,
+            comment_ap_id,
+        })
+        .await
+    });
+}
+
+/// Queues the Announce of a comment for the community's followers, then records that it was
+/// announced
+pub async fn announce_community_comment(
+    community: CommunityLocalID,
+    comment_local_id: CommentLocalID,
+    comment_ap_id: url::Url,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let private = is_community_private(community, &ctx).await?;
+
     let announce = local_community_comment_announce_ap(
         community,
         comment_local_id,
         comment_ap_id,
+        private,
         &ctx.host_url_apub,
-    );
+    )?;
+    enqueue_send_to_community_followers(community, announce, ctx.clone()).await?;
 
-    crate::spawn_task(async move {
-        let announce = announce?;
-        enqueue_send_to_community_followers(community, announce, ctx).await
-    });
+    let db = ctx.db_pool.get().await?;
+    db.execute(
+        "UPDATE reply SET announced_at=current_timestamp WHERE id=$1",
+        &[&comment_local_id],
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub fn local_community_modlog_event_to_ap(
+    community_id: CommunityLocalID,
+    event_id: i64,
+    action: &str,
+    time: chrono::DateTime<chrono::offset::FixedOffset>,
+    object: activitystreams::base::AnyBase,
+    reason: Option<&str>,
+    host_url_apub: &BaseURL,
+) -> Result<Option<activitystreams::base::AnyBase>, crate::Error> {
+    fn apply_properties<
+        K,
+        O: activitystreams::object::ObjectExt<K> + activitystreams::base::BaseExt<K>,
+    >(
+        activity: &mut O,
+        event_ap_id: BaseURL,
+        time: chrono::DateTime<chrono::offset::FixedOffset>,
+        reason: Option<&str>,
+    ) {
+        activity
+            .set_many_contexts(default_contexts())
+            .set_id(event_ap_id.into())
+            .set_published(time)
+            .set_to(activitystreams::public());
+        if let Some(reason) = reason {
+            activity.set_summary(reason);
+        }
+    }
+
+    let community_ap_id = LocalObjectRef::Community(community_id).to_local_uri(host_url_apub);
+    let event_ap_id =
+        LocalObjectRef::CommunityModlogEvent(community_id, event_id).to_local_uri(host_url_apub);
+
+    // reversing actions are expressed as Undo of the original, since there is no inverse type
+    let undo = |inner: activitystreams::base::AnyBase| {
+        let mut undo = activitystreams::activity::Undo::new(community_ap_id.clone(), inner);
+        apply_properties(&mut undo, event_ap_id.clone(), time, reason);
+        undo.into_any_base()
+    };
+
+    Ok(Some(match action {
+        "approve_post" | "reject_post" | "sticky_post" | "unsticky_post" | "add_moderator"
+        | "remove_moderator" => {
+            let target = match action {
+                "approve_post" | "reject_post" => LocalObjectRef::CommunityOutbox(community_id),
+                "sticky_post" | "unsticky_post" => LocalObjectRef::CommunityFeatured(community_id),
+                _ => LocalObjectRef::CommunityModerators(community_id),
+            }
+            .to_local_uri(host_url_apub);
+
+            if matches!(action, "approve_post" | "sticky_post" | "add_moderator") {
+                let mut add = activitystreams::activity::Add::new(community_ap_id, object);
+                apply_properties(&mut add, event_ap_id, time, reason);
+                add.set_target(target);
+
+                add.into_any_base()?
+            } else {
+                let mut remove = activitystreams::activity::Remove::new(community_ap_id, object);
+                apply_properties(&mut remove, event_ap_id, time, reason);
+                remove.set_target(target);
+
+                remove.into_any_base()?
+            }
+        }
+        "lock_post" | "unlock_post" => {
+            // activitystreams has no Lock type, so this follows the form other software uses
+            let mut lock = serde_json::json!({
+                "type": "Lock",
+                "actor": &community_ap_id,
+                "object": &object,
+            });
+
+            if action == "lock_post" {
+                lock["@context"] = serde_json::to_value(default_contexts())?;
+                lock["id"] = serde_json::to_value(&event_ap_id)?;
+                lock["published"] = time.to_rfc3339().into();
+                lock["to"] = activitystreams::public().as_str().into();
+                if let Some(reason) = reason {
+                    lock["summary"] = reason.into();
+                }
+
+                activitystreams::base::AnyBase::from_arbitrary_json(lock)?
+            } else {
+                undo(activitystreams::base::AnyBase::from_arbitrary_json(lock)?)?
+            }
+        }
+        "mute_user" | "unmute_user" | "block_domain" | "unblock_domain" => {
+            let mut block = activitystreams::activity::Block::new(community_ap_id.clone(), object);
+
+            if matches!(action, "mute_user" | "block_domain") {
+                apply_properties(&mut block, event_ap_id, time, reason);
+
+                block.into_any_base()?
+            } else {
+                undo(block.into_any_base()?)?
+            }
+        }
+        "edit_community" => {
+            let mut update =
+                activitystreams::activity::Update::new(community_ap_id.clone(), object);
+            apply_properties(&mut update, event_ap_id, time, reason);
+
+            update.into_any_base()?
+        }
+        "delete_post" | "delete_reply" => {
+            let mut delete = activitystreams::activity::Delete::new(community_ap_id, object);
+            apply_properties(&mut delete, event_ap_id, time, reason);
+
+            delete.into_any_base()?
+        }
+        _ => return Ok(None),
+    }))
+}
+
+/// Builds a response signed by the community key, so that the contents can be verified even
+/// after being copied elsewhere.
+pub async fn local_community_signed_response(
+    community_id: CommunityLocalID,
+    body: Vec<u8>,
+    db: &tokio_postgres::Client,
+    host_url_apub: &BaseURL,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let privkey = fetch_or_create_local_community_privkey(community_id, db).await?;
+    let key_id = get_local_community_pubkey_apub_id(community_id, host_url_apub);
+
+    let digest = openssl::hash::hash(openssl::hash::MessageDigest::sha256(), &body)?;
+    let mut digest_header = "SHA-256=".to_owned();
+    base64::encode_config_buf(digest, base64::STANDARD, &mut digest_header);
+
+    let signature = do_sign(&privkey, format!("digest: {}", digest_header).as_bytes())?;
+    let signature_header = format!(
+        "keyId=\"{}\",algorithm=\"rsa-sha256\",headers=\"digest\",signature=\"{}\"",
+        key_id.as_str(),
+        base64::encode(signature),
+    );
+
+    Ok(hyper::Response::builder()
+        .header(hyper::header::CONTENT_TYPE, ACTIVITY_TYPE)
+        .header("Digest", digest_header)
+        .header("Signature", signature_header)
+        .body(body.into())?)
 }
 
 pub fn local_community_update_to_ap(
@@ -983,7 +1676,7 @@ pub fn local_community_delete_to_ap(
     let mut delete =
         activitystreams::activity::Delete::new(community_ap_id.clone(), community_ap_id.clone());
     delete
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id;
             res.path_segments_mut().push("delete");
@@ -995,6 +1688,49 @@ pub fn local_community_delete_to_ap(
     delete
 }
 
+pub fn local_user_delete_to_ap(
+    user_id: UserLocalID,
+    host_url_apub: &BaseURL,
+) -> activitystreams::activity::Delete {
+    let user_ap_id = LocalObjectRef::User(user_id).to_local_uri(host_url_apub);
+
+    let mut delete = activitystreams::activity::Delete::new(user_ap_id.clone(), user_ap_id.clone());
+    delete
+        .set_many_contexts(default_contexts())
+        .set_id({
+            let mut res = user_ap_id;
+            res.path_segments_mut().push("delete");
+            res.into()
+        })
+        .set_to(LocalObjectRef::UserFollowers(user_id).to_local_uri(host_url_apub))
+        .set_cc(activitystreams::public());
+
+    delete
+}
+
+pub fn local_user_move_to_ap(
+    user_id: UserLocalID,
+    target: url::Url,
+    host_url_apub: &BaseURL,
+) -> activitystreams::activity::Move {
+    let user_ap_id = LocalObjectRef::User(user_id).to_local_uri(host_url_apub);
+
+    let mut move_ = activitystreams::activity::Move::new(user_ap_id.clone(), user_ap_id.clone());
+    move_
+        .set_many_contexts(default_contexts())
+        .set_id({
+            let mut res = user_ap_id;
+            res.path_segments_mut()
+                .extend(&["moves", &uuid::Uuid::new_v4().to_string()]);
+            res.into()
+        })
+        .set_target(target)
+        .set_to(LocalObjectRef::UserFollowers(user_id).to_local_uri(host_url_apub))
+        .set_cc(activitystreams::public());
+
+    move_
+}
+
 pub fn local_community_follow_undo_to_ap(
     undo_id: uuid::Uuid,
     community_local_id: CommunityLocalID,
@@ -1007,7 +1743,7 @@ pub fn local_community_follow_undo_to_ap(
         LocalObjectRef::CommunityFollow(community_local_id, local_follower)
             .to_local_uri(host_url_apub),
     );
-    undo.set_context(activitystreams::context())
+    undo.set_many_contexts(default_contexts())
         .set_id({
             let mut res = host_url_apub.clone();
             res.path_segments_mut()
@@ -1019,16 +1755,60 @@ pub fn local_community_follow_undo_to_ap(
     Ok(undo)
 }
 
+pub fn local_relay_follow_to_ap(
+    relay: RelayID,
+    relay_ap_id: url::Url,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Follow, crate::Error> {
+    // Mastodon-style relays expect the public collection as the object
+    let mut follow = activitystreams::activity::Follow::new(
+        LocalObjectRef::InstanceActor.to_local_uri(host_url_apub),
+        activitystreams::public(),
+    );
+    follow
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::RelayFollow(relay)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_to(relay_ap_id);
+
+    Ok(follow)
+}
+
+pub fn local_relay_follow_undo_to_ap(
+    undo_id: uuid::Uuid,
+    relay: RelayID,
+    relay_ap_id: url::Url,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Undo, crate::Error> {
+    let mut undo = activitystreams::activity::Undo::new(
+        LocalObjectRef::InstanceActor.to_local_uri(host_url_apub),
+        LocalObjectRef::RelayFollow(relay).to_local_uri(host_url_apub),
+    );
+    undo.set_many_contexts(default_contexts())
+        .set_id({
+            let mut res = host_url_apub.clone();
+            res.path_segments_mut()
+                .extend(&["relay_follow_undos", &undo_id.to_string()]);
+            res.into()
+        })
+        .set_to(relay_ap_id);
+
+    Ok(undo)
+}
+
 pub fn community_follow_accept_to_ap(
     community_ap_id: BaseURL,
     follower_local_id: UserLocalID,
     follower_ap_id: url::Url,
-    follow_ap_id: url::Url,
+    follow: activitystreams::base::AnyBase,
 ) -> Result<activitystreams::activity::Accept, crate::Error> {
-    let mut accept = activitystreams::activity::Accept::new(community_ap_id.clone(), follow_ap_id);
+    let mut accept = activitystreams::activity::Accept::new(community_ap_id.clone(), follow);
 
     accept
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = community_ap_id;
             res.path_segments_mut().extend(&[
@@ -1043,6 +1823,93 @@ pub fn community_follow_accept_to_ap(
     Ok(accept)
 }
 
+pub fn community_follow_reject_to_ap(
+    community_local_id: CommunityLocalID,
+    follower_local_id: UserLocalID,
+    follower_ap_id: url::Url,
+    follow: activitystreams::base::AnyBase,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Reject, crate::Error> {
+    let mut reject = activitystreams::activity::Reject::new(
+        LocalObjectRef::Community(community_local_id).to_local_uri(host_url_apub),
+        follow,
+    );
+
+    reject
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::CommunityFollowReject(community_local_id, follower_local_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_to(follower_ap_id);
+
+    Ok(reject)
+}
+
+/// Sends Accept or Reject for a follow request that was held for moderator approval
+pub fn spawn_enqueue_send_community_follow_response(
+    local_community: CommunityLocalID,
+    follower: UserLocalID,
+    follow_ap_id: url::Url,
+    accepted: bool,
+    ctx: Arc<crate::RouteContext>,
+) {
+    crate::spawn_task(async move {
+        let db = ctx.db_pool.get().await?;
+
+        let row = db
+            .query_one(
+                "SELECT ap_id, ap_inbox FROM person WHERE id=$1",
+                &[&follower],
+            )
+            .await?;
+
+        std::mem::drop(db);
+
+        let (follower_ap_id, follower_inbox): (url::Url, url::Url) =
+            match (row.get::<_, Option<&str>>(0), row.get::<_, Option<&str>>(1)) {
+                (Some(ap_id), Some(inbox)) => (ap_id.parse()?, inbox.parse()?),
+                _ => {
+                    return Err(crate::Error::InternalStr(format!(
+                        "Missing apub info for user {}",
+                        follower
+                    )));
+                }
+            };
+
+        let community_ap_id =
+            LocalObjectRef::Community(local_community).to_local_uri(&ctx.host_url_apub);
+        let follow = activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id);
+
+        let body = if accepted {
+            serde_json::to_string(&community_follow_accept_to_ap(
+                community_ap_id,
+                follower,
+                follower_ap_id,
+                follow,
+            )?)?
+        } else {
+            serde_json::to_string(&community_follow_reject_to_ap(
+                local_community,
+                follower,
+                follower_ap_id,
+                follow,
+                &ctx.host_url_apub,
+            )?)?
+        };
+
+        ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+            inbox: Cow::Owned(follower_inbox),
+            sign_as: Some(ActorLocalRef::Community(local_community)),
+            object: body,
+        })
+        .await?;
+
+        Ok(())
+    });
+}
+
 pub fn spawn_enqueue_send_community_follow_accept(
     local_community: CommunityLocalID,
     follower: UserLocalID,
@@ -1052,16 +1919,19 @@ pub fn spawn_enqueue_send_community_follow_accept(
     crate::spawn_task(async move {
         let db = ctx.db_pool.get().await?;
 
-        let follow_ap_id = {
-            (match follow.into_inner() {
-                Cow::Owned(follow) => follow.into_inner().take_id(),
-                Cow::Borrowed(follow) => follow.id_unchecked().cloned(),
-            })
-            .ok_or(crate::Error::InternalStrStatic(
-                "Missing ID in Follow activity",
-            ))?
+        let follow = match follow.into_inner() {
+            Cow::Owned(follow) => follow.into_inner(),
+            Cow::Borrowed(follow) => follow.deref().clone(),
         };
 
+        let follow_ap_id =
+            follow
+                .id_unchecked()
+                .cloned()
+                .ok_or(crate::Error::InternalStrStatic(
+                    "Missing ID in Follow activity",
+                ))?;
+
         let community_ap_id =
             LocalObjectRef::Community(local_community).to_local_uri(&ctx.host_url_apub);
 
@@ -1102,8 +1972,19 @@ pub fn spawn_enqueue_send_community_follow_accept(
             }
         };
 
+        let quirks = match compat::get_url_host_key(&follower_ap_id) {
+            Some(host) => compat::get_host_quirks(host, &db).await?,
+            None => Default::default(),
+        };
+
+        let follow = if quirks.inline_objects {
+            follow.into_any_base()?
+        } else {
+            activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id)
+        };
+
         let accept =
-            community_follow_accept_to_ap(community_ap_id, follower, follower_ap_id, follow_ap_id)?;
+            community_follow_accept_to_ap(community_ap_id, follower, follower_ap_id, follow)?;
         log::debug!("{:?}", accept);
 
         let body = serde_json::to_string(&accept)?;
@@ -1121,6 +2002,189 @@ pub fn spawn_enqueue_send_community_follow_accept(
     });
 }
 
+pub fn user_follow_accept_to_ap(
+    user: UserLocalID,
+    follower_local_id: UserLocalID,
+    follower_ap_id: url::Url,
+    follow: activitystreams::base::AnyBase,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Accept, crate::Error> {
+    let mut accept = activitystreams::activity::Accept::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        follow,
+    );
+
+    accept
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::UserFollowAccept(user, follower_local_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_to(follower_ap_id);
+
+    Ok(accept)
+}
+
+pub fn user_follow_reject_to_ap(
+    user: UserLocalID,
+    follower_local_id: UserLocalID,
+    follower_ap_id: url::Url,
+    follow: activitystreams::base::AnyBase,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Reject, crate::Error> {
+    let mut reject = activitystreams::activity::Reject::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        follow,
+    );
+
+    reject
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::UserFollowReject(user, follower_local_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_to(follower_ap_id);
+
+    Ok(reject)
+}
+
+pub fn spawn_enqueue_send_user_follow_response(
+    user: UserLocalID,
+    follower: UserLocalID,
+    follow: Contained<'static, FollowLike>,
+    accepted: bool,
+    ctx: Arc<crate::RouteContext>,
+) {
+    crate::spawn_task(async move {
+        let db = ctx.db_pool.get().await?;
+
+        let follow = match follow.into_inner() {
+            Cow::Owned(follow) => follow.into_inner(),
+            Cow::Borrowed(follow) => follow.deref().clone(),
+        };
+
+        let follow_ap_id =
+            follow
+                .id_unchecked()
+                .cloned()
+                .ok_or(crate::Error::InternalStrStatic(
+                    "Missing ID in Follow activity",
+                ))?;
+
+        let row = db
+            .query_one(
+                "SELECT local, ap_inbox, ap_id FROM person WHERE id=$1",
+                &[&follower],
+            )
+            .await?;
+
+        if row.get(0) {
+            // Shouldn't happen, but fine to ignore it
+            return Ok(());
+        }
+
+        let (follower_inbox, follower_ap_id): (url::Url, url::Url) = {
+            let ap_inbox: Option<&str> = row.get(1);
+            let ap_id: Option<&str> = row.get(2);
+
+            match (ap_inbox, ap_id) {
+                (Some(ap_inbox), Some(ap_id)) => (ap_inbox.parse()?, ap_id.parse()?),
+                _ => {
+                    return Err(crate::Error::InternalStr(format!(
+                        "Missing apub info for user {}",
+                        follower
+                    )))
+                }
+            }
+        };
+
+        let quirks = match compat::get_url_host_key(&follower_ap_id) {
+            Some(host) => compat::get_host_quirks(host, &db).await?,
+            None => Default::default(),
+        };
+
+        std::mem::drop(db);
+
+        let follow = if quirks.inline_objects {
+            follow.into_any_base()?
+        } else {
+            activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id)
+        };
+
+        let body = if accepted {
+            serde_json::to_string(&user_follow_accept_to_ap(
+                user,
+                follower,
+                follower_ap_id,
+                follow,
+                &ctx.host_url_apub,
+            )?)?
+        } else {
+            serde_json::to_string(&user_follow_reject_to_ap(
+                user,
+                follower,
+                follower_ap_id,
+                follow,
+                &ctx.host_url_apub,
+            )?)?
+        };
+
+        ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+            inbox: Cow::Owned(follower_inbox),
+            sign_as: Some(ActorLocalRef::Person(user)),
+            object: body,
+        })
+        .await?;
+
+        Ok(())
+    });
+}
+
+/// Sends Reject for every remote follow of a local user, and removes them
+pub fn spawn_enqueue_reject_user_followers(user: UserLocalID, ctx: Arc<crate::RouteContext>) {
+    crate::spawn_task(async move {
+        let db = ctx.db_pool.get().await?;
+
+        // rejections are kept so the Reject activities can still be fetched
+        let rows = db.query(
+            "WITH removed AS (DELETE FROM person_follow USING person WHERE person_follow.person=$1 AND person.id = person_follow.follower AND NOT person.local RETURNING person_follow.follower, person_follow.ap_id, person.ap_id AS follower_ap_id, person.ap_inbox), recorded AS (INSERT INTO person_follow_reject (person, follower, ap_id, created) SELECT $1, follower, ap_id, current_timestamp FROM removed WHERE ap_id IS NOT NULL ON CONFLICT (person, follower) DO UPDATE SET ap_id=excluded.ap_id, created=excluded.created) SELECT follower, ap_id, follower_ap_id, ap_inbox FROM removed",
+            &[&user],
+        ).await?;
+
+        std::mem::drop(db);
+
+        for row in rows {
+            let follower = UserLocalID(row.get(0));
+            let follow_ap_id: Option<&str> = row.get(1);
+            let follower_ap_id: Option<&str> = row.get(2);
+            let follower_inbox: Option<&str> = row.get(3);
+
+            if let (Some(follow_ap_id), Some(follower_ap_id), Some(follower_inbox)) =
+                (follow_ap_id, follower_ap_id, follower_inbox)
+            {
+                let reject = user_follow_reject_to_ap(
+                    user,
+                    follower,
+                    follower_ap_id.parse()?,
+                    activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id.parse()?),
+                    &ctx.host_url_apub,
+                )?;
+
+                ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                    inbox: Cow::Owned(follower_inbox.parse()?),
+                    sign_as: Some(ActorLocalRef::Person(user)),
+                    object: serde_json::to_string(&reject)?,
+                })
+                .await?;
+            }
+        }
+
+        Ok(())
+    });
+}
+
 pub fn post_to_ap(
     post: &crate::PostInfo<'_>,
     community_ap_id: url::Url,
@@ -1145,13 +2209,16 @@ pub fn post_to_ap(
                     .to_local_uri(&ctx.host_url_apub)
                     .into(),
             )
-            .set_context(activitystreams::context())
+            .set_many_contexts(default_contexts())
             .set_attributed_to(
                 LocalObjectRef::User(post.author.unwrap()).to_local_uri(&ctx.host_url_apub),
             )
             .set_published(*post.created)
-            .set_to(community_ap_id)
-            .set_cc(activitystreams::public());
+            .set_to(community_ap_id);
+
+        if !post.community_private {
+            props.set_cc(activitystreams::public());
+        }
 
         if let Some(community_ap_followers) = community_ap_followers {
             props.add_to(community_ap_followers);
@@ -1164,6 +2231,25 @@ pub fn post_to_ap(
         }
 
         props.ext_two.sensitive = Some(post.sensitive);
+        props.ext_two.stickied = Some(post.sticky);
+        props.ext_two.comments_enabled = Some(!post.locked);
+
+        if let Some(language) = post.language {
+            let content = post
+                .content_html
+                .map(crate::clean_html)
+                .or_else(|| post.content_text.map(ToOwned::to_owned));
+            if let Some(content) = content {
+                props.ext_three.content_map =
+                    Some(std::iter::once((language.to_owned(), content)).collect());
+            }
+
+            let mut contexts = default_contexts();
+            contexts.push(activitystreams::base::AnyBase::from_arbitrary_json(
+                serde_json::json!({ "@language": language }),
+            )?);
+            props.set_many_contexts(contexts);
+        }
 
         if let Some(html) = post.content_html {
             props
@@ -1181,6 +2267,49 @@ pub fn post_to_ap(
             props.set_content(text).set_media_type(mime::TEXT_PLAIN);
         }
 
+        for (idx, attachment) in post.attachments.iter().enumerate() {
+            let url = ctx
+                .process_post_attachment_href(attachment.url.as_str(), post.id, idx)
+                .into_owned();
+            let media_type: Option<mime::Mime> = attachment
+                .media_type
+                .as_deref()
+                .and_then(|x| x.parse().ok());
+
+            let attachment_ap = if media_type.as_ref().map(|x| x.type_()) == Some(mime::IMAGE) {
+                let mut attachment_ap = activitystreams::object::Image::new();
+                attachment_ap.set_url(url);
+                if let Some(media_type) = media_type {
+                    attachment_ap.set_media_type(media_type);
+                }
+                if let Some(alt_text) = &attachment.alt_text {
+                    attachment_ap.set_name(alt_text.as_str());
+                }
+                attachment_ap.into_any_base()?
+            } else {
+                let mut attachment_ap = activitystreams::object::Document::new();
+                attachment_ap.set_url(url);
+                if let Some(media_type) = media_type {
+                    attachment_ap.set_media_type(media_type);
+                }
+                if let Some(alt_text) = &attachment.alt_text {
+                    attachment_ap.set_name(alt_text.as_str());
+                }
+                attachment_ap.into_any_base()?
+            };
+
+            props.add_attachment(attachment_ap);
+        }
+
+        for flair in post.flairs.iter() {
+            let name: String = flair.split_whitespace().collect();
+            props.add_tag(hashtag_to_ap(&name)?);
+        }
+
+        for tag in crate::extract_hashtags(post.content_text, post.content_markdown) {
+            props.add_tag(hashtag_to_ap(&tag)?);
+        }
+
         Ok(())
     }
 
@@ -1257,6 +2386,52 @@ pub fn post_to_ap(
                     &ctx,
                 )?;
 
+                Ok(activitystreams::base::AnyBase::from_arbitrary_json(
+                    post_ap,
+                )?)
+            } else if ctx.link_posts_as_notes || post.community_link_posts_as_notes {
+                // Mastodon drops Page objects, but shows a Note's content and link preview
+                let mut attachment =
+                    activitystreams::link::Link::<activitystreams::link::kind::LinkType>::new();
+                attachment.set_href(url::Url::try_from(href)?);
+
+                let mut post_ap = activitystreams::object::Note::new();
+
+                post_ap
+                    .set_name(post.title)
+                    .add_attachment(attachment.into_any_base()?);
+
+                let mut post_ap =
+                    make_extended_postlike(activitystreams::object::ApObject::new(post_ap));
+
+                apply_properties(
+                    &mut post_ap,
+                    post,
+                    community_ap_id,
+                    community_ap_outbox,
+                    community_ap_followers,
+                    &ctx,
+                )?;
+
+                let body_html = post.content_html.map(crate::clean_html).or_else(|| {
+                    post.content_text
+                        .map(|text| format!("<p>{}</p>", v_htmlescape::escape(text)))
+                });
+                let content = format!(
+                    "<p><a href=\"{}\">{}</a></p>{}",
+                    v_htmlescape::escape(href),
+                    v_htmlescape::escape(post.title),
+                    body_html.as_deref().unwrap_or(""),
+                );
+
+                if let Some(content_map) = &mut post_ap.ext_three.content_map {
+                    for value in content_map.values_mut() {
+                        *value = content.clone();
+                    }
+                }
+
+                post_ap.set_content(content).set_media_type(mime::TEXT_HTML);
+
                 Ok(activitystreams::base::AnyBase::from_arbitrary_json(
                     post_ap,
                 )?)
@@ -1351,23 +2526,267 @@ pub fn local_post_to_create_ap(
         ctx,
     )?;
 
-    let mut create = activitystreams::activity::Create::new(
-        LocalObjectRef::User(post.author.unwrap()).to_local_uri(&ctx.host_url_apub),
+    Ok(local_post_object_to_create_ap(
+        post.id,
+        post.author.unwrap(),
         post_ap,
+        post.community_private,
+        community_ap_id,
+        community_ap_followers,
+        &ctx.host_url_apub,
+    ))
+}
+
+pub fn local_post_object_to_create_ap(
+    post_id: PostLocalID,
+    author: UserLocalID,
+    object: activitystreams::base::AnyBase,
+    community_private: bool,
+    community_ap_id: url::Url,
+    community_ap_followers: Option<url::Url>,
+    host_url_apub: &BaseURL,
+) -> activitystreams::activity::Create {
+    let mut create = activitystreams::activity::Create::new(
+        LocalObjectRef::User(author).to_local_uri(host_url_apub),
+        object,
     );
-    create.set_context(activitystreams::context()).set_id({
-        let mut res = LocalObjectRef::Post(post.id).to_local_uri(&ctx.host_url_apub);
+    create.set_many_contexts(default_contexts()).set_id({
+        let mut res = LocalObjectRef::Post(post_id).to_local_uri(host_url_apub);
         res.path_segments_mut().push("create");
         res.into()
     });
     create.set_to(community_ap_id);
-    create.set_cc(activitystreams::public());
+    if !community_private {
+        create.set_cc(activitystreams::public());
+        create.add_cc(LocalObjectRef::UserFollowers(author).to_local_uri(host_url_apub));
+    }
 
     if let Some(community_ap_followers) = community_ap_followers {
         create.add_to(community_ap_followers);
     }
 
-    Ok(create)
+    create
+}
+
+pub enum LocalPostAP {
+    NotFound,
+    NotLocal,
+    Deleted {
+        former_type: &'static str,
+    },
+    Found {
+        object: activitystreams::base::AnyBase,
+        author: UserLocalID,
+        community: CommunityLocalID,
+        /// `None` for polls, since vote counts change without the post being modified
+        modified_at: Option<chrono::DateTime<chrono::FixedOffset>>,
+    },
+}
+
+pub async fn get_local_post_ap(
+    post_id: PostLocalID,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<LocalPostAP, crate::Error> {
+    match db
+        .query_opt(
+            "SELECT post.author, post.href, post.title, post.created, post.community, post.local, post.deleted, post.had_href, post.content_text, post.content_markdown, post.content_html, community.ap_id, community.ap_outbox, community.local, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, poll.id, post.sensitive, (SELECT COALESCE(json_agg(json_build_object('url', url, 'media_type', media_type, 'alt_text', alt_text) ORDER BY position), '[]') FROM post_attachment WHERE post=post.id), (SELECT COALESCE(array_agg(community_flair.name ORDER BY community_flair.name), '{}') FROM post_flair INNER JOIN community_flair ON (community_flair.id = post_flair.flair) WHERE post_flair.post = post.id), post.language, community.private, post.modified_at, post.sticky, post.locked, community.link_posts_as_notes FROM post INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.id=$1",
+            &[&post_id.raw()],
+        )
+        .await?
+    {
+        None => Ok(LocalPostAP::NotFound),
+        Some(row) => {
+            let local: bool = row.get(5);
+
+            if !local {
+                return Ok(LocalPostAP::NotLocal);
+            }
+
+            if row.get(6) {
+                let had_href: Option<bool> = row.get(7);
+                let poll_id = row.get::<_, Option<_>>(18).map(PollLocalID);
+
+                return Ok(LocalPostAP::Deleted {
+                    former_type: if poll_id.is_some() {
+                        "Question"
+                    } else if had_href == Some(true) {
+                        "Page"
+                    } else {
+                        "Note"
+                    },
+                });
+            }
+
+            let community_local_id = CommunityLocalID(row.get(4));
+            let community_local: bool = row.get(13);
+
+            let community_ap_id = match row.get(11) {
+                Option::<&str>::Some(ap_id) => ap_id.parse()?,
+                None => {
+                    if community_local {
+                        LocalObjectRef::Community(community_local_id).to_local_uri(&ctx.host_url_apub)
+                    } else {
+                        return Err(crate::Error::InternalStrStatic("Missing community AP id"));
+                    }
+                }
+            };
+
+            let community_ap_outbox = match row.get(12) {
+                Option::<&str>::Some(ap_outbox) => Some(ap_outbox.parse()?),
+                None => {
+                    if community_local {
+                        Some(LocalObjectRef::CommunityOutbox(community_local_id).to_local_uri(&ctx.host_url_apub))
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            let community_ap_followers = match row.get(14) {
+                Option::<&str>::Some(ap_followers) => Some(ap_followers.parse()?),
+                None => {
+                    if community_local {
+                        Some(LocalObjectRef::CommunityFollowers(community_local_id).to_local_uri(&ctx.host_url_apub))
+                    } else {
+                        None
+                    }
+                }
+            };
+
+            let closed_at: Option<chrono::DateTime<chrono::FixedOffset>>;
+
+            let poll = if let Some(multiple) = row.get(15) {
+                Some({
+                    let options: Vec<_> = row.get::<_, Vec<postgres_types::Json<(i64, &str, i64)>>>(16)
+                        .into_iter()
+                        .map(|x| x.0)
+                        .map(|(id, name, votes): (i64, &str, i64)| {
+                            crate::PollOption {
+                                id: PollOptionLocalID(id),
+                                name,
+                                votes: votes as u32,
+                            }
+                        })
+                        .collect();
+
+                    closed_at = row.get(17);
+
+                    Cow::Owned(crate::PollInfo {
+                        multiple,
+                        options: Cow::Owned(options),
+                        closed_at: closed_at.as_ref(),
+                    })
+                })
+            } else {
+                None
+            };
+
+            let post_info = crate::PostInfo {
+                author: Some(UserLocalID(row.get(0))),
+                community: community_local_id,
+                community_private: row.get(23),
+                community_link_posts_as_notes: row.get(27),
+                created: &row.get(3),
+                href: row.get(1),
+                content_text: row.get(8),
+                content_markdown: row.get(9),
+                content_html: row.get(10),
+                id: post_id,
+                title: row.get(2),
+                poll,
+                sensitive: row.get(19),
+                sticky: row.get(25),
+                locked: row.get(26),
+                attachments: Cow::Owned(row.get::<_, postgres_types::Json<Vec<crate::PostAttachment>>>(20).0),
+                flairs: Cow::Owned(row.get(21)),
+                language: row.get(22),
+            };
+
+            let modified_at = if post_info.poll.is_some() {
+                None
+            } else {
+                Some(row.get(24))
+            };
+
+            let object = post_to_ap(
+                &post_info,
+                community_ap_id.into(),
+                community_ap_outbox.map(Into::into),
+                community_ap_followers.map(Into::into),
+                ctx,
+            )?;
+
+            Ok(LocalPostAP::Found {
+                object,
+                author: post_info.author.unwrap(),
+                community: community_local_id,
+                modified_at,
+            })
+        }
+    }
+}
+
+pub fn local_post_update_to_ap(
+    post_id: PostLocalID,
+    author: UserLocalID,
+    object: activitystreams::base::AnyBase,
+    private_community: Option<CommunityLocalID>,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Update, crate::Error> {
+    let mut update = activitystreams::activity::Update::new(
+        LocalObjectRef::User(author).to_local_uri(host_url_apub),
+        object,
+    );
+    update.set_many_contexts(default_contexts()).set_id({
+        let mut res = LocalObjectRef::Post(post_id).to_local_uri(host_url_apub);
+        res.path_segments_mut()
+            .extend(&["updates", &uuid::Uuid::new_v4().to_string()]);
+        res.into()
+    });
+
+    match private_community {
+        Some(community) => {
+            update
+                .set_to(LocalObjectRef::CommunityFollowers(community).to_local_uri(host_url_apub));
+        }
+        None => {
+            update.set_to(activitystreams::public());
+        }
+    }
+
+    Ok(update)
+}
+
+/// Schedules sending updated poll results for a local post, unless already scheduled
+pub async fn enqueue_poll_results_update_if_needed(
+    post_id: PostLocalID,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let row = db.query_opt(
+        "UPDATE poll SET update_pending=TRUE WHERE id=(SELECT poll_id FROM post WHERE id=$1 AND local) AND NOT update_pending RETURNING id",
+        &[&post_id],
+    ).await?;
+
+    if row.is_some() {
+        ctx.enqueue_task_delayed(
+            &crate::tasks::SendPollResultsUpdate { post: post_id },
+            std::time::Duration::from_secs(60),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn hashtag_to_ap(name: &str) -> Result<activitystreams::base::AnyBase, crate::Error> {
+    Ok(activitystreams::base::AnyBase::from_arbitrary_json(
+        serde_json::json!({
+            "type": "Hashtag",
+            "name": format!("#{}", name),
+        }),
+    )?)
 }
 
 pub fn local_comment_to_ap(
@@ -1376,17 +2795,18 @@ pub fn local_comment_to_ap(
     parent_ap_id: Option<url::Url>,
     parent_or_post_author_ap_id: Option<url::Url>,
     community_ap_id: url::Url,
+    community_private: bool,
     ctx: &crate::BaseContext,
 ) -> Result<
     activitystreams_ext::Ext1<
         activitystreams::object::ApObject<activitystreams::object::Note>,
-        SensitiveExtension,
+        ContentFlagsExtension,
     >,
     crate::Error,
 > {
     let mut obj = activitystreams::object::Note::new();
 
-    obj.set_context(activitystreams::context())
+    obj.set_many_contexts(default_contexts())
         .set_id(
             LocalObjectRef::Comment(comment.id)
                 .to_local_uri(&ctx.host_url_apub)
@@ -1398,6 +2818,10 @@ pub fn local_comment_to_ap(
         .set_published(comment.created)
         .set_in_reply_to(parent_ap_id.unwrap_or_else(|| post_ap_id.clone()));
 
+    if let Some(updated) = comment.updated {
+        obj.set_updated(updated);
+    }
+
     if let Some(attachment_href) = ctx.process_attachments_inner(
         comment.attachment_href.as_deref().map(Cow::Borrowed),
         comment.id,
@@ -1408,6 +2832,13 @@ pub fn local_comment_to_ap(
         obj.add_attachment(attachment.into_any_base()?);
     }
 
+    for tag in crate::extract_hashtags(
+        comment.content_text.as_deref(),
+        comment.content_markdown.as_deref(),
+    ) {
+        obj.add_tag(hashtag_to_ap(&tag)?);
+    }
+
     let mut obj = activitystreams::object::ApObject::new(obj);
 
     if let Some(html) = &comment.content_html {
@@ -1428,89 +2859,287 @@ pub fn local_comment_to_ap(
 
     if let Some(parent_or_post_author_ap_id) = parent_or_post_author_ap_id {
         obj.set_to(parent_or_post_author_ap_id)
-            .set_many_ccs(vec![activitystreams::public(), community_ap_id]);
+            .set_cc(community_ap_id);
     } else {
-        obj.set_to(community_ap_id)
-            .set_cc(activitystreams::public());
+        obj.set_to(community_ap_id);
+    }
+    if !community_private {
+        obj.add_cc(activitystreams::public());
     }
 
     Ok(activitystreams_ext::Ext1::new(
         obj,
-        SensitiveExtension {
+        ContentFlagsExtension {
             sensitive: Some(comment.sensitive),
+            ..Default::default()
         },
     ))
 }
 
-pub fn spawn_enqueue_send_local_post_to_community(
-    post: crate::PostInfoOwned,
-    ctx: Arc<crate::RouteContext>,
-) {
-    crate::spawn_task(async move {
-        let db = ctx.db_pool.get().await?;
+pub enum LocalCommentAP {
+    NotFound,
+    NotLocal,
+    Deleted,
+    Found {
+        object: activitystreams::base::AnyBase,
+        author: UserLocalID,
+        community: CommunityLocalID,
+        community_private: bool,
+    },
+}
 
-        let (community_ap_id, community_inbox, community_outbox, community_followers): (
-            url::Url,
-            url::Url,
-            Option<url::Url>,
-            Option<url::Url>,
-        ) = {
-            let row = db
-                .query_one(
-                    "SELECT local, ap_id, COALESCE(ap_shared_inbox, ap_inbox), ap_outbox, ap_followers FROM community WHERE id=$1",
-                    &[&post.community],
-                )
-                .await?;
-            let local = row.get(0);
-            if local {
-                // no need to send posts for local communities
-                return Ok(());
+pub async fn get_local_comment_ap(
+    comment_id: CommentLocalID,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<LocalCommentAP, crate::Error> {
+    match db
+        .query_opt(
+            "SELECT reply.author, reply.content_text, reply.post, reply.created, reply.local, reply.parent, post.local, post.ap_id, post.community, community.local, community.ap_id, reply_parent.local, reply_parent.ap_id, post_author.id, post_author.local, post_author.ap_id, reply_parent_author.id, reply_parent_author.local, reply_parent_author.ap_id, reply.deleted, reply.content_markdown, reply.content_html, reply.attachment_href, reply.sensitive, reply.updated, COALESCE(community.local AND community.private, FALSE) FROM reply LEFT OUTER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN community ON (community.id = post.community) LEFT OUTER JOIN reply AS reply_parent ON (reply_parent.id = reply.parent) LEFT OUTER JOIN person AS reply_parent_author ON (reply_parent_author.id = reply_parent.author) WHERE reply.id=$1 AND NOT reply.pending_approval",
+            &[&comment_id],
+        )
+        .await?
+    {
+        None => Ok(LocalCommentAP::NotFound),
+        Some(row) => {
+            let local: bool = row.get(4);
+
+            if !local {
+                return Ok(LocalCommentAP::NotLocal);
+            }
+
+            if row.get(19) {
+                return Ok(LocalCommentAP::Deleted);
+            }
+
+            let post_local_id = PostLocalID(row.get(2));
+
+            let community_local_id = CommunityLocalID(row.get(8));
+            let community_private: bool = row.get(25);
+
+            let community_ap_id = if row.get(9) {
+                LocalObjectRef::Community(community_local_id).to_local_uri(&ctx.host_url_apub)
             } else {
-                let ap_id: Option<&str> = row.get(1);
-                let ap_inbox: Option<&str> = row.get(2);
-                let ap_outbox: Option<&str> = row.get(3);
-                let ap_followers: Option<&str> = row.get(4);
+                std::str::FromStr::from_str(row.get(10))?
+            };
 
-                (if let Some(ap_id) = ap_id {
-                    if let Some(ap_inbox) = ap_inbox {
-                        Some((
-                            ap_id.parse()?,
-                            ap_inbox.parse()?,
-                            ap_outbox.and_then(|x| x.parse().ok()),
-                            ap_followers.and_then(|x| x.parse().ok()),
-                        ))
-                    } else {
-                        None
+            let post_ap_id = if row.get(6) {
+                LocalObjectRef::Post(post_local_id).to_local_uri(&ctx.host_url_apub)
+            } else {
+                std::str::FromStr::from_str(row.get(7))?
+            };
+
+            let parent_local_id = row.get::<_, Option<_>>(5).map(CommentLocalID);
+
+            let info = crate::CommentInfo {
+                author: Some(UserLocalID(row.get(0))),
+                created: row.get(3),
+                updated: row.get(24),
+                content_text: row.get::<_, Option<_>>(1).map(Cow::Borrowed),
+                content_markdown: row.get::<_, Option<_>>(20).map(Cow::Borrowed),
+                content_html: row.get::<_, Option<_>>(21).map(Cow::Borrowed),
+                id: comment_id,
+                post: post_local_id,
+                parent: parent_local_id,
+                ap_id: crate::APIDOrLocal::Local,
+                attachment_href: row.get::<_, Option<_>>(22).map(Cow::Borrowed),
+                sensitive: row.get(23),
+            };
+
+            let parent_ap_id = match row.get(11) {
+                None => None,
+                Some(true) => Some(LocalObjectRef::Comment(parent_local_id.unwrap()).to_local_uri(&ctx.host_url_apub)),
+                Some(false) => row.get::<_, Option<&str>>(12).map(|x| x.parse()).transpose()?,
+            };
+
+            let post_or_parent_author_ap_id = match parent_local_id {
+                None => {
+                    // no parent comment, use post
+                    match row.get(14) {
+                        Some(post_author_local) => {
+                            if post_author_local {
+                                Some(LocalObjectRef::User(UserLocalID(row.get(13))).to_local_uri(&ctx.host_url_apub))
+                            } else {
+                                Some(std::str::FromStr::from_str(row.get(15))?)
+                            }
+                        },
+                        None => None,
                     }
-                } else {
-                    None
-                })
-                .ok_or_else(|| {
-                    crate::Error::InternalStr(format!(
-                        "Missing apub info for community {}",
-                        post.community
-                    ))
-                })?
+                },
+                Some(_) => {
+                    match row.get(17) {
+                        Some(parent_author_local) => {
+                            if parent_author_local {
+                                Some(LocalObjectRef::User(UserLocalID(row.get(16))).to_local_uri(&ctx.host_url_apub))
+                            } else {
+                                Some(std::str::FromStr::from_str(row.get(18))?)
+                            }
+                        },
+                        None => None,
+                    }
+                },
+            };
+
+            let object = local_comment_to_ap(
+                &info,
+                &post_ap_id,
+                parent_ap_id.map(From::from),
+                post_or_parent_author_ap_id.map(From::from),
+                community_ap_id.into(),
+                community_private,
+                ctx,
+            )?;
+
+            Ok(LocalCommentAP::Found {
+                object: activitystreams::base::AnyBase::from_arbitrary_json(object)?,
+                author: info.author.unwrap(),
+                community: community_local_id,
+                community_private,
+            })
+        }
+    }
+}
+
+pub fn local_comment_update_to_ap(
+    comment_id: CommentLocalID,
+    author: UserLocalID,
+    object: activitystreams::base::AnyBase,
+    private_community: Option<CommunityLocalID>,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Update, crate::Error> {
+    let mut update = activitystreams::activity::Update::new(
+        LocalObjectRef::User(author).to_local_uri(host_url_apub),
+        object,
+    );
+    update.set_many_contexts(default_contexts()).set_id({
+        let mut res = LocalObjectRef::Comment(comment_id).to_local_uri(host_url_apub);
+        res.path_segments_mut()
+            .extend(&["updates", &uuid::Uuid::new_v4().to_string()]);
+        res.into()
+    });
+
+    match private_community {
+        Some(community) => {
+            update
+                .set_to(LocalObjectRef::CommunityFollowers(community).to_local_uri(host_url_apub));
+        }
+        None => {
+            update.set_to(activitystreams::public());
+        }
+    }
+
+    Ok(update)
+}
+
+/// Sends a new local post to its community, and to the author's followers and relays
+pub async fn enqueue_send_local_post_to_community(
+    post_id: PostLocalID,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let (object, author, community) = match get_local_post_ap(post_id, &db, &ctx).await? {
+        LocalPostAP::Found {
+            object,
+            author,
+            community,
+            ..
+        } => (object, author, community),
+        _ => return Ok(()),
+    };
+
+    let row = db
+        .query_one(
+            "SELECT community.local, community.ap_id, (CASE WHEN COALESCE(remote_host.override_no_shared_inbox, remote_host.no_shared_inbox, FALSE) THEN community.ap_inbox ELSE COALESCE(community.ap_shared_inbox, community.ap_inbox) END), community.ap_followers, community.private FROM community LEFT OUTER JOIN remote_host ON (remote_host.host = url_host_key(community.ap_id)) WHERE community.id=$1",
+            &[&community],
+        )
+        .await?;
+    let community_private: bool = row.get(4);
+
+    let (community_ap_id, community_inbox, community_followers): (
+        url::Url,
+        Option<url::Url>,
+        Option<url::Url>,
+    ) = if row.get(0) {
+        // community will handle announcing it, just need to send to author's followers
+        (
+            LocalObjectRef::Community(community)
+                .to_local_uri(&ctx.host_url_apub)
+                .into(),
+            None,
+            Some(
+                LocalObjectRef::CommunityFollowers(community)
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into(),
+            ),
+        )
+    } else {
+        let ap_id: Option<&str> = row.get(1);
+        let ap_inbox: Option<&str> = row.get(2);
+        let ap_followers: Option<&str> = row.get(3);
+
+        match (ap_id, ap_inbox) {
+            (Some(ap_id), Some(ap_inbox)) => (
+                ap_id.parse()?,
+                Some(ap_inbox.parse()?),
+                ap_followers.and_then(|x| x.parse().ok()),
+            ),
+            _ => {
+                return Err(crate::Error::InternalStr(format!(
+                    "Missing apub info for community {}",
+                    community
+                )))
             }
-        };
+        }
+    };
 
-        let create = local_post_to_create_ap(
-            &(&post).into(),
-            community_ap_id,
-            community_outbox,
-            community_followers,
-            &ctx,
-        )?;
+    let create = local_post_object_to_create_ap(
+        post_id,
+        author,
+        object,
+        community_private,
+        community_ap_id,
+        community_followers,
+        &ctx.host_url_apub,
+    );
+    let create = serde_json::to_string(&create)?;
 
+    if let Some(community_inbox) = community_inbox {
         ctx.enqueue_task(&crate::tasks::DeliverToInbox {
             inbox: Cow::Owned(community_inbox),
-            sign_as: Some(ActorLocalRef::Person(post.author.unwrap())),
-            object: serde_json::to_string(&create)?,
+            sign_as: Some(ActorLocalRef::Person(author)),
+            object: create.clone(),
         })
         .await?;
+    }
 
-        Ok(())
-    });
+    // posts in private communities only go to the community's followers
+    if !community_private {
+        let relay_tasks: Vec<_> = db
+            .query("SELECT inbox FROM relay WHERE accepted", &[])
+            .await?
+            .iter()
+            .filter_map(|row| row.get::<_, &str>(0).parse().ok())
+            .map(|inbox| crate::tasks::DeliverToInbox {
+                inbox: Cow::Owned(inbox),
+                sign_as: Some(ActorLocalRef::Person(author)),
+                object: create.clone(),
+            })
+            .collect();
+
+        if !relay_tasks.is_empty() {
+            ctx.enqueue_tasks(&relay_tasks).await?;
+        }
+
+        ctx.enqueue_task(&crate::tasks::DeliverToFollowers {
+            actor: ActorLocalRef::Person(author),
+            sign: true,
+            object: create,
+        })
+        .await?;
+    }
+
+    Ok(())
 }
 
 pub fn local_post_delete_to_ap(
@@ -1524,7 +3153,7 @@ pub fn local_post_delete_to_ap(
         post_ap_id.clone(),
     );
     delete
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = post_ap_id;
             res.path_segments_mut().push("delete");
@@ -1548,7 +3177,7 @@ pub fn local_comment_delete_to_ap(
     );
 
     delete
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id({
             let mut res = comment_ap_id;
             res.path_segments_mut().push("delete");
@@ -1565,6 +3194,7 @@ pub fn local_comment_to_create_ap(
     parent_ap_id: Option<url::Url>,
     parent_or_post_author_ap_id: Option<url::Url>,
     community_ap_id: url::Url,
+    community_private: bool,
     ctx: &crate::BaseContext,
 ) -> Result<activitystreams::activity::Create, crate::Error> {
     let comment_ap = local_comment_to_ap(
@@ -1573,6 +3203,7 @@ pub fn local_comment_to_create_ap(
         parent_ap_id,
         parent_or_post_author_ap_id.clone(),
         community_ap_id.clone(),
+        community_private,
         ctx,
     )?;
 
@@ -1582,7 +3213,7 @@ pub fn local_comment_to_create_ap(
         LocalObjectRef::User(author).to_local_uri(&ctx.host_url_apub),
         activitystreams::base::AnyBase::from_arbitrary_json(comment_ap)?,
     );
-    create.set_context(activitystreams::context()).set_id({
+    create.set_many_contexts(default_contexts()).set_id({
         let mut res = LocalObjectRef::Comment(comment.id).to_local_uri(&ctx.host_url_apub);
         res.path_segments_mut().push("create");
         res.into()
@@ -1591,11 +3222,13 @@ pub fn local_comment_to_create_ap(
     if let Some(parent_or_post_author_ap_id) = parent_or_post_author_ap_id {
         create
             .set_to(parent_or_post_author_ap_id)
-            .set_many_ccs(vec![activitystreams::public(), community_ap_id]);
+            .set_cc(community_ap_id);
     } else {
-        create
-            .set_to(community_ap_id)
-            .set_cc(activitystreams::public());
+        create.set_to(community_ap_id);
+    }
+    if !community_private {
+        create.add_cc(activitystreams::public());
+        create.add_cc(LocalObjectRef::UserFollowers(author).to_local_uri(&ctx.host_url_apub));
     }
 
     Ok(create)
@@ -1615,7 +3248,7 @@ pub fn local_post_flag_to_ap(
         post_ap_id,
     );
 
-    flag.set_context(activitystreams::context()).set_id({
+    flag.set_many_contexts(default_contexts()).set_id({
         let mut res = host_url_apub.clone();
         res.path_segments_mut()
             .extend(&["flags", &flag_local_id.to_string()]);
@@ -1648,7 +3281,7 @@ pub fn local_post_like_to_ap(
         crate::apub_util::LocalObjectRef::User(user).to_local_uri(host_url_apub),
         post_ap_id,
     );
-    like.set_context(activitystreams::context()).set_id(
+    like.set_many_contexts(default_contexts()).set_id(
         LocalObjectRef::PostLike(post_local_id, user)
             .to_local_uri(host_url_apub)
             .into(),
@@ -1663,20 +3296,52 @@ pub fn local_post_like_to_ap(
     Ok(like)
 }
 
+pub fn local_post_dislike_to_ap(
+    post_local_id: PostLocalID,
+    post_ap_id: BaseURL,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Dislike, crate::Error> {
+    let mut dislike = activitystreams::activity::Dislike::new(
+        crate::apub_util::LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        post_ap_id,
+    );
+    dislike.set_many_contexts(default_contexts()).set_id(
+        LocalObjectRef::PostDislike(post_local_id, user)
+            .to_local_uri(host_url_apub)
+            .into(),
+    );
+
+    if let Some(author_ap_id) = author_ap_id {
+        dislike.set_to(author_ap_id);
+    }
+
+    dislike.set_cc(activitystreams::public());
+
+    Ok(dislike)
+}
+
 pub fn local_post_like_undo_to_ap(
     undo_id: uuid::Uuid,
     post_local_id: PostLocalID,
+    is_dislike: bool,
     author_ap_id: Option<url::Url>,
     user: UserLocalID,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Undo, crate::Error> {
-    let like_ap_id = LocalObjectRef::PostLike(post_local_id, user).to_local_uri(host_url_apub);
+    let like_ap_id = if is_dislike {
+        LocalObjectRef::PostDislike(post_local_id, user)
+    } else {
+        LocalObjectRef::PostLike(post_local_id, user)
+    }
+    .to_local_uri(host_url_apub);
 
     let mut undo = activitystreams::activity::Undo::new(
         LocalObjectRef::User(user).to_local_uri(host_url_apub),
         like_ap_id,
     );
-    undo.set_context(activitystreams::context()).set_id({
+    undo.set_many_contexts(default_contexts()).set_id({
         let mut res = host_url_apub.clone();
         res.path_segments_mut()
             .extend(&["post_like_undos", &undo_id.to_string()]);
@@ -1705,31 +3370,64 @@ pub fn local_comment_like_to_ap(
         crate::apub_util::LocalObjectRef::User(user).to_local_uri(host_url_apub),
         comment_ap_id,
     );
-    like.set_context(activitystreams::context())
+    like.set_many_contexts(default_contexts())
         .set_id(like_ap_id.into());
 
     if let Some(author_ap_id) = author_ap_id {
         like.set_to(author_ap_id);
     }
 
+    like.set_cc(activitystreams::public());
+
     Ok(like)
 }
 
+pub fn local_comment_dislike_to_ap(
+    comment_local_id: CommentLocalID,
+    comment_ap_id: BaseURL,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Dislike, crate::Error> {
+    let dislike_ap_id =
+        LocalObjectRef::CommentDislike(comment_local_id, user).to_local_uri(host_url_apub);
+    let mut dislike = activitystreams::activity::Dislike::new(
+        crate::apub_util::LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        comment_ap_id,
+    );
+    dislike
+        .set_many_contexts(default_contexts())
+        .set_id(dislike_ap_id.into());
+
+    if let Some(author_ap_id) = author_ap_id {
+        dislike.set_to(author_ap_id);
+    }
+
+    dislike.set_cc(activitystreams::public());
+
+    Ok(dislike)
+}
+
 pub fn local_comment_like_undo_to_ap(
     undo_id: uuid::Uuid,
     comment_local_id: CommentLocalID,
+    is_dislike: bool,
     author_ap_id: Option<url::Url>,
     user: UserLocalID,
     host_url_apub: &BaseURL,
 ) -> Result<activitystreams::activity::Undo, crate::Error> {
-    let like_ap_id =
-        LocalObjectRef::CommentLike(comment_local_id, user).to_local_uri(host_url_apub);
+    let like_ap_id = if is_dislike {
+        LocalObjectRef::CommentDislike(comment_local_id, user)
+    } else {
+        LocalObjectRef::CommentLike(comment_local_id, user)
+    }
+    .to_local_uri(host_url_apub);
 
     let mut undo = activitystreams::activity::Undo::new(
         LocalObjectRef::User(user).to_local_uri(host_url_apub),
         like_ap_id,
     );
-    undo.set_context(activitystreams::context()).set_id({
+    undo.set_many_contexts(default_contexts()).set_id({
         let mut res = host_url_apub.clone();
         res.path_segments_mut()
             .extend(&["comment_like_undos", &undo_id.to_string()]);
@@ -1740,6 +3438,128 @@ pub fn local_comment_like_undo_to_ap(
         undo.set_to(author_ap_id);
     }
 
+    undo.set_cc(activitystreams::public());
+
+    Ok(undo)
+}
+
+pub fn local_post_reaction_to_ap(
+    post_local_id: PostLocalID,
+    reaction_id: i64,
+    post_ap_id: BaseURL,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    content: String,
+    host_url_apub: &BaseURL,
+) -> Result<EmojiReact, crate::Error> {
+    let mut react = EmojiReact::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        post_ap_id,
+    );
+    react
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::PostReaction(post_local_id, reaction_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_content(content);
+
+    if let Some(author_ap_id) = author_ap_id {
+        react.set_to(author_ap_id);
+    }
+
+    react.set_cc(activitystreams::public());
+
+    Ok(react)
+}
+
+pub fn local_post_reaction_undo_to_ap(
+    undo_id: uuid::Uuid,
+    post_local_id: PostLocalID,
+    reaction_id: i64,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Undo, crate::Error> {
+    let react_ap_id =
+        LocalObjectRef::PostReaction(post_local_id, reaction_id).to_local_uri(host_url_apub);
+
+    let mut undo = activitystreams::activity::Undo::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        react_ap_id,
+    );
+    undo.set_many_contexts(default_contexts()).set_id({
+        let mut res = host_url_apub.clone();
+        res.path_segments_mut()
+            .extend(&["post_reaction_undos", &undo_id.to_string()]);
+        res.into()
+    });
+
+    if let Some(author_ap_id) = author_ap_id {
+        undo.set_to(author_ap_id);
+    }
+
+    undo.set_cc(activitystreams::public());
+
+    Ok(undo)
+}
+
+pub fn local_comment_reaction_to_ap(
+    comment_local_id: CommentLocalID,
+    reaction_id: i64,
+    comment_ap_id: BaseURL,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    content: String,
+    host_url_apub: &BaseURL,
+) -> Result<EmojiReact, crate::Error> {
+    let mut react = EmojiReact::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        comment_ap_id,
+    );
+    react
+        .set_many_contexts(default_contexts())
+        .set_id(
+            LocalObjectRef::CommentReaction(comment_local_id, reaction_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )
+        .set_content(content);
+
+    if let Some(author_ap_id) = author_ap_id {
+        react.set_to(author_ap_id);
+    }
+
+    Ok(react)
+}
+
+pub fn local_comment_reaction_undo_to_ap(
+    undo_id: uuid::Uuid,
+    comment_local_id: CommentLocalID,
+    reaction_id: i64,
+    author_ap_id: Option<url::Url>,
+    user: UserLocalID,
+    host_url_apub: &BaseURL,
+) -> Result<activitystreams::activity::Undo, crate::Error> {
+    let react_ap_id =
+        LocalObjectRef::CommentReaction(comment_local_id, reaction_id).to_local_uri(host_url_apub);
+
+    let mut undo = activitystreams::activity::Undo::new(
+        LocalObjectRef::User(user).to_local_uri(host_url_apub),
+        react_ap_id,
+    );
+    undo.set_many_contexts(default_contexts()).set_id({
+        let mut res = host_url_apub.clone();
+        res.path_segments_mut()
+            .extend(&["comment_reaction_undos", &undo_id.to_string()]);
+        res.into()
+    });
+
+    if let Some(author_ap_id) = author_ap_id {
+        undo.set_to(author_ap_id);
+    }
+
     Ok(undo)
 }
 
@@ -1773,7 +3593,7 @@ pub fn local_poll_vote_to_ap(
 
     let mut create = activitystreams::activity::Create::new(actor, note.into_any_base()?);
     create
-        .set_context(activitystreams::context())
+        .set_many_contexts(default_contexts())
         .set_id(id.into());
 
     if let Some(author_ap_id) = author_ap_id {
@@ -1796,7 +3616,7 @@ pub fn local_poll_vote_undo_to_ap(
         LocalObjectRef::User(user).to_local_uri(host_url_apub),
         LocalObjectRef::PollVote(poll_id, user, option_id).to_local_uri(host_url_apub),
     );
-    undo.set_context(activitystreams::context()).set_id({
+    undo.set_many_contexts(default_contexts()).set_id({
         let mut res = host_url_apub.clone();
         res.path_segments_mut()
             .extend(&["tmp_objects", &undo_id.to_string()]);
@@ -1814,35 +3634,42 @@ pub fn spawn_enqueue_send_comment(
     inboxes: HashSet<url::Url>,
     comment: crate::CommentInfo,
     community_ap_id: url::Url,
+    community_private: bool,
     post_ap_id: url::Url,
     parent_ap_id: Option<url::Url>,
     post_or_parent_author_ap_id: Option<url::Url>,
     ctx: Arc<crate::RouteContext>,
 ) {
-    if inboxes.is_empty() {
-        return;
-    }
-
     let create = local_comment_to_create_ap(
         &comment,
         &post_ap_id,
         parent_ap_id,
         post_or_parent_author_ap_id,
         community_ap_id,
+        community_private,
         &ctx,
     );
 
     let author = comment.author.unwrap();
 
     crate::spawn_task(async move {
-        let create = create?;
+        let create = serde_json::to_string(&create?)?;
 
         // TODO maybe insert these at the same time
         for inbox in inboxes {
             ctx.enqueue_task(&crate::tasks::DeliverToInbox {
                 inbox: Cow::Owned(inbox),
                 sign_as: Some(ActorLocalRef::Person(author)),
-                object: serde_json::to_string(&create)?,
+                object: create.clone(),
+            })
+            .await?;
+        }
+
+        if !community_private {
+            ctx.enqueue_task(&crate::tasks::DeliverToFollowers {
+                actor: ActorLocalRef::Person(author),
+                sign: true,
+                object: create,
             })
             .await?;
         }
@@ -1864,6 +3691,23 @@ pub async fn enqueue_forward_to_community_followers(
     .await
 }
 
+/// Activities from private communities are only addressed to their followers
+pub async fn is_community_private(
+    community: CommunityLocalID,
+    ctx: &crate::BaseContext,
+) -> Result<bool, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let row = db
+        .query_opt("SELECT private FROM community WHERE id=$1", &[&community])
+        .await?;
+
+    Ok(match row {
+        None => false,
+        Some(row) => row.get(0),
+    })
+}
+
 async fn enqueue_send_to_community_followers(
     community_id: CommunityLocalID,
     activity: impl serde::Serialize,
@@ -1894,13 +3738,42 @@ pub async fn check_signature_for_actor(
     db: &tokio_postgres::Client,
     ctx: &Arc<crate::BaseContext>,
 ) -> Result<bool, crate::Error> {
-    let found_key = db.query_opt("(SELECT public_key, public_key_sigalg FROM person WHERE ap_id=$1) UNION ALL (SELECT public_key, public_key_sigalg FROM community WHERE ap_id=$1) LIMIT 1", &[&actor_ap_id.as_str()]).await?
-        .and_then(|row| {
-            row.get::<_, Option<&[u8]>>(0).map(|key| {
-                openssl::pkey::PKey::public_key_from_pem(key)
-                    .map(|key| (key, get_message_digest(row.get(1))))
-            })
-        }).transpose()?;
+    let found_key = match get_cached_actor(actor_ap_id, ctx) {
+        Some(actor) => actor.key,
+        None => {
+            let row = db.query_opt("(SELECT public_key, public_key_sigalg, id, TRUE FROM person WHERE ap_id=$1) UNION ALL (SELECT public_key, public_key_sigalg, id, FALSE FROM community WHERE ap_id=$1) LIMIT 1", &[&actor_ap_id.as_str()]).await?;
+
+            match row {
+                None => None,
+                Some(row) => {
+                    let key = row
+                        .get::<_, Option<&[u8]>>(0)
+                        .map(|key| {
+                            openssl::pkey::PKey::public_key_from_pem(key)
+                                .map(|key| (key, get_message_digest(row.get(1))))
+                        })
+                        .transpose()?;
+
+                    let id = if row.get(3) {
+                        ThingLocalRef::User(row.get(2))
+                    } else {
+                        ThingLocalRef::Community(row.get(2))
+                    };
+
+                    cache_actor(
+                        actor_ap_id,
+                        CachedActor {
+                            id,
+                            key: key.clone(),
+                        },
+                        ctx,
+                    );
+
+                    key
+                }
+            }
+        }
+    };
 
     log::debug!("signature: {:?}", signature);
     log::debug!("found_key: {:?}", found_key.is_some());
@@ -1924,7 +3797,13 @@ pub async fn check_signature_for_actor(
     // Either no key found or failed to verify
     // Try fetching the actor/key
 
-    let actor = fetch_actor(actor_ap_id, ctx.clone()).await?;
+    let actor = match fetch_actor(actor_ap_id, ctx.clone()).await {
+        Err(err) if is_remote_gone(&err) => {
+            ingest::ingest_remote_actor_delete(actor_ap_id, ctx).await?;
+            return Ok(false);
+        }
+        res => res?,
+    };
 
     if let Some(key_info) = actor.public_key() {
         let key = openssl::pkey::PKey::public_key_from_pem(&key_info.key)?;
@@ -1993,12 +3872,181 @@ pub fn check_digest(body: &[u8], digest_header: &http::header::HeaderValue) -> b
     true
 }
 
+fn get_signature_key_id(signature: &str) -> Option<&str> {
+    signature.split(',').find_map(|part| {
+        let (key, value) = part.split_once('=')?;
+        if key.trim() == "keyId" {
+            Some(value.trim().trim_matches('"'))
+        } else {
+            None
+        }
+    })
+}
+
+/// Checks the signature on a signed fetch, returning the ID of the actor that signed it
+pub async fn verify_fetch_signature(
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+    ctx: &Arc<crate::BaseContext>,
+) -> Result<Option<url::Url>, crate::Error> {
+    let signature = match req.headers().get("signature") {
+        None => return Ok(None),
+        Some(signature) => signature,
+    };
+
+    let key_id = match get_signature_key_id(signature.to_str()?) {
+        None => return Ok(None),
+        Some(key_id) => key_id,
+    };
+
+    let mut actor_ap_id: url::Url = match key_id.parse() {
+        Err(_) => return Ok(None),
+        Ok(value) => value,
+    };
+    actor_ap_id.set_fragment(None);
+
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .ok_or(crate::Error::InternalStrStatic(
+            "Missing path, cannot verify signature",
+        ))?
+        .as_str();
+
+    // path ends up wrong with our recommended proxy config
+    let path_and_query = if ctx.apub_proxy_rewrites {
+        req.headers()
+            .get("x-forwarded-path")
+            .map(|x| x.to_str())
+            .transpose()?
+    } else {
+        None
+    }
+    .unwrap_or(path_and_query);
+
+    if check_signature_for_actor(
+        signature,
+        req.method(),
+        path_and_query,
+        req.headers(),
+        &actor_ap_id,
+        db,
+        ctx,
+    )
+    .await?
+    {
+        Ok(Some(actor_ap_id))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Checks whether content of a local community can be served for a fetch, returning whether the
+/// community is private.
+///
+/// Private community content is only served to signed fetches from hosts with accepted followers.
+pub async fn check_local_community_fetch(
+    community: CommunityLocalID,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+    ctx: &Arc<crate::BaseContext>,
+) -> Result<bool, crate::Error> {
+    let row = db
+        .query_opt(
+            "SELECT private FROM community WHERE id=$1 AND local",
+            &[&community],
+        )
+        .await?;
+
+    if !row.map(|row| row.get(0)).unwrap_or(false) {
+        return Ok(false);
+    }
+
+    let actor_ap_id = verify_fetch_signature(req, db, ctx).await?.ok_or_else(|| {
+        crate::Error::UserError(crate::simple_response(
+            hyper::StatusCode::UNAUTHORIZED,
+            "A valid signature is required",
+        ))
+    })?;
+    let host = compat::get_url_host_key(&actor_ap_id).ok_or(crate::Error::InternalStrStatic(
+        "Signing actor ID is missing a host",
+    ))?;
+
+    if get_local_community_followers_on_host(community, host, db)
+        .await?
+        .is_empty()
+    {
+        return Err(crate::Error::UserError(crate::simple_response(
+            hyper::StatusCode::FORBIDDEN,
+            "Community content is only available to followers",
+        )));
+    }
+
+    Ok(true)
+}
+
+pub fn get_local_community_followers_sync_url(
+    community: CommunityLocalID,
+    host_url_apub: &BaseURL,
+) -> BaseURL {
+    let mut res = LocalObjectRef::Community(community).to_local_uri(host_url_apub);
+    res.path_segments_mut().push("followers_synchronization");
+    res
+}
+
+/// Lists the IDs of a local community's followers from a single remote host
+pub async fn get_local_community_followers_on_host(
+    community: CommunityLocalID,
+    host: &str,
+    db: &tokio_postgres::Client,
+) -> Result<Vec<String>, crate::Error> {
+    let rows = db.query(
+        "SELECT person.ap_id FROM community_follow INNER JOIN person ON (person.id = community_follow.follower) WHERE community_follow.community=$1 AND community_follow.accepted AND NOT person.local AND url_host_key(person.ap_id)=$2",
+        &[&community, &host],
+    ).await?;
+
+    Ok(rows.iter().filter_map(|row| row.get(0)).collect())
+}
+
+/// Builds the Collection-Synchronization header (FEP-8fcf) for a delivery from a local community
+/// to a remote host
+pub async fn get_local_community_followers_sync_header(
+    community: CommunityLocalID,
+    host: &str,
+    db: &tokio_postgres::Client,
+    host_url_apub: &BaseURL,
+) -> Result<hyper::header::HeaderValue, crate::Error> {
+    let followers = get_local_community_followers_on_host(community, host, db).await?;
+
+    let mut digest = [0u8; 32];
+    for follower in followers {
+        let hash =
+            openssl::hash::hash(openssl::hash::MessageDigest::sha256(), follower.as_bytes())?;
+        for (dest, src) in digest.iter_mut().zip(hash.iter()) {
+            *dest ^= src;
+        }
+    }
+
+    let mut digest_hex = String::with_capacity(64);
+    for byte in digest.iter() {
+        digest_hex.push_str(&format!("{:02x}", byte));
+    }
+
+    Ok(hyper::header::HeaderValue::from_str(&format!(
+        r#"collectionId="{}", url="{}", digest="{}""#,
+        LocalObjectRef::CommunityFollowers(community).to_local_uri(host_url_apub),
+        get_local_community_followers_sync_url(community, host_url_apub),
+        digest_hex,
+    ))?)
+}
+
 pub async fn verify_incoming_object(
     mut req: hyper::Request<hyper::Body>,
     db: &tokio_postgres::Client,
     ctx: &Arc<crate::BaseContext>,
 ) -> Result<Verified<KnownObject>, crate::Error> {
-    let req_body = hyper::body::to_bytes(req.body_mut()).await?;
+    let req_body =
+        crate::to_bytes_limited(std::mem::take(req.body_mut()), ctx.max_request_body_size).await?;
 
     match req.headers().get("signature") {
         None => {
@@ -2024,6 +4072,20 @@ pub async fn verify_incoming_object(
                 ));
             };
 
+            if db
+                .query_opt(
+                    "SELECT 1 FROM person WHERE ap_id=$1 AND banned",
+                    &[&actor_ap_id.as_str()],
+                )
+                .await?
+                .is_some()
+            {
+                return Err(crate::Error::UserError(crate::simple_response(
+                    hyper::StatusCode::FORBIDDEN,
+                    "Actor is banned",
+                )));
+            }
+
             let path_and_query = req
                 .uri()
                 .path_and_query()
@@ -2066,7 +4128,9 @@ pub async fn verify_incoming_object(
                     "Received remote object: {}",
                     String::from_utf8_lossy(&req_body)
                 );
-                Ok(Verified(serde_json::from_slice(&req_body)?))
+                Ok(Verified(jsonld::parse_known_object(
+                    serde_json::from_slice(&req_body)?,
+                )?))
             } else {
                 Err(crate::Error::UserError(crate::simple_response(
                     hyper::StatusCode::FORBIDDEN,
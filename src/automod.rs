@@ -0,0 +1,314 @@
+use crate::types::{
+    AutomodAction, AutomodRuleID, AutomodRuleKind, CommentLocalID, CommunityLocalID, FlagLocalID,
+    PostLocalID, UserLocalID, WebhookEvent,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const REGEX_SIZE_LIMIT: usize = 1 << 16;
+
+pub struct AutomodContent<'a> {
+    pub community: CommunityLocalID,
+    pub author: Option<UserLocalID>,
+    pub title: Option<&'a str>,
+    pub href: Option<&'a str>,
+    pub content: Option<&'a str>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AutomodMatch {
+    pub rule: AutomodRuleID,
+    pub action: AutomodAction,
+    /// Whether the rule is instance-wide rather than belonging to the community
+    pub instance_level: bool,
+}
+
+pub fn build_regex(pattern: &str) -> Result<regex::Regex, regex::Error> {
+    regex::RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+/// Compiled patterns for regex rules, along with the pattern each was compiled from so rules
+/// changed by another process are recompiled
+#[derive(Default)]
+pub struct RegexCache(std::sync::Mutex<HashMap<AutomodRuleID, (String, Arc<regex::Regex>)>>);
+
+impl RegexCache {
+    fn get(&self, rule: AutomodRuleID, pattern: &str) -> Result<Arc<regex::Regex>, regex::Error> {
+        if let Some((cached_pattern, regex)) = self.0.lock().unwrap().get(&rule) {
+            if cached_pattern == pattern {
+                return Ok(regex.clone());
+            }
+        }
+
+        let regex = Arc::new(build_regex(pattern)?);
+        self.0
+            .lock()
+            .unwrap()
+            .insert(rule, (pattern.to_owned(), regex.clone()));
+
+        Ok(regex)
+    }
+
+    pub fn invalidate(&self, rule: AutomodRuleID) {
+        self.0.lock().unwrap().remove(&rule);
+    }
+}
+
+pub fn is_valid_rule_value(kind: AutomodRuleKind, value: &str) -> bool {
+    match kind {
+        AutomodRuleKind::Keyword => !value.trim().is_empty(),
+        AutomodRuleKind::Regex => build_regex(value).is_ok(),
+        AutomodRuleKind::LinkDomain => {
+            !value.is_empty() && !value.contains('/') && url::Host::parse(value).is_ok()
+        }
+        AutomodRuleKind::MinAccountAge | AutomodRuleKind::RateLimit => value.parse::<u32>().is_ok(),
+    }
+}
+
+fn extract_link_hosts(text: &str) -> Vec<String> {
+    text.match_indices("http")
+        .filter_map(|(idx, _)| {
+            let rest = &text[idx..];
+            let end = rest
+                .find(|c: char| {
+                    c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | ')' | ']')
+                })
+                .unwrap_or(rest.len());
+
+            url::Url::parse(&rest[..end]).ok()
+        })
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .filter_map(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        .collect()
+}
+
+fn host_matches(host: &str, domain: &str) -> bool {
+    match host.strip_suffix(domain) {
+        Some(rest) => rest.is_empty() || rest.ends_with('.'),
+        None => false,
+    }
+}
+
+/// Evaluates instance-wide and community rules against new content, returning the most severe match
+pub async fn check_content(
+    content: &AutomodContent<'_>,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<Option<AutomodMatch>, crate::Error> {
+    let rows = db
+        .query(
+            "SELECT id, kind, value, action, community IS NULL FROM automod_rule WHERE community IS NULL OR community=$1",
+            &[&content.community],
+        )
+        .await?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let text = [content.title, content.href, content.content]
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text_lower = text.to_lowercase();
+
+    let mut link_hosts = None;
+    let mut author_stats = None;
+
+    let mut result: Option<AutomodMatch> = None;
+
+    for row in rows {
+        let kind: AutomodRuleKind = match row.get::<_, &str>(1).parse() {
+            Ok(kind) => kind,
+            Err(_) => continue,
+        };
+        let action: AutomodAction = match row.get::<_, &str>(3).parse() {
+            Ok(action) => action,
+            Err(_) => continue,
+        };
+
+        if let Some(found) = &result {
+            if found.action >= action {
+                continue;
+            }
+        }
+
+        let rule: AutomodRuleID = row.get(0);
+        let value: &str = row.get(2);
+
+        let matched = match kind {
+            AutomodRuleKind::Keyword => text_lower.contains(&value.to_lowercase()),
+            AutomodRuleKind::Regex => match ctx.automod_regex_cache.get(rule, value) {
+                Ok(regex) => regex.is_match(&text),
+                Err(err) => {
+                    log::warn!("Skipping automod rule {} with invalid regex: {}", rule, err);
+                    false
+                }
+            },
+            AutomodRuleKind::LinkDomain => {
+                let domain = value.to_ascii_lowercase();
+                link_hosts
+                    .get_or_insert_with(|| extract_link_hosts(&text))
+                    .iter()
+                    .any(|host| host_matches(host, &domain))
+            }
+            AutomodRuleKind::MinAccountAge | AutomodRuleKind::RateLimit => {
+                let author = match content.author {
+                    Some(author) => author,
+                    None => continue,
+                };
+                let limit: i64 = match value.parse() {
+                    Ok(limit) => limit,
+                    Err(_) => continue,
+                };
+
+                if author_stats.is_none() {
+                    let row = db.query_opt(
+                        "SELECT EXTRACT(EPOCH FROM current_timestamp - created_local)::BIGINT / 3600, (SELECT COUNT(*) FROM post WHERE author=$1 AND created > current_timestamp - INTERVAL '1 HOUR') + (SELECT COUNT(*) FROM reply WHERE author=$1 AND created > current_timestamp - INTERVAL '1 HOUR') FROM person WHERE id=$1",
+                        &[&author],
+                    ).await?;
+
+                    author_stats =
+                        Some(row.map(|row| (row.get::<_, i64>(0), row.get::<_, i64>(1))));
+                }
+
+                match author_stats.unwrap() {
+                    None => false,
+                    Some((age_hours, recent_count)) => {
+                        if kind == AutomodRuleKind::MinAccountAge {
+                            age_hours < limit
+                        } else {
+                            recent_count >= limit
+                        }
+                    }
+                }
+            }
+        };
+
+        if matched {
+            result = Some(AutomodMatch {
+                rule,
+                action,
+                instance_level: row.get(4),
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum CommentOutcome {
+    Allow,
+    Hold,
+    Report(AutomodMatch),
+    Reject,
+}
+
+/// Like `check_content`, for a comment on the given post
+pub async fn check_comment(
+    post: PostLocalID,
+    author: Option<UserLocalID>,
+    content: Option<&str>,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<CommentOutcome, crate::Error> {
+    let row = db
+        .query_opt("SELECT community FROM post WHERE id=$1", &[&post])
+        .await?;
+
+    let row = match row {
+        None => return Ok(CommentOutcome::Allow),
+        Some(row) => row,
+    };
+
+    let found = check_content(
+        &AutomodContent {
+            community: row.get(0),
+            author,
+            title: None,
+            href: None,
+            content,
+        },
+        db,
+        ctx,
+    )
+    .await?;
+
+    Ok(match found {
+        None => CommentOutcome::Allow,
+        Some(found) => match found.action {
+            AutomodAction::Report => CommentOutcome::Report(found),
+            AutomodAction::Hold => CommentOutcome::Hold,
+            AutomodAction::Reject => CommentOutcome::Reject,
+        },
+    })
+}
+
+/// Files a report on a post on behalf of the matched rule
+pub async fn report_post(
+    post: PostLocalID,
+    found: AutomodMatch,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let mut db = ctx.db_pool.get().await?;
+    let trans = db.transaction().await?;
+    report_post_in_transaction(post, found, &trans, ctx).await?;
+    trans.commit().await?;
+
+    ctx.notify_worker()
+}
+
+pub async fn report_post_in_transaction(
+    post: PostLocalID,
+    found: AutomodMatch,
+    trans: &tokio_postgres::Transaction<'_>,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let row = trans.query_one(
+        "INSERT INTO flag (kind, post, to_community, to_site_admin, to_remote_site_admin, created_local, local, category, automod_rule) VALUES ('post', $1, TRUE, $2, FALSE, current_timestamp, TRUE, 'spam', $3) RETURNING id",
+        &[&post, &found.instance_level, &found.rule],
+    ).await?;
+
+    let id: FlagLocalID = row.get(0);
+
+    ctx.enqueue_webhook_event_in_transaction(
+        trans,
+        WebhookEvent::NewReport,
+        serde_json::json!({ "flag": id, "post": post, "automod_rule": found.rule }),
+    )
+    .await
+}
+
+/// Files a report on a comment on behalf of the matched rule
+pub async fn report_comment(
+    comment: CommentLocalID,
+    found: AutomodMatch,
+    ctx: &crate::BaseContext,
+) -> Result<(), crate::Error> {
+    let mut db = ctx.db_pool.get().await?;
+    let trans = db.transaction().await?;
+
+    let row = trans.query_one(
+        "INSERT INTO flag (kind, reply, to_community, to_site_admin, to_remote_site_admin, created_local, local, category, automod_rule) VALUES ('comment', $1, TRUE, $2, FALSE, current_timestamp, TRUE, 'spam', $3) RETURNING id",
+        &[&comment, &found.instance_level, &found.rule],
+    ).await?;
+
+    let id: FlagLocalID = row.get(0);
+
+    ctx.enqueue_webhook_event_in_transaction(
+        &trans,
+        WebhookEvent::NewReport,
+        serde_json::json!({ "flag": id, "comment": comment, "automod_rule": found.rule }),
+    )
+    .await?;
+
+    trans.commit().await?;
+
+    ctx.notify_worker()
+}
@@ -5,11 +5,122 @@ fn default_port() -> u16 {
     3333
 }
 
+fn default_actor_refresh_age_hours() -> u32 {
+    72
+}
+
+fn default_database_pool_size() -> usize {
+    16
+}
+
+fn default_actor_refresh_interval_minutes() -> u32 {
+    60
+}
+
+fn default_login_prune_interval_minutes() -> u32 {
+    60
+}
+
+fn default_failed_task_retry_interval_minutes() -> u32 {
+    6 * 60
+}
+
+fn default_community_activity_interval_minutes() -> u32 {
+    15
+}
+
+fn default_post_view_flush_interval_minutes() -> u32 {
+    1
+}
+
+fn default_announce_reconcile_interval_minutes() -> u32 {
+    15
+}
+
+fn default_hot_rank_interval_minutes() -> u32 {
+    5
+}
+
+fn default_community_activity_window_hours() -> u32 {
+    48
+}
+
+fn default_duplicate_post_window_hours() -> u32 {
+    24
+}
+
+fn default_host_dead_after_days() -> u32 {
+    3
+}
+
+fn default_worker_concurrency() -> usize {
+    4
+}
+
+fn default_worker_host_concurrency() -> u32 {
+    2
+}
+
+fn default_worker_poll_interval_seconds() -> u32 {
+    10
+}
+
+fn default_password_hash_memory_kib() -> u32 {
+    19456
+}
+
+fn default_password_hash_iterations() -> u32 {
+    2
+}
+
+fn default_password_hash_parallelism() -> u32 {
+    1
+}
+
+fn default_max_request_body_kib() -> u32 {
+    1024
+}
+
+fn default_max_import_body_mib() -> u32 {
+    64
+}
+
+fn default_fetch_timeout_seconds() -> u32 {
+    30
+}
+
+fn default_max_fetch_response_kib() -> u32 {
+    4096
+}
+
+fn default_max_replies_depth() -> u8 {
+    8
+}
+
+fn default_max_comments_per_request() -> u32 {
+    1000
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Deserialize)]
 pub struct Config {
     pub database_url: String,
+    #[serde(default = "default_database_pool_size")]
+    pub database_pool_size: usize,
+    pub database_pool_timeout_seconds: Option<u64>,
+    pub database_connect_timeout_seconds: Option<u64>,
+    pub database_statement_timeout_seconds: Option<u64>,
+    pub database_tls_root_cert: Option<String>,
+    pub database_url_read: Option<String>,
+
     pub host_url_activitypub: String,
     pub host_url_api: String,
+    pub frontend_url_pattern: Option<String>,
+    pub lang_dir: Option<String>,
+    pub strip_href_query_params: Option<String>,
 
     #[serde(default = "default_port")]
     pub port: u16,
@@ -18,8 +129,14 @@ pub struct Config {
     pub apub_proxy_rewrites: bool,
     #[serde(default)]
     pub allow_forwarded: bool,
+    pub trusted_proxies: Option<String>,
+    pub outbound_allowed_ranges: Option<String>,
     #[serde(default)]
     pub dev_mode: bool,
+    #[serde(default)]
+    pub sign_fetches: bool,
+    #[serde(default)]
+    pub link_posts_as_notes: bool,
 
     pub media_storage: Option<String>,
     pub media_location: Option<String>,
@@ -32,6 +149,67 @@ pub struct Config {
     pub smtp_url: Option<String>,
     pub smtp_from: Option<String>,
 
+    #[serde(default = "default_actor_refresh_age_hours")]
+    pub actor_refresh_age_hours: u32,
+    pub login_max_age_days: Option<u32>,
+    #[serde(default = "default_community_activity_window_hours")]
+    pub community_activity_window_hours: u32,
+    #[serde(default = "default_duplicate_post_window_hours")]
+    pub duplicate_post_window_hours: u32,
+    pub comment_edit_window_minutes: Option<u32>,
+    #[serde(default = "default_max_replies_depth")]
+    pub max_replies_depth: u8,
+    #[serde(default = "default_max_comments_per_request")]
+    pub max_comments_per_request: u32,
+    #[serde(default = "default_host_dead_after_days")]
+    pub host_dead_after_days: u32,
+    #[serde(default = "default_worker_concurrency")]
+    pub worker_concurrency: usize,
+    #[serde(default = "default_worker_host_concurrency")]
+    pub worker_host_concurrency: u32,
+    #[serde(default = "default_worker_poll_interval_seconds")]
+    pub worker_poll_interval_seconds: u32,
+
+    #[serde(default = "default_max_request_body_kib")]
+    pub max_request_body_kib: u32,
+    #[serde(default = "default_max_import_body_mib")]
+    pub max_import_body_mib: u32,
+    #[serde(default = "default_fetch_timeout_seconds")]
+    pub fetch_timeout_seconds: u32,
+    #[serde(default = "default_max_fetch_response_kib")]
+    pub max_fetch_response_kib: u32,
+
+    #[serde(default = "default_password_hash_memory_kib")]
+    pub password_hash_memory_kib: u32,
+    #[serde(default = "default_password_hash_iterations")]
+    pub password_hash_iterations: u32,
+    #[serde(default = "default_password_hash_parallelism")]
+    pub password_hash_parallelism: u32,
+
+    #[serde(default = "default_actor_refresh_interval_minutes")]
+    pub actor_refresh_interval_minutes: u32,
+    #[serde(default = "default_login_prune_interval_minutes")]
+    pub login_prune_interval_minutes: u32,
+    #[serde(default)]
+    pub media_vacuum_interval_minutes: u32,
+    #[serde(default = "default_failed_task_retry_interval_minutes")]
+    pub failed_task_retry_interval_minutes: u32,
+    #[serde(default = "default_community_activity_interval_minutes")]
+    pub community_activity_interval_minutes: u32,
+    #[serde(default = "default_post_view_flush_interval_minutes")]
+    pub post_view_flush_interval_minutes: u32,
+    #[serde(default = "default_announce_reconcile_interval_minutes")]
+    pub announce_reconcile_interval_minutes: u32,
+    #[serde(default = "default_hot_rank_interval_minutes")]
+    pub hot_rank_interval_minutes: u32,
+
+    #[serde(default)]
+    pub markdown_tables: bool,
+    #[serde(default)]
+    pub markdown_strikethrough: bool,
+    #[serde(default = "default_true")]
+    pub markdown_autolink: bool,
+
     #[serde(default)]
     pub break_stuff: bool,
 }
@@ -50,6 +228,185 @@ impl Config {
 
         src.try_into()
     }
+
+    /// Checks everything that can be checked without connecting anywhere, returning all problems found
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        if let Err(err) = self.database_url.parse::<tokio_postgres::Config>() {
+            problems.push(format!(
+                "DATABASE_URL is not a valid connection string: {}",
+                err
+            ));
+        }
+
+        if let Some(database_url_read) = &self.database_url_read {
+            if let Err(err) = database_url_read.parse::<tokio_postgres::Config>() {
+                problems.push(format!(
+                    "DATABASE_URL_READ is not a valid connection string: {}",
+                    err
+                ));
+            }
+        }
+
+        if self.database_pool_size == 0 {
+            problems.push("DATABASE_POOL_SIZE must be at least 1".to_owned());
+        }
+
+        if let Some(path) = &self.database_tls_root_cert {
+            if let Err(err) = std::fs::metadata(path) {
+                problems.push(format!("DATABASE_TLS_ROOT_CERT could not be read: {}", err));
+            }
+        }
+
+        match self.host_url_activitypub.parse::<url::Url>() {
+            Err(err) => problems.push(format!("HOST_URL_ACTIVITYPUB is not a valid URL: {}", err)),
+            Ok(url) => {
+                if url.cannot_be_a_base() || !url.has_host() {
+                    problems.push(
+                        "HOST_URL_ACTIVITYPUB must be an absolute URL with a host, e.g. https://example.com"
+                            .to_owned(),
+                    );
+                }
+            }
+        }
+
+        if let Err(err) = self.host_url_api.parse::<url::Url>() {
+            problems.push(format!("HOST_URL_API is not a valid URL: {}", err));
+        }
+
+        if let Some(pattern) = &self.frontend_url_pattern {
+            if !pattern.contains("{id}") {
+                problems.push("FRONTEND_URL_PATTERN must contain {id}".to_owned());
+            }
+        }
+
+        if let Some(path) = &self.lang_dir {
+            if let Err(err) = std::fs::read_dir(path) {
+                problems.push(format!("LANG_DIR could not be read: {}", err));
+            }
+        }
+
+        if self.max_request_body_kib == 0 {
+            problems.push("MAX_REQUEST_BODY_KIB must be at least 1".to_owned());
+        }
+
+        if self.max_import_body_mib == 0 {
+            problems.push("MAX_IMPORT_BODY_MIB must be at least 1".to_owned());
+        }
+
+        if self.fetch_timeout_seconds == 0 {
+            problems.push("FETCH_TIMEOUT_SECONDS must be at least 1".to_owned());
+        }
+
+        if self.max_fetch_response_kib == 0 {
+            problems.push("MAX_FETCH_RESPONSE_KIB must be at least 1".to_owned());
+        }
+
+        if let Err(err) = argon2::Params::new(
+            self.password_hash_memory_kib,
+            self.password_hash_iterations,
+            self.password_hash_parallelism,
+            None,
+        ) {
+            problems.push(format!(
+                "PASSWORD_HASH_MEMORY_KIB, PASSWORD_HASH_ITERATIONS, and PASSWORD_HASH_PARALLELISM are not valid Argon2 parameters: {}",
+                err
+            ));
+        }
+
+        if let Some(trusted_proxies) = &self.trusted_proxies {
+            for proxy in trusted_proxies.split(',').map(str::trim) {
+                if !proxy.is_empty() && proxy.parse::<crate::IpRange>().is_err() {
+                    problems.push(format!(
+                        "TRUSTED_PROXIES contains an invalid address or range: {}",
+                        proxy
+                    ));
+                }
+            }
+        }
+
+        if let Some(outbound_allowed_ranges) = &self.outbound_allowed_ranges {
+            for range in outbound_allowed_ranges.split(',').map(str::trim) {
+                if !range.is_empty() && range.parse::<crate::IpRange>().is_err() {
+                    problems.push(format!(
+                        "OUTBOUND_ALLOWED_RANGES contains an invalid address or range: {}",
+                        range
+                    ));
+                }
+            }
+        }
+
+        if let Some(smtp_url) = &self.smtp_url {
+            match smtp_url.parse::<url::Url>() {
+                Err(err) => problems.push(format!("SMTP_URL is not a valid URL: {}", err)),
+                Ok(url) => {
+                    if url.scheme() != "smtp" && url.scheme() != "smtps" {
+                        problems.push("SMTP_URL must use the smtp or smtps scheme".to_owned());
+                    }
+                    if url.host_str().is_none() {
+                        problems.push("SMTP_URL is missing a host".to_owned());
+                    }
+                }
+            }
+
+            if self.smtp_from.is_none() {
+                problems.push("SMTP_URL was provided, but SMTP_FROM was not".to_owned());
+            }
+        }
+
+        if let Some(smtp_from) = &self.smtp_from {
+            if let Err(err) = smtp_from.parse::<lettre::message::Mailbox>() {
+                problems.push(format!("SMTP_FROM is not a valid address: {}", err));
+            }
+        }
+
+        match self.media_storage.as_deref() {
+            None => {}
+            Some("local") => {
+                if self.media_location.is_none() {
+                    problems.push(
+                        "MEDIA_STORAGE is local, but MEDIA_LOCATION was not provided".to_owned(),
+                    );
+                }
+            }
+            Some("s3") => {
+                if self.media_location.is_none() {
+                    problems.push(
+                        "MEDIA_STORAGE is s3, but MEDIA_LOCATION (the bucket name) was not provided"
+                            .to_owned(),
+                    );
+                }
+                if self.media_s3_access_key_id.is_some() && self.media_s3_secret_key.is_none() {
+                    problems.push(
+                        "MEDIA_S3_ACCESS_KEY_ID was provided, but MEDIA_S3_SECRET_KEY was not"
+                            .to_owned(),
+                    );
+                }
+                if self.media_s3_endpoint.is_none() {
+                    if let Some(region) = &self.media_s3_region {
+                        if region.parse::<rusoto_core::Region>().is_err() {
+                            problems.push(format!("MEDIA_S3_REGION {:?} is not a known AWS region (set MEDIA_S3_ENDPOINT for other providers)", region));
+                        }
+                    }
+                }
+            }
+            Some(other) => problems.push(format!(
+                "Unknown MEDIA_STORAGE {:?}, expected local or s3",
+                other
+            )),
+        }
+
+        if self.login_max_age_days == Some(0) {
+            problems.push("LOGIN_MAX_AGE_DAYS must be at least 1".to_owned());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,90 @@
+use crate::types::{CommunityLocalID, FollowState, UserLocalID};
+
+/// Result of a community follow request, used to decide what needs to be sent
+pub struct FollowTransition {
+    pub previous: Option<FollowState>,
+    pub current: FollowState,
+}
+
+impl FollowTransition {
+    pub fn changed(&self) -> bool {
+        self.previous != Some(self.current)
+    }
+}
+
+pub struct FollowInfo {
+    pub local: bool,
+    pub ap_id: Option<String>,
+}
+
+fn parse_state(src: &str) -> Result<FollowState, crate::Error> {
+    src.parse()
+        .map_err(|_| crate::Error::InternalStr(format!("Unknown follow state {}", src)))
+}
+
+/// States a follow may be moved to `to` from, apart from requests which are handled separately
+fn valid_sources(to: FollowState) -> &'static [&'static str] {
+    match to {
+        FollowState::Pending => &[],
+        FollowState::Accepted => &["pending"],
+        FollowState::Rejected => &["pending", "accepted"],
+    }
+}
+
+/// Creates or renews a follow request.
+///
+/// Accepted follows stay accepted, and a repeat of a rejected request (same `ap_id`) stays
+/// rejected, so receiving the same Follow more than once has no further effect.
+pub async fn request_community_follow(
+    db: &tokio_postgres::Client,
+    community: CommunityLocalID,
+    follower: UserLocalID,
+    local: bool,
+    ap_id: Option<&str>,
+    auto_accept: bool,
+) -> Result<FollowTransition, crate::Error> {
+    let row = db.query_one(
+        "WITH previous AS (SELECT state FROM community_follow WHERE community=$1 AND follower=$2) INSERT INTO community_follow (community, follower, local, ap_id, accepted, state) VALUES ($1, $2, $3, $4, FALSE, (CASE WHEN $5 THEN 'accepted' ELSE 'pending' END)::follow_state) ON CONFLICT (community, follower) DO UPDATE SET ap_id = COALESCE($4, community_follow.ap_id), state = (CASE WHEN community_follow.state = 'accepted' THEN 'accepted' WHEN community_follow.state = 'rejected' AND community_follow.ap_id = $4 THEN 'rejected' WHEN $5 THEN 'accepted' ELSE 'pending' END)::follow_state RETURNING state::TEXT, (SELECT state::TEXT FROM previous)",
+        &[&community, &follower, &local, &ap_id, &auto_accept],
+    ).await?;
+
+    Ok(FollowTransition {
+        current: parse_state(row.get(0))?,
+        previous: row.get::<_, Option<&str>>(1).map(parse_state).transpose()?,
+    })
+}
+
+/// Moves an existing follow to a new state, if that is a valid transition from its current one
+pub async fn transition_community_follow(
+    db: &tokio_postgres::Client,
+    community: CommunityLocalID,
+    follower: UserLocalID,
+    to: FollowState,
+) -> Result<Option<FollowInfo>, crate::Error> {
+    let sources = valid_sources(to);
+
+    let row = db.query_opt(
+        "UPDATE community_follow SET state=$3::TEXT::follow_state WHERE community=$1 AND follower=$2 AND state::TEXT = ANY($4::TEXT[]) RETURNING local, ap_id",
+        &[&community, &follower, &to.as_str(), &sources],
+    ).await?;
+
+    Ok(row.map(|row| FollowInfo {
+        local: row.get(0),
+        ap_id: row.get(1),
+    }))
+}
+
+pub async fn get_community_follow_state(
+    db: &tokio_postgres::Client,
+    community: CommunityLocalID,
+    follower: UserLocalID,
+) -> Result<Option<FollowState>, crate::Error> {
+    let row = db
+        .query_opt(
+            "SELECT state::TEXT FROM community_follow WHERE community=$1 AND follower=$2",
+            &[&community, &follower],
+        )
+        .await?;
+
+    row.map(|row| parse_state(row.get(0))).transpose()
+}
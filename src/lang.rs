@@ -1,35 +1,33 @@
 use std::borrow::Cow;
 
 pub struct Translator {
-    bundle: fluent::concurrent::FluentBundle<&'static fluent::FluentResource>,
+    /// Bundles in order of preference, ending with the default language
+    bundles: Vec<fluent::concurrent::FluentBundle<&'static fluent::FluentResource>>,
 }
 impl Translator {
     pub fn new(
-        bundle: fluent::concurrent::FluentBundle<&'static fluent::FluentResource>,
+        bundles: Vec<fluent::concurrent::FluentBundle<&'static fluent::FluentResource>>,
     ) -> Translator {
-        Translator { bundle }
+        Translator { bundles }
     }
 
     pub fn tr<'a>(&'a self, input: &'a (impl ToKeyAndArgs + 'a)) -> Cow<'a, str> {
         let (key, args) = input.to_key_and_args();
 
-        let mut errors = Vec::with_capacity(0);
-        let out = match self.bundle.get_message(key) {
-            Some(msg) => self.bundle.format_pattern(
-                msg.value.expect("Missing value for translation key"),
-                args,
-                &mut errors,
-            ),
-            None => {
-                log::error!("Missing translation for {}", key);
-                Cow::Borrowed(key)
+        for bundle in &self.bundles {
+            if let Some(value) = bundle.get_message(key).and_then(|msg| msg.value) {
+                let mut errors = Vec::with_capacity(0);
+                let out = bundle.format_pattern(value, args, &mut errors);
+                if !errors.is_empty() {
+                    log::error!("Errors in translation for {}: {:?}", key, errors);
+                }
+
+                return out;
             }
-        };
-        if !errors.is_empty() {
-            log::error!("Errors in translation: {:?}", errors);
         }
 
-        out
+        log::error!("Missing translation for {}", key);
+        Cow::Borrowed(key)
     }
 }
 
@@ -9,16 +9,22 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 mod apub_util;
+mod automod;
 mod config;
+mod follows;
 mod lang;
 mod migrate;
+mod outbound;
+mod passwords;
+mod query;
 mod routes;
 mod tasks;
 mod worker;
 
 use self::config::Config;
 use self::types::{
-    CommentLocalID, CommunityLocalID, NotificationID, PollOptionLocalID, PostLocalID, UserLocalID,
+    CommentLocalID, CommunityLocalID, NotificationID, PollOptionLocalID, PostLocalID, RespError,
+    UserLocalID,
 };
 
 pub use self::lang::Translator;
@@ -119,6 +125,10 @@ impl Pineapple {
         }
     }
 
+    pub fn from_int(value: i32) -> Self {
+        Self { value }
+    }
+
     pub fn as_int(&self) -> i32 {
         self.value
     }
@@ -146,15 +156,22 @@ impl std::str::FromStr for Pineapple {
 }
 
 pub type DbPool = deadpool_postgres::Pool;
-pub type HttpClient = hyper::Client<hyper_tls::HttpsConnector<hyper::client::HttpConnector>>;
+pub type HttpClient = hyper::Client<
+    hyper_tls::HttpsConnector<hyper::client::HttpConnector<outbound::FilteringResolver>>,
+>;
 
 pub struct BaseContext {
     pub db_pool: DbPool,
+    pub db_pool_read: Option<DbPool>,
     pub mailer: Option<lettre::AsyncSmtpTransport<lettre::Tokio1Executor>>,
     pub mail_from: Option<lettre::message::Mailbox>,
     pub host_url_api: String,
     pub host_url_apub: BaseURL,
+    pub frontend_url_pattern: Option<String>,
+    pub strip_href_query_params: Vec<String>,
+    pub trusted_proxies: Vec<IpRange>,
     pub http_client: HttpClient,
+    pub outbound_policy: Arc<outbound::AddressPolicy>,
     pub apub_proxy_rewrites: bool,
     pub media_storage: Option<MediaStorage>,
     pub api_ratelimit: henry::RatelimitBucket<std::net::IpAddr>,
@@ -162,10 +179,36 @@ pub struct BaseContext {
     pub vapid_signature_builder: web_push::PartialVapidSignatureBuilder,
     pub break_stuff: bool,
     pub dev_mode: bool,
+    pub markdown_options: MarkdownOptions,
+    pub sign_fetches: bool,
+    pub link_posts_as_notes: bool,
+    pub instance_actor_key: openssl::pkey::PKey<openssl::pkey::Private>,
 
     pub local_hostname: String,
 
+    pub actor_refresh_age: std::time::Duration,
+    pub login_max_age: Option<std::time::Duration>,
+    pub community_activity_window: std::time::Duration,
+    pub duplicate_post_window: std::time::Duration,
+    pub comment_edit_window: Option<std::time::Duration>,
+    pub max_replies_depth: u8,
+    pub max_comments_per_request: usize,
+    pub host_dead_after: std::time::Duration,
+    pub password_hash_params: argon2::Params,
+    pub max_request_body_size: usize,
+    pub max_import_body_size: usize,
+    pub fetch_timeout: std::time::Duration,
+    pub max_fetch_response_size: usize,
+
+    pub user_insights_cache:
+        std::sync::Mutex<HashMap<UserLocalID, (std::time::Instant, bytes::Bytes)>>,
+    /// Views not yet written to the database, `None` if view counting is disabled
+    pub post_view_counts: Option<std::sync::Mutex<HashMap<PostLocalID, i64>>>,
+    pub actor_cache: apub_util::ActorCache,
+    pub automod_regex_cache: automod::RegexCache,
+
     worker_trigger: tokio::sync::mpsc::Sender<()>,
+    stream_events: tokio::sync::broadcast::Sender<StreamEvent>,
 }
 
 impl BaseContext {
@@ -182,6 +225,38 @@ impl BaseContext {
         }
     }
 
+    /// Normalizes a link so that different forms of the same URL compare equal, dropping
+    /// `utm_*` and configured tracking parameters
+    pub fn canonicalize_href(&self, href: &str) -> String {
+        // parsing already lowercases the host and removes default ports
+        let mut url = match url::Url::parse(href) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            _ => return href.to_owned(),
+        };
+
+        if url.query().is_some() {
+            let params: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| {
+                    !key.starts_with("utm_")
+                        && !self
+                            .strip_href_query_params
+                            .iter()
+                            .any(|param| param == key)
+                })
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+
+            if params.is_empty() {
+                url.set_query(None);
+            } else if params.len() < url.query_pairs().count() {
+                url.query_pairs_mut().clear().extend_pairs(params);
+            }
+        }
+
+        url.into()
+    }
+
     pub fn process_href_opt<'a>(
         &self,
         href: Option<Cow<'a, str>>,
@@ -208,6 +283,38 @@ impl BaseContext {
         })
     }
 
+    pub fn process_post_attachment_href<'a>(
+        &self,
+        href: impl Into<Cow<'a, str>>,
+        post_id: PostLocalID,
+        idx: usize,
+    ) -> Cow<'a, str> {
+        let href = href.into();
+        if href.starts_with("local-media://") {
+            format!(
+                "{}/stable/posts/{}/attachments/{}/href",
+                self.host_url_api, post_id, idx
+            )
+            .into()
+        } else {
+            href
+        }
+    }
+
+    pub fn get_post_href_thumbnail_url(&self, post_id: PostLocalID) -> String {
+        format!(
+            "{}/stable/posts/{}/href/thumbnail",
+            self.host_url_api, post_id
+        )
+    }
+
+    pub fn get_post_attachment_thumbnail_url(&self, post_id: PostLocalID, idx: usize) -> String {
+        format!(
+            "{}/stable/posts/{}/attachments/{}/href/thumbnail",
+            self.host_url_api, post_id, idx
+        )
+    }
+
     pub fn process_avatar_href<'a>(
         &self,
         href: impl Into<Cow<'a, str>>,
@@ -221,16 +328,107 @@ impl BaseContext {
         }
     }
 
+    /// Gets a connection for read-only queries, using the read replica if one is configured and
+    /// reachable
+    pub async fn get_db_read(&self) -> Result<deadpool_postgres::Client, crate::Error> {
+        if let Some(db_pool_read) = &self.db_pool_read {
+            match db_pool_read.get().await {
+                Ok(db) => return Ok(db),
+                Err(err) => {
+                    log::warn!(
+                        "Failed to get read replica connection, falling back to primary: {:?}",
+                        err
+                    );
+                }
+            }
+        }
+
+        Ok(self.db_pool.get().await?)
+    }
+
     pub async fn enqueue_task<T: crate::tasks::TaskDef>(
         &self,
         task: &T,
     ) -> Result<(), crate::Error> {
         let db = self.db_pool.get().await?;
         db.execute(
-            "INSERT INTO task (kind, params, max_attempts, created_at) VALUES ($1, $2, $3, current_timestamp)",
-            &[&T::KIND, &tokio_postgres::types::Json(task), &T::MAX_ATTEMPTS],
+            "INSERT INTO task (kind, params, max_attempts, created_at, destination_host, priority) VALUES ($1, $2, $3, current_timestamp, $4, $5)",
+            &[&T::KIND, &tokio_postgres::types::Json(task), &T::MAX_ATTEMPTS, &task.destination_host(), &T::PRIORITY],
+        ).await?;
+
+        match self.worker_trigger.clone().try_send(()) {
+            Ok(_) | Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => Ok(()),
+            Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                Err(crate::Error::InternalStrStatic("Worker channel closed"))
+            }
+        }
+    }
+
+    /// Queues a task as part of a transaction, so it only runs if the transaction is committed.
+    ///
+    /// The worker isn't woken until `notify_worker` is called after committing.
+    pub async fn enqueue_task_in_transaction<T: crate::tasks::TaskDef>(
+        &self,
+        trans: &tokio_postgres::Transaction<'_>,
+        task: &T,
+    ) -> Result<(), crate::Error> {
+        trans.execute(
+            "INSERT INTO task (kind, params, max_attempts, created_at, destination_host, priority) VALUES ($1, $2, $3, current_timestamp, $4, $5)",
+            &[&T::KIND, &tokio_postgres::types::Json(task), &T::MAX_ATTEMPTS, &task.destination_host(), &T::PRIORITY],
+        ).await?;
+
+        Ok(())
+    }
+
+    pub fn notify_worker(&self) -> Result<(), crate::Error> {
+        match self.worker_trigger.clone().try_send(()) {
+            Ok(_) | Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => Ok(()),
+            Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                Err(crate::Error::InternalStrStatic("Worker channel closed"))
+            }
+        }
+    }
+
+    pub async fn enqueue_task_delayed<T: crate::tasks::TaskDef>(
+        &self,
+        task: &T,
+        delay: std::time::Duration,
+    ) -> Result<(), crate::Error> {
+        let db = self.db_pool.get().await?;
+        let delay = delay.as_secs_f64();
+        db.execute(
+            "INSERT INTO task (kind, params, max_attempts, created_at, run_after, destination_host, priority) VALUES ($1, $2, $3, current_timestamp, current_timestamp + make_interval(secs => $4), $5, $6)",
+            &[&T::KIND, &tokio_postgres::types::Json(task), &T::MAX_ATTEMPTS, &delay, &task.destination_host(), &T::PRIORITY],
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Queues a periodic task unless one of the same kind is already waiting or running, or
+    /// another process queued one less than `min_interval` ago
+    pub async fn enqueue_periodic_task<T: crate::tasks::TaskDef>(
+        &self,
+        task: &T,
+        min_interval: std::time::Duration,
+    ) -> Result<(), crate::Error> {
+        let mut db = self.db_pool.get().await?;
+        let trans = db.transaction().await?;
+
+        // the check and insert would race between processes without the lock
+        trans
+            .execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&T::KIND])
+            .await?;
+        let row_count = trans.execute(
+            "INSERT INTO task (kind, params, max_attempts, created_at, priority) SELECT $1, $2, $3, current_timestamp, $4 WHERE NOT EXISTS (SELECT 1 FROM task WHERE kind=$1 AND (state IN ('pending', 'running') OR created_at > current_timestamp - make_interval(secs => $5)))",
+            &[&T::KIND, &tokio_postgres::types::Json(task), &T::MAX_ATTEMPTS, &T::PRIORITY, &min_interval.as_secs_f64()],
         ).await?;
 
+        trans.commit().await?;
+
+        if row_count == 0 {
+            return Ok(());
+        }
+
         match self.worker_trigger.clone().try_send(()) {
             Ok(_) | Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => Ok(()),
             Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
@@ -239,6 +437,38 @@ impl BaseContext {
         }
     }
 
+    /// Counts a view of a post, to be written by the next flush
+    pub fn record_post_view(&self, post: PostLocalID) {
+        if let Some(counts) = &self.post_view_counts {
+            *counts.lock().unwrap().entry(post).or_insert(0) += 1;
+        }
+    }
+
+    pub async fn flush_post_views(&self) -> Result<(), crate::Error> {
+        let counts = match &self.post_view_counts {
+            Some(counts) => std::mem::take(&mut *counts.lock().unwrap()),
+            None => return Ok(()),
+        };
+
+        if counts.is_empty() {
+            return Ok(());
+        }
+
+        let (ids, views): (Vec<i64>, Vec<i64>) = counts
+            .into_iter()
+            .map(|(post, views)| (post.raw(), views))
+            .unzip();
+
+        let db = self.db_pool.get().await?;
+        db.execute(
+            "UPDATE post SET views = views + counts.views FROM UNNEST($1::BIGINT[], $2::BIGINT[]) AS counts (id, views) WHERE post.id = counts.id",
+            &[&ids, &views],
+        )
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn enqueue_tasks<T: crate::tasks::TaskDef>(
         &self,
         tasks: &[T],
@@ -246,10 +476,11 @@ impl BaseContext {
         let db = self.db_pool.get().await?;
 
         let tasks_param: Vec<_> = tasks.iter().map(tokio_postgres::types::Json).collect();
+        let hosts_param: Vec<_> = tasks.iter().map(|task| task.destination_host()).collect();
 
         db.execute(
-            "INSERT INTO task (kind, max_attempts, created_at, params) SELECT $1, $3, current_timestamp, * FROM UNNEST($2::JSON[])",
-            &[&T::KIND, &tasks_param, &T::MAX_ATTEMPTS],
+            "INSERT INTO task (kind, max_attempts, created_at, priority, params, destination_host) SELECT $1, $3, current_timestamp, $5, * FROM UNNEST($2::JSON[], $4::TEXT[])",
+            &[&T::KIND, &tasks_param, &T::MAX_ATTEMPTS, &hosts_param, &T::PRIORITY],
         ).await?;
 
         match self.worker_trigger.clone().try_send(()) {
@@ -259,6 +490,83 @@ impl BaseContext {
             }
         }
     }
+
+    /// Queues delivery of an event to every webhook subscribed to it
+    pub async fn enqueue_webhook_event(
+        &self,
+        event: crate::types::WebhookEvent,
+        data: serde_json::Value,
+    ) -> Result<(), crate::Error> {
+        let mut db = self.db_pool.get().await?;
+        let trans = db.transaction().await?;
+        self.enqueue_webhook_event_in_transaction(&trans, event, data)
+            .await?;
+        trans.commit().await?;
+
+        self.notify_worker()
+    }
+
+    /// Like `enqueue_webhook_event`, but only delivered if the transaction is committed.
+    ///
+    /// The worker isn't woken until `notify_worker` is called after committing.
+    pub async fn enqueue_webhook_event_in_transaction(
+        &self,
+        trans: &tokio_postgres::Transaction<'_>,
+        event: crate::types::WebhookEvent,
+        data: serde_json::Value,
+    ) -> Result<(), crate::Error> {
+        use crate::tasks::TaskDef;
+
+        let body = serde_json::to_string(&serde_json::json!({
+            "event": event.as_str(),
+            "created_at": chrono::Utc::now().to_rfc3339(),
+            "data": data,
+        }))?;
+
+        trans.execute(
+            "INSERT INTO task (kind, params, max_attempts, created_at, priority, destination_host) SELECT $1, json_build_object('webhook', id, 'body', $2::TEXT, 'destination_host', url_host_key(url)), $3, current_timestamp, $5, url_host_key(url) FROM webhook WHERE $4 = ANY(events)",
+            &[&crate::tasks::DeliverToWebhook::KIND, &body, &crate::tasks::DeliverToWebhook::MAX_ATTEMPTS, &event.as_str(), &crate::tasks::DeliverToWebhook::PRIORITY],
+        ).await?;
+
+        Ok(())
+    }
+
+    pub fn send_stream_event(&self, event: StreamEvent) {
+        // only fails if nobody is listening
+        let _ = self.stream_events.send(event);
+    }
+
+    pub fn subscribe_stream_events(&self) -> tokio::sync::broadcast::Receiver<StreamEvent> {
+        self.stream_events.subscribe()
+    }
+
+    /// Sends an outgoing request, giving up if no response arrives within the fetch timeout.
+    ///
+    /// GET requests are signed as the instance actor if SIGN_FETCHES is enabled.
+    pub async fn fetch(
+        &self,
+        mut req: hyper::Request<hyper::Body>,
+    ) -> Result<hyper::Response<hyper::Body>, Error> {
+        self.outbound_policy.check_uri(req.uri())?;
+
+        if self.sign_fetches && req.method() == hyper::Method::GET {
+            apub_util::sign_fetch_request(&mut req, self)?;
+        }
+
+        Ok(tokio::time::timeout(self.fetch_timeout, self.http_client.request(req)).await??)
+    }
+
+    /// Reads the body of a response to an outgoing request, within the fetch size limit and timeout
+    pub async fn read_fetched_body(
+        &self,
+        res: hyper::Response<hyper::Body>,
+    ) -> Result<bytes::Bytes, Error> {
+        tokio::time::timeout(
+            self.fetch_timeout,
+            to_bytes_limited(res.into_body(), self.max_fetch_response_size),
+        )
+        .await?
+    }
 }
 
 pub type RouteContext = BaseContext;
@@ -332,6 +640,49 @@ impl std::str::FromStr for TimestampOrLatest {
     }
 }
 
+/// Page key for listings ordered by time, with the ID breaking ties between items at the same time
+#[derive(Clone, Copy, Debug)]
+pub enum TimestampAndIDOrLatest {
+    Latest,
+    Before(chrono::DateTime<chrono::offset::FixedOffset>, i64),
+}
+
+impl std::fmt::Display for TimestampAndIDOrLatest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TimestampAndIDOrLatest::Latest => write!(f, "latest"),
+            TimestampAndIDOrLatest::Before(ts, id) => write!(f, "{}_{}", ts.timestamp_micros(), id),
+        }
+    }
+}
+
+impl std::str::FromStr for TimestampAndIDOrLatest {
+    type Err = TimestampOrLatestParseError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        if src == "latest" {
+            Ok(TimestampAndIDOrLatest::Latest)
+        } else {
+            use chrono::offset::TimeZone;
+
+            let (ts, id) = src
+                .split_once('_')
+                .ok_or(TimestampOrLatestParseError::Timestamp)?;
+            let ts: i64 = ts.parse().map_err(TimestampOrLatestParseError::Number)?;
+            let id = id.parse().map_err(TimestampOrLatestParseError::Number)?;
+
+            let ts = chrono::offset::Utc
+                .timestamp_opt(
+                    ts.div_euclid(1_000_000),
+                    (ts.rem_euclid(1_000_000) * 1000) as u32,
+                )
+                .single()
+                .ok_or(TimestampOrLatestParseError::Timestamp)?;
+            Ok(TimestampAndIDOrLatest::Before(ts.into(), id))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PostInfo<'a> {
     id: PostLocalID,
@@ -345,40 +696,22 @@ pub struct PostInfo<'a> {
     created: &'a chrono::DateTime<chrono::FixedOffset>,
     #[allow(dead_code)]
     community: CommunityLocalID,
+    community_private: bool,
+    community_link_posts_as_notes: bool,
     poll: Option<Cow<'a, PollInfo<'a>>>,
     sensitive: bool,
+    sticky: bool,
+    locked: bool,
+    attachments: Cow<'a, [PostAttachment]>,
+    flairs: Cow<'a, [String]>,
+    language: Option<&'a str>,
 }
 
-pub struct PostInfoOwned {
-    id: PostLocalID,
-    author: Option<UserLocalID>,
-    href: Option<String>,
-    content_text: Option<String>,
-    content_markdown: Option<String>,
-    content_html: Option<String>,
-    title: String,
-    created: chrono::DateTime<chrono::FixedOffset>,
-    community: CommunityLocalID,
-    poll: Option<PollInfoOwned>,
-    sensitive: bool,
-}
-
-impl<'a> From<&'a PostInfoOwned> for PostInfo<'a> {
-    fn from(src: &'a PostInfoOwned) -> PostInfo<'a> {
-        PostInfo {
-            id: src.id,
-            author: src.author,
-            href: src.href.as_deref(),
-            content_text: src.content_text.as_deref(),
-            content_markdown: src.content_markdown.as_deref(),
-            content_html: src.content_html.as_deref(),
-            title: &src.title,
-            created: &src.created,
-            community: src.community,
-            poll: src.poll.as_ref().map(|x| Cow::Owned(x.into())),
-            sensitive: src.sensitive,
-        }
-    }
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostAttachment {
+    url: String,
+    media_type: Option<String>,
+    alt_text: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -440,6 +773,7 @@ pub struct CommentInfo<'a> {
     content_markdown: Option<Cow<'a, str>>,
     content_html: Option<Cow<'a, str>>,
     created: chrono::DateTime<chrono::FixedOffset>,
+    updated: Option<chrono::DateTime<chrono::FixedOffset>>,
     ap_id: APIDOrLocal,
     attachment_href: Option<Cow<'a, str>>,
     sensitive: bool,
@@ -447,6 +781,8 @@ pub struct CommentInfo<'a> {
 
 pub const KEY_BITS: u32 = 2048;
 
+const STREAM_EVENTS_CAPACITY: usize = 256;
+
 pub fn get_url_host(url: &url::Url) -> Option<String> {
     url.host_str().map(|host| match url.port() {
         Some(port) => format!("{}:{}", host, port),
@@ -482,20 +818,6 @@ pub fn get_path_and_query(url: &url::Url) -> Result<String, url::ParseError> {
     Ok(format!("{}{}", url.path(), url.query().unwrap_or("")))
 }
 
-fn slice_iter<'a>(
-    s: &'a [&'a (dyn postgres_types::ToSql + Sync)],
-) -> impl ExactSizeIterator<Item = &'a dyn postgres_types::ToSql> + 'a {
-    s.iter().map(|s| *s as _)
-}
-
-pub async fn query_stream(
-    db: &tokio_postgres::Client,
-    statement: &(impl tokio_postgres::ToStatement + ?Sized),
-    params: ParamSlice<'_>,
-) -> Result<tokio_postgres::RowStream, tokio_postgres::Error> {
-    db.query_raw(statement, slice_iter(params)).await
-}
-
 pub fn common_response_builder() -> http::response::Builder {
     hyper::Response::builder().header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
 }
@@ -517,6 +839,32 @@ pub fn simple_response(
         .unwrap()
 }
 
+pub fn error_response(
+    code: hyper::StatusCode,
+    lang: &Translator,
+    key: &impl lang::ToKeyAndArgs,
+) -> hyper::Response<hyper::Body> {
+    let body = serde_json::to_vec(&RespError {
+        code: key.to_key_and_args().0,
+        message: lang.tr(key),
+    })
+    .unwrap();
+
+    common_response_builder()
+        .status(code)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .unwrap()
+}
+
+pub fn user_error(
+    code: hyper::StatusCode,
+    lang: &Translator,
+    key: &impl lang::ToKeyAndArgs,
+) -> Error {
+    Error::UserError(error_response(code, lang, key))
+}
+
 pub fn json_response(body: &impl serde::Serialize) -> Result<hyper::Response<hyper::Body>, Error> {
     let body = serde_json::to_vec(&body)?;
     Ok(common_response_builder()
@@ -524,13 +872,51 @@ pub fn json_response(body: &impl serde::Serialize) -> Result<hyper::Response<hyp
         .body(body.into())?)
 }
 
+#[derive(Debug)]
+pub struct BodyTooLarge;
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Body exceeded size limit")
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// Like hyper::body::to_bytes, but stops reading once the body is larger than `limit` bytes
+pub async fn to_bytes_limited(mut body: hyper::Body, limit: usize) -> Result<bytes::Bytes, Error> {
+    use hyper::body::HttpBody;
+
+    if body.size_hint().lower() > limit as u64 {
+        return Err(BodyTooLarge.into());
+    }
+
+    let mut buf = bytes::BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buf.len() + chunk.len() > limit {
+            return Err(BodyTooLarge.into());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+
+    Ok(buf.freeze())
+}
+
+const REMOTE_ERROR_BODY_LIMIT: usize = 64 * 1024;
+const REMOTE_ERROR_BODY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 pub async fn res_to_error(
     res: hyper::Response<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     if res.status().is_success() {
         Ok(res)
     } else {
-        let bytes = hyper::body::to_bytes(res.into_body()).await?;
+        let bytes = tokio::time::timeout(
+            REMOTE_ERROR_BODY_TIMEOUT,
+            to_bytes_limited(res.into_body(), REMOTE_ERROR_BODY_LIMIT),
+        )
+        .await??;
         Err(crate::Error::InternalStr(format!(
             "Error in remote response: {}",
             String::from_utf8_lossy(&bytes)
@@ -538,6 +924,172 @@ pub async fn res_to_error(
     }
 }
 
+/// An address or CIDR range, e.g. for trusted proxies
+#[derive(Clone, Copy, Debug)]
+pub struct IpRange {
+    addr: std::net::IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, addr: std::net::IpAddr) -> bool {
+        fn prefix_matches(net: u128, addr: u128, bits: u8, prefix_len: u8) -> bool {
+            if prefix_len == 0 {
+                true
+            } else {
+                let shift = bits - prefix_len;
+                (net >> shift) == (addr >> shift)
+            }
+        }
+
+        match (self.addr, unmap_ip(addr)) {
+            (std::net::IpAddr::V4(net), std::net::IpAddr::V4(addr)) => prefix_matches(
+                u32::from(net).into(),
+                u32::from(addr).into(),
+                32,
+                self.prefix_len,
+            ),
+            (std::net::IpAddr::V6(net), std::net::IpAddr::V6(addr)) => {
+                prefix_matches(net.into(), addr.into(), 128, self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpRange {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<Self, ()> {
+        let mut spl = src.splitn(2, '/');
+        let addr: std::net::IpAddr = spl.next().unwrap().parse().map_err(|_| ())?;
+        let addr = unmap_ip(addr);
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match spl.next() {
+            None => max_len,
+            Some(len) => len.parse().map_err(|_| ())?,
+        };
+
+        if prefix_len > max_len {
+            return Err(());
+        }
+
+        Ok(IpRange { addr, prefix_len })
+    }
+}
+
+/// Client address attached to each request, as determined from the connection and any trusted
+/// forwarding headers
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIP(pub std::net::IpAddr);
+
+pub fn get_client_ip<T>(req: &hyper::Request<T>) -> Option<std::net::IpAddr> {
+    req.extensions().get::<ClientIP>().map(|x| x.0)
+}
+
+/// Since the server listens on IPv6, IPv4 clients show up as IPv4-mapped addresses
+fn unmap_ip(addr: std::net::IpAddr) -> std::net::IpAddr {
+    match addr {
+        std::net::IpAddr::V6(addr) => match addr.segments() {
+            [0, 0, 0, 0, 0, 0xffff, high, low] => std::net::IpAddr::V4(std::net::Ipv4Addr::new(
+                (high >> 8) as u8,
+                high as u8,
+                (low >> 8) as u8,
+                low as u8,
+            )),
+            _ => std::net::IpAddr::V6(addr),
+        },
+        addr => addr,
+    }
+}
+
+/// Nodes that aren't addresses, such as `unknown` or obfuscated identifiers, are `None`
+fn parse_forwarded_header(value: &str) -> Vec<Option<std::net::IpAddr>> {
+    value
+        .split(',')
+        .map(|element| {
+            let node = element
+                .split(';')
+                .filter_map(|pair| {
+                    let mut spl = pair.trim().splitn(2, '=');
+                    let key = spl.next()?;
+                    let value = spl.next()?;
+                    if key.eq_ignore_ascii_case("for") {
+                        Some(value)
+                    } else {
+                        None
+                    }
+                })
+                .next()?;
+
+            let node = node.trim_matches('"');
+            // strip the port, which IPv6 addresses can only have inside brackets
+            let node = match node.strip_prefix('[') {
+                Some(rest) => rest.split(']').next().unwrap(),
+                None => node.split(':').next().unwrap(),
+            };
+
+            node.parse().ok()
+        })
+        .collect()
+}
+
+/// Determines the client address, only reading forwarding headers from trusted proxies (or from
+/// anyone if `allow_forwarded` is set).
+///
+/// Returns `Ok(None)` if forwarding headers are trusted from anyone but none were sent.
+fn resolve_client_ip(
+    addr_direct: std::net::IpAddr,
+    headers: &hyper::HeaderMap<hyper::header::HeaderValue>,
+    allow_forwarded: bool,
+    trusted_proxies: &[IpRange],
+) -> Result<Option<std::net::IpAddr>, ()> {
+    let addr_direct = unmap_ip(addr_direct);
+    let is_trusted = |addr| trusted_proxies.iter().any(|proxy| proxy.contains(addr));
+
+    let from_trusted = is_trusted(addr_direct);
+    if !allow_forwarded && !from_trusted {
+        return Ok(Some(addr_direct));
+    }
+
+    let chain: Vec<Option<std::net::IpAddr>> =
+        if let Some(value) = headers.get(hyper::header::FORWARDED) {
+            parse_forwarded_header(value.to_str().map_err(|_| ())?)
+        } else if let Some(value) =
+            headers.get(hyper::header::HeaderName::from_static("x-forwarded-for"))
+        {
+            value
+                .to_str()
+                .map_err(|_| ())?
+                .split(',')
+                .map(|addr| addr.trim().parse().ok())
+                .collect()
+        } else {
+            return Ok(if from_trusted {
+                Some(addr_direct)
+            } else {
+                None
+            });
+        };
+
+    // if forwarding headers are trusted from anyone, so is every hop they list
+    let trust_all = !from_trusted;
+
+    // each proxy appends the address it received from, so the nearest untrusted one is the client
+    let mut last_trusted = addr_direct;
+    for node in chain.into_iter().rev() {
+        match node.map(unmap_ip) {
+            Some(addr) if trust_all || is_trusted(addr) => last_trusted = addr,
+            Some(addr) => return Ok(Some(addr)),
+            // a hop without an address can't be checked, so nothing before it is trusted
+            None => break,
+        }
+    }
+
+    Ok(Some(last_trusted))
+}
+
 pub trait ReqParts {
     fn headers(&self) -> &hyper::HeaderMap<hyper::header::HeaderValue>;
 }
@@ -554,23 +1106,84 @@ impl ReqParts for http::request::Parts {
     }
 }
 
-lazy_static::lazy_static! {
-    static ref LANG_MAP: HashMap<unic_langid::LanguageIdentifier, fluent::FluentResource> = {
-        let mut result = HashMap::new();
+static DEFAULT_LANG: unic_langid::LanguageIdentifier = unic_langid::langid!("en");
 
-        result.insert(unic_langid::langid!("de"), fluent::FluentResource::try_new(include_str!("../res/lang/de.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("en"), fluent::FluentResource::try_new(include_str!("../res/lang/en.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("eo"), fluent::FluentResource::try_new(include_str!("../res/lang/eo.ftl").to_owned()).expect("Failed to parse translation"));
-        result.insert(unic_langid::langid!("fr"), fluent::FluentResource::try_new(include_str!("../res/lang/fr.ftl").to_owned()).expect("Failed to parse translation"));
+struct LangResources {
+    /// Resources for each language, in order of priority
+    map: HashMap<unic_langid::LanguageIdentifier, Vec<&'static fluent::FluentResource>>,
+    available: Vec<unic_langid::LanguageIdentifier>,
+}
 
-        result
-    };
+impl LangResources {
+    fn add(&mut self, lang: unic_langid::LanguageIdentifier, res: fluent::FluentResource) {
+        let res: &'static fluent::FluentResource = Box::leak(Box::new(res));
+
+        match self.map.entry(lang) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                entry.get_mut().insert(0, res);
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                self.available.push(entry.key().clone());
+                entry.insert(vec![res]);
+            }
+        }
+    }
+}
 
-    static ref LANGS: Vec<unic_langid::LanguageIdentifier> = {
-        LANG_MAP.keys().cloned().collect()
+lazy_static::lazy_static! {
+    static ref LANG_RESOURCES: std::sync::RwLock<LangResources> = {
+        let mut result = LangResources {
+            map: HashMap::new(),
+            available: Vec::new(),
+        };
+
+        for (lang, src) in &[
+            (unic_langid::langid!("de"), include_str!("../res/lang/de.ftl")),
+            (DEFAULT_LANG.clone(), include_str!("../res/lang/en.ftl")),
+            (unic_langid::langid!("eo"), include_str!("../res/lang/eo.ftl")),
+            (unic_langid::langid!("fr"), include_str!("../res/lang/fr.ftl")),
+        ] {
+            result.add(lang.clone(), fluent::FluentResource::try_new((*src).to_owned()).expect("Failed to parse translation"));
+        }
+
+        std::sync::RwLock::new(result)
     };
 }
 
+/// Loads additional translations from .ftl files named by language, e.g. `nl.ftl`.
+///
+/// Messages in these files take precedence over the built-in translations for the same language.
+pub fn load_lang_dir(path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut loaded = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.extension() != Some(std::ffi::OsStr::new("ftl")) {
+            continue;
+        }
+
+        let lang: unic_langid::LanguageIdentifier = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("Invalid translation file name: {}", path.display()))?
+            .parse()
+            .map_err(|err| format!("Invalid language for {}: {}", path.display(), err))?;
+
+        let res = fluent::FluentResource::try_new(std::fs::read_to_string(&path)?)
+            .map_err(|(_, errors)| format!("Failed to parse {}: {:?}", path.display(), errors))?;
+
+        loaded.push((lang, res));
+    }
+
+    let mut resources = LANG_RESOURCES.write().unwrap();
+    for (lang, res) in loaded {
+        log::debug!("Loaded translations for {}", lang);
+        resources.add(lang, res);
+    }
+
+    Ok(())
+}
+
 pub fn get_lang_for_req(req: &impl ReqParts) -> Translator {
     get_lang_for_header(
         req.headers()
@@ -580,36 +1193,50 @@ pub fn get_lang_for_req(req: &impl ReqParts) -> Translator {
 }
 
 pub fn get_lang_for_header(accept_language: Option<&str>) -> Translator {
-    let default = unic_langid::langid!("en");
-    let languages = match accept_language {
+    let resources = LANG_RESOURCES.read().unwrap();
+
+    let mut languages = match accept_language {
         Some(accept_language) => {
             let requested = fluent_langneg::accepted_languages::parse(accept_language);
             fluent_langneg::negotiate_languages(
                 &requested,
-                &LANGS,
-                Some(&default),
+                &resources.available,
+                Some(&DEFAULT_LANG),
                 fluent_langneg::NegotiationStrategy::Filtering,
             )
         }
-        None => vec![&default],
+        None => vec![&DEFAULT_LANG],
     };
+    if !languages.contains(&&DEFAULT_LANG) {
+        languages.push(&DEFAULT_LANG);
+    }
 
-    let mut bundle = fluent::concurrent::FluentBundle::new(languages.iter().copied());
-    for lang in languages {
-        if let Err(errors) = bundle.add_resource(&LANG_MAP[lang]) {
-            for err in errors {
-                match err {
-                    fluent::FluentError::Overriding { .. } => {}
-                    _ => {
-                        log::error!("Failed to add language resource: {:?}", err);
-                        break;
+    // one bundle per language, so plural rules always match the language of the message
+    let bundles = languages
+        .into_iter()
+        .filter_map(|lang| {
+            let lang_resources = resources.map.get(lang)?;
+
+            let mut bundle = fluent::concurrent::FluentBundle::new(std::iter::once(lang));
+            for res in lang_resources {
+                if let Err(errors) = bundle.add_resource(*res) {
+                    for err in errors {
+                        match err {
+                            fluent::FluentError::Overriding { .. } => {}
+                            _ => {
+                                log::error!("Failed to add language resource: {:?}", err);
+                                break;
+                            }
+                        }
                     }
                 }
             }
-        }
-    }
 
-    Translator::new(bundle)
+            Some(bundle)
+        })
+        .collect();
+
+    Translator::new(bundles)
 }
 
 pub fn get_auth_token(req: &impl ReqParts) -> Option<uuid::Uuid> {
@@ -654,10 +1281,11 @@ pub async fn require_login(
     db: &tokio_postgres::Client,
 ) -> Result<UserLocalID, Error> {
     authenticate(req, db).await?.ok_or_else(|| {
-        Error::UserError(simple_response(
+        user_error(
             hyper::StatusCode::UNAUTHORIZED,
-            "Login Required",
-        ))
+            &get_lang_for_req(req),
+            &lang::login_required(),
+        )
     })
 }
 
@@ -681,6 +1309,56 @@ pub async fn is_local_user(db: &tokio_postgres::Client, user: UserLocalID) -> Re
     })
 }
 
+/// SQL condition for whether content in `community` is visible to the user in the given
+/// parameter, or to anonymous users if `None`
+pub fn community_visible_sql(user_idx: Option<usize>) -> String {
+    match user_idx {
+        None => "NOT community.private".to_owned(),
+        Some(idx) => format!(
+            "(NOT community.private OR EXISTS(SELECT 1 FROM community_follow WHERE community=community.id AND follower=${0} AND accepted) OR EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=${0}) OR EXISTS(SELECT 1 FROM person WHERE id=${0} AND is_site_admin))",
+            idx
+        ),
+    }
+}
+
+/// Private communities only show their content to approved followers, moderators, and admins
+pub async fn can_view_community(
+    db: &tokio_postgres::Client,
+    user: Option<UserLocalID>,
+    community: CommunityLocalID,
+) -> Result<bool, Error> {
+    let sql: &str = &format!(
+        "SELECT {} FROM community WHERE id=$1",
+        community_visible_sql(user.as_ref().map(|_| 2))
+    );
+
+    let row = match &user {
+        None => db.query_opt(sql, &[&community]).await?,
+        Some(user) => db.query_opt(sql, &[&community, user]).await?,
+    };
+
+    Ok(match row {
+        None => true,
+        Some(row) => row.get(0),
+    })
+}
+
+/// Local communities can limit posting to approved followers and moderators
+pub async fn can_post_in_community(
+    db: &tokio_postgres::Client,
+    user: UserLocalID,
+    community: CommunityLocalID,
+) -> Result<bool, Error> {
+    let row = db.query_opt(
+        "SELECT NOT local OR NOT members_only_posting OR EXISTS(SELECT 1 FROM community_follow WHERE community=community.id AND follower=$2 AND accepted) OR EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$2) FROM community WHERE id=$1",
+        &[&community, &user],
+    ).await?;
+    Ok(match row {
+        None => true,
+        Some(row) => row.get(0),
+    })
+}
+
 pub fn spawn_task<F: std::future::Future<Output = Result<(), Error>> + Send + 'static>(task: F) {
     use futures::future::TryFutureExt;
     tokio::spawn(task.map_err(|err| {
@@ -688,22 +1366,256 @@ pub fn spawn_task<F: std::future::Future<Output = Result<(), Error>> + Send + 's
     }));
 }
 
-pub fn render_markdown(src: &str) -> String {
-    let parser = pulldown_cmark::Parser::new(src);
+#[derive(Clone, Copy, Debug)]
+pub struct MarkdownOptions {
+    pub tables: bool,
+    pub strikethrough: bool,
+    pub autolink: bool,
+}
+
+pub fn render_markdown(src: &str, options: MarkdownOptions) -> String {
+    let mut parser_options = pulldown_cmark::Options::empty();
+    if options.tables {
+        parser_options.insert(pulldown_cmark::Options::ENABLE_TABLES);
+    }
+    if options.strikethrough {
+        parser_options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+    }
 
-    let stream = pdcm_linkify::AutoLinker::new(parser);
+    let parser = pulldown_cmark::Parser::new_ext(src, parser_options);
 
     let mut output = String::new();
-    pulldown_cmark::html::push_html(&mut output, stream);
+    if options.autolink {
+        pulldown_cmark::html::push_html(&mut output, pdcm_linkify::AutoLinker::new(parser));
+    } else {
+        pulldown_cmark::html::push_html(&mut output, parser);
+    }
+
+    // markdown allows inline HTML, so the output needs the same treatment as remote content
+    clean_html(&output)
+}
+
+const MAX_HASHTAG_LENGTH: usize = 64;
+
+fn is_hashtag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Validates and lowercases a hashtag name, which may include the leading `#`
+pub fn normalize_hashtag(src: &str) -> Option<String> {
+    let src = src.strip_prefix('#').unwrap_or(src);
+
+    if src.is_empty()
+        || src.chars().count() > MAX_HASHTAG_LENGTH
+        || !src.chars().all(is_hashtag_char)
+        || src.chars().all(|c| c.is_ascii_digit())
+    {
+        None
+    } else {
+        Some(src.to_lowercase())
+    }
+}
+
+/// Reduces a BCP 47 language tag to its lowercased primary subtag, e.g. `en-US` to `en`
+pub fn normalize_language(src: &str) -> Option<String> {
+    let primary = src.split(|c| c == '-' || c == '_').next().unwrap();
+
+    if (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(primary.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+const MAX_REACTION_LENGTH: usize = 32;
+
+/// Validates an emoji reaction, either an emoji sequence or a custom emoji `:shortcode:`
+pub fn normalize_reaction(src: &str) -> Option<String> {
+    let src = src.trim();
+
+    if src.is_empty() || src.chars().count() > MAX_REACTION_LENGTH {
+        return None;
+    }
+
+    let is_shortcode = src.len() > 2
+        && src.starts_with(':')
+        && src.ends_with(':')
+        && src[1..(src.len() - 1)]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    // ASCII is only found in emoji as part of keycap sequences
+    let is_emoji = src.chars().any(|c| !c.is_ascii())
+        && src.chars().all(|c| {
+            c.is_ascii_digit()
+                || c == '#'
+                || c == '*'
+                || !(c.is_ascii() || c.is_alphanumeric() || c.is_whitespace() || c.is_control())
+        });
+
+    if is_shortcode || is_emoji {
+        Some(src.to_owned())
+    } else {
+        None
+    }
+}
+
+fn extract_hashtags_from_text(src: &str, output: &mut Vec<String>) {
+    let mut prev = None;
+    let mut chars = src.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        // avoid matching things like URL fragments and HTML entities
+        let at_boundary = match prev {
+            None => true,
+            Some(prev) => !(is_hashtag_char(prev) || prev == '#' || prev == '&' || prev == '/'),
+        };
+        prev = Some(c);
+
+        if c != '#' || !at_boundary {
+            continue;
+        }
+
+        let start = idx + 1;
+        let mut end = start;
+        while let Some(&(idx, c)) = chars.peek() {
+            if !is_hashtag_char(c) {
+                break;
+            }
+
+            end = idx + c.len_utf8();
+            prev = Some(c);
+            chars.next();
+        }
+
+        if let Some(tag) = normalize_hashtag(&src[start..end]) {
+            if !output.contains(&tag) {
+                output.push(tag);
+            }
+        }
+    }
+}
+
+/// Finds hashtags in post or comment content, skipping code and links in markdown
+pub fn extract_hashtags(content_text: Option<&str>, content_markdown: Option<&str>) -> Vec<String> {
+    use pulldown_cmark::{Event, Tag};
+
+    let mut output = Vec::new();
+
+    if let Some(text) = content_text {
+        extract_hashtags_from_text(text, &mut output);
+    }
+
+    if let Some(markdown) = content_markdown {
+        let mut skip_depth = 0;
+        let mut text = String::new();
+
+        for event in pulldown_cmark::Parser::new(markdown) {
+            if let Event::Text(value) = &event {
+                if skip_depth == 0 {
+                    text.push_str(value);
+                }
+                continue;
+            }
+
+            // text may be split into multiple events
+            extract_hashtags_from_text(&text, &mut output);
+            text.clear();
+
+            match event {
+                Event::Start(Tag::CodeBlock(_))
+                | Event::Start(Tag::Link(..))
+                | Event::Start(Tag::Image(..)) => skip_depth += 1,
+                Event::End(Tag::CodeBlock(_))
+                | Event::End(Tag::Link(..))
+                | Event::End(Tag::Image(..)) => skip_depth -= 1,
+                _ => {}
+            }
+        }
+
+        extract_hashtags_from_text(&text, &mut output);
+    }
 
     output
 }
 
+async fn set_hashtags(
+    link_table: &str,
+    link_column: &str,
+    id: i64,
+    hashtags: &[String],
+    db: &tokio_postgres::Client,
+) -> Result<(), Error> {
+    db.execute(
+        format!("DELETE FROM {} WHERE {}=$1", link_table, link_column).as_str(),
+        &[&id],
+    )
+    .await?;
+
+    if hashtags.is_empty() {
+        return Ok(());
+    }
+
+    db.execute(
+        "INSERT INTO hashtag (name) SELECT * FROM UNNEST($1::TEXT[]) ON CONFLICT (name) DO NOTHING",
+        &[&hashtags],
+    )
+    .await?;
+
+    db.execute(
+        format!(
+            "INSERT INTO {} ({}, hashtag) SELECT $1, id FROM hashtag WHERE name = ANY($2::TEXT[]) ON CONFLICT DO NOTHING",
+            link_table, link_column,
+        )
+        .as_str(),
+        &[&id, &hashtags],
+    )
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_post_hashtags(
+    post: PostLocalID,
+    hashtags: &[String],
+    db: &tokio_postgres::Client,
+) -> Result<(), Error> {
+    set_hashtags("post_hashtag", "post", post.raw(), hashtags, db).await
+}
+
+pub async fn set_comment_hashtags(
+    comment: CommentLocalID,
+    hashtags: &[String],
+    db: &tokio_postgres::Client,
+) -> Result<(), Error> {
+    set_hashtags("reply_hashtag", "reply", comment.raw(), hashtags, db).await
+}
+
 lazy_static::lazy_static! {
     static ref SANITIZER: ammonia::Builder<'static> = {
+        fn set(items: &[&'static str]) -> HashSet<&'static str> {
+            items.iter().copied().collect()
+        }
+
         let mut builder = ammonia::Builder::default();
+        builder.tags(set(&[
+            "a", "b", "blockquote", "br", "code", "del", "em", "h1", "h2", "h3", "h4", "h5", "h6",
+            "hr", "i", "li", "ol", "p", "pre", "s", "span", "strong", "sub", "sup", "table",
+            "tbody", "td", "th", "thead", "tr", "u", "ul",
+        ]));
+        builder.generic_attributes(set(&["title"]));
+        builder.tag_attributes(
+            vec![
+                ("a", set(&["href"])),
+                ("ol", set(&["start"])),
+                ("td", set(&["align"])),
+                ("th", set(&["align"])),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        builder.url_schemes(set(&["http", "https", "mailto"]));
         builder.link_rel(Some("ugc noopener"));
-        builder.rm_tags(&["img"]);
 
         builder
     };
@@ -713,6 +1625,29 @@ pub fn clean_html(src: &str) -> String {
     SANITIZER.clean(src).to_string()
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum StreamEvent {
+    NewPost {
+        community: CommunityLocalID,
+        post: PostLocalID,
+    },
+    NewNotification {
+        user: UserLocalID,
+        notification: NotificationID,
+    },
+}
+
+pub fn on_community_add_post(
+    community: CommunityLocalID,
+    post_local_id: PostLocalID,
+    ctx: &crate::RouteContext,
+) {
+    ctx.send_stream_event(StreamEvent::NewPost {
+        community,
+        post: post_local_id,
+    });
+}
+
 pub fn on_local_community_add_post(
     community: CommunityLocalID,
     post_local_id: PostLocalID,
@@ -720,7 +1655,12 @@ pub fn on_local_community_add_post(
     ctx: Arc<crate::RouteContext>,
 ) {
     log::debug!("on_community_add_post");
-    crate::apub_util::spawn_announce_community_post(community, post_local_id, post_ap_id, ctx);
+    crate::apub_util::spawn_enqueue_announce_community_post(
+        community,
+        post_local_id,
+        post_ap_id,
+        ctx,
+    );
 }
 
 pub fn on_local_community_add_comment(
@@ -729,7 +1669,7 @@ pub fn on_local_community_add_comment(
     comment_ap_id: url::Url,
     ctx: Arc<crate::RouteContext>,
 ) {
-    crate::apub_util::spawn_announce_community_comment(
+    crate::apub_util::spawn_enqueue_announce_community_comment(
         community,
         comment_local_id,
         comment_ap_id,
@@ -746,8 +1686,8 @@ pub fn on_post_add_comment(comment: CommentInfo<'static>, ctx: Arc<crate::RouteC
 
         let res = futures::future::try_join(
             db.query_opt(
-                "SELECT community.id, community.local, community.ap_id, community.ap_inbox, post.local, post.ap_id, person.id, person.ap_id, COALESCE(person.ap_shared_inbox, person.ap_inbox) FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.id = $1 AND post.community = community.id",
-                &[&comment.post.raw()],
+                "SELECT community.id, community.local, community.ap_id, community.ap_inbox, post.local, post.ap_id, person.id, person.ap_id, COALESCE(person.ap_shared_inbox, person.ap_inbox), community.private FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.id = $1 AND post.community = community.id",
+                &[&comment.post],
             )
             .map_err(crate::Error::from),
             async {
@@ -859,16 +1799,25 @@ pub fn on_post_add_comment(comment: CommentInfo<'static>, ctx: Arc<crate::RouteC
                             if let Some(parent_author_id) = parent_author_id {
                                 let ctx = ctx.clone();
                                 let comment_id = comment.id;
+                                let comment_post = comment.post;
                                 crate::spawn_task(async move {
                                     let db = ctx.db_pool.get().await?;
-                                    let row = db.query_one(
-                                        "INSERT INTO notification (kind, created_at, to_user, reply, parent_reply) VALUES ('reply_reply', current_timestamp, $1, $2, $3) RETURNING id",
-                                        &[&parent_author_id, &comment_id.raw(), &parent_id.raw()],
+                                    // skipped if disabled in preferences, or if the post or any comment in the thread is muted
+                                    let row = db.query_opt(
+                                        "INSERT INTO notification (kind, created_at, to_user, reply, parent_reply) SELECT 'reply_reply', current_timestamp, $1, $2, $3 WHERE NOT EXISTS(SELECT 1 FROM person WHERE id=$1 AND preferences->>'notify_comment_replies' = 'false') AND NOT EXISTS(SELECT 1 FROM post_mute WHERE person=$1 AND post=$4) AND NOT EXISTS(WITH RECURSIVE thread(id, parent) AS (SELECT id, parent FROM reply WHERE id=$3 UNION SELECT reply.id, reply.parent FROM reply, thread WHERE reply.id = thread.parent) SELECT 1 FROM reply_mute WHERE person=$1 AND reply IN (SELECT id FROM thread)) RETURNING id",
+                                        &[&parent_author_id, &comment_id, &parent_id, &comment_post],
                                     ).await?;
-                                    ctx.enqueue_task(&tasks::SendNotification {
-                                        notification: NotificationID(row.get(0)),
-                                    })
-                                    .await?;
+                                    let row = match row {
+                                        Some(row) => row,
+                                        None => return Ok(()),
+                                    };
+                                    let notification = NotificationID(row.get(0));
+                                    ctx.send_stream_event(StreamEvent::NewNotification {
+                                        user: parent_author_id,
+                                        notification,
+                                    });
+                                    ctx.enqueue_task(&tasks::SendNotification { notification })
+                                        .await?;
 
                                     Ok(())
                                 });
@@ -885,14 +1834,21 @@ pub fn on_post_add_comment(comment: CommentInfo<'static>, ctx: Arc<crate::RouteC
                             let comment_post = comment.post;
                             crate::spawn_task(async move {
                                 let db = ctx.db_pool.get().await?;
-                                let row = db.query_one(
-                                    "INSERT INTO notification (kind, created_at, to_user, reply, parent_post) VALUES ('post_reply', current_timestamp, $1, $2, $3) RETURNING id",
-                                    &[&post_or_parent_author_local_id.raw(), &comment_id.raw(), &comment_post.raw()],
+                                let row = db.query_opt(
+                                    "INSERT INTO notification (kind, created_at, to_user, reply, parent_post) SELECT 'post_reply', current_timestamp, $1, $2, $3 WHERE NOT EXISTS(SELECT 1 FROM person WHERE id=$1 AND preferences->>'notify_post_replies' = 'false') AND NOT EXISTS(SELECT 1 FROM post_mute WHERE person=$1 AND post=$3) RETURNING id",
+                                    &[&post_or_parent_author_local_id, &comment_id, &comment_post],
                                 ).await?;
-                                ctx.enqueue_task(&tasks::SendNotification {
-                                    notification: NotificationID(row.get(0)),
-                                })
-                                .await?;
+                                let row = match row {
+                                    Some(row) => row,
+                                    None => return Ok(()),
+                                };
+                                let notification = NotificationID(row.get(0));
+                                ctx.send_stream_event(StreamEvent::NewNotification {
+                                    user: post_or_parent_author_local_id,
+                                    notification,
+                                });
+                                ctx.enqueue_task(&tasks::SendNotification { notification })
+                                    .await?;
 
                                 Ok(())
                             });
@@ -927,25 +1883,24 @@ pub fn on_post_add_comment(comment: CommentInfo<'static>, ctx: Arc<crate::RouteC
                         }
                     }
 
-                    if !inboxes.is_empty() {
-                        let community_ap_id = if community_local {
-                            apub_util::LocalObjectRef::Community(community_id)
-                                .to_local_uri(&ctx.host_url_apub)
-                                .into()
-                        } else {
-                            std::str::FromStr::from_str(post_row.get(2))?
-                        };
+                    let community_ap_id = if community_local {
+                        apub_util::LocalObjectRef::Community(community_id)
+                            .to_local_uri(&ctx.host_url_apub)
+                            .into()
+                    } else {
+                        std::str::FromStr::from_str(post_row.get(2))?
+                    };
 
-                        crate::apub_util::spawn_enqueue_send_comment(
-                            inboxes,
-                            comment,
-                            community_ap_id,
-                            post_ap_id.into(),
-                            parent_ap_id.map(|x| x.deref().clone()),
-                            post_or_parent_author_ap_id.map(|x| x.into_owned().into()),
-                            ctx,
-                        );
-                    }
+                    crate::apub_util::spawn_enqueue_send_comment(
+                        inboxes,
+                        comment,
+                        community_ap_id,
+                        post_row.get(9),
+                        post_ap_id.into(),
+                        parent_ap_id.map(|x| x.deref().clone()),
+                        post_or_parent_author_ap_id.map(|x| x.into_owned().into()),
+                        ctx,
+                    );
                 }
             }
         }
@@ -997,6 +1952,29 @@ impl MediaStorage {
         }
     }
 
+    pub async fn delete(&self, path: &str) -> Result<(), crate::Error> {
+        match self {
+            MediaStorage::Local(root) => match tokio::fs::remove_file(root.join(path)).await {
+                Ok(()) => Ok(()),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(err) => Err(err.into()),
+            },
+            MediaStorage::S3 { client, bucket } => {
+                use rusoto_s3::S3;
+
+                client
+                    .delete_object(rusoto_s3::DeleteObjectRequest {
+                        bucket: bucket.clone(),
+                        key: path.to_owned(),
+                        ..Default::default()
+                    })
+                    .await?;
+
+                Ok(())
+            }
+        }
+    }
+
     pub async fn save(
         &self,
         src: impl Stream<Item = Result<bytes::Bytes, std::io::Error>> + Send + 'static,
@@ -1177,25 +2155,105 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .get_matches();
 
-    let config = Config::load(matches.value_of_os("config")).expect("Failed to load config");
+    let config = match Config::load(matches.value_of_os("config")) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to load config: {}", err);
+            eprintln!("See doc/INSTALL.md for the required settings");
+            std::process::exit(1);
+        }
+    };
 
     if let Some(matches) = matches.subcommand_matches("migrate") {
         crate::migrate::run(config, matches);
         Ok(())
     } else {
+        if let Err(problems) = config.validate() {
+            eprintln!("Invalid configuration:");
+            for problem in problems {
+                eprintln!(" - {}", problem);
+            }
+            std::process::exit(1);
+        }
+
         run(config)
     }
 }
 
+fn create_db_pool(
+    database_url: &str,
+    config: &Config,
+) -> Result<DbPool, Box<dyn std::error::Error>> {
+    let mut db_cfg: tokio_postgres::Config = database_url.parse()?;
+
+    if let Some(seconds) = config.database_connect_timeout_seconds {
+        db_cfg.connect_timeout(std::time::Duration::from_secs(seconds));
+    }
+    if let Some(seconds) = config.database_statement_timeout_seconds {
+        db_cfg.options(&format!("-c statement_timeout={}s", seconds));
+    }
+
+    let pool_cfg = deadpool_postgres::PoolConfig {
+        max_size: config.database_pool_size,
+        timeouts: deadpool_postgres::Timeouts {
+            wait: config
+                .database_pool_timeout_seconds
+                .map(std::time::Duration::from_secs),
+            ..Default::default()
+        },
+    };
+
+    // Only verify certificates when TLS was explicitly requested, since the default sslmode of
+    // prefer would otherwise break servers with self-signed certificates
+    let use_tls = config.database_tls_root_cert.is_some()
+        || matches!(
+            db_cfg.get_ssl_mode(),
+            tokio_postgres::config::SslMode::Require
+        );
+
+    Ok(if use_tls {
+        let mut builder = openssl::ssl::SslConnector::builder(openssl::ssl::SslMethod::tls())?;
+        if let Some(path) = &config.database_tls_root_cert {
+            builder.set_ca_file(path)?;
+        }
+
+        deadpool_postgres::Pool::from_config(
+            deadpool_postgres::Manager::new(
+                db_cfg,
+                postgres_openssl::MakeTlsConnector::new(builder.build()),
+            ),
+            pool_cfg,
+        )
+    } else {
+        deadpool_postgres::Pool::from_config(
+            deadpool_postgres::Manager::new(db_cfg, tokio_postgres::NoTls),
+            pool_cfg,
+        )
+    })
+}
+
 #[tokio::main]
 async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
-    let db_pool = deadpool_postgres::Pool::new(
-        deadpool_postgres::Manager::new(
-            config.database_url.parse().unwrap(),
-            tokio_postgres::NoTls,
-        ),
-        16,
-    );
+    if let Some(path) = &config.lang_dir {
+        if let Err(err) = load_lang_dir(path.as_ref()) {
+            return Err(format!("Failed to load translations from LANG_DIR: {}", err).into());
+        }
+    }
+
+    let db_pool = create_db_pool(&config.database_url, &config)?;
+    let db_pool_read = config
+        .database_url_read
+        .as_deref()
+        .map(|database_url| create_db_pool(database_url, &config))
+        .transpose()?;
+
+    if let Err(err) = db_pool.get().await {
+        return Err(format!(
+            "Failed to connect to database (check DATABASE_URL): {}",
+            err
+        )
+        .into());
+    }
 
     let vapid_key: openssl::ec::EcKey<openssl::pkey::Private> = {
         let db = db_pool.get().await?;
@@ -1221,6 +2279,28 @@ async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    let instance_actor_key: openssl::pkey::PKey<openssl::pkey::Private> = {
+        let db = db_pool.get().await?;
+        let row = db
+            .query_one("SELECT private_key FROM site WHERE local=TRUE", &[])
+            .await?;
+        match row.get(0) {
+            Some(bytes) => openssl::pkey::PKey::private_key_from_pem(bytes)?,
+            None => {
+                let rsa = openssl::rsa::Rsa::generate(KEY_BITS)?;
+                let private_key = rsa.private_key_to_pem()?;
+                let public_key = rsa.public_key_to_pem()?;
+                db.execute(
+                    "UPDATE site SET private_key=$1, public_key=$2 WHERE local=TRUE",
+                    &[&private_key, &public_key],
+                )
+                .await?;
+
+                openssl::pkey::PKey::from_rsa(rsa)?
+            }
+        }
+    };
+
     let vapid_signature_builder = web_push::VapidSignatureBuilder::from_pem_no_sub::<&[u8]>(
         vapid_key.private_key_to_pem()?.as_ref(),
     )?;
@@ -1271,22 +2351,119 @@ async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         .as_ref()
         .map(|value| value.parse().expect("Failed to parse SMTP_FROM"));
 
-    if mailer.is_some() && mail_from.is_none() {
-        panic!("SMTP_URL was provided, but SMTP_FROM was not");
+    // the SMTP server may only be down temporarily, so don't refuse to start over it
+    if let Some(mailer) = &mailer {
+        match mailer.test_connection().await {
+            Ok(true) => {}
+            Ok(false) => {
+                log::warn!("SMTP server did not accept the connection (check SMTP_URL)");
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to connect to SMTP server (check SMTP_URL credentials): {}",
+                    err
+                );
+            }
+        }
+    }
+
+    println!("Starting lotide {}", env!("CARGO_PKG_VERSION"));
+    println!(" - ActivityPub URL: {}", host_url_apub.as_str());
+    println!(" - API URL: {}", config.host_url_api);
+    println!(" - Listening on port {}", config.port);
+    println!(
+        " - Email: {}",
+        if mailer.is_some() {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    println!(
+        " - Media storage: {}",
+        match (config.media_storage.as_deref(), &config.media_location) {
+            (Some("s3"), _) => "s3",
+            (_, Some(_)) => "local",
+            (_, None) => "disabled",
+        }
+    );
+    if config.dev_mode {
+        println!(" - Dev mode enabled");
+    } else if host_url_apub.scheme() != "https" {
+        eprintln!("Warning: HOST_URL_ACTIVITYPUB does not use https, so other servers will likely refuse to federate");
     }
 
     let allow_forwarded = config.allow_forwarded;
 
     let (worker_trigger, worker_rx) = tokio::sync::mpsc::channel(1);
+    let (stream_events, _) = tokio::sync::broadcast::channel(STREAM_EVENTS_CAPACITY);
 
     let routes = Arc::new(routes::route_root());
+    let maintenance_intervals = worker::MaintenanceIntervals::from_config(&config);
+    let worker_options = worker::WorkerOptions::from_config(&config);
+
+    let outbound_policy = Arc::new(outbound::AddressPolicy {
+        allow_all: config.dev_mode,
+        allowed_ranges: config
+            .outbound_allowed_ranges
+            .as_deref()
+            .map(|src| {
+                src.split(',')
+                    .map(str::trim)
+                    .filter(|range| !range.is_empty())
+                    .filter_map(|range| range.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    });
+
     let context = Arc::new(BaseContext {
         local_hostname: get_url_host(&host_url_apub)
             .expect("Couldn't find host in HOST_URL_ACTIVITYPUB"),
 
         break_stuff: config.break_stuff,
         dev_mode: config.dev_mode,
+        markdown_options: MarkdownOptions {
+            tables: config.markdown_tables,
+            strikethrough: config.markdown_strikethrough,
+            autolink: config.markdown_autolink,
+        },
+        sign_fetches: config.sign_fetches,
+        link_posts_as_notes: config.link_posts_as_notes,
+        instance_actor_key,
+        actor_refresh_age: std::time::Duration::from_secs(
+            u64::from(config.actor_refresh_age_hours) * 60 * 60,
+        ),
+        login_max_age: config
+            .login_max_age_days
+            .map(|days| std::time::Duration::from_secs(u64::from(days) * 60 * 60 * 24)),
+        community_activity_window: std::time::Duration::from_secs(
+            u64::from(config.community_activity_window_hours) * 60 * 60,
+        ),
+        duplicate_post_window: std::time::Duration::from_secs(
+            u64::from(config.duplicate_post_window_hours) * 60 * 60,
+        ),
+        comment_edit_window: config
+            .comment_edit_window_minutes
+            .map(|minutes| std::time::Duration::from_secs(u64::from(minutes) * 60)),
+        max_replies_depth: config.max_replies_depth,
+        max_comments_per_request: config.max_comments_per_request as usize,
+        host_dead_after: std::time::Duration::from_secs(
+            u64::from(config.host_dead_after_days) * 60 * 60 * 24,
+        ),
+        password_hash_params: argon2::Params::new(
+            config.password_hash_memory_kib,
+            config.password_hash_iterations,
+            config.password_hash_parallelism,
+            None,
+        )
+        .expect("Invalid password hashing parameters"),
+        max_request_body_size: (config.max_request_body_kib as usize) * 1024,
+        max_import_body_size: (config.max_import_body_mib as usize) * 1024 * 1024,
+        fetch_timeout: std::time::Duration::from_secs(config.fetch_timeout_seconds.into()),
+        max_fetch_response_size: (config.max_fetch_response_kib as usize) * 1024,
         db_pool,
+        db_pool_read,
         mailer,
         mail_from,
         media_storage: match config.media_storage.as_deref() {
@@ -1345,16 +2522,79 @@ async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
         },
         host_url_api: config.host_url_api.clone(),
         host_url_apub,
-        http_client: hyper::Client::builder().build(hyper_tls::HttpsConnector::new()),
+        frontend_url_pattern: config.frontend_url_pattern.clone(),
+        trusted_proxies: config
+            .trusted_proxies
+            .as_deref()
+            .map(|src| {
+                src.split(',')
+                    .map(str::trim)
+                    .filter(|proxy| !proxy.is_empty())
+                    .filter_map(|proxy| proxy.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        strip_href_query_params: config
+            .strip_href_query_params
+            .as_deref()
+            .map(|src| {
+                src.split(',')
+                    .map(str::trim)
+                    .filter(|param| !param.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        http_client: {
+            let mut connector = hyper::client::HttpConnector::new_with_resolver(
+                outbound::FilteringResolver::new(outbound_policy.clone()),
+            );
+            connector.enforce_http(false);
+
+            hyper::Client::builder().build(hyper_tls::HttpsConnector::new_with_connector(connector))
+        },
+        outbound_policy,
         apub_proxy_rewrites: config.apub_proxy_rewrites,
         api_ratelimit: henry::RatelimitBucket::new(300),
         vapid_public_key_base64,
         vapid_signature_builder,
 
+        user_insights_cache: Default::default(),
+        automod_regex_cache: Default::default(),
+        post_view_counts: maintenance_intervals
+            .post_view_flush
+            .map(|_| Default::default()),
+        actor_cache: Default::default(),
+
         worker_trigger,
+        stream_events,
     });
 
-    worker::start_worker(context.clone(), worker_rx);
+    if !context.dev_mode && context.host_url_apub.scheme() == "https" {
+        let context = context.clone();
+        crate::spawn_task(async move {
+            // any response means the certificate for our public host was accepted
+            let uri: hyper::Uri = context.host_url_apub.as_str().parse()?;
+            match tokio::time::timeout(
+                std::time::Duration::from_secs(30),
+                context.http_client.get(uri),
+            )
+            .await
+            {
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => eprintln!(
+                    "Warning: failed to reach HOST_URL_ACTIVITYPUB, check that its TLS certificate matches: {}",
+                    err
+                ),
+                Err(_) => eprintln!("Warning: timed out trying to reach HOST_URL_ACTIVITYPUB"),
+            }
+
+            Ok(())
+        });
+    }
+
+    worker::start_worker(context.clone(), worker_rx, worker_options);
+    worker::start_scheduler(context.clone(), maintenance_intervals);
 
     let server = hyper::Server::bind(&(std::net::Ipv6Addr::UNSPECIFIED, config.port).into()).serve(
         hyper::service::make_service_fn(|sock: &hyper::server::conn::AddrStream| {
@@ -1362,44 +2602,61 @@ async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
             let routes = routes.clone();
             let context = context.clone();
             async move {
-                Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
+                Ok::<_, hyper::Error>(hyper::service::service_fn(move |mut req| {
                     let routes = routes.clone();
                     let context = context.clone();
                     async move {
-                        let ratelimit_addr = if allow_forwarded {
-                            if let Some(value) = req
-                                .headers()
-                                .get(hyper::header::HeaderName::from_static("x-forwarded-for"))
-                            {
-                                match value
-                                    .to_str()
-                                    .map_err(|_| ())
-                                    .and_then(|value| value.split(", ").next().ok_or(()))
-                                    .and_then(|value| value.parse().map_err(|_| ()))
-                                {
-                                    Err(_) => {
-                                        return Ok(simple_response(
-                                            hyper::StatusCode::BAD_REQUEST,
-                                            "Invalid X-Forwarded-For value",
-                                        ));
-                                    }
-                                    Ok(value) => Some(value),
-                                }
-                            } else {
-                                None
-                            }
+                        let accept_language = req
+                            .headers()
+                            .get(hyper::header::ACCEPT_LANGUAGE)
+                            .and_then(|x| x.to_str().ok())
+                            .map(ToOwned::to_owned);
+                        let get_lang = || get_lang_for_header(accept_language.as_deref());
+
+                        // ActivityPub documents are negotiated after routing, since some of those
+                        // routes can also respond with HTML
+                        let apub_accept = if req.method() == hyper::Method::GET
+                            && req.uri().path().starts_with("/apub/")
+                        {
+                            Some(
+                                req.headers()
+                                    .get(hyper::header::ACCEPT)
+                                    .and_then(|x| x.to_str().ok())
+                                    .map(ToOwned::to_owned),
+                            )
                         } else {
-                            Some(addr_direct)
+                            None
+                        };
+
+                        let client_addr = match resolve_client_ip(
+                            addr_direct,
+                            req.headers(),
+                            allow_forwarded,
+                            &context.trusted_proxies,
+                        ) {
+                            Err(_) => {
+                                return Ok(error_response(
+                                    hyper::StatusCode::BAD_REQUEST,
+                                    &get_lang(),
+                                    &lang::forwarded_for_invalid(),
+                                ));
+                            }
+                            Ok(value) => value,
                         };
 
-                        let ratelimit_ok = match ratelimit_addr {
+                        if let Some(addr) = client_addr {
+                            req.extensions_mut().insert(ClientIP(addr));
+                        }
+
+                        let ratelimit_ok = match client_addr {
                             Some(addr) => context.api_ratelimit.try_call(addr),
                             None => true,
                         };
                         let result = if !ratelimit_ok {
-                            Ok(simple_response(
+                            Ok(error_response(
                                 hyper::StatusCode::TOO_MANY_REQUESTS,
-                                "Ratelimit exceeded.",
+                                &get_lang(),
+                                &lang::ratelimit_exceeded(),
                             ))
                         } else if req.method() == hyper::Method::OPTIONS
                             && req.uri().path().starts_with("/api")
@@ -1424,41 +2681,64 @@ async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
                             }
                         };
 
+                        let result = match apub_accept {
+                            Some(accept) => result.map(|res| {
+                                apub_util::negotiate_activity_response(
+                                    accept.as_deref(),
+                                    res,
+                                    &get_lang(),
+                                )
+                            }),
+                            None => result,
+                        };
+
                         Ok::<_, hyper::Error>(match result {
                             Ok(val) => val,
                             Err(Error::UserError(res)) => res,
-                            Err(Error::RoutingError(err)) => {
-                                let code = match err {
-                                    trout::RoutingFailure::NotFound => hyper::StatusCode::NOT_FOUND,
-                                    trout::RoutingFailure::MethodNotAllowed => {
-                                        hyper::StatusCode::METHOD_NOT_ALLOWED
-                                    }
-                                };
-
-                                simple_response(code, code.canonical_reason().unwrap())
+                            Err(Error::Internal(err)) if err.is::<BodyTooLarge>() => {
+                                error_response(
+                                    hyper::StatusCode::PAYLOAD_TOO_LARGE,
+                                    &get_lang(),
+                                    &lang::request_body_too_large(),
+                                )
                             }
+                            Err(Error::RoutingError(err)) => match err {
+                                trout::RoutingFailure::NotFound => error_response(
+                                    hyper::StatusCode::NOT_FOUND,
+                                    &get_lang(),
+                                    &lang::not_found(),
+                                ),
+                                trout::RoutingFailure::MethodNotAllowed => error_response(
+                                    hyper::StatusCode::METHOD_NOT_ALLOWED,
+                                    &get_lang(),
+                                    &lang::method_not_allowed(),
+                                ),
+                            },
                             Err(Error::Internal(err)) => {
                                 log::error!("Error: {:?}", err);
 
-                                simple_response(
+                                error_response(
                                     hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                                    "Internal Server Error",
+                                    &get_lang(),
+                                    &lang::internal_error(),
                                 )
                             }
                             Err(Error::InternalStr(err)) => {
                                 log::error!("Error: {}", err);
 
-                                simple_response(
+                                error_response(
                                     hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                                    "Internal Server Error",
+                                    &get_lang(),
+                                    &lang::internal_error(),
                                 )
                             }
                             Err(Error::InternalStrStatic(err)) => {
                                 log::error!("Error: {}", err);
 
-                                simple_response(
+                                error_response(
                                     hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                                    "Internal Server Error",
+                                    &get_lang(),
+                                    &lang::internal_error(),
                                 )
                             }
                         })
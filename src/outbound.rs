@@ -0,0 +1,125 @@
+use hyper::client::connect::dns::{GaiResolver, Name};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+fn is_public_v4(addr: Ipv4Addr) -> bool {
+    let octets = addr.octets();
+
+    !(addr.is_private()
+        || addr.is_loopback()
+        || addr.is_link_local()
+        || addr.is_unspecified()
+        || addr.is_broadcast()
+        || addr.is_multicast()
+        || addr.is_documentation()
+        || octets[0] == 0
+        // shared address space (carrier-grade NAT)
+        || (octets[0] == 100 && (octets[1] & 0b1100_0000) == 64)
+        // reserved
+        || octets[0] >= 240)
+}
+
+fn is_public_v6(addr: Ipv6Addr) -> bool {
+    let first = addr.segments()[0];
+
+    !(addr.is_loopback()
+        || addr.is_unspecified()
+        || addr.is_multicast()
+        // unique local
+        || (first & 0xfe00) == 0xfc00
+        // link-local
+        || (first & 0xffc0) == 0xfe80
+        // documentation
+        || (first == 0x2001 && addr.segments()[1] == 0x0db8))
+}
+
+pub fn is_public_addr(addr: IpAddr) -> bool {
+    match crate::unmap_ip(addr) {
+        IpAddr::V4(addr) => is_public_v4(addr),
+        IpAddr::V6(addr) => is_public_v6(addr),
+    }
+}
+
+/// Decides which addresses outgoing requests may connect to
+#[derive(Clone, Debug, Default)]
+pub struct AddressPolicy {
+    /// Skips all checks, for testing federation between local instances
+    pub allow_all: bool,
+    pub allowed_ranges: Vec<crate::IpRange>,
+}
+
+impl AddressPolicy {
+    pub fn is_allowed(&self, addr: IpAddr) -> bool {
+        self.allow_all
+            || is_public_addr(addr)
+            || self.allowed_ranges.iter().any(|range| range.contains(addr))
+    }
+
+    /// Rejects URIs that name a disallowed address directly, since those skip DNS resolution
+    pub fn check_uri(&self, uri: &hyper::Uri) -> Result<(), crate::Error> {
+        let host = uri.host().ok_or(crate::Error::InternalStrStatic(
+            "Missing host in outgoing request",
+        ))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        match host.parse::<IpAddr>() {
+            Ok(addr) if !self.is_allowed(addr) => Err(crate::Error::InternalStr(format!(
+                "Refusing to connect to non-public address {}",
+                addr
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// DNS resolver that drops any results not permitted by the address policy
+#[derive(Clone)]
+pub struct FilteringResolver {
+    inner: GaiResolver,
+    policy: Arc<AddressPolicy>,
+}
+
+impl FilteringResolver {
+    pub fn new(policy: Arc<AddressPolicy>) -> Self {
+        Self {
+            inner: GaiResolver::new(),
+            policy,
+        }
+    }
+}
+
+impl hyper::service::Service<Name> for FilteringResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = std::io::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let policy = self.policy.clone();
+        let host = name.as_str().to_owned();
+        let fut = self.inner.call(name);
+
+        Box::pin(async move {
+            let addrs: Vec<_> = fut
+                .await?
+                .filter(|addr| policy.is_allowed(addr.ip()))
+                .collect();
+
+            if addrs.is_empty() {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    format!("{} does not resolve to any allowed address", host),
+                ))
+            } else {
+                Ok(addrs.into_iter())
+            }
+        })
+    }
+}
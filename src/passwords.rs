@@ -0,0 +1,56 @@
+use argon2::password_hash::{
+    rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use std::convert::TryFrom;
+
+fn hasher(params: &Params) -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+}
+
+pub fn hash(password: &str, params: &Params) -> Result<String, crate::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Ok(hasher(params)
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Checks a password against either an Argon2 hash or a legacy bcrypt hash
+pub fn verify(password: &str, passhash: &str) -> Result<bool, crate::Error> {
+    if passhash.starts_with("$argon2") {
+        let parsed = PasswordHash::new(passhash)?;
+
+        // algorithm and parameters are taken from the hash itself
+        match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    } else {
+        Ok(bcrypt::verify(password, passhash)?)
+    }
+}
+
+/// Whether a hash was made with a different algorithm or parameters than currently configured
+pub fn needs_rehash(passhash: &str, params: &Params) -> bool {
+    let parsed = match PasswordHash::new(passhash) {
+        Ok(parsed) => parsed,
+        Err(_) => return true,
+    };
+
+    if parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+    {
+        return true;
+    }
+
+    match Params::try_from(&parsed) {
+        Ok(current) => {
+            current.m_cost() != params.m_cost()
+                || current.t_cost() != params.t_cost()
+                || current.p_cost() != params.p_cost()
+        }
+        Err(_) => true,
+    }
+}
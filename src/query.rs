@@ -0,0 +1,50 @@
+//! Helpers for running queries through the per-connection prepared statement cache.
+//!
+//! Statements are keyed by their SQL text, so dynamically built queries should only vary in
+//! structure (sort order, optional clauses), never by inlining values.
+
+use crate::ParamSlice;
+
+pub type DbClient = deadpool_postgres::ClientWrapper;
+
+fn slice_iter<'a>(
+    s: &'a [&'a (dyn postgres_types::ToSql + Sync)],
+) -> impl ExactSizeIterator<Item = &'a dyn postgres_types::ToSql> + 'a {
+    s.iter().map(|s| *s as _)
+}
+
+pub async fn query(
+    db: &DbClient,
+    sql: &str,
+    params: ParamSlice<'_>,
+) -> Result<Vec<tokio_postgres::Row>, tokio_postgres::Error> {
+    let statement = db.prepare_cached(sql).await?;
+    db.query(&statement, params).await
+}
+
+pub async fn query_opt(
+    db: &DbClient,
+    sql: &str,
+    params: ParamSlice<'_>,
+) -> Result<Option<tokio_postgres::Row>, tokio_postgres::Error> {
+    let statement = db.prepare_cached(sql).await?;
+    db.query_opt(&statement, params).await
+}
+
+pub async fn query_one(
+    db: &DbClient,
+    sql: &str,
+    params: ParamSlice<'_>,
+) -> Result<tokio_postgres::Row, tokio_postgres::Error> {
+    let statement = db.prepare_cached(sql).await?;
+    db.query_one(&statement, params).await
+}
+
+pub async fn query_stream(
+    db: &DbClient,
+    sql: &str,
+    params: ParamSlice<'_>,
+) -> Result<tokio_postgres::RowStream, tokio_postgres::Error> {
+    let statement = db.prepare_cached(sql).await?;
+    db.query_raw(&statement, slice_iter(params)).await
+}
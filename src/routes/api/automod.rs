@@ -0,0 +1,359 @@
+use crate::automod::CommentOutcome;
+use crate::lang;
+use crate::types::{
+    AutomodAction, AutomodRuleID, AutomodRuleKind, CommunityLocalID, PostLocalID,
+    RespAutomodRuleInfo, RespList, UserLocalID,
+};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Checks that the user can manage rules for the community, or instance-wide rules if `None`
+async fn require_can_manage_rules(
+    community: Option<CommunityLocalID>,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+) -> Result<UserLocalID, crate::Error> {
+    match community {
+        None => super::require_site_admin(req, db).await,
+        Some(community) => super::require_community_moderator(community, req, db).await,
+    }
+}
+
+fn validate_rule_value<'a>(
+    kind: AutomodRuleKind,
+    value: &'a str,
+    lang: &crate::Translator,
+) -> Result<&'a str, crate::Error> {
+    let value = value.trim();
+
+    if crate::automod::is_valid_rule_value(kind, value) {
+        Ok(value)
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            lang,
+            &lang::automod_rule_value_invalid(),
+        ))
+    }
+}
+
+fn no_such_rule(lang: &crate::Translator) -> crate::Error {
+    crate::user_error(
+        hyper::StatusCode::NOT_FOUND,
+        lang,
+        &lang::no_such_automod_rule(),
+    )
+}
+
+/// Link domain rules act as a domain blocklist, so changes to them are recorded in the modlog
+async fn log_domain_event(
+    community: Option<CommunityLocalID>,
+    user: UserLocalID,
+    action: &str,
+    domain: &str,
+    db: &tokio_postgres::Client,
+) -> Result<(), crate::Error> {
+    db.execute(
+        "INSERT INTO modlog_event (time, by_community, by_person, action, domain) VALUES (current_timestamp, $1, $2, $3, $4)",
+        &[&community, &user, &action, &domain],
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Rejects comments blocked by automod, returning the outcome otherwise
+pub async fn require_comment_allowed(
+    post: PostLocalID,
+    user: UserLocalID,
+    content: Option<&str>,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+    lang: &crate::Translator,
+) -> Result<CommentOutcome, crate::Error> {
+    match crate::automod::check_comment(post, Some(user), content, db, ctx).await? {
+        CommentOutcome::Reject => Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            lang,
+            &lang::automod_rejected(),
+        )),
+        outcome => Ok(outcome),
+    }
+}
+
+async fn list_rules(
+    community: Option<CommunityLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    require_can_manage_rules(community, &req, &db).await?;
+
+    let rows = db
+        .query(
+            "SELECT id, kind, value, action, created_at FROM automod_rule WHERE community IS NOT DISTINCT FROM $1 ORDER BY id ASC",
+            &[&community],
+        )
+        .await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .filter_map(|row| {
+            let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(4);
+
+            Some(RespAutomodRuleInfo {
+                id: row.get(0),
+                kind: row.get::<_, &str>(1).parse().ok()?,
+                value: Cow::Borrowed(row.get(2)),
+                action: row.get::<_, &str>(3).parse().ok()?,
+                created_at: created_at.to_rfc3339(),
+            })
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn create_rule(
+    community: Option<CommunityLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct AutomodRulesCreateBody<'a> {
+        kind: AutomodRuleKind,
+        value: Cow<'a, str>,
+        action: AutomodAction,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = require_can_manage_rules(community, &req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: AutomodRulesCreateBody = serde_json::from_slice(&body)?;
+
+    let value = validate_rule_value(body.kind, &body.value, &lang)?;
+
+    let row = db.query_one(
+        "INSERT INTO automod_rule (community, kind, value, action, created_by, created_at) VALUES ($1, $2, $3, $4, $5, current_timestamp) RETURNING id",
+        &[&community, &body.kind.as_str(), &value, &body.action.as_str(), &user],
+    ).await?;
+
+    let id: AutomodRuleID = row.get(0);
+
+    if body.kind == AutomodRuleKind::LinkDomain {
+        log_domain_event(community, user, "block_domain", value, &db).await?;
+    }
+
+    crate::json_response(&serde_json::json!({ "id": id }))
+}
+
+async fn edit_rule(
+    community: Option<CommunityLocalID>,
+    rule_id: AutomodRuleID,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct AutomodRulesEditBody<'a> {
+        value: Option<Cow<'a, str>>,
+        action: Option<AutomodAction>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = require_can_manage_rules(community, &req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: AutomodRulesEditBody = serde_json::from_slice(&body)?;
+
+    let row = db
+        .query_opt(
+            "SELECT kind, value FROM automod_rule WHERE id=$1 AND community IS NOT DISTINCT FROM $2",
+            &[&rule_id, &community],
+        )
+        .await?
+        .ok_or_else(|| no_such_rule(&lang))?;
+
+    let kind: AutomodRuleKind = row
+        .get::<_, &str>(0)
+        .parse()
+        .map_err(|_| crate::Error::InternalStrStatic("Unknown automod rule kind"))?;
+    let old_value: &str = row.get(1);
+
+    let value = match &body.value {
+        Some(value) => Some(validate_rule_value(kind, value, &lang)?),
+        None => None,
+    };
+
+    db.execute(
+        "UPDATE automod_rule SET value=COALESCE($2, value), action=COALESCE($3, action) WHERE id=$1",
+        &[&rule_id, &value, &body.action.map(|x| x.as_str())],
+    )
+    .await?;
+
+    ctx.automod_regex_cache.invalidate(rule_id);
+
+    if kind == AutomodRuleKind::LinkDomain {
+        if let Some(value) = value {
+            if value != old_value {
+                log_domain_event(community, user, "unblock_domain", old_value, &db).await?;
+                log_domain_event(community, user, "block_domain", value, &db).await?;
+            }
+        }
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn delete_rule(
+    community: Option<CommunityLocalID>,
+    rule_id: AutomodRuleID,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let user = require_can_manage_rules(community, &req, &db).await?;
+
+    let row = db
+        .query_opt(
+            "DELETE FROM automod_rule WHERE id=$1 AND community IS NOT DISTINCT FROM $2 RETURNING kind, value",
+            &[&rule_id, &community],
+        )
+        .await?;
+
+    ctx.automod_regex_cache.invalidate(rule_id);
+
+    if let Some(row) = row {
+        if row.get::<_, &str>(0) == AutomodRuleKind::LinkDomain.as_str() {
+            log_domain_event(community, user, "unblock_domain", row.get(1), &db).await?;
+        }
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_instance_automod_rules_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    list_rules(None, ctx, req).await
+}
+
+async fn route_unstable_instance_automod_rules_create(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    create_rule(None, ctx, req).await
+}
+
+async fn route_unstable_instance_automod_rules_edit(
+    params: (AutomodRuleID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (rule_id,) = params;
+    edit_rule(None, rule_id, ctx, req).await
+}
+
+async fn route_unstable_instance_automod_rules_delete(
+    params: (AutomodRuleID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (rule_id,) = params;
+    delete_rule(None, rule_id, ctx, req).await
+}
+
+async fn route_unstable_communities_automod_rules_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    list_rules(Some(community_id), ctx, req).await
+}
+
+async fn route_unstable_communities_automod_rules_create(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    create_rule(Some(community_id), ctx, req).await
+}
+
+async fn route_unstable_communities_automod_rules_edit(
+    params: (CommunityLocalID, AutomodRuleID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, rule_id) = params;
+    edit_rule(Some(community_id), rule_id, ctx, req).await
+}
+
+async fn route_unstable_communities_automod_rules_delete(
+    params: (CommunityLocalID, AutomodRuleID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, rule_id) = params;
+    delete_rule(Some(community_id), rule_id, ctx, req).await
+}
+
+pub fn route_instance_automod_rules() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(
+            hyper::Method::GET,
+            route_unstable_instance_automod_rules_list,
+        )
+        .with_handler_async(
+            hyper::Method::POST,
+            route_unstable_instance_automod_rules_create,
+        )
+        .with_child_parse::<AutomodRuleID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(
+                    hyper::Method::PATCH,
+                    route_unstable_instance_automod_rules_edit,
+                )
+                .with_handler_async(
+                    hyper::Method::DELETE,
+                    route_unstable_instance_automod_rules_delete,
+                ),
+        )
+}
+
+pub fn route_communities_automod_rules() -> crate::RouteNode<(CommunityLocalID,)> {
+    crate::RouteNode::new()
+        .with_handler_async(
+            hyper::Method::GET,
+            route_unstable_communities_automod_rules_list,
+        )
+        .with_handler_async(
+            hyper::Method::POST,
+            route_unstable_communities_automod_rules_create,
+        )
+        .with_child_parse::<AutomodRuleID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(
+                    hyper::Method::PATCH,
+                    route_unstable_communities_automod_rules_edit,
+                )
+                .with_handler_async(
+                    hyper::Method::DELETE,
+                    route_unstable_communities_automod_rules_delete,
+                ),
+        )
+}
@@ -1,17 +1,59 @@
+use super::posts::ReactionActivity;
 use super::{
-    JustURL, RespAvatarInfo, RespList, RespMinimalAuthorInfo, RespMinimalCommentInfo,
-    RespPostCommentInfo,
+    format_number_58, parse_number_58, AuthorColumns, InvalidPage, JustURL, RespList,
+    RespMinimalCommentInfo, RespPostCommentInfo,
 };
 use crate::lang;
 use crate::types::{
     ActorLocalRef, CommentLocalID, CommunityLocalID, JustID, JustUser, MaybeIncludeYour,
-    PostLocalID, RespCommentInfo, RespMinimalPostInfo, UserLocalID,
+    PostLocalID, RespCommentContext, RespCommentInfo, RespContentRevision, RespMinimalPostInfo,
+    RespReactionInfo, UserLocalID,
 };
 use serde_derive::Deserialize;
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::sync::Arc;
 
+/// Hides comments in private communities from users who aren't members
+pub async fn require_comment_visible(
+    comment: CommentLocalID,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let row = db
+        .query_opt(
+            "SELECT post.community, reply.pending_approval FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.id=$1",
+            &[&comment],
+        )
+        .await?;
+
+    if let Some(row) = row {
+        let community: CommunityLocalID = row.get(0);
+        let user = crate::authenticate(req, db).await?;
+
+        // held comments are only shown to those who can approve them
+        let visible = if row.get(1) {
+            match user {
+                None => false,
+                Some(user) => super::can_moderate_community(db, user, community).await?,
+            }
+        } else {
+            crate::can_view_community(db, user, community).await?
+        };
+
+        if !visible {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                lang,
+                &lang::no_such_comment(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 async fn route_unstable_comments_get(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -26,6 +68,8 @@ async fn route_unstable_comments_get(
     let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
+    require_comment_visible(comment_id, &req, &db, &lang).await?;
+
     let include_your_for = if query.include_your {
         let user = crate::require_login(&req, &db).await?;
         Some(user)
@@ -33,33 +77,47 @@ async fn route_unstable_comments_get(
         None
     };
 
-    let (row, your_vote) = futures::future::try_join(
+    let (row, your_vote, reactions) = futures::future::try_join3(
         db.query_opt(
-            "SELECT reply.author, reply.post, reply.content_text, reply.created, reply.local, reply.content_html, person.username, person.local, person.ap_id, post.title, reply.deleted, reply.parent, person.avatar, reply.attachment_href, (SELECT COUNT(*) FROM reply_like WHERE reply = reply.id), EXISTS(SELECT 1 FROM reply AS r2 WHERE r2.parent = reply.id), reply.content_markdown, person.is_bot, post.ap_id, post.local, reply.ap_id, post.sensitive, reply.sensitive FROM reply INNER JOIN post ON (reply.post = post.id) LEFT OUTER JOIN person ON (reply.author = person.id) WHERE reply.id = $1",
+            "SELECT reply.author, reply.post, reply.content_text, reply.created, reply.local, reply.content_html, person.username, person.local, person.ap_id, post.title, reply.deleted, reply.parent, person.avatar, reply.attachment_href, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply = reply.id), EXISTS(SELECT 1 FROM reply AS r2 WHERE r2.parent = reply.id AND NOT r2.pending_approval), reply.content_markdown, person.is_bot, post.ap_id, post.local, reply.ap_id, post.sensitive, reply.sensitive, (SELECT hide_scores_minutes FROM community WHERE id=post.community), reply.updated FROM reply INNER JOIN post ON (reply.post = post.id) LEFT OUTER JOIN person ON (reply.author = person.id) WHERE reply.id = $1",
             &[&comment_id],
         )
         .map_err(crate::Error::from),
         async {
             Ok(if let Some(user) = include_your_for {
                 let row = db.query_opt(
-                    "SELECT 1 FROM reply_like WHERE reply=$1 AND person=$2",
+                    "SELECT is_dislike FROM reply_like WHERE reply=$1 AND person=$2",
                     &[&comment_id, &user],
                 ).await?;
 
-                Some(row.map(|_| crate::types::Empty {}))
+                Some(row.map(|row| crate::types::RespYourVote { is_dislike: row.get(0) }))
             } else {
                 None
             })
         },
+        async {
+            let rows = db.query(
+                "SELECT content, COUNT(*), BOOL_OR(person = $2) FROM reply_reaction WHERE reply=$1 GROUP BY content ORDER BY COUNT(*) DESC, content",
+                &[&comment_id, &include_your_for],
+            ).await?;
+
+            Ok(rows.iter().map(|row| RespReactionInfo {
+                content: row.get(0),
+                count: row.get(1),
+                your_reaction: include_your_for.map(|_| row.get::<_, Option<bool>>(2) == Some(true)),
+            }).collect())
+        },
     ).await?;
 
     match row {
-        None => Ok(crate::simple_response(
+        None => Ok(crate::error_response(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_comment()).into_owned(),
+            &lang,
+            &lang::no_such_comment(),
         )),
         Some(row) => {
             let created: chrono::DateTime<chrono::FixedOffset> = row.get(3);
+            let updated: Option<chrono::DateTime<chrono::FixedOffset>> = row.get(24);
             let ap_id: Option<&str> = row.get(20);
             let local: bool = row.get(4);
 
@@ -72,44 +130,19 @@ async fn route_unstable_comments_get(
                 ap_id.map(Cow::Borrowed)
             };
 
-            let author = match row.get(6) {
-                Some(author_username) => {
-                    let author_id = UserLocalID(row.get(0));
-                    let author_local = row.get(7);
-                    let author_ap_id: Option<&str> = row.get(8);
-                    let author_avatar: Option<&str> = row.get(12);
-
-                    let author_remote_url = if author_local {
-                        Some(Cow::Owned(String::from(
-                            crate::apub_util::LocalObjectRef::User(author_id)
-                                .to_local_uri(&ctx.host_url_apub),
-                        )))
-                    } else {
-                        author_ap_id.map(Cow::Borrowed)
-                    };
-
-                    Some(RespMinimalAuthorInfo {
-                        id: author_id,
-                        username: Cow::Borrowed(author_username),
-                        local: author_local,
-                        host: crate::get_actor_host_or_unknown(
-                            author_local,
-                            author_ap_id,
-                            &ctx.local_hostname,
-                        ),
-                        remote_url: author_remote_url,
-                        is_bot: row.get(17),
-                        avatar: author_avatar.map(|url| RespAvatarInfo {
-                            url: ctx.process_avatar_href(url, author_id),
-                        }),
-                    })
-                }
-                None => None,
-            };
+            let author = AuthorColumns {
+                id: 0,
+                username: 6,
+                local: 7,
+                ap_id: 8,
+                avatar: 12,
+                is_bot: 17,
+            }
+            .get(&row, &ctx);
 
             let post = match row.get(9) {
                 Some(post_title) => {
-                    let post_id = PostLocalID(row.get(1));
+                    let post_id: PostLocalID = row.get(1);
                     let post_ap_id: Option<&str> = row.get(18);
                     let post_local: bool = row.get(19);
                     let post_sensitive: bool = row.get(21);
@@ -156,19 +189,21 @@ async fn route_unstable_comments_get(
                     content_markdown: row.get::<_, Option<&str>>(16).map(Cow::Borrowed),
                     created: created.to_rfc3339(),
                     deleted: row.get(10),
+                    edited_at: updated.map(|time| time.to_rfc3339()),
                     local,
                     replies: if row.get(15) {
                         None
                     } else {
                         Some(RespList::empty())
                     },
-                    score: row.get(14),
+                    score: super::get_resp_score(row.get(14), &created, row.get(23)),
                     your_vote,
                 },
                 parent: row.get::<_, Option<_>>(11).map(|id| JustID {
                     id: CommentLocalID(id),
                 }),
                 post,
+                reactions,
             };
 
             crate::json_response(&output)
@@ -197,16 +232,17 @@ async fn route_unstable_comments_delete(
     match row {
         None => Ok(crate::empty_response()), // already gone
         Some(row) => {
-            let author = row.get::<_, Option<_>>(0).map(UserLocalID);
+            let author = row.get::<_, Option<UserLocalID>>(0);
             let is_mod_action = if author != Some(login_user) {
                 if row.get(2) && crate::is_site_admin(&db, login_user).await? {
                     // still ok
                     true
                 } else {
-                    return Err(crate::Error::UserError(crate::simple_response(
+                    return Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::comment_not_yours()).into_owned(),
-                    )));
+                        &lang,
+                        &lang::comment_not_yours(),
+                    ));
                 }
             } else {
                 false
@@ -223,6 +259,13 @@ async fn route_unstable_comments_delete(
                 )
                 .await?;
 
+                trans
+                    .execute(
+                        "DELETE FROM content_revision WHERE reply=$1",
+                        &[&comment_id],
+                    )
+                    .await?;
+
                 if is_mod_action {
                     trans.execute("INSERT INTO modlog_event (time, by_person, action, reply) VALUES (current_timestamp, $1, 'delete_reply', $2)", &[&login_user, &comment_id]).await?;
                 }
@@ -231,7 +274,7 @@ async fn route_unstable_comments_delete(
             }
 
             crate::spawn_task(async move {
-                let community = row.get::<_, Option<_>>(1).map(CommunityLocalID);
+                let community = row.get::<_, Option<CommunityLocalID>>(1);
                 if let Some(community) = community {
                     let delete_ap = crate::apub_util::local_comment_delete_to_ap(
                         comment_id,
@@ -273,6 +316,203 @@ async fn route_unstable_comments_delete(
     }
 }
 
+async fn route_unstable_comments_edit(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    #[derive(Deserialize)]
+    struct CommentsEditBody<'a> {
+        content_text: Option<Cow<'a, str>>,
+        content_markdown: Option<String>,
+        sensitive: Option<bool>,
+    }
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: CommentsEditBody<'_> = serde_json::from_slice(&body)?;
+
+    let edit_window = ctx.comment_edit_window.map(|window| window.as_secs_f64());
+
+    let row = db
+        .query_opt(
+            "SELECT author, post, local, COALESCE(created > current_timestamp - make_interval(secs => $2), TRUE) FROM reply WHERE id=$1 AND NOT deleted",
+            &[&comment_id, &edit_window],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_comment(),
+            )
+        })?;
+
+    let author: Option<UserLocalID> = row.get(0);
+    let local: bool = row.get(2);
+    if author != Some(user) || !local {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::comment_not_yours(),
+        ));
+    }
+
+    if !row.get::<_, bool>(3) {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::comment_edit_window_passed(),
+        ));
+    }
+
+    let post = PostLocalID(row.get(1));
+
+    let (content_text, content_markdown, content_html) = super::process_comment_content(
+        &lang,
+        body.content_text,
+        body.content_markdown,
+        ctx.markdown_options,
+    )
+    .await?;
+
+    let outcome = super::automod::require_comment_allowed(
+        post,
+        user,
+        content_markdown.as_deref().or(content_text.as_deref()),
+        &db,
+        &ctx,
+        &lang,
+    )
+    .await?;
+    let held = matches!(outcome, crate::automod::CommentOutcome::Hold);
+
+    // previous content is kept as a revision, dated from when it was written
+    db.execute(
+        "WITH revision AS (INSERT INTO content_revision (reply, created_at, content_text, content_markdown, content_html) SELECT id, COALESCE(updated, created), content_text, content_markdown, content_html FROM reply WHERE id=$1) UPDATE reply SET content_text=$2, content_markdown=$3, content_html=$4, sensitive=COALESCE($5, sensitive), pending_approval=(pending_approval OR $6), updated=current_timestamp WHERE id=$1",
+        &[&comment_id, &content_text, &content_markdown, &content_html, &body.sensitive, &held],
+    ).await?;
+
+    crate::set_comment_hashtags(
+        comment_id,
+        &crate::extract_hashtags(content_text.as_deref(), content_markdown.as_deref()),
+        &db,
+    )
+    .await?;
+
+    if let crate::automod::CommentOutcome::Report(found) = outcome {
+        crate::automod::report_comment(comment_id, found, &ctx).await?;
+    }
+
+    if !held {
+        ctx.enqueue_task(&crate::tasks::SendCommentUpdate {
+            comment: comment_id,
+        })
+        .await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_comments_revisions_list(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct RevisionsListQuery<'a> {
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+    }
+    let query: RevisionsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let row = db
+        .query_opt(
+            "SELECT EXISTS(SELECT 1 FROM community_moderator WHERE community=post.community AND person=$2) FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.id=$1",
+            &[&comment_id, &user],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_comment(),
+            )
+        })?;
+
+    if !row.get::<_, bool>(0) && !crate::is_site_admin(&db, user).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::must_be_moderator(),
+        ));
+    }
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let page: Option<i64> = query
+        .page
+        .as_deref()
+        .map(|src| parse_number_58(src).map_err(|_| InvalidPage))
+        .transpose()
+        .map_err(|err| err.into_user_error(&lang))?;
+
+    let mut rows = db
+        .query(
+            "SELECT id, created_at, content_text, content_markdown, content_html FROM content_revision WHERE reply=$1 AND ($3::BIGINT IS NULL OR id <= $3) ORDER BY id DESC LIMIT $2",
+            &[&comment_id, &limit_plus_1, &page],
+        )
+        .await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(format_number_58(row.get(0)))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(1);
+
+            RespContentRevision {
+                id: row.get(0),
+                created: created.to_rfc3339(),
+                content_text: row.get::<_, Option<&str>>(2).map(Cow::Borrowed),
+                content_markdown: row.get::<_, Option<&str>>(3).map(Cow::Borrowed),
+                content_html_safe: row
+                    .get::<_, Option<&str>>(4)
+                    .map(|html| crate::clean_html(html)),
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: next_page.map(Cow::Owned),
+    })
+}
+
 async fn route_unstable_comments_like(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -280,14 +520,286 @@ async fn route_unstable_comments_like(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (comment_id,) = params;
 
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_comment_visible(comment_id, &req, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    super::mutes::require_not_muted_for_comment(user, comment_id, &db, &lang).await?;
+
+    set_comment_vote(comment_id, user, false, db, ctx).await
+}
+
+async fn route_unstable_comments_dislike(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_comment_visible(comment_id, &req, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    let row = db.query_opt(
+        "SELECT site.allow_dislikes AND community.allow_dislikes FROM reply INNER JOIN post ON (post.id = reply.post) INNER JOIN community ON (community.id = post.community), site WHERE site.local AND reply.id=$1",
+        &[&comment_id],
+    ).await?.ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_comment())
+    })?;
+
+    if !row.get::<_, bool>(0) {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::dislikes_disabled(),
+        ));
+    }
+
+    super::mutes::require_not_muted_for_comment(user, comment_id, &db, &lang).await?;
+
+    set_comment_vote(comment_id, user, true, db, ctx).await
+}
+
+async fn route_unstable_comments_your_reactions_add(
+    params: (CommentLocalID, String),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id, content) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let content = super::posts::parse_reaction_param(&content, &lang)?;
+
     let db = ctx.db_pool.get().await?;
 
+    require_comment_visible(comment_id, &req, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    super::mutes::require_not_muted_for_comment(user, comment_id, &db, &lang).await?;
+
+    let row = db.query_opt(
+        "INSERT INTO reply_reaction (reply, person, content, local) VALUES ($1, $2, $3, TRUE) ON CONFLICT (reply, person, content) DO NOTHING RETURNING id",
+        &[&comment_id, &user, &content],
+    ).await?;
+
+    if let Some(row) = row {
+        let reaction_id: i64 = row.get(0);
+
+        crate::spawn_task(send_comment_reaction_activity(
+            comment_id,
+            user,
+            reaction_id,
+            ReactionActivity::React(content),
+            db,
+            ctx,
+        ));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_comments_your_reactions_remove(
+    params: (CommentLocalID, String),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id, content) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let content = super::posts::parse_reaction_param(&content, &lang)?;
+
+    let mut db = ctx.db_pool.get().await?;
+
     let user = crate::require_login(&req, &db).await?;
 
-    let row_count = db.execute(
-        "INSERT INTO reply_like (reply, person, local) VALUES ($1, $2, TRUE) ON CONFLICT (reply, person) DO NOTHING",
-        &[&comment_id, &user],
+    let new_undo = {
+        let trans = db.transaction().await?;
+
+        let row = trans
+            .query_opt(
+                "DELETE FROM reply_reaction WHERE reply=$1 AND person=$2 AND content=$3 RETURNING id",
+                &[&comment_id, &user, &content],
+            )
+            .await?;
+
+        let new_undo = if let Some(row) = row {
+            let reaction_id: i64 = row.get(0);
+
+            let id = uuid::Uuid::new_v4();
+            trans
+                .execute(
+                    "INSERT INTO local_reply_reaction_undo (id, reply, person, reaction, content) VALUES ($1, $2, $3, $4, $5)",
+                    &[&id, &comment_id, &user, &reaction_id, &content],
+                )
+                .await?;
+
+            Some((id, reaction_id))
+        } else {
+            None
+        };
+
+        trans.commit().await?;
+
+        new_undo
+    };
+
+    if let Some((undo_id, reaction_id)) = new_undo {
+        crate::spawn_task(send_comment_reaction_activity(
+            comment_id,
+            user,
+            reaction_id,
+            ReactionActivity::Undo(undo_id),
+            db,
+            ctx,
+        ));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn send_comment_reaction_activity(
+    comment_id: CommentLocalID,
+    user: UserLocalID,
+    reaction_id: i64,
+    activity: ReactionActivity,
+    db: deadpool_postgres::Client,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let row = db.query_opt(
+        "SELECT reply.local, reply.ap_id, community.id, community.local, community.ap_id, COALESCE(community.ap_shared_inbox, community.ap_inbox), COALESCE(comment_author.ap_shared_inbox, comment_author.ap_inbox), comment_author.id, comment_author.ap_id FROM reply LEFT OUTER JOIN post ON (reply.post = post.id) LEFT OUTER JOIN community ON (post.community = community.id) LEFT OUTER JOIN person AS comment_author ON (comment_author.id = reply.author) WHERE reply.id = $1",
+        &[&comment_id],
     ).await?;
+    if let Some(row) = row {
+        let comment_local = row.get(0);
+
+        let mut inboxes = HashSet::new();
+
+        if !comment_local {
+            let author_inbox: Option<&str> = row.get(6);
+            if let Some(inbox) = author_inbox {
+                inboxes.insert(inbox);
+            }
+        }
+
+        let community_local: Option<bool> = row.get(3);
+
+        if community_local == Some(false) {
+            if let Some(inbox) = row.get(5) {
+                inboxes.insert(inbox);
+            }
+        }
+
+        let author_ap_id = if comment_local {
+            Some(
+                crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(7)))
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into(),
+            )
+        } else {
+            row.get::<_, Option<&str>>(8)
+                .map(|x| x.parse())
+                .transpose()?
+        };
+
+        let body = match activity {
+            ReactionActivity::Undo(undo_id) => {
+                serde_json::to_string(&crate::apub_util::local_comment_reaction_undo_to_ap(
+                    undo_id,
+                    comment_id,
+                    reaction_id,
+                    author_ap_id,
+                    user,
+                    &ctx.host_url_apub,
+                )?)?
+            }
+            ReactionActivity::React(content) => {
+                let comment_ap_id = if comment_local {
+                    crate::apub_util::LocalObjectRef::Comment(comment_id)
+                        .to_local_uri(&ctx.host_url_apub)
+                } else {
+                    row.get::<_, &str>(1).parse()?
+                };
+
+                serde_json::to_string(&crate::apub_util::local_comment_reaction_to_ap(
+                    comment_id,
+                    reaction_id,
+                    comment_ap_id,
+                    author_ap_id,
+                    user,
+                    content,
+                    &ctx.host_url_apub,
+                )?)?
+            }
+        };
+
+        for inbox in inboxes {
+            ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                inbox: Cow::Owned(inbox.parse()?),
+                sign_as: Some(ActorLocalRef::Person(user)),
+                object: (&body).into(),
+            })
+            .await?;
+        }
+
+        if community_local == Some(true) {
+            let community_local_id: CommunityLocalID = row.get(2);
+            crate::apub_util::enqueue_forward_to_community_followers(community_local_id, body, ctx)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_comment_vote(
+    comment_id: CommentLocalID,
+    user: UserLocalID,
+    is_dislike: bool,
+    mut db: deadpool_postgres::Client,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (row_count, replaced_undo) = {
+        let trans = db.transaction().await?;
+
+        let existing = trans
+            .query_opt(
+                "SELECT is_dislike FROM reply_like WHERE reply=$1 AND person=$2 FOR UPDATE",
+                &[&comment_id, &user],
+            )
+            .await?;
+
+        // switching between like and dislike replaces the old vote, so it needs to be undone
+        let replaced_undo = match existing {
+            Some(row) if row.get::<_, bool>(0) != is_dislike => {
+                let id = uuid::Uuid::new_v4();
+                trans
+                    .execute(
+                        "INSERT INTO local_reply_like_undo (id, reply, person, is_dislike) VALUES ($1, $2, $3, $4)",
+                        &[&id, &comment_id, &user, &!is_dislike],
+                    )
+                    .await?;
+
+                Some(id)
+            }
+            _ => None,
+        };
+
+        let row_count = trans.execute(
+            "INSERT INTO reply_like (reply, person, local, is_dislike) VALUES ($1, $2, TRUE, $3) ON CONFLICT (reply, person) DO UPDATE SET is_dislike=$3 WHERE reply_like.is_dislike != $3",
+            &[&comment_id, &user, &is_dislike],
+        ).await?;
+
+        trans.commit().await?;
+
+        (row_count, replaced_undo)
+    };
 
     if row_count > 0 {
         crate::spawn_task(async move {
@@ -334,33 +846,58 @@ async fn route_unstable_comments_like(
                         .transpose()?
                 };
 
-                let like = crate::apub_util::local_comment_like_to_ap(
-                    comment_id,
-                    comment_ap_id,
-                    author_ap_id,
-                    user,
-                    &ctx.host_url_apub,
-                )?;
-
-                let body = serde_json::to_string(&like)?;
-
-                for inbox in inboxes {
-                    ctx.enqueue_task(&crate::tasks::DeliverToInbox {
-                        inbox: Cow::Owned(inbox.parse()?),
-                        sign_as: Some(ActorLocalRef::Person(user)),
-                        object: (&body).into(),
-                    })
-                    .await?;
+                let mut bodies = Vec::with_capacity(2);
+
+                if let Some(replaced_undo) = replaced_undo {
+                    bodies.push(serde_json::to_string(
+                        &crate::apub_util::local_comment_like_undo_to_ap(
+                            replaced_undo,
+                            comment_id,
+                            !is_dislike,
+                            author_ap_id.clone(),
+                            user,
+                            &ctx.host_url_apub,
+                        )?,
+                    )?);
                 }
 
-                if community_local == Some(true) {
-                    let community_local_id = CommunityLocalID(row.get(2));
-                    crate::apub_util::enqueue_forward_to_community_followers(
-                        community_local_id,
-                        body,
-                        ctx,
-                    )
-                    .await?;
+                bodies.push(if is_dislike {
+                    serde_json::to_string(&crate::apub_util::local_comment_dislike_to_ap(
+                        comment_id,
+                        comment_ap_id,
+                        author_ap_id,
+                        user,
+                        &ctx.host_url_apub,
+                    )?)?
+                } else {
+                    serde_json::to_string(&crate::apub_util::local_comment_like_to_ap(
+                        comment_id,
+                        comment_ap_id,
+                        author_ap_id,
+                        user,
+                        &ctx.host_url_apub,
+                    )?)?
+                });
+
+                for body in bodies {
+                    for inbox in &inboxes {
+                        ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                            inbox: Cow::Owned(inbox.parse()?),
+                            sign_as: Some(ActorLocalRef::Person(user)),
+                            object: (&body).into(),
+                        })
+                        .await?;
+                    }
+
+                    if community_local == Some(true) {
+                        let community_local_id: CommunityLocalID = row.get(2);
+                        crate::apub_util::enqueue_forward_to_community_followers(
+                            community_local_id,
+                            body,
+                            ctx.clone(),
+                        )
+                        .await?;
+                    }
                 }
             }
 
@@ -386,6 +923,7 @@ async fn route_unstable_comments_likes_list(
         pub page: Option<Cow<'a, str>>,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: LikesListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
     let page: Option<(chrono::DateTime<chrono::offset::FixedOffset>, i64)> = query
         .page
@@ -407,10 +945,7 @@ async fn route_unstable_comments_likes_list(
         })
         .transpose()
         .map_err(|_| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::BAD_REQUEST,
-                "Invalid page",
-            ))
+            crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::page_invalid())
         })?;
 
     let limit: i64 = 30;
@@ -418,6 +953,8 @@ async fn route_unstable_comments_likes_list(
 
     let db = ctx.db_pool.get().await?;
 
+    require_comment_visible(comment_id, &req, &db, &lang).await?;
+
     let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&comment_id, &real_limit];
     let page_conditions = match &page {
         Some((ts, u)) => {
@@ -429,14 +966,14 @@ async fn route_unstable_comments_likes_list(
         None => "",
     };
 
-    let sql: &str = &format!("SELECT person.id, person.username, person.local, person.ap_id, reply_like.created_local, person.avatar, person.is_bot FROM reply_like, person WHERE person.id = reply_like.person AND reply_like.reply = $1{} ORDER BY reply_like.created_local DESC, reply_like.person DESC LIMIT $2", page_conditions);
+    let sql: &str = &format!("SELECT person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, reply_like.created_local FROM reply_like, person WHERE person.id = reply_like.person AND reply_like.reply = $1 AND NOT reply_like.is_dislike{} ORDER BY reply_like.created_local DESC, reply_like.person DESC LIMIT $2", page_conditions);
 
-    let mut rows = db.query(sql, &values).await?;
+    let mut rows = crate::query::query(&db, sql, &values).await?;
 
     let next_page = if rows.len() > limit.try_into().unwrap() {
         let row = rows.pop().unwrap();
 
-        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(4);
+        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(6);
         let ts = ts.timestamp_nanos();
 
         let u: i64 = row.get(0);
@@ -448,34 +985,10 @@ async fn route_unstable_comments_likes_list(
 
     let likes = rows
         .iter()
-        .map(|row| {
-            let id = UserLocalID(row.get(0));
-            let username: &str = row.get(1);
-            let local: bool = row.get(2);
-            let ap_id: Option<&str> = row.get(3);
-            let avatar: Option<&str> = row.get(5);
-
-            let remote_url = if local {
-                Some(Cow::Owned(String::from(
-                    crate::apub_util::LocalObjectRef::User(id).to_local_uri(&ctx.host_url_apub),
-                )))
-            } else {
-                ap_id.map(Cow::Borrowed)
-            };
-
-            JustUser {
-                user: RespMinimalAuthorInfo {
-                    id,
-                    username: Cow::Borrowed(username),
-                    local,
-                    host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
-                    remote_url,
-                    is_bot: row.get(6),
-                    avatar: avatar.map(|url| RespAvatarInfo {
-                        url: ctx.process_avatar_href(url, id),
-                    }),
-                },
-            }
+        .filter_map(|row| {
+            Some(JustUser {
+                user: AuthorColumns::starting_at(0).get(row, &ctx)?,
+            })
         })
         .collect::<Vec<_>>();
 
@@ -487,6 +1000,57 @@ async fn route_unstable_comments_likes_list(
     crate::json_response(&body)
 }
 
+async fn route_unstable_comments_mute(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.query_opt("SELECT 1 FROM reply WHERE id=$1", &[&comment_id])
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_comment(),
+            )
+        })?;
+
+    db.execute(
+        "INSERT INTO reply_mute (person, reply) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[&user, &comment_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_comments_unmute(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.execute(
+        "DELETE FROM reply_mute WHERE person=$1 AND reply=$2",
+        &[&user, &comment_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
 async fn route_unstable_comments_unlike(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -501,23 +1065,25 @@ async fn route_unstable_comments_unlike(
     let new_undo = {
         let trans = db.transaction().await?;
 
-        let row_count = trans
-            .execute(
-                "DELETE FROM reply_like WHERE reply=$1 AND person=$2",
+        let row = trans
+            .query_opt(
+                "DELETE FROM reply_like WHERE reply=$1 AND person=$2 RETURNING is_dislike",
                 &[&comment_id, &user],
             )
             .await?;
 
-        let new_undo = if row_count > 0 {
+        let new_undo = if let Some(row) = row {
+            let is_dislike: bool = row.get(0);
+
             let id = uuid::Uuid::new_v4();
             trans
                 .execute(
-                    "INSERT INTO local_reply_like_undo (id, reply, person) VALUES ($1, $2, $3)",
-                    &[&id, &comment_id, &user],
+                    "INSERT INTO local_reply_like_undo (id, reply, person, is_dislike) VALUES ($1, $2, $3, $4)",
+                    &[&id, &comment_id, &user, &is_dislike],
                 )
                 .await?;
 
-            Some(id)
+            Some((id, is_dislike))
         } else {
             None
         };
@@ -527,7 +1093,7 @@ async fn route_unstable_comments_unlike(
         new_undo
     };
 
-    if let Some(new_undo) = new_undo {
+    if let Some((new_undo, is_dislike)) = new_undo {
         crate::spawn_task(async move {
             let row = db.query_opt(
                 "SELECT reply.local, reply.ap_id, community.id, community.local, community.ap_id, COALESCE(community.ap_shared_inbox, community.ap_inbox), COALESCE(comment_author.ap_shared_inbox, comment_author.ap_inbox), comment_author.id, comment_author.ap_id FROM reply LEFT OUTER JOIN post ON (reply.post = post.id) LEFT OUTER JOIN community ON (post.community = community.id) LEFT OUTER JOIN person AS comment_author ON (comment_author.id = reply.author) WHERE reply.id = $1",
@@ -568,6 +1134,7 @@ async fn route_unstable_comments_unlike(
                 let undo = crate::apub_util::local_comment_like_undo_to_ap(
                     new_undo,
                     comment_id,
+                    is_dislike,
                     author_ap_id,
                     user,
                     &ctx.host_url_apub,
@@ -585,7 +1152,7 @@ async fn route_unstable_comments_unlike(
                 }
 
                 if community_local == Some(true) {
-                    let community_local_id = CommunityLocalID(row.get(2));
+                    let community_local_id: CommunityLocalID = row.get(2);
                     crate::apub_util::enqueue_forward_to_community_followers(
                         community_local_id,
                         body,
@@ -617,31 +1184,43 @@ async fn route_unstable_comments_replies_list(
         depth: u8,
         #[serde(default = "super::default_replies_limit")]
         limit: u8,
+        replies_limit: Option<u8>,
         #[serde(default = "super::default_comment_sort")]
         sort: super::SortType,
         page: Option<Cow<'a, str>>,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: RepliesListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let db = ctx.db_pool.get().await?;
+    let include_your_for = {
+        let db = ctx.db_pool.get().await?;
 
-    let include_your_for = if query.include_your {
-        let user = crate::require_login(&req, &db).await?;
-        Some(user)
-    } else {
-        None
+        require_comment_visible(comment_id, &req, &db, &lang).await?;
+
+        if query.include_your {
+            let user = crate::require_login(&req, &db).await?;
+            Some(user)
+        } else {
+            None
+        }
     };
 
+    let db = ctx.get_db_read().await?;
+
+    let mut remaining = ctx.max_comments_per_request;
     let body: RespList<RespPostCommentInfo> = super::get_comments_replies(
         &[comment_id],
         include_your_for,
-        query.depth,
+        query.depth.min(ctx.max_replies_depth),
         query.limit,
+        query.replies_limit.unwrap_or(query.limit),
         query.sort,
         query.page.as_deref(),
+        &mut remaining,
         &db,
         &ctx,
+        &lang,
     )
     .await?
     .remove(&comment_id)
@@ -651,6 +1230,141 @@ async fn route_unstable_comments_replies_list(
     crate::json_response(&body)
 }
 
+async fn route_unstable_comments_context_get(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    #[derive(Deserialize)]
+    struct CommentContextQuery {
+        #[serde(default)]
+        include_your: bool,
+        #[serde(default = "super::default_replies_depth")]
+        depth: u8,
+        #[serde(default = "super::default_replies_limit")]
+        limit: u8,
+        replies_limit: Option<u8>,
+        #[serde(default = "super::default_comment_sort")]
+        sort: super::SortType,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let query: CommentContextQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let include_your_for = {
+        let db = ctx.db_pool.get().await?;
+
+        require_comment_visible(comment_id, &req, &db, &lang).await?;
+
+        if query.include_your {
+            let user = crate::require_login(&req, &db).await?;
+            Some(user)
+        } else {
+            None
+        }
+    };
+
+    let db = ctx.get_db_read().await?;
+
+    let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&comment_id];
+    let your_vote_sql = match &include_your_for {
+        Some(user) => {
+            values.push(user);
+            ", (SELECT is_dislike FROM reply_like WHERE reply = reply.id AND person = $2)"
+        }
+        None => "",
+    };
+
+    // walks up from the requested comment, so the comment itself has distance 0
+    let sql: &str = &format!(
+        "WITH RECURSIVE chain(id, distance) AS (SELECT id, 0 FROM reply WHERE id=$1 UNION ALL SELECT reply.parent, chain.distance + 1 FROM reply, chain WHERE reply.id = chain.id AND reply.parent IS NOT NULL) SELECT {}{}, reply.post FROM chain INNER JOIN reply ON (reply.id = chain.id) LEFT OUTER JOIN person ON (person.id = reply.author) ORDER BY chain.distance DESC",
+        super::COMMENT_FIELDS_SQL,
+        your_vote_sql,
+    );
+
+    let rows = crate::query::query(&db, sql, &values).await?;
+
+    let post_id: PostLocalID = match rows.last() {
+        Some(row) => row.get(if include_your_for.is_some() { 22 } else { 21 }),
+        None => {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_comment(),
+            ))
+        }
+    };
+
+    let mut ancestors: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let mut comment = super::comment_row_to_info(row, include_your_for.is_some(), &ctx);
+            comment.replies = None;
+            comment
+        })
+        .collect();
+    let mut comment = ancestors.pop().unwrap();
+
+    let depth = query.depth.min(ctx.max_replies_depth);
+    if depth > 0 {
+        let mut remaining = ctx.max_comments_per_request;
+        let replies: RespList<RespPostCommentInfo> = super::get_comments_replies(
+            &[comment_id],
+            include_your_for,
+            depth - 1,
+            query.limit,
+            query.replies_limit.unwrap_or(query.limit),
+            query.sort,
+            None,
+            &mut remaining,
+            &db,
+            &ctx,
+            &lang,
+        )
+        .await?
+        .remove(&comment_id)
+        .unwrap_or_default()
+        .into();
+
+        comment.replies = Some(replies);
+    }
+
+    let post_row = db
+        .query_opt(
+            "SELECT title, ap_id, local, sensitive FROM post WHERE id=$1",
+            &[&post_id],
+        )
+        .await?;
+
+    let post = post_row.as_ref().map(|row| {
+        let post_ap_id: Option<&str> = row.get(1);
+        let post_local: bool = row.get(2);
+
+        let post_remote_url = if post_local {
+            Some(Cow::Owned(String::from(
+                crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub),
+            )))
+        } else {
+            post_ap_id.map(Cow::Borrowed)
+        };
+
+        RespMinimalPostInfo {
+            id: post_id,
+            title: row.get(0),
+            remote_url: post_remote_url,
+            sensitive: row.get(3),
+        }
+    });
+
+    crate::json_response(&RespCommentContext {
+        post,
+        ancestors,
+        comment,
+    })
+}
+
 async fn route_unstable_comments_replies_create(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -661,8 +1375,13 @@ async fn route_unstable_comments_replies_create(
     let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
+    require_comment_visible(parent_id, &req, &db, &lang).await?;
+
     let user = crate::require_login(&req, &db).await?;
 
+    super::mutes::require_not_muted_for_comment(user, parent_id, &db, &lang).await?;
+    super::follow_requests::require_can_post_for_comment(user, parent_id, &db, &lang).await?;
+
     #[derive(Deserialize)]
     struct CommentRepliesCreateBody<'a> {
         content_text: Option<Cow<'a, str>>,
@@ -671,42 +1390,69 @@ async fn route_unstable_comments_replies_create(
         sensitive: Option<bool>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: CommentRepliesCreateBody<'_> = serde_json::from_slice(&body)?;
 
     if let Some(attachment) = &body.attachment {
         if !attachment.starts_with("local-media://") {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Comment attachment must be local media",
-            )));
+                &lang,
+                &lang::comment_attachment_not_local(),
+            ));
         }
     }
 
-    let (content_text, content_markdown, content_html) =
-        super::process_comment_content(&lang, body.content_text, body.content_markdown).await?;
+    let (content_text, content_markdown, content_html) = super::process_comment_content(
+        &lang,
+        body.content_text,
+        body.content_markdown,
+        ctx.markdown_options,
+    )
+    .await?;
 
     let post: PostLocalID = match db
         .query_opt("SELECT post FROM reply WHERE id=$1", &[&parent_id])
         .await?
     {
-        None => Err(crate::Error::UserError(crate::simple_response(
+        None => Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_comment()).into_owned(),
-        ))),
+            &lang,
+            &lang::no_such_comment(),
+        )),
         Some(row) => Ok(PostLocalID(row.get(0))),
     }?;
 
+    super::posts::require_post_not_locked(post, &db, &lang).await?;
+
+    let outcome = super::automod::require_comment_allowed(
+        post,
+        user,
+        content_markdown.as_deref().or(content_text.as_deref()),
+        &db,
+        &ctx,
+        &lang,
+    )
+    .await?;
+    let held = matches!(outcome, crate::automod::CommentOutcome::Hold);
+
     let sensitive = body.sensitive.unwrap_or(false);
 
     let row = db.query_one(
-        "INSERT INTO reply (post, parent, author, created, local, content_text, content_markdown, content_html, attachment_href, sensitive) VALUES ($1, $2, $3, current_timestamp, TRUE, $4, $5, $6, $7, $8) RETURNING id, created",
-        &[&post, &parent_id, &user, &content_text, &content_markdown, &content_html, &body.attachment, &sensitive],
+        "INSERT INTO reply (post, parent, author, created, local, content_text, content_markdown, content_html, attachment_href, sensitive, pending_approval) VALUES ($1, $2, $3, current_timestamp, TRUE, $4, $5, $6, $7, $8, $9) RETURNING id, created",
+        &[&post, &parent_id, &user, &content_text, &content_markdown, &content_html, &body.attachment, &sensitive, &held],
     ).await?;
 
-    let reply_id = CommentLocalID(row.get(0));
+    let reply_id: CommentLocalID = row.get(0);
     let created = row.get(1);
 
+    crate::set_comment_hashtags(
+        reply_id,
+        &crate::extract_hashtags(content_text.as_deref(), content_markdown.as_deref()),
+        &db,
+    )
+    .await?;
+
     let info = crate::CommentInfo {
         id: reply_id,
         author: Some(user),
@@ -716,21 +1462,108 @@ async fn route_unstable_comments_replies_create(
         content_markdown: content_markdown.map(Cow::Owned),
         content_html: content_html.map(Cow::Owned),
         created,
+        updated: None,
         ap_id: crate::APIDOrLocal::Local,
         attachment_href: body.attachment,
         sensitive,
     };
 
-    crate::on_post_add_comment(info, ctx);
+    super::reply_drafts::clear_reply_draft(user, post, Some(parent_id), &db).await?;
+
+    if let crate::automod::CommentOutcome::Report(found) = outcome {
+        crate::automod::report_comment(reply_id, found, &ctx).await?;
+    }
+
+    if !held {
+        crate::on_post_add_comment(info, ctx);
+    }
 
     crate::json_response(&serde_json::json!({ "id": reply_id, "post": {"id": post} }))
 }
 
+async fn get_comment_post(
+    comment_id: CommentLocalID,
+    ctx: &crate::RouteContext,
+    lang: &crate::Translator,
+) -> Result<PostLocalID, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    match db
+        .query_opt(
+            "SELECT post FROM reply WHERE id=$1 AND NOT deleted",
+            &[&comment_id],
+        )
+        .await?
+    {
+        None => Err(crate::user_error(
+            hyper::StatusCode::NOT_FOUND,
+            lang,
+            &lang::no_such_comment(),
+        )),
+        Some(row) => Ok(PostLocalID(row.get(0))),
+    }
+}
+
+async fn route_unstable_comments_your_reply_draft_get(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let post = get_comment_post(comment_id, &ctx, &lang).await?;
+
+    super::reply_drafts::get_reply_draft(post, Some(comment_id), ctx, req).await
+}
+
+async fn route_unstable_comments_your_reply_draft_put(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let post = get_comment_post(comment_id, &ctx, &lang).await?;
+
+    super::reply_drafts::put_reply_draft(post, Some(comment_id), ctx, req).await
+}
+
+async fn route_unstable_comments_your_reply_draft_delete(
+    params: (CommentLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let post = get_comment_post(comment_id, &ctx, &lang).await?;
+
+    super::reply_drafts::delete_reply_draft(post, Some(comment_id), ctx, req).await
+}
+
 pub fn route_comments() -> crate::RouteNode<()> {
     crate::RouteNode::new().with_child_parse::<CommentLocalID, _>(
         crate::RouteNode::new()
             .with_handler_async(hyper::Method::GET, route_unstable_comments_get)
+            .with_handler_async(hyper::Method::PATCH, route_unstable_comments_edit)
             .with_handler_async(hyper::Method::DELETE, route_unstable_comments_delete)
+            .with_child(
+                "context",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, route_unstable_comments_context_get),
+            )
+            .with_child(
+                "dislike",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::POST, route_unstable_comments_dislike),
+            )
+            .with_child(
+                "mute",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::POST, route_unstable_comments_mute),
+            )
             .with_child(
                 "replies",
                 crate::RouteNode::new()
@@ -740,16 +1573,56 @@ pub fn route_comments() -> crate::RouteNode<()> {
                         route_unstable_comments_replies_create,
                     ),
             )
+            .with_child(
+                "revisions",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, route_unstable_comments_revisions_list),
+            )
+            .with_child(
+                "unmute",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::POST, route_unstable_comments_unmute),
+            )
             .with_child(
                 "votes",
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::GET, route_unstable_comments_likes_list),
             )
+            .with_child(
+                "your_reactions",
+                crate::RouteNode::new().with_child_str(
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::PUT,
+                            route_unstable_comments_your_reactions_add,
+                        )
+                        .with_handler_async(
+                            hyper::Method::DELETE,
+                            route_unstable_comments_your_reactions_remove,
+                        ),
+                ),
+            )
             .with_child(
                 "your_vote",
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::PUT, route_unstable_comments_like)
                     .with_handler_async(hyper::Method::DELETE, route_unstable_comments_unlike),
+            )
+            .with_child(
+                "your_reply_draft",
+                crate::RouteNode::new()
+                    .with_handler_async(
+                        hyper::Method::GET,
+                        route_unstable_comments_your_reply_draft_get,
+                    )
+                    .with_handler_async(
+                        hyper::Method::PUT,
+                        route_unstable_comments_your_reply_draft_put,
+                    )
+                    .with_handler_async(
+                        hyper::Method::DELETE,
+                        route_unstable_comments_your_reply_draft_delete,
+                    ),
             ),
     )
 }
@@ -1,7 +1,9 @@
 use super::{format_number_58, parse_number_58, CommunitiesSortType, InvalidPage, ValueConsumer};
 use crate::lang;
 use crate::types::{
-    CommunityLocalID, MaybeIncludeYour, PostLocalID, RespAvatarInfo, RespCommunityFeeds,
+    CommentLocalID, CommunityArchive, CommunityArchiveAuthor, CommunityArchiveComment,
+    CommunityArchiveCommunity, CommunityArchivePost, CommunityLocalID, FlairLocalID, FollowState,
+    JustID, MaybeIncludeYour, PostLocalID, RespAvatarInfo, RespCommunityFeeds,
     RespCommunityFeedsType, RespCommunityInfo, RespCommunityModlogEvent,
     RespCommunityModlogEventDetails, RespList, RespMinimalAuthorInfo, RespMinimalCommunityInfo,
     RespMinimalPostInfo, RespModeratorInfo, RespYourFollowInfo, UserLocalID,
@@ -13,6 +15,8 @@ use std::convert::TryInto;
 use std::ops::Deref;
 use std::sync::Arc;
 
+const COMMUNITY_ARCHIVE_VERSION: u32 = 1;
+
 async fn require_community_exists(
     community_id: CommunityLocalID,
     db: &tokio_postgres::Client,
@@ -32,10 +36,11 @@ async fn require_community_exists(
     if exists {
         Ok(())
     } else {
-        Err(crate::Error::UserError(crate::simple_response(
+        Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_community()).into_owned(),
-        )))
+            lang,
+            &lang::no_such_community(),
+        ))
     }
 }
 
@@ -96,24 +101,27 @@ async fn route_unstable_communities_list(
         sort: CommunitiesSortType,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: CommunitiesListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
     let mut sql = String::from(
-        "SELECT id, name, local, ap_id, description, description_html, description_markdown",
+        "SELECT id, name, local, ap_id, description, description_html, description_markdown, followers_count, COALESCE(community_activity.score, 0), posts_count, last_activity",
     );
     let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new();
 
-    let db = ctx.db_pool.get().await?;
-
+    // authenticate against the primary, since a new login may not have reached the replica yet
     let login_user_maybe = if query.include_your
         || query.your_follow_accepted.is_some()
         || query.you_are_moderator.is_some()
     {
+        let db = ctx.db_pool.get().await?;
         Some(crate::require_login(&req, &db).await?)
     } else {
         None
     };
 
+    let db = ctx.get_db_read().await?;
+
     let include_your_for = if query.include_your {
         Some(login_user_maybe.unwrap())
     } else {
@@ -122,10 +130,15 @@ async fn route_unstable_communities_list(
 
     if let Some(user) = &include_your_for {
         values.push(user);
-        sql.push_str(", (SELECT accepted FROM community_follow WHERE community=community.id AND follower=$1), EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$1)");
+        sql.push_str(", (SELECT state::TEXT FROM community_follow WHERE community=community.id AND follower=$1), EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$1)");
     }
 
-    sql.push_str(" FROM community WHERE NOT deleted");
+    sql.push_str(" FROM community LEFT OUTER JOIN community_activity ON (community_activity.community = community.id) WHERE NOT deleted");
+
+    // unlisted communities only show up in lists specific to the user
+    if query.your_follow_accepted.is_none() && query.you_are_moderator != Some(true) {
+        sql.push_str(" AND NOT unlisted");
+    }
 
     if let Some(search) = &query.search {
         values.push(search);
@@ -179,7 +192,7 @@ async fn route_unstable_communities_list(
                 used: 0,
             },
         )
-        .map_err(super::InvalidPage::into_user_error)?;
+        .map_err(|err| err.into_user_error(&lang))?;
     if let Some(value) = &con1 {
         values.push(value.as_ref());
         if let Some(value) = &con2 {
@@ -205,15 +218,17 @@ async fn route_unstable_communities_list(
     log::debug!("sql = {:?}", sql);
 
     let sql: &str = &sql;
-    let mut rows = db.query(sql, &values).await?;
+    let mut rows = crate::query::query(&db, sql, &values).await?;
 
     let next_page = if rows.len() > query.limit.try_into().unwrap() {
         let row = rows.pop().unwrap();
 
-        let id = CommunityLocalID(row.get(0));
+        let id: CommunityLocalID = row.get(0);
         let name = Cow::Borrowed(row.get(1));
         let local = row.get(2);
         let ap_id: Option<&str> = row.get(3);
+        let followers_count = row.get(7);
+        let activity_score = row.get(8);
 
         Some(query.sort.get_next_page(
             &RespMinimalCommunityInfo {
@@ -224,6 +239,8 @@ async fn route_unstable_communities_list(
                 remote_url: ap_id.map(Cow::Borrowed),
                 deleted: false,
             },
+            followers_count,
+            activity_score,
             query.page.as_deref(),
         ))
     } else {
@@ -236,7 +253,7 @@ async fn route_unstable_communities_list(
         let moderated_communities: Vec<_> = rows
             .iter()
             .filter_map(|row| {
-                if row.get(8) {
+                if row.get(12) {
                     Some(CommunityLocalID(row.get(0)))
                 } else {
                     None
@@ -262,7 +279,7 @@ async fn route_unstable_communities_list(
         items: rows
             .iter()
             .map(|row| {
-                let id = CommunityLocalID(row.get(0));
+                let id: CommunityLocalID = row.get(0);
                 let name: &str = row.get(1);
                 let local = row.get(2);
                 let ap_id = row.get(3);
@@ -279,7 +296,7 @@ async fn route_unstable_communities_list(
                 };
 
                 let you_are_moderator = if query.include_your {
-                    Some(row.get(8))
+                    Some(row.get(12))
                 } else {
                     None
                 };
@@ -305,12 +322,21 @@ async fn route_unstable_communities_list(
                             new: format!("{}/stable/communities/{}/feed", ctx.host_url_api, id),
                         },
                     },
+                    followers_count: row.get(7),
+                    posts_count: row.get(9),
+                    last_activity: row
+                        .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(10)
+                        .map(|time| time.to_rfc3339()),
 
                     you_are_moderator,
                     your_follow: if query.include_your {
                         Some(
-                            row.get::<_, Option<bool>>(7)
-                                .map(|accepted| RespYourFollowInfo { accepted }),
+                            row.get::<_, Option<&str>>(11)
+                                .and_then(|state| state.parse().ok())
+                                .map(|state| RespYourFollowInfo {
+                                    accepted: state == FollowState::Accepted,
+                                    state,
+                                }),
                         )
                     } else {
                         None
@@ -328,6 +354,14 @@ async fn route_unstable_communities_list(
                     } else {
                         None
                     },
+
+                    allow_dislikes: None,
+                    hide_scores_minutes: None,
+                    unlisted: None,
+                    approve_follows: None,
+                    members_only_posting: None,
+                    private: None,
+                    rules: None,
                 }
             })
             .collect::<Vec<_>>()
@@ -354,16 +388,16 @@ async fn route_unstable_communities_create(
         name: &'a str,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: CommunitiesCreateBody<'_> = serde_json::from_slice(&body)?;
 
     for ch in body.name.chars() {
         if !super::USERNAME_ALLOWED_CHARS.contains(&ch) {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::community_name_disallowed_chars())
-                    .into_owned(),
-            )));
+                &lang,
+                &lang::community_name_disallowed_chars(),
+            ));
         }
     }
 
@@ -381,11 +415,11 @@ async fn route_unstable_communities_create(
                 if crate::is_site_admin(&db, user).await? {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::BAD_REQUEST,
-                        lang.tr(&lang::permission_missing_create_community())
-                            .into_owned(),
-                    )))
+                        &lang,
+                        &lang::permission_missing_create_community(),
+                    ))
                 }
             }
         }
@@ -406,10 +440,7 @@ async fn route_unstable_communities_create(
             .await
             .map_err(|err| {
                 if err.code() == Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION) {
-                    crate::Error::UserError(crate::simple_response(
-                        hyper::StatusCode::BAD_REQUEST,
-                        lang.tr(&lang::name_in_use()).into_owned(),
-                    ))
+                    crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::name_in_use())
                 } else {
                     err.into()
                 }
@@ -418,11 +449,11 @@ async fn route_unstable_communities_create(
         let row = trans
             .query_one(
                 "INSERT INTO community (name, local, private_key, public_key, created_by, created_local) VALUES ($1, TRUE, $2, $3, $4, current_timestamp) RETURNING id",
-                &[&body.name, &private_key, &public_key, &user.raw()],
+                &[&body.name, &private_key, &public_key, &user],
             )
             .await?;
 
-        let community_id = CommunityLocalID(row.get(0));
+        let community_id: CommunityLocalID = row.get(0);
 
         trans
             .execute(
@@ -464,10 +495,11 @@ async fn route_unstable_communities_delete(
                 if row.get(0) {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::BAD_REQUEST,
-                        lang.tr(&lang::community_not_local()).into_owned(),
-                    )))
+                        &lang,
+                        &lang::community_not_local(),
+                    ))
                 }
             }
         }
@@ -487,10 +519,11 @@ async fn route_unstable_communities_delete(
                 if crate::is_site_admin(&db, user).await? {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::community_edit_denied()).into_owned(),
-                    )))
+                        &lang,
+                        &lang::community_edit_denied(),
+                    ))
                 }
             }
             Some(_) => Ok(()),
@@ -530,20 +563,17 @@ async fn route_unstable_communities_get(
         (if query.include_your {
             let user = crate::require_login(&req, &db).await?;
             db.query_opt(
-                "SELECT name, local, ap_id, description, description_html, description_markdown, (SELECT accepted FROM community_follow WHERE community=community.id AND follower=$2), EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$2) FROM community WHERE id=$1 AND NOT deleted",
-                &[&community_id.raw(), &user.raw()],
+                "SELECT name, local, ap_id, description, description_html, description_markdown, allow_dislikes AND (SELECT allow_dislikes FROM site WHERE local), hide_scores_minutes, followers_count, unlisted, approve_follows, members_only_posting, private, posts_count, last_activity, rules, (SELECT state::TEXT FROM community_follow WHERE community=community.id AND follower=$2), EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$2) FROM community WHERE id=$1 AND NOT deleted",
+                &[&community_id, &user],
             ).await?
         } else {
             db.query_opt(
-                "SELECT name, local, ap_id, description, description_html, description_markdown FROM community WHERE id=$1 AND NOT deleted",
-                &[&community_id.raw()],
+                "SELECT name, local, ap_id, description, description_html, description_markdown, allow_dislikes AND (SELECT allow_dislikes FROM site WHERE local), hide_scores_minutes, followers_count, unlisted, approve_follows, members_only_posting, private, posts_count, last_activity, rules FROM community WHERE id=$1 AND NOT deleted",
+                &[&community_id],
             ).await?
         })
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::NOT_FOUND,
-                lang.tr(&lang::no_such_community()).into_owned(),
-            ))
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_community())
         })?
     };
 
@@ -560,7 +590,7 @@ async fn route_unstable_communities_get(
     };
 
     let you_are_moderator = if query.include_your {
-        Some(row.get(7))
+        Some(row.get(17))
     } else {
         None
     };
@@ -597,17 +627,33 @@ async fn route_unstable_communities_get(
                 ),
             },
         },
+        followers_count: row.get(8),
+        posts_count: row.get(13),
+        last_activity: row
+            .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(14)
+            .map(|time| time.to_rfc3339()),
         you_are_moderator,
         your_follow: if query.include_your {
             Some(
-                row.get::<_, Option<bool>>(6)
-                    .map(|accepted| RespYourFollowInfo { accepted }),
+                row.get::<_, Option<&str>>(16)
+                    .and_then(|state| state.parse().ok())
+                    .map(|state| RespYourFollowInfo {
+                        accepted: state == FollowState::Accepted,
+                        state,
+                    }),
             )
         } else {
             None
         },
 
         pending_moderation_actions,
+        allow_dislikes: Some(row.get(6)),
+        hide_scores_minutes: Some(row.get::<_, Option<i32>>(7).unwrap_or(0) as u32),
+        unlisted: Some(row.get(9)),
+        approve_follows: Some(row.get(10)),
+        members_only_posting: Some(row.get(11)),
+        private: Some(row.get(12)),
+        rules: Some(row.get(15)),
     };
 
     crate::json_response(&info)
@@ -632,9 +678,18 @@ async fn route_unstable_communities_patch(
         description_text: Option<Cow<'a, str>>,
         description_markdown: Option<Cow<'a, str>>,
         description_html: Option<Cow<'a, str>>,
+        allow_dislikes: Option<bool>,
+        modlog_reasons_public: Option<bool>,
+        link_posts_as_notes: Option<bool>,
+        hide_scores_minutes: Option<u32>,
+        unlisted: Option<bool>,
+        approve_follows: Option<bool>,
+        members_only_posting: Option<bool>,
+        private: Option<bool>,
+        rules: Option<Vec<String>>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: CommunitiesEditBody = serde_json::from_slice(&body)?;
 
     let too_many_description_updates = if body.description_text.is_some() {
@@ -644,12 +699,26 @@ async fn route_unstable_communities_patch(
     };
 
     if too_many_description_updates {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::description_content_conflict()).into_owned(),
-        )));
+            &lang,
+            &lang::description_content_conflict(),
+        ));
     }
 
+    let any_changes = body.description_text.is_some()
+        || body.description_markdown.is_some()
+        || body.description_html.is_some()
+        || body.allow_dislikes.is_some()
+        || body.modlog_reasons_public.is_some()
+        || body.link_posts_as_notes.is_some()
+        || body.hide_scores_minutes.is_some()
+        || body.unlisted.is_some()
+        || body.approve_follows.is_some()
+        || body.members_only_posting.is_some()
+        || body.private.is_some()
+        || body.rules.is_some();
+
     ({
         let row = db
             .query_opt(
@@ -658,10 +727,11 @@ async fn route_unstable_communities_patch(
             )
             .await?;
         match row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::community_edit_denied()).into_owned(),
-            ))),
+                &lang,
+                &lang::community_edit_denied(),
+            )),
             Some(_) => Ok(()),
         }
     })?;
@@ -675,8 +745,12 @@ async fn route_unstable_communities_patch(
 
         crate::apub_util::spawn_enqueue_send_new_community_update(community_id, ctx);
     } else if let Some(description) = body.description_markdown {
+        let markdown_options = ctx.markdown_options;
         let (html, md) = tokio::task::spawn_blocking(move || {
-            (crate::render_markdown(&description), description)
+            (
+                crate::render_markdown(&description, markdown_options),
+                description,
+            )
         })
         .await?;
 
@@ -697,6 +771,92 @@ async fn route_unstable_communities_patch(
         crate::apub_util::spawn_enqueue_send_new_community_update(community_id, ctx);
     }
 
+    if let Some(allow_dislikes) = body.allow_dislikes {
+        db.execute(
+            "UPDATE community SET allow_dislikes=$1 WHERE id=$2",
+            &[&allow_dislikes, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(hide_scores_minutes) = body.hide_scores_minutes {
+        let hide_scores_minutes = if hide_scores_minutes == 0 {
+            None
+        } else {
+            Some(hide_scores_minutes.min(i32::MAX as u32) as i32)
+        };
+
+        db.execute(
+            "UPDATE community SET hide_scores_minutes=$1 WHERE id=$2",
+            &[&hide_scores_minutes, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(modlog_reasons_public) = body.modlog_reasons_public {
+        db.execute(
+            "UPDATE community SET modlog_reasons_public=$1 WHERE id=$2",
+            &[&modlog_reasons_public, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(link_posts_as_notes) = body.link_posts_as_notes {
+        db.execute(
+            "UPDATE community SET link_posts_as_notes=$1 WHERE id=$2",
+            &[&link_posts_as_notes, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(unlisted) = body.unlisted {
+        db.execute(
+            "UPDATE community SET unlisted=$1 WHERE id=$2",
+            &[&unlisted, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(approve_follows) = body.approve_follows {
+        db.execute(
+            "UPDATE community SET approve_follows=$1 WHERE id=$2",
+            &[&approve_follows, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(members_only_posting) = body.members_only_posting {
+        db.execute(
+            "UPDATE community SET members_only_posting=$1 WHERE id=$2",
+            &[&members_only_posting, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(private) = body.private {
+        db.execute(
+            "UPDATE community SET private=$1 WHERE id=$2",
+            &[&private, &community_id],
+        )
+        .await?;
+    }
+
+    if let Some(rules) = &body.rules {
+        db.execute(
+            "UPDATE community SET rules=$1 WHERE id=$2",
+            &[rules, &community_id],
+        )
+        .await?;
+    }
+
+    if any_changes {
+        db.execute(
+            "INSERT INTO modlog_event (time, by_community, by_person, action) VALUES (current_timestamp, $1, $2, 'edit_community')",
+            &[&community_id, &user],
+        )
+        .await?;
+    }
+
     Ok(crate::empty_response())
 }
 
@@ -718,70 +878,67 @@ async fn route_unstable_communities_follow(
         try_wait_for_accept: bool,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: CommunitiesFollowBody = serde_json::from_slice(&body)?;
 
     let row = db
         .query_opt(
-            "SELECT local, deleted FROM community WHERE id=$1",
-            &[&community],
+            "SELECT local, deleted, approve_follows AND NOT EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$2) FROM community WHERE id=$1",
+            &[&community, &user],
         )
         .await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
+            crate::user_error(
                 hyper::StatusCode::NOT_FOUND,
-                lang.tr(&lang::no_such_community()).into_owned(),
-            ))
+                &lang,
+                &lang::no_such_community(),
+            )
         })?;
 
     let community_local: bool = row.get(0);
+    let needs_approval: bool = row.get(2);
 
     if row.get(1) {
         // deleted
 
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_community()).into_owned(),
-        )));
+            &lang,
+            &lang::no_such_community(),
+        ));
     }
 
-    let row_count = db.execute("INSERT INTO community_follow (community, follower, local, accepted) VALUES ($1, $2, TRUE, $3) ON CONFLICT DO NOTHING", &[&community, &user.raw(), &community_local]).await?;
+    let transition = crate::follows::request_community_follow(
+        &db,
+        community,
+        user,
+        true,
+        None,
+        community_local && !needs_approval,
+    )
+    .await?;
 
-    let output = if community_local {
-        RespYourFollowInfo { accepted: true }
-    } else if row_count > 0 {
-        crate::apub_util::spawn_enqueue_send_community_follow(community, user, ctx);
+    let state =
+        if !community_local && transition.changed() && transition.current == FollowState::Pending {
+            crate::apub_util::spawn_enqueue_send_community_follow(community, user, ctx);
 
-        if body.try_wait_for_accept {
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            if body.try_wait_for_accept {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-            let row = db
-                .query_one(
-                    "SELECT accepted FROM community_follow WHERE community=$1 AND follower=$2",
-                    &[&community, &user.raw()],
-                )
-                .await?;
-
-            RespYourFollowInfo {
-                accepted: row.get(0),
+                crate::follows::get_community_follow_state(&db, community, user)
+                    .await?
+                    .unwrap_or(FollowState::Pending)
+            } else {
+                FollowState::Pending
             }
         } else {
-            RespYourFollowInfo { accepted: false }
-        }
-    } else {
-        let row = db
-            .query_one(
-                "SELECT accepted FROM community_follow WHERE community=$1 AND follower=$2",
-                &[&community, &user.raw()],
-            )
-            .await?;
-
-        RespYourFollowInfo {
-            accepted: row.get(0),
-        }
-    };
+            transition.current
+        };
 
-    crate::json_response(&output)
+    crate::json_response(&RespYourFollowInfo {
+        accepted: state == FollowState::Accepted,
+        state,
+    })
 }
 
 async fn route_unstable_communities_moderators_list(
@@ -801,19 +958,20 @@ async fn route_unstable_communities_moderators_list(
             .await?;
 
         match row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::NOT_FOUND,
-                lang.tr(&lang::no_such_community()).into_owned(),
-            ))),
+                &lang,
+                &lang::no_such_community(),
+            )),
             Some(row) => {
                 if row.get(0) {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::NOT_FOUND,
-                        lang.tr(&lang::community_moderators_not_local())
-                            .into_owned(),
-                    )))
+                        &lang,
+                        &lang::community_moderators_not_local(),
+                    ))
                 }
             }
         }
@@ -827,7 +985,7 @@ async fn route_unstable_communities_moderators_list(
     let output: Vec<_> = rows
         .iter()
         .map(|row| {
-            let id = UserLocalID(row.get(0));
+            let id: UserLocalID = row.get(0);
             let local = row.get(2);
             let ap_id: Option<_> = row.get(3);
 
@@ -869,7 +1027,7 @@ async fn route_unstable_communities_moderators_add(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, user_id) = params;
 
-    let db = ctx.db_pool.get().await?;
+    let mut db = ctx.db_pool.get().await?;
 
     let lang = crate::get_lang_for_req(&req);
     let login_user = crate::require_login(&req, &db).await?;
@@ -882,10 +1040,11 @@ async fn route_unstable_communities_moderators_add(
             )
             .await?;
         match row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::must_be_moderator()).into_owned(),
-            ))),
+                &lang,
+                &lang::must_be_moderator(),
+            )),
             Some(_) => Ok(()),
         }
     })?;
@@ -896,30 +1055,41 @@ async fn route_unstable_communities_moderators_add(
             .await?;
 
         match row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::no_such_user()).into_owned(),
-            ))),
+                &lang,
+                &lang::no_such_user(),
+            )),
             Some(row) => {
                 let local: bool = row.get(0);
 
                 if local {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::moderators_only_local()).into_owned(),
-                    )))
+                        &lang,
+                        &lang::moderators_only_local(),
+                    ))
                 }
             }
         }
     })?;
 
-    db.execute(
-        "INSERT INTO community_moderator (community, person, created_local) VALUES ($1, $2, current_timestamp)",
-        &[&community_id, &user_id],
-    )
-    .await?;
+    {
+        let trans = db.transaction().await?;
+        trans.execute(
+            "INSERT INTO community_moderator (community, person, created_local) VALUES ($1, $2, current_timestamp)",
+            &[&community_id, &user_id],
+        )
+        .await?;
+        trans.execute(
+            "INSERT INTO modlog_event (time, by_community, by_person, action, person) VALUES (current_timestamp, $1, $2, 'add_moderator', $3)",
+            &[&community_id, &login_user, &user_id],
+        )
+        .await?;
+        trans.commit().await?;
+    }
 
     Ok(crate::empty_response())
 }
@@ -945,10 +1115,11 @@ async fn route_unstable_communities_moderators_remove(
 
     let self_moderator_since: Option<chrono::DateTime<chrono::offset::Utc>> = ({
         match self_moderator_row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::must_be_moderator()).into_owned(),
-            ))),
+                &lang,
+                &lang::must_be_moderator(),
+            )),
             Some(row) => Ok(row.get(0)),
         }
     })?;
@@ -961,6 +1132,8 @@ async fn route_unstable_communities_moderators_remove(
         )
         .await?;
 
+        let removed = row.is_some();
+
         let is_allowed = match self_moderator_since {
             None => true, // self was moderator before timestamps existed, can remove anyone
             Some(_) => {
@@ -975,26 +1148,39 @@ async fn route_unstable_communities_moderators_remove(
         };
 
         if is_allowed {
+            if removed {
+                trans.execute(
+                    "INSERT INTO modlog_event (time, by_community, by_person, action, person) VALUES (current_timestamp, $1, $2, 'remove_moderator', $3)",
+                    &[&community_id, &login_user, &user_id],
+                )
+                .await?;
+            }
+
             trans.commit().await?;
             Ok(crate::empty_response())
         } else {
             trans.rollback().await?;
 
-            Err(crate::Error::UserError(crate::simple_response(
+            Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::community_moderators_remove_must_be_older())
-                    .into_owned(),
-            )))
+                &lang,
+                &lang::community_moderators_remove_must_be_older(),
+            ))
         }
     }
 }
 
-async fn route_unstable_communities_modlog_events_list(
-    params: (CommunityLocalID,),
+/// Lists modlog events for a community, or for all communities if `community` is None.
+/// `privileged` controls whether moderator identities and private reasons are included.
+pub async fn list_community_modlog_events(
+    community: Option<CommunityLocalID>,
+    privileged: bool,
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let (community,) = params;
+    use std::fmt::Write;
+
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
     fn default_limit() -> u32 {
@@ -1018,18 +1204,25 @@ async fn route_unstable_communities_modlog_events_list(
         .as_deref()
         .map(parse_number_58)
         .transpose()
-        .map_err(|_| InvalidPage.into_user_error())?;
+        .map_err(|_| InvalidPage.into_user_error(&lang))?;
+
+    let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&inner_limit];
 
-    let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> =
-        vec![&community, &inner_limit];
+    let mut sql = "SELECT modlog_event.id, modlog_event.time, modlog_event.action, post.id, post.title, post.ap_id, post.local, post.sensitive, modlog_event.reason, by_person.id, by_person.username, by_person.local, by_person.ap_id, by_person.avatar, by_person.is_bot, person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, community.id, community.name, community.local, community.ap_id, community.deleted, community.modlog_reasons_public, modlog_event.domain FROM modlog_event INNER JOIN community ON (community.id = modlog_event.by_community) LEFT OUTER JOIN post ON (post.id = modlog_event.post) LEFT OUTER JOIN person AS by_person ON (by_person.id = modlog_event.by_person) LEFT OUTER JOIN person ON (person.id = modlog_event.person) WHERE TRUE".to_owned();
 
-    let rows = db.query(&format!("SELECT modlog_event.id, modlog_event.time, modlog_event.action, post.id, post.title, post.ap_id, post.local, post.sensitive FROM modlog_event LEFT OUTER JOIN post ON (post.id = modlog_event.post) WHERE modlog_event.by_community=$1{}ORDER BY modlog_event.id DESC LIMIT $2", if let Some(page) = &page {
+    if let Some(community) = &community {
+        values.push(community);
+        write!(sql, " AND modlog_event.by_community=${}", values.len()).unwrap();
+    }
+    if let Some(page) = &page {
         values.push(page);
+        write!(sql, " AND modlog_event.id <= ${}", values.len()).unwrap();
+    }
 
-        " AND modlog_event.id <= $3"
-    } else {
-        ""
-    }), &values).await?;
+    sql.push_str(" ORDER BY modlog_event.id DESC LIMIT $1");
+
+    let sql: &str = &sql;
+    let rows = crate::query::query(&db, sql, &values).await?;
 
     let (rows, next_page) = if rows.len() > query.limit as usize {
         let next_page = format_number_58(rows.last().unwrap().get(0));
@@ -1069,14 +1262,76 @@ async fn route_unstable_communities_modlog_events_list(
                     }
                 });
 
+                let user = super::AuthorColumns::starting_at(15).get(row, &ctx);
+
                 let details = match action {
                     "approve_post" => RespCommunityModlogEventDetails::ApprovePost { post: post? },
                     "reject_post" => RespCommunityModlogEventDetails::RejectPost { post: post? },
+                    "sticky_post" => RespCommunityModlogEventDetails::StickyPost { post: post? },
+                    "unsticky_post" => {
+                        RespCommunityModlogEventDetails::UnstickyPost { post: post? }
+                    }
+                    "lock_post" => RespCommunityModlogEventDetails::LockPost { post: post? },
+                    "unlock_post" => RespCommunityModlogEventDetails::UnlockPost { post: post? },
+                    "add_moderator" => {
+                        RespCommunityModlogEventDetails::AddModerator { user: user? }
+                    }
+                    "remove_moderator" => {
+                        RespCommunityModlogEventDetails::RemoveModerator { user: user? }
+                    }
+                    "mute_user" => RespCommunityModlogEventDetails::MuteUser { user: user? },
+                    "unmute_user" => RespCommunityModlogEventDetails::UnmuteUser { user: user? },
+                    "edit_community" => RespCommunityModlogEventDetails::EditCommunity,
+                    "block_domain" => RespCommunityModlogEventDetails::BlockDomain {
+                        domain: Cow::Borrowed(row.get::<_, Option<_>>(27)?),
+                    },
+                    "unblock_domain" => RespCommunityModlogEventDetails::UnblockDomain {
+                        domain: Cow::Borrowed(row.get::<_, Option<_>>(27)?),
+                    },
                     _ => return None,
                 };
 
+                let community_id: CommunityLocalID = row.get(21);
+                let community_local = row.get(23);
+                let community_ap_id: Option<&str> = row.get(24);
+
+                let reasons_public: bool = row.get(26);
+
                 Some(RespCommunityModlogEvent {
                     time: time.to_rfc3339(),
+                    by: if privileged {
+                        super::AuthorColumns::starting_at(9).get(row, &ctx)
+                    } else {
+                        None
+                    },
+                    reason: if privileged || reasons_public {
+                        row.get::<_, Option<&str>>(8).map(Cow::Borrowed)
+                    } else {
+                        None
+                    },
+                    community: if community.is_none() {
+                        Some(RespMinimalCommunityInfo {
+                            id: community_id,
+                            name: Cow::Borrowed(row.get(22)),
+                            local: community_local,
+                            host: crate::get_actor_host_or_unknown(
+                                community_local,
+                                community_ap_id,
+                                &ctx.local_hostname,
+                            ),
+                            remote_url: if community_local {
+                                Some(Cow::Owned(String::from(
+                                    crate::apub_util::LocalObjectRef::Community(community_id)
+                                        .to_local_uri(&ctx.host_url_apub),
+                                )))
+                            } else {
+                                community_ap_id.map(Cow::Borrowed)
+                            },
+                            deleted: row.get(25),
+                        })
+                    } else {
+                        None
+                    },
                     details,
                 })
             })
@@ -1087,6 +1342,33 @@ async fn route_unstable_communities_modlog_events_list(
     crate::json_response(&output)
 }
 
+async fn route_unstable_communities_modlog_events_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community,) = params;
+
+    let privileged = {
+        let db = ctx.db_pool.get().await?;
+
+        match crate::authenticate(&req, &db).await? {
+            None => false,
+            Some(user) => {
+                db.query_opt(
+                    "SELECT 1 FROM community_moderator WHERE community=$1 AND person=$2",
+                    &[&community, &user],
+                )
+                .await?
+                .is_some()
+                    || crate::is_site_admin(&db, user).await?
+            }
+        }
+    };
+
+    list_community_modlog_events(Some(community), privileged, ctx, req).await
+}
+
 async fn route_unstable_communities_unfollow(
     params: (CommunityLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -1103,7 +1385,7 @@ async fn route_unstable_communities_unfollow(
         let row_count = trans
             .execute(
                 "DELETE FROM community_follow WHERE community=$1 AND follower=$2",
-                &[&community, &user.raw()],
+                &[&community, &user],
             )
             .await?;
 
@@ -1111,7 +1393,7 @@ async fn route_unstable_communities_unfollow(
             let id = uuid::Uuid::new_v4();
             trans.execute(
                 "INSERT INTO local_community_follow_undo (id, community, follower) VALUES ($1, $2, $3)",
-                &[&id, &community, &user.raw()],
+                &[&id, &community, &user],
             ).await?;
 
             trans.commit().await?;
@@ -1129,6 +1411,96 @@ async fn route_unstable_communities_unfollow(
     Ok(crate::simple_response(hyper::StatusCode::ACCEPTED, ""))
 }
 
+async fn route_unstable_communities_announcements_create(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_community_exists(community_id, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    #[derive(Deserialize)]
+    struct CommunityAnnouncementCreateBody {
+        post: PostLocalID,
+    }
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: CommunityAnnouncementCreateBody = serde_json::from_slice(&body)?;
+
+    ({
+        let row = db
+            .query_opt(
+                "SELECT 1 FROM community_moderator WHERE community=$1 AND person=$2",
+                &[&community_id, &user],
+            )
+            .await?;
+        match row {
+            None => Err(crate::user_error(
+                hyper::StatusCode::FORBIDDEN,
+                &lang,
+                &lang::must_be_moderator(),
+            )),
+            Some(_) => Ok(()),
+        }
+    })?;
+
+    let post_row = db
+        .query_opt(
+            "SELECT community FROM post WHERE id=$1 AND approved AND NOT deleted",
+            &[&body.post],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
+        })?;
+
+    if community_id != CommunityLocalID(post_row.get(0)) {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::post_not_in_community(),
+        ));
+    }
+
+    let row = db.query_one(
+        "INSERT INTO community_announcement (community, post, created_by, created_at) VALUES ($1, $2, $3, current_timestamp) RETURNING id",
+        &[&community_id, &body.post, &user],
+    ).await?;
+
+    let id: i64 = row.get(0);
+
+    ctx.enqueue_task(&crate::tasks::SendCommunityAnnouncement {
+        announcement: id,
+        after: None,
+    })
+    .await?;
+
+    crate::json_response(&JustID { id })
+}
+
+async fn route_unstable_communities_posts_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    {
+        let lang = crate::get_lang_for_req(&req);
+        let db = ctx.get_db_read().await?;
+
+        require_community_exists(community_id, &db, &lang).await?;
+    }
+
+    super::posts::list_posts(Some(community_id), None, ctx, req).await
+}
+
 async fn route_unstable_communities_posts_patch(
     params: (CommunityLocalID, PostLocalID),
     ctx: Arc<crate::RouteContext>,
@@ -1146,12 +1518,15 @@ async fn route_unstable_communities_posts_patch(
     let user = crate::require_login(&req, &db).await?;
 
     #[derive(Deserialize)]
-    struct CommunityPostEditBody {
+    struct CommunityPostEditBody<'a> {
         approved: Option<bool>,
         sticky: Option<bool>,
+        locked: Option<bool>,
+        flairs: Option<Vec<FlairLocalID>>,
+        reason: Option<Cow<'a, str>>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: CommunityPostEditBody = serde_json::from_slice(&body)?;
 
     ({
@@ -1162,36 +1537,36 @@ async fn route_unstable_communities_posts_patch(
             )
             .await?;
         match row {
-            None => Err(crate::Error::UserError(crate::simple_response(
+            None => Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::community_edit_denied()).into_owned(),
-            ))),
+                &lang,
+                &lang::community_edit_denied(),
+            )),
             Some(_) => Ok(()),
         }
     })?;
 
     let old_row = db
         .query_opt(
-            "SELECT community, approved, local, ap_id, sticky FROM post WHERE id=$1",
+            "SELECT community, approved, local, ap_id, sticky, locked FROM post WHERE id=$1",
             &[&post_id],
         )
         .await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::NOT_FOUND,
-                lang.tr(&lang::no_such_post()).into_owned(),
-            ))
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
         })?;
 
     if community_id != CommunityLocalID(old_row.get(0)) {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::post_not_in_community()).into_owned(),
-        )));
+            &lang,
+            &lang::post_not_in_community(),
+        ));
     }
 
     let old_approved: bool = old_row.get(1);
     let old_sticky: bool = old_row.get(4);
+    let old_locked: bool = old_row.get(5);
 
     let post_ap_id = if old_row.get(2) {
         crate::apub_util::LocalObjectRef::Post(post_id)
@@ -1223,6 +1598,15 @@ async fn route_unstable_communities_posts_patch(
         values.push(sticky);
         write!(sql, "sticky=${}", values.len()).unwrap();
     }
+    if let Some(locked) = &body.locked {
+        if !any_changes {
+            any_changes = true;
+        } else {
+            sql.push(',');
+        }
+        values.push(locked);
+        write!(sql, "locked=${}", values.len()).unwrap();
+    }
 
     if any_changes {
         sql.push_str(" WHERE id=$1");
@@ -1239,7 +1623,27 @@ async fn route_unstable_communities_posts_patch(
                         "reject_post"
                     };
 
-                    trans.execute("INSERT INTO modlog_event (time, by_community, by_person, action, post) VALUES (current_timestamp, $1, $2, $3, $4)", &[&community_id, &user, &action, &post_id]).await?;
+                    trans.execute("INSERT INTO modlog_event (time, by_community, by_person, action, post, reason) VALUES (current_timestamp, $1, $2, $3, $4, $5)", &[&community_id, &user, &action, &post_id, &body.reason]).await?;
+                }
+            }
+
+            if let Some(sticky) = body.sticky {
+                if sticky != old_sticky {
+                    let action = if sticky {
+                        "sticky_post"
+                    } else {
+                        "unsticky_post"
+                    };
+
+                    trans.execute("INSERT INTO modlog_event (time, by_community, by_person, action, post, reason) VALUES (current_timestamp, $1, $2, $3, $4, $5)", &[&community_id, &user, &action, &post_id, &body.reason]).await?;
+                }
+            }
+
+            if let Some(locked) = body.locked {
+                if locked != old_locked {
+                    let action = if locked { "lock_post" } else { "unlock_post" };
+
+                    trans.execute("INSERT INTO modlog_event (time, by_community, by_person, action, post, reason) VALUES (current_timestamp, $1, $2, $3, $4, $5)", &[&community_id, &user, &action, &post_id, &body.reason]).await?;
                 }
             }
 
@@ -1249,7 +1653,7 @@ async fn route_unstable_communities_posts_patch(
         if let Some(approved) = body.approved {
             if approved != old_approved {
                 if approved {
-                    crate::apub_util::spawn_announce_community_post(
+                    crate::apub_util::spawn_enqueue_announce_community_post(
                         community_id,
                         post_id,
                         post_ap_id,
@@ -1268,14 +1672,477 @@ async fn route_unstable_communities_posts_patch(
 
         if let Some(sticky) = body.sticky {
             if sticky != old_sticky {
-                crate::apub_util::spawn_enqueue_send_new_community_update(community_id, ctx);
+                crate::apub_util::spawn_enqueue_send_new_community_update(
+                    community_id,
+                    ctx.clone(),
+                );
             }
         }
     }
 
+    if let Some(flairs) = &body.flairs {
+        let trans = db.transaction().await?;
+        super::flairs::set_post_flairs(post_id, community_id, flairs, &trans, &lang).await?;
+
+        // flairs are federated as tags on the post
+        if old_row.get(2) {
+            ctx.enqueue_task_in_transaction(
+                &trans,
+                &crate::tasks::SendPostUpdate { post: post_id },
+            )
+            .await?;
+        }
+
+        trans.commit().await?;
+
+        ctx.notify_worker()?;
+    }
+
     Ok(crate::empty_response())
 }
 
+fn get_archive_author<'a>(
+    row: &'a tokio_postgres::Row,
+    start_idx: usize,
+    ctx: &'a crate::BaseContext,
+) -> Option<CommunityArchiveAuthor<'a>> {
+    let username: Option<&str> = row.get(start_idx + 1);
+    username.map(|username| {
+        let id = UserLocalID(row.get(start_idx));
+        let local: bool = row.get(start_idx + 2);
+        let ap_id: Option<&str> = row.get(start_idx + 3);
+
+        CommunityArchiveAuthor {
+            username: Cow::Borrowed(username),
+            host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
+            remote_url: if local {
+                Some(Cow::Owned(String::from(
+                    crate::apub_util::LocalObjectRef::User(id).to_local_uri(&ctx.host_url_apub),
+                )))
+            } else {
+                ap_id.map(Cow::Borrowed)
+            },
+        }
+    })
+}
+
+async fn route_unstable_communities_archive_get(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    let row = db.query_opt(
+        "SELECT name, local, description, description_markdown, description_html, EXISTS(SELECT 1 FROM community_moderator WHERE community=community.id AND person=$2) FROM community WHERE id=$1 AND NOT deleted",
+        &[&community_id, &user],
+    ).await?.ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_community())
+    })?;
+
+    if !row.get::<_, bool>(1) {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::community_not_local(),
+        ));
+    }
+
+    if !row.get::<_, bool>(5) && !crate::is_site_admin(&db, user).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::must_be_moderator(),
+        ));
+    }
+
+    let (post_rows, comment_rows) = futures::future::try_join(
+        db.query(
+            "SELECT post.id, post.local, post.ap_id, post.title, post.href, post.content_text, post.content_markdown, post.content_html, post.created, post.sensitive, post.sticky, person.id, person.username, person.local, person.ap_id FROM post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.community=$1 AND post.approved AND NOT post.deleted ORDER BY post.created ASC",
+            &[&community_id],
+        ),
+        db.query(
+            "SELECT reply.id, reply.post, reply.parent, reply.local, reply.ap_id, reply.content_text, reply.content_markdown, reply.content_html, reply.created, reply.sensitive, person.id, person.username, person.local, person.ap_id FROM reply INNER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person ON (person.id = reply.author) WHERE post.community=$1 AND post.approved AND NOT post.deleted AND NOT reply.deleted ORDER BY reply.created ASC",
+            &[&community_id],
+        ),
+    )
+    .await?;
+
+    let posts = post_rows
+        .iter()
+        .map(|row| {
+            let id: PostLocalID = row.get(0);
+            let local: bool = row.get(1);
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(8);
+
+            CommunityArchivePost {
+                id,
+                remote_url: if local {
+                    Some(Cow::Owned(String::from(
+                        crate::apub_util::LocalObjectRef::Post(id).to_local_uri(&ctx.host_url_apub),
+                    )))
+                } else {
+                    row.get::<_, Option<&str>>(2).map(Cow::Borrowed)
+                },
+                author: get_archive_author(row, 11, &ctx),
+                title: Cow::Borrowed(row.get(3)),
+                href: row.get::<_, Option<&str>>(4).map(Cow::Borrowed),
+                content_text: row.get::<_, Option<&str>>(5).map(Cow::Borrowed),
+                content_markdown: row.get::<_, Option<&str>>(6).map(Cow::Borrowed),
+                content_html: row.get::<_, Option<&str>>(7).map(Cow::Borrowed),
+                created: Cow::Owned(created.to_rfc3339()),
+                sensitive: row.get(9),
+                sticky: row.get(10),
+            }
+        })
+        .collect();
+
+    let comments = comment_rows
+        .iter()
+        .map(|row| {
+            let id: CommentLocalID = row.get(0);
+            let local: bool = row.get(3);
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(8);
+
+            CommunityArchiveComment {
+                id,
+                post: row.get(1),
+                parent: row.get::<_, Option<CommentLocalID>>(2),
+                remote_url: if local {
+                    Some(Cow::Owned(String::from(
+                        crate::apub_util::LocalObjectRef::Comment(id)
+                            .to_local_uri(&ctx.host_url_apub),
+                    )))
+                } else {
+                    row.get::<_, Option<&str>>(4).map(Cow::Borrowed)
+                },
+                author: get_archive_author(row, 10, &ctx),
+                content_text: row.get::<_, Option<&str>>(5).map(Cow::Borrowed),
+                content_markdown: row.get::<_, Option<&str>>(6).map(Cow::Borrowed),
+                content_html: row.get::<_, Option<&str>>(7).map(Cow::Borrowed),
+                created: Cow::Owned(created.to_rfc3339()),
+                sensitive: row.get(9),
+            }
+        })
+        .collect();
+
+    let output = CommunityArchive {
+        version: COMMUNITY_ARCHIVE_VERSION,
+        community: CommunityArchiveCommunity {
+            name: Cow::Borrowed(row.get(0)),
+            remote_url: Some(Cow::Owned(String::from(
+                crate::apub_util::LocalObjectRef::Community(community_id)
+                    .to_local_uri(&ctx.host_url_apub),
+            ))),
+            description_text: row.get::<_, Option<&str>>(2).map(Cow::Borrowed),
+            description_markdown: row.get::<_, Option<&str>>(3).map(Cow::Borrowed),
+            description_html: row.get::<_, Option<&str>>(4).map(Cow::Borrowed),
+        },
+        posts,
+        comments,
+    };
+
+    crate::json_response(&output)
+}
+
+async fn route_unstable_communities_import(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    if !crate::is_site_admin(&db, user).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::not_admin(),
+        ));
+    }
+
+    #[derive(Deserialize)]
+    struct CommunitiesImportBody<'a> {
+        name: Option<Cow<'a, str>>,
+        archive: CommunityArchive<'a>,
+    }
+
+    // only available to admins, and archives can be much larger than other requests
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_import_body_size).await?;
+    let body: CommunitiesImportBody<'_> = serde_json::from_slice(&body)?;
+
+    let archive = body.archive;
+
+    if archive.version != COMMUNITY_ARCHIVE_VERSION {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::community_archive_version_unsupported(),
+        ));
+    }
+
+    let name = body.name.unwrap_or_else(|| archive.community.name.clone());
+
+    for ch in name.chars() {
+        if !super::USERNAME_ALLOWED_CHARS.contains(&ch) {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::community_name_disallowed_chars(),
+            ));
+        }
+    }
+
+    let parse_created = |src: &str| {
+        chrono::DateTime::parse_from_rfc3339(src).map_err(|_| {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::community_archive_timestamp_invalid(),
+            )
+        })
+    };
+
+    // Authors are matched by their ActivityPub ID. Anyone who can't be resolved gets recorded as an
+    // archived author instead.
+    let mut authors: HashMap<&str, Option<UserLocalID>> = HashMap::new();
+    for author in archive
+        .posts
+        .iter()
+        .filter_map(|post| post.author.as_ref())
+        .chain(
+            archive
+                .comments
+                .iter()
+                .filter_map(|comment| comment.author.as_ref()),
+        )
+    {
+        if let Some(remote_url) = &author.remote_url {
+            if authors.contains_key(remote_url.deref()) {
+                continue;
+            }
+
+            let id = match remote_url.parse::<url::Url>() {
+                Ok(remote_url) => {
+                    match crate::apub_util::get_or_fetch_user_local_id(&remote_url, &db, &ctx).await
+                    {
+                        Ok(id) => Some(id),
+                        Err(err) => {
+                            log::warn!(
+                                "Failed to resolve archived author {}: {:?}",
+                                remote_url,
+                                err
+                            );
+                            None
+                        }
+                    }
+                }
+                Err(_) => None,
+            };
+
+            authors.insert(remote_url.deref(), id);
+        }
+    }
+
+    {
+        let ids: Vec<UserLocalID> = authors.values().filter_map(|x| *x).collect();
+        let rows = db
+            .query(
+                "SELECT id FROM person WHERE id = ANY($1::BIGINT[])",
+                &[&ids],
+            )
+            .await?;
+        let existing: std::collections::HashSet<_> = rows
+            .into_iter()
+            .map(|row| UserLocalID(row.get(0)))
+            .collect();
+
+        for value in authors.values_mut() {
+            if let Some(id) = value {
+                if !existing.contains(id) {
+                    *value = None;
+                }
+            }
+        }
+    }
+
+    let get_author =
+        |author: Option<&CommunityArchiveAuthor>| -> (Option<UserLocalID>, Option<String>) {
+            match author {
+                None => (None, None),
+                Some(author) => {
+                    match author
+                        .remote_url
+                        .as_deref()
+                        .and_then(|remote_url| authors.get(remote_url).copied().flatten())
+                    {
+                        Some(id) => (Some(id), None),
+                        None => (None, Some(format!("{}@{}", author.username, author.host))),
+                    }
+                }
+            }
+        };
+
+    // rendered the same way as newly created content, other HTML is only sanitized
+    let markdown_sources: Vec<Option<String>> = archive
+        .posts
+        .iter()
+        .map(|post| post.content_markdown.as_deref().map(ToOwned::to_owned))
+        .chain(
+            archive
+                .comments
+                .iter()
+                .map(|comment| comment.content_markdown.as_deref().map(ToOwned::to_owned)),
+        )
+        .collect();
+    let markdown_options = ctx.markdown_options;
+    let mut rendered = tokio::task::spawn_blocking(move || {
+        markdown_sources
+            .into_iter()
+            .map(|md| md.map(|md| crate::render_markdown(&md, markdown_options)))
+            .collect::<Vec<_>>()
+    })
+    .await?
+    .into_iter();
+
+    let mut get_content_html = |content_html: Option<&str>| -> Option<String> {
+        match rendered.next().flatten() {
+            Some(html) => Some(html),
+            None => content_html.map(crate::clean_html),
+        }
+    };
+
+    let post_contents: Vec<_> = archive
+        .posts
+        .iter()
+        .map(|post| get_content_html(post.content_html.as_deref()))
+        .collect();
+    let comment_contents: HashMap<CommentLocalID, _> = archive
+        .comments
+        .iter()
+        .map(|comment| {
+            (
+                comment.id,
+                get_content_html(comment.content_html.as_deref()),
+            )
+        })
+        .collect();
+
+    let rsa = openssl::rsa::Rsa::generate(crate::KEY_BITS)?;
+    let private_key = rsa.private_key_to_pem()?;
+    let public_key = rsa.public_key_to_pem()?;
+
+    let community_id = {
+        let trans = db.transaction().await?;
+
+        trans
+            .execute("INSERT INTO local_actor_name (name) VALUES ($1)", &[&name])
+            .await
+            .map_err(|err| {
+                if err.code() == Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION) {
+                    crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::name_in_use())
+                } else {
+                    err.into()
+                }
+            })?;
+
+        let row = trans
+            .query_one(
+                "INSERT INTO community (name, local, private_key, public_key, created_by, created_local, description, description_markdown, description_html) VALUES ($1, TRUE, $2, $3, $4, current_timestamp, $5, $6, $7) RETURNING id",
+                &[&name, &private_key, &public_key, &user, &archive.community.description_text, &archive.community.description_markdown, &archive.community.description_html],
+            )
+            .await?;
+
+        let community_id: CommunityLocalID = row.get(0);
+
+        trans
+            .execute(
+                "INSERT INTO community_moderator (community, person, created_local) VALUES ($1, $2, current_timestamp)",
+                &[&community_id, &user],
+            )
+            .await?;
+
+        // Imported content keeps its original ActivityPub IDs and isn't treated as local, since it
+        // was written elsewhere. Anything already known here, or without an ID, is skipped.
+        let mut post_ids = HashMap::new();
+        for (post, content_html) in archive.posts.iter().zip(post_contents) {
+            let created = parse_created(&post.created)?;
+            let remote_url = match &post.remote_url {
+                Some(remote_url) => remote_url,
+                None => continue,
+            };
+            let (author, archived_author) = get_author(post.author.as_ref());
+            let href_canonical = post.href.as_deref().map(|href| ctx.canonicalize_href(href));
+
+            let row = trans.query_opt(
+                "INSERT INTO post (author, href, title, created, community, local, ap_id, content_text, content_markdown, content_html, approved, updated_local, sensitive, sticky, archived_author, href_canonical) VALUES ($1, $2, $3, $4, $5, FALSE, $6, $7, $8, $9, TRUE, current_timestamp, $10, $11, $12, $13) ON CONFLICT (ap_id) DO NOTHING RETURNING id",
+                &[&author, &post.href, &post.title, &created, &community_id, &remote_url, &post.content_text, &post.content_markdown, &content_html, &post.sensitive, &post.sticky, &archived_author, &href_canonical],
+            ).await?;
+
+            if let Some(row) = row {
+                post_ids.insert(post.id, PostLocalID(row.get(0)));
+            }
+        }
+
+        let mut comments = archive
+            .comments
+            .iter()
+            .map(|comment| Ok((parse_created(&comment.created)?, comment)))
+            .collect::<Result<Vec<_>, crate::Error>>()?;
+        comments.sort_by_key(|(created, _)| *created);
+
+        let mut comment_ids = HashMap::new();
+        for (created, comment) in comments {
+            let post_id = match post_ids.get(&comment.post) {
+                Some(id) => *id,
+                None => continue,
+            };
+            // replies to comments that weren't imported are dropped rather than moved to the top
+            let parent_id = match comment.parent {
+                None => None,
+                Some(parent) => match comment_ids.get(&parent) {
+                    Some(id) => Some(*id),
+                    None => continue,
+                },
+            };
+            let remote_url = match &comment.remote_url {
+                Some(remote_url) => remote_url,
+                None => continue,
+            };
+
+            let (author, archived_author) = get_author(comment.author.as_ref());
+            let content_html = comment_contents.get(&comment.id).cloned().flatten();
+
+            let row = trans.query_opt(
+                "INSERT INTO reply (post, parent, author, created, local, ap_id, content_text, content_markdown, content_html, sensitive, archived_author) VALUES ($1, $2, $3, $4, FALSE, $5, $6, $7, $8, $9, $10) ON CONFLICT (ap_id) DO NOTHING RETURNING id",
+                &[&post_id, &parent_id, &author, &created, &remote_url, &comment.content_text, &comment.content_markdown, &content_html, &comment.sensitive, &archived_author],
+            ).await?;
+
+            if let Some(row) = row {
+                comment_ids.insert(comment.id, CommentLocalID(row.get(0)));
+            }
+        }
+
+        trans.commit().await?;
+
+        community_id
+    };
+
+    crate::json_response(&serde_json::json!({"community": {"id": community_id}}))
+}
+
+pub fn route_communities_import() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::POST, route_unstable_communities_import)
+}
+
 pub fn route_communities() -> crate::RouteNode<()> {
     crate::RouteNode::new()
         .with_handler_async(hyper::Method::GET, route_unstable_communities_list)
@@ -1285,11 +2152,34 @@ pub fn route_communities() -> crate::RouteNode<()> {
                 .with_handler_async(hyper::Method::DELETE, route_unstable_communities_delete)
                 .with_handler_async(hyper::Method::GET, route_unstable_communities_get)
                 .with_handler_async(hyper::Method::PATCH, route_unstable_communities_patch)
+                .with_child(
+                    "announcements",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_communities_announcements_create,
+                    ),
+                )
+                .with_child(
+                    "archive",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::GET,
+                        route_unstable_communities_archive_get,
+                    ),
+                )
+                .with_child(
+                    "automod_rules",
+                    super::automod::route_communities_automod_rules(),
+                )
+                .with_child("flairs", super::flairs::route_communities_flairs())
                 .with_child(
                     "follow",
                     crate::RouteNode::new()
                         .with_handler_async(hyper::Method::POST, route_unstable_communities_follow),
                 )
+                .with_child(
+                    "follow_requests",
+                    super::follow_requests::route_communities_follow_requests(),
+                )
                 .with_child(
                     "moderators",
                     crate::RouteNode::new()
@@ -1309,6 +2199,7 @@ pub fn route_communities() -> crate::RouteNode<()> {
                                 ),
                         ),
                 )
+                .with_child("mutes", super::mutes::route_communities_mutes())
                 .with_child(
                     "modlog",
                     crate::RouteNode::new().with_child(
@@ -1319,6 +2210,7 @@ pub fn route_communities() -> crate::RouteNode<()> {
                         ),
                     ),
                 )
+                .with_child("modqueue", super::modqueue::route_communities_modqueue())
                 .with_child(
                     "unfollow",
                     crate::RouteNode::new().with_handler_async(
@@ -1328,12 +2220,17 @@ pub fn route_communities() -> crate::RouteNode<()> {
                 )
                 .with_child(
                     "posts",
-                    crate::RouteNode::new().with_child_parse::<PostLocalID, _>(
-                        crate::RouteNode::new().with_handler_async(
-                            hyper::Method::PATCH,
-                            route_unstable_communities_posts_patch,
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_communities_posts_list,
+                        )
+                        .with_child_parse::<PostLocalID, _>(
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::PATCH,
+                                route_unstable_communities_posts_patch,
+                            ),
                         ),
-                    ),
                 ),
         )
 }
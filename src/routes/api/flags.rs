@@ -1,34 +1,29 @@
 use crate::lang;
 use crate::types::{
-    CommunityLocalID, FlagLocalID, JustContentText, PostLocalID, RespAvatarInfo, RespFlagDetails,
-    RespFlagInfo, RespList, RespMinimalAuthorInfo, RespMinimalCommunityInfo, RespPostListPost,
-    UserLocalID,
+    CommentLocalID, CommunityLocalID, FlagCategory, FlagLocalID, JustContentText, PostLocalID,
+    RespAvatarInfo, RespFlagCategoryStat, RespFlagDetails, RespFlagInfo, RespList,
+    RespMinimalAuthorInfo, RespMinimalCommentInfo, RespMinimalCommunityInfo, RespMinimalPostInfo,
+    RespPostListPost, UserLocalID,
 };
 use serde::Deserialize;
 use std::borrow::Cow;
 use std::sync::Arc;
 
-async fn route_unstable_flags_list(
-    _: (),
-    ctx: Arc<crate::RouteContext>,
-    req: hyper::Request<hyper::Body>,
-) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    use std::fmt::Write;
-
-    #[derive(Deserialize)]
-    struct FlagsListQuery {
-        to_this_site_admin: Option<bool>,
-        to_community: Option<CommunityLocalID>,
-        dismissed: Option<bool>,
-    }
-
-    let query: FlagsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
-
-    let lang = crate::get_lang_for_req(&req);
-    let db = ctx.db_pool.get().await?;
-
-    let user = crate::require_login(&req, &db).await?;
+#[derive(Deserialize)]
+struct FlagsListQuery {
+    to_this_site_admin: Option<bool>,
+    to_community: Option<CommunityLocalID>,
+    dismissed: Option<bool>,
+    category: Option<FlagCategory>,
+    rule: Option<i32>,
+}
 
+async fn check_flags_access(
+    query: &FlagsListQuery,
+    user: UserLocalID,
+    lang: &crate::Translator,
+    db: &tokio_postgres::Client,
+) -> Result<(), crate::Error> {
     match query.to_community {
         Some(community_id) => {
             let row = db
@@ -42,10 +37,11 @@ async fn route_unstable_flags_list(
                     if crate::is_site_admin(&db, user).await? {
                         Ok(())
                     } else {
-                        Err(crate::Error::UserError(crate::simple_response(
+                        Err(crate::user_error(
                             hyper::StatusCode::FORBIDDEN,
-                            lang.tr(&lang::must_be_moderator()).into_owned(),
-                        )))
+                            lang,
+                            &lang::must_be_moderator(),
+                        ))
                     }
                 }
                 Some(_) => Ok(()),
@@ -55,22 +51,31 @@ async fn route_unstable_flags_list(
             if crate::is_site_admin(&db, user).await? {
                 Ok(())
             } else {
-                Err(crate::Error::UserError(crate::simple_response(
+                Err(crate::user_error(
                     hyper::StatusCode::FORBIDDEN,
-                    lang.tr(&lang::not_admin()).into_owned(),
-                )))
+                    lang,
+                    &lang::not_admin(),
+                ))
             }
         }
-    }?;
+    }
+}
 
-    let mut sql = "SELECT flag.kind, flag.id, flag.content_text, flag.created_local, flagger.id, flagger.local, flagger.username, flagger.ap_id, flagger.avatar, flagger.is_bot, post.id, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, post_author.id, post_author.username, post_author.local, post_author.ap_id, post_author.avatar, (SELECT COUNT(*) FROM post_like WHERE post_like.post = post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, post_author.is_bot, post.ap_id, post.local, post.approved, community.id, community.name, community.local, community.ap_id, community.deleted, post.sensitive FROM flag INNER JOIN person AS flagger ON (flagger.id = flag.person) LEFT OUTER JOIN post ON (post.id = flag.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN community ON (community.id = post.community) WHERE TRUE".to_owned();
-    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![];
+// Appends filter conditions for the query to `sql`, which must already contain a WHERE clause
+fn add_flags_filters<'a>(
+    query: &'a FlagsListQuery,
+    category_str: &'a Option<&'static str>,
+    sql: &mut String,
+    values: &mut Vec<&'a (dyn postgres_types::ToSql + Sync)>,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    use std::fmt::Write;
 
     if let Some(to_community) = &query.to_community {
         values.push(to_community);
         write!(
             sql,
-            " AND flag.to_community=TRUE AND post.community=${}",
+            " AND flag.to_community=TRUE AND COALESCE(post.community, reply_post.community)=${}",
             values.len()
         )
         .unwrap();
@@ -82,29 +87,62 @@ async fn route_unstable_flags_list(
         values.push(dismissed);
         if query.to_community.is_some() {
             if query.to_this_site_admin == Some(true) {
-                return Err(crate::Error::UserError(crate::simple_response(
+                return Err(crate::user_error(
                     hyper::StatusCode::BAD_REQUEST,
-                    "Cannot filter by dismissal with multiple target filters",
-                )));
+                    lang,
+                    &lang::flags_dismissed_filter_ambiguous(),
+                ));
             }
 
             write!(sql, " AND to_community_dismissed=").unwrap();
         } else if query.to_this_site_admin == Some(true) {
             write!(sql, " AND to_site_admin_dismissed=").unwrap();
         } else {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Cannot filter by dismissal without target filter",
-            )));
+                lang,
+                &lang::flags_dismissed_filter_needs_target(),
+            ));
         }
 
         write!(sql, "${}", values.len()).unwrap();
     }
+    if let Some(category) = category_str {
+        values.push(category);
+        write!(sql, " AND flag.category=${}", values.len()).unwrap();
+    }
+    if let Some(rule) = &query.rule {
+        values.push(rule);
+        write!(sql, " AND flag.category_rule=${}", values.len()).unwrap();
+    }
+
+    Ok(())
+}
+
+async fn route_unstable_flags_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let query: FlagsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    check_flags_access(&query, user, &lang, &db).await?;
+
+    let mut sql = "SELECT flag.kind, flag.id, flag.content_text, flag.created_local, flagger.id, flagger.local, flagger.username, flagger.ap_id, flagger.avatar, flagger.is_bot, post.id, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, post_author.id, post_author.username, post_author.local, post_author.ap_id, post_author.avatar, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, post_author.is_bot, post.ap_id, post.local, post.approved, community.id, community.name, community.local, community.ap_id, community.deleted, post.sensitive, community.hide_scores_minutes, flag.category, flag.category_rule, flag.automod_rule, reply.id, reply.content_text, reply.content_html, reply.local, reply.ap_id, reply.sensitive, reply_post.id, reply_post.title, reply_post.local, reply_post.ap_id, reply_post.sensitive, reply_author.id, reply_author.username, reply_author.local, reply_author.ap_id, reply_author.avatar, reply_author.is_bot FROM flag LEFT OUTER JOIN person AS flagger ON (flagger.id = flag.person) LEFT OUTER JOIN post ON (post.id = flag.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN reply ON (reply.id = flag.reply) LEFT OUTER JOIN post AS reply_post ON (reply_post.id = reply.post) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) LEFT OUTER JOIN community ON (community.id = COALESCE(post.community, reply_post.community)) WHERE TRUE".to_owned();
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![];
+
+    let category_str = query.category.map(|x| x.as_str());
+    add_flags_filters(&query, &category_str, &mut sql, &mut values, &lang)?;
 
     sql.push_str(" ORDER BY flag.id DESC LIMIT 30");
 
     let sql: &str = &sql;
-    let rows = db.query(sql, &values).await?;
+    let rows = crate::query::query(&db, sql, &values).await?;
 
     let items: Vec<_> = rows
         .iter()
@@ -117,7 +155,7 @@ async fn route_unstable_flags_list(
                         let post_ap_id: Option<&str> = row.get(26);
                         let post_local = row.get(27);
 
-                        let author_id = UserLocalID(row.get(17));
+                        let author_id: UserLocalID = row.get(17);
                         let author_local = row.get(19);
                         let author_ap_id: Option<&str> = row.get(20);
                         let author_avatar: Option<&str> = row.get(21);
@@ -145,7 +183,7 @@ async fn route_unstable_flags_list(
                             is_bot: row.get(25),
                         };
 
-                        let community_id = CommunityLocalID(row.get(29));
+                        let community_id: CommunityLocalID = row.get(29);
                         let community_local = row.get(31);
                         let community_ap_id: Option<&str> = row.get(32);
 
@@ -181,7 +219,7 @@ async fn route_unstable_flags_list(
                                 .map(|html| crate::clean_html(&html)),
                             title: Cow::Borrowed(row.get(13)),
                             created: post_created.to_rfc3339().into(),
-                            score: row.get(22),
+                            score: super::get_resp_score(row.get(22), &post_created, row.get(35)),
                             replies_count_total: Some(row.get(23)),
                             sensitive: row.get(34),
                             sticky: row.get(24),
@@ -195,6 +233,8 @@ async fn route_unstable_flags_list(
                                 post_ap_id.map(Cow::Borrowed)
                             },
                             your_vote: None,
+                            flairs: None,
+                            thumbnail: None,
                             relevance: None,
                             community: Cow::Owned(community),
                         };
@@ -204,6 +244,84 @@ async fn route_unstable_flags_list(
                         None
                     }
                 }
+                "comment" => {
+                    if let Some(comment_id) = row.get(39) {
+                        let comment_id = CommentLocalID(comment_id);
+                        let comment_local: bool = row.get(42);
+                        let comment_ap_id: Option<&str> = row.get(43);
+
+                        let post_id = PostLocalID(row.get(45));
+                        let post_local: bool = row.get(47);
+                        let post_ap_id: Option<&str> = row.get(48);
+
+                        let author = row.get::<_, Option<UserLocalID>>(50).map(|author_id| {
+                            let author_local = row.get(52);
+                            let author_ap_id: Option<&str> = row.get(53);
+                            let author_avatar: Option<&str> = row.get(54);
+
+                            RespMinimalAuthorInfo {
+                                id: author_id,
+                                username: Cow::Borrowed(row.get(51)),
+                                local: author_local,
+                                host: crate::get_actor_host_or_unknown(
+                                    author_local,
+                                    author_ap_id,
+                                    &ctx.local_hostname,
+                                ),
+                                remote_url: if author_local {
+                                    Some(Cow::Owned(String::from(
+                                        crate::apub_util::LocalObjectRef::User(author_id)
+                                            .to_local_uri(&ctx.host_url_apub),
+                                    )))
+                                } else {
+                                    author_ap_id.map(Cow::Borrowed)
+                                },
+                                avatar: author_avatar.map(|url| RespAvatarInfo {
+                                    url: ctx
+                                        .process_avatar_href(url, author_id)
+                                        .into_owned()
+                                        .into(),
+                                }),
+                                is_bot: row.get(55),
+                            }
+                        });
+
+                        Some(RespFlagDetails::Comment {
+                            comment: RespMinimalCommentInfo {
+                                id: comment_id,
+                                remote_url: if comment_local {
+                                    Some(Cow::Owned(String::from(
+                                        crate::apub_util::LocalObjectRef::Comment(comment_id)
+                                            .to_local_uri(&ctx.host_url_apub),
+                                    )))
+                                } else {
+                                    comment_ap_id.map(Cow::Borrowed)
+                                },
+                                sensitive: row.get(44),
+                                content_text: row.get::<_, Option<&str>>(40).map(Cow::Borrowed),
+                                content_html_safe: row
+                                    .get::<_, Option<&str>>(41)
+                                    .map(crate::clean_html),
+                            },
+                            post: RespMinimalPostInfo {
+                                id: post_id,
+                                title: row.get(46),
+                                remote_url: if post_local {
+                                    Some(Cow::Owned(String::from(
+                                        crate::apub_util::LocalObjectRef::Post(post_id)
+                                            .to_local_uri(&ctx.host_url_apub),
+                                    )))
+                                } else {
+                                    post_ap_id.map(Cow::Borrowed)
+                                },
+                                sensitive: row.get(49),
+                            },
+                            author,
+                        })
+                    } else {
+                        None
+                    }
+                }
                 _ => None,
             };
             match details {
@@ -211,37 +329,38 @@ async fn route_unstable_flags_list(
                 Some(details) => {
                     let created_local: chrono::DateTime<chrono::FixedOffset> = row.get(3);
 
-                    let flagger_id = UserLocalID(row.get(4));
-                    let flagger_local = row.get(5);
-                    let flagger_ap_id: Option<&str> = row.get(7);
-                    let flagger_avatar: Option<&str> = row.get(8);
-
-                    let flagger = RespMinimalAuthorInfo {
-                        id: flagger_id,
-                        username: Cow::Borrowed(row.get(6)),
-                        local: flagger_local,
-                        host: crate::get_actor_host_or_unknown(
-                            flagger_local,
-                            flagger_ap_id.as_deref(),
-                            &ctx.local_hostname,
-                        ),
-                        remote_url: if flagger_local {
-                            Some(Cow::Owned(String::from(
-                                crate::apub_util::LocalObjectRef::User(flagger_id)
-                                    .to_local_uri(&ctx.host_url_apub),
-                            )))
-                        } else {
-                            flagger_ap_id.map(Cow::Borrowed)
-                        },
-                        avatar: flagger_avatar.map(|url| RespAvatarInfo {
-                            url: ctx.process_avatar_href(url, flagger_id).into_owned().into(),
-                        }),
-                        is_bot: row.get(9),
-                    };
+                    let flagger = row.get::<_, Option<UserLocalID>>(4).map(|flagger_id| {
+                        let flagger_local = row.get(5);
+                        let flagger_ap_id: Option<&str> = row.get(7);
+                        let flagger_avatar: Option<&str> = row.get(8);
+
+                        RespMinimalAuthorInfo {
+                            id: flagger_id,
+                            username: Cow::Borrowed(row.get(6)),
+                            local: flagger_local,
+                            host: crate::get_actor_host_or_unknown(
+                                flagger_local,
+                                flagger_ap_id.as_deref(),
+                                &ctx.local_hostname,
+                            ),
+                            remote_url: if flagger_local {
+                                Some(Cow::Owned(String::from(
+                                    crate::apub_util::LocalObjectRef::User(flagger_id)
+                                        .to_local_uri(&ctx.host_url_apub),
+                                )))
+                            } else {
+                                flagger_ap_id.map(Cow::Borrowed)
+                            },
+                            avatar: flagger_avatar.map(|url| RespAvatarInfo {
+                                url: ctx.process_avatar_href(url, flagger_id).into_owned().into(),
+                            }),
+                            is_bot: row.get(9),
+                        }
+                    });
 
                     Some(RespFlagInfo {
                         details,
-                        id: FlagLocalID(row.get(1)),
+                        id: row.get(1),
                         content: row.get::<_, Option<&str>>(2).map(|content_text| {
                             JustContentText {
                                 content_text: content_text.into(),
@@ -249,6 +368,9 @@ async fn route_unstable_flags_list(
                         }),
                         created_local: created_local.to_rfc3339(),
                         flagger,
+                        automod_rule: row.get(38),
+                        category: row.get::<_, Option<&str>>(36).and_then(|x| x.parse().ok()),
+                        rule: row.get(37),
                     })
                 }
             }
@@ -263,6 +385,43 @@ async fn route_unstable_flags_list(
     crate::json_response(&output)
 }
 
+async fn route_unstable_flags_stats(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let query: FlagsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    check_flags_access(&query, user, &lang, &db).await?;
+
+    let mut sql = "SELECT flag.category, flag.category_rule, COUNT(*) FROM flag LEFT OUTER JOIN post ON (post.id = flag.post) LEFT OUTER JOIN reply ON (reply.id = flag.reply) LEFT OUTER JOIN post AS reply_post ON (reply_post.id = reply.post) WHERE TRUE".to_owned();
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![];
+
+    let category_str = query.category.map(|x| x.as_str());
+    add_flags_filters(&query, &category_str, &mut sql, &mut values, &lang)?;
+
+    sql.push_str(" GROUP BY flag.category, flag.category_rule ORDER BY COUNT(*) DESC");
+
+    let sql: &str = &sql;
+    let rows = crate::query::query(&db, sql, &values).await?;
+
+    let output: Vec<_> = rows
+        .iter()
+        .map(|row| RespFlagCategoryStat {
+            category: row.get::<_, Option<&str>>(0).and_then(|x| x.parse().ok()),
+            rule: row.get(1),
+            count: row.get(2),
+        })
+        .collect();
+
+    crate::json_response(&output)
+}
+
 async fn route_unstable_flags_edit(
     params: (FlagLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -280,7 +439,7 @@ async fn route_unstable_flags_edit(
         community_dismissed: Option<bool>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     let body: Body = serde_json::from_slice(&body)?;
 
@@ -299,16 +458,17 @@ async fn route_unstable_flags_edit(
     }
 
     if needs_community_mod {
-        let row = db.query_opt("SELECT community FROM flag LEFT OUTER JOIN post ON (post.id = flag.post) WHERE flag.id = $1", &[&flag_id]).await?;
+        let row = db.query_opt("SELECT COALESCE(post.community, reply_post.community) FROM flag LEFT OUTER JOIN post ON (post.id = flag.post) LEFT OUTER JOIN reply ON (reply.id = flag.reply) LEFT OUTER JOIN post AS reply_post ON (reply_post.id = reply.post) WHERE flag.id = $1", &[&flag_id]).await?;
         match row {
             None => return Ok(crate::empty_response()),
             Some(row) => {
-                let community_id = row.get::<_, Option<_>>(0).map(CommunityLocalID);
+                let community_id = row.get::<_, Option<CommunityLocalID>>(0);
                 match community_id {
-                    None => Err(crate::Error::UserError(crate::simple_response(
+                    None => Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        "Unknown community for flag",
-                    ))),
+                        &lang,
+                        &lang::flag_community_unknown(),
+                    )),
                     Some(community_id) => {
                         let row = db
                             .query_opt(
@@ -317,10 +477,11 @@ async fn route_unstable_flags_edit(
                             )
                             .await?;
                         match row {
-                            None => Err(crate::Error::UserError(crate::simple_response(
+                            None => Err(crate::user_error(
                                 hyper::StatusCode::FORBIDDEN,
-                                lang.tr(&lang::must_be_moderator()).into_owned(),
-                            ))),
+                                &lang,
+                                &lang::must_be_moderator(),
+                            )),
                             Some(_) => Ok(()),
                         }
                     }
@@ -360,6 +521,10 @@ async fn route_unstable_flags_edit(
     Ok(crate::empty_response())
 }
 
+pub fn route_flags_stats() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_handler_async(hyper::Method::GET, route_unstable_flags_stats)
+}
+
 pub fn route_flags() -> crate::RouteNode<()> {
     crate::RouteNode::new()
         .with_handler_async(hyper::Method::GET, route_unstable_flags_list)
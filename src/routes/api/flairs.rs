@@ -0,0 +1,220 @@
+use crate::lang;
+use crate::types::{CommunityLocalID, FlairLocalID, PostLocalID, RespFlairInfo, RespList};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const MAX_FLAIR_NAME_LENGTH: usize = 64;
+
+fn validate_flair_name<'a>(
+    name: &'a str,
+    lang: &crate::Translator,
+) -> Result<&'a str, crate::Error> {
+    let name = name.trim();
+    let len = name.chars().count();
+
+    if len == 0 || len > MAX_FLAIR_NAME_LENGTH {
+        Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            lang,
+            &lang::flair_name_invalid(MAX_FLAIR_NAME_LENGTH),
+        ))
+    } else {
+        Ok(name)
+    }
+}
+
+fn map_flair_name_conflict(err: tokio_postgres::Error, lang: &crate::Translator) -> crate::Error {
+    match err.as_db_error() {
+        Some(db_err)
+            if db_err.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION
+                && db_err.constraint() == Some("community_flair_community_name_key") =>
+        {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                lang,
+                &lang::flair_name_taken(),
+            )
+        }
+        _ => err.into(),
+    }
+}
+
+fn no_such_flair(lang: &crate::Translator) -> crate::Error {
+    crate::user_error(hyper::StatusCode::NOT_FOUND, lang, &lang::no_such_flair())
+}
+
+/// Replaces the flairs on a post, returning the names of the new set.
+///
+/// All flairs must belong to the post's community.
+pub async fn set_post_flairs(
+    post: PostLocalID,
+    community: CommunityLocalID,
+    flairs: &[FlairLocalID],
+    db: &tokio_postgres::Transaction<'_>,
+    lang: &crate::Translator,
+) -> Result<Vec<String>, crate::Error> {
+    let mut flairs = flairs.to_vec();
+    flairs.sort_unstable_by_key(|flair| flair.raw());
+    flairs.dedup();
+
+    db.execute("DELETE FROM post_flair WHERE post=$1", &[&post])
+        .await?;
+
+    if flairs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows = db.query(
+        "WITH inserted AS (INSERT INTO post_flair (post, flair) SELECT $1, id FROM community_flair WHERE community=$2 AND id = ANY($3) RETURNING flair) SELECT community_flair.name FROM inserted INNER JOIN community_flair ON (community_flair.id = inserted.flair) ORDER BY community_flair.name",
+        &[&post, &community, &flairs],
+    ).await?;
+
+    if rows.len() != flairs.len() {
+        return Err(no_such_flair(lang));
+    }
+
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+async fn route_unstable_communities_flairs_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    let db = ctx.get_db_read().await?;
+
+    let rows = db
+        .query(
+            "SELECT id, name FROM community_flair WHERE community=$1 ORDER BY name",
+            &[&community_id],
+        )
+        .await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| RespFlairInfo {
+            id: row.get(0),
+            name: Cow::Borrowed(row.get(1)),
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn route_unstable_communities_flairs_create(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    #[derive(Deserialize)]
+    struct FlairsCreateBody<'a> {
+        name: Cow<'a, str>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_community_moderator(community_id, &req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: FlairsCreateBody = serde_json::from_slice(&body)?;
+
+    let name = validate_flair_name(&body.name, &lang)?;
+
+    let row = db
+        .query_one(
+            "INSERT INTO community_flair (community, name, created_at) VALUES ($1, $2, current_timestamp) RETURNING id",
+            &[&community_id, &name],
+        )
+        .await
+        .map_err(|err| map_flair_name_conflict(err, &lang))?;
+
+    let id: FlairLocalID = row.get(0);
+
+    crate::json_response(&serde_json::json!({ "id": id }))
+}
+
+async fn route_unstable_communities_flairs_edit(
+    params: (CommunityLocalID, FlairLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, flair_id) = params;
+
+    #[derive(Deserialize)]
+    struct FlairsEditBody<'a> {
+        name: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_community_moderator(community_id, &req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: FlairsEditBody = serde_json::from_slice(&body)?;
+
+    let name = match &body.name {
+        Some(name) => Some(validate_flair_name(name, &lang)?),
+        None => None,
+    };
+
+    let row_count = db
+        .execute(
+            "UPDATE community_flair SET name=COALESCE($3, name) WHERE id=$1 AND community=$2",
+            &[&flair_id, &community_id, &name],
+        )
+        .await
+        .map_err(|err| map_flair_name_conflict(err, &lang))?;
+
+    if row_count == 0 {
+        return Err(no_such_flair(&lang));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_communities_flairs_delete(
+    params: (CommunityLocalID, FlairLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, flair_id) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_community_moderator(community_id, &req, &db).await?;
+
+    db.execute(
+        "DELETE FROM community_flair WHERE id=$1 AND community=$2",
+        &[&flair_id, &community_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+pub fn route_communities_flairs() -> crate::RouteNode<(CommunityLocalID,)> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_communities_flairs_list)
+        .with_handler_async(
+            hyper::Method::POST,
+            route_unstable_communities_flairs_create,
+        )
+        .with_child_parse::<FlairLocalID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::PATCH, route_unstable_communities_flairs_edit)
+                .with_handler_async(
+                    hyper::Method::DELETE,
+                    route_unstable_communities_flairs_delete,
+                ),
+        )
+}
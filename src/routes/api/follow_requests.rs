@@ -0,0 +1,260 @@
+use crate::lang;
+use crate::types::{
+    CommentLocalID, CommunityLocalID, FollowState, JustUser, PostLocalID, RespList, UserLocalID,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+async fn require_can_moderate(
+    community: CommunityLocalID,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+) -> Result<UserLocalID, crate::Error> {
+    let lang = crate::get_lang_for_req(req);
+
+    let user = crate::require_login(req, db).await?;
+
+    let row = db
+        .query_opt(
+            "SELECT local FROM community WHERE id=$1 AND NOT deleted",
+            &[&community],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_community(),
+            )
+        })?;
+
+    if !row.get::<_, bool>(0) {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::community_not_local(),
+        ));
+    }
+
+    if super::can_moderate_community(db, user, community).await? {
+        Ok(user)
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::must_be_moderator(),
+        ))
+    }
+}
+
+async fn check_can_post(
+    user: UserLocalID,
+    community_expr: &str,
+    param: &(dyn postgres_types::ToSql + Sync),
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let sql: &str = &format!("SELECT id FROM community WHERE id=({})", community_expr);
+
+    let community = match db.query_opt(sql, &[param]).await? {
+        None => return Ok(()),
+        Some(row) => row.get(0),
+    };
+
+    if crate::can_post_in_community(db, user, community).await? {
+        Ok(())
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            lang,
+            &lang::community_members_only(),
+        ))
+    }
+}
+
+pub async fn require_can_post(
+    user: UserLocalID,
+    community: CommunityLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    if crate::can_post_in_community(db, user, community).await? {
+        Ok(())
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            lang,
+            &lang::community_members_only(),
+        ))
+    }
+}
+
+pub async fn require_can_post_for_post(
+    user: UserLocalID,
+    post: PostLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    check_can_post(
+        user,
+        "SELECT community FROM post WHERE id=$1",
+        &post,
+        db,
+        lang,
+    )
+    .await
+}
+
+pub async fn require_can_post_for_comment(
+    user: UserLocalID,
+    comment: CommentLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    check_can_post(
+        user,
+        "SELECT post.community FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.id=$1",
+        &comment,
+        db,
+        lang,
+    )
+    .await
+}
+
+fn no_such_follow_request(lang: &crate::Translator) -> crate::Error {
+    crate::user_error(
+        hyper::StatusCode::NOT_FOUND,
+        lang,
+        &lang::no_such_follow_request(),
+    )
+}
+
+async fn route_unstable_communities_follow_requests_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    require_can_moderate(community_id, &req, &db).await?;
+
+    let rows = db
+        .query(
+            "SELECT person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot FROM community_follow INNER JOIN person ON (person.id = community_follow.follower) WHERE community_follow.community=$1 AND community_follow.state='pending' ORDER BY person.id",
+            &[&community_id],
+        )
+        .await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .filter_map(|row| {
+            Some(JustUser {
+                user: super::AuthorColumns::starting_at(0).get(row, &ctx)?,
+            })
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn route_unstable_communities_follow_requests_approve(
+    params: (CommunityLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, follower) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_can_moderate(community_id, &req, &db).await?;
+
+    let info = crate::follows::transition_community_follow(
+        &db,
+        community_id,
+        follower,
+        FollowState::Accepted,
+    )
+    .await?
+    .ok_or_else(|| no_such_follow_request(&lang))?;
+
+    if !info.local {
+        if let Some(follow_ap_id) = info.ap_id {
+            crate::apub_util::spawn_enqueue_send_community_follow_response(
+                community_id,
+                follower,
+                follow_ap_id.parse()?,
+                true,
+                ctx,
+            );
+        }
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_communities_follow_requests_reject(
+    params: (CommunityLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, follower) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_can_moderate(community_id, &req, &db).await?;
+
+    let info = crate::follows::transition_community_follow(
+        &db,
+        community_id,
+        follower,
+        FollowState::Rejected,
+    )
+    .await?
+    .ok_or_else(|| no_such_follow_request(&lang))?;
+
+    if !info.local {
+        if let Some(follow_ap_id) = info.ap_id {
+            crate::apub_util::spawn_enqueue_send_community_follow_response(
+                community_id,
+                follower,
+                follow_ap_id.parse()?,
+                false,
+                ctx,
+            );
+        }
+    }
+
+    Ok(crate::empty_response())
+}
+
+pub fn route_communities_follow_requests() -> crate::RouteNode<(CommunityLocalID,)> {
+    crate::RouteNode::new()
+        .with_handler_async(
+            hyper::Method::GET,
+            route_unstable_communities_follow_requests_list,
+        )
+        .with_child_parse::<UserLocalID, _>(
+            crate::RouteNode::new()
+                .with_child(
+                    "approve",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_communities_follow_requests_approve,
+                    ),
+                )
+                .with_child(
+                    "reject",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_communities_follow_requests_reject,
+                    ),
+                ),
+        )
+}
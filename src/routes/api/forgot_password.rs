@@ -14,10 +14,11 @@ async fn route_unstable_forgot_password_keys_create(
     let lang = crate::get_lang_for_req(&req);
 
     if ctx.mailer.is_none() {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::INTERNAL_SERVER_ERROR,
-            lang.tr(&lang::email_not_configured()).into_owned(),
-        )));
+            &lang,
+            &lang::email_not_configured(),
+        ));
     }
 
     #[derive(Deserialize)]
@@ -25,20 +26,17 @@ async fn route_unstable_forgot_password_keys_create(
         email_address: Cow<'a, str>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: ForgotPasswordBody = serde_json::from_slice(&body)?;
 
     let db = ctx.db_pool.get().await?;
 
     let user_row = db.query_opt("SELECT id, username, email_address FROM person WHERE local AND LOWER(email_address) = LOWER($1)", &[&body.email_address]).await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::no_such_local_user_by_email()).into_owned(),
-            ))
+            crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::no_such_local_user_by_email())
         })?;
 
-    let user_id = UserLocalID(user_row.get(0));
+    let user_id: UserLocalID = user_row.get(0);
     let username: &str = user_row.get(1);
     let user_email: &str = user_row.get(2);
 
@@ -100,10 +98,11 @@ async fn route_unstable_forgot_password_keys_get(
             .header(hyper::header::CONTENT_TYPE, "application/json")
             .body("{}".into())?)
     } else {
-        Err(crate::Error::UserError(crate::simple_response(
+        Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_forgot_password_key()).into_owned(),
-        )))
+            &lang,
+            &lang::no_such_forgot_password_key(),
+        ))
     }
 }
 
@@ -121,7 +120,7 @@ async fn route_unstable_forgot_password_keys_reset(
 
     let lang = crate::get_lang_for_req(&req);
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: PasswordResetBody = serde_json::from_slice(&body)?;
 
     let mut db = ctx.db_pool.get().await?;
@@ -141,8 +140,9 @@ async fn route_unstable_forgot_password_keys_reset(
 
     match user_id {
         Some(user_id) => {
+            let params = ctx.password_hash_params.clone();
             let passhash = tokio::task::spawn_blocking(move || {
-                bcrypt::hash(body.new_password, bcrypt::DEFAULT_COST)
+                crate::passwords::hash(&body.new_password, &params)
             })
             .await??;
 
@@ -168,10 +168,11 @@ async fn route_unstable_forgot_password_keys_reset(
                 .header(hyper::header::CONTENT_TYPE, "application/json")
                 .body("{}".into())?)
         }
-        None => Err(crate::Error::UserError(crate::simple_response(
+        None => Err(crate::user_error(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_forgot_password_key()).into_owned(),
-        ))),
+            &lang,
+            &lang::no_such_forgot_password_key(),
+        )),
     }
 }
 
@@ -0,0 +1,36 @@
+use crate::lang;
+use std::sync::Arc;
+
+async fn route_unstable_hashtags_posts_list(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (name,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+
+    let hashtag = percent_encoding::percent_decode_str(&name)
+        .decode_utf8()
+        .ok()
+        .and_then(|name| crate::normalize_hashtag(&name))
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::hashtag_invalid(),
+            )
+        })?;
+
+    super::posts::list_posts(None, Some(hashtag), ctx, req).await
+}
+
+pub fn route_hashtags() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_child_str(
+        crate::RouteNode::new().with_child(
+            "posts",
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::GET, route_unstable_hashtags_posts_list),
+        ),
+    )
+}
@@ -0,0 +1,401 @@
+use super::{AuthorColumns, InvalidPage};
+use crate::lang;
+use crate::types::{RespAdminUserInfo, RespList, UserLocalID};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Returns whether the user is local, or an error if they don't exist
+async fn get_user_local(
+    user: UserLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<bool, crate::Error> {
+    let row = db
+        .query_opt(
+            "SELECT local FROM person WHERE id=$1 AND NOT deleted",
+            &[&user],
+        )
+        .await?;
+
+    match row {
+        None => Err(crate::user_error(
+            hyper::StatusCode::NOT_FOUND,
+            lang,
+            &lang::no_such_user(),
+        )),
+        Some(row) => Ok(row.get(0)),
+    }
+}
+
+async fn route_unstable_instance_users_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct InstanceUsersListQuery<'a> {
+        local: Option<bool>,
+        suspended: Option<bool>,
+        banned: Option<bool>,
+        created_after: Option<Cow<'a, str>>,
+        created_before: Option<Cow<'a, str>>,
+
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let query: InstanceUsersListQuery =
+        serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let parse_timestamp = |value: &Option<Cow<'_, str>>, field: &'static str| {
+        value
+            .as_deref()
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|_| {
+                crate::user_error(
+                    hyper::StatusCode::BAD_REQUEST,
+                    &lang,
+                    &lang::timestamp_invalid(field),
+                )
+            })
+    };
+
+    let created_after = parse_timestamp(&query.created_after, "created_after")?;
+    let created_before = parse_timestamp(&query.created_before, "created_before")?;
+
+    let page = query
+        .page
+        .as_deref()
+        .map(super::parse_number_58)
+        .transpose()
+        .map_err(|_| InvalidPage.into_user_error(&lang))?;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let mut rows = db.query(
+        "SELECT id, username, local, ap_id, avatar, is_bot, created_local, suspended, banned, (SELECT COUNT(*) FROM post WHERE author=person.id AND NOT deleted), (SELECT COUNT(*) FROM reply WHERE author=person.id AND NOT deleted) FROM person WHERE NOT deleted AND ($1::BOOLEAN IS NULL OR local=$1) AND ($2::BOOLEAN IS NULL OR suspended=$2) AND ($3::BOOLEAN IS NULL OR banned=$3) AND ($4::TIMESTAMPTZ IS NULL OR created_local >= $4) AND ($5::TIMESTAMPTZ IS NULL OR created_local < $5) AND ($6::BIGINT IS NULL OR id <= $6) ORDER BY id DESC LIMIT $7",
+        &[&query.local, &query.suspended, &query.banned, &created_after, &created_before, &page, &limit_plus_1],
+    ).await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(Cow::Owned(super::format_number_58(row.get(0))))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows
+        .iter()
+        .filter_map(|row| {
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(6);
+
+            Some(RespAdminUserInfo {
+                base: AuthorColumns::starting_at(0).get(row, &ctx)?,
+                created: created.to_rfc3339(),
+                suspended: row.get(7),
+                banned: row.get(8),
+                post_count: row.get(9),
+                comment_count: row.get(10),
+            })
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page,
+    })
+}
+
+async fn set_suspended(
+    user: UserLocalID,
+    suspended: bool,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let login_user = super::require_site_admin(&req, &db).await?;
+
+    if !get_user_local(user, &db, &lang).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::user_not_local(),
+        ));
+    }
+
+    let trans = db.transaction().await?;
+
+    let changed = trans
+        .execute(
+            "UPDATE person SET suspended=$2 WHERE id=$1 AND suspended <> $2",
+            &[&user, &suspended],
+        )
+        .await?;
+
+    if changed > 0 {
+        super::users::on_suspended_changed(user, suspended, login_user, &trans).await?;
+    }
+
+    trans.commit().await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_instance_users_suspend(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+    set_suspended(user, true, ctx, req).await
+}
+
+async fn route_unstable_instance_users_unsuspend(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+    set_suspended(user, false, ctx, req).await
+}
+
+async fn route_unstable_instance_users_purge_content(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let login_user = super::require_site_admin(&req, &db).await?;
+
+    if get_user_local(user, &db, &lang).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::user_not_remote(),
+        ));
+    }
+
+    let trans = db.transaction().await?;
+
+    purge_user_content(user, &trans).await?;
+
+    trans.execute(
+        "INSERT INTO modlog_event (time, by_person, action, person) VALUES (current_timestamp, $1, 'purge_user_content', $2)",
+        &[&login_user, &user],
+    ).await?;
+
+    trans.commit().await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn purge_user_content(
+    user: UserLocalID,
+    trans: &tokio_postgres::Transaction<'_>,
+) -> Result<(), crate::Error> {
+    trans.execute(
+        "UPDATE post SET had_href=(href IS NOT NULL), href=NULL, href_canonical=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE author=$1 AND NOT deleted",
+        &[&user],
+    ).await?;
+    trans
+        .execute(
+            "DELETE FROM post_attachment WHERE post IN (SELECT id FROM post WHERE author=$1)",
+            &[&user],
+        )
+        .await?;
+    trans.execute(
+        "UPDATE reply SET content_text='[deleted]', content_markdown=NULL, content_html=NULL, attachment_href=NULL, deleted=TRUE WHERE author=$1 AND NOT deleted",
+        &[&user],
+    ).await?;
+
+    trans
+        .execute("DELETE FROM post_like WHERE person=$1", &[&user])
+        .await?;
+    trans
+        .execute("DELETE FROM reply_like WHERE person=$1", &[&user])
+        .await?;
+
+    Ok(())
+}
+
+async fn route_unstable_instance_users_ban(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    #[derive(Deserialize, Default)]
+    struct InstanceUsersBanBody {
+        #[serde(default)]
+        delete_content: bool,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let login_user = super::require_site_admin(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: InstanceUsersBanBody = if body.is_empty() {
+        Default::default()
+    } else {
+        serde_json::from_slice(&body)?
+    };
+
+    let local = get_user_local(user, &db, &lang).await?;
+
+    if local {
+        if body.delete_content {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::user_not_remote(),
+            ));
+        }
+
+        // local bans are suspensions that also drop remote followers
+        let trans = db.transaction().await?;
+
+        let changed = trans
+            .execute(
+                "UPDATE person SET suspended=TRUE WHERE id=$1 AND NOT suspended",
+                &[&user],
+            )
+            .await?;
+
+        if changed > 0 {
+            super::users::on_suspended_changed(user, true, login_user, &trans).await?;
+        }
+
+        trans.commit().await?;
+
+        crate::apub_util::spawn_enqueue_reject_user_followers(user, ctx);
+    } else {
+        let trans = db.transaction().await?;
+
+        let changed = trans
+            .execute(
+                "UPDATE person SET banned=TRUE WHERE id=$1 AND NOT banned",
+                &[&user],
+            )
+            .await?;
+
+        // stop delivering to them
+        trans
+            .execute("DELETE FROM community_follow WHERE follower=$1", &[&user])
+            .await?;
+        trans
+            .execute("DELETE FROM person_follow WHERE follower=$1", &[&user])
+            .await?;
+
+        if body.delete_content {
+            purge_user_content(user, &trans).await?;
+        }
+
+        if changed > 0 {
+            trans.execute(
+                "INSERT INTO modlog_event (time, by_person, action, person) VALUES (current_timestamp, $1, 'ban_user', $2)",
+                &[&login_user, &user],
+            ).await?;
+        }
+
+        trans.commit().await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_instance_users_unban(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let login_user = super::require_site_admin(&req, &db).await?;
+
+    if get_user_local(user, &db, &lang).await? {
+        std::mem::drop(db);
+        return set_suspended(user, false, ctx, req).await;
+    }
+
+    let changed = db
+        .execute(
+            "UPDATE person SET banned=FALSE WHERE id=$1 AND banned",
+            &[&user],
+        )
+        .await?;
+
+    if changed > 0 {
+        db.execute(
+            "INSERT INTO modlog_event (time, by_person, action, person) VALUES (current_timestamp, $1, 'unban_user', $2)",
+            &[&login_user, &user],
+        ).await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+pub fn route_instance_users() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_instance_users_list)
+        .with_child_parse::<UserLocalID, _>(
+            crate::RouteNode::new()
+                .with_child(
+                    "suspend",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_instance_users_suspend,
+                    ),
+                )
+                .with_child(
+                    "unsuspend",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_instance_users_unsuspend,
+                    ),
+                )
+                .with_child(
+                    "ban",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_instance_users_ban),
+                )
+                .with_child(
+                    "unban",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_instance_users_unban,
+                    ),
+                )
+                .with_child(
+                    "purge_content",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_instance_users_purge_content,
+                    ),
+                ),
+        )
+}
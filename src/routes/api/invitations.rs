@@ -11,6 +11,7 @@ async fn route_unstable_invitations_list(
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
     let query: InvitationsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
@@ -22,7 +23,7 @@ async fn route_unstable_invitations_list(
 
                 if let Some(row) = row {
                     let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(2);
-                    let user_id = UserLocalID(row.get(4));
+                    let user_id: UserLocalID = row.get(4);
                     let user_local = row.get(6);
                     let user_ap_id: Option<&str> = row.get(7);
                     let user_avatar: Option<&str> = row.get(9);
@@ -71,10 +72,11 @@ async fn route_unstable_invitations_list(
             }),
         }
     } else {
-        Err(crate::Error::UserError(crate::simple_response(
+        Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            "A query is required",
-        )))
+            &lang,
+            &lang::invitations_query_required(),
+        ))
     }
 }
 
@@ -103,17 +105,19 @@ async fn route_unstable_invitations_create(
                 if crate::is_site_admin(&db, user).await? {
                     Ok(())
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::invitations_not_allowed()).into_owned(),
-                    )))
+                        &lang,
+                        &lang::invitations_not_allowed(),
+                    ))
                 }
             }
         } else {
-            Err(crate::Error::UserError(crate::simple_response(
+            Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::invitations_disabled()).into_owned(),
-            )))
+                &lang,
+                &lang::invitations_disabled(),
+            ))
         }
     }?;
 
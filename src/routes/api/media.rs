@@ -1,7 +1,34 @@
 use crate::lang;
 use futures::TryStreamExt;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+pub fn parse_local_media_href(href: &str) -> Option<i32> {
+    let rest = href.strip_prefix("local-media://")?;
+    let media_id: crate::Pineapple = rest.parse().ok()?;
+    Some(media_id.as_int())
+}
+
+/// Finds blurhashes for any of the given hrefs that refer to local media with a generated thumbnail
+pub async fn get_thumbnail_blurhashes<'a>(
+    hrefs: impl Iterator<Item = &'a str>,
+    db: &tokio_postgres::Client,
+) -> Result<HashMap<i32, String>, crate::Error> {
+    let ids: Vec<i32> = hrefs.filter_map(parse_local_media_href).collect();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = db
+        .query(
+            "SELECT id, blurhash FROM media WHERE id = ANY($1) AND thumbnail_path IS NOT NULL AND blurhash IS NOT NULL",
+            &[&ids],
+        )
+        .await?;
+
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
 async fn route_unstable_media_create(
     _: (),
     ctx: Arc<crate::RouteContext>,
@@ -13,19 +40,21 @@ async fn route_unstable_media_create(
         .headers()
         .get(hyper::header::CONTENT_TYPE)
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
+            crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::missing_content_type()).into_owned(),
-            ))
+                &lang,
+                &lang::missing_content_type(),
+            )
         })?;
     let content_type = std::str::from_utf8(content_type.as_ref())?;
     let content_type: mime::Mime = content_type.parse()?;
 
     if content_type.type_() != mime::IMAGE {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::media_upload_not_image()).into_owned(),
-        )));
+            &lang,
+            &lang::media_upload_not_image(),
+        ));
     }
 
     let db = ctx.db_pool.get().await?;
@@ -44,17 +73,21 @@ async fn route_unstable_media_create(
         let id = crate::Pineapple::generate();
 
         db.execute(
-            "INSERT INTO media (id, path, person, mime) VALUES ($1, $2, $3, $4)",
+            "INSERT INTO media (id, path, person, mime, created_at) VALUES ($1, $2, $3, $4, current_timestamp)",
             &[&id.as_int(), &path, &user, &content_type.as_ref()],
         )
         .await?;
 
+        ctx.enqueue_task(&crate::tasks::GenerateMediaThumbnail { media: id.as_int() })
+            .await?;
+
         crate::json_response(&serde_json::json!({"id": id.to_string()}))
     } else {
-        Err(crate::Error::UserError(crate::simple_response(
+        Err(crate::user_error(
             hyper::StatusCode::INTERNAL_SERVER_ERROR,
-            lang.tr(&lang::media_upload_not_configured()).into_owned(),
-        )))
+            &lang,
+            &lang::media_upload_not_configured(),
+        ))
     }
 }
 
@@ -1,10 +1,10 @@
 use crate::lang;
 use crate::types::{
-    CommentLocalID, CommunityLocalID, FingerRequestQuery, FingerResponse, JustURL, PostLocalID,
-    RespAvatarInfo, RespList, RespLoginInfo, RespLoginPermissions, RespLoginUserInfo,
+    CommentLocalID, CommunityLocalID, FingerRequestQuery, FingerResponse, JustURL, NotificationID,
+    PostLocalID, RespAvatarInfo, RespList, RespLoginInfo, RespLoginPermissions, RespLoginUserInfo,
     RespMinimalAuthorInfo, RespMinimalCommentInfo, RespMinimalCommunityInfo, RespMinimalPostInfo,
-    RespPermissionInfo, RespPostCommentInfo, RespPostListPost, RespSiteModlogEvent,
-    RespSiteModlogEventDetails, UserLocalID,
+    RespPermissionInfo, RespPostCommentInfo, RespPostListPost, RespScore, RespSiteModlogEvent,
+    RespSiteModlogEventDetails, RespUserPreferences, ThingLocalRef, UserLocalID, UserPreferences,
 };
 use serde_derive::Deserialize;
 use std::borrow::Cow;
@@ -12,15 +12,30 @@ use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::sync::Arc;
 
+mod automod;
 mod comments;
 mod communities;
 mod flags;
+mod flairs;
+mod follow_requests;
 mod forgot_password;
+mod hashtags;
+mod instance_users;
 mod invitations;
 mod media;
+mod modqueue;
+mod mutes;
+mod peers;
 mod posts;
+mod relays;
+mod remote_hosts;
+mod reply_drafts;
 mod stable;
+mod stats;
+mod streams;
+mod task_queue;
 mod users;
+mod webhooks;
 
 lazy_static::lazy_static! {
     static ref USERNAME_ALLOWED_CHARS: HashSet<char> = {
@@ -28,6 +43,8 @@ lazy_static::lazy_static! {
         chars!('a'..='z').into_iter().chain(chars!('A'..='Z')).chain(chars!('0'..='9')).chain(std::iter::once('_'))
             .collect()
     };
+    static ref OPENAPI_SPEC: serde_json::Value =
+        serde_json::from_str(include_str!("../../../openapi/openapi.json")).unwrap();
 }
 
 #[derive(Debug)]
@@ -68,11 +85,8 @@ impl<'a> ValueConsumer<'a> {
 
 pub struct InvalidPage;
 impl InvalidPage {
-    fn into_user_error(self) -> crate::Error {
-        crate::Error::UserError(crate::simple_response(
-            hyper::StatusCode::BAD_REQUEST,
-            "Invalid page",
-        ))
+    fn into_user_error(self, lang: &crate::Translator) -> crate::Error {
+        crate::user_error(hyper::StatusCode::BAD_REQUEST, lang, &lang::page_invalid())
     }
 }
 
@@ -85,19 +99,27 @@ pub enum SortType {
 }
 
 impl SortType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortType::Hot => "hot",
+            SortType::New => "new",
+            SortType::Top => "top",
+        }
+    }
+
     pub fn post_sort_sql(&self) -> &'static str {
         match self {
-            SortType::Hot => "hot_rank((SELECT COUNT(*) FROM post_like WHERE post = post.id AND person != post.author), post.created) DESC",
+            SortType::Hot => "post_score.hot_rank DESC NULLS FIRST, post.id DESC",
             SortType::New => "post.created DESC, post.id DESC",
-            SortType::Top => "(SELECT COUNT(*) FROM post_like WHERE post = post.id AND person != post.author) DESC, post.id DESC",
+            SortType::Top => "post_score.score DESC, post.id DESC",
         }
     }
 
     pub fn comment_sort_sql(&self) -> &'static str {
         match self {
-            SortType::Hot => "hot_rank((SELECT COUNT(*) FROM reply_like WHERE reply = reply.id AND person != reply.author), reply.created) DESC",
+            SortType::Hot => "hot_rank((SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply = reply.id AND person != reply.author), reply.created) DESC",
             SortType::New => "reply.created DESC",
-            SortType::Top => "(SELECT COUNT(*) FROM reply_like WHERE reply = reply.id AND person != reply.author) DESC, reply.id DESC",
+            SortType::Top => "(SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply = reply.id AND person != reply.author) DESC, reply.id DESC",
         }
     }
 
@@ -222,11 +244,36 @@ impl SortType {
     }
 }
 
+/// Limits `sort=top` to posts from a recent period
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TopWindow {
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl TopWindow {
+    pub fn interval_sql(&self) -> Option<&'static str> {
+        match self {
+            TopWindow::Day => Some("1 DAY"),
+            TopWindow::Week => Some("1 WEEK"),
+            TopWindow::Month => Some("1 MONTH"),
+            TopWindow::Year => Some("1 YEAR"),
+            TopWindow::All => None,
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum CommunitiesSortType {
     OldLocal,
     Alphabetic,
+    FollowersCount,
+    Activity,
 }
 
 impl CommunitiesSortType {
@@ -234,6 +281,8 @@ impl CommunitiesSortType {
         match self {
             Self::OldLocal => "community.id ASC",
             Self::Alphabetic => "community.name ASC, ap_id ASC",
+            Self::FollowersCount => "community.followers_count DESC, community.id ASC",
+            Self::Activity => "COALESCE(community_activity.score, 0) DESC, community.id ASC",
         }
     }
 
@@ -280,6 +329,38 @@ impl CommunitiesSortType {
                         }
                     }
                 }
+                Self::FollowersCount => {
+                    let (count, id) = page.split_once(',').ok_or(InvalidPage)?;
+                    let count = parse_number_58(count).map_err(|_| InvalidPage)?;
+                    let id = parse_number_58(id).map_err(|_| InvalidPage)?;
+
+                    let idx1 = value_out.push(count);
+                    let idx2 = value_out.push(id);
+
+                    Ok((
+                        Some(format!(
+                            " AND (community.followers_count < ${0} OR (community.followers_count = ${0} AND community.id >= ${1}))",
+                            idx1, idx2
+                        )),
+                        None,
+                    ))
+                }
+                Self::Activity => {
+                    let (score, id) = page.split_once(',').ok_or(InvalidPage)?;
+                    let score = parse_number_58(score).map_err(|_| InvalidPage)?;
+                    let id = parse_number_58(id).map_err(|_| InvalidPage)?;
+
+                    let idx1 = value_out.push(score);
+                    let idx2 = value_out.push(id);
+
+                    Ok((
+                        Some(format!(
+                            " AND (COALESCE(community_activity.score, 0) < ${0} OR (COALESCE(community_activity.score, 0) = ${0} AND community.id >= ${1}))",
+                            idx1, idx2
+                        )),
+                        None,
+                    ))
+                }
             },
         }
     }
@@ -287,10 +368,22 @@ impl CommunitiesSortType {
     pub fn get_next_page(
         &self,
         community: &RespMinimalCommunityInfo,
+        followers_count: i64,
+        activity_score: i64,
         _current_page: Option<&str>,
     ) -> String {
         match self {
             Self::OldLocal => format_number_58(community.id.raw()),
+            Self::FollowersCount => format!(
+                "{},{}",
+                format_number_58(followers_count),
+                format_number_58(community.id.raw())
+            ),
+            Self::Activity => format!(
+                "{},{}",
+                format_number_58(activity_score),
+                format_number_58(community.id.raw())
+            ),
             Self::Alphabetic => {
                 let mut result = bs58::encode(community.name.as_bytes()).into_string();
 
@@ -307,6 +400,117 @@ impl CommunitiesSortType {
     }
 }
 
+fn get_resp_score(
+    value: i64,
+    created: &chrono::DateTime<chrono::FixedOffset>,
+    hide_scores_minutes: Option<i32>,
+) -> RespScore {
+    let hidden_until = hide_scores_minutes
+        .map(|minutes| *created + chrono::Duration::minutes(minutes.into()))
+        .filter(|hidden_until| *hidden_until > chrono::offset::Utc::now());
+
+    RespScore {
+        value,
+        hidden_until: hidden_until.map(|hidden_until| hidden_until.to_rfc3339()),
+    }
+}
+
+/// Column positions used to build a `RespMinimalAuthorInfo` from a query row
+#[derive(Clone, Copy)]
+pub struct AuthorColumns {
+    pub id: usize,
+    pub username: usize,
+    pub local: usize,
+    pub ap_id: usize,
+    pub avatar: usize,
+    pub is_bot: usize,
+}
+
+impl AuthorColumns {
+    /// Columns selected as `person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot`
+    pub const fn starting_at(idx: usize) -> Self {
+        Self {
+            id: idx,
+            username: idx + 1,
+            local: idx + 2,
+            ap_id: idx + 3,
+            avatar: idx + 4,
+            is_bot: idx + 5,
+        }
+    }
+
+    /// Returns None if the ID column is NULL
+    pub fn get<'a>(
+        self,
+        row: &'a tokio_postgres::Row,
+        ctx: &'a crate::BaseContext,
+    ) -> Option<RespMinimalAuthorInfo<'a>> {
+        let id = UserLocalID(row.get::<_, Option<_>>(self.id)?);
+        let local: bool = row.get(self.local);
+        let ap_id: Option<&str> = row.get(self.ap_id);
+        let avatar: Option<&str> = row.get(self.avatar);
+
+        let remote_url = if local {
+            Some(Cow::Owned(String::from(
+                crate::apub_util::LocalObjectRef::User(id).to_local_uri(&ctx.host_url_apub),
+            )))
+        } else {
+            ap_id.map(Cow::Borrowed)
+        };
+
+        Some(RespMinimalAuthorInfo {
+            id,
+            username: Cow::Borrowed(row.get(self.username)),
+            local,
+            host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
+            remote_url,
+            is_bot: row.get(self.is_bot),
+            avatar: avatar.map(|url| RespAvatarInfo {
+                url: ctx.process_avatar_href(url, id),
+            }),
+        })
+    }
+}
+
+/// Column positions used to build a `RespMinimalCommunityInfo` from a query row
+#[derive(Clone, Copy)]
+pub struct CommunityColumns {
+    pub id: usize,
+    pub name: usize,
+    pub local: usize,
+    pub ap_id: usize,
+    pub deleted: usize,
+}
+
+impl CommunityColumns {
+    pub fn get<'a>(
+        self,
+        row: &'a tokio_postgres::Row,
+        ctx: &'a crate::BaseContext,
+    ) -> RespMinimalCommunityInfo<'a> {
+        let id = CommunityLocalID(row.get(self.id));
+        let local: bool = row.get(self.local);
+        let ap_id: Option<&str> = row.get(self.ap_id);
+
+        let remote_url = if local {
+            Some(Cow::Owned(String::from(
+                crate::apub_util::LocalObjectRef::Community(id).to_local_uri(&ctx.host_url_apub),
+            )))
+        } else {
+            ap_id.map(Cow::Borrowed)
+        };
+
+        RespMinimalCommunityInfo {
+            id,
+            name: Cow::Borrowed(row.get(self.name)),
+            local,
+            host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
+            remote_url,
+            deleted: row.get(self.deleted),
+        }
+    }
+}
+
 pub fn default_replies_depth() -> u8 {
     3
 }
@@ -319,8 +523,139 @@ pub fn default_comment_sort() -> SortType {
     SortType::Hot
 }
 
+/// A tree of API routes. Handlers shared between trees take the version so responses can differ.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    Unstable,
+    V1,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::Unstable, ApiVersion::V1];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiVersion::Unstable => "unstable",
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    pub fn is_stable(self) -> bool {
+        match self {
+            ApiVersion::Unstable => false,
+            ApiVersion::V1 => true,
+        }
+    }
+}
+
+/// Mounts a handler shared between API versions into the tree for one of them
+fn versioned<P, F, R>(
+    version: ApiVersion,
+    handler: F,
+) -> impl Fn(P, Arc<crate::RouteContext>, hyper::Request<hyper::Body>) -> R + Send + Sync + 'static
+where
+    F: Fn(ApiVersion, P, Arc<crate::RouteContext>, hyper::Request<hyper::Body>) -> R
+        + Send
+        + Sync
+        + 'static,
+    P: 'static,
+    R: 'static,
+{
+    move |params, ctx, req| handler(version, params, ctx, req)
+}
+
+/// Mounts an unstable handler which has an equivalent in a stable tree, adding headers pointing
+/// clients to it. `successor` is relative to the API root, e.g. `v1/instance`.
+fn deprecated<P, F, R>(
+    successor: &'static str,
+    handler: F,
+) -> impl Fn(
+    P,
+    Arc<crate::RouteContext>,
+    hyper::Request<hyper::Body>,
+) -> PinBoxFuture<'static, Result<hyper::Response<hyper::Body>, crate::Error>>
+       + Send
+       + Sync
+       + 'static
+where
+    F: Fn(P, Arc<crate::RouteContext>, hyper::Request<hyper::Body>) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<hyper::Response<hyper::Body>, crate::Error>> + Send + 'static,
+    P: 'static,
+{
+    move |params, ctx, req| {
+        let link = format!(
+            "<{}/{}>; rel=\"successor-version\"",
+            ctx.host_url_api, successor
+        );
+        let fut = handler(params, ctx, req);
+
+        let fut: PinBoxFuture<'static, _> = Box::pin(async move {
+            let mut res = fut.await?;
+
+            let headers = res.headers_mut();
+            headers.insert(
+                hyper::header::HeaderName::from_static("deprecation"),
+                hyper::header::HeaderValue::from_static("true"),
+            );
+            if let Ok(value) = hyper::header::HeaderValue::from_str(&link) {
+                headers.append(hyper::header::LINK, value);
+            }
+
+            Ok(res)
+        });
+        fut
+    }
+}
+
+async fn route_api_versions_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let versions: Vec<_> = ApiVersion::ALL
+        .iter()
+        .map(|version| {
+            serde_json::json!({
+                "name": version.as_str(),
+                "stable": version.is_stable(),
+                "url": format!("{}/{}", ctx.host_url_api, version.as_str()),
+            })
+        })
+        .collect();
+
+    crate::json_response(&serde_json::json!({ "versions": versions }))
+}
+
+async fn route_unstable_openapi_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let mut spec = OPENAPI_SPEC.clone();
+
+    spec["info"]["version"] = env!("CARGO_PKG_VERSION").into();
+
+    // documented paths include the /api prefix
+    if let Some(base) = ctx.host_url_api.strip_suffix("/api") {
+        spec["servers"] = serde_json::json!([{ "url": base }]);
+    }
+
+    crate::json_response(&spec)
+}
+
+fn route_v1() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_child(
+        "instance",
+        crate::RouteNode::new().with_handler_async(
+            hyper::Method::GET,
+            versioned(ApiVersion::V1, route_instance_get),
+        ),
+    )
+}
+
 pub fn route_api() -> crate::RouteNode<()> {
     crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_api_versions_list)
         .with_child(
             "unstable",
             crate::RouteNode::new()
@@ -332,6 +667,8 @@ pub fn route_api() -> crate::RouteNode<()> {
                     ),
                 )
                 .with_child("flags", flags::route_flags())
+                .with_child("flags:stats", flags::route_flags_stats())
+                .with_child("hashtags", hashtags::route_hashtags())
                 .with_child("invitations", invitations::route_invitations())
                 .with_child(
                     "logins",
@@ -356,28 +693,67 @@ pub fn route_api() -> crate::RouteNode<()> {
                     crate::RouteNode::new()
                         .with_handler_async(hyper::Method::GET, route_unstable_nodeinfo_20_get),
                 )
+                .with_child(
+                    "nodeinfo/2.1",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::GET, route_unstable_nodeinfo_21_get),
+                )
                 .with_child(
                     "objects:lookup",
-                    crate::RouteNode::new().with_child_str(
-                        crate::RouteNode::new()
-                            .with_handler_async(hyper::Method::GET, route_unstable_objects_lookup),
-                    ),
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_objects_lookup_by_uri,
+                        )
+                        .with_child_str(
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::GET,
+                                route_unstable_objects_lookup,
+                            ),
+                        ),
                 )
                 .with_child("communities", communities::route_communities())
+                .with_child(
+                    "communities:import",
+                    communities::route_communities_import(),
+                )
                 .with_child(
                     "instance",
                     crate::RouteNode::new()
-                        .with_handler_async(hyper::Method::GET, route_unstable_instance_get)
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            deprecated(
+                                "v1/instance",
+                                versioned(ApiVersion::Unstable, route_instance_get),
+                            ),
+                        )
                         .with_handler_async(hyper::Method::PATCH, route_unstable_instance_patch)
+                        .with_child("automod_rules", automod::route_instance_automod_rules())
+                        .with_child("mutes", mutes::route_instance_mutes())
+                        .with_child("peers", peers::route_peers())
+                        .with_child("relays", relays::route_relays())
+                        .with_child("remote_hosts", remote_hosts::route_remote_hosts())
+                        .with_child("stats", stats::route_instance_stats())
+                        .with_child("task_queue", task_queue::route_task_queue())
+                        .with_child("users", instance_users::route_instance_users())
+                        .with_child("webhooks", webhooks::route_webhooks())
                         .with_child(
                             "modlog",
-                            crate::RouteNode::new().with_child(
-                                "events",
-                                crate::RouteNode::new().with_handler_async(
-                                    hyper::Method::GET,
-                                    route_unstable_instance_modlog_events_list,
+                            crate::RouteNode::new()
+                                .with_child(
+                                    "events",
+                                    crate::RouteNode::new().with_handler_async(
+                                        hyper::Method::GET,
+                                        route_unstable_instance_modlog_events_list,
+                                    ),
+                                )
+                                .with_child(
+                                    "community_events",
+                                    crate::RouteNode::new().with_handler_async(
+                                        hyper::Method::GET,
+                                        route_unstable_instance_modlog_community_events_list,
+                                    ),
                                 ),
-                            ),
                         ),
                 )
                 .with_child(
@@ -390,28 +766,70 @@ pub fn route_api() -> crate::RouteNode<()> {
                         ),
                     ),
                 )
+                .with_child(
+                    "openapi.json",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::GET, route_unstable_openapi_get),
+                )
                 .with_child("posts", posts::route_posts())
                 .with_child("comments", comments::route_comments())
                 .with_child("users", users::route_users())
+                .with_child("streams", streams::route_streams())
                 .with_child("forgot_password", forgot_password::route_forgot_password()),
         )
         .with_child("stable", stable::route_stable())
+        .with_child("v1", route_v1())
 }
 
 async fn insert_token(
     user_id: UserLocalID,
+    ip: Option<std::net::IpAddr>,
     db: &tokio_postgres::Client,
 ) -> Result<uuid::Uuid, tokio_postgres::Error> {
     let token = uuid::Uuid::new_v4();
     db.execute(
-        "INSERT INTO login (token, person, created) VALUES ($1, $2, current_timestamp)",
-        &[&token, &user_id],
+        "INSERT INTO login (token, person, created, ip) VALUES ($1, $2, current_timestamp, $3)",
+        &[&token, &user_id, &ip],
     )
     .await?;
 
     Ok(token)
 }
 
+async fn record_login_attempt(
+    user: UserLocalID,
+    success: bool,
+    ip: Option<std::net::IpAddr>,
+    user_agent: Option<&str>,
+    ctx: &crate::RouteContext,
+    db: &tokio_postgres::Client,
+) -> Result<(), crate::Error> {
+    let row = db
+        .query_one(
+            "INSERT INTO login_attempt (person, created, success, ip, user_agent) VALUES ($1, current_timestamp, $2, $3, $4) RETURNING id",
+            &[&user, &success, &ip, &user_agent],
+        )
+        .await?;
+    let attempt_id: i64 = row.get(0);
+
+    if success && ip.is_some() {
+        // only notify if there is a known address to compare against
+        let row = db.query_opt(
+            "WITH known AS (SELECT registration_ip AS ip FROM person WHERE id=$1 UNION ALL SELECT ip FROM login_attempt WHERE person=$1 AND success AND id < $2) INSERT INTO notification (kind, created_at, to_user, login_attempt) SELECT 'new_login', current_timestamp, $1, $2 WHERE EXISTS(SELECT 1 FROM known WHERE ip IS NOT NULL) AND NOT EXISTS(SELECT 1 FROM known WHERE ip=$3) RETURNING id",
+            &[&user, &attempt_id, &ip],
+        ).await?;
+
+        if let Some(row) = row {
+            let notification = NotificationID(row.get(0));
+            ctx.send_stream_event(crate::StreamEvent::NewNotification { user, notification });
+            ctx.enqueue_task(&crate::tasks::SendNotification { notification })
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
 enum Lookup<'a> {
     Url(url::Url),
     WebFinger { user: &'a str, host: &'a str },
@@ -460,8 +878,7 @@ async fn route_unstable_actors_lookup(
             );
             log::debug!("{}", uri);
             let res = ctx
-                .http_client
-                .request(hyper::Request::get(uri).body(Default::default())?)
+                .fetch(hyper::Request::get(uri).body(Default::default())?)
                 .await?;
 
             if res.status() == hyper::StatusCode::NOT_FOUND {
@@ -470,7 +887,7 @@ async fn route_unstable_actors_lookup(
             } else {
                 let res = crate::res_to_error(res).await?;
 
-                let res = hyper::body::to_bytes(res.into_body()).await?;
+                let res = ctx.read_fetched_body(res).await?;
                 let res: FingerResponse = serde_json::from_slice(&res)?;
 
                 let mut found_uri = None;
@@ -521,7 +938,14 @@ async fn route_unstable_logins_create(
     let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let client_ip = crate::get_client_ip(&req);
+    let user_agent = req
+        .headers()
+        .get(hyper::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct LoginsCreateBody<'a> {
@@ -533,42 +957,68 @@ async fn route_unstable_logins_create(
 
     let row = db
         .query_opt(
-            "SELECT id, passhash, suspended FROM person WHERE LOWER(username)=LOWER($1) AND local",
+            "SELECT id, passhash, suspended FROM person WHERE LOWER(username)=LOWER($1) AND local AND NOT deleted",
             &[&body.username],
         )
         .await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::no_such_local_user_by_name()).into_owned(),
-            ))
+            crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::no_such_local_user_by_name())
         })?;
 
-    let id = UserLocalID(row.get(0));
+    let id: UserLocalID = row.get(0);
     let passhash: Option<String> = row.get(1);
 
     let passhash = passhash.ok_or_else(|| {
-        crate::Error::UserError(crate::simple_response(
-            hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::no_password()).into_owned(),
-        ))
+        crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::no_password())
     })?;
 
-    let req_password = body.password.to_owned();
+    let req_password = body.password.into_owned();
+    let params = ctx.password_hash_params.clone();
+
+    // hashes made with bcrypt or outdated parameters are replaced while we have the password
+    let (correct, new_passhash) =
+        tokio::task::spawn_blocking(move || -> Result<_, crate::Error> {
+            let correct = crate::passwords::verify(&req_password, &passhash)?;
+            let new_passhash = if correct && crate::passwords::needs_rehash(&passhash, &params) {
+                Some(crate::passwords::hash(&req_password, &params)?)
+            } else {
+                None
+            };
+
+            Ok((correct, new_passhash))
+        })
+        .await??;
 
-    let correct =
-        tokio::task::spawn_blocking(move || bcrypt::verify(req_password.as_ref(), &passhash))
-            .await??;
+    if let Some(new_passhash) = new_passhash {
+        db.execute(
+            "UPDATE person SET passhash=$1 WHERE id=$2",
+            &[&new_passhash, &id],
+        )
+        .await?;
+    }
+
+    let suspended: bool = row.get(2);
+
+    record_login_attempt(
+        id,
+        correct && !suspended,
+        client_ip,
+        user_agent.as_deref(),
+        &ctx,
+        &db,
+    )
+    .await?;
 
     if correct {
-        if row.get(2) {
-            return Err(crate::Error::UserError(crate::simple_response(
+        if suspended {
+            return Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::user_suspended_error()).into_owned(),
-            )));
+                &lang,
+                &lang::user_suspended_error(),
+            ));
         }
 
-        let token = insert_token(id, &db).await?;
+        let token = insert_token(id, client_ip, &db).await?;
 
         let info = fetch_login_info(&db, id).await?;
 
@@ -576,9 +1026,10 @@ async fn route_unstable_logins_create(
             &serde_json::json!({"token": token.to_string(), "user": info.user, "permissions": info.permissions}),
         )
     } else {
-        Ok(crate::simple_response(
+        Ok(crate::error_response(
             hyper::StatusCode::FORBIDDEN,
-            lang.tr(&lang::password_incorrect()).into_owned(),
+            &lang,
+            &lang::password_incorrect(),
         ))
     }
 }
@@ -611,10 +1062,9 @@ async fn route_unstable_logins_current_delete(
     Ok(crate::empty_response())
 }
 
-async fn route_unstable_nodeinfo_20_get(
-    _: (),
-    ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+async fn get_nodeinfo(
+    version: &str,
+    ctx: &crate::RouteContext,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let db = ctx.db_pool.get().await?;
 
@@ -636,34 +1086,65 @@ async fn route_unstable_nodeinfo_20_get(
             .await?;
         row.get::<_, i64>(0)
     };
+    // users count as active from logging in or from posting
+    let active_row = db
+        .query_one(
+            "SELECT COUNT(*) FILTER (WHERE active_at > current_timestamp - INTERVAL '1 MONTH'), COUNT(*) FILTER (WHERE active_at > current_timestamp - INTERVAL '6 MONTHS') FROM (SELECT GREATEST((SELECT MAX(created) FROM login WHERE person=person.id), (SELECT MAX(created) FROM post WHERE author=person.id), (SELECT MAX(created) FROM reply WHERE author=person.id)) AS active_at FROM person WHERE local) AS person_activity",
+            &[],
+        )
+        .await?;
+    let active_month: i64 = active_row.get(0);
+    let active_halfyear: i64 = active_row.get(1);
 
-    let open_registrations = {
-        let row = db
-            .query_one("SELECT signup_allowed FROM site WHERE local", &[])
-            .await?;
-        row.get::<_, bool>(0)
-    };
+    let site_row = db
+        .query_one(
+            "SELECT signup_allowed, COALESCE(description, description_markdown, description_html), title, contact, allow_invitations, allow_dislikes FROM site WHERE local",
+            &[],
+        )
+        .await?;
+    let open_registrations: bool = site_row.get(0);
+    let description: Option<&str> = site_row.get(1);
+    let title: Option<&str> = site_row.get(2);
+    let contact: Option<&str> = site_row.get(3);
+    let allow_invitations: bool = site_row.get(4);
+    let allow_dislikes: bool = site_row.get(5);
+
+    let mut software = serde_json::json!({
+        "name": "lotide",
+        "version": env!("CARGO_PKG_VERSION")
+    });
+    if version != "2.0" {
+        software["repository"] = "https://git.sr.ht/~vpzom/lotide".into();
+        software["homepage"] = "https://sr.ht/~vpzom/lotide".into();
+    }
 
     let body = serde_json::json!({
-        "version": "2.0",
-        "software": {
-            "name": "lotide",
-            "version": env!("CARGO_PKG_VERSION")
-        },
+        "version": version,
+        "software": software,
         "protocols": ["activitypub"],
         "services": {
             "inbound": [],
-            "outbound": []
+            "outbound": ["atom1.0", "rss2.0"]
         },
         "openRegistrations": open_registrations,
         "usage": {
             "users": {
                 "total": local_users,
+                "activeMonth": active_month,
+                "activeHalfyear": active_halfyear,
             },
             "localPosts": local_posts,
             "localComments": local_comments
         },
-        "metadata": {}
+        "metadata": {
+            "nodeName": title.unwrap_or(&ctx.local_hostname),
+            "nodeDescription": description.unwrap_or(""),
+            "contact": contact,
+            "features": {
+                "downvotes": allow_dislikes,
+                "registration_mode": get_registration_mode(open_registrations, allow_invitations),
+            },
+        }
     });
 
     let body = serde_json::to_vec(&body)?.into();
@@ -671,12 +1152,42 @@ async fn route_unstable_nodeinfo_20_get(
     Ok(crate::common_response_builder()
         .header(
             hyper::header::CONTENT_TYPE,
-            "application/json; profile=http://nodeinfo.diaspora.software/ns/schema/2.0#",
+            format!(
+                "application/json; profile=http://nodeinfo.diaspora.software/ns/schema/{}#",
+                version
+            ),
         )
         .body(body)?)
 }
 
-async fn route_unstable_instance_get(
+fn get_registration_mode(signup_allowed: bool, allow_invitations: bool) -> &'static str {
+    if signup_allowed {
+        "open"
+    } else if allow_invitations {
+        "invitation_only"
+    } else {
+        "closed"
+    }
+}
+
+async fn route_unstable_nodeinfo_20_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    get_nodeinfo("2.0", &ctx).await
+}
+
+async fn route_unstable_nodeinfo_21_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    get_nodeinfo("2.1", &ctx).await
+}
+
+async fn route_instance_get(
+    version: ApiVersion,
     _: (),
     ctx: Arc<crate::RouteContext>,
     _req: hyper::Request<hyper::Body>,
@@ -684,14 +1195,21 @@ async fn route_unstable_instance_get(
     let db = ctx.db_pool.get().await?;
 
     let row = db
-        .query_one("SELECT description, description_markdown, description_html, signup_allowed FROM site WHERE local = TRUE", &[])
+        .query_one("SELECT description, description_markdown, description_html, signup_allowed, allow_dislikes, title, rules_markdown, rules_html, contact, default_sort, allow_invitations FROM site WHERE local = TRUE", &[])
         .await?;
     let description_text: Option<&str> = row.get(0);
     let description_markdown: Option<&str> = row.get(1);
     let description_html: Option<&str> = row.get(2);
     let signup_allowed: bool = row.get(3);
-
-    let body = serde_json::json!({
+    let allow_dislikes: bool = row.get(4);
+    let title: Option<&str> = row.get(5);
+    let rules_markdown: Option<&str> = row.get(6);
+    let rules_html: Option<&str> = row.get(7);
+    let contact: Option<&str> = row.get(8);
+    let default_sort: &str = row.get(9);
+    let allow_invitations: bool = row.get(10);
+
+    let mut body = serde_json::json!({
         "web_push_vapid_key": ctx.vapid_public_key_base64,
         "description": crate::types::Content {
             content_text: description_text.map(Cow::Borrowed),
@@ -702,9 +1220,26 @@ async fn route_unstable_instance_get(
             "name": "lotide",
             "version": env!("CARGO_PKG_VERSION"),
         },
-        "signup_allowed": signup_allowed
+        "title": title,
+        "rules": crate::types::Content {
+            content_text: None,
+            content_markdown: rules_markdown.map(Cow::Borrowed),
+            content_html_safe: rules_html.map(|x| crate::clean_html(x)),
+        },
+        "contact": contact,
+        "default_sort": default_sort,
+        "features": {
+            "downvotes": allow_dislikes,
+            "registration_mode": get_registration_mode(signup_allowed, allow_invitations),
+        },
     });
 
+    if version == ApiVersion::Unstable {
+        // superseded by `features`
+        body["signup_allowed"] = signup_allowed.into();
+        body["allow_dislikes"] = allow_dislikes.into();
+    }
+
     crate::json_response(&body)
 }
 
@@ -719,13 +1254,18 @@ async fn route_unstable_instance_patch(
         description_markdown: Option<Cow<'a, str>>,
         description_html: Option<Cow<'a, str>>,
         signup_allowed: Option<bool>,
+        allow_dislikes: Option<bool>,
+        title: Option<Cow<'a, str>>,
+        rules_markdown: Option<Cow<'a, str>>,
+        contact: Option<Cow<'a, str>>,
+        default_sort: Option<SortType>,
     }
 
     let lang = crate::get_lang_for_req(&req);
 
     let (req_parts, body) = req.into_parts();
 
-    let body = hyper::body::to_bytes(body).await?;
+    let body = crate::to_bytes_limited(body, ctx.max_request_body_size).await?;
     let body: InstanceEditBody = serde_json::from_slice(&body)?;
 
     let db = ctx.db_pool.get().await?;
@@ -742,10 +1282,11 @@ async fn route_unstable_instance_patch(
         };
 
         if description_conflict {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::description_content_conflict()).into_owned(),
-            )));
+                &lang,
+                &lang::description_content_conflict(),
+            ));
         }
 
         if let Some(description) = body.description_text {
@@ -755,8 +1296,12 @@ async fn route_unstable_instance_patch(
             )
             .await?;
         } else if let Some(description) = body.description_markdown {
+            let markdown_options = ctx.markdown_options;
             let (html, md) = tokio::task::spawn_blocking(move || {
-                (crate::render_markdown(&description), description)
+                (
+                    crate::render_markdown(&description, markdown_options),
+                    description,
+                )
             })
             .await?;
 
@@ -778,11 +1323,52 @@ async fn route_unstable_instance_patch(
                 .await?;
         }
 
+        if let Some(allow_dislikes) = body.allow_dislikes {
+            db.execute("UPDATE site SET allow_dislikes=$1", &[&allow_dislikes])
+                .await?;
+        }
+
+        if let Some(title) = body.title {
+            let title = Some(title.trim()).filter(|x| !x.is_empty());
+            db.execute("UPDATE site SET title=$1", &[&title]).await?;
+        }
+
+        if let Some(rules) = body.rules_markdown {
+            if rules.trim().is_empty() {
+                db.execute("UPDATE site SET rules_markdown=NULL, rules_html=NULL", &[])
+                    .await?;
+            } else {
+                let markdown_options = ctx.markdown_options;
+                let (html, md) = tokio::task::spawn_blocking(move || {
+                    (crate::render_markdown(&rules, markdown_options), rules)
+                })
+                .await?;
+
+                db.execute(
+                    "UPDATE site SET rules_markdown=$1, rules_html=$2",
+                    &[&md, &html],
+                )
+                .await?;
+            }
+        }
+
+        if let Some(contact) = body.contact {
+            let contact = Some(contact.trim()).filter(|x| !x.is_empty());
+            db.execute("UPDATE site SET contact=$1", &[&contact])
+                .await?;
+        }
+
+        if let Some(default_sort) = body.default_sort {
+            db.execute("UPDATE site SET default_sort=$1", &[&default_sort.as_str()])
+                .await?;
+        }
+
         Ok(crate::empty_response())
     } else {
-        Ok(crate::simple_response(
+        Ok(crate::error_response(
             hyper::StatusCode::FORBIDDEN,
-            lang.tr(&lang::not_admin()).into_owned(),
+            &lang,
+            &lang::not_admin(),
         ))
     }
 }
@@ -810,8 +1396,7 @@ async fn route_unstable_objects_lookup(
             );
             log::debug!("{}", uri);
             let res = ctx
-                .http_client
-                .request(hyper::Request::get(uri).body(Default::default())?)
+                .fetch(hyper::Request::get(uri).body(Default::default())?)
                 .await?;
 
             if res.status() == hyper::StatusCode::NOT_FOUND {
@@ -820,7 +1405,7 @@ async fn route_unstable_objects_lookup(
             } else {
                 let res = crate::res_to_error(res).await?;
 
-                let res = hyper::body::to_bytes(res.into_body()).await?;
+                let res = ctx.read_fetched_body(res).await?;
                 let res: FingerResponse = serde_json::from_slice(&res)?;
 
                 let mut found_uri = None;
@@ -862,23 +1447,145 @@ async fn route_unstable_objects_lookup(
     }
 }
 
+/// Finds the local post, comment, user, or community for an ActivityPub ID without fetching it
+async fn lookup_known_object(
+    uri: &url::Url,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<Option<ThingLocalRef>, crate::Error> {
+    if let Some(local_ref) = crate::apub_util::LocalObjectRef::try_from_uri(uri, &ctx.host_url_apub)
+    {
+        let (found, sql, id) = match local_ref {
+            crate::apub_util::LocalObjectRef::Post(id) => (
+                ThingLocalRef::Post(id),
+                "SELECT 1 FROM post WHERE id=$1 AND local",
+                id.raw(),
+            ),
+            crate::apub_util::LocalObjectRef::Comment(id) => (
+                ThingLocalRef::Comment(id),
+                "SELECT 1 FROM reply WHERE id=$1 AND local",
+                id.raw(),
+            ),
+            crate::apub_util::LocalObjectRef::User(id) => (
+                ThingLocalRef::User(id),
+                "SELECT 1 FROM person WHERE id=$1 AND local",
+                id.raw(),
+            ),
+            crate::apub_util::LocalObjectRef::Community(id) => (
+                ThingLocalRef::Community(id),
+                "SELECT 1 FROM community WHERE id=$1 AND local",
+                id.raw(),
+            ),
+            _ => return Ok(None),
+        };
+
+        return Ok(db.query_opt(sql, &[&id]).await?.map(|_| found));
+    }
+
+    let row = db.query_opt(
+        "(SELECT 'post', id FROM post WHERE ap_id=$1) UNION ALL (SELECT 'comment', id FROM reply WHERE ap_id=$1) UNION ALL (SELECT 'user', id FROM person WHERE ap_id=$1) UNION ALL (SELECT 'community', id FROM community WHERE ap_id=$1) LIMIT 1",
+        &[&uri.as_str()],
+    ).await?;
+
+    Ok(row.and_then(|row| {
+        let id: i64 = row.get(1);
+        match row.get(0) {
+            "post" => Some(ThingLocalRef::Post(PostLocalID(id))),
+            "comment" => Some(ThingLocalRef::Comment(CommentLocalID(id))),
+            "user" => Some(ThingLocalRef::User(UserLocalID(id))),
+            "community" => Some(ThingLocalRef::Community(CommunityLocalID(id))),
+            _ => None,
+        }
+    }))
+}
+
+async fn route_unstable_objects_lookup_by_uri(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
+
+    #[derive(Deserialize)]
+    struct ObjectsLookupQuery<'a> {
+        uri: Cow<'a, str>,
+    }
+
+    let query: ObjectsLookupQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let uri: url::Url = match query.uri.parse() {
+        Ok(uri) if uri.scheme() == "https" || uri.scheme() == "http" => uri,
+        _ => {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::lookup_uri_invalid(),
+            ));
+        }
+    };
+
+    let db = ctx.db_pool.get().await?;
+
+    let found = match lookup_known_object(&uri, &db, &ctx).await? {
+        Some(found) => Some(found),
+        None => {
+            if crate::apub_util::LocalObjectRef::try_from_uri(&uri, &ctx.host_url_apub).is_some() {
+                None
+            } else {
+                // may also be a link to a web page which serves the object by content negotiation
+                let obj = crate::apub_util::fetch_ap_object(&uri, &ctx).await?;
+
+                crate::apub_util::ingest::ingest_object(
+                    obj,
+                    crate::apub_util::ingest::FoundFrom::Other,
+                    ctx.clone(),
+                )
+                .await?
+                .map(|res| res.into_ref())
+            }
+        }
+    };
+
+    match found {
+        None => Ok(crate::common_response_builder()
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body("[]".into())?),
+        Some(found) => crate::json_response(&[found]),
+    }
+}
+
+/// Loads `depth` levels of replies below `comments`, at most `limit` per comment.
+///
+/// Stops descending once `remaining` comments have been loaded in total, leaving `replies` unset
+/// on comments with unloaded replies so they can be fetched separately.
 async fn apply_comments_replies<'a, T>(
     comments: &mut Vec<(T, RespPostCommentInfo<'a>)>,
     include_your_for: Option<UserLocalID>,
     depth: u8,
     limit: u8,
     sort: SortType,
-    db: &tokio_postgres::Client,
+    remaining: &mut usize,
+    db: &crate::query::DbClient,
     ctx: &'a crate::BaseContext,
+    lang: &crate::Translator,
 ) -> Result<(), crate::Error> {
     let ids = comments
         .iter()
         .map(|(_, comment)| comment.base.id)
         .collect::<Vec<_>>();
-    if depth > 0 {
-        let mut replies =
-            get_comments_replies_box(&ids, include_your_for, depth - 1, limit, sort, db, ctx)
-                .await?;
+    if depth > 0 && *remaining > 0 {
+        let mut replies = get_comments_replies_box(
+            &ids,
+            include_your_for,
+            depth - 1,
+            limit,
+            sort,
+            remaining,
+            db,
+            ctx,
+            lang,
+        )
+        .await?;
 
         for (_, comment) in comments.iter_mut() {
             let list: RespList<RespPostCommentInfo> =
@@ -888,9 +1595,9 @@ async fn apply_comments_replies<'a, T>(
     } else {
         use futures::stream::TryStreamExt;
 
-        let stream = crate::query_stream(
+        let stream = crate::query::query_stream(
             db,
-            "SELECT DISTINCT parent FROM reply WHERE parent = ANY($1)",
+            "SELECT DISTINCT parent FROM reply WHERE parent = ANY($1) AND NOT pending_approval",
             &[&ids],
         )
         .await?;
@@ -915,6 +1622,76 @@ async fn apply_comments_replies<'a, T>(
     Ok(())
 }
 
+/// Columns read by `comment_row_to_info`, optionally followed by the user's vote
+const COMMENT_FIELDS_SQL: &str = "reply.id, reply.author, reply.content_text, reply.created, reply.parent, reply.content_html, person.username, person.local, person.ap_id, reply.deleted, person.avatar, reply.attachment_href, reply.local, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply = reply.id), reply.content_markdown, person.is_bot, reply.ap_id, reply.local, reply.sensitive, (SELECT hide_scores_minutes FROM community WHERE id=(SELECT community FROM post WHERE id=reply.post)), reply.updated";
+
+fn comment_row_to_info<'a>(
+    row: &tokio_postgres::Row,
+    include_your: bool,
+    ctx: &'a crate::BaseContext,
+) -> RespPostCommentInfo<'a> {
+    let id: CommentLocalID = row.get(0);
+    let content_text: Option<String> = row.get(2);
+    let content_html: Option<String> = row.get(5);
+    let created: chrono::DateTime<chrono::FixedOffset> = row.get(3);
+    let ap_id: Option<String> = row.get(16);
+    let local: bool = row.get(17);
+    let sensitive: bool = row.get(18);
+    let updated: Option<chrono::DateTime<chrono::FixedOffset>> = row.get(20);
+
+    let remote_url = if local {
+        Some(String::from(
+            crate::apub_util::LocalObjectRef::Comment(id).to_local_uri(&ctx.host_url_apub),
+        ))
+    } else {
+        ap_id
+    };
+
+    let author = AuthorColumns {
+        id: 1,
+        username: 6,
+        local: 7,
+        ap_id: 8,
+        avatar: 10,
+        is_bot: 15,
+    }
+    .get(row, ctx)
+    .map(RespMinimalAuthorInfo::into_owned);
+
+    RespPostCommentInfo {
+        base: RespMinimalCommentInfo {
+            id,
+            remote_url: remote_url.map(Cow::Owned),
+            content_text: content_text.map(From::from),
+            content_html_safe: content_html.map(|html| crate::clean_html(&html)),
+            sensitive,
+        },
+
+        attachments: match ctx
+            .process_attachments_inner(row.get::<_, Option<_>>(11).map(Cow::Owned), id)
+        {
+            None => vec![],
+            Some(href) => vec![JustURL { url: href }],
+        },
+        author,
+        content_markdown: row.get::<_, Option<String>>(14).map(Cow::Owned),
+        created: created.to_rfc3339(),
+        deleted: row.get(9),
+        edited_at: updated.map(|time| time.to_rfc3339()),
+        local: row.get(12),
+        replies: Some(RespList::empty()),
+        score: get_resp_score(row.get(13), &created, row.get(19)),
+        your_vote: if include_your {
+            Some(
+                row.get::<_, Option<bool>>(21)
+                    .map(|is_dislike| crate::types::RespYourVote { is_dislike }),
+            )
+        } else {
+            None
+        },
+    }
+}
+
 type PinBoxFuture<'a, T> = std::pin::Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
 #[derive(Default)]
@@ -938,8 +1715,10 @@ fn get_comments_replies_box<'a: 'b, 'b>(
     depth: u8,
     limit: u8,
     sort: SortType,
-    db: &'b tokio_postgres::Client,
+    remaining: &'b mut usize,
+    db: &'b crate::query::DbClient,
     ctx: &'a crate::BaseContext,
+    lang: &'b crate::Translator,
 ) -> PinBoxFuture<'b, Result<HashMap<CommentLocalID, CommentsRepliesInfoInternal<'a>>, crate::Error>>
 {
     Box::pin(get_comments_replies(
@@ -947,46 +1726,55 @@ fn get_comments_replies_box<'a: 'b, 'b>(
         include_your_for,
         depth,
         limit,
+        limit,
         sort,
         None,
+        remaining,
         db,
         ctx,
+        lang,
     ))
 }
 
 // https://github.com/rust-lang/rust-clippy/issues/7271
 #[allow(clippy::needless_lifetimes)]
+/// Loads up to `limit` replies to each of `parents`, then `depth` further levels of up to
+/// `replies_limit` each
 async fn get_comments_replies<'a>(
     parents: &[CommentLocalID],
     include_your_for: Option<UserLocalID>,
     depth: u8,
     limit: u8,
+    replies_limit: u8,
     sort: SortType,
     page: Option<&str>,
-    db: &tokio_postgres::Client,
+    remaining: &mut usize,
+    db: &crate::query::DbClient,
     ctx: &'a crate::BaseContext,
+    lang: &crate::Translator,
 ) -> Result<HashMap<CommentLocalID, CommentsRepliesInfoInternal<'a>>, crate::Error> {
     use futures::TryStreamExt;
 
     let limit_i = i64::from(limit) + 1;
 
-    let sql1 = "SELECT result.* FROM UNNEST($1::BIGINT[]) JOIN LATERAL (SELECT reply.id, reply.author, reply.content_text, reply.created, reply.parent, reply.content_html, person.username, person.local, person.ap_id, reply.deleted, person.avatar, reply.attachment_href, reply.local, (SELECT COUNT(*) FROM reply_like WHERE reply = reply.id), reply.content_markdown, person.is_bot, reply.ap_id, reply.local, reply.sensitive";
+    let sql1 = format!(
+        "SELECT result.* FROM UNNEST($1::BIGINT[]) JOIN LATERAL (SELECT {}",
+        COMMENT_FIELDS_SQL
+    );
     let (sql2, mut values): (_, Vec<&(dyn tokio_postgres::types::ToSql + Sync)>) =
         if include_your_for.is_some() {
             (
-                ", EXISTS(SELECT 1 FROM reply_like WHERE reply = reply.id AND person = $3)",
+                ", (SELECT is_dislike FROM reply_like WHERE reply = reply.id AND person = $3)",
                 vec![&parents, &limit_i, &include_your_for],
             )
         } else {
             ("", vec![&parents, &limit_i])
         };
     let mut sql3 =
-        " FROM reply LEFT OUTER JOIN person ON (person.id = reply.author) WHERE parent = unnest"
+        " FROM reply LEFT OUTER JOIN person ON (person.id = reply.author) WHERE parent = unnest AND NOT reply.pending_approval"
             .to_owned();
-    let mut sql4 = format!(
-        " ORDER BY {}) AS result ON TRUE LIMIT $2",
-        sort.comment_sort_sql()
-    );
+    // limited inside the join, so each parent gets its own page of replies
+    let mut sql4 = format!(" ORDER BY {} LIMIT $2", sort.comment_sort_sql());
 
     let mut con1 = None;
     let mut con2 = None;
@@ -1001,7 +1789,7 @@ async fn get_comments_replies<'a>(
                 used: 0,
             },
         )
-        .map_err(InvalidPage::into_user_error)?;
+        .map_err(|err| err.into_user_error(lang))?;
     if let Some(value) = &con1 {
         values.push(value.as_ref());
         if let Some(value) = &con2 {
@@ -1016,102 +1804,38 @@ async fn get_comments_replies<'a>(
         sql4.push_str(&part);
     }
 
-    let sql: String = format!("{}{}{}{}", sql1, sql2, sql3, sql4);
+    let sql: String = format!("{}{}{}{}) AS result ON TRUE", sql1, sql2, sql3, sql4);
     let sql: &str = &sql;
 
-    let stream = crate::query_stream(db, sql, &values).await?;
+    let stream = crate::query::query_stream(db, sql, &values).await?;
 
     let mut comments: Vec<_> = stream
         .map_err(crate::Error::from)
         .and_then(|row| {
-            let id = CommentLocalID(row.get(0));
-            let content_text: Option<String> = row.get(2);
-            let content_html: Option<String> = row.get(5);
-            let created: chrono::DateTime<chrono::FixedOffset> = row.get(3);
-            let parent = CommentLocalID(row.get(4));
-            let ap_id: Option<String> = row.get(16);
-            let local: bool = row.get(17);
-            let sensitive: bool = row.get(18);
-
-            let remote_url = if local {
-                Some(String::from(
-                    crate::apub_util::LocalObjectRef::Comment(id).to_local_uri(&ctx.host_url_apub),
-                ))
-            } else {
-                ap_id
-            };
-
-            let author_username: Option<String> = row.get(6);
-            let author = author_username.map(|author_username| {
-                let author_id = UserLocalID(row.get(1));
-                let author_local: bool = row.get(7);
-                let author_ap_id: Option<&str> = row.get(8);
-                let author_avatar: Option<&str> = row.get(10);
-
-                let author_remote_url = if author_local {
-                    Some(String::from(
-                        crate::apub_util::LocalObjectRef::User(author_id)
-                            .to_local_uri(&ctx.host_url_apub),
-                    ))
-                } else {
-                    author_ap_id.map(ToOwned::to_owned)
-                };
-
-                RespMinimalAuthorInfo {
-                    id: author_id,
-                    username: author_username.into(),
-                    local: author_local,
-                    host: crate::get_actor_host_or_unknown(
-                        author_local,
-                        author_ap_id,
-                        &ctx.local_hostname,
-                    ),
-                    remote_url: author_remote_url.map(Cow::Owned),
-                    is_bot: row.get(15),
-                    avatar: author_avatar.map(|url| RespAvatarInfo {
-                        url: ctx.process_avatar_href(url, author_id).into_owned().into(),
-                    }),
-                }
-            });
+            let parent: CommentLocalID = row.get(4);
 
             futures::future::ok((
                 parent,
-                RespPostCommentInfo {
-                    base: RespMinimalCommentInfo {
-                        id,
-                        remote_url: remote_url.map(Cow::Owned),
-                        content_text: content_text.map(From::from),
-                        content_html_safe: content_html.map(|html| crate::clean_html(&html)),
-                        sensitive,
-                    },
-
-                    attachments: match ctx
-                        .process_attachments_inner(row.get::<_, Option<_>>(11).map(Cow::Owned), id)
-                    {
-                        None => vec![],
-                        Some(href) => vec![JustURL { url: href }],
-                    },
-                    author,
-                    content_markdown: row.get::<_, Option<String>>(14).map(Cow::Owned),
-                    created: created.to_rfc3339(),
-                    deleted: row.get(9),
-                    local: row.get(12),
-                    replies: Some(RespList::empty()),
-                    score: row.get(13),
-                    your_vote: include_your_for.map(|_| {
-                        if row.get(19) {
-                            Some(crate::types::Empty {})
-                        } else {
-                            None
-                        }
-                    }),
-                },
+                comment_row_to_info(&row, include_your_for.is_some(), ctx),
             ))
         })
         .try_collect()
         .await?;
 
-    apply_comments_replies(&mut comments, include_your_for, depth, limit, sort, db, ctx).await?;
+    *remaining = remaining.saturating_sub(comments.len());
+
+    apply_comments_replies(
+        &mut comments,
+        include_your_for,
+        depth,
+        replies_limit,
+        sort,
+        remaining,
+        db,
+        ctx,
+        lang,
+    )
+    .await?;
 
     let mut result = HashMap::new();
     for (parent, comment) in comments {
@@ -1131,11 +1855,35 @@ async fn get_comments_replies<'a>(
     Ok(result)
 }
 
+async fn route_unstable_instance_modlog_community_events_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    {
+        let lang = crate::get_lang_for_req(&req);
+        let db = ctx.db_pool.get().await?;
+
+        let user = crate::require_login(&req, &db).await?;
+
+        if !crate::is_site_admin(&db, user).await? {
+            return Err(crate::user_error(
+                hyper::StatusCode::FORBIDDEN,
+                &lang,
+                &lang::not_admin(),
+            ));
+        }
+    }
+
+    communities::list_community_modlog_events(None, true, ctx, req).await
+}
+
 async fn route_unstable_instance_modlog_events_list(
     _: (),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
     fn default_limit() -> u32 {
@@ -1159,11 +1907,16 @@ async fn route_unstable_instance_modlog_events_list(
         .as_deref()
         .map(parse_number_58)
         .transpose()
-        .map_err(|_| InvalidPage.into_user_error())?;
+        .map_err(|_| InvalidPage.into_user_error(&lang))?;
+
+    let is_admin = match crate::authenticate(&req, &db).await? {
+        None => false,
+        Some(user) => crate::is_site_admin(&db, user).await?,
+    };
 
     let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&inner_limit];
 
-    let rows = db.query(&format!("SELECT modlog_event.id, modlog_event.time, modlog_event.action, reply_post.id, reply_post.title, reply_post.local, reply_post.ap_id, reply_post.sensitive, person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, reply_author.id, reply_author.username, reply_author.local, reply_author.ap_id, reply_author.avatar, reply_author.is_bot, post_community.id, post_community.name, post_community.local, post_community.ap_id, post_community.deleted, post_author.id, post_author.username, post_author.local, post_author.ap_id, post_author.avatar, post_author.is_bot FROM modlog_event LEFT OUTER JOIN reply ON (reply.id = modlog_event.reply) LEFT OUTER JOIN post AS reply_post ON (reply_post.id = reply.post) LEFT OUTER JOIN person ON (person.id = modlog_event.person) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) LEFT OUTER JOIN post ON (post.id = modlog_event.post) LEFT OUTER JOIN community AS post_community ON (post_community.id = post.community) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE modlog_event.by_community IS NULL{} ORDER BY modlog_event.id DESC LIMIT $1", if let Some(page) = &page {
+    let rows = db.query(&format!("SELECT modlog_event.id, modlog_event.time, modlog_event.action, reply_post.id, reply_post.title, reply_post.local, reply_post.ap_id, reply_post.sensitive, person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, reply_author.id, reply_author.username, reply_author.local, reply_author.ap_id, reply_author.avatar, reply_author.is_bot, post_community.id, post_community.name, post_community.local, post_community.ap_id, post_community.deleted, post_author.id, post_author.username, post_author.local, post_author.ap_id, post_author.avatar, post_author.is_bot, by_person.id, by_person.username, by_person.local, by_person.ap_id, by_person.avatar, by_person.is_bot, modlog_event.domain FROM modlog_event LEFT OUTER JOIN reply ON (reply.id = modlog_event.reply) LEFT OUTER JOIN post AS reply_post ON (reply_post.id = reply.post) LEFT OUTER JOIN person ON (person.id = modlog_event.person) LEFT OUTER JOIN person AS by_person ON (by_person.id = modlog_event.by_person) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) LEFT OUTER JOIN post ON (post.id = modlog_event.post) LEFT OUTER JOIN community AS post_community ON (post_community.id = post.community) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE modlog_event.by_community IS NULL{} ORDER BY modlog_event.id DESC LIMIT $1", if let Some(page) = &page {
         values.push(page);
 
         " AND modlog_event.id <= $2"
@@ -1356,11 +2109,57 @@ async fn route_unstable_instance_modlog_events_list(
                             return None;
                         }
                     }
+                    "mute_user" => {
+                        if let Some(user) = user {
+                            RespSiteModlogEventDetails::MuteUser { user }
+                        } else {
+                            return None;
+                        }
+                    }
+                    "unmute_user" => {
+                        if let Some(user) = user {
+                            RespSiteModlogEventDetails::UnmuteUser { user }
+                        } else {
+                            return None;
+                        }
+                    }
+                    "purge_user_content" => {
+                        if let Some(user) = user {
+                            RespSiteModlogEventDetails::PurgeUserContent { user }
+                        } else {
+                            return None;
+                        }
+                    }
+                    "ban_user" => {
+                        if let Some(user) = user {
+                            RespSiteModlogEventDetails::BanUser { user }
+                        } else {
+                            return None;
+                        }
+                    }
+                    "unban_user" => {
+                        if let Some(user) = user {
+                            RespSiteModlogEventDetails::UnbanUser { user }
+                        } else {
+                            return None;
+                        }
+                    }
+                    "block_domain" => RespSiteModlogEventDetails::BlockDomain {
+                        domain: Cow::Borrowed(row.get::<_, Option<_>>(37)?),
+                    },
+                    "unblock_domain" => RespSiteModlogEventDetails::UnblockDomain {
+                        domain: Cow::Borrowed(row.get::<_, Option<_>>(37)?),
+                    },
                     _ => return None,
                 };
 
                 Some(RespSiteModlogEvent {
                     time: time.to_rfc3339(),
+                    by: if is_admin {
+                        AuthorColumns::starting_at(31).get(row, &ctx)
+                    } else {
+                        None
+                    },
                     details,
                 })
             })
@@ -1376,7 +2175,7 @@ async fn route_unstable_misc_render_markdown(
     _ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct RenderMarkdownBody<'a> {
@@ -1385,8 +2184,11 @@ async fn route_unstable_misc_render_markdown(
 
     let body: RenderMarkdownBody = serde_json::from_slice(&body)?;
 
-    let html =
-        tokio::task::spawn_blocking(move || crate::render_markdown(&body.content_markdown)).await?;
+    let markdown_options = ctx.markdown_options;
+    let html = tokio::task::spawn_blocking(move || {
+        crate::render_markdown(&body.content_markdown, markdown_options)
+    })
+    .await?;
 
     crate::json_response(&serde_json::json!({ "content_html": html }))
 }
@@ -1397,34 +2199,40 @@ pub async fn process_comment_content<'a, 'b>(
     lang: &'b crate::Translator,
     content_text: Option<Cow<'a, str>>,
     content_markdown: Option<String>,
+    markdown_options: crate::MarkdownOptions,
 ) -> Result<(Option<Cow<'a, str>>, Option<String>, Option<String>), crate::Error> {
     if !(content_markdown.is_some() ^ content_text.is_some()) {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::comment_content_conflict()).into_owned(),
-        )));
+            lang,
+            &lang::comment_content_conflict(),
+        ));
     }
 
     Ok(match content_markdown {
         Some(md) => {
             if md.trim().is_empty() {
-                return Err(crate::Error::UserError(crate::simple_response(
+                return Err(crate::user_error(
                     hyper::StatusCode::BAD_REQUEST,
-                    lang.tr(&lang::comment_empty()).into_owned(),
-                )));
+                    lang,
+                    &lang::comment_empty(),
+                ));
             }
 
-            let (html, md) =
-                tokio::task::spawn_blocking(move || (crate::render_markdown(&md), md)).await?;
+            let (html, md) = tokio::task::spawn_blocking(move || {
+                (crate::render_markdown(&md, markdown_options), md)
+            })
+            .await?;
             (None, Some(md), Some(html))
         }
         None => match content_text {
             Some(text) => {
                 if text.trim().is_empty() {
-                    return Err(crate::Error::UserError(crate::simple_response(
+                    return Err(crate::user_error(
                         hyper::StatusCode::BAD_REQUEST,
-                        lang.tr(&lang::comment_empty()).into_owned(),
-                    )));
+                        lang,
+                        &lang::comment_empty(),
+                    ));
                 }
 
                 (Some(text), None, None)
@@ -1434,11 +2242,67 @@ pub async fn process_comment_content<'a, 'b>(
     })
 }
 
+/// Requires the request to be from a logged-in site admin, returning their ID
+pub async fn require_site_admin(
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+) -> Result<UserLocalID, crate::Error> {
+    let lang = crate::get_lang_for_req(req);
+
+    let user = crate::require_login(req, db).await?;
+
+    if crate::is_site_admin(db, user).await? {
+        Ok(user)
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::not_admin(),
+        ))
+    }
+}
+
+/// Whether the user moderates the community, or is a site admin
+pub async fn can_moderate_community(
+    db: &tokio_postgres::Client,
+    user: UserLocalID,
+    community: CommunityLocalID,
+) -> Result<bool, crate::Error> {
+    let row = db.query_one(
+        "SELECT EXISTS(SELECT 1 FROM community_moderator WHERE community=$1 AND person=$2) OR EXISTS(SELECT 1 FROM person WHERE id=$2 AND is_site_admin)",
+        &[&community, &user],
+    )
+    .await?;
+
+    Ok(row.get(0))
+}
+
+/// Requires the request to be from a logged-in moderator of the community or a site admin, returning their ID
+pub async fn require_community_moderator(
+    community: CommunityLocalID,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+) -> Result<UserLocalID, crate::Error> {
+    let lang = crate::get_lang_for_req(req);
+
+    let user = crate::require_login(req, db).await?;
+
+    if can_moderate_community(db, user, community).await? {
+        Ok(user)
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::must_be_moderator(),
+        ))
+    }
+}
+
 pub async fn fetch_login_info(
     db: &tokio_postgres::Client,
     user: UserLocalID,
 ) -> Result<RespLoginInfo, crate::Error> {
-    let row = db.query_one("SELECT username, is_site_admin, EXISTS(SELECT 1 FROM notification WHERE to_user = person.id AND created_at > person.last_checked_notifications), EXISTS(SELECT 1 FROM flag INNER JOIN post ON (post.id = post) WHERE flag.to_community AND NOT flag.to_community_dismissed AND post.approved AND post.community IN (SELECT community FROM community_moderator WHERE person=person.id)), site.community_creation_requirement, site.allow_invitations, site.users_create_invitations FROM person, site WHERE site.local AND id=$1", &[&user]).await?;
+    let row = db.query_one("SELECT username, is_site_admin, EXISTS(SELECT 1 FROM notification WHERE to_user = person.id AND created_at > person.last_checked_notifications), EXISTS(SELECT 1 FROM flag INNER JOIN post ON (post.id = post) WHERE flag.to_community AND NOT flag.to_community_dismissed AND post.approved AND post.community IN (SELECT community FROM community_moderator WHERE person=person.id)), site.community_creation_requirement, site.allow_invitations, site.users_create_invitations, (SELECT COUNT(*) FROM notification WHERE to_user = person.id AND NOT read), person.preferred_languages, person.preferences, person.notify_community_announcements FROM person, site WHERE site.local AND id=$1", &[&user]).await?;
 
     let is_site_admin = row.get(1);
 
@@ -1448,7 +2312,15 @@ pub async fn fetch_login_info(
             username: row.get(0),
             is_site_admin,
             has_unread_notifications: row.get(2),
+            unread_notifications_count: row.get(7),
             has_pending_moderation_actions: row.get(3),
+            preferred_languages: row.get(8),
+            preferences: RespUserPreferences {
+                base: row
+                    .get::<_, postgres_types::Json<UserPreferences<'static>>>(9)
+                    .0,
+                notify_community_announcements: row.get(10),
+            },
         },
         permissions: RespLoginPermissions {
             create_community: RespPermissionInfo {
@@ -0,0 +1,347 @@
+use crate::lang;
+use crate::types::{
+    CommentLocalID, CommunityLocalID, ModQueueReason, PostLocalID, RespList,
+    RespMinimalCommentInfo, RespMinimalPostInfo, RespModQueueItem, RespModQueueItemDetails,
+    UserLocalID,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Posts with at least this many open reports are listed in the queue
+const REPORTED_THRESHOLD: i64 = 2;
+
+const MAX_ITEMS: i64 = 100;
+
+async fn route_unstable_communities_modqueue_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_community_moderator(community_id, &req, &db).await?;
+
+    let rows = db.query(
+        "SELECT * FROM ((SELECT post.id, post.title, post.local, post.ap_id, post.sensitive, NULL::BIGINT AS reply_id, NULL::TEXT, NULL::TEXT, NULL::BOOLEAN, NULL::TEXT, NULL::BOOLEAN, post.pending_approval, (SELECT COUNT(*) FROM flag WHERE flag.post = post.id AND flag.to_community AND NOT flag.to_community_dismissed) AS flag_count, post.created, person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, EXISTS(SELECT 1 FROM flag WHERE flag.post = post.id AND flag.automod_rule IS NOT NULL AND flag.to_community AND NOT flag.to_community_dismissed) AS automod_flagged FROM post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.community=$1 AND NOT post.deleted) UNION ALL (SELECT post.id, post.title, post.local, post.ap_id, post.sensitive, reply.id, reply.content_text, reply.content_html, reply.local, reply.ap_id, reply.sensitive, reply.pending_approval, (SELECT COUNT(*) FROM flag WHERE flag.reply = reply.id AND flag.to_community AND NOT flag.to_community_dismissed) AS flag_count, reply.created, person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, EXISTS(SELECT 1 FROM flag WHERE flag.reply = reply.id AND flag.automod_rule IS NOT NULL AND flag.to_community AND NOT flag.to_community_dismissed) FROM reply INNER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person ON (person.id = reply.author) WHERE post.community=$1 AND NOT reply.deleted AND (reply.pending_approval OR EXISTS(SELECT 1 FROM flag WHERE flag.reply = reply.id AND flag.to_community AND NOT flag.to_community_dismissed)))) AS item WHERE pending_approval OR flag_count >= $2 OR automod_flagged ORDER BY created ASC LIMIT $3",
+        &[&community_id, &REPORTED_THRESHOLD, &MAX_ITEMS],
+    ).await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let post_id = PostLocalID(row.get(0));
+            let post_local: bool = row.get(2);
+            let post_ap_id: Option<&str> = row.get(3);
+
+            let post = RespMinimalPostInfo {
+                id: post_id,
+                title: row.get(1),
+                remote_url: if post_local {
+                    Some(Cow::Owned(String::from(
+                        crate::apub_util::LocalObjectRef::Post(post_id)
+                            .to_local_uri(&ctx.host_url_apub),
+                    )))
+                } else {
+                    post_ap_id.map(Cow::Borrowed)
+                },
+                sensitive: row.get(4),
+            };
+
+            let details = match row.get::<_, Option<_>>(5).map(CommentLocalID) {
+                None => RespModQueueItemDetails::Post { post },
+                Some(comment_id) => {
+                    let comment_local: bool = row.get(8);
+                    let comment_ap_id: Option<&str> = row.get(9);
+
+                    RespModQueueItemDetails::Comment {
+                        comment: RespMinimalCommentInfo {
+                            id: comment_id,
+                            remote_url: if comment_local {
+                                Some(Cow::Owned(String::from(
+                                    crate::apub_util::LocalObjectRef::Comment(comment_id)
+                                        .to_local_uri(&ctx.host_url_apub),
+                                )))
+                            } else {
+                                comment_ap_id.map(Cow::Borrowed)
+                            },
+                            sensitive: row.get(10),
+                            content_text: row.get::<_, Option<&str>>(6).map(Cow::Borrowed),
+                            content_html_safe: row.get::<_, Option<&str>>(7).map(crate::clean_html),
+                        },
+                        post,
+                    }
+                }
+            };
+
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(13);
+
+            RespModQueueItem {
+                reason: if row.get(11) {
+                    ModQueueReason::Held
+                } else {
+                    ModQueueReason::Reported
+                },
+                flag_count: row.get(12),
+                created: created.to_rfc3339(),
+                author: super::AuthorColumns::starting_at(14).get(row, &ctx),
+                details,
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn decide_post(
+    community_id: CommunityLocalID,
+    post_id: PostLocalID,
+    approved: bool,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let user = super::require_community_moderator(community_id, &req, &db).await?;
+
+    let old_row = db
+        .query_opt(
+            "SELECT post.approved, post.local, post.ap_id, post.pending_approval, community.local FROM post INNER JOIN community ON (community.id = post.community) WHERE post.id=$1 AND post.community=$2 AND NOT post.deleted",
+            &[&post_id, &community_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
+        })?;
+
+    let old_approved: bool = old_row.get(0);
+    let post_local: bool = old_row.get(1);
+    let was_pending: bool = old_row.get(3);
+    let community_local: bool = old_row.get(4);
+
+    let post_ap_id = if post_local {
+        crate::apub_util::LocalObjectRef::Post(post_id)
+            .to_local_uri(&ctx.host_url_apub)
+            .into()
+    } else {
+        std::str::FromStr::from_str(old_row.get(2))?
+    };
+
+    {
+        let trans = db.transaction().await?;
+
+        // remote communities decide approval themselves, so only an announced post can be shown
+        trans
+            .execute(
+                "UPDATE post SET approved=(CASE WHEN $3 THEN $2 ELSE $2 AND approved_ap_id IS NOT NULL END), rejected=(NOT $2), pending_approval=FALSE WHERE id=$1",
+                &[&post_id, &approved, &community_local],
+            )
+            .await?;
+        trans
+            .execute(
+                "UPDATE flag SET to_community_dismissed=TRUE WHERE post=$1 AND to_community",
+                &[&post_id],
+            )
+            .await?;
+
+        let action = if approved {
+            "approve_post"
+        } else {
+            "reject_post"
+        };
+        trans.execute("INSERT INTO modlog_event (time, by_community, by_person, action, post) VALUES (current_timestamp, $1, $2, $3, $4)", &[&community_id, &user, &action, &post_id]).await?;
+
+        // held local posts haven't been sent anywhere yet
+        if approved && was_pending && post_local {
+            ctx.enqueue_task_in_transaction(
+                &trans,
+                &crate::tasks::LocalPostCreated { post: post_id },
+            )
+            .await?;
+        }
+
+        trans.commit().await?;
+    }
+
+    ctx.notify_worker()?;
+
+    if community_local && approved != old_approved {
+        if approved {
+            crate::apub_util::spawn_enqueue_announce_community_post(
+                community_id,
+                post_id,
+                post_ap_id,
+                ctx,
+            );
+        } else {
+            crate::apub_util::spawn_enqueue_send_community_post_announce_undo(
+                community_id,
+                post_id,
+                post_ap_id,
+                ctx,
+            );
+        }
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn decide_comment(
+    community_id: CommunityLocalID,
+    comment_id: CommentLocalID,
+    approved: bool,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let user = super::require_community_moderator(community_id, &req, &db).await?;
+
+    let row = db.query_opt(
+        "SELECT reply.author, reply.post, reply.parent, reply.content_text, reply.content_markdown, reply.content_html, reply.created, reply.local, reply.ap_id, reply.attachment_href, reply.sensitive, reply.pending_approval FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.id=$1 AND post.community=$2 AND NOT reply.deleted",
+        &[&comment_id, &community_id],
+    ).await?.ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_comment())
+    })?;
+
+    db.execute(
+        "UPDATE flag SET to_community_dismissed=TRUE WHERE reply=$1 AND to_community",
+        &[&comment_id],
+    )
+    .await?;
+
+    if approved {
+        if !row.get::<_, bool>(11) {
+            // only reported, already visible
+            return Ok(crate::empty_response());
+        }
+
+        db.execute(
+            "UPDATE reply SET pending_approval=FALSE WHERE id=$1",
+            &[&comment_id],
+        )
+        .await?;
+
+        let local: bool = row.get(7);
+
+        let info = crate::CommentInfo {
+            id: comment_id,
+            author: row.get::<_, Option<_>>(0).map(UserLocalID),
+            post: PostLocalID(row.get(1)),
+            parent: row.get::<_, Option<_>>(2).map(CommentLocalID),
+            content_text: row.get::<_, Option<String>>(3).map(Cow::Owned),
+            content_markdown: row.get::<_, Option<String>>(4).map(Cow::Owned),
+            content_html: row.get::<_, Option<String>>(5).map(Cow::Owned),
+            created: row.get(6),
+            ap_id: if local {
+                crate::APIDOrLocal::Local
+            } else {
+                crate::APIDOrLocal::APID(std::str::FromStr::from_str(row.get(8))?)
+            },
+            attachment_href: row.get::<_, Option<String>>(9).map(Cow::Owned),
+            sensitive: row.get(10),
+        };
+
+        crate::on_post_add_comment(info, ctx);
+    } else {
+        let trans = db.transaction().await?;
+
+        trans.execute(
+            "UPDATE reply SET content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE, pending_approval=FALSE WHERE id=$1",
+            &[&comment_id],
+        )
+        .await?;
+        trans.execute("INSERT INTO modlog_event (time, by_person, action, reply) VALUES (current_timestamp, $1, 'delete_reply', $2)", &[&user, &comment_id]).await?;
+
+        trans.commit().await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_communities_modqueue_posts_approve(
+    params: (CommunityLocalID, PostLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, post_id) = params;
+    decide_post(community_id, post_id, true, ctx, req).await
+}
+
+async fn route_unstable_communities_modqueue_posts_reject(
+    params: (CommunityLocalID, PostLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, post_id) = params;
+    decide_post(community_id, post_id, false, ctx, req).await
+}
+
+async fn route_unstable_communities_modqueue_comments_approve(
+    params: (CommunityLocalID, CommentLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, comment_id) = params;
+    decide_comment(community_id, comment_id, true, ctx, req).await
+}
+
+async fn route_unstable_communities_modqueue_comments_reject(
+    params: (CommunityLocalID, CommentLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, comment_id) = params;
+    decide_comment(community_id, comment_id, false, ctx, req).await
+}
+
+pub fn route_communities_modqueue() -> crate::RouteNode<(CommunityLocalID,)> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_communities_modqueue_list)
+        .with_child(
+            "comments",
+            crate::RouteNode::new().with_child_parse::<CommentLocalID, _>(
+                crate::RouteNode::new()
+                    .with_child(
+                        "approve",
+                        crate::RouteNode::new().with_handler_async(
+                            hyper::Method::POST,
+                            route_unstable_communities_modqueue_comments_approve,
+                        ),
+                    )
+                    .with_child(
+                        "reject",
+                        crate::RouteNode::new().with_handler_async(
+                            hyper::Method::POST,
+                            route_unstable_communities_modqueue_comments_reject,
+                        ),
+                    ),
+            ),
+        )
+        .with_child(
+            "posts",
+            crate::RouteNode::new().with_child_parse::<PostLocalID, _>(
+                crate::RouteNode::new()
+                    .with_child(
+                        "approve",
+                        crate::RouteNode::new().with_handler_async(
+                            hyper::Method::POST,
+                            route_unstable_communities_modqueue_posts_approve,
+                        ),
+                    )
+                    .with_child(
+                        "reject",
+                        crate::RouteNode::new().with_handler_async(
+                            hyper::Method::POST,
+                            route_unstable_communities_modqueue_posts_reject,
+                        ),
+                    ),
+            ),
+        )
+}
@@ -0,0 +1,300 @@
+use crate::lang;
+use crate::types::{
+    CommentLocalID, CommunityLocalID, PostLocalID, RespError, RespList, RespMuteInfo,
+    RespMutedError, UserLocalID,
+};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const MAX_MUTE_DURATION_SECONDS: u32 = 60 * 60 * 24 * 365;
+
+async fn check_mute(
+    user: UserLocalID,
+    community_expr: &str,
+    param: &(dyn postgres_types::ToSql + Sync),
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let sql: &str = &format!("SELECT community, expires_at FROM person_mute WHERE person=$1 AND (community IS NULL OR community=({})) AND expires_at > current_timestamp ORDER BY expires_at DESC LIMIT 1", community_expr);
+
+    let row = db.query_opt(sql, &[&user, param]).await?;
+
+    match row {
+        None => Ok(()),
+        Some(row) => {
+            let expires_at: chrono::DateTime<chrono::FixedOffset> = row.get(1);
+            let expires_at = expires_at.to_rfc3339();
+
+            let body = serde_json::to_vec(&RespMutedError {
+                base: RespError {
+                    code: "user_muted",
+                    message: lang.tr(&lang::user_muted(expires_at.as_str())),
+                },
+                community: row.get::<_, Option<CommunityLocalID>>(0),
+                expires_at: Cow::Borrowed(&expires_at),
+            })?;
+
+            Err(crate::Error::UserError(
+                crate::common_response_builder()
+                    .status(hyper::StatusCode::FORBIDDEN)
+                    .header(hyper::header::CONTENT_TYPE, "application/json")
+                    .body(body.into())?,
+            ))
+        }
+    }
+}
+
+pub async fn require_not_muted(
+    user: UserLocalID,
+    community: CommunityLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    check_mute(user, "$2", &community, db, lang).await
+}
+
+pub async fn require_not_muted_for_post(
+    user: UserLocalID,
+    post: PostLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    check_mute(
+        user,
+        "SELECT community FROM post WHERE id=$2",
+        &post,
+        db,
+        lang,
+    )
+    .await
+}
+
+pub async fn require_not_muted_for_comment(
+    user: UserLocalID,
+    comment: CommentLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    check_mute(
+        user,
+        "SELECT post.community FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.id=$2",
+        &comment,
+        db,
+        lang,
+    )
+    .await
+}
+
+async fn require_can_mute(
+    community: Option<CommunityLocalID>,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+) -> Result<UserLocalID, crate::Error> {
+    match community {
+        None => super::require_site_admin(req, db).await,
+        Some(community) => super::require_community_moderator(community, req, db).await,
+    }
+}
+
+async fn list_mutes(
+    community: Option<CommunityLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    require_can_mute(community, &req, &db).await?;
+
+    let rows = db.query(
+        "SELECT person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, by_person.id, by_person.username, by_person.local, by_person.ap_id, by_person.avatar, by_person.is_bot, person_mute.created_at, person_mute.expires_at, person_mute.reason FROM person_mute INNER JOIN person ON (person.id = person_mute.person) LEFT OUTER JOIN person AS by_person ON (by_person.id = person_mute.by_person) WHERE person_mute.community IS NOT DISTINCT FROM $1 AND person_mute.expires_at > current_timestamp ORDER BY person_mute.expires_at ASC",
+        &[&community],
+    ).await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .filter_map(|row| {
+            let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(12);
+            let expires_at: chrono::DateTime<chrono::FixedOffset> = row.get(13);
+
+            Some(RespMuteInfo {
+                user: super::AuthorColumns::starting_at(0).get(row, &ctx)?,
+                by: super::AuthorColumns::starting_at(6).get(row, &ctx),
+                created_at: created_at.to_rfc3339(),
+                expires_at: expires_at.to_rfc3339(),
+                reason: row.get::<_, Option<&str>>(14).map(Cow::Borrowed),
+            })
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn set_mute(
+    community: Option<CommunityLocalID>,
+    user_id: UserLocalID,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct MuteBody<'a> {
+        duration_seconds: u32,
+        reason: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let login_user = require_can_mute(community, &req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: MuteBody = serde_json::from_slice(&body)?;
+
+    if body.duration_seconds == 0 || body.duration_seconds > MAX_MUTE_DURATION_SECONDS {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::mute_duration_invalid(MAX_MUTE_DURATION_SECONDS),
+        ));
+    }
+
+    let duration = f64::from(body.duration_seconds);
+
+    {
+        let trans = db.transaction().await?;
+
+        let row_count = trans.execute(
+            "INSERT INTO person_mute (person, community, by_person, created_at, expires_at, reason) SELECT $1, $2, $3, current_timestamp, current_timestamp + make_interval(secs => $4), $5 FROM person WHERE id=$1 ON CONFLICT (person, (COALESCE(community, 0))) DO UPDATE SET by_person=$3, created_at=current_timestamp, expires_at=current_timestamp + make_interval(secs => $4), reason=$5",
+            &[&user_id, &community, &login_user, &duration, &body.reason],
+        ).await?;
+
+        if row_count == 0 {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_user(),
+            ));
+        }
+
+        trans.execute(
+            "INSERT INTO modlog_event (time, by_community, by_person, action, person, reason) VALUES (current_timestamp, $1, $2, 'mute_user', $3, $4)",
+            &[&community, &login_user, &user_id, &body.reason],
+        ).await?;
+
+        trans.commit().await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn remove_mute(
+    community: Option<CommunityLocalID>,
+    user_id: UserLocalID,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let mut db = ctx.db_pool.get().await?;
+
+    let login_user = require_can_mute(community, &req, &db).await?;
+
+    {
+        let trans = db.transaction().await?;
+
+        let row_count = trans
+            .execute(
+                "DELETE FROM person_mute WHERE person=$1 AND community IS NOT DISTINCT FROM $2 AND expires_at > current_timestamp",
+                &[&user_id, &community],
+            )
+            .await?;
+
+        if row_count > 0 {
+            trans.execute(
+                "INSERT INTO modlog_event (time, by_community, by_person, action, person) VALUES (current_timestamp, $1, $2, 'unmute_user', $3)",
+                &[&community, &login_user, &user_id],
+            ).await?;
+        }
+
+        trans.commit().await?;
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_communities_mutes_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    list_mutes(Some(community_id), ctx, req).await
+}
+
+async fn route_unstable_communities_mutes_set(
+    params: (CommunityLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, user_id) = params;
+    set_mute(Some(community_id), user_id, ctx, req).await
+}
+
+async fn route_unstable_communities_mutes_remove(
+    params: (CommunityLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, user_id) = params;
+    remove_mute(Some(community_id), user_id, ctx, req).await
+}
+
+async fn route_unstable_instance_mutes_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    list_mutes(None, ctx, req).await
+}
+
+async fn route_unstable_instance_mutes_set(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+    set_mute(None, user_id, ctx, req).await
+}
+
+async fn route_unstable_instance_mutes_remove(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+    remove_mute(None, user_id, ctx, req).await
+}
+
+pub fn route_communities_mutes() -> crate::RouteNode<(CommunityLocalID,)> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_communities_mutes_list)
+        .with_child_parse::<UserLocalID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::PUT, route_unstable_communities_mutes_set)
+                .with_handler_async(
+                    hyper::Method::DELETE,
+                    route_unstable_communities_mutes_remove,
+                ),
+        )
+}
+
+pub fn route_instance_mutes() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_instance_mutes_list)
+        .with_child_parse::<UserLocalID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::PUT, route_unstable_instance_mutes_set)
+                .with_handler_async(hyper::Method::DELETE, route_unstable_instance_mutes_remove),
+        )
+}
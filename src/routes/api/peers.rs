@@ -0,0 +1,89 @@
+use crate::types::{RespList, RespPeerInfo};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+async fn route_unstable_instance_peers_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.get_db_read().await?;
+
+    let rows = db
+        .query(
+            "SELECT host FROM remote_host WHERE NOT blocked ORDER BY host ASC",
+            &[],
+        )
+        .await?;
+
+    let hosts: Vec<&str> = rows.iter().map(|row| row.get(0)).collect();
+
+    crate::json_response(&hosts)
+}
+
+async fn route_unstable_instance_peers_counts_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct PeersCountsListQuery<'a> {
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+    }
+
+    let query: PeersCountsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let db = ctx.get_db_read().await?;
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let mut rows = db.query(
+        "SELECT host, (SELECT COUNT(*) FROM person WHERE NOT local AND url_host_key(ap_id) = remote_host.host), (SELECT COUNT(*) FROM community WHERE NOT local AND url_host_key(ap_id) = remote_host.host), software_name, last_delivery_at FROM remote_host WHERE NOT blocked AND ($1::TEXT IS NULL OR host >= $1) ORDER BY host ASC LIMIT $2",
+        &[&query.page, &limit_plus_1],
+    ).await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(Cow::Owned(row.get::<_, String>(0)))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| RespPeerInfo {
+            host: Cow::Borrowed(row.get(0)),
+            users: row.get(1),
+            communities: row.get(2),
+            software_name: row.get::<_, Option<&str>>(3).map(Cow::Borrowed),
+            last_delivery_at: row
+                .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(4)
+                .map(|time| time.to_rfc3339()),
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page,
+    })
+}
+
+pub fn route_peers() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_instance_peers_list)
+        .with_child(
+            "counts",
+            crate::RouteNode::new().with_handler_async(
+                hyper::Method::GET,
+                route_unstable_instance_peers_counts_list,
+            ),
+        )
+}
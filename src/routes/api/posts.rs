@@ -1,12 +1,15 @@
 use super::{
-    InvalidPage, JustURL, RespAvatarInfo, RespList, RespMinimalAuthorInfo, RespMinimalCommentInfo,
-    RespMinimalCommunityInfo, RespPostCommentInfo, RespPostListPost, ValueConsumer,
+    AuthorColumns, CommunityColumns, InvalidPage, JustURL, RespList, RespMinimalAuthorInfo,
+    RespMinimalCommentInfo, RespPostCommentInfo, RespPostListPost, ValueConsumer,
 };
 use crate::lang;
 use crate::types::{
-    ActorLocalRef, CommentLocalID, CommunityLocalID, FlagLocalID, JustID, JustUser, PollLocalID,
-    PollOptionLocalID, PollVoteBody, PostLocalID, RespPollInfo, RespPollOption, RespPollYourVote,
-    RespPostInfo, UserLocalID,
+    ActorLocalRef, AutomodAction, CommentLocalID, CommunityLocalID, FlagCategory, FlagLocalID,
+    FlairLocalID, JustID, JustUser, PollLocalID, PollOptionLocalID, PollVoteBody, PostLocalID,
+    RespFlairInfo, RespPollInfo, RespPollOption, RespPollYourVote, RespPostAttachment,
+    RespPostCreated, RespPostInfo, RespReactionInfo, RespThumbnailInfo, RespVoteAnalytics,
+    RespVoteHostStat, RespVoteResult, RespVoteStat, RespVoteWindowStat, RespYourVote, UserLocalID,
+    WebhookEvent,
 };
 use crate::BaseURL;
 use serde_derive::Deserialize;
@@ -15,30 +18,84 @@ use std::collections::HashSet;
 use std::fmt::Write;
 use std::sync::Arc;
 
+const MAX_POST_ATTACHMENTS: usize = 10;
+const MAX_VOTE_ANALYTICS_WINDOW_MINUTES: u32 = 60 * 24 * 7;
+
+/// Votes from accounts without other activity are flagged once there are at least this many and they
+/// make up at least half of the group
+const VOTE_ANOMALY_MIN_VOTES: i64 = 5;
+
+/// Hides posts in private communities from users who aren't members
+pub async fn require_post_visible(
+    post: PostLocalID,
+    req: &hyper::Request<hyper::Body>,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let row = db
+        .query_opt("SELECT community FROM post WHERE id=$1", &[&post])
+        .await?;
+
+    if let Some(row) = row {
+        let user = crate::authenticate(req, db).await?;
+        if !crate::can_view_community(db, user, row.get(0)).await? {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                lang,
+                &lang::no_such_post(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn require_post_not_locked(
+    post: PostLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let row = db
+        .query_opt("SELECT locked FROM post WHERE id=$1", &[&post])
+        .await?;
+
+    match row {
+        Some(row) if row.get(0) => Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            lang,
+            &lang::post_locked(),
+        )),
+        _ => Ok(()),
+    }
+}
+
 async fn get_post_comments<'a>(
     post_id: PostLocalID,
     include_your_for: Option<UserLocalID>,
     sort: super::SortType,
+    depth: u8,
     limit: u8,
+    replies_limit: u8,
     page: Option<&'a str>,
-    db: &tokio_postgres::Client,
+    db: &crate::query::DbClient,
     ctx: &'a crate::BaseContext,
+    lang: &crate::Translator,
 ) -> Result<(Vec<RespPostCommentInfo<'a>>, Option<String>), crate::Error> {
     use futures::TryStreamExt;
 
     let limit_i = i64::from(limit) + 1;
 
-    let sql1 = "SELECT reply.id, reply.author, reply.content_text, reply.created, reply.content_html, person.username, person.local, person.ap_id, reply.deleted, person.avatar, attachment_href, reply.local, (SELECT COUNT(*) FROM reply_like WHERE reply = reply.id), reply.content_markdown, person.is_bot, reply.ap_id, reply.local, reply.sensitive";
+    let sql1 = "SELECT reply.id, reply.author, reply.content_text, reply.created, reply.content_html, person.username, person.local, person.ap_id, reply.deleted, person.avatar, attachment_href, reply.local, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply = reply.id), reply.content_markdown, person.is_bot, reply.ap_id, reply.local, reply.sensitive, (SELECT hide_scores_minutes FROM community WHERE id=(SELECT community FROM post WHERE id=reply.post)), reply.updated";
     let (sql2, mut values): (_, Vec<&(dyn tokio_postgres::types::ToSql + Sync)>) =
         if include_your_for.is_some() {
             (
-                ", EXISTS(SELECT 1 FROM reply_like WHERE reply = reply.id AND person = $3)",
+                ", (SELECT is_dislike FROM reply_like WHERE reply = reply.id AND person = $3)",
                 vec![&post_id, &limit_i, &include_your_for],
             )
         } else {
             ("", vec![&post_id, &limit_i])
         };
-    let mut sql3 = " FROM reply LEFT OUTER JOIN person ON (person.id = reply.author) WHERE post=$1 AND parent IS NULL ".to_owned();
+    let mut sql3 = " FROM reply LEFT OUTER JOIN person ON (person.id = reply.author) WHERE post=$1 AND parent IS NULL AND NOT reply.pending_approval ".to_owned();
     let mut sql4 = format!("ORDER BY {} LIMIT $2", sort.comment_sort_sql());
 
     let mut con1 = None;
@@ -54,7 +111,7 @@ async fn get_post_comments<'a>(
                 used: 0,
             },
         )
-        .map_err(super::InvalidPage::into_user_error)?;
+        .map_err(|err| err.into_user_error(lang))?;
     if let Some(value) = &con1 {
         values.push(value.as_ref());
         if let Some(value) = &con2 {
@@ -71,18 +128,19 @@ async fn get_post_comments<'a>(
 
     let sql: &str = &format!("{}{}{}{}", sql1, sql2, sql3, sql4);
 
-    let stream = crate::query_stream(db, sql, &values[..]).await?;
+    let stream = crate::query::query_stream(db, sql, &values[..]).await?;
 
     let mut comments: Vec<_> = stream
         .map_err(crate::Error::from)
         .and_then(|row| {
-            let id = CommentLocalID(row.get(0));
+            let id: CommentLocalID = row.get(0);
             let content_text: Option<String> = row.get(2);
             let content_html: Option<String> = row.get(4);
             let created: chrono::DateTime<chrono::FixedOffset> = row.get(3);
             let ap_id: Option<String> = row.get(15);
             let local: bool = row.get(16);
             let sensitive: bool = row.get(17);
+            let updated: Option<chrono::DateTime<chrono::FixedOffset>> = row.get(19);
 
             let remote_url = if local {
                 Some(String::from(
@@ -92,38 +150,16 @@ async fn get_post_comments<'a>(
                 ap_id
             };
 
-            let author_username: Option<String> = row.get(5);
-            let author = author_username.map(|author_username| {
-                let author_id = UserLocalID(row.get(1));
-                let author_local: bool = row.get(6);
-                let author_ap_id: Option<&str> = row.get(7);
-                let author_avatar: Option<&str> = row.get(9);
-
-                let author_remote_url = if author_local {
-                    Some(String::from(
-                        crate::apub_util::LocalObjectRef::User(author_id)
-                            .to_local_uri(&ctx.host_url_apub),
-                    ))
-                } else {
-                    author_ap_id.map(ToOwned::to_owned)
-                };
-
-                RespMinimalAuthorInfo {
-                    id: author_id,
-                    username: author_username.into(),
-                    local: author_local,
-                    host: crate::get_actor_host_or_unknown(
-                        author_local,
-                        author_ap_id,
-                        &ctx.local_hostname,
-                    ),
-                    remote_url: author_remote_url.map(Cow::Owned),
-                    is_bot: row.get(14),
-                    avatar: author_avatar.map(|url| RespAvatarInfo {
-                        url: ctx.process_avatar_href(url, author_id).into_owned().into(),
-                    }),
-                }
-            });
+            let author = AuthorColumns {
+                id: 1,
+                username: 5,
+                local: 6,
+                ap_id: 7,
+                avatar: 9,
+                is_bot: 14,
+            }
+            .get(&row, ctx)
+            .map(RespMinimalAuthorInfo::into_owned);
 
             futures::future::ok((
                 (),
@@ -146,15 +182,13 @@ async fn get_post_comments<'a>(
                     content_markdown: row.get::<_, Option<String>>(13).map(Cow::Owned),
                     created: created.to_rfc3339(),
                     deleted: row.get(8),
+                    edited_at: updated.map(|time| time.to_rfc3339()),
                     local: row.get(11),
                     replies: Some(RespList::empty()),
-                    score: row.get(12),
+                    score: super::get_resp_score(row.get(12), &created, row.get(18)),
                     your_vote: include_your_for.map(|_| {
-                        if row.get(18) {
-                            Some(crate::types::Empty {})
-                        } else {
-                            None
-                        }
+                        row.get::<_, Option<bool>>(20)
+                            .map(|is_dislike| RespYourVote { is_dislike })
                     }),
                 },
             ))
@@ -168,7 +202,20 @@ async fn get_post_comments<'a>(
         None
     };
 
-    super::apply_comments_replies(&mut comments, include_your_for, 2, limit, sort, db, ctx).await?;
+    let mut remaining = ctx.max_comments_per_request.saturating_sub(comments.len());
+
+    super::apply_comments_replies(
+        &mut comments,
+        include_your_for,
+        depth.saturating_sub(1),
+        replies_limit,
+        sort,
+        &mut remaining,
+        db,
+        ctx,
+        lang,
+    )
+    .await?;
 
     Ok((
         comments.into_iter().map(|(_, comment)| comment).collect(),
@@ -180,6 +227,16 @@ async fn route_unstable_posts_list(
     _: (),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    list_posts(None, None, ctx, req).await
+}
+
+/// Shared by the global post listing and the per-community and per-hashtag listings, `community` takes precedence over the query
+pub async fn list_posts(
+    community: Option<CommunityLocalID>,
+    hashtag: Option<String>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     #[derive(Deserialize)]
     #[serde(rename_all = "snake_case")]
@@ -256,11 +313,20 @@ async fn route_unstable_posts_list(
     struct PostsListQuery<'a> {
         in_any_local_community: Option<bool>,
         in_your_follows: Option<bool>,
+        hosted_locally: Option<bool>,
         search: Option<Cow<'a, str>>,
         #[serde(default)]
         use_aggregate_filters: bool,
         community: Option<CommunityLocalID>,
         created_within: Option<Cow<'a, str>>,
+        flair: Option<FlairLocalID>,
+        href: Option<Cow<'a, str>>,
+        #[serde(default)]
+        in_your_languages: bool,
+        #[serde(default)]
+        exclude_your_hidden: bool,
+        #[serde(default)]
+        exclude_your_likes: bool,
 
         #[serde(default = "default_limit")]
         limit: u8,
@@ -273,35 +339,55 @@ async fn route_unstable_posts_list(
         #[serde(default)]
         sort: PostsListSortType,
 
+        within: Option<super::TopWindow>,
+
         #[serde(default)]
         sort_sticky: bool,
     }
 
+    let lang = crate::get_lang_for_req(&req);
+
     let query: PostsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
+    let community = community.or(query.community);
+
     let created_within = query
         .created_within
         .as_deref()
         .map(|x| date_duration::DateDuration::parse_iso8601(x))
         .transpose()
         .map_err(|_| {
-            crate::Error::UserError(crate::simple_response(
+            crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Invalid duration for created_within",
-            ))
+                &lang,
+                &lang::duration_invalid("created_within"),
+            )
         })?
         .map(|x| x.to_iso8601_long());
 
-    let lang = crate::get_lang_for_req(&req);
-    let db = ctx.db_pool.get().await?;
+    // authenticate against the primary, since a new login may not have reached the replica yet
+    let login_user_maybe = {
+        let db = ctx.db_pool.get().await?;
+        crate::authenticate(&req, &db).await?
+    };
+    let require_login = || {
+        login_user_maybe.ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::UNAUTHORIZED,
+                &lang,
+                &lang::login_required(),
+            )
+        })
+    };
 
     let include_your_for = if query.include_your {
-        let user = crate::require_login(&req, &db).await?;
-        Some(user)
+        Some(require_login()?)
     } else {
         None
     };
 
+    let db = ctx.get_db_read().await?;
+
     let limit_plus_1: i64 = (query.limit + 1).into();
 
     let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&limit_plus_1];
@@ -320,11 +406,11 @@ async fn route_unstable_posts_list(
         None
     };
 
-    let mut sql = "SELECT post.id, post.author, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, community.id, community.name, community.local, community.ap_id, person.username, person.local, person.ap_id, person.avatar, (SELECT COUNT(*) FROM post_like WHERE post_like.post = post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, person.is_bot, post.ap_id, post.local, community.deleted, post.sensitive".to_owned();
+    let mut sql = "SELECT post.id, post.author, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, community.id, community.name, community.local, community.ap_id, person.username, person.local, person.ap_id, person.avatar, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, person.is_bot, post.ap_id, post.local, community.deleted, post.sensitive, community.hide_scores_minutes, (SELECT COALESCE(json_agg(json_build_object('id', community_flair.id, 'name', community_flair.name) ORDER BY community_flair.name), '[]') FROM post_flair INNER JOIN community_flair ON (community_flair.id = post_flair.flair) WHERE post_flair.post = post.id)".to_owned();
     if let Some(idx) = include_your_idx {
         write!(
             sql,
-            ", EXISTS(SELECT 1 FROM post_like WHERE post=post.id AND person=${})",
+            ", (SELECT is_dislike FROM post_like WHERE post=post.id AND person=${})",
             idx
         )
         .unwrap();
@@ -343,10 +429,26 @@ async fn route_unstable_posts_list(
         false
     };
 
-    sql.push_str( " FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.community = community.id AND post.deleted=FALSE AND post.approved");
+    sql.push_str( " FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) INNER JOIN post_score ON (post_score.post = post.id) WHERE post.community = community.id AND post.deleted=FALSE AND post.approved");
     if query.use_aggregate_filters {
         sql.push_str(" AND community.hide_posts_from_aggregates=FALSE");
     }
+    if community.is_none() && query.in_your_follows != Some(true) {
+        sql.push_str(" AND NOT community.unlisted AND NOT community.private");
+    }
+    let maybe_viewer_id;
+    let viewer_idx = match include_your_idx {
+        Some(idx) => Some(idx),
+        None => match login_user_maybe {
+            Some(user) => {
+                maybe_viewer_id = user;
+                values.push(&maybe_viewer_id);
+                Some(values.len())
+            }
+            None => None,
+        },
+    };
+    write!(sql, " AND {}", crate::community_visible_sql(viewer_idx)).unwrap();
     if let Some(search_value_idx) = &search_value_idx {
         write!(sql, " AND to_tsvector('english', title || ' ' || COALESCE(content_text, content_markdown, content_html, '')) @@ plainto_tsquery('english', ${})", search_value_idx).unwrap();
     }
@@ -358,12 +460,15 @@ async fn route_unstable_posts_list(
         )
         .unwrap();
     }
+    if let Some(value) = query.hosted_locally {
+        write!(sql, " AND {}(post.local)", if value { "" } else { "NOT " }).unwrap();
+    }
     let maybe_user_id;
     if let Some(value) = query.in_your_follows {
         let user_idx = match include_your_idx {
             Some(idx) => idx,
             None => {
-                let user = crate::require_login(&req, &db).await?;
+                let user = require_login()?;
                 maybe_user_id = user;
                 values.push(&maybe_user_id);
                 values.len()
@@ -377,19 +482,109 @@ async fn route_unstable_posts_list(
             user_idx,
         ).unwrap();
     }
-    if let Some(value) = &query.community {
+    let maybe_languages_user_id;
+    if query.in_your_languages {
+        let user_idx = match include_your_idx {
+            Some(idx) => idx,
+            None => {
+                let user = require_login()?;
+                maybe_languages_user_id = user;
+                values.push(&maybe_languages_user_id);
+                values.len()
+            }
+        };
+
+        // posts without a known language are always included
+        write!(
+            sql,
+            " AND (post.language IS NULL OR (SELECT cardinality(preferred_languages) = 0 OR post.language = ANY(preferred_languages) FROM person WHERE id=${}))",
+            user_idx,
+        ).unwrap();
+    }
+    let maybe_exclude_user_id;
+    if query.exclude_your_hidden || query.exclude_your_likes {
+        let user_idx = match include_your_idx {
+            Some(idx) => idx,
+            None => {
+                let user = require_login()?;
+                maybe_exclude_user_id = user;
+                values.push(&maybe_exclude_user_id);
+                values.len()
+            }
+        };
+
+        if query.exclude_your_hidden {
+            write!(
+                sql,
+                " AND NOT EXISTS(SELECT 1 FROM post_hide WHERE post_hide.post = post.id AND post_hide.person=${})",
+                user_idx,
+            ).unwrap();
+        }
+        if query.exclude_your_likes {
+            write!(
+                sql,
+                " AND NOT EXISTS(SELECT 1 FROM post_like WHERE post_like.post = post.id AND post_like.person=${} AND NOT post_like.is_dislike)",
+                user_idx,
+            ).unwrap();
+        }
+    }
+    if let Some(value) = &community {
         values.push(value);
         write!(sql, " AND community.id=${}", values.len(),).unwrap();
     }
+    if let Some(value) = &hashtag {
+        values.push(value);
+        write!(
+            sql,
+            " AND post.id IN (SELECT post_hashtag.post FROM post_hashtag INNER JOIN hashtag ON (hashtag.id = post_hashtag.hashtag) WHERE hashtag.name=${})",
+            values.len()
+        )
+        .unwrap();
+    }
+    if let Some(value) = &query.flair {
+        values.push(value);
+        write!(
+            sql,
+            " AND post.id IN (SELECT post FROM post_flair WHERE flair=${})",
+            values.len()
+        )
+        .unwrap();
+    }
+    let href_canonical = query
+        .href
+        .as_deref()
+        .map(|href| ctx.canonicalize_href(href));
+    if let Some(value) = &href_canonical {
+        values.push(value);
+        write!(sql, " AND post.href_canonical=${}", values.len()).unwrap();
+    }
     if let Some(value) = &created_within {
         values.push(value);
         write!(
             sql,
-            " AND created > (current_timestamp - ${}::TEXT::INTERVAL)",
+            " AND post.created > (current_timestamp - ${}::TEXT::INTERVAL)",
             values.len()
         )
         .unwrap();
     }
+    if let Some(within) = &query.within {
+        if !matches!(query.sort, PostsListSortType::Normal(super::SortType::Top)) {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::sort_within_not_top(),
+            ));
+        }
+
+        if let Some(interval) = within.interval_sql() {
+            write!(
+                sql,
+                " AND post_score.created > (current_timestamp - INTERVAL '{}')",
+                interval
+            )
+            .unwrap();
+        }
+    }
 
     let mut con1 = None;
     let mut con2 = None;
@@ -404,7 +599,7 @@ async fn route_unstable_posts_list(
                 used: 0,
             },
         )
-        .map_err(super::InvalidPage::into_user_error)?;
+        .map_err(|err| err.into_user_error(&lang))?;
     if let Some(value) = &con1 {
         values.push(value.as_ref());
         if let Some(value) = &con2 {
@@ -426,10 +621,11 @@ async fn route_unstable_posts_list(
             if let Some(relevance_sql) = relevance_sql {
                 write!(sql, "{} DESC, post.id DESC", relevance_sql).unwrap();
             } else {
-                return Err(crate::Error::UserError(crate::simple_response(
+                return Err(crate::user_error(
                     hyper::StatusCode::BAD_REQUEST,
-                    lang.tr(&lang::sort_relevant_not_search()).into_owned(),
-                )));
+                    &lang,
+                    &lang::sort_relevant_not_search(),
+                ));
             }
         }
     }
@@ -441,23 +637,24 @@ async fn route_unstable_posts_list(
 
     let sql: &str = &sql;
 
-    let rows = db.query(sql, &values).await?;
+    let rows = crate::query::query(&db, sql, &values).await?;
+
+    let thumbnails = super::media::get_thumbnail_blurhashes(
+        rows.iter().filter_map(|row| row.get::<_, Option<&str>>(2)),
+        &db,
+    )
+    .await?;
 
     let posts = rows
         .iter()
         .map(|row| {
-            let id = PostLocalID(row.get(0));
-            let author_id = row.get::<_, Option<_>>(1).map(UserLocalID);
+            let id: PostLocalID = row.get(0);
             let href: Option<&str> = row.get(2);
             let content_text: Option<&str> = row.get(3);
             let content_markdown: Option<&str> = row.get(6);
             let content_html: Option<&str> = row.get(7);
             let title: &str = row.get(4);
             let created: chrono::DateTime<chrono::FixedOffset> = row.get(5);
-            let community_id = CommunityLocalID(row.get(8));
-            let community_name: &str = row.get(9);
-            let community_local: bool = row.get(10);
-            let community_ap_id: Option<&str> = row.get(11);
             let ap_id: Option<&str> = row.get(20);
             let local: bool = row.get(21);
 
@@ -469,62 +666,24 @@ async fn route_unstable_posts_list(
                 ap_id.map(Cow::Borrowed)
             };
 
-            let community_remote_url = if community_local {
-                Some(Cow::Owned(String::from(
-                    crate::apub_util::LocalObjectRef::Community(community_id)
-                        .to_local_uri(&ctx.host_url_apub),
-                )))
-            } else {
-                community_ap_id.map(Cow::Borrowed)
-            };
-
-            let author = author_id.map(|id| {
-                let author_name: &str = row.get(12);
-                let author_local: bool = row.get(13);
-                let author_ap_id: Option<&str> = row.get(14);
-                let author_avatar: Option<&str> = row.get(15);
-
-                let author_remote_url = if author_local {
-                    Some(Cow::Owned(String::from(
-                        crate::apub_util::LocalObjectRef::User(id).to_local_uri(&ctx.host_url_apub),
-                    )))
-                } else {
-                    author_ap_id.map(Cow::Borrowed)
-                };
-
-                RespMinimalAuthorInfo {
-                    id,
-                    username: author_name.into(),
-                    local: author_local,
-                    host: crate::get_actor_host_or_unknown(
-                        author_local,
-                        author_ap_id.as_deref(),
-                        &ctx.local_hostname,
-                    )
-                    .into_owned()
-                    .into(),
-                    remote_url: author_remote_url,
-                    is_bot: row.get(19),
-                    avatar: author_avatar.map(|url| RespAvatarInfo {
-                        url: ctx.process_avatar_href(url, id).into_owned().into(),
-                    }),
-                }
-            });
-
-            let community = RespMinimalCommunityInfo {
-                id: community_id,
-                name: Cow::Borrowed(community_name),
-                local: community_local,
-                host: crate::get_actor_host_or_unknown(
-                    community_local,
-                    community_ap_id.as_deref(),
-                    &ctx.local_hostname,
-                )
-                .into_owned()
-                .into(),
-                remote_url: community_remote_url,
-                deleted: row.get(22),
-            };
+            let author = AuthorColumns {
+                id: 1,
+                username: 12,
+                local: 13,
+                ap_id: 14,
+                avatar: 15,
+                is_bot: 19,
+            }
+            .get(row, &ctx);
+
+            let community = CommunityColumns {
+                id: 8,
+                name: 9,
+                local: 10,
+                ap_id: 11,
+                deleted: 22,
+            }
+            .get(row, &ctx);
 
             let post = RespPostListPost {
                 id,
@@ -536,25 +695,32 @@ async fn route_unstable_posts_list(
                 author: author.map(Cow::Owned),
                 created: Cow::Owned(created.to_rfc3339()),
                 community: Cow::Owned(community),
-                score: row.get(16),
+                score: super::get_resp_score(row.get(16), &created, row.get(24)),
                 sensitive: row.get(23),
                 sticky: row.get(18),
                 relevance: if has_relevance {
-                    row.get(if include_your_idx.is_some() { 25 } else { 24 })
+                    row.get(if include_your_idx.is_some() { 27 } else { 26 })
                 } else {
                     None
                 },
                 remote_url,
                 replies_count_total: Some(row.get(17)),
                 your_vote: if include_your_idx.is_some() {
-                    Some(if row.get(24) {
-                        Some(crate::types::Empty {})
-                    } else {
-                        None
-                    })
+                    Some(
+                        row.get::<_, Option<bool>>(26)
+                            .map(|is_dislike| RespYourVote { is_dislike }),
+                    )
                 } else {
                     None
                 },
+                flairs: Some(row.get::<_, postgres_types::Json<Vec<RespFlairInfo>>>(25).0),
+                thumbnail: href
+                    .and_then(super::media::parse_local_media_href)
+                    .and_then(|media_id| thumbnails.get(&media_id))
+                    .map(|blurhash| RespThumbnailInfo {
+                        url: Cow::Owned(ctx.get_post_href_thumbnail_url(id)),
+                        blurhash: Cow::Borrowed(blurhash),
+                    }),
             };
 
             post
@@ -595,7 +761,7 @@ async fn route_unstable_posts_flags_create(
 
     let user = crate::require_login(&req, &db).await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct PostFlagsCreateBody<'a> {
@@ -606,31 +772,57 @@ async fn route_unstable_posts_flags_create(
 
         #[serde(default)]
         to_remote_site_admin: bool,
+
+        category: Option<FlagCategory>,
+        rule: Option<i32>,
     }
 
     let body: PostFlagsCreateBody = serde_json::from_slice(&body)?;
 
+    if (body.category == Some(FlagCategory::Rule)) != body.rule.is_some()
+        || body.rule.map(|rule| rule < 1).unwrap_or(false)
+    {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::flag_rule_mismatch(),
+        ));
+    }
+
     let post_row = db
         .query_opt(
-            "SELECT local, ap_id, community, author FROM post WHERE id=$1 AND NOT deleted",
+            "SELECT post.local, post.ap_id, post.community, post.author, cardinality(community.rules) FROM post INNER JOIN community ON (community.id = post.community) WHERE post.id=$1 AND NOT post.deleted",
             &[&post_id],
         )
         .await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::NOT_FOUND,
-                lang.tr(&lang::no_such_post()).into_owned(),
-            ))
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
         })?;
 
+    if let Some(rule) = body.rule {
+        if rule > post_row.get::<_, i32>(4) {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::flag_rule_not_found(),
+            ));
+        }
+    }
+
     let res_row = db.query_one(
-        "INSERT INTO flag (kind, person, post, content_text, to_community, to_site_admin, to_remote_site_admin, created_local, local) VALUES ('post', $1, $2, $3, $4, $5, $6, current_timestamp, TRUE) RETURNING id",
-        &[&user, &post_id, &body.content_text, &body.to_community, &body.to_site_admin, &body.to_remote_site_admin]
+        "INSERT INTO flag (kind, person, post, content_text, to_community, to_site_admin, to_remote_site_admin, created_local, local, category, category_rule) VALUES ('post', $1, $2, $3, $4, $5, $6, current_timestamp, TRUE, $7, $8) RETURNING id",
+        &[&user, &post_id, &body.content_text, &body.to_community, &body.to_site_admin, &body.to_remote_site_admin, &body.category.map(|x| x.as_str()), &body.rule]
     ).await?;
 
-    let id = FlagLocalID(res_row.get(0));
+    let id: FlagLocalID = res_row.get(0);
 
     crate::spawn_task(async move {
+        ctx.enqueue_webhook_event(
+            WebhookEvent::NewReport,
+            serde_json::json!({ "flag": id, "post": post_id, "user": user }),
+        )
+        .await?;
+
         let post_local = post_row.get(0);
 
         let post_ap_id = if post_local {
@@ -740,32 +932,38 @@ async fn route_unstable_posts_poll_your_vote_set(
     let lang = crate::get_lang_for_req(&req);
     let mut db = ctx.db_pool.get().await?;
 
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
     let user = crate::require_login(&req, &db).await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    super::mutes::require_not_muted_for_post(user, post_id, &db, &lang).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: PollVoteBody = serde_json::from_slice(&body)?;
 
-    let row = db.query_opt("SELECT poll.multiple, poll.id, author.local, COALESCE(author.ap_inbox, author.ap_shared_inbox), post.ap_id, COALESCE(poll.is_closed, poll.closed_at <= current_timestamp, FALSE), author.ap_id FROM post INNER JOIN poll ON (poll.id = post.poll_id) LEFT OUTER JOIN person AS author ON (author.id = post.author) WHERE post.id = $1", &[&post_id]).await?.ok_or_else(|| crate::Error::UserError(crate::simple_response(hyper::StatusCode::BAD_REQUEST, "No such poll")))?;
+    let row = db.query_opt("SELECT poll.multiple, poll.id, author.local, COALESCE(author.ap_inbox, author.ap_shared_inbox), post.ap_id, COALESCE(poll.is_closed, poll.closed_at <= current_timestamp, FALSE), author.ap_id FROM post INNER JOIN poll ON (poll.id = post.poll_id) LEFT OUTER JOIN person AS author ON (author.id = post.author) WHERE post.id = $1", &[&post_id]).await?.ok_or_else(|| crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::no_such_poll()))?;
 
     let multiple: bool = row.get(0);
-    let poll_id = PollLocalID(row.get(1));
+    let poll_id: PollLocalID = row.get(1);
     let closed: bool = row.get(5);
 
     if closed {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::FORBIDDEN,
-            lang.tr(&lang::poll_is_closed()).into_owned(),
-        )));
+            &lang,
+            &lang::poll_is_closed(),
+        ));
     }
 
     let tmp;
     let options: Result<&[PollOptionLocalID], _> = if multiple {
         match &body {
             PollVoteBody::Multiple { options } => Ok(&options),
-            PollVoteBody::Single { .. } => Err(crate::Error::UserError(crate::simple_response(
+            PollVoteBody::Single { .. } => Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Cannot use `option` for multiple-choice poll",
-            ))),
+                &lang,
+                &lang::poll_vote_multiple_needs_options(),
+            )),
         }
     } else {
         match &body {
@@ -773,10 +971,11 @@ async fn route_unstable_posts_poll_your_vote_set(
                 tmp = [*option];
                 Ok(&tmp[..])
             }
-            PollVoteBody::Multiple { .. } => Err(crate::Error::UserError(crate::simple_response(
+            PollVoteBody::Multiple { .. } => Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Cannot use `options` for single-choice poll",
-            ))),
+                &lang,
+                &lang::poll_vote_single_needs_option(),
+            )),
         }
     };
     let options = options?;
@@ -793,7 +992,7 @@ async fn route_unstable_posts_poll_your_vote_set(
         };
 
         let added: Vec<(PollOptionLocalID, String)> = {
-            let added_rows = trans.query("INSERT INTO poll_vote (poll_id, person, option_id) SELECT $1, $3, * FROM UNNEST($2::BIGINT[]) RETURNING option_id, (SELECT name FROM poll_option WHERE id=option_id)", &[&poll_id, &options, &user]).await?;
+            let added_rows = trans.query("INSERT INTO poll_vote (poll_id, person, option_id) SELECT $1, $3, * FROM UNNEST($2::BIGINT[]) ON CONFLICT DO NOTHING RETURNING option_id, (SELECT name FROM poll_option WHERE id=option_id)", &[&poll_id, &options, &user]).await?;
 
             added_rows
                 .into_iter()
@@ -808,7 +1007,9 @@ async fn route_unstable_posts_poll_your_vote_set(
 
     if !removed.is_empty() || !added.is_empty() {
         let author_local: bool = row.get(2);
-        if !author_local {
+        if author_local {
+            crate::apub_util::enqueue_poll_results_update_if_needed(post_id, &db, &ctx).await?;
+        } else {
             let inbox: Option<&str> = row.get(3);
             let post_ap_id: Option<String> = row.get(4);
 
@@ -882,32 +1083,45 @@ async fn route_unstable_posts_replies_list(
     struct RepliesListQuery<'a> {
         #[serde(default)]
         include_your: bool,
+        #[serde(default = "super::default_replies_depth")]
+        depth: u8,
         #[serde(default = "super::default_replies_limit")]
         limit: u8,
+        replies_limit: Option<u8>,
         #[serde(default = "super::default_comment_sort")]
         sort: super::SortType,
         page: Option<Cow<'a, str>>,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: RepliesListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
-    let db = ctx.db_pool.get().await?;
+    let include_your_for = {
+        let db = ctx.db_pool.get().await?;
 
-    let include_your_for = if query.include_your {
-        let user = crate::require_login(&req, &db).await?;
-        Some(user)
-    } else {
-        None
+        require_post_visible(post_id, &req, &db, &lang).await?;
+
+        if query.include_your {
+            let user = crate::require_login(&req, &db).await?;
+            Some(user)
+        } else {
+            None
+        }
     };
 
+    let db = ctx.get_db_read().await?;
+
     let (replies, next_page) = get_post_comments(
         post_id,
         include_your_for,
         query.sort,
+        query.depth.min(ctx.max_replies_depth),
         query.limit,
+        query.replies_limit.unwrap_or(query.limit),
         query.page.as_deref(),
         &db,
         &ctx,
+        &lang,
     )
     .await?;
 
@@ -929,7 +1143,7 @@ async fn route_unstable_posts_create(
 
     let user = crate::require_login(&req, &db).await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct PollCreateInfo<'a> {
@@ -948,55 +1162,118 @@ async fn route_unstable_posts_create(
         poll: Option<PollCreateInfo<'a>>,
         #[serde(default)]
         sensitive: bool,
+        #[serde(default)]
+        attachments: Vec<crate::PostAttachment>,
+        #[serde(default)]
+        flairs: Vec<FlairLocalID>,
+        language: Option<Cow<'a, str>>,
+        #[serde(default)]
+        allow_duplicate: bool,
     }
 
     let body: PostsCreateBody = serde_json::from_slice(&body)?;
 
-    if body.href.is_none() && body.content_text.is_none() && body.content_markdown.is_none() {
-        return Err(crate::Error::UserError(crate::simple_response(
+    super::mutes::require_not_muted(user, body.community, &db, &lang).await?;
+    super::follow_requests::require_can_post(user, body.community, &db, &lang).await?;
+
+    if body.href.is_none()
+        && body.content_text.is_none()
+        && body.content_markdown.is_none()
+        && body.attachments.is_empty()
+    {
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::post_needs_content()).into_owned(),
-        )));
+            &lang,
+            &lang::post_needs_content(),
+        ));
     }
 
     if body.content_markdown.is_some() && body.content_text.is_some() {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::post_content_conflict()).into_owned(),
-        )));
+            &lang,
+            &lang::post_content_conflict(),
+        ));
     }
 
     if body.href.is_some() && body.poll.is_some() {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::post_conflict_href_poll()).into_owned(),
-        )));
+            &lang,
+            &lang::post_conflict_href_poll(),
+        ));
     }
 
+    // without an explicit language, assume the author's first preferred one
+    let language: Option<String> = match &body.language {
+        Some(language) => Some(crate::normalize_language(language).ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::language_invalid(),
+            )
+        })?),
+        None => db
+            .query_one(
+                "SELECT preferred_languages[1] FROM person WHERE id=$1",
+                &[&user],
+            )
+            .await?
+            .get(0),
+    };
+
     if let Some(poll) = &body.poll {
         if poll.options.is_empty() {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::post_poll_empty()).into_owned(),
-            )));
+                &lang,
+                &lang::post_poll_empty(),
+            ));
         }
     }
 
     if let Some(href) = &body.href {
         if url::Url::parse(href).is_err() {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::post_href_invalid()).into_owned(),
-            )));
+                &lang,
+                &lang::post_href_invalid(),
+            ));
         }
     }
 
+    if body.attachments.len() > MAX_POST_ATTACHMENTS {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::post_attachments_too_many(MAX_POST_ATTACHMENTS),
+        ));
+    }
+
+    if body.attachments.iter().any(|attachment| {
+        !matches!(
+            url::Url::parse(&attachment.url)
+                .as_ref()
+                .map(url::Url::scheme),
+            Ok("http" | "https" | "local-media")
+        )
+    }) {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::post_attachment_url_invalid(),
+        ));
+    }
+
     // TODO validate permissions to post
 
     let (content_text, content_markdown, content_html) = match body.content_markdown {
         Some(md) => {
-            let (html, md) =
-                tokio::task::spawn_blocking(move || (crate::render_markdown(&md), md)).await?;
+            let markdown_options = ctx.markdown_options;
+            let (html, md) = tokio::task::spawn_blocking(move || {
+                (crate::render_markdown(&md, markdown_options), md)
+            })
+            .await?;
             (None, Some(md), Some(html))
         }
         None => match body.content_text {
@@ -1012,48 +1289,95 @@ async fn route_unstable_posts_create(
         )
         .await?
         .ok_or_else(|| {
-            crate::Error::UserError(crate::simple_response(
+            crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::no_such_community()).into_owned(),
-            ))
+                &lang,
+                &lang::no_such_community(),
+            )
         })?;
 
     let community_local: bool = community_row.get(0);
-    let already_approved = community_local;
 
-    let (id, created, poll) = {
+    let href_canonical = body.href.as_deref().map(|href| ctx.canonicalize_href(href));
+
+    if let Some(href_canonical) = &href_canonical {
+        if !body.allow_duplicate && ctx.duplicate_post_window.as_secs() > 0 {
+            let window = ctx.duplicate_post_window.as_secs_f64();
+            let existing = db.query_opt(
+                "SELECT id FROM post WHERE community=$1 AND href_canonical=$2 AND NOT deleted AND created > current_timestamp - make_interval(secs => $3) ORDER BY created DESC LIMIT 1",
+                &[&body.community, href_canonical, &window],
+            ).await?;
+
+            if let Some(row) = existing {
+                let id: PostLocalID = row.get(0);
+                return crate::json_response(&RespPostCreated {
+                    id,
+                    duplicate: true,
+                });
+            }
+        }
+    }
+
+    let automod_match = crate::automod::check_content(
+        &crate::automod::AutomodContent {
+            community: body.community,
+            author: Some(user),
+            title: Some(&body.title),
+            href: body.href.as_deref(),
+            content: content_markdown.as_deref().or(content_text.as_deref()),
+        },
+        &db,
+        &ctx,
+    )
+    .await?;
+
+    let held = match automod_match.map(|found| found.action) {
+        // held posts aren't sent to the community until approved in the local modqueue
+        Some(AutomodAction::Hold) => true,
+        Some(AutomodAction::Reject) => {
+            return Err(crate::user_error(
+                hyper::StatusCode::FORBIDDEN,
+                &lang,
+                &lang::automod_rejected(),
+            ));
+        }
+        Some(AutomodAction::Report) | None => false,
+    };
+
+    let already_approved = community_local && !held;
+
+    let id = {
         let trans = db.transaction().await?;
 
-        let poll_data = if let Some(poll) = body.poll {
+        let poll_id = if let Some(poll) = body.poll {
             let closed_in = date_duration::DateDuration::parse_iso8601(&poll.closed_in)
                 .map_err(|_| {
-                    crate::Error::UserError(crate::simple_response(
+                    crate::user_error(
                         hyper::StatusCode::BAD_REQUEST,
-                        "Invalid duration for closed_in",
-                    ))
+                        &lang,
+                        &lang::duration_invalid("closed_in"),
+                    )
                 })?
                 .to_iso8601_long();
 
             Some({
                 let row = trans
                     .query_one(
-                        "INSERT INTO poll (multiple, closed_at) VALUES ($1, current_timestamp + $2::TEXT::INTERVAL) RETURNING id, closed_at",
+                        "INSERT INTO poll (multiple, closed_at) VALUES ($1, current_timestamp + $2::TEXT::INTERVAL) RETURNING id",
                         &[&poll.multiple, &closed_in],
                     )
                     .await?;
                 let poll_id: i64 = row.get(0);
-                let closed_at: chrono::DateTime<chrono::FixedOffset> = row.get(1);
 
                 let indices: Vec<i32> = (0..(poll.options.len() as i32)).collect();
-                let mut names: Vec<Option<String>> = poll.options.into_iter().map(Some).collect();
 
-                let rows = trans.query("INSERT INTO poll_option (poll_id, name, position) SELECT $1, * FROM UNNEST($2::TEXT[], $3::INTEGER[]) RETURNING id, position", &[&poll_id, &names, &indices]).await
+                trans.execute("INSERT INTO poll_option (poll_id, name, position) SELECT $1, * FROM UNNEST($2::TEXT[], $3::INTEGER[])", &[&poll_id, &poll.options, &indices]).await
                     .map_err(|err| {
                         match err.as_db_error() {
                             None => err.into(),
                             Some(db_err) => {
                                 if db_err.code() == &tokio_postgres::error::SqlState::UNIQUE_VIOLATION && db_err.constraint() == Some("poll_option_poll_id_name_key") {
-                                    crate::Error::UserError(crate::simple_response(hyper::StatusCode::BAD_REQUEST, lang.tr(&lang::post_poll_options_conflict()).into_owned()))
+                                    crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::post_poll_options_conflict())
                                 } else {
                                     err.into()
                                 }
@@ -1061,82 +1385,94 @@ async fn route_unstable_posts_create(
                         }
                     })?;
 
-                assert_eq!(names.len(), rows.len());
-
-                let mut options = vec![None; rows.len()];
-
-                for row in rows {
-                    let idx: i32 = row.get(1);
-                    let idx = idx as usize;
-
-                    options[idx] = Some(crate::PollOptionOwned {
-                        id: PollOptionLocalID(row.get(0)),
-                        name: names[idx].take().unwrap(),
-                        votes: 0,
-                    });
-                }
-
-                (
-                    crate::PollInfoOwned {
-                        multiple: poll.multiple,
-                        options: options.into_iter().map(Option::unwrap).collect(),
-                        is_closed: false,
-                        closed_at: Some(closed_at),
-                    },
-                    poll_id,
-                )
+                poll_id
             })
         } else {
             None
         };
 
-        let poll_id = poll_data.as_ref().map(|(_, poll_id)| *poll_id);
-
         let res_row = trans.query_one(
-            "INSERT INTO post (author, href, title, created, community, local, content_text, content_markdown, content_html, approved, poll_id, updated_local, sensitive) VALUES ($1, $2, $3, current_timestamp, $4, TRUE, $5, $6, $7, $8, $9, current_timestamp, $10) RETURNING id, created",
-            &[&user, &body.href, &body.title, &body.community, &content_text, &content_markdown, &content_html, &already_approved, &poll_id, &body.sensitive],
+            "INSERT INTO post (author, href, title, created, community, local, content_text, content_markdown, content_html, approved, poll_id, updated_local, sensitive, pending_approval, language, href_canonical) VALUES ($1, $2, $3, current_timestamp, $4, TRUE, $5, $6, $7, $8, $9, current_timestamp, $10, $11, $12, $13) RETURNING id",
+            &[&user, &body.href, &body.title, &body.community, &content_text, &content_markdown, &content_html, &already_approved, &poll_id, &body.sensitive, &held, &language, &href_canonical],
         ).await?;
 
-        let id = PostLocalID(res_row.get(0));
-        let created = res_row.get(1);
+        let id: PostLocalID = res_row.get(0);
+
+        if !body.attachments.is_empty() {
+            let positions: Vec<i32> = (0..(body.attachments.len() as i32)).collect();
+            let urls: Vec<&str> = body.attachments.iter().map(|x| x.url.as_str()).collect();
+            let media_types: Vec<Option<&str>> = body
+                .attachments
+                .iter()
+                .map(|x| x.media_type.as_deref())
+                .collect();
+            let alt_texts: Vec<Option<&str>> = body
+                .attachments
+                .iter()
+                .map(|x| x.alt_text.as_deref())
+                .collect();
+
+            trans.execute(
+                "INSERT INTO post_attachment (post, position, url, media_type, alt_text) SELECT $1, * FROM UNNEST($2::INTEGER[], $3::TEXT[], $4::TEXT[], $5::TEXT[])",
+                &[&id, &positions, &urls, &media_types, &alt_texts],
+            ).await?;
+        }
+
+        super::flairs::set_post_flairs(id, body.community, &body.flairs, &trans, &lang).await?;
+
+        // side effects are queued with the post, so each happens exactly once
+        ctx.enqueue_webhook_event_in_transaction(
+            &trans,
+            crate::types::WebhookEvent::NewPost,
+            serde_json::json!({ "post": id, "community": body.community, "author": user }),
+        )
+        .await?;
+
+        if let Some(found) = automod_match {
+            if found.action == AutomodAction::Report {
+                crate::automod::report_post_in_transaction(id, found, &trans, &ctx).await?;
+            }
+        }
+
+        if already_approved {
+            ctx.enqueue_task_in_transaction(
+                &trans,
+                &crate::tasks::AnnounceCommunityPost {
+                    community: body.community,
+                    post: id,
+                    post_ap_id: crate::apub_util::LocalObjectRef::Post(id)
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
+                },
+            )
+            .await?;
+        }
+
+        ctx.enqueue_task_in_transaction(&trans, &crate::tasks::LocalPostCreated { post: id })
+            .await?;
 
         trans.commit().await?;
 
-        (id, created, poll_data.map(|(info, _)| info))
+        id
     };
 
-    let post = crate::PostInfoOwned {
-        id,
-        author: Some(user),
-        content_text,
-        content_markdown,
-        content_html,
-        href: body.href,
-        title: body.title,
-        created,
-        community: body.community,
-        poll,
-        sensitive: body.sensitive,
-    };
+    ctx.notify_worker()?;
 
-    crate::spawn_task(async move {
-        if community_local {
-            crate::on_local_community_add_post(
-                post.community,
-                post.id,
-                crate::apub_util::LocalObjectRef::Post(post.id)
-                    .to_local_uri(&ctx.host_url_apub)
-                    .into(),
-                ctx,
-            );
-        } else {
-            crate::apub_util::spawn_enqueue_send_local_post_to_community(post, ctx);
-        }
+    if already_approved {
+        crate::on_community_add_post(body.community, id, &ctx);
+    }
 
-        Ok(())
-    });
+    crate::set_post_hashtags(
+        id,
+        &crate::extract_hashtags(content_text.as_deref(), content_markdown.as_deref()),
+        &db,
+    )
+    .await?;
 
-    crate::json_response(&serde_json::json!({ "id": id }))
+    crate::json_response(&RespPostCreated {
+        id,
+        duplicate: false,
+    })
 }
 
 async fn route_unstable_posts_get(
@@ -1166,30 +1502,41 @@ async fn route_unstable_posts_get(
 
     let (post_id,) = params;
 
-    let (row, your_vote) = futures::future::try_join(
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
+    let (row, your_vote, reactions) = futures::future::try_join3(
         db.query_opt(
-            "SELECT post.author, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, community.id, community.name, community.local, community.ap_id, person.username, person.local, person.ap_id, (SELECT COUNT(*) FROM post_like WHERE post_like.post = $1), post.approved, person.avatar, post.local, post.sticky, person.is_bot, post.ap_id, post.local, community.deleted, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, CASE WHEN post.local THEN (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id) ELSE COALESCE(remote_vote_count, 0) END) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.id, (NOT post.local AND (current_timestamp - post.updated_local) > '1 MINUTE' AND COALESCE(post.updated_local < poll.closed_at, TRUE)), COALESCE(poll.is_closed, poll.closed_at < current_timestamp, FALSE), poll.closed_at, post.rejected, post.sensitive FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.community = community.id AND post.id = $1",
+            "SELECT post.author, post.href, post.content_text, post.title, post.created, post.content_markdown, post.content_html, community.id, community.name, community.local, community.ap_id, person.username, person.local, person.ap_id, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = $1), post.approved, person.avatar, post.local, post.sticky, person.is_bot, post.ap_id, post.local, community.deleted, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, CASE WHEN post.local THEN (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id) ELSE COALESCE(remote_vote_count, 0) END) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.id, (NOT post.local AND (current_timestamp - post.updated_local) > '1 MINUTE' AND COALESCE(post.updated_local < poll.closed_at, TRUE)), COALESCE(poll.is_closed, poll.closed_at < current_timestamp, FALSE), poll.closed_at, post.rejected, post.sensitive, community.hide_scores_minutes, (SELECT COALESCE(json_agg(json_build_object('url', url, 'media_type', media_type, 'alt_text', alt_text) ORDER BY position), '[]') FROM post_attachment WHERE post=post.id), (SELECT COALESCE(json_agg(json_build_object('id', community_flair.id, 'name', community_flair.name) ORDER BY community_flair.name), '[]') FROM post_flair INNER JOIN community_flair ON (community_flair.id = post_flair.flair) WHERE post_flair.post = post.id), post.language, post.href_canonical, post.views, post.locked FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.community = community.id AND post.id = $1",
             &[&post_id],
         )
         .map_err(crate::Error::from),
         async {
             if let Some(user) = include_your_for {
-                let row = db.query_opt("SELECT 1 FROM post_like WHERE post=$1 AND person=$2", &[&post_id, &user]).await?;
-                if row.is_some() {
-                    Ok(Some(Some(crate::types::Empty {})))
-                } else {
-                    Ok(Some(None))
-                }
+                let row = db.query_opt("SELECT is_dislike FROM post_like WHERE post=$1 AND person=$2", &[&post_id, &user]).await?;
+                Ok(Some(row.map(|row| RespYourVote { is_dislike: row.get(0) })))
             } else {
                 Ok(None)
             }
-        }
+        },
+        async {
+            let rows = db.query(
+                "SELECT content, COUNT(*), BOOL_OR(person = $2) FROM post_reaction WHERE post=$1 GROUP BY content ORDER BY COUNT(*) DESC, content",
+                &[&post_id, &include_your_for],
+            ).await?;
+
+            Ok(rows.iter().map(|row| RespReactionInfo {
+                content: row.get(0),
+                count: row.get(1),
+                your_reaction: include_your_for.map(|_| row.get::<_, Option<bool>>(2) == Some(true)),
+            }).collect())
+        },
     ).await?;
 
     match row {
-        None => Ok(crate::simple_response(
+        None => Ok(crate::error_response(
             hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_post()).into_owned(),
+            &lang,
+            &lang::no_such_post(),
         )),
         Some(row) => {
             let href: Option<&str> = row.get(1);
@@ -1200,10 +1547,8 @@ async fn route_unstable_posts_get(
             let created: chrono::DateTime<chrono::FixedOffset> = row.get(4);
             let local: bool = row.get(21);
             let ap_id: Option<&str> = row.get(20);
-            let community_id = CommunityLocalID(row.get(7));
-            let community_name: &str = row.get(8);
-            let community_local = row.get(9);
-            let community_ap_id: Option<&str> = row.get(10);
+
+            ctx.record_post_view(post_id);
 
             let remote_url = if local {
                 Some(Cow::Owned(String::from(
@@ -1214,62 +1559,24 @@ async fn route_unstable_posts_get(
                 ap_id.map(Cow::Borrowed)
             };
 
-            let community_remote_url = if community_local {
-                Some(Cow::Owned(String::from(
-                    crate::apub_util::LocalObjectRef::Community(community_id)
-                        .to_local_uri(&ctx.host_url_apub),
-                )))
-            } else {
-                community_ap_id.map(Cow::Borrowed)
-            };
-
-            let author = match row.get(11) {
-                Some(author_username) => {
-                    let author_id = UserLocalID(row.get(0));
-                    let author_local = row.get(12);
-                    let author_ap_id: Option<_> = row.get(13);
-                    let author_avatar: Option<&str> = row.get(16);
-
-                    let author_remote_url = if author_local {
-                        Some(Cow::Owned(String::from(
-                            crate::apub_util::LocalObjectRef::User(author_id)
-                                .to_local_uri(&ctx.host_url_apub),
-                        )))
-                    } else {
-                        author_ap_id.map(Cow::Borrowed)
-                    };
-
-                    Some(RespMinimalAuthorInfo {
-                        id: author_id,
-                        username: Cow::Borrowed(author_username),
-                        local: author_local,
-                        host: crate::get_actor_host_or_unknown(
-                            author_local,
-                            author_ap_id,
-                            &ctx.local_hostname,
-                        ),
-                        remote_url: author_remote_url,
-                        is_bot: row.get(19),
-                        avatar: author_avatar.map(|url| RespAvatarInfo {
-                            url: ctx.process_avatar_href(url, author_id),
-                        }),
-                    })
-                }
-                None => None,
-            };
-
-            let community = RespMinimalCommunityInfo {
-                id: community_id,
-                name: Cow::Borrowed(community_name),
-                local: community_local,
-                host: crate::get_actor_host_or_unknown(
-                    community_local,
-                    community_ap_id,
-                    &ctx.local_hostname,
-                ),
-                remote_url: community_remote_url,
-                deleted: row.get(22),
-            };
+            let author = AuthorColumns {
+                id: 0,
+                username: 11,
+                local: 12,
+                ap_id: 13,
+                avatar: 16,
+                is_bot: 19,
+            }
+            .get(&row, &ctx);
+
+            let community = CommunityColumns {
+                id: 7,
+                name: 8,
+                local: 9,
+                ap_id: 10,
+                deleted: 22,
+            }
+            .get(&row, &ctx);
 
             let fetched_info;
             let poll = if let Some(multiple) = row.get(23) {
@@ -1344,7 +1651,7 @@ async fn route_unstable_posts_get(
                     };
 
                     let your_vote = if let Some(user) = include_your_for {
-                        let poll_id = PollLocalID(row.get(25));
+                        let poll_id: PollLocalID = row.get(25);
                         Some({
                             let rows = db.query("SELECT option_id FROM poll_vote WHERE poll_id=$1 AND person=$2", &[&poll_id, &user]).await?;
                             if rows.is_empty() {
@@ -1353,9 +1660,7 @@ async fn route_unstable_posts_get(
                                 Some(RespPollYourVote {
                                     options: rows
                                         .into_iter()
-                                        .map(|row| JustID {
-                                            id: PollOptionLocalID(row.get(0)),
-                                        })
+                                        .map(|row| JustID { id: row.get(0) })
                                         .collect(),
                                 })
                             }
@@ -1376,6 +1681,19 @@ async fn route_unstable_posts_get(
                 None
             };
 
+            let attachments: postgres_types::Json<Vec<crate::PostAttachment>> = row.get(32);
+
+            let thumbnails = super::media::get_thumbnail_blurhashes(
+                href.into_iter().chain(
+                    attachments
+                        .0
+                        .iter()
+                        .map(|attachment| attachment.url.as_str()),
+                ),
+                &db,
+            )
+            .await?;
+
             let post = RespPostListPost {
                 id: post_id,
                 title: Cow::Borrowed(title),
@@ -1389,18 +1707,54 @@ async fn route_unstable_posts_get(
                 relevance: None,
                 remote_url,
                 replies_count_total: None,
-                score: row.get(14),
+                score: super::get_resp_score(row.get(14), &created, row.get(31)),
                 sensitive: row.get(30),
                 sticky: row.get(18),
                 your_vote,
+                flairs: Some(row.get::<_, postgres_types::Json<Vec<RespFlairInfo>>>(33).0),
+                thumbnail: href
+                    .and_then(super::media::parse_local_media_href)
+                    .and_then(|media_id| thumbnails.get(&media_id))
+                    .map(|blurhash| RespThumbnailInfo {
+                        url: Cow::Owned(ctx.get_post_href_thumbnail_url(post_id)),
+                        blurhash: Cow::Borrowed(blurhash),
+                    }),
             };
 
             let output = RespPostInfo {
                 post: &post,
+                language: row.get::<_, Option<&str>>(34).map(Cow::Borrowed),
+                canonical_href: ctx
+                    .process_href_opt(row.get::<_, Option<&str>>(35).map(Cow::Borrowed), post_id),
                 local: row.get(17),
                 approved: row.get(15),
                 rejected: row.get(29),
+                locked: row.get(37),
+                views: row.get(36),
                 poll,
+                attachments: attachments
+                    .0
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, attachment)| RespPostAttachment {
+                        url: ctx.process_post_attachment_href(
+                            attachment.url.as_str(),
+                            post_id,
+                            idx,
+                        ),
+                        media_type: attachment.media_type.as_deref(),
+                        alt_text: attachment.alt_text.as_deref(),
+                        thumbnail: super::media::parse_local_media_href(&attachment.url)
+                            .and_then(|media_id| thumbnails.get(&media_id))
+                            .map(|blurhash| RespThumbnailInfo {
+                                url: Cow::Owned(
+                                    ctx.get_post_attachment_thumbnail_url(post_id, idx),
+                                ),
+                                blurhash: Cow::Borrowed(blurhash),
+                            }),
+                    })
+                    .collect(),
+                reactions,
             };
 
             crate::json_response(&output)
@@ -1429,16 +1783,17 @@ async fn route_unstable_posts_delete(
     match row {
         None => Ok(crate::empty_response()), // already gone
         Some(row) => {
-            let author = row.get::<_, Option<_>>(0).map(UserLocalID);
+            let author = row.get::<_, Option<UserLocalID>>(0);
             let is_mod_action = if author != Some(login_user) {
                 if row.get(2) && crate::is_site_admin(&db, login_user).await? {
                     // still ok
                     true
                 } else {
-                    return Err(crate::Error::UserError(crate::simple_response(
+                    return Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::post_not_yours()).into_owned(),
-                    )));
+                        &lang,
+                        &lang::post_not_yours(),
+                    ));
                 }
             } else {
                 false
@@ -1449,7 +1804,10 @@ async fn route_unstable_posts_delete(
             {
                 let trans = db.transaction().await?;
 
-                trans.execute("UPDATE post SET had_href=(href IS NOT NULL), href=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE id=$1", &[&post_id]).await?;
+                trans.execute("UPDATE post SET had_href=(href IS NOT NULL), href=NULL, href_canonical=NULL, title='[deleted]', content_text='[deleted]', content_markdown=NULL, content_html=NULL, deleted=TRUE WHERE id=$1", &[&post_id]).await?;
+                trans
+                    .execute("DELETE FROM post_attachment WHERE post=$1", &[&post_id])
+                    .await?;
 
                 if is_mod_action {
                     trans.execute("INSERT INTO modlog_event (time, by_person, action, post) VALUES (current_timestamp, $1, 'delete_post', $2)", &[&login_user, &post_id]).await?;
@@ -1459,7 +1817,7 @@ async fn route_unstable_posts_delete(
             }
 
             crate::spawn_task(async move {
-                let community = row.get::<_, Option<_>>(1).map(CommunityLocalID);
+                let community = row.get::<_, Option<CommunityLocalID>>(1);
                 if let Some(community) = community {
                     let delete_ap = crate::apub_util::local_post_delete_to_ap(
                         post_id,
@@ -1501,22 +1859,164 @@ async fn route_unstable_posts_delete(
     }
 }
 
-async fn route_unstable_posts_like(
+async fn route_unstable_posts_dislike(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    require_dislikes_allowed(post_id, &db, &lang).await?;
+
+    super::mutes::require_not_muted_for_post(user, post_id, &db, &lang).await?;
+
+    set_post_vote(post_id, user, true, db, ctx).await
+}
+
+async fn route_unstable_posts_your_vote_set(
     params: (PostLocalID,),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
+    #[derive(Deserialize, Clone, Copy)]
+    #[serde(rename_all = "snake_case")]
+    enum VoteState {
+        Like,
+        None,
+        Dislike,
+    }
+
+    #[derive(Deserialize)]
+    struct YourVoteBody {
+        state: VoteState,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
     let user = crate::require_login(&req, &db).await?;
 
-    let row_count = db.execute(
-        "INSERT INTO post_like (post, person, local) VALUES ($1, $2, TRUE) ON CONFLICT (post, person) DO NOTHING",
+    // an empty body likes the post, for clients from before states could be set
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let state = if body.is_empty() {
+        VoteState::Like
+    } else {
+        serde_json::from_slice::<YourVoteBody>(&body)?.state
+    };
+
+    match state {
+        VoteState::None => remove_post_vote(post_id, user, db, ctx).await,
+        VoteState::Like | VoteState::Dislike => {
+            let is_dislike = matches!(state, VoteState::Dislike);
+            if is_dislike {
+                require_dislikes_allowed(post_id, &db, &lang).await?;
+            }
+
+            super::mutes::require_not_muted_for_post(user, post_id, &db, &lang).await?;
+
+            set_post_vote(post_id, user, is_dislike, db, ctx).await
+        }
+    }
+}
+
+async fn require_dislikes_allowed(
+    post_id: PostLocalID,
+    db: &tokio_postgres::Client,
+    lang: &crate::Translator,
+) -> Result<(), crate::Error> {
+    let row = db.query_opt(
+        "SELECT site.allow_dislikes AND community.allow_dislikes FROM post INNER JOIN community ON (community.id = post.community), site WHERE site.local AND post.id=$1",
+        &[&post_id],
+    ).await?.ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::NOT_FOUND, lang, &lang::no_such_post())
+    })?;
+
+    if row.get(0) {
+        Ok(())
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            lang,
+            &lang::dislikes_disabled(),
+        ))
+    }
+}
+
+/// Responds with the post's score and the user's vote, after it has been changed
+async fn post_vote_result_response(
+    post_id: PostLocalID,
+    user: UserLocalID,
+    db: &tokio_postgres::Client,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let row = db.query_one(
+        "SELECT (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post = post.id), (SELECT is_dislike FROM post_like WHERE post = post.id AND person = $2), post.created, community.hide_scores_minutes FROM post INNER JOIN community ON (community.id = post.community) WHERE post.id = $1",
         &[&post_id, &user],
     ).await?;
 
+    crate::json_response(&RespVoteResult {
+        score: super::get_resp_score(row.get(0), &row.get(2), row.get(3)),
+        your_vote: row
+            .get::<_, Option<bool>>(1)
+            .map(|is_dislike| RespYourVote { is_dislike }),
+    })
+}
+
+async fn set_post_vote(
+    post_id: PostLocalID,
+    user: UserLocalID,
+    is_dislike: bool,
+    mut db: deadpool_postgres::Client,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (row_count, replaced_undo) = {
+        let trans = db.transaction().await?;
+
+        let existing = trans
+            .query_opt(
+                "SELECT is_dislike FROM post_like WHERE post=$1 AND person=$2 FOR UPDATE",
+                &[&post_id, &user],
+            )
+            .await?;
+
+        // switching between like and dislike replaces the old vote, so it needs to be undone
+        let replaced_undo = match existing {
+            Some(row) if row.get::<_, bool>(0) != is_dislike => {
+                let id = uuid::Uuid::new_v4();
+                trans
+                    .execute(
+                        "INSERT INTO local_post_like_undo (id, post, person, is_dislike) VALUES ($1, $2, $3, $4)",
+                        &[&id, &post_id, &user, &!is_dislike],
+                    )
+                    .await?;
+
+                Some(id)
+            }
+            _ => None,
+        };
+
+        let row_count = trans.execute(
+            "INSERT INTO post_like (post, person, local, is_dislike) VALUES ($1, $2, TRUE, $3) ON CONFLICT (post, person) DO UPDATE SET is_dislike=$3 WHERE post_like.is_dislike != $3",
+            &[&post_id, &user, &is_dislike],
+        ).await?;
+
+        trans.commit().await?;
+
+        (row_count, replaced_undo)
+    };
+
+    let res = post_vote_result_response(post_id, user, &db).await?;
+
     if row_count > 0 {
         crate::spawn_task(async move {
             let row = db.query_opt(
@@ -1560,33 +2060,58 @@ async fn route_unstable_posts_like(
                         .transpose()?
                 };
 
-                let like = crate::apub_util::local_post_like_to_ap(
-                    post_id,
-                    post_ap_id,
-                    author_ap_id,
-                    user,
-                    &ctx.host_url_apub,
-                )?;
+                let mut bodies = Vec::with_capacity(2);
 
-                let body = serde_json::to_string(&like)?;
-
-                for inbox in inboxes {
-                    ctx.enqueue_task(&crate::tasks::DeliverToInbox {
-                        inbox: Cow::Owned(inbox.parse()?),
-                        sign_as: Some(ActorLocalRef::Person(user)),
-                        object: (&body).into(),
-                    })
-                    .await?;
+                if let Some(replaced_undo) = replaced_undo {
+                    bodies.push(serde_json::to_string(
+                        &crate::apub_util::local_post_like_undo_to_ap(
+                            replaced_undo,
+                            post_id,
+                            !is_dislike,
+                            author_ap_id.clone(),
+                            user,
+                            &ctx.host_url_apub,
+                        )?,
+                    )?);
                 }
 
-                if community_local == Some(true) {
-                    let community_local_id = CommunityLocalID(row.get(2));
-                    crate::apub_util::enqueue_forward_to_community_followers(
-                        community_local_id,
-                        body,
-                        ctx,
-                    )
-                    .await?;
+                bodies.push(if is_dislike {
+                    serde_json::to_string(&crate::apub_util::local_post_dislike_to_ap(
+                        post_id,
+                        post_ap_id,
+                        author_ap_id,
+                        user,
+                        &ctx.host_url_apub,
+                    )?)?
+                } else {
+                    serde_json::to_string(&crate::apub_util::local_post_like_to_ap(
+                        post_id,
+                        post_ap_id,
+                        author_ap_id,
+                        user,
+                        &ctx.host_url_apub,
+                    )?)?
+                });
+
+                for body in bodies {
+                    for inbox in &inboxes {
+                        ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                            inbox: Cow::Owned(inbox.parse()?),
+                            sign_as: Some(ActorLocalRef::Person(user)),
+                            object: (&body).into(),
+                        })
+                        .await?;
+                    }
+
+                    if community_local == Some(true) {
+                        let community_local_id: CommunityLocalID = row.get(2);
+                        crate::apub_util::enqueue_forward_to_community_followers(
+                            community_local_id,
+                            body,
+                            ctx.clone(),
+                        )
+                        .await?;
+                    }
                 }
             }
 
@@ -1594,7 +2119,7 @@ async fn route_unstable_posts_like(
         });
     }
 
-    Ok(crate::empty_response())
+    Ok(res)
 }
 
 async fn route_unstable_posts_likes_list(
@@ -1612,6 +2137,7 @@ async fn route_unstable_posts_likes_list(
         pub page: Option<Cow<'a, str>>,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: LikesListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
     let page: Option<(chrono::DateTime<chrono::offset::FixedOffset>, i64)> = query
         .page
@@ -1633,10 +2159,7 @@ async fn route_unstable_posts_likes_list(
         })
         .transpose()
         .map_err(|_| {
-            crate::Error::UserError(crate::simple_response(
-                hyper::StatusCode::BAD_REQUEST,
-                "Invalid page",
-            ))
+            crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::page_invalid())
         })?;
 
     let limit: i64 = 30;
@@ -1644,6 +2167,8 @@ async fn route_unstable_posts_likes_list(
 
     let db = ctx.db_pool.get().await?;
 
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
     let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&post_id, &real_limit];
     let page_conditions = match &page {
         Some((ts, u)) => {
@@ -1655,14 +2180,14 @@ async fn route_unstable_posts_likes_list(
         None => "",
     };
 
-    let sql: &str = &format!("SELECT person.id, person.username, person.local, person.ap_id, post_like.created_local, person.avatar, person.is_bot FROM post_like, person WHERE person.id = post_like.person AND post_like.post = $1{} ORDER BY post_like.created_local DESC, post_like.person DESC LIMIT $2", page_conditions);
+    let sql: &str = &format!("SELECT person.id, person.username, person.local, person.ap_id, person.avatar, person.is_bot, post_like.created_local FROM post_like, person WHERE person.id = post_like.person AND post_like.post = $1 AND NOT post_like.is_dislike{} ORDER BY post_like.created_local DESC, post_like.person DESC LIMIT $2", page_conditions);
 
-    let mut rows = db.query(sql, &values).await?;
+    let mut rows = crate::query::query(&db, sql, &values).await?;
 
     let next_page = if rows.len() > limit.try_into().unwrap() {
         let row = rows.pop().unwrap();
 
-        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(4);
+        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(6);
         let ts = ts.timestamp_nanos();
 
         let u: i64 = row.get(0);
@@ -1674,34 +2199,10 @@ async fn route_unstable_posts_likes_list(
 
     let likes = rows
         .iter()
-        .map(|row| {
-            let id = UserLocalID(row.get(0));
-            let username: &str = row.get(1);
-            let local: bool = row.get(2);
-            let ap_id: Option<&str> = row.get(3);
-            let avatar: Option<&str> = row.get(5);
-
-            let remote_url = if local {
-                Some(Cow::Owned(String::from(
-                    crate::apub_util::LocalObjectRef::User(id).to_local_uri(&ctx.host_url_apub),
-                )))
-            } else {
-                ap_id.map(Cow::Borrowed)
-            };
-
-            JustUser {
-                user: RespMinimalAuthorInfo {
-                    id,
-                    username: Cow::Borrowed(username),
-                    local,
-                    host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
-                    remote_url,
-                    is_bot: row.get(6),
-                    avatar: avatar.map(|url| RespAvatarInfo {
-                        url: ctx.process_avatar_href(url, id),
-                    }),
-                },
-            }
+        .filter_map(|row| {
+            Some(JustUser {
+                user: AuthorColumns::starting_at(0).get(row, &ctx)?,
+            })
         })
         .collect::<Vec<_>>();
 
@@ -1712,6 +2213,183 @@ async fn route_unstable_posts_likes_list(
     crate::json_response(&body)
 }
 
+async fn route_unstable_posts_votes_analytics_get(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    fn default_window_minutes() -> u32 {
+        60
+    }
+
+    #[derive(Deserialize)]
+    struct VotesAnalyticsQuery {
+        #[serde(default = "default_window_minutes")]
+        window_minutes: u32,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let query: VotesAnalyticsQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    if query.window_minutes == 0 || query.window_minutes > MAX_VOTE_ANALYTICS_WINDOW_MINUTES {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::vote_analytics_window_invalid(MAX_VOTE_ANALYTICS_WINDOW_MINUTES),
+        ));
+    }
+
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+    if !crate::is_site_admin(&db, user).await? {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::not_admin(),
+        ));
+    }
+
+    let window_seconds = f64::from(query.window_minutes * 60);
+
+    let rows = db.query(
+        "WITH vote AS (SELECT post_like.is_dislike, CASE WHEN person.local THEN $3 ELSE url_host_key(person.ap_id) END AS host, to_timestamp(floor(extract(epoch FROM post_like.created_local)::FLOAT8 / $2::FLOAT8) * $2::FLOAT8) AS window_start, NOT (EXISTS(SELECT 1 FROM post_like AS other_like WHERE other_like.person = post_like.person AND other_like.post != post_like.post) OR EXISTS(SELECT 1 FROM reply_like WHERE reply_like.person = post_like.person) OR EXISTS(SELECT 1 FROM post WHERE post.author = post_like.person) OR EXISTS(SELECT 1 FROM reply WHERE reply.author = post_like.person)) AS no_history FROM post_like INNER JOIN person ON (person.id = post_like.person) WHERE post_like.post = $1) SELECT GROUPING(host) = 0, host, window_start, COUNT(*) FILTER (WHERE NOT is_dislike), COUNT(*) FILTER (WHERE is_dislike), COUNT(*) FILTER (WHERE no_history) FROM vote GROUP BY GROUPING SETS ((host), (window_start)) ORDER BY window_start ASC, COUNT(*) DESC",
+        &[&post_id, &window_seconds, &ctx.local_hostname],
+    ).await?;
+
+    let mut by_host = Vec::new();
+    let mut by_window = Vec::new();
+
+    for row in &rows {
+        let likes: i64 = row.get(3);
+        let dislikes: i64 = row.get(4);
+        let no_history: i64 = row.get(5);
+
+        let stat = RespVoteStat {
+            likes,
+            dislikes,
+            no_history,
+            anomalous: no_history >= VOTE_ANOMALY_MIN_VOTES && no_history * 2 >= likes + dislikes,
+        };
+
+        if row.get(0) {
+            by_host.push(RespVoteHostStat {
+                host: Cow::Borrowed(row.get::<_, Option<&str>>(1).unwrap_or("[unknown]")),
+                stat,
+            });
+        } else {
+            let window_start: Option<chrono::DateTime<chrono::FixedOffset>> = row.get(2);
+
+            by_window.push(RespVoteWindowStat {
+                start: window_start.map(|x| x.to_rfc3339()),
+                stat,
+            });
+        }
+    }
+
+    crate::json_response(&RespVoteAnalytics {
+        window_minutes: query.window_minutes,
+        by_host,
+        by_window,
+    })
+}
+
+async fn route_unstable_posts_mute(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.query_opt("SELECT 1 FROM post WHERE id=$1", &[&post_id])
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
+        })?;
+
+    db.execute(
+        "INSERT INTO post_mute (person, post) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[&user, &post_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_posts_hide(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.query_opt("SELECT 1 FROM post WHERE id=$1", &[&post_id])
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_post())
+        })?;
+
+    db.execute(
+        "INSERT INTO post_hide (person, post) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        &[&user, &post_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_posts_unhide(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.execute(
+        "DELETE FROM post_hide WHERE person=$1 AND post=$2",
+        &[&user, &post_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_posts_unmute(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    db.execute(
+        "DELETE FROM post_mute WHERE person=$1 AND post=$2",
+        &[&user, &post_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
 async fn route_unstable_posts_unlike(
     params: (PostLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -1719,30 +2397,41 @@ async fn route_unstable_posts_unlike(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
-    let mut db = ctx.db_pool.get().await?;
+    let db = ctx.db_pool.get().await?;
 
     let user = crate::require_login(&req, &db).await?;
 
+    remove_post_vote(post_id, user, db, ctx).await
+}
+
+async fn remove_post_vote(
+    post_id: PostLocalID,
+    user: UserLocalID,
+    mut db: deadpool_postgres::Client,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let new_undo = {
         let trans = db.transaction().await?;
 
-        let row_count = trans
-            .execute(
-                "DELETE FROM post_like WHERE post=$1 AND person=$2",
+        let row = trans
+            .query_opt(
+                "DELETE FROM post_like WHERE post=$1 AND person=$2 RETURNING is_dislike",
                 &[&post_id, &user],
             )
             .await?;
 
-        let new_undo = if row_count > 0 {
+        let new_undo = if let Some(row) = row {
+            let is_dislike: bool = row.get(0);
+
             let id = uuid::Uuid::new_v4();
             trans
                 .execute(
-                    "INSERT INTO local_post_like_undo (id, post, person) VALUES ($1, $2, $3)",
-                    &[&id, &post_id, &user],
+                    "INSERT INTO local_post_like_undo (id, post, person, is_dislike) VALUES ($1, $2, $3, $4)",
+                    &[&id, &post_id, &user, &is_dislike],
                 )
                 .await?;
 
-            Some(id)
+            Some((id, is_dislike))
         } else {
             None
         };
@@ -1752,7 +2441,9 @@ async fn route_unstable_posts_unlike(
         new_undo
     };
 
-    if let Some(new_undo) = new_undo {
+    let res = post_vote_result_response(post_id, user, &db).await?;
+
+    if let Some((new_undo, is_dislike)) = new_undo {
         crate::spawn_task(async move {
             let row = db.query_opt(
                 "SELECT post.local, community.id, community.local, community.ap_id, COALESCE(community.ap_shared_inbox, community.ap_inbox), COALESCE(post_author.ap_shared_inbox, post_author.ap_inbox), post_author.id, post_author.ap_id FROM post LEFT OUTER JOIN community ON (post.community = community.id) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE post.id = $1",
@@ -1793,6 +2484,7 @@ async fn route_unstable_posts_unlike(
                 let undo = crate::apub_util::local_post_like_undo_to_ap(
                     new_undo,
                     post_id,
+                    is_dislike,
                     author_ap_id,
                     user,
                     &ctx.host_url_apub,
@@ -1810,7 +2502,7 @@ async fn route_unstable_posts_unlike(
                 }
 
                 if community_local == Some(true) {
-                    let community_local_id = CommunityLocalID(row.get(1));
+                    let community_local_id: CommunityLocalID = row.get(1);
                     crate::apub_util::enqueue_forward_to_community_followers(
                         community_local_id,
                         body,
@@ -1824,9 +2516,219 @@ async fn route_unstable_posts_unlike(
         });
     }
 
+    Ok(res)
+}
+
+pub enum ReactionActivity {
+    React(String),
+    Undo(uuid::Uuid),
+}
+
+pub fn parse_reaction_param(src: &str, lang: &crate::Translator) -> Result<String, crate::Error> {
+    percent_encoding::percent_decode_str(src)
+        .decode_utf8()
+        .ok()
+        .and_then(|src| crate::normalize_reaction(&src))
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                lang,
+                &lang::reaction_invalid(),
+            )
+        })
+}
+
+async fn route_unstable_posts_your_reactions_add(
+    params: (PostLocalID, String),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id, content) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let content = parse_reaction_param(&content, &lang)?;
+
+    let db = ctx.db_pool.get().await?;
+
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    super::mutes::require_not_muted_for_post(user, post_id, &db, &lang).await?;
+
+    let row = db.query_opt(
+        "INSERT INTO post_reaction (post, person, content, local) VALUES ($1, $2, $3, TRUE) ON CONFLICT (post, person, content) DO NOTHING RETURNING id",
+        &[&post_id, &user, &content],
+    ).await?;
+
+    if let Some(row) = row {
+        let reaction_id: i64 = row.get(0);
+
+        crate::spawn_task(send_post_reaction_activity(
+            post_id,
+            user,
+            reaction_id,
+            ReactionActivity::React(content),
+            db,
+            ctx,
+        ));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_posts_your_reactions_remove(
+    params: (PostLocalID, String),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id, content) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let content = parse_reaction_param(&content, &lang)?;
+
+    let mut db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    let new_undo = {
+        let trans = db.transaction().await?;
+
+        let row = trans
+            .query_opt(
+                "DELETE FROM post_reaction WHERE post=$1 AND person=$2 AND content=$3 RETURNING id",
+                &[&post_id, &user, &content],
+            )
+            .await?;
+
+        let new_undo = if let Some(row) = row {
+            let reaction_id: i64 = row.get(0);
+
+            let id = uuid::Uuid::new_v4();
+            trans
+                .execute(
+                    "INSERT INTO local_post_reaction_undo (id, post, person, reaction, content) VALUES ($1, $2, $3, $4, $5)",
+                    &[&id, &post_id, &user, &reaction_id, &content],
+                )
+                .await?;
+
+            Some((id, reaction_id))
+        } else {
+            None
+        };
+
+        trans.commit().await?;
+
+        new_undo
+    };
+
+    if let Some((undo_id, reaction_id)) = new_undo {
+        crate::spawn_task(send_post_reaction_activity(
+            post_id,
+            user,
+            reaction_id,
+            ReactionActivity::Undo(undo_id),
+            db,
+            ctx,
+        ));
+    }
+
     Ok(crate::empty_response())
 }
 
+async fn send_post_reaction_activity(
+    post_id: PostLocalID,
+    user: UserLocalID,
+    reaction_id: i64,
+    activity: ReactionActivity,
+    db: deadpool_postgres::Client,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<(), crate::Error> {
+    let row = db.query_opt(
+        "SELECT post.local, post.ap_id, community.id, community.local, community.ap_id, COALESCE(community.ap_shared_inbox, community.ap_inbox), COALESCE(post_author.ap_shared_inbox, post_author.ap_inbox), post_author.id, post_author.ap_id FROM post LEFT OUTER JOIN community ON (post.community = community.id) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE post.id = $1",
+        &[&post_id],
+    ).await?;
+    if let Some(row) = row {
+        let post_local = row.get(0);
+
+        let mut inboxes = HashSet::new();
+
+        if !post_local {
+            let author_inbox: Option<&str> = row.get(6);
+            if let Some(inbox) = author_inbox {
+                inboxes.insert(inbox);
+            }
+        }
+
+        let community_local: Option<bool> = row.get(3);
+
+        if community_local == Some(false) {
+            if let Some(inbox) = row.get(5) {
+                inboxes.insert(inbox);
+            }
+        }
+
+        let author_ap_id = if post_local {
+            Some(
+                crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(7)))
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into(),
+            )
+        } else {
+            row.get::<_, Option<&str>>(8)
+                .map(|x| x.parse())
+                .transpose()?
+        };
+
+        let body = match activity {
+            ReactionActivity::Undo(undo_id) => {
+                serde_json::to_string(&crate::apub_util::local_post_reaction_undo_to_ap(
+                    undo_id,
+                    post_id,
+                    reaction_id,
+                    author_ap_id,
+                    user,
+                    &ctx.host_url_apub,
+                )?)?
+            }
+            ReactionActivity::React(content) => {
+                let post_ap_id = if post_local {
+                    crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub)
+                } else {
+                    row.get::<_, &str>(1).parse()?
+                };
+
+                serde_json::to_string(&crate::apub_util::local_post_reaction_to_ap(
+                    post_id,
+                    reaction_id,
+                    post_ap_id,
+                    author_ap_id,
+                    user,
+                    content,
+                    &ctx.host_url_apub,
+                )?)?
+            }
+        };
+
+        for inbox in inboxes {
+            ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                inbox: Cow::Owned(inbox.parse()?),
+                sign_as: Some(ActorLocalRef::Person(user)),
+                object: (&body).into(),
+            })
+            .await?;
+        }
+
+        if community_local == Some(true) {
+            let community_local_id: CommunityLocalID = row.get(2);
+            crate::apub_util::enqueue_forward_to_community_followers(community_local_id, body, ctx)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
 async fn route_unstable_posts_replies_create(
     params: (PostLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -1837,9 +2739,15 @@ async fn route_unstable_posts_replies_create(
     let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
+    require_post_visible(post_id, &req, &db, &lang).await?;
+
     let user = crate::require_login(&req, &db).await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    super::mutes::require_not_muted_for_post(user, post_id, &db, &lang).await?;
+    super::follow_requests::require_can_post_for_post(user, post_id, &db, &lang).await?;
+    require_post_not_locked(post_id, &db, &lang).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct RepliesCreateBody<'a> {
@@ -1853,26 +2761,50 @@ async fn route_unstable_posts_replies_create(
 
     if let Some(attachment) = &body.attachment {
         if !attachment.starts_with("local-media://") {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Comment attachment must be local media",
-            )));
+                &lang,
+                &lang::comment_attachment_not_local(),
+            ));
         }
     }
 
-    let (content_text, content_markdown, content_html) =
-        super::process_comment_content(&lang, body.content_text, body.content_markdown).await?;
+    let (content_text, content_markdown, content_html) = super::process_comment_content(
+        &lang,
+        body.content_text,
+        body.content_markdown,
+        ctx.markdown_options,
+    )
+    .await?;
+
+    let outcome = super::automod::require_comment_allowed(
+        post_id,
+        user,
+        content_markdown.as_deref().or(content_text.as_deref()),
+        &db,
+        &ctx,
+        &lang,
+    )
+    .await?;
+    let held = matches!(outcome, crate::automod::CommentOutcome::Hold);
 
     let sensitive = body.sensitive.unwrap_or(false);
 
     let row = db.query_one(
-        "INSERT INTO reply (post, author, created, local, content_text, content_markdown, content_html, attachment_href, sensitive) VALUES ($1, $2, current_timestamp, TRUE, $3, $4, $5, $6, $7) RETURNING id, created",
-        &[&post_id, &user, &content_text, &content_markdown, &content_html, &body.attachment, &sensitive],
+        "INSERT INTO reply (post, author, created, local, content_text, content_markdown, content_html, attachment_href, sensitive, pending_approval) VALUES ($1, $2, current_timestamp, TRUE, $3, $4, $5, $6, $7, $8) RETURNING id, created",
+        &[&post_id, &user, &content_text, &content_markdown, &content_html, &body.attachment, &sensitive, &held],
     ).await?;
 
-    let reply_id = CommentLocalID(row.get(0));
+    let reply_id: CommentLocalID = row.get(0);
     let created = row.get(1);
 
+    crate::set_comment_hashtags(
+        reply_id,
+        &crate::extract_hashtags(content_text.as_deref(), content_markdown.as_deref()),
+        &db,
+    )
+    .await?;
+
     let comment = crate::CommentInfo {
         id: reply_id,
         author: Some(user),
@@ -1882,16 +2814,75 @@ async fn route_unstable_posts_replies_create(
         content_markdown: content_markdown.map(Cow::Owned),
         content_html: content_html.map(Cow::Owned),
         created,
+        updated: None,
         ap_id: crate::APIDOrLocal::Local,
         attachment_href: body.attachment,
         sensitive,
     };
 
-    crate::on_post_add_comment(comment, ctx);
+    super::reply_drafts::clear_reply_draft(user, post_id, None, &db).await?;
+
+    if let crate::automod::CommentOutcome::Report(found) = outcome {
+        crate::automod::report_comment(reply_id, found, &ctx).await?;
+    }
+
+    if !held {
+        crate::on_post_add_comment(comment, ctx);
+    }
 
     crate::json_response(&serde_json::json!({ "id": reply_id }))
 }
 
+async fn route_unstable_posts_your_reply_draft_get(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    super::reply_drafts::get_reply_draft(post_id, None, ctx, req).await
+}
+
+async fn route_unstable_posts_your_reply_draft_put(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+
+    {
+        let db = ctx.db_pool.get().await?;
+
+        let row = db
+            .query_opt(
+                "SELECT 1 FROM post WHERE id=$1 AND NOT deleted",
+                &[&post_id],
+            )
+            .await?;
+        if row.is_none() {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_post(),
+            ));
+        }
+    }
+
+    super::reply_drafts::put_reply_draft(post_id, None, ctx, req).await
+}
+
+async fn route_unstable_posts_your_reply_draft_delete(
+    params: (PostLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id,) = params;
+
+    super::reply_drafts::delete_reply_draft(post_id, None, ctx, req).await
+}
+
 pub fn route_posts() -> crate::RouteNode<()> {
     crate::RouteNode::new()
         .with_handler_async(hyper::Method::GET, route_unstable_posts_list)
@@ -1900,11 +2891,26 @@ pub fn route_posts() -> crate::RouteNode<()> {
             crate::RouteNode::new()
                 .with_handler_async(hyper::Method::GET, route_unstable_posts_get)
                 .with_handler_async(hyper::Method::DELETE, route_unstable_posts_delete)
+                .with_child(
+                    "dislike",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_posts_dislike),
+                )
                 .with_child(
                     "flags",
                     crate::RouteNode::new()
                         .with_handler_async(hyper::Method::POST, route_unstable_posts_flags_create),
                 )
+                .with_child(
+                    "hide",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_posts_hide),
+                )
+                .with_child(
+                    "mute",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_posts_mute),
+                )
                 .with_child(
                     "poll",
                     crate::RouteNode::new().with_child(
@@ -1924,16 +2930,63 @@ pub fn route_posts() -> crate::RouteNode<()> {
                             route_unstable_posts_replies_create,
                         ),
                 )
+                .with_child(
+                    "unhide",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_posts_unhide),
+                )
+                .with_child(
+                    "unmute",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_posts_unmute),
+                )
                 .with_child(
                     "votes",
                     crate::RouteNode::new()
-                        .with_handler_async(hyper::Method::GET, route_unstable_posts_likes_list),
+                        .with_handler_async(hyper::Method::GET, route_unstable_posts_likes_list)
+                        .with_child(
+                            "analytics",
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::GET,
+                                route_unstable_posts_votes_analytics_get,
+                            ),
+                        ),
+                )
+                .with_child(
+                    "your_reactions",
+                    crate::RouteNode::new().with_child_str(
+                        crate::RouteNode::new()
+                            .with_handler_async(
+                                hyper::Method::PUT,
+                                route_unstable_posts_your_reactions_add,
+                            )
+                            .with_handler_async(
+                                hyper::Method::DELETE,
+                                route_unstable_posts_your_reactions_remove,
+                            ),
+                    ),
                 )
                 .with_child(
                     "your_vote",
                     crate::RouteNode::new()
-                        .with_handler_async(hyper::Method::PUT, route_unstable_posts_like)
+                        .with_handler_async(hyper::Method::PUT, route_unstable_posts_your_vote_set)
                         .with_handler_async(hyper::Method::DELETE, route_unstable_posts_unlike),
+                )
+                .with_child(
+                    "your_reply_draft",
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_posts_your_reply_draft_get,
+                        )
+                        .with_handler_async(
+                            hyper::Method::PUT,
+                            route_unstable_posts_your_reply_draft_put,
+                        )
+                        .with_handler_async(
+                            hyper::Method::DELETE,
+                            route_unstable_posts_your_reply_draft_delete,
+                        ),
                 ),
         )
 }
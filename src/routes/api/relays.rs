@@ -0,0 +1,152 @@
+use crate::lang;
+use crate::types::{RelayID, RespList, RespRelayInfo};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+async fn route_unstable_relays_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let rows = db
+        .query(
+            "SELECT id, ap_id, accepted, created_at FROM relay ORDER BY id ASC",
+            &[],
+        )
+        .await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(3);
+
+            RespRelayInfo {
+                id: row.get(0),
+                ap_id: Cow::Borrowed(row.get(1)),
+                accepted: row.get(2),
+                created_at: created_at.to_rfc3339(),
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn route_unstable_relays_create(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct RelaysCreateBody<'a> {
+        ap_id: Cow<'a, str>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = super::require_site_admin(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: RelaysCreateBody = serde_json::from_slice(&body)?;
+
+    let relay_invalid = || {
+        crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::relay_invalid(),
+        )
+    };
+
+    let ap_id: url::Url = body.ap_id.parse().map_err(|_| relay_invalid())?;
+
+    let actor = crate::apub_util::fetch_ap_object_raw(&ap_id, &ctx)
+        .await
+        .map_err(|err| {
+            log::warn!("Failed to fetch relay {}: {:?}", ap_id, err);
+            relay_invalid()
+        })?;
+
+    let inbox: url::Url = actor
+        .get("inbox")
+        .and_then(|inbox| inbox.as_str())
+        .and_then(|inbox| inbox.parse().ok())
+        .ok_or_else(relay_invalid)?;
+
+    // adding an existing relay again retries the follow
+    let row = db.query_one(
+        "INSERT INTO relay (ap_id, inbox, created_by, created_at) VALUES ($1, $2, $3, current_timestamp) ON CONFLICT (ap_id) DO UPDATE SET inbox=$2 RETURNING id",
+        &[&ap_id.as_str(), &inbox.as_str(), &user],
+    ).await?;
+
+    let id: RelayID = row.get(0);
+
+    let follow = crate::apub_util::local_relay_follow_to_ap(id, ap_id, &ctx.host_url_apub)?;
+
+    ctx.enqueue_task(&crate::tasks::DeliverAsInstance {
+        inbox: Cow::Owned(inbox),
+        object: serde_json::to_string(&follow)?,
+    })
+    .await?;
+
+    crate::json_response(&serde_json::json!({ "id": id }))
+}
+
+async fn route_unstable_relays_delete(
+    params: (RelayID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (relay_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let row = db
+        .query_opt(
+            "DELETE FROM relay WHERE id=$1 RETURNING ap_id, inbox",
+            &[&relay_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_relay())
+        })?;
+
+    let ap_id: url::Url = row.get::<_, &str>(0).parse()?;
+    let inbox: url::Url = row.get::<_, &str>(1).parse()?;
+
+    let undo = crate::apub_util::local_relay_follow_undo_to_ap(
+        uuid::Uuid::new_v4(),
+        relay_id,
+        ap_id,
+        &ctx.host_url_apub,
+    )?;
+
+    ctx.enqueue_task(&crate::tasks::DeliverAsInstance {
+        inbox: Cow::Owned(inbox),
+        object: serde_json::to_string(&undo)?,
+    })
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+pub fn route_relays() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_relays_list)
+        .with_handler_async(hyper::Method::POST, route_unstable_relays_create)
+        .with_child_parse::<RelayID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::DELETE, route_unstable_relays_delete),
+        )
+}
@@ -0,0 +1,305 @@
+use crate::lang;
+use crate::types::{HostQuirkOverrides, HostQuirks, RespList, RespRemoteHostInfo};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+fn remote_host_row_to_info(row: &tokio_postgres::Row) -> RespRemoteHostInfo<'_> {
+    RespRemoteHostInfo {
+        host: Cow::Borrowed(row.get(0)),
+        software_name: row.get::<_, Option<&str>>(1).map(Cow::Borrowed),
+        software_version: row.get::<_, Option<&str>>(2).map(Cow::Borrowed),
+        detected_quirks: HostQuirks {
+            no_shared_inbox: row.get(3),
+            inline_objects: row.get(4),
+        },
+        quirk_overrides: HostQuirkOverrides {
+            no_shared_inbox: row.get(5),
+            inline_objects: row.get(6),
+        },
+        delivery_paused: row.get(7),
+        blocked: row.get(8),
+        unreachable_since: row
+            .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(9)
+            .map(|time| time.to_rfc3339()),
+        consecutive_failures: row.get(10),
+        last_delivery_at: row
+            .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(11)
+            .map(|time| time.to_rfc3339()),
+        dead: row.get(12),
+    }
+}
+
+async fn route_unstable_remote_hosts_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct RemoteHostsListQuery<'a> {
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+
+        #[serde(default)]
+        failing: bool,
+    }
+
+    let query: RemoteHostsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let mut rows = db.query(
+        "SELECT host, software_name, software_version, no_shared_inbox, inline_objects, override_no_shared_inbox, override_inline_objects, delivery_paused, blocked, unreachable_since, consecutive_failures, last_delivery_at, dead FROM remote_host WHERE ($1::TEXT IS NULL OR host >= $1) AND (NOT $3 OR consecutive_failures > 0) ORDER BY host ASC LIMIT $2",
+        &[&query.page, &limit_plus_1, &query.failing],
+    ).await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(Cow::Owned(row.get::<_, String>(0)))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows.iter().map(remote_host_row_to_info).collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page,
+    })
+}
+
+async fn route_unstable_remote_hosts_get(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let row = db.query_opt(
+        "SELECT host, software_name, software_version, no_shared_inbox, inline_objects, override_no_shared_inbox, override_inline_objects, delivery_paused, blocked, unreachable_since, consecutive_failures, last_delivery_at, dead FROM remote_host WHERE host=$1",
+        &[&host],
+    ).await?
+    .ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_remote_host())
+    })?;
+
+    crate::json_response(&remote_host_row_to_info(&row))
+}
+
+async fn route_unstable_remote_hosts_quirk_overrides_put(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: HostQuirkOverrides = serde_json::from_slice(&body)?;
+
+    crate::apub_util::compat::enqueue_probe_host_if_needed(&host, &ctx).await?;
+
+    db.execute(
+        "UPDATE remote_host SET override_no_shared_inbox=$2, override_inline_objects=$3 WHERE host=$1",
+        &[&host, &body.no_shared_inbox, &body.inline_objects],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_remote_hosts_delivery_pause_put(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    crate::apub_util::compat::enqueue_probe_host_if_needed(&host, &ctx).await?;
+
+    db.execute(
+        "UPDATE remote_host SET delivery_paused=TRUE WHERE host=$1",
+        &[&host],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_remote_hosts_delivery_pause_delete(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    db.execute(
+        "UPDATE remote_host SET delivery_paused=FALSE WHERE host=$1",
+        &[&host],
+    )
+    .await?;
+
+    // held tasks can run now
+    let _ = ctx.worker_trigger.clone().try_send(());
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_remote_hosts_delivery_retry_post(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    {
+        let trans = db.transaction().await?;
+
+        let row_count = trans.execute(
+            "UPDATE remote_host SET dead=FALSE, consecutive_failures=0, unreachable_since=NULL WHERE host=$1",
+            &[&host],
+        )
+        .await?;
+
+        if row_count == 0 {
+            return Err(crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_remote_host(),
+            ));
+        }
+
+        // includes deliveries dropped while the host was dead
+        trans.execute(
+            "UPDATE task SET state='pending', attempts=0, attempted_at=NULL WHERE destination_host=$1 AND state IN ('pending', 'failed')",
+            &[&host],
+        )
+        .await?;
+
+        trans.commit().await?;
+    }
+
+    let _ = ctx.worker_trigger.clone().try_send(());
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_remote_hosts_block_put(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    crate::apub_util::compat::enqueue_probe_host_if_needed(&host, &ctx).await?;
+
+    db.execute(
+        "UPDATE remote_host SET blocked=TRUE WHERE host=$1",
+        &[&host],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_remote_hosts_block_delete(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (host,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    db.execute(
+        "UPDATE remote_host SET blocked=FALSE WHERE host=$1",
+        &[&host],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+pub fn route_remote_hosts() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_remote_hosts_list)
+        .with_child_str(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::GET, route_unstable_remote_hosts_get)
+                .with_child(
+                    "quirk_overrides",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::PUT,
+                        route_unstable_remote_hosts_quirk_overrides_put,
+                    ),
+                )
+                .with_child(
+                    "block",
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::PUT,
+                            route_unstable_remote_hosts_block_put,
+                        )
+                        .with_handler_async(
+                            hyper::Method::DELETE,
+                            route_unstable_remote_hosts_block_delete,
+                        ),
+                )
+                .with_child(
+                    "delivery_retry",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_remote_hosts_delivery_retry_post,
+                    ),
+                )
+                .with_child(
+                    "delivery_pause",
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::PUT,
+                            route_unstable_remote_hosts_delivery_pause_put,
+                        )
+                        .with_handler_async(
+                            hyper::Method::DELETE,
+                            route_unstable_remote_hosts_delivery_pause_delete,
+                        ),
+                ),
+        )
+}
@@ -0,0 +1,122 @@
+use crate::lang;
+use crate::types::{CommentLocalID, PostLocalID, RespReplyDraft, UserLocalID};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const MAX_REPLY_DRAFT_LENGTH: usize = 50000;
+
+pub async fn get_reply_draft(
+    post: PostLocalID,
+    parent: Option<CommentLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    let row = db.query_opt(
+        "SELECT content_text, content_markdown, updated_at FROM reply_draft WHERE person=$1 AND post=$2 AND parent IS NOT DISTINCT FROM $3 AND updated_at > current_timestamp - INTERVAL '30 DAYS'",
+        &[&user, &post, &parent],
+    ).await?;
+
+    match row {
+        None => Ok(crate::simple_response(hyper::StatusCode::NOT_FOUND, "")),
+        Some(row) => {
+            let updated_at: chrono::DateTime<chrono::FixedOffset> = row.get(2);
+
+            crate::json_response(&RespReplyDraft {
+                content_text: row.get::<_, Option<&str>>(0).map(Cow::Borrowed),
+                content_markdown: row.get::<_, Option<&str>>(1).map(Cow::Borrowed),
+                updated_at: updated_at.to_rfc3339(),
+            })
+        }
+    }
+}
+
+pub async fn put_reply_draft(
+    post: PostLocalID,
+    parent: Option<CommentLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct ReplyDraftBody<'a> {
+        content_text: Option<Cow<'a, str>>,
+        content_markdown: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: ReplyDraftBody<'_> = serde_json::from_slice(&body)?;
+
+    if body.content_text.is_some() && body.content_markdown.is_some() {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::comment_content_conflict(),
+        ));
+    }
+
+    if body
+        .content_text
+        .iter()
+        .chain(body.content_markdown.iter())
+        .any(|content| content.chars().count() > MAX_REPLY_DRAFT_LENGTH)
+    {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::reply_draft_too_long(MAX_REPLY_DRAFT_LENGTH),
+        ));
+    }
+
+    // clean up expired drafts while we're here
+    db.execute(
+        "DELETE FROM reply_draft WHERE person=$1 AND updated_at < current_timestamp - INTERVAL '30 DAYS'",
+        &[&user],
+    )
+    .await?;
+
+    db.execute(
+        "INSERT INTO reply_draft (person, post, parent, content_text, content_markdown, updated_at) VALUES ($1, $2, $3, $4, $5, current_timestamp) ON CONFLICT (person, post, (COALESCE(parent, 0))) DO UPDATE SET content_text=$4, content_markdown=$5, updated_at=current_timestamp",
+        &[&user, &post, &parent, &body.content_text, &body.content_markdown],
+    ).await?;
+
+    Ok(crate::empty_response())
+}
+
+pub async fn delete_reply_draft(
+    post: PostLocalID,
+    parent: Option<CommentLocalID>,
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    let user = crate::require_login(&req, &db).await?;
+
+    clear_reply_draft(user, post, parent, &db).await?;
+
+    Ok(crate::empty_response())
+}
+
+pub async fn clear_reply_draft(
+    user: UserLocalID,
+    post: PostLocalID,
+    parent: Option<CommentLocalID>,
+    db: &tokio_postgres::Client,
+) -> Result<(), tokio_postgres::Error> {
+    db.execute(
+        "DELETE FROM reply_draft WHERE person=$1 AND post=$2 AND parent IS NOT DISTINCT FROM $3",
+        &[&user, &post, &parent],
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::types::{RespInstanceStats, RespInstanceStatsBucket};
+use serde_derive::Deserialize;
+use std::sync::Arc;
+
+const MAX_BUCKETS: u16 = 366;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum StatsInterval {
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl StatsInterval {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StatsInterval::Hour => "hour",
+            StatsInterval::Day => "day",
+            StatsInterval::Week => "week",
+            StatsInterval::Month => "month",
+        }
+    }
+}
+
+impl Default for StatsInterval {
+    fn default() -> Self {
+        StatsInterval::Day
+    }
+}
+
+fn default_count() -> u16 {
+    30
+}
+
+async fn route_unstable_instance_stats_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct StatsQuery {
+        #[serde(default)]
+        interval: StatsInterval,
+        #[serde(default = "default_count")]
+        count: u16,
+    }
+
+    let query: StatsQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    {
+        let db = ctx.db_pool.get().await?;
+        super::require_site_admin(&req, &db).await?;
+    }
+
+    let db = ctx.get_db_read().await?;
+
+    let interval = query.interval.as_str();
+    let count = i32::from(query.count.clamp(1, MAX_BUCKETS));
+
+    // buckets are aligned to the start of each interval, ending with the current one
+    let rows = db.query(
+        "WITH bucket AS (SELECT start, start + ('1 ' || $1)::INTERVAL AS bucket_end FROM generate_series(date_trunc($1, current_timestamp) - ($2 - 1) * ('1 ' || $1)::INTERVAL, date_trunc($1, current_timestamp), ('1 ' || $1)::INTERVAL) AS start) SELECT start, (SELECT COUNT(*) FROM post WHERE local AND created >= start AND created < bucket_end), (SELECT COUNT(*) FROM reply WHERE local AND created >= start AND created < bucket_end), (SELECT COUNT(*) FROM person WHERE local AND created_local >= start AND created_local < bucket_end), (SELECT COUNT(*) FROM task WHERE kind='deliver_to_inbox' AND state='completed' AND completed_at >= start AND completed_at < bucket_end), (SELECT COUNT(*) FROM task WHERE kind='deliver_to_inbox' AND state='failed' AND attempted_at >= start AND attempted_at < bucket_end) FROM bucket ORDER BY start",
+        &[&interval, &count],
+    ).await?;
+
+    let info = RespInstanceStats {
+        buckets: rows
+            .iter()
+            .map(|row| {
+                let start: chrono::DateTime<chrono::FixedOffset> = row.get(0);
+
+                RespInstanceStatsBucket {
+                    start: start.to_rfc3339(),
+                    posts: row.get(1),
+                    comments: row.get(2),
+                    registrations: row.get(3),
+                    deliveries: row.get(4),
+                    failed_deliveries: row.get(5),
+                }
+            })
+            .collect(),
+    };
+
+    crate::json_response(&info)
+}
+
+pub fn route_instance_stats() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_instance_stats_get)
+}
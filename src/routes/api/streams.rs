@@ -0,0 +1,122 @@
+use crate::types::{CommunityLocalID, UserLocalID};
+use crate::StreamEvent;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+const KEEPALIVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+const FOLLOWS_REFRESH_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
+struct StreamState {
+    ctx: Arc<crate::RouteContext>,
+    user: UserLocalID,
+    receiver: tokio::sync::broadcast::Receiver<StreamEvent>,
+    keepalive: tokio::time::Interval,
+    follows: Option<(std::time::Instant, HashSet<CommunityLocalID>)>,
+}
+
+impl StreamState {
+    async fn is_following(&mut self, community: CommunityLocalID) -> Result<bool, crate::Error> {
+        let stale = match &self.follows {
+            None => true,
+            Some((fetched_at, _)) => fetched_at.elapsed() > FOLLOWS_REFRESH_AGE,
+        };
+
+        if stale {
+            let db = self.ctx.db_pool.get().await?;
+            let rows = db
+                .query(
+                    "SELECT community FROM community_follow WHERE follower=$1 AND accepted",
+                    &[&self.user],
+                )
+                .await?;
+
+            self.follows = Some((
+                std::time::Instant::now(),
+                rows.iter()
+                    .map(|row| CommunityLocalID(row.get(0)))
+                    .collect(),
+            ));
+        }
+
+        Ok(self.follows.as_ref().unwrap().1.contains(&community))
+    }
+
+    async fn next_message(&mut self) -> Option<String> {
+        loop {
+            let event = tokio::select! {
+                event = self.receiver.recv() => event,
+                _ = self.keepalive.tick() => return Some(":\n\n".to_owned()),
+            };
+
+            let event = match event {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                    log::warn!("Event stream lagged, skipped {} events", count);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+            };
+
+            match event {
+                StreamEvent::NewPost { community, post } => {
+                    match self.is_following(community).await {
+                        Ok(true) => {
+                            return Some(format!(
+                                "event: post\ndata: {}\n\n",
+                                serde_json::json!({ "id": post, "community": community }),
+                            ));
+                        }
+                        Ok(false) => {}
+                        Err(err) => {
+                            log::error!("Failed to check follows for event stream: {:?}", err);
+                        }
+                    }
+                }
+                StreamEvent::NewNotification { user, notification } => {
+                    if user == self.user {
+                        return Some(format!(
+                            "event: notification\ndata: {}\n\n",
+                            serde_json::json!({ "id": notification }),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn route_unstable_streams_me(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let user = {
+        let db = ctx.db_pool.get().await?;
+        crate::require_login(&req, &db).await?
+    };
+
+    let state = StreamState {
+        receiver: ctx.subscribe_stream_events(),
+        ctx,
+        user,
+        keepalive: tokio::time::interval(KEEPALIVE_INTERVAL),
+        follows: None,
+    };
+
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        let message = state.next_message().await?;
+        Some((Ok::<_, std::convert::Infallible>(message), state))
+    });
+
+    Ok(crate::common_response_builder()
+        .header(hyper::header::CONTENT_TYPE, "text/event-stream")
+        .header(hyper::header::CACHE_CONTROL, "no-cache")
+        .body(hyper::Body::wrap_stream(stream))?)
+}
+
+pub fn route_streams() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_child(
+        "me",
+        crate::RouteNode::new().with_handler_async(hyper::Method::GET, route_unstable_streams_me),
+    )
+}
@@ -0,0 +1,81 @@
+use crate::types::{
+    RespTaskQueueFailure, RespTaskQueueHostCount, RespTaskQueueInfo, RespTaskQueueKindCount,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+const MAX_HOSTS: i64 = 50;
+const MAX_FAILURES: i64 = 50;
+
+async fn route_unstable_task_queue_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let (kind_rows, host_rows, oldest_row, failure_rows) = futures::future::try_join4(
+        db.query(
+            "SELECT kind, COUNT(*), COUNT(*) FILTER (WHERE attempts > 0) FROM task WHERE state='pending' GROUP BY kind ORDER BY COUNT(*) DESC",
+            &[],
+        ),
+        db.query(
+            "SELECT task.destination_host, COUNT(*), COALESCE(BOOL_OR(remote_host.delivery_paused), FALSE) FROM task LEFT OUTER JOIN remote_host ON (remote_host.host = task.destination_host) WHERE task.state='pending' AND task.destination_host IS NOT NULL GROUP BY task.destination_host ORDER BY COUNT(*) DESC LIMIT $1",
+            &[&MAX_HOSTS],
+        ),
+        db.query_one(
+            "SELECT MIN(created_at), EXTRACT(EPOCH FROM current_timestamp - MIN(created_at))::BIGINT FROM task WHERE state='pending'",
+            &[],
+        ),
+        db.query(
+            "SELECT kind, latest_error, COUNT(*), MAX(attempted_at) FROM task WHERE state IN ('pending', 'failed') AND latest_error IS NOT NULL AND attempted_at > current_timestamp - INTERVAL '1 DAY' GROUP BY kind, latest_error ORDER BY COUNT(*) DESC LIMIT $1",
+            &[&MAX_FAILURES],
+        ),
+    )
+    .await?;
+
+    let oldest_pending_created_at: Option<chrono::DateTime<chrono::FixedOffset>> =
+        oldest_row.get(0);
+
+    let info = RespTaskQueueInfo {
+        pending_by_kind: kind_rows
+            .iter()
+            .map(|row| RespTaskQueueKindCount {
+                kind: Cow::Borrowed(row.get(0)),
+                pending: row.get(1),
+                retrying: row.get(2),
+            })
+            .collect(),
+        pending_by_host: host_rows
+            .iter()
+            .map(|row| RespTaskQueueHostCount {
+                host: Cow::Borrowed(row.get(0)),
+                pending: row.get(1),
+                delivery_paused: row.get(2),
+            })
+            .collect(),
+        oldest_pending_created_at: oldest_pending_created_at.map(|x| x.to_rfc3339()),
+        oldest_pending_age_seconds: oldest_row.get(1),
+        recent_failures: failure_rows
+            .iter()
+            .map(|row| {
+                let latest_at: Option<chrono::DateTime<chrono::FixedOffset>> = row.get(3);
+
+                RespTaskQueueFailure {
+                    kind: Cow::Borrowed(row.get(0)),
+                    error: row.get::<_, Option<&str>>(1).map(Cow::Borrowed),
+                    count: row.get(2),
+                    latest_at: latest_at.map(|x| x.to_rfc3339()),
+                }
+            })
+            .collect(),
+    };
+
+    crate::json_response(&info)
+}
+
+pub fn route_task_queue() -> crate::RouteNode<()> {
+    crate::RouteNode::new().with_handler_async(hyper::Method::GET, route_unstable_task_queue_get)
+}
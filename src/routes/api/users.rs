@@ -1,16 +1,29 @@
-use super::InvalidPage;
+use super::{format_number_58, parse_number_58, InvalidPage};
 use crate::lang;
 use crate::types::{
-    CommentLocalID, CommunityLocalID, JustContentText, JustID, JustURL, MaybeIncludeYour,
-    NotificationSubscriptionCreateQuery, NotificationSubscriptionID, PostLocalID, RespAvatarInfo,
-    RespList, RespLoginUserInfo, RespMinimalAuthorInfo, RespMinimalCommentInfo,
+    ActorLocalRef, CommentLocalID, CommunityLocalID, JustContentText, JustID, JustURL,
+    MaybeIncludeYour, NotificationID, NotificationSubscriptionCreateQuery,
+    NotificationSubscriptionID, PostLocalID, ProfileField, RespAvatarInfo, RespCommunityFollowInfo,
+    RespList, RespLoginAttempt, RespLoginUserInfo, RespMinimalAuthorInfo, RespMinimalCommentInfo,
     RespMinimalCommunityInfo, RespMinimalPostInfo, RespNotification, RespNotificationInfo,
-    RespPostCommentInfo, RespPostListPost, RespThingInfo, RespUserInfo, UserLocalID,
+    RespPostCommentInfo, RespPostListPost, RespThingInfo, RespUserInfo, RespUserInsights,
+    RespUserInsightsCommunity, RespUserInsightsWindow, RespUserPreferences, UserLocalID,
+    UserPreferences, WebhookEvent,
 };
 use serde_derive::Deserialize;
 use std::borrow::Cow;
 use std::sync::Arc;
 
+const MAX_PROFILE_FIELDS: usize = 4;
+const MAX_PROFILE_TEXT_LENGTH: usize = 255;
+const MAX_ALIASES: usize = 5;
+const MAX_PREFERRED_LANGUAGES: usize = 16;
+const MAX_TIMEZONE_LENGTH: usize = 64;
+
+const USER_INSIGHTS_CACHE_DURATION: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+const USER_INSIGHTS_WINDOWS: &[(&str, &str)] =
+    &[("day", "1 DAY"), ("week", "7 DAYS"), ("month", "30 DAYS")];
+
 struct MeOrLocalAndAdminResult {
     pub login_user: UserLocalID,
     pub target_user: UserLocalID,
@@ -31,10 +44,11 @@ impl MeOrLocalAndAdminResult {
         if is_admin {
             Ok(())
         } else {
-            Err(crate::Error::UserError(crate::simple_response(
+            Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                lang.tr(&lang::not_admin()).into_owned(),
-            )))
+                lang,
+                &lang::not_admin(),
+            ))
         }
     }
 }
@@ -76,10 +90,11 @@ impl UserIDOrMe {
                 if id == login_user {
                     Ok(login_user)
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        "This endpoint is only available for the current user",
-                    )))
+                        &crate::get_lang_for_req(req),
+                        &lang::not_current_user(),
+                    ))
                 }
             }
         }
@@ -113,10 +128,11 @@ impl UserIDOrMe {
                         is_admin: Some(true),
                     })
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        "You do not have permission to do that",
-                    )))
+                        &crate::get_lang_for_req(req),
+                        &lang::permission_denied(),
+                    ))
                 }
             }
         }
@@ -146,22 +162,24 @@ async fn route_unstable_users_list(
         username: Option<Cow<'a, str>>,
     }
 
+    let lang = crate::get_lang_for_req(&req);
     let query: UsersListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
     let username = match (query.local, query.username) {
         (Some(true), Some(username)) => username,
         _ => {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::FORBIDDEN,
-                "User listing is only allowed when filtering by local=true and a username",
-            )))
+                &lang,
+                &lang::users_list_filter_required(),
+            ))
         }
     };
 
     let db = ctx.db_pool.get().await?;
 
     let rows = db.query(
-        "SELECT id, description, description_html, avatar, suspended, is_bot, description_markdown FROM person WHERE local AND username=$1",
+        "SELECT id, description, description_html, avatar, suspended, is_bot, description_markdown, display_name, profile_fields, created_local, (SELECT COUNT(*) FROM post WHERE author=person.id AND NOT deleted), (SELECT COUNT(*) FROM reply WHERE author=person.id AND NOT deleted), moved_to FROM person WHERE local AND NOT deleted AND username=$1",
         &[&username]
     )
         .await?;
@@ -173,7 +191,7 @@ async fn route_unstable_users_list(
                 .map(|row| {
                     let avatar: Option<&str> = row.get(3);
 
-                    let user_id = UserLocalID(row.get(0));
+                    let user_id: UserLocalID = row.get(0);
 
                     let info = RespMinimalAuthorInfo {
                         id: user_id,
@@ -211,6 +229,15 @@ async fn route_unstable_users_list(
                             content_markdown: description_markdown.map(Cow::Borrowed),
                             content_html_safe: description_html.map(|x| crate::clean_html(x)),
                         },
+                        display_name: row.get::<_, Option<&str>>(7).map(Cow::Borrowed),
+                        profile_fields: row.get::<_, postgres_types::Json<Vec<ProfileField>>>(8).0,
+                        post_count: row.get(10),
+                        comment_count: row.get(11),
+                        moved_to: row.get::<_, Option<&str>>(12).map(Cow::Borrowed),
+                        created: Some(
+                            row.get::<_, chrono::DateTime<chrono::FixedOffset>>(9)
+                                .to_rfc3339(),
+                        ),
                         suspended: Some(row.get(4)),
                         your_note: None,
                     }
@@ -230,7 +257,9 @@ async fn route_unstable_users_create(
     let lang = crate::get_lang_for_req(&req);
     let mut db = ctx.db_pool.get().await?;
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let client_ip = crate::get_client_ip(&req);
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
 
     #[derive(Deserialize)]
     struct UsersCreateBody<'a> {
@@ -247,19 +276,21 @@ async fn route_unstable_users_create(
 
     for ch in body.username.chars() {
         if !super::USERNAME_ALLOWED_CHARS.contains(&ch) {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::user_name_disallowed_chars()).into_owned(),
-            )));
+                &lang,
+                &lang::user_name_disallowed_chars(),
+            ));
         }
     }
 
     if let Some(email) = &body.email_address {
         if !fast_chemail::is_valid_email(email) {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::user_email_invalid()).into_owned(),
-            )));
+                &lang,
+                &lang::user_email_invalid(),
+            ));
         }
     }
 
@@ -288,37 +319,42 @@ async fn route_unstable_users_create(
 
                     if let Some(invitation_row) = invitation_row {
                         if invitation_row.get::<_, Option<i64>>(0).is_some() {
-                            Err(crate::Error::UserError(crate::simple_response(
+                            Err(crate::user_error(
                                 hyper::StatusCode::FORBIDDEN,
-                                lang.tr(&lang::invitation_already_used()).into_owned(),
-                            )))
+                                &lang,
+                                &lang::invitation_already_used(),
+                            ))
                         } else {
                             Ok(invitation_row.get(1))
                         }
                     } else {
-                        Err(crate::Error::UserError(crate::simple_response(
+                        Err(crate::user_error(
                             hyper::StatusCode::FORBIDDEN,
-                            lang.tr(&lang::no_such_invitation()).into_owned(),
-                        )))
+                            &lang,
+                            &lang::no_such_invitation(),
+                        ))
                     }
                 } else {
-                    Err(crate::Error::UserError(crate::simple_response(
+                    Err(crate::user_error(
                         hyper::StatusCode::FORBIDDEN,
-                        lang.tr(&lang::invitations_disabled()).into_owned(),
-                    )))
+                        &lang,
+                        &lang::invitations_disabled(),
+                    ))
                 }
             } else {
-                Err(crate::Error::UserError(crate::simple_response(
+                Err(crate::user_error(
                     hyper::StatusCode::FORBIDDEN,
-                    lang.tr(&lang::signup_not_allowed()).into_owned(),
-                )))
+                    &lang,
+                    &lang::signup_not_allowed(),
+                ))
             }
         }
     }?;
 
     let req_password = body.password;
+    let params = ctx.password_hash_params.clone();
     let passhash =
-        tokio::task::spawn_blocking(move || bcrypt::hash(req_password, bcrypt::DEFAULT_COST))
+        tokio::task::spawn_blocking(move || crate::passwords::hash(&req_password, &params))
             .await??;
 
     let user_id = {
@@ -331,20 +367,17 @@ async fn route_unstable_users_create(
             .await
             .map_err(|err| {
                 if err.code() == Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION) {
-                    crate::Error::UserError(crate::simple_response(
-                        hyper::StatusCode::BAD_REQUEST,
-                        lang.tr(&lang::name_in_use()).into_owned(),
-                    ))
+                    crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::name_in_use())
                 } else {
                     err.into()
                 }
             })?;
         let row = trans.query_one(
-            "INSERT INTO person (username, local, created_local, passhash, email_address) VALUES ($1, TRUE, current_timestamp, $2, $3) RETURNING id",
-            &[&body.username, &passhash, &body.email_address],
+            "INSERT INTO person (username, local, created_local, passhash, email_address, registration_ip) VALUES ($1, TRUE, current_timestamp, $2, $3, $4) RETURNING id",
+            &[&body.username, &passhash, &body.email_address, &client_ip],
         ).await?;
 
-        let id = UserLocalID(row.get(0));
+        let id: UserLocalID = row.get(0);
 
         if let Some(invitation_id) = invitation_id {
             trans
@@ -360,8 +393,19 @@ async fn route_unstable_users_create(
         id
     };
 
+    {
+        let ctx = ctx.clone();
+        crate::spawn_task(async move {
+            ctx.enqueue_webhook_event(
+                WebhookEvent::NewRegistration,
+                serde_json::json!({ "user": user_id }),
+            )
+            .await
+        });
+    }
+
     let output = if body.login {
-        let token = super::insert_token(user_id, &db).await?;
+        let token = super::insert_token(user_id, client_ip, &db).await?;
 
         let info = super::fetch_login_info(&db, user_id).await?;
 
@@ -372,7 +416,13 @@ async fn route_unstable_users_create(
             username: body.username,
             is_site_admin: false,
             has_unread_notifications: false,
+            unread_notifications_count: 0,
             has_pending_moderation_actions: false,
+            preferred_languages: Vec::new(),
+            preferences: RespUserPreferences {
+                base: Default::default(),
+                notify_community_announcements: true,
+            },
         };
 
         serde_json::json!({ "user": info })
@@ -402,11 +452,35 @@ async fn route_unstable_users_patch(
         avatar: Option<Cow<'a, str>>,
         suspended: Option<bool>,
         is_bot: Option<bool>,
+        display_name: Option<Cow<'a, str>>,
+        profile_fields: Option<Vec<ProfileField<'a>>>,
+        notify_community_announcements: Option<bool>,
+        also_known_as: Option<Vec<Cow<'a, str>>>,
+        preferred_languages: Option<Vec<Cow<'a, str>>>,
     }
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: UsersEditBody = serde_json::from_slice(&body)?;
 
+    let profile_text_too_long = body
+        .display_name
+        .iter()
+        .chain(
+            body.profile_fields
+                .iter()
+                .flatten()
+                .flat_map(|field| vec![&field.name, &field.value]),
+        )
+        .any(|text| text.chars().count() > MAX_PROFILE_TEXT_LENGTH);
+
+    if profile_text_too_long {
+        return Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::user_profile_text_too_long(MAX_PROFILE_TEXT_LENGTH),
+        ));
+    }
+
     let too_many_description_updates = if body.description_text.is_some() {
         body.description_markdown.is_some() || body.description_html.is_some()
     } else {
@@ -414,10 +488,11 @@ async fn route_unstable_users_patch(
     };
 
     if too_many_description_updates {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            lang.tr(&lang::description_content_conflict()).into_owned(),
-        )));
+            &lang,
+            &lang::description_content_conflict(),
+        ));
     }
 
     let arena = bumpalo::Bump::new();
@@ -429,8 +504,12 @@ async fn route_unstable_users_patch(
         changes.push(("description_markdown", &Option::<&str>::None));
         changes.push(("description_html", &Option::<&str>::None));
     } else if let Some(description) = body.description_markdown {
+        let markdown_options = ctx.markdown_options;
         let (html, md) = tokio::task::spawn_blocking(move || {
-            (crate::render_markdown(&description), description)
+            (
+                crate::render_markdown(&description, markdown_options),
+                description,
+            )
         })
         .await?;
 
@@ -445,27 +524,30 @@ async fn route_unstable_users_patch(
 
     if let Some(email_address) = body.email_address.as_ref() {
         if !fast_chemail::is_valid_email(email_address) {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                lang.tr(&lang::user_email_invalid()).into_owned(),
-            )));
+                &lang,
+                &lang::user_email_invalid(),
+            ));
         }
 
         changes.push(("email_address", email_address));
     }
     if let Some(password) = body.password {
+        let params = ctx.password_hash_params.clone();
         let passhash =
-            tokio::task::spawn_blocking(move || bcrypt::hash(password, bcrypt::DEFAULT_COST))
+            tokio::task::spawn_blocking(move || crate::passwords::hash(&password, &params))
                 .await??;
 
         changes.push(("passhash", arena.alloc(passhash)));
     }
     if let Some(avatar) = &body.avatar {
         if !avatar.starts_with("local-media://") {
-            return Err(crate::Error::UserError(crate::simple_response(
+            return Err(crate::user_error(
                 hyper::StatusCode::BAD_REQUEST,
-                "Avatar must be local media",
-            )));
+                &lang,
+                &lang::user_avatar_not_local(),
+            ));
         }
 
         changes.push(("avatar", avatar));
@@ -478,6 +560,87 @@ async fn route_unstable_users_patch(
     if let Some(is_bot) = &body.is_bot {
         changes.push(("is_bot", is_bot));
     }
+    if let Some(notify_community_announcements) = &body.notify_community_announcements {
+        changes.push((
+            "notify_community_announcements",
+            notify_community_announcements,
+        ));
+    }
+    if let Some(display_name) = &body.display_name {
+        let display_name = if display_name.is_empty() {
+            None
+        } else {
+            Some(display_name)
+        };
+
+        changes.push(("display_name", arena.alloc(display_name)));
+    }
+    if let Some(profile_fields) = &body.profile_fields {
+        if profile_fields.len() > MAX_PROFILE_FIELDS {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::user_profile_fields_too_many(MAX_PROFILE_FIELDS),
+            ));
+        }
+
+        changes.push((
+            "profile_fields",
+            arena.alloc(postgres_types::Json(profile_fields)),
+        ));
+    }
+    if let Some(also_known_as) = &body.also_known_as {
+        if also_known_as.len() > MAX_ALIASES {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::user_aliases_too_many(MAX_ALIASES),
+            ));
+        }
+
+        let valid = also_known_as
+            .iter()
+            .all(|alias| match url::Url::parse(alias) {
+                Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+                Err(_) => false,
+            });
+        if !valid {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::user_alias_invalid(),
+            ));
+        }
+
+        changes.push(("also_known_as", also_known_as));
+    }
+    if let Some(preferred_languages) = &body.preferred_languages {
+        if preferred_languages.len() > MAX_PREFERRED_LANGUAGES {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::user_preferred_languages_too_many(MAX_PREFERRED_LANGUAGES),
+            ));
+        }
+
+        let mut languages = Vec::with_capacity(preferred_languages.len());
+        for language in preferred_languages {
+            let language = crate::normalize_language(language).ok_or_else(|| {
+                crate::user_error(
+                    hyper::StatusCode::BAD_REQUEST,
+                    &lang,
+                    &lang::language_invalid(),
+                )
+            })?;
+
+            // order matters, since the first is used as the default for new posts
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+
+        changes.push(("preferred_languages", arena.alloc(languages)));
+    }
 
     if !changes.is_empty() {
         use std::fmt::Write;
@@ -507,49 +670,422 @@ async fn route_unstable_users_patch(
         let trans = db.transaction().await?;
         trans.execute(sql, &values).await?;
         if let Some(suspended) = body.suspended {
-            if suspended {
-                // just suspended, need to clear out current logins
+            on_suspended_changed(user_id, suspended, me_or_admin.login_user, &trans).await?;
+        }
 
-                trans
-                    .execute("DELETE FROM login WHERE person=$1", &[&user_id])
-                    .await?;
-            }
+        trans.commit().await?;
+    }
 
-            let action = if suspended {
-                "suspend_user"
-            } else {
-                "unsuspend_user"
-            };
+    Ok(crate::empty_response())
+}
 
-            trans.execute("INSERT INTO modlog_event (time, by_person, action, person) VALUES (current_timestamp, $1, $2, $3)", &[&me_or_admin.login_user, &action, &user_id]).await?;
-        }
+/// Revokes logins for newly suspended users and records the change in the modlog
+pub async fn on_suspended_changed(
+    user: UserLocalID,
+    suspended: bool,
+    by: UserLocalID,
+    trans: &tokio_postgres::Transaction<'_>,
+) -> Result<(), crate::Error> {
+    if suspended {
+        // just suspended, need to clear out current logins
+
+        trans
+            .execute("DELETE FROM login WHERE person=$1", &[&user])
+            .await?;
+    }
+
+    let action = if suspended {
+        "suspend_user"
+    } else {
+        "unsuspend_user"
+    };
+
+    trans.execute("INSERT INTO modlog_event (time, by_person, action, person) VALUES (current_timestamp, $1, $2, $3)", &[&by, &action, &user]).await?;
+
+    Ok(())
+}
+
+async fn route_unstable_users_delete(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct UsersDeleteBody {
+        password: String,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let mut db = ctx.db_pool.get().await?;
+
+    let user_id = params.0.require_me(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: UsersDeleteBody = serde_json::from_slice(&body)?;
+
+    let passhash: Option<String> = db
+        .query_one("SELECT passhash FROM person WHERE id=$1", &[&user_id])
+        .await?
+        .get(0);
+
+    let passhash = passhash.ok_or_else(|| {
+        crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::no_password())
+    })?;
+
+    let req_password = body.password;
+    let correct =
+        tokio::task::spawn_blocking(move || crate::passwords::verify(&req_password, &passhash))
+            .await??;
+
+    if !correct {
+        return Err(crate::user_error(
+            hyper::StatusCode::FORBIDDEN,
+            &lang,
+            &lang::password_incorrect(),
+        ));
+    }
+
+    let community_rows = {
+        let trans = db.transaction().await?;
+
+        // communities that may have seen this user, to be notified of the deletion
+        let community_rows = trans.query(
+            "SELECT id, local, COALESCE(ap_shared_inbox, ap_inbox) FROM community WHERE id IN (SELECT community FROM community_follow WHERE follower=$1 UNION SELECT community FROM post WHERE author=$1 UNION SELECT post.community FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.author=$1)",
+            &[&user_id],
+        ).await?;
+
+        trans.execute(
+            "UPDATE person SET deleted=TRUE, username='[deleted]', passhash=NULL, email_address=NULL, description='', description_html=NULL, description_markdown=NULL, avatar=NULL, display_name=NULL, profile_fields='[]', is_site_admin=FALSE, is_bot=FALSE WHERE id=$1",
+            &[&user_id],
+        ).await?;
+
+        trans
+            .execute("DELETE FROM login WHERE person=$1", &[&user_id])
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM forgot_password_key WHERE person=$1",
+                &[&user_id],
+            )
+            .await?;
+        trans
+            .execute("DELETE FROM reply_draft WHERE person=$1", &[&user_id])
+            .await?;
+        trans
+            .execute("DELETE FROM notification WHERE to_user=$1", &[&user_id])
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM person_notification_subscription WHERE person=$1",
+                &[&user_id],
+            )
+            .await?;
+        trans
+            .execute("DELETE FROM person_note WHERE author=$1", &[&user_id])
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM community_follow WHERE follower=$1",
+                &[&user_id],
+            )
+            .await?;
+        trans
+            .execute("DELETE FROM person_follow WHERE follower=$1", &[&user_id])
+            .await?;
+        trans
+            .execute(
+                "DELETE FROM community_moderator WHERE person=$1",
+                &[&user_id],
+            )
+            .await?;
 
         trans.commit().await?;
+
+        community_rows
+    };
+
+    crate::spawn_task(async move {
+        let delete_ap = crate::apub_util::local_user_delete_to_ap(user_id, &ctx.host_url_apub);
+        let body = serde_json::to_string(&delete_ap)?;
+
+        ctx.enqueue_task(&crate::tasks::DeliverToFollowers {
+            actor: ActorLocalRef::Person(user_id),
+            sign: true,
+            object: body.clone(),
+        })
+        .await?;
+
+        for row in community_rows {
+            let community_id: CommunityLocalID = row.get(0);
+            let local: bool = row.get(1);
+
+            if local {
+                crate::apub_util::enqueue_forward_to_community_followers(
+                    community_id,
+                    body.clone(),
+                    ctx.clone(),
+                )
+                .await?;
+            } else if let Some(inbox) = row.get::<_, Option<&str>>(2) {
+                ctx.enqueue_task(&crate::tasks::DeliverToInbox {
+                    inbox: Cow::Owned(inbox.parse()?),
+                    sign_as: Some(ActorLocalRef::Person(user_id)),
+                    object: body.clone(),
+                })
+                .await?;
+            }
+        }
+
+        Ok(())
+    });
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_users_move(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct UsersMoveBody {
+        target: url::Url,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user_id = params.0.require_me(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: UsersMoveBody = serde_json::from_slice(&body)?;
+
+    let invalid_target = || {
+        crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            &lang,
+            &lang::user_move_target_invalid(),
+        )
+    };
+
+    let target_id =
+        match crate::apub_util::LocalObjectRef::try_from_uri(&body.target, &ctx.host_url_apub) {
+            Some(crate::apub_util::LocalObjectRef::User(target_id)) => target_id,
+            Some(_) => return Err(invalid_target()),
+            None => match crate::apub_util::fetch_actor(&body.target, ctx.clone()).await {
+                Ok(crate::apub_util::ActorLocalInfo::User { id, .. }) => id,
+                Ok(_) | Err(_) => return Err(invalid_target()),
+            },
+        };
+
+    if target_id == user_id {
+        return Err(invalid_target());
+    }
+
+    let user_ap_id =
+        crate::apub_util::LocalObjectRef::User(user_id).to_local_uri(&ctx.host_url_apub);
+
+    let row = db
+        .query_opt(
+            "SELECT 1 FROM person WHERE id=$1 AND $2 = ANY(also_known_as)",
+            &[&target_id, &user_ap_id.as_str()],
+        )
+        .await?;
+    if row.is_none() {
+        return Err(invalid_target());
     }
 
+    db.execute(
+        "UPDATE person SET moved_to=$2 WHERE id=$1",
+        &[&user_id, &body.target.as_str()],
+    )
+    .await?;
+
+    let move_ap = crate::apub_util::local_user_move_to_ap(user_id, body.target, &ctx.host_url_apub);
+
+    ctx.enqueue_task(&crate::tasks::DeliverToFollowers {
+        actor: ActorLocalRef::Person(user_id),
+        sign: true,
+        object: serde_json::to_string(&move_ap)?,
+    })
+    .await?;
+
     Ok(crate::empty_response())
 }
 
+async fn route_unstable_users_insights_get(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let user = user.require_me(&req, &db).await?;
+
+    {
+        let cache = ctx.user_insights_cache.lock().unwrap();
+        if let Some((time, body)) = cache.get(&user) {
+            if time.elapsed() < USER_INSIGHTS_CACHE_DURATION {
+                return Ok(crate::common_response_builder()
+                    .header(hyper::header::CONTENT_TYPE, "application/json")
+                    .body(body.clone().into())?);
+            }
+        }
+    }
+
+    let mut window_rows = Vec::with_capacity(USER_INSIGHTS_WINDOWS.len());
+    for (_, interval) in USER_INSIGHTS_WINDOWS {
+        let (totals_row, community_rows) = futures::future::try_join(
+            db.query_one(
+                "SELECT * FROM (SELECT COUNT(*), COALESCE(SUM((SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id)), 0)::BIGINT, COALESCE(SUM((SELECT COUNT(*) FROM reply WHERE reply.post = post.id AND reply.parent IS NULL AND reply.author IS DISTINCT FROM $1)), 0)::BIGINT FROM post WHERE author=$1 AND NOT deleted AND created > (current_timestamp - $2::TEXT::INTERVAL)) AS posts, (SELECT COUNT(*), COALESCE(SUM((SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply_like.reply = reply.id)), 0)::BIGINT, COALESCE(SUM((SELECT COUNT(*) FROM reply AS child WHERE child.parent = reply.id AND child.author IS DISTINCT FROM $1)), 0)::BIGINT FROM reply WHERE author=$1 AND NOT deleted AND created > (current_timestamp - $2::TEXT::INTERVAL)) AS comments",
+                &[&user, interval],
+            ),
+            db.query(
+                "SELECT community.id, community.name, community.local, community.ap_id, community.deleted, COUNT(*), COALESCE(SUM((SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id)), 0)::BIGINT AS score FROM post INNER JOIN community ON (community.id = post.community) WHERE post.author=$1 AND NOT post.deleted AND post.created > (current_timestamp - $2::TEXT::INTERVAL) GROUP BY community.id ORDER BY score DESC, COUNT(*) DESC LIMIT 5",
+                &[&user, interval],
+            ),
+        )
+        .await?;
+
+        window_rows.push((totals_row, community_rows));
+    }
+
+    let output = RespUserInsights {
+        generated_at: chrono::offset::Utc::now().to_rfc3339(),
+        windows: USER_INSIGHTS_WINDOWS
+            .iter()
+            .zip(window_rows.iter())
+            .map(
+                |((window, _), (totals_row, community_rows))| RespUserInsightsWindow {
+                    window,
+                    post_count: totals_row.get(0),
+                    post_score: totals_row.get(1),
+                    post_replies_count: totals_row.get(2),
+                    comment_count: totals_row.get(3),
+                    comment_score: totals_row.get(4),
+                    comment_replies_count: totals_row.get(5),
+                    top_communities: community_rows
+                        .iter()
+                        .map(|row| {
+                            let community_id: CommunityLocalID = row.get(0);
+                            let community_local: bool = row.get(2);
+                            let community_ap_id: Option<&str> = row.get(3);
+
+                            RespUserInsightsCommunity {
+                                community: RespMinimalCommunityInfo {
+                                    id: community_id,
+                                    name: Cow::Borrowed(row.get(1)),
+                                    local: community_local,
+                                    host: crate::get_actor_host_or_unknown(
+                                        community_local,
+                                        community_ap_id,
+                                        &ctx.local_hostname,
+                                    ),
+                                    remote_url: if community_local {
+                                        Some(Cow::Owned(String::from(
+                                            crate::apub_util::LocalObjectRef::Community(
+                                                community_id,
+                                            )
+                                            .to_local_uri(&ctx.host_url_apub),
+                                        )))
+                                    } else {
+                                        community_ap_id.map(Cow::Borrowed)
+                                    },
+                                    deleted: row.get(4),
+                                },
+                                post_count: row.get(5),
+                                score: row.get(6),
+                            }
+                        })
+                        .collect(),
+                },
+            )
+            .collect(),
+    };
+
+    let body = bytes::Bytes::from(serde_json::to_vec(&output)?);
+
+    {
+        let mut cache = ctx.user_insights_cache.lock().unwrap();
+        cache.retain(|_, (time, _)| time.elapsed() < USER_INSIGHTS_CACHE_DURATION);
+        cache.insert(user, (std::time::Instant::now(), body.clone()));
+    }
+
+    Ok(crate::common_response_builder()
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(body.into())?)
+}
+
 async fn route_unstable_users_notifications_list(
     params: (UserIDOrMe,),
     ctx: Arc<crate::RouteContext>,
     req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    use chrono::offset::TimeZone;
+    use std::convert::TryInto;
+
     let (user,) = params;
 
+    #[derive(Deserialize)]
+    struct NotificationsListQuery<'a> {
+        page: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let query: NotificationsListQuery =
+        serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+    let page: Option<(chrono::DateTime<chrono::offset::FixedOffset>, i64)> = query
+        .page
+        .map(|src| {
+            let mut spl = src.split(',');
+
+            let ts = spl.next().ok_or(())?;
+            let id = spl.next().ok_or(())?;
+            if spl.next().is_some() {
+                Err(())
+            } else {
+                let ts: i64 = ts.parse().map_err(|_| ())?;
+                let id: i64 = id.parse().map_err(|_| ())?;
+
+                let ts = chrono::offset::Utc.timestamp_nanos(ts);
+
+                Ok((ts.into(), id))
+            }
+        })
+        .transpose()
+        .map_err(|_| {
+            crate::user_error(hyper::StatusCode::BAD_REQUEST, &lang, &lang::page_invalid())
+        })?;
+
     let mut db = ctx.db_pool.get().await?;
 
     let user = user.require_me(&req, &db).await?;
 
     let limit: i64 = 30;
+    let real_limit = limit + 1;
 
-    let rows = {
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&user, &real_limit];
+    let page_conditions = match &page {
+        Some((ts, id)) => {
+            values.push(ts);
+            values.push(id);
+
+            " AND (notification.created_at < $3 OR (notification.created_at = $3 AND notification.id < $4))"
+        }
+        None => "",
+    };
+
+    let mut rows = {
         let trans = db.transaction().await?;
 
-        let rows = trans.query(
-            "SELECT notification.kind, (notification.created_at > (SELECT last_checked_notifications FROM person WHERE id=$1)), reply.id, reply.content_text, reply.content_html, parent_reply.id, parent_reply.content_text, parent_reply.content_html, parent_post.id, parent_post.title, parent_post.ap_id, parent_post.local, reply.ap_id, reply.local, parent_post.href, parent_post.content_text, parent_post.created, parent_post.content_markdown, parent_post.content_html, community.id, community.local, community.ap_id, parent_post_author.id, parent_post_author.username, parent_post_author.local, parent_post_author.ap_id, parent_post_author.avatar, (SELECT COUNT(*) FROM post_like WHERE post_like.post = parent_post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = parent_post.id), parent_post.sticky, parent_post_author.is_bot, parent_reply_author.id, parent_reply_author.is_bot, parent_reply_author.username, parent_reply_author.ap_id, parent_reply_author.local, parent_reply_author.avatar, parent_reply.ap_id, parent_reply.local, EXISTS(SELECT 1 FROM post_like WHERE post_like.post = parent_post.id AND post_like.person = $1), reply.attachment_href, parent_reply.attachment_href, reply.content_markdown, parent_reply.content_markdown, reply.created, parent_reply.created, (SELECT COUNT(*) FROM reply_like WHERE reply_like.reply = parent_reply.id), EXISTS(SELECT 1 FROM reply_like WHERE reply_like.reply = parent_reply.id AND reply_like.person = $1), (SELECT COUNT(*) FROM reply_like WHERE reply_like.reply = reply.id), EXISTS(SELECT 1 FROM reply_like WHERE reply_like.reply = reply.id AND reply_like.person = $1), reply_author.id, reply_author.is_bot, reply_author.username, reply_author.ap_id, reply_author.local, reply_author.avatar, community.name, EXISTS(SELECT 1 FROM reply AS reply_reply WHERE reply_reply.parent = reply.id), community.deleted, parent_post.sensitive, reply.sensitive, parent_reply.sensitive FROM notification LEFT OUTER JOIN reply ON (reply.id = notification.reply) LEFT OUTER JOIN reply AS parent_reply ON (parent_reply.id = notification.parent_reply) LEFT OUTER JOIN post AS parent_post ON (parent_post.id = COALESCE(parent_reply.post, notification.parent_post)) LEFT OUTER JOIN community ON (community.id = parent_post.community) LEFT OUTER JOIN person AS parent_post_author ON (parent_post_author.id = parent_post.author) LEFT OUTER JOIN person AS parent_reply_author ON (parent_reply_author.id = parent_reply.author) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) WHERE notification.to_user = $1 AND NOT COALESCE(reply.deleted OR parent_reply.deleted OR parent_post.deleted, FALSE) ORDER BY created_at DESC LIMIT $2",
-            &[&user, &limit],
-        ).await?;
+        let sql: &str = &format!(
+            "SELECT notification.kind, (notification.created_at > (SELECT last_checked_notifications FROM person WHERE id=$1)), reply.id, reply.content_text, reply.content_html, parent_reply.id, parent_reply.content_text, parent_reply.content_html, parent_post.id, parent_post.title, parent_post.ap_id, parent_post.local, reply.ap_id, reply.local, parent_post.href, parent_post.content_text, parent_post.created, parent_post.content_markdown, parent_post.content_html, community.id, community.local, community.ap_id, parent_post_author.id, parent_post_author.username, parent_post_author.local, parent_post_author.ap_id, parent_post_author.avatar, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = parent_post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = parent_post.id), parent_post.sticky, parent_post_author.is_bot, parent_reply_author.id, parent_reply_author.is_bot, parent_reply_author.username, parent_reply_author.ap_id, parent_reply_author.local, parent_reply_author.avatar, parent_reply.ap_id, parent_reply.local, (SELECT is_dislike FROM post_like WHERE post_like.post = parent_post.id AND post_like.person = $1), reply.attachment_href, parent_reply.attachment_href, reply.content_markdown, parent_reply.content_markdown, reply.created, parent_reply.created, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply_like.reply = parent_reply.id), (SELECT is_dislike FROM reply_like WHERE reply_like.reply = parent_reply.id AND reply_like.person = $1), (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply_like.reply = reply.id), (SELECT is_dislike FROM reply_like WHERE reply_like.reply = reply.id AND reply_like.person = $1), reply_author.id, reply_author.is_bot, reply_author.username, reply_author.ap_id, reply_author.local, reply_author.avatar, community.name, EXISTS(SELECT 1 FROM reply AS reply_reply WHERE reply_reply.parent = reply.id AND NOT reply_reply.pending_approval), community.deleted, parent_post.sensitive, reply.sensitive, parent_reply.sensitive, community.hide_scores_minutes, notification.id, notification.created_at, notification.read, login_attempt.id, login_attempt.created, login_attempt.success, login_attempt.ip, login_attempt.user_agent, reply.updated, parent_reply.updated FROM notification LEFT OUTER JOIN reply ON (reply.id = notification.reply) LEFT OUTER JOIN reply AS parent_reply ON (parent_reply.id = notification.parent_reply) LEFT OUTER JOIN post AS parent_post ON (parent_post.id = COALESCE(parent_reply.post, notification.parent_post)) LEFT OUTER JOIN community ON (community.id = parent_post.community) LEFT OUTER JOIN person AS parent_post_author ON (parent_post_author.id = parent_post.author) LEFT OUTER JOIN person AS parent_reply_author ON (parent_reply_author.id = parent_reply.author) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) LEFT OUTER JOIN login_attempt ON (login_attempt.id = notification.login_attempt) WHERE notification.to_user = $1 AND NOT COALESCE(reply.deleted OR parent_reply.deleted OR parent_post.deleted, FALSE){} ORDER BY notification.created_at DESC, notification.id DESC LIMIT $2",
+            page_conditions,
+        );
+
+        let rows = trans.query(sql, &values).await?;
         trans
             .execute(
                 "UPDATE person SET last_checked_notifications=current_timestamp WHERE id=$1",
@@ -562,6 +1098,19 @@ async fn route_unstable_users_notifications_list(
         rows
     };
 
+    let next_page = if rows.len() > limit.try_into().unwrap() {
+        rows.pop();
+
+        let row = rows.last().unwrap();
+
+        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(64);
+        let id: i64 = row.get(63);
+
+        Some(format!("{},{}", ts.timestamp_nanos(), id))
+    } else {
+        None
+    };
+
     let notifications: Vec<_> = rows
         .iter()
         .filter_map(|row| {
@@ -570,6 +1119,7 @@ async fn route_unstable_users_notifications_list(
 
             let post = row.get::<_, Option<_>>(8).map(|post_id| {
                 let post_id = PostLocalID(post_id);
+                let post_created: chrono::DateTime<chrono::FixedOffset> = row.get(16);
 
                 let post_ap_id: Option<&str> = row.get(10);
                 let post_local: bool = row.get(11);
@@ -583,7 +1133,7 @@ async fn route_unstable_users_notifications_list(
                     post_ap_id.map(Cow::Borrowed)
                 };
 
-                let community_id = CommunityLocalID(row.get(19));
+                let community_id: CommunityLocalID = row.get(19);
                 let community_local: bool = row.get(20);
                 let community_ap_id: Option<&str> = row.get(21);
 
@@ -596,10 +1146,7 @@ async fn route_unstable_users_notifications_list(
                         post_id,
                     ),
                     content_text: row.get::<_, Option<_>>(15).map(Cow::Borrowed),
-                    created: Cow::Owned(
-                        row.get::<_, chrono::DateTime<chrono::FixedOffset>>(16)
-                            .to_rfc3339(),
-                    ),
+                    created: Cow::Owned(post_created.to_rfc3339()),
                     content_markdown: row.get::<_, Option<_>>(17).map(Cow::Borrowed),
                     content_html_safe: row
                         .get::<_, Option<&str>>(18)
@@ -655,14 +1202,15 @@ async fn route_unstable_users_notifications_list(
                         None
                     },
                     relevance: None,
-                    score: row.get(27),
+                    score: super::get_resp_score(row.get(27), &post_created, row.get(62)),
                     replies_count_total: row.get(28),
                     sticky: row.get(29),
-                    your_vote: Some(if row.get(39) {
-                        Some(crate::types::Empty {})
-                    } else {
-                        None
-                    }),
+                    your_vote: Some(
+                        row.get::<_, Option<bool>>(39)
+                            .map(|is_dislike| crate::types::RespYourVote { is_dislike }),
+                    ),
+                    flairs: None,
+                    thumbnail: None,
                 }
             });
 
@@ -671,6 +1219,7 @@ async fn route_unstable_users_notifications_list(
 
                 let reply_ap_id: Option<&str> = row.get(12);
                 let reply_local: bool = row.get(13);
+                let reply_created: chrono::DateTime<chrono::FixedOffset> = row.get(44);
 
                 RespPostCommentInfo {
                     base: RespMinimalCommentInfo {
@@ -727,16 +1276,16 @@ async fn route_unstable_users_notifications_list(
                         None
                     },
                     content_markdown: row.get::<_, Option<_>>(42).map(Cow::Borrowed),
-                    created: row
-                        .get::<_, chrono::DateTime<chrono::FixedOffset>>(44)
-                        .to_rfc3339(),
+                    created: reply_created.to_rfc3339(),
                     deleted: false,
-                    score: row.get(48),
-                    your_vote: Some(if row.get::<_, bool>(49) {
-                        Some(crate::types::Empty {})
-                    } else {
-                        None
-                    }),
+                    edited_at: row
+                        .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(71)
+                        .map(|time| time.to_rfc3339()),
+                    score: super::get_resp_score(row.get(48), &reply_created, row.get(62)),
+                    your_vote: Some(
+                        row.get::<_, Option<bool>>(49)
+                            .map(|is_dislike| crate::types::RespYourVote { is_dislike }),
+                    ),
                     local: reply_local,
                     replies: if row.get(57) {
                         None
@@ -753,6 +1302,7 @@ async fn route_unstable_users_notifications_list(
                 let parent_id = CommentLocalID(parent_id);
                 let parent_ap_id: Option<&str> = row.get(37);
                 let parent_local: bool = row.get(38);
+                let parent_created: chrono::DateTime<chrono::FixedOffset> = row.get(45);
 
                 RespPostCommentInfo {
                     base: RespMinimalCommentInfo {
@@ -809,18 +1359,18 @@ async fn route_unstable_users_notifications_list(
                         Some(href) => vec![JustURL { url: href }],
                     },
                     content_markdown: row.get::<_, Option<_>>(43).map(Cow::Borrowed),
-                    created: row
-                        .get::<_, chrono::DateTime<chrono::FixedOffset>>(45)
-                        .to_rfc3339(),
+                    created: parent_created.to_rfc3339(),
                     deleted: false,
+                    edited_at: row
+                        .get::<_, Option<chrono::DateTime<chrono::FixedOffset>>>(72)
+                        .map(|time| time.to_rfc3339()),
                     local: parent_local,
-                    score: row.get(46),
+                    score: super::get_resp_score(row.get(46), &parent_created, row.get(62)),
                     replies: None,
-                    your_vote: Some(if row.get::<_, bool>(47) {
-                        Some(crate::types::Empty {})
-                    } else {
-                        None
-                    }),
+                    your_vote: Some(
+                        row.get::<_, Option<bool>>(47)
+                            .map(|is_dislike| crate::types::RespYourVote { is_dislike }),
+                    ),
                 }
             });
 
@@ -855,19 +1405,117 @@ async fn route_unstable_users_notifications_list(
                         None
                     }
                 }
+                "community_announcement" => {
+                    post.map(|post| RespNotificationInfo::CommunityAnnouncement { post })
+                }
+                "new_login" => row.get::<_, Option<i64>>(66).map(|id| {
+                    let created: chrono::DateTime<chrono::FixedOffset> = row.get(67);
+
+                    RespNotificationInfo::NewLogin {
+                        login: RespLoginAttempt {
+                            id,
+                            created: created.to_rfc3339(),
+                            success: row.get(68),
+                            ip: row
+                                .get::<_, Option<std::net::IpAddr>>(69)
+                                .map(|ip| ip.to_string()),
+                            user_agent: row.get::<_, Option<&str>>(70).map(Cow::Borrowed),
+                        },
+                    }
+                }),
                 _ => None,
             };
 
-            info.map(|info| RespNotification { info, unseen })
+            let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(64);
+
+            info.map(|info| RespNotification {
+                id: row.get(63),
+                info,
+                unseen,
+                read: row.get(65),
+                created_at: created_at.to_rfc3339(),
+            })
         })
         .collect();
 
     crate::json_response(&RespList {
         items: Cow::Owned(notifications),
-        next_page: None,
+        next_page: next_page.map(Cow::Owned),
     })
 }
 
+async fn route_unstable_users_notifications_read_put(
+    params: (UserIDOrMe, NotificationID),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user, notification_id) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = user.require_me(&req, &db).await?;
+
+    let row_count = db
+        .execute(
+            "UPDATE notification SET read=TRUE WHERE id=$1 AND to_user=$2",
+            &[&notification_id, &user],
+        )
+        .await?;
+
+    if row_count == 0 {
+        return Err(crate::user_error(
+            hyper::StatusCode::NOT_FOUND,
+            &lang,
+            &lang::no_such_notification(),
+        ));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_users_notifications_mark_read(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    #[derive(Deserialize)]
+    struct NotificationsMarkReadBody<'a> {
+        before: Option<Cow<'a, str>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = user.require_me(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: NotificationsMarkReadBody = serde_json::from_slice(&body)?;
+
+    let before = body
+        .before
+        .as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|_| {
+            crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::timestamp_invalid("before"),
+            )
+        })?;
+
+    db.execute(
+        "UPDATE notification SET read=TRUE WHERE to_user=$1 AND NOT read AND ($2::TIMESTAMPTZ IS NULL OR created_at <= $2)",
+        &[&user, &before],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
 async fn route_unstable_users_notifications_subscriptions_create(
     params: (UserIDOrMe,),
     ctx: Arc<crate::RouteContext>,
@@ -875,6 +1523,7 @@ async fn route_unstable_users_notifications_subscriptions_create(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
     let user_id = user_id.require_me(&req, &db).await?;
@@ -886,25 +1535,295 @@ async fn route_unstable_users_notifications_subscriptions_create(
         .get(hyper::header::ACCEPT_LANGUAGE)
         .and_then(|x| x.to_str().ok());
 
-    let body = hyper::body::to_bytes(body).await?;
+    let body = crate::to_bytes_limited(body, ctx.max_request_body_size).await?;
     let body: NotificationSubscriptionCreateQuery = serde_json::from_slice(&body)?;
 
     if body.type_ != "web_push" {
-        return Err(crate::Error::UserError(crate::simple_response(
+        return Err(crate::user_error(
             hyper::StatusCode::BAD_REQUEST,
-            "Unknown subscription type",
-        )));
+            &lang,
+            &lang::notification_subscription_type_unknown(),
+        ));
     }
 
     let row = db.query_one(
         "INSERT INTO person_notification_subscription (person, endpoint, p256dh_key, auth_key, language) VALUES ($1, $2, $3, $4, $5) RETURNING id",
         &[&user_id, &body.endpoint, &body.p256dh_key, &body.auth_key, &language],
     ).await?;
-    let id = NotificationSubscriptionID(row.get(0));
+    let id: NotificationSubscriptionID = row.get(0);
 
     crate::json_response(&JustID { id })
 }
 
+fn is_valid_timezone(src: &str) -> bool {
+    // can't check against the tz database, but at least keep it to something that looks like a name
+    !src.is_empty()
+        && src.len() <= MAX_TIMEZONE_LENGTH
+        && src
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '_' | '-' | '+'))
+}
+
+async fn route_unstable_users_preferences_get(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let user_id = user_id.require_me(&req, &db).await?;
+
+    let row = db
+        .query_one(
+            "SELECT preferences, notify_community_announcements FROM person WHERE id=$1",
+            &[&user_id],
+        )
+        .await?;
+
+    let output = RespUserPreferences {
+        base: row.get::<_, postgres_types::Json<UserPreferences>>(0).0,
+        notify_community_announcements: row.get(1),
+    };
+
+    crate::json_response(&output)
+}
+
+async fn route_unstable_users_preferences_patch(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+
+    #[derive(Deserialize)]
+    struct UserPreferencesEditBody<'a> {
+        default_sort: Option<super::SortType>,
+        show_sensitive: Option<bool>,
+        timezone: Option<Cow<'a, str>>,
+        notify_post_replies: Option<bool>,
+        notify_comment_replies: Option<bool>,
+        notify_community_announcements: Option<bool>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user_id = user_id.require_me(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: UserPreferencesEditBody = serde_json::from_slice(&body)?;
+
+    let mut changes = serde_json::Map::new();
+
+    if let Some(default_sort) = body.default_sort {
+        changes.insert("default_sort".to_owned(), default_sort.as_str().into());
+    }
+    if let Some(show_sensitive) = body.show_sensitive {
+        changes.insert("show_sensitive".to_owned(), show_sensitive.into());
+    }
+    if let Some(timezone) = body.timezone {
+        if timezone.is_empty() {
+            changes.insert("timezone".to_owned(), serde_json::Value::Null);
+        } else if is_valid_timezone(&timezone) {
+            changes.insert("timezone".to_owned(), timezone.into_owned().into());
+        } else {
+            return Err(crate::user_error(
+                hyper::StatusCode::BAD_REQUEST,
+                &lang,
+                &lang::timezone_invalid(),
+            ));
+        }
+    }
+    if let Some(notify_post_replies) = body.notify_post_replies {
+        changes.insert("notify_post_replies".to_owned(), notify_post_replies.into());
+    }
+    if let Some(notify_comment_replies) = body.notify_comment_replies {
+        changes.insert(
+            "notify_comment_replies".to_owned(),
+            notify_comment_replies.into(),
+        );
+    }
+
+    db.execute(
+        "UPDATE person SET preferences = preferences || $1, notify_community_announcements = COALESCE($2, notify_community_announcements) WHERE id=$3",
+        &[&serde_json::Value::Object(changes), &body.notify_community_announcements, &user_id],
+    )
+    .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_users_logins_history_list(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = user.require_me(&req, &db).await?;
+
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct LoginsHistoryListQuery<'a> {
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+    }
+    let query: LoginsHistoryListQuery =
+        serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let page: Option<i64> = query
+        .page
+        .as_deref()
+        .map(|src| parse_number_58(src).map_err(|_| InvalidPage))
+        .transpose()
+        .map_err(|err| err.into_user_error(&lang))?;
+
+    let mut rows = db
+        .query(
+            "SELECT id, created, success, ip, user_agent FROM login_attempt WHERE person=$1 AND ($3::BIGINT IS NULL OR id <= $3) ORDER BY id DESC LIMIT $2",
+            &[&user, &limit_plus_1, &page],
+        )
+        .await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(format_number_58(row.get(0)))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let created: chrono::DateTime<chrono::FixedOffset> = row.get(1);
+
+            RespLoginAttempt {
+                id: row.get(0),
+                created: created.to_rfc3339(),
+                success: row.get(2),
+                ip: row
+                    .get::<_, Option<std::net::IpAddr>>(3)
+                    .map(|ip| ip.to_string()),
+                user_agent: row.get::<_, Option<&str>>(4).map(Cow::Borrowed),
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: next_page.map(Cow::Owned),
+    })
+}
+
+async fn route_unstable_users_following_list(
+    params: (UserIDOrMe,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    use std::fmt::Write;
+
+    let (user,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = user.require_me(&req, &db).await?;
+
+    fn default_limit() -> u8 {
+        30
+    }
+
+    #[derive(Deserialize)]
+    struct UserFollowingListQuery<'a> {
+        accepted: Option<bool>,
+
+        #[serde(default = "default_limit")]
+        limit: u8,
+
+        page: Option<Cow<'a, str>>,
+    }
+    let query: UserFollowingListQuery =
+        serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+
+    let limit_plus_1: i64 = (query.limit + 1).into();
+
+    let page: Option<i64> = query
+        .page
+        .as_deref()
+        .map(|src| parse_number_58(src).map_err(|_| InvalidPage))
+        .transpose()
+        .map_err(|err| err.into_user_error(&lang))?;
+
+    let mut sql = String::from("SELECT community.id, community.name, community.local, community.ap_id, community_follow.accepted FROM community_follow INNER JOIN community ON (community.id = community_follow.community) WHERE community_follow.follower=$1 AND NOT community.deleted");
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&user, &limit_plus_1];
+
+    if let Some(accepted) = &query.accepted {
+        values.push(accepted);
+        write!(sql, " AND community_follow.accepted=${}", values.len()).unwrap();
+    }
+    if let Some(page) = &page {
+        values.push(page);
+        write!(sql, " AND community.id >= ${}", values.len()).unwrap();
+    }
+
+    sql.push_str(" ORDER BY community.id ASC LIMIT $2");
+
+    let sql: &str = &sql;
+    let mut rows = crate::query::query(&db, sql, &values).await?;
+
+    let next_page = if rows.len() > query.limit as usize {
+        let row = rows.pop().unwrap();
+        Some(format_number_58(row.get(0)))
+    } else {
+        None
+    };
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let id: CommunityLocalID = row.get(0);
+            let local = row.get(2);
+            let ap_id: Option<&str> = row.get(3);
+
+            RespCommunityFollowInfo {
+                community: RespMinimalCommunityInfo {
+                    id,
+                    name: Cow::Borrowed(row.get(1)),
+                    local,
+                    host: crate::get_actor_host_or_unknown(local, ap_id, &ctx.local_hostname),
+                    remote_url: if local {
+                        Some(Cow::Owned(String::from(
+                            crate::apub_util::LocalObjectRef::Community(id)
+                                .to_local_uri(&ctx.host_url_apub),
+                        )))
+                    } else {
+                        ap_id.map(Cow::Borrowed)
+                    },
+                    deleted: false,
+                },
+                accepted: row.get(4),
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: next_page.map(Cow::Owned),
+    })
+}
+
 async fn route_unstable_users_get(
     params: (UserIDOrMe,),
     ctx: Arc<crate::RouteContext>,
@@ -946,16 +1865,13 @@ async fn route_unstable_users_get(
 
     let row = db
         .query_opt(
-            "SELECT username, local, ap_id, description, description_html, avatar, suspended, is_bot, description_markdown FROM person WHERE id=$1",
+            "SELECT username, local, ap_id, description, description_html, avatar, suspended, is_bot, description_markdown, display_name, profile_fields, created_local, (SELECT COUNT(*) FROM post WHERE author=person.id AND NOT deleted), (SELECT COUNT(*) FROM reply WHERE author=person.id AND NOT deleted), moved_to FROM person WHERE id=$1",
             &[&user_id],
         )
         .await?;
 
     let row = row.ok_or_else(|| {
-        crate::Error::UserError(crate::simple_response(
-            hyper::StatusCode::NOT_FOUND,
-            lang.tr(&lang::no_such_user()).into_owned(),
-        ))
+        crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_user())
     })?;
 
     let local = row.get(1);
@@ -1000,6 +1916,19 @@ async fn route_unstable_users_get(
             content_markdown: description_markdown.map(Cow::Borrowed),
             content_html_safe: description_html.map(|x| crate::clean_html(x)),
         },
+        display_name: row.get::<_, Option<&str>>(9).map(Cow::Borrowed),
+        profile_fields: row.get::<_, postgres_types::Json<Vec<ProfileField>>>(10).0,
+        post_count: row.get(12),
+        comment_count: row.get(13),
+        moved_to: row.get::<_, Option<&str>>(14).map(Cow::Borrowed),
+        created: if local {
+            Some(
+                row.get::<_, chrono::DateTime<chrono::FixedOffset>>(11)
+                    .to_rfc3339(),
+            )
+        } else {
+            None
+        },
         suspended: if local { Some(row.get(6)) } else { None },
         your_note,
     };
@@ -1019,7 +1948,7 @@ async fn route_unstable_users_your_note_put(
 
     let target_user = target_user.resolve(login_user);
 
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
     let body: JustContentText = serde_json::from_slice(&body)?;
 
     db.execute(
@@ -1037,6 +1966,7 @@ async fn route_unstable_users_things_list(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
 
+    let lang = crate::get_lang_for_req(&req);
     let db = ctx.db_pool.get().await?;
 
     let user_id = user_id.try_resolve(&req, &db).await?;
@@ -1045,78 +1975,160 @@ async fn route_unstable_users_things_list(
         30
     }
 
+    #[derive(Deserialize, Clone, Copy)]
+    #[serde(rename_all = "snake_case")]
+    enum UserThingsType {
+        Posts,
+        Comments,
+    }
+
+    #[derive(Deserialize, Clone, Copy)]
+    #[serde(rename_all = "snake_case")]
+    enum UserThingsSortType {
+        New,
+        Top,
+    }
+
+    impl Default for UserThingsSortType {
+        fn default() -> Self {
+            Self::New
+        }
+    }
+
     #[derive(Deserialize)]
     struct UserThingsListQuery<'a> {
         #[serde(default = "default_limit")]
         limit: u8,
 
         page: Option<Cow<'a, str>>,
+
+        #[serde(rename = "type")]
+        type_: Option<UserThingsType>,
+
+        #[serde(default)]
+        sort: UserThingsSortType,
     }
     let query: UserThingsListQuery = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
 
     let limit_plus_1: i64 = (query.limit + 1).into();
 
-    let page: Option<(chrono::DateTime<chrono::offset::FixedOffset>, bool, i64)> = query
-        .page
-        .map(|src| {
-            let mut spl = src.split(',');
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&user_id, &limit_plus_1];
 
-            let ts = spl.next().ok_or(InvalidPage)?;
-            let is_post = spl.next().ok_or(InvalidPage)?;
-            let id = spl.next().ok_or(InvalidPage)?;
-            if spl.next().is_some() {
-                Err(InvalidPage)
-            } else {
-                use chrono::TimeZone;
+    let page_new: Option<(chrono::DateTime<chrono::offset::FixedOffset>, bool, i64)>;
+    let page_offset: Option<i64>;
+
+    let (page_conditions, page_offset_sql) = match query.sort {
+        UserThingsSortType::New => {
+            page_offset = None;
+            page_new = query
+                .page
+                .as_deref()
+                .map(|src| {
+                    let mut spl = src.split(',');
+
+                    let ts = spl.next().ok_or(InvalidPage)?;
+                    let is_post = spl.next().ok_or(InvalidPage)?;
+                    let id = spl.next().ok_or(InvalidPage)?;
+                    if spl.next().is_some() {
+                        Err(InvalidPage)
+                    } else {
+                        use chrono::TimeZone;
 
-                let ts: i64 = ts.parse().map_err(|_| InvalidPage)?;
-                let is_post: bool = is_post.parse().map_err(|_| InvalidPage)?;
-                let id: i64 = id.parse().map_err(|_| InvalidPage)?;
+                        let ts: i64 = ts.parse().map_err(|_| InvalidPage)?;
+                        let is_post: bool = is_post.parse().map_err(|_| InvalidPage)?;
+                        let id: i64 = id.parse().map_err(|_| InvalidPage)?;
 
-                let ts = chrono::offset::Utc.timestamp_nanos(ts);
+                        let ts = chrono::offset::Utc.timestamp_nanos(ts);
 
-                Ok((ts.into(), is_post, id))
+                        Ok((ts.into(), is_post, id))
+                    }
+                })
+                .transpose()
+                .map_err(|err| err.into_user_error(&lang))?;
+
+            match &page_new {
+                Some((ts, is_post, id)) => {
+                    values.push(ts);
+                    values.push(id);
+
+                    // posts sort after comments with the same timestamp
+                    (
+                        Cow::Owned(format!(
+                            " WHERE (created < $3 OR (created = $3 AND {}))",
+                            if *is_post {
+                                "is_post AND thing_id <= $4"
+                            } else {
+                                "(is_post OR thing_id <= $4)"
+                            }
+                        )),
+                        Cow::Borrowed(""),
+                    )
+                }
+                None => (Cow::Borrowed(""), Cow::Borrowed("")),
             }
-        })
-        .transpose()
-        .map_err(|err| err.into_user_error())?;
-
-    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&user_id, &limit_plus_1];
-
-    let page_conditions = match &page {
-        Some((ts, is_post, id)) => {
-            values.push(ts);
-            values.push(id);
-
-            Cow::Owned(format!(
-                " AND (created < $3 OR (created = $3 AND {}))",
-                if *is_post {
-                    "is_post AND id > $4"
-                } else {
-                    "is_post OR id > $4"
+        }
+        UserThingsSortType::Top => {
+            page_new = None;
+            page_offset = query
+                .page
+                .as_deref()
+                .map(|src| parse_number_58(src).map_err(|_| InvalidPage))
+                .transpose()
+                .map_err(|err| err.into_user_error(&lang))?;
+
+            match &page_offset {
+                Some(offset) => {
+                    values.push(offset);
+                    (Cow::Borrowed(""), Cow::Borrowed(" OFFSET $3"))
                 }
-            ))
+                None => (Cow::Borrowed(""), Cow::Borrowed("")),
+            }
         }
-        None => Cow::Borrowed(""),
+    };
+
+    const POSTS_SQL: &str = "(SELECT TRUE AS is_post, post.id AS thing_id, post.href, post.title, post.created, community.id, community.name, community.local, community.ap_id, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM post_like WHERE post_like.post = post.id) AS score, (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, post.ap_id, post.local, post.content_html, post.content_text, post.content_markdown, community.deleted, post.sensitive, community.hide_scores_minutes FROM post, community WHERE post.community = community.id AND post.author = $1 AND NOT post.deleted AND NOT community.private)";
+    const COMMENTS_SQL: &str = "(SELECT FALSE AS is_post, reply.id AS thing_id, reply.content_text, reply.content_html, reply.created, post.id, post.title, NULL, reply.ap_id, (SELECT COUNT(*) FILTER (WHERE NOT is_dislike) - COUNT(*) FILTER (WHERE is_dislike) FROM reply_like WHERE reply_like.reply = reply.id) AS score, NULL, reply.local, post.ap_id, post.local, NULL, NULL, NULL, reply.sensitive, post.sensitive, (SELECT hide_scores_minutes FROM community WHERE id=post.community) FROM reply, post WHERE post.id = reply.post AND reply.author = $1 AND NOT reply.deleted AND NOT reply.pending_approval AND NOT (SELECT private FROM community WHERE id=post.community))";
+
+    let things_sql: Cow<'static, str> = match query.type_ {
+        None => Cow::Owned(format!("{} UNION ALL {}", POSTS_SQL, COMMENTS_SQL)),
+        Some(UserThingsType::Posts) => Cow::Borrowed(POSTS_SQL),
+        Some(UserThingsType::Comments) => Cow::Borrowed(COMMENTS_SQL),
     };
 
     let sql: &str = &format!(
-        "(SELECT TRUE AS is_post, post.id AS thing_id, post.href, post.title, post.created, community.id, community.name, community.local, community.ap_id, (SELECT COUNT(*) FROM post_like WHERE post_like.post = post.id), (SELECT COUNT(*) FROM reply WHERE reply.post = post.id), post.sticky, post.ap_id, post.local, post.content_html, post.content_text, post.content_markdown, community.deleted, post.sensitive FROM post, community WHERE post.community = community.id AND post.author = $1 AND NOT post.deleted) UNION ALL (SELECT FALSE AS is_post, reply.id AS thing_id, reply.content_text, reply.content_html, reply.created, post.id, post.title, NULL, reply.ap_id, NULL, NULL, reply.local, post.ap_id, post.local, NULL, NULL, NULL, reply.sensitive, post.sensitive FROM reply, post WHERE post.id = reply.post AND reply.author = $1 AND NOT reply.deleted){} ORDER BY created DESC, is_post ASC, thing_id DESC LIMIT $2",
+        "SELECT * FROM ({}) AS thing{} ORDER BY {}created DESC, is_post ASC, thing_id DESC LIMIT $2{}",
+        things_sql,
         page_conditions,
+        match query.sort {
+            UserThingsSortType::New => "",
+            UserThingsSortType::Top => "score DESC, ",
+        },
+        page_offset_sql,
     );
 
-    let mut rows = db.query(sql, &values).await?;
+    let mut rows = crate::query::query(&db, sql, &values).await?;
 
     let next_page = if rows.len() > query.limit as usize {
-        let row = rows.pop().unwrap();
+        match query.sort {
+            UserThingsSortType::New => {
+                let row = rows.pop().unwrap();
 
-        let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(4);
-        let ts = ts.timestamp_nanos();
+                let ts: chrono::DateTime<chrono::offset::FixedOffset> = row.get(4);
+                let ts = ts.timestamp_nanos();
 
-        let is_post: bool = row.get(0);
-        let id: i64 = row.get(1);
+                let is_post: bool = row.get(0);
+                let id: i64 = row.get(1);
 
-        Some(format!("{},{},{}", ts, is_post, id))
+                Some(format!("{},{},{}", ts, is_post, id))
+            }
+            UserThingsSortType::Top => {
+                rows.pop();
+
+                Some(format_number_58(
+                    i64::from(query.limit) + page_offset.unwrap_or(0),
+                ))
+            }
+        }
     } else {
         None
     };
@@ -1126,27 +2138,14 @@ async fn route_unstable_users_things_list(
     let things: Vec<RespThingInfo> = rows
         .iter()
         .map(|row| {
-            let created: chrono::DateTime<chrono::FixedOffset> = row.get(4);
-            let created = created.to_rfc3339();
+            let created_ts: chrono::DateTime<chrono::FixedOffset> = row.get(4);
+            let created = created_ts.to_rfc3339();
 
             let post_ap_id: Option<&str> = row.get(12);
             let post_local: bool = row.get(13);
 
             if row.get(0) {
-                let community_id = CommunityLocalID(row.get(5));
-                let community_local = row.get(7);
-                let community_ap_id: Option<_> = row.get(8);
-
-                let community_remote_url = if community_local {
-                    Some(Cow::Owned(String::from(
-                        crate::apub_util::LocalObjectRef::Community(community_id)
-                            .to_local_uri(&ctx.host_url_apub),
-                    )))
-                } else {
-                    community_ap_id.map(Cow::Borrowed)
-                };
-
-                let post_id = PostLocalID(row.get(1));
+                let post_id: PostLocalID = row.get(1);
 
                 let post_remote_url = if post_local {
                     Some(Cow::Owned(String::from(
@@ -1165,23 +2164,21 @@ async fn route_unstable_users_things_list(
                     ),
                     title: Cow::Borrowed(row.get(3)),
                     created: Cow::Owned(created),
-                    community: Cow::Owned(RespMinimalCommunityInfo {
-                        id: community_id,
-                        name: Cow::Borrowed(row.get(6)),
-                        local: community_local,
-                        host: crate::get_actor_host_or_unknown(
-                            community_local,
-                            community_ap_id,
-                            &ctx.local_hostname,
-                        ),
-                        remote_url: community_remote_url,
-                        deleted: row.get(17),
-                    }),
+                    community: Cow::Owned(
+                        super::CommunityColumns {
+                            id: 5,
+                            name: 6,
+                            local: 7,
+                            ap_id: 8,
+                            deleted: 17,
+                        }
+                        .get(row, &ctx),
+                    ),
                     relevance: None,
                     remote_url: post_remote_url,
                     replies_count_total: row.get(10),
                     sticky: row.get(11),
-                    score: row.get(9),
+                    score: super::get_resp_score(row.get(9), &created_ts, row.get(19)),
                     content_html_safe: row
                         .get::<_, Option<&str>>(14)
                         .map(|html| crate::clean_html(&html)),
@@ -1190,9 +2187,11 @@ async fn route_unstable_users_things_list(
                     sensitive: row.get(18),
                     author: None,
                     your_vote: None,
+                    flairs: None,
+                    thumbnail: None,
                 })
             } else {
-                let post_id = PostLocalID(row.get(5));
+                let post_id: PostLocalID = row.get(5);
 
                 let post_remote_url = if post_local {
                     Some(Cow::Owned(String::from(
@@ -1203,7 +2202,7 @@ async fn route_unstable_users_things_list(
                     post_ap_id.map(Cow::Borrowed)
                 };
 
-                let comment_id = CommentLocalID(row.get(1));
+                let comment_id: CommentLocalID = row.get(1);
                 let comment_ap_id: Option<&str> = row.get(8);
                 let comment_local: bool = row.get(11);
 
@@ -1226,6 +2225,7 @@ async fn route_unstable_users_things_list(
                             .map(|html| crate::clean_html(html)),
                         sensitive: row.get(17),
                     },
+                    score: super::get_resp_score(row.get(9), &created_ts, row.get(19)),
                     created,
                     post: RespMinimalPostInfo {
                         id: post_id,
@@ -1252,11 +2252,54 @@ pub fn route_users() -> crate::RouteNode<()> {
             crate::RouteNode::new()
                 .with_handler_async(hyper::Method::GET, route_unstable_users_get)
                 .with_handler_async(hyper::Method::PATCH, route_unstable_users_patch)
+                .with_handler_async(hyper::Method::DELETE, route_unstable_users_delete)
                 .with_child(
-                    "notifications",
+                    "following",
                     crate::RouteNode::new().with_handler_async(
                         hyper::Method::GET,
-                        route_unstable_users_notifications_list,
+                        route_unstable_users_following_list,
+                    ),
+                )
+                .with_child(
+                    "insights",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::GET, route_unstable_users_insights_get),
+                )
+                .with_child(
+                    "logins",
+                    crate::RouteNode::new().with_child(
+                        "history",
+                        crate::RouteNode::new().with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_users_logins_history_list,
+                        ),
+                    ),
+                )
+                .with_child(
+                    "move",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_users_move),
+                )
+                .with_child(
+                    "notifications",
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_users_notifications_list,
+                        )
+                        .with_child_parse::<NotificationID, _>(crate::RouteNode::new().with_child(
+                            "read",
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::PUT,
+                                route_unstable_users_notifications_read_put,
+                            ),
+                        )),
+                )
+                .with_child(
+                    "notifications:mark_read",
+                    crate::RouteNode::new().with_handler_async(
+                        hyper::Method::POST,
+                        route_unstable_users_notifications_mark_read,
                     ),
                 )
                 .with_child(
@@ -1266,6 +2309,18 @@ pub fn route_users() -> crate::RouteNode<()> {
                         route_unstable_users_notifications_subscriptions_create,
                     ),
                 )
+                .with_child(
+                    "preferences",
+                    crate::RouteNode::new()
+                        .with_handler_async(
+                            hyper::Method::GET,
+                            route_unstable_users_preferences_get,
+                        )
+                        .with_handler_async(
+                            hyper::Method::PATCH,
+                            route_unstable_users_preferences_patch,
+                        ),
+                )
                 .with_child(
                     "things",
                     crate::RouteNode::new()
@@ -0,0 +1,224 @@
+use crate::lang;
+use crate::types::{RespList, RespWebhookInfo, WebhookEvent, WebhookID};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+fn validate_webhook_url(url: &str, lang: &crate::Translator) -> Result<(), crate::Error> {
+    let valid = match url::Url::parse(url) {
+        Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+        Err(_) => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(crate::user_error(
+            hyper::StatusCode::BAD_REQUEST,
+            lang,
+            &lang::webhook_url_invalid(),
+        ))
+    }
+}
+
+fn events_to_sql(events: &[WebhookEvent]) -> Vec<&'static str> {
+    let mut result: Vec<_> = events.iter().map(|event| event.as_str()).collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+fn no_such_webhook(lang: &crate::Translator) -> crate::Error {
+    crate::user_error(hyper::StatusCode::NOT_FOUND, lang, &lang::no_such_webhook())
+}
+
+async fn route_unstable_webhooks_list(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let rows = db
+        .query(
+            "SELECT id, url, events, created_at FROM webhook ORDER BY id ASC",
+            &[],
+        )
+        .await?;
+
+    let items: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            let created_at: chrono::DateTime<chrono::FixedOffset> = row.get(3);
+
+            RespWebhookInfo {
+                id: row.get(0),
+                url: Cow::Borrowed(row.get(1)),
+                events: row
+                    .get::<_, Vec<&str>>(2)
+                    .into_iter()
+                    .filter_map(|event| event.parse().ok())
+                    .collect(),
+                created_at: created_at.to_rfc3339(),
+            }
+        })
+        .collect();
+
+    crate::json_response(&RespList {
+        items: Cow::Owned(items),
+        next_page: None,
+    })
+}
+
+async fn route_unstable_webhooks_create(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    #[derive(Deserialize)]
+    struct WebhooksCreateBody<'a> {
+        url: Cow<'a, str>,
+        events: Vec<WebhookEvent>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let user = super::require_site_admin(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: WebhooksCreateBody = serde_json::from_slice(&body)?;
+
+    validate_webhook_url(&body.url, &lang)?;
+
+    let secret = {
+        use rand::Rng;
+
+        let bytes: [u8; 32] = rand::thread_rng().gen();
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    };
+
+    let row = db.query_one(
+        "INSERT INTO webhook (url, secret, events, created_by, created_at) VALUES ($1, $2, $3, $4, current_timestamp) RETURNING id",
+        &[&body.url, &secret, &events_to_sql(&body.events), &user],
+    ).await?;
+
+    let id: WebhookID = row.get(0);
+
+    crate::json_response(&serde_json::json!({ "id": id, "secret": secret }))
+}
+
+async fn route_unstable_webhooks_edit(
+    params: (WebhookID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (webhook_id,) = params;
+
+    #[derive(Deserialize)]
+    struct WebhooksEditBody<'a> {
+        url: Option<Cow<'a, str>>,
+        events: Option<Vec<WebhookEvent>>,
+    }
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let body = crate::to_bytes_limited(req.into_body(), ctx.max_request_body_size).await?;
+    let body: WebhooksEditBody = serde_json::from_slice(&body)?;
+
+    if let Some(url) = &body.url {
+        validate_webhook_url(url, &lang)?;
+    }
+
+    let events = body.events.as_deref().map(events_to_sql);
+
+    let row_count = db
+        .execute(
+            "UPDATE webhook SET url=COALESCE($2, url), events=COALESCE($3, events) WHERE id=$1",
+            &[&webhook_id, &body.url, &events],
+        )
+        .await?;
+
+    if row_count == 0 {
+        return Err(no_such_webhook(&lang));
+    }
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_webhooks_delete(
+    params: (WebhookID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (webhook_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    db.execute("DELETE FROM webhook WHERE id=$1", &[&webhook_id])
+        .await?;
+
+    Ok(crate::empty_response())
+}
+
+async fn route_unstable_webhooks_test(
+    params: (WebhookID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (webhook_id,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    super::require_site_admin(&req, &db).await?;
+
+    let row = db
+        .query_opt(
+            "SELECT url_host_key(url) FROM webhook WHERE id=$1",
+            &[&webhook_id],
+        )
+        .await?;
+    let destination_host: Option<String> = match row {
+        Some(row) => row.get(0),
+        None => return Err(no_such_webhook(&lang)),
+    };
+
+    let body = serde_json::to_string(&serde_json::json!({
+        "event": "test",
+        "created_at": chrono::Utc::now().to_rfc3339(),
+        "data": {},
+    }))?;
+
+    ctx.enqueue_task(&crate::tasks::DeliverToWebhook {
+        webhook: webhook_id,
+        body,
+        destination_host,
+    })
+    .await?;
+
+    Ok(crate::simple_response(hyper::StatusCode::ACCEPTED, ""))
+}
+
+pub fn route_webhooks() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_handler_async(hyper::Method::GET, route_unstable_webhooks_list)
+        .with_handler_async(hyper::Method::POST, route_unstable_webhooks_create)
+        .with_child_parse::<WebhookID, _>(
+            crate::RouteNode::new()
+                .with_handler_async(hyper::Method::PATCH, route_unstable_webhooks_edit)
+                .with_handler_async(hyper::Method::DELETE, route_unstable_webhooks_delete)
+                .with_child(
+                    "test",
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::POST, route_unstable_webhooks_test),
+                ),
+        )
+}
@@ -1,3 +1,4 @@
+use crate::lang;
 use crate::{CommentLocalID, CommunityLocalID, PostLocalID, UserLocalID};
 use activitystreams::prelude::*;
 use std::ops::Deref;
@@ -62,14 +63,57 @@ pub fn route_communities() -> crate::RouteNode<()> {
                                     hyper::Method::GET,
                                     handler_communities_followers_join_get,
                                 ),
+                            )
+                            .with_child(
+                                "reject",
+                                crate::RouteNode::new().with_handler_async(
+                                    hyper::Method::GET,
+                                    handler_communities_followers_reject_get,
+                                ),
                             ),
                     ),
             )
+            .with_child(
+                "followers_synchronization",
+                crate::RouteNode::new().with_handler_async(
+                    hyper::Method::GET,
+                    handler_communities_followers_synchronization_get,
+                ),
+            )
             .with_child(
                 "inbox",
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::POST, handler_communities_inbox_post),
             )
+            .with_child(
+                "moderators",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, handler_communities_moderators_list),
+            )
+            .with_child(
+                "modlog",
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, handler_communities_modlog_get)
+                    .with_child(
+                        "events",
+                        crate::RouteNode::new().with_child_parse::<i64, _>(
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::GET,
+                                handler_communities_modlog_events_get,
+                            ),
+                        ),
+                    )
+                    .with_child(
+                        "page",
+                        crate::RouteNode::new()
+                            .with_child_parse::<crate::TimestampAndIDOrLatest, _>(
+                                crate::RouteNode::new().with_handler_async(
+                                    hyper::Method::GET,
+                                    handler_communities_modlog_page_get,
+                                ),
+                            ),
+                    ),
+            )
             .with_child(
                 "outbox",
                 crate::RouteNode::new()
@@ -137,14 +181,23 @@ pub fn route_communities() -> crate::RouteNode<()> {
 async fn handler_communities_get(
     params: (CommunityLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id,) = params;
     let db = ctx.db_pool.get().await?;
 
+    if super::html::wants_html(&req) {
+        return super::html::html_response(
+            super::html::HtmlTarget::Community(community_id),
+            &db,
+            &ctx,
+        )
+        .await;
+    }
+
     match db
         .query_opt(
-            "SELECT name, local, public_key, description, description_html, deleted FROM community WHERE id=$1",
+            "SELECT name, local, public_key, description, description_html, deleted, modified_at FROM community WHERE id=$1",
             &[&community_id],
         )
         .await?
@@ -170,18 +223,12 @@ async fn handler_communities_get(
 
                 let mut info = activitystreams::object::Tombstone::new();
                 info.set_former_type("Group".to_owned())
-                    .set_context(activitystreams::context())
+                    .set_many_contexts(crate::apub_util::default_contexts())
                     .set_id(community_ap_id.into());
 
                 let body = serde_json::to_vec(&info)?;
 
-                let mut resp = hyper::Response::new(body.into());
-                resp.headers_mut().insert(
-                    hyper::header::CONTENT_TYPE,
-                    hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-                );
-
-                Ok(resp)
+                Ok(crate::apub_util::activity_response(body))
             } else {
                 let name: String = row.get(0);
                 let public_key =
@@ -226,6 +273,9 @@ async fn handler_communities_get(
                 .set_followers(crate::apub_util::LocalObjectRef::CommunityFollowers(community_id).to_local_uri(&ctx.host_url_apub).into())
                 .set_preferred_username(name);
 
+                // Lemmy reads the moderator list from this
+                info.set_attributed_to(crate::apub_util::LocalObjectRef::CommunityModerators(community_id).to_local_uri(&ctx.host_url_apub));
+
                 let featured_ext = crate::apub_util::FeaturedExtension {
                     featured: Some(crate::apub_util::LocalObjectRef::CommunityFeatured(community_id).to_local_uri(&ctx.host_url_apub).into()),
                 };
@@ -237,30 +287,24 @@ async fn handler_communities_get(
                     ctx.host_url_apub, community_id
                 );
 
-                let body = if let Some(public_key) = public_key {
-                    let public_key_ext = crate::apub_util::PublicKeyExtension {
-                        public_key: Some(crate::apub_util::PublicKey {
-                            id: (&key_id).into(),
-                            owner: community_ap_id.as_str().into(),
-                            public_key_pem: public_key.into(),
-                            signature_algorithm: Some(crate::apub_util::SIGALG_RSA_SHA256.into()),
-                        }),
-                    };
-
-                    let info = activitystreams_ext::Ext1::new(info, public_key_ext);
-
-                    serde_json::to_vec(&info)
-                } else {
-                    serde_json::to_vec(&info)
-                }?;
+                crate::apub_util::cached_activity_response(&req, Some(row.get(6)), false, || {
+                    Ok(if let Some(public_key) = public_key {
+                        let public_key_ext = crate::apub_util::PublicKeyExtension {
+                            public_key: Some(crate::apub_util::PublicKey {
+                                id: (&key_id).into(),
+                                owner: community_ap_id.as_str().into(),
+                                public_key_pem: public_key.into(),
+                                signature_algorithm: Some(crate::apub_util::SIGALG_RSA_SHA256.into()),
+                            }),
+                        };
 
-                let mut resp = hyper::Response::new(body.into());
-                resp.headers_mut().insert(
-                    hyper::header::CONTENT_TYPE,
-                    hyper::header::HeaderValue::from_static("application/activity+json"),
-                );
+                        let info = activitystreams_ext::Ext1::new(info, public_key_ext);
 
-                Ok(resp)
+                        serde_json::to_vec(&info)
+                    } else {
+                        serde_json::to_vec(&info)
+                    }?)
+                })
             }
         }
     }
@@ -269,14 +313,14 @@ async fn handler_communities_get(
 async fn handler_communities_comments_announce_get(
     params: (CommunityLocalID, CommentLocalID),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, comment_id) = params;
     let db = ctx.db_pool.get().await?;
 
     match db
         .query_opt(
-            "SELECT reply.id, reply.local, reply.ap_id, community.local FROM reply, post, community WHERE reply.post = post.id AND post.community = community.id AND reply.id = $1 AND community.id = $2",
+            "SELECT reply.id, reply.local, reply.ap_id, community.local, community.modified_at FROM reply, post, community WHERE reply.post = post.id AND post.community = community.id AND reply.id = $1 AND community.id = $2",
             &[&comment_id, &community_id],
         )
         .await?
@@ -294,6 +338,8 @@ async fn handler_communities_comments_announce_get(
                 )));
             }
 
+            let private = crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
             let comment_local_id = CommentLocalID(row.get(0));
             let comment_ap_id = if row.get(1) {
                 crate::apub_util::LocalObjectRef::Comment(comment_local_id).to_local_uri(&ctx.host_url_apub)
@@ -301,12 +347,10 @@ async fn handler_communities_comments_announce_get(
                 std::str::FromStr::from_str(row.get(2))?
             };
 
-            let body = crate::apub_util::local_community_comment_announce_ap(community_id, comment_local_id, comment_ap_id.into(), &ctx.host_url_apub)?;
-            let body = serde_json::to_vec(&body)?;
-
-            Ok(hyper::Response::builder()
-               .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-               .body(body.into())?)
+            crate::apub_util::cached_activity_response(&req, Some(row.get(4)), private, || {
+                let body = crate::apub_util::local_community_comment_announce_ap(community_id, comment_local_id, comment_ap_id.into(), private, &ctx.host_url_apub)?;
+                Ok(serde_json::to_vec(&body)?)
+            })
         }
     }
 }
@@ -340,11 +384,9 @@ async fn handler_communities_delete_get(
                         &ctx.host_url_apub,
                     );
 
-                    let body = serde_json::to_vec(&info)?.into();
+                    let body = serde_json::to_vec(&info)?;
 
-                    Ok(hyper::Response::builder()
-                        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                        .body(body)?)
+                    Ok(crate::apub_util::activity_response(body))
                 } else {
                     Ok(crate::simple_response(
                         hyper::StatusCode::NOT_FOUND,
@@ -364,11 +406,13 @@ async fn handler_communities_delete_get(
 async fn handler_communities_featured_list(
     params: (CommunityLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id,) = params;
     let db = ctx.db_pool.get().await?;
 
+    crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
     let rows = db
         .query(
             "SELECT id, local, ap_id FROM post WHERE community=$1 AND sticky ORDER BY created",
@@ -400,15 +444,13 @@ async fn handler_communities_featured_list(
             .to_local_uri(&ctx.host_url_apub)
             .into(),
     );
-    body.set_context(activitystreams::context());
+    body.set_many_contexts(crate::apub_util::default_contexts());
     body.set_total_items(items.len() as u64);
     body.set_many_ordered_items(items);
 
     let body = serde_json::to_vec(&body)?;
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body.into())?)
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_communities_followers_list(
@@ -421,7 +463,7 @@ async fn handler_communities_followers_list(
 
     let row = db
         .query_one(
-            "SELECT COUNT(*) FROM community_follow WHERE community=$1",
+            "SELECT COUNT(*) FROM community_follow WHERE community=$1 AND accepted",
             &[&community_id],
         )
         .await?;
@@ -430,12 +472,67 @@ async fn handler_communities_followers_list(
     let body = serde_json::to_vec(&serde_json::json!({
         "type": "Collection",
         "totalItems": count,
-    }))?
-    .into();
+    }))?;
+
+    Ok(crate::apub_util::activity_response(body))
+}
+
+async fn handler_communities_followers_synchronization_get(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    let db = ctx.db_pool.get().await?;
+
+    let row = db
+        .query_opt(
+            "SELECT local FROM community WHERE id=$1 AND NOT deleted",
+            &[&community_id],
+        )
+        .await?;
+    match row {
+        None => {
+            return Ok(crate::simple_response(
+                hyper::StatusCode::NOT_FOUND,
+                "No such community",
+            ))
+        }
+        Some(row) => {
+            if !row.get::<_, bool>(0) {
+                return Err(crate::Error::UserError(crate::simple_response(
+                    hyper::StatusCode::BAD_REQUEST,
+                    "Requested community is not owned by this instance",
+                )));
+            }
+        }
+    }
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body)?)
+    // only the followers on the requesting instance are listed
+    let actor_ap_id = crate::apub_util::verify_fetch_signature(&req, &db, &ctx)
+        .await?
+        .ok_or_else(|| {
+            crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::UNAUTHORIZED,
+                "A valid signature is required",
+            ))
+        })?;
+    let host = crate::apub_util::compat::get_url_host_key(&actor_ap_id).ok_or(
+        crate::Error::InternalStrStatic("Signing actor ID is missing a host"),
+    )?;
+
+    let items =
+        crate::apub_util::get_local_community_followers_on_host(community_id, host, &db).await?;
+
+    let body = serde_json::to_vec(&serde_json::json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "type": "OrderedCollection",
+        "id": crate::apub_util::get_local_community_followers_sync_url(community_id, &ctx.host_url_apub).as_str(),
+        "totalItems": items.len(),
+        "orderedItems": items,
+    }))?;
+
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_communities_followers_get(
@@ -449,7 +546,7 @@ async fn handler_communities_followers_get(
 
     let row = db.query_opt(
         "SELECT person.local, community.local, community.ap_id FROM community_follow, community, person WHERE community.id=$1 AND community.id = community_follow.community AND person.id = community_follow.follower AND person.id = $2",
-        &[&community_id.raw(), &user_id.raw()],
+        &[&community_id, &user_id],
     ).await?;
     match row {
         None => Ok(crate::simple_response(
@@ -487,7 +584,7 @@ async fn handler_communities_followers_get(
                 activitystreams::activity::Follow::new(person_ap_id, community_ap_id.clone());
 
             follow
-                .set_context(activitystreams::context())
+                .set_many_contexts(crate::apub_util::default_contexts())
                 .set_id({
                     let mut res = crate::apub_util::LocalObjectRef::Community(community_id)
                         .to_local_uri(&ctx.host_url_apub);
@@ -497,11 +594,9 @@ async fn handler_communities_followers_get(
                 })
                 .set_to(community_ap_id);
 
-            let body = serde_json::to_vec(&follow)?.into();
+            let body = serde_json::to_vec(&follow)?;
 
-            Ok(hyper::Response::builder()
-                .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                .body(body)?)
+            Ok(crate::apub_util::activity_response(body))
         }
     }
 }
@@ -517,7 +612,7 @@ async fn handler_communities_followers_join_get(
 
     let row = db.query_opt(
         "SELECT person.local, community.local, community.ap_id FROM community_follow, community, person WHERE community.id=$1 AND community.id = community_follow.community AND person.id = community_follow.follower AND person.id = $2",
-        &[&community_id.raw(), &user_id.raw()],
+        &[&community_id, &user_id],
     ).await?;
     match row {
         None => Ok(crate::simple_response(
@@ -555,7 +650,7 @@ async fn handler_communities_followers_join_get(
                 activitystreams::activity::Join::new(person_ap_id, community_ap_id.clone());
 
             follow
-                .set_context(activitystreams::context())
+                .set_many_contexts(crate::apub_util::default_contexts())
                 .set_id({
                     let mut res = crate::apub_util::LocalObjectRef::Community(community_id)
                         .to_local_uri(&ctx.host_url_apub);
@@ -565,11 +660,9 @@ async fn handler_communities_followers_join_get(
                 })
                 .set_to(community_ap_id);
 
-            let body = serde_json::to_vec(&follow)?.into();
+            let body = serde_json::to_vec(&follow)?;
 
-            Ok(hyper::Response::builder()
-                .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                .body(body)?)
+            Ok(crate::apub_util::activity_response(body))
         }
     }
 }
@@ -583,7 +676,7 @@ async fn handler_communities_followers_accept_get(
     let db = ctx.db_pool.get().await?;
 
     let row = db.query_opt(
-        "SELECT community.local, community_follow.ap_id, person.id, person.local, person.ap_id FROM community_follow, community, person WHERE community_follow.community = community.id AND community_follow.follower = person.id AND community.id = $1 AND person.id = $2",
+        "SELECT community.local, community_follow.ap_id, person.id, person.local, person.ap_id FROM community_follow, community, person WHERE community_follow.community = community.id AND community_follow.follower = person.id AND community.id = $1 AND person.id = $2 AND community_follow.accepted",
         &[&community_id, &user_id],
     ).await?;
 
@@ -642,13 +735,68 @@ async fn handler_communities_followers_accept_get(
                     .to_local_uri(&ctx.host_url_apub),
                 user_id,
                 follower_ap_id,
-                follow_ap_id.into(),
+                activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id),
+            )?;
+            let body = serde_json::to_vec(&body)?;
+
+            Ok(crate::apub_util::activity_response(body))
+        }
+    }
+}
+
+async fn handler_communities_followers_reject_get(
+    params: (CommunityLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, user_id) = params;
+    let db = ctx.db_pool.get().await?;
+
+    let row = db.query_opt(
+        "SELECT community.local, community_follow.ap_id, person.ap_id FROM community_follow, community, person WHERE community_follow.community = community.id AND community_follow.follower = person.id AND community.id = $1 AND person.id = $2 AND community_follow.state = 'rejected' AND NOT person.local",
+        &[&community_id, &user_id],
+    ).await?;
+
+    match row {
+        None => Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such follow",
+        )),
+        Some(row) => {
+            let community_local: bool = row.get(0);
+            if !community_local {
+                return Err(crate::Error::UserError(crate::simple_response(
+                    hyper::StatusCode::BAD_REQUEST,
+                    "Requested community is not owned by this instance",
+                )));
+            }
+
+            let follow_ap_id: Option<&str> = row.get(1);
+            let follower_ap_id: Option<&str> = row.get(2);
+
+            let (follow_ap_id, follower_ap_id): (url::Url, url::Url) =
+                match (follow_ap_id, follower_ap_id) {
+                    (Some(follow_ap_id), Some(follower_ap_id)) => {
+                        (follow_ap_id.parse()?, follower_ap_id.parse()?)
+                    }
+                    _ => {
+                        return Err(crate::Error::InternalStr(format!(
+                            "Missing ap_id for follow ({} / {})",
+                            community_id, user_id
+                        )))
+                    }
+                };
+
+            let body = crate::apub_util::community_follow_reject_to_ap(
+                community_id,
+                user_id,
+                follower_ap_id,
+                activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id),
+                &ctx.host_url_apub,
             )?;
-            let body = serde_json::to_vec(&body)?.into();
+            let body = serde_json::to_vec(&body)?;
 
-            Ok(hyper::Response::builder()
-                .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                .body(body)?)
+            Ok(crate::apub_util::activity_response(body))
         }
     }
 }
@@ -661,6 +809,314 @@ async fn handler_communities_inbox_post(
     super::inbox_common(ctx, req).await
 }
 
+async fn get_local_community_modlog_reasons_public(
+    community_id: CommunityLocalID,
+    db: &tokio_postgres::Client,
+) -> Result<bool, crate::Error> {
+    let row = db
+        .query_opt(
+            "SELECT local, modlog_reasons_public FROM community WHERE id=$1",
+            &[&community_id],
+        )
+        .await?;
+    match row {
+        None => Err(crate::Error::UserError(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such community",
+        ))),
+        Some(row) => {
+            let local: bool = row.get(0);
+            if local {
+                Ok(row.get(1))
+            } else {
+                Err(crate::Error::UserError(crate::simple_response(
+                    hyper::StatusCode::BAD_REQUEST,
+                    "Requested community is not owned by this instance",
+                )))
+            }
+        }
+    }
+}
+
+const MODLOG_EVENT_COLUMNS: &str = "modlog_event.id, modlog_event.time, modlog_event.action, modlog_event.reason, post.id, post.local, post.ap_id, reply.id, reply.local, reply.ap_id, person.id, person.local, person.ap_id, modlog_event.domain FROM modlog_event LEFT OUTER JOIN post ON (post.id = modlog_event.post) LEFT OUTER JOIN reply ON (reply.id = modlog_event.reply) LEFT OUTER JOIN person ON (person.id = modlog_event.person)";
+
+// expects the columns from MODLOG_EVENT_COLUMNS
+fn modlog_event_row_to_ap(
+    row: &tokio_postgres::Row,
+    community_id: CommunityLocalID,
+    reasons_public: bool,
+    host_url_apub: &crate::BaseURL,
+) -> Result<Option<activitystreams::base::AnyBase>, crate::Error> {
+    // reads an (id, local, ap_id) group of columns
+    let get_ap_id = |idx: usize,
+                     to_local: fn(i64) -> crate::apub_util::LocalObjectRef|
+     -> Result<Option<url::Url>, crate::Error> {
+        match row.get::<_, Option<i64>>(idx) {
+            None => Ok(None),
+            Some(id) => {
+                if row.get(idx + 1) {
+                    Ok(Some(to_local(id).to_local_uri(host_url_apub).into()))
+                } else {
+                    Ok(row
+                        .get::<_, Option<&str>>(idx + 2)
+                        .map(std::str::FromStr::from_str)
+                        .transpose()?)
+                }
+            }
+        }
+    };
+
+    let action: &str = row.get(2);
+
+    let object = match action {
+        "edit_community" => Some(activitystreams::base::AnyBase::from_xsd_any_uri(
+            crate::apub_util::LocalObjectRef::Community(community_id)
+                .to_local_uri(host_url_apub)
+                .into(),
+        )),
+        "block_domain" | "unblock_domain" => {
+            let domain: Option<&str> = row.get(13);
+            domain.map(|domain| activitystreams::base::AnyBase::from_xsd_string(domain.to_owned()))
+        }
+        "add_moderator" | "remove_moderator" | "mute_user" | "unmute_user" => {
+            get_ap_id(10, |id| {
+                crate::apub_util::LocalObjectRef::User(UserLocalID(id))
+            })?
+            .map(activitystreams::base::AnyBase::from_xsd_any_uri)
+        }
+        "delete_reply" => get_ap_id(7, |id| {
+            crate::apub_util::LocalObjectRef::Comment(CommentLocalID(id))
+        })?
+        .map(activitystreams::base::AnyBase::from_xsd_any_uri),
+        _ => get_ap_id(4, |id| {
+            crate::apub_util::LocalObjectRef::Post(PostLocalID(id))
+        })?
+        .map(activitystreams::base::AnyBase::from_xsd_any_uri),
+    };
+
+    let object = match object {
+        None => return Ok(None),
+        Some(object) => object,
+    };
+
+    let reason = if reasons_public { row.get(3) } else { None };
+
+    crate::apub_util::local_community_modlog_event_to_ap(
+        community_id,
+        row.get(0),
+        action,
+        row.get(1),
+        object,
+        reason,
+        host_url_apub,
+    )
+}
+
+async fn handler_communities_moderators_list(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    let db = ctx.db_pool.get().await?;
+
+    crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
+    let rows = db
+        .query(
+            "SELECT person.id, person.local, person.ap_id FROM community_moderator INNER JOIN person ON (person.id = community_moderator.person) WHERE community_moderator.community=$1 ORDER BY community_moderator.created_local NULLS FIRST, person.id",
+            &[&community_id],
+        )
+        .await?;
+
+    let items: Result<Vec<_>, _> = rows
+        .into_iter()
+        .map(|row| {
+            use std::str::FromStr;
+
+            if row.get(1) {
+                Ok(
+                    crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(0)))
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
+                )
+            } else {
+                url::Url::from_str(row.get(2))
+            }
+        })
+        .collect();
+    let items = items?;
+
+    let mut body = activitystreams::collection::OrderedCollection::new();
+    body.set_id(
+        crate::apub_util::LocalObjectRef::CommunityModerators(community_id)
+            .to_local_uri(&ctx.host_url_apub)
+            .into(),
+    );
+    body.set_many_contexts(crate::apub_util::default_contexts());
+    body.set_total_items(items.len() as u64);
+    body.set_many_ordered_items(items);
+
+    let body = serde_json::to_vec(&body)?;
+
+    Ok(crate::apub_util::activity_response(body))
+}
+
+async fn handler_communities_modlog_get(
+    params: (CommunityLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id,) = params;
+    let db = ctx.db_pool.get().await?;
+
+    get_local_community_modlog_reasons_public(community_id, &db).await?;
+
+    let page_ap_id = crate::apub_util::LocalObjectRef::CommunityModlogPage(
+        community_id,
+        crate::TimestampAndIDOrLatest::Latest,
+    )
+    .to_local_uri(&ctx.host_url_apub);
+
+    let collection = serde_json::json!({
+        "@context": activitystreams::context(),
+        "type": activitystreams::collection::kind::OrderedCollectionType::OrderedCollection,
+        "id": crate::apub_util::LocalObjectRef::CommunityModlog(community_id).to_local_uri(&ctx.host_url_apub),
+        "attributedTo": crate::apub_util::LocalObjectRef::Community(community_id).to_local_uri(&ctx.host_url_apub),
+        "first": &page_ap_id,
+        "current": &page_ap_id
+    });
+
+    let body = serde_json::to_vec(&collection)?;
+
+    crate::apub_util::local_community_signed_response(community_id, body, &db, &ctx.host_url_apub)
+        .await
+}
+
+async fn handler_communities_modlog_events_get(
+    params: (CommunityLocalID, i64),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (community_id, event_id) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+    let db = ctx.db_pool.get().await?;
+
+    let reasons_public = get_local_community_modlog_reasons_public(community_id, &db).await?;
+
+    let row = db
+        .query_opt(
+            format!(
+                "SELECT {} WHERE modlog_event.id=$1 AND modlog_event.by_community=$2",
+                MODLOG_EVENT_COLUMNS
+            )
+            .as_str(),
+            &[&event_id, &community_id],
+        )
+        .await?;
+
+    let activity = match row {
+        None => None,
+        Some(row) => {
+            modlog_event_row_to_ap(&row, community_id, reasons_public, &ctx.host_url_apub)?
+        }
+    };
+
+    match activity {
+        None => Err(crate::user_error(
+            hyper::StatusCode::NOT_FOUND,
+            &lang,
+            &lang::no_such_modlog_event(),
+        )),
+        Some(activity) => {
+            let body = serde_json::to_vec(&activity)?;
+
+            crate::apub_util::local_community_signed_response(
+                community_id,
+                body,
+                &db,
+                &ctx.host_url_apub,
+            )
+            .await
+        }
+    }
+}
+
+async fn handler_communities_modlog_page_get(
+    params: (CommunityLocalID, crate::TimestampAndIDOrLatest),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    use crate::TimestampAndIDOrLatest;
+
+    let (community_id, page) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let reasons_public = get_local_community_modlog_reasons_public(community_id, &db).await?;
+
+    const LIMIT: usize = 30;
+    let limit_plus_1 = (LIMIT + 1) as i64;
+
+    let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&community_id, &limit_plus_1];
+
+    let extra_condition = match &page {
+        TimestampAndIDOrLatest::Latest => "",
+        TimestampAndIDOrLatest::Before(ts, id) => {
+            values.push(ts);
+            values.push(id);
+            " AND (modlog_event.time, modlog_event.id) < ($3, $4)"
+        }
+    };
+
+    let sql: &str = &format!(
+        "SELECT {} WHERE modlog_event.by_community=$1{} ORDER BY modlog_event.time DESC, modlog_event.id DESC LIMIT $2",
+        MODLOG_EVENT_COLUMNS, extra_condition,
+    );
+
+    let rows = crate::query::query(&db, sql, &values[..]).await?;
+
+    // only link a next page if there is one
+    let (rows, next) = if rows.len() > LIMIT {
+        let last = &rows[LIMIT - 1];
+        let next = crate::apub_util::LocalObjectRef::CommunityModlogPage(
+            community_id,
+            TimestampAndIDOrLatest::Before(last.get(1), last.get(0)),
+        )
+        .to_local_uri(&ctx.host_url_apub);
+
+        (&rows[..LIMIT], Some(next))
+    } else {
+        (&rows[..], None)
+    };
+
+    let items: Result<Vec<_>, crate::Error> = rows
+        .iter()
+        .filter_map(|row| {
+            modlog_event_row_to_ap(row, community_id, reasons_public, &ctx.host_url_apub)
+                .transpose()
+        })
+        .collect();
+    let items = items?;
+
+    let mut info = serde_json::json!({
+        "@context": activitystreams::context(),
+        "type": activitystreams::collection::kind::OrderedCollectionPageType::OrderedCollectionPage,
+        "id": crate::apub_util::LocalObjectRef::CommunityModlogPage(community_id, page).to_local_uri(&ctx.host_url_apub),
+        "partOf": crate::apub_util::LocalObjectRef::CommunityModlog(community_id).to_local_uri(&ctx.host_url_apub),
+        "orderedItems": items,
+    });
+    if let Some(next) = next {
+        info["next"] = serde_json::to_value(&next)?;
+    }
+
+    let body = serde_json::to_vec(&info)?;
+
+    crate::apub_util::local_community_signed_response(community_id, body, &db, &ctx.host_url_apub)
+        .await
+}
+
 async fn handler_communities_outbox_get(
     params: (CommunityLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -681,17 +1137,15 @@ async fn handler_communities_outbox_get(
         "current": &page_ap_id
     });
 
-    let body = serde_json::to_vec(&collection)?.into();
+    let body = serde_json::to_vec(&collection)?;
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body)?)
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_communities_outbox_page_get(
     params: (CommunityLocalID, crate::TimestampOrLatest),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     use crate::TimestampOrLatest;
 
@@ -699,6 +1153,9 @@ async fn handler_communities_outbox_page_get(
 
     let db = ctx.db_pool.get().await?;
 
+    let private =
+        crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
     let limit: i64 = 30;
 
     let mut values: Vec<&(dyn postgres_types::ToSql + Sync)> = vec![&community_id, &limit];
@@ -713,7 +1170,7 @@ async fn handler_communities_outbox_page_get(
 
     let sql: &str = &format!("SELECT post.id, post.local, post.ap_id, post.created FROM post WHERE community=$1{} ORDER BY created DESC LIMIT $2", extra_condition);
 
-    let rows = db.query(sql, &values[..]).await?;
+    let rows = crate::query::query(&db, sql, &values[..]).await?;
 
     let last_created = rows.last().map(|row| {
         let created: chrono::DateTime<chrono::offset::FixedOffset> = row.get(3);
@@ -735,12 +1192,14 @@ async fn handler_communities_outbox_page_get(
                     community_id,
                     post_id,
                     post_ap_id.clone().into(),
+                    private,
                     &ctx.host_url_apub,
                 )?)?,
                 serde_json::to_value(crate::apub_util::local_community_post_add_ap(
                     community_id,
                     post_id,
                     post_ap_id.into(),
+                    private,
                     &ctx.host_url_apub,
                 )?)?,
             ])
@@ -765,23 +1224,21 @@ async fn handler_communities_outbox_page_get(
         "next": next,
     });
 
-    let body = serde_json::to_vec(&info)?.into();
+    let body = serde_json::to_vec(&info)?;
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body)?)
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_communities_posts_announce_get(
     params: (CommunityLocalID, PostLocalID),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, post_id) = params;
     let db = ctx.db_pool.get().await?;
 
     match db.query_opt(
-        "SELECT post.id, post.local, post.ap_id, community.local FROM post, community WHERE post.community = community.id AND post.id=$1 AND post.community=$2 AND post.approved",
+        "SELECT post.id, post.local, post.ap_id, community.local, community.modified_at FROM post, community WHERE post.community = community.id AND post.id=$1 AND post.community=$2 AND post.approved",
         &[&post_id, &community_id],
     ).await? {
         None => {
@@ -802,6 +1259,8 @@ async fn handler_communities_posts_announce_get(
                         "Requested community is not owned by this instance",
                     )),
                 Some(true) => {
+                    let private = crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
                     let post_local_id = PostLocalID(row.get(0));
                     let post_ap_id = if row.get(1) {
                         crate::apub_util::LocalObjectRef::Post(post_local_id).to_local_uri(&ctx.host_url_apub)
@@ -809,17 +1268,16 @@ async fn handler_communities_posts_announce_get(
                         std::str::FromStr::from_str(row.get(2))?
                     };
 
-                    let body = crate::apub_util::local_community_post_announce_ap(
-                        community_id,
-                        post_local_id,
-                        post_ap_id.into(),
-                        &ctx.host_url_apub,
-                    )?;
-                    let body = serde_json::to_vec(&body)?;
-
-                    Ok(hyper::Response::builder()
-                       .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                       .body(body.into())?)
+                    crate::apub_util::cached_activity_response(&req, Some(row.get(4)), private, || {
+                        let body = crate::apub_util::local_community_post_announce_ap(
+                            community_id,
+                            post_local_id,
+                            post_ap_id.into(),
+                            private,
+                            &ctx.host_url_apub,
+                        )?;
+                        Ok(serde_json::to_vec(&body)?)
+                    })
                 }
             }
         },
@@ -829,7 +1287,7 @@ async fn handler_communities_posts_announce_get(
 async fn handler_communities_posts_announce_undos_get(
     params: (CommunityLocalID, PostLocalID, uuid::Uuid),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, post_id, undo_id) = params;
     let db = ctx.db_pool.get().await?;
@@ -847,17 +1305,17 @@ async fn handler_communities_posts_announce_undos_get(
         Some(row) => {
             let community_local = row.get(2);
             if community_local {
+                let private = crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
                 let post_ap_id = if row.get(0) {
                     crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub).into()
                 } else {
                     std::str::FromStr::from_str(row.get(1))?
                 };
-                let body = crate::apub_util::local_community_post_announce_undo_ap(community_id, post_id, post_ap_id, &undo_id, &ctx.host_url_apub)?;
+                let body = crate::apub_util::local_community_post_announce_undo_ap(community_id, post_id, post_ap_id, private, &undo_id, &ctx.host_url_apub)?;
                 let body = serde_json::to_vec(&body)?;
 
-                Ok(hyper::Response::builder()
-                   .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                   .body(body.into())?)
+                Ok(crate::apub_util::activity_response(body))
             } else {
                 Ok(crate::simple_response(
                     hyper::StatusCode::BAD_REQUEST,
@@ -871,7 +1329,7 @@ async fn handler_communities_posts_announce_undos_get(
 async fn handler_communities_posts_add_get(
     params: (CommunityLocalID, PostLocalID),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, post_id) = params;
     let db = ctx.db_pool.get().await?;
@@ -898,6 +1356,8 @@ async fn handler_communities_posts_add_get(
                         "Requested community is not owned by this instance",
                     )),
                 Some(true) => {
+                    let private = crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
                     let post_local_id = PostLocalID(row.get(0));
                     let post_ap_id = if row.get(1) {
                         crate::apub_util::LocalObjectRef::Post(post_local_id).to_local_uri(&ctx.host_url_apub)
@@ -909,13 +1369,12 @@ async fn handler_communities_posts_add_get(
                         community_id,
                         post_local_id,
                         post_ap_id.into(),
+                        private,
                         &ctx.host_url_apub,
                     )?;
                     let body = serde_json::to_vec(&body)?;
 
-                    Ok(hyper::Response::builder()
-                       .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                       .body(body.into())?)
+                    Ok(crate::apub_util::activity_response(body))
                 }
             }
         },
@@ -925,7 +1384,7 @@ async fn handler_communities_posts_add_get(
 async fn handler_communities_posts_add_undos_get(
     params: (CommunityLocalID, PostLocalID, uuid::Uuid),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (community_id, post_id, undo_id) = params;
     let db = ctx.db_pool.get().await?;
@@ -943,17 +1402,17 @@ async fn handler_communities_posts_add_undos_get(
         Some(row) => {
             let community_local = row.get(2);
             if community_local {
+                let private = crate::apub_util::check_local_community_fetch(community_id, &req, &db, &ctx).await?;
+
                 let post_ap_id = if row.get(0) {
                     crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub).into()
                 } else {
                     std::str::FromStr::from_str(row.get(1))?
                 };
-                let body = crate::apub_util::local_community_post_add_undo_ap(community_id, post_id, post_ap_id, &undo_id, &ctx.host_url_apub)?;
+                let body = crate::apub_util::local_community_post_add_undo_ap(community_id, post_id, post_ap_id, private, &undo_id, &ctx.host_url_apub)?;
                 let body = serde_json::to_vec(&body)?;
 
-                Ok(hyper::Response::builder()
-                   .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                   .body(body.into())?)
+                Ok(crate::apub_util::activity_response(body))
             } else {
                 Ok(crate::simple_response(
                     hyper::StatusCode::BAD_REQUEST,
@@ -990,9 +1449,7 @@ async fn handler_communities_updates_get(
                 )?;
                 let body = serde_json::to_vec(&body)?;
 
-                Ok(hyper::Response::builder()
-                    .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                    .body(body.into())?)
+                Ok(crate::apub_util::activity_response(body))
             } else {
                 Ok(crate::simple_response(
                     hyper::StatusCode::BAD_REQUEST,
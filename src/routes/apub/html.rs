@@ -0,0 +1,284 @@
+use crate::types::{CommunityLocalID, PostLocalID, UserLocalID};
+use std::fmt::Write;
+
+const DESCRIPTION_MAX_CHARS: usize = 300;
+
+#[derive(Clone, Copy)]
+pub enum HtmlTarget {
+    Post(PostLocalID),
+    Community(CommunityLocalID),
+    User(UserLocalID),
+}
+
+impl HtmlTarget {
+    fn kind(self) -> &'static str {
+        match self {
+            HtmlTarget::Post(_) => "posts",
+            HtmlTarget::Community(_) => "communities",
+            HtmlTarget::User(_) => "users",
+        }
+    }
+
+    fn id(self) -> i64 {
+        match self {
+            HtmlTarget::Post(id) => id.raw(),
+            HtmlTarget::Community(id) => id.raw(),
+            HtmlTarget::User(id) => id.raw(),
+        }
+    }
+
+    fn to_local_object_ref(self) -> crate::apub_util::LocalObjectRef {
+        match self {
+            HtmlTarget::Post(id) => crate::apub_util::LocalObjectRef::Post(id),
+            HtmlTarget::Community(id) => crate::apub_util::LocalObjectRef::Community(id),
+            HtmlTarget::User(id) => crate::apub_util::LocalObjectRef::User(id),
+        }
+    }
+}
+
+/// Whether the request came from a browser rather than an ActivityPub client
+pub fn wants_html(req: &hyper::Request<hyper::Body>) -> bool {
+    let accept = match req
+        .headers()
+        .get(hyper::header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(accept) => accept,
+        None => return false,
+    };
+
+    let mut html = false;
+    for media_type in accept.split(',') {
+        let media_type = media_type.split(';').next().unwrap().trim();
+        match media_type {
+            "text/html" | "application/xhtml+xml" => html = true,
+            "application/activity+json" | "application/ld+json" | "application/json" => {
+                return false
+            }
+            _ => {}
+        }
+    }
+
+    html
+}
+
+struct PageInfo {
+    og_type: &'static str,
+    title: String,
+    description: Option<String>,
+    image: Option<String>,
+    author: Option<String>,
+    link: Option<String>,
+}
+
+fn truncate_description(src: &str) -> String {
+    let src = src.trim();
+    match src.char_indices().nth(DESCRIPTION_MAX_CHARS) {
+        None => src.to_owned(),
+        Some((idx, _)) => format!("{}…", &src[..idx]),
+    }
+}
+
+async fn fetch_page_info(
+    target: HtmlTarget,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<Option<PageInfo>, crate::Error> {
+    Ok(match target {
+        HtmlTarget::Post(post_id) => {
+            let row = db.query_opt(
+                "SELECT post.title, post.content_text, post.href, community.name, person.username FROM post INNER JOIN community ON (community.id = post.community) LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.id=$1 AND post.local AND NOT post.deleted AND NOT community.private",
+                &[&post_id],
+            ).await?;
+
+            row.map(|row| {
+                let community_name: &str = row.get(3);
+                let href: Option<&str> = row.get(2);
+
+                PageInfo {
+                    og_type: "article",
+                    title: row.get(0),
+                    description: Some(match row.get::<_, Option<&str>>(1) {
+                        Some(content_text) if !content_text.trim().is_empty() => {
+                            truncate_description(content_text)
+                        }
+                        _ => format!("Posted in {}", community_name),
+                    }),
+                    image: None,
+                    author: row.get(4),
+                    link: href.map(|href| ctx.process_href(href, post_id).into_owned()),
+                }
+            })
+        }
+        HtmlTarget::Community(community_id) => {
+            let row = db
+                .query_opt(
+                    "SELECT name, description FROM community WHERE id=$1 AND local AND NOT deleted",
+                    &[&community_id],
+                )
+                .await?;
+
+            row.map(|row| PageInfo {
+                og_type: "profile",
+                title: row.get(0),
+                description: row.get::<_, Option<&str>>(1).map(truncate_description),
+                image: None,
+                author: None,
+                link: None,
+            })
+        }
+        HtmlTarget::User(user_id) => {
+            let row = db
+                .query_opt(
+                    "SELECT username, display_name, description, avatar FROM person WHERE id=$1 AND local AND NOT deleted",
+                    &[&user_id],
+                )
+                .await?;
+
+            row.map(|row| {
+                let username: String = row.get(0);
+
+                PageInfo {
+                    og_type: "profile",
+                    title: row.get::<_, Option<String>>(1).unwrap_or(username),
+                    description: row.get::<_, Option<&str>>(2).map(truncate_description),
+                    image: row
+                        .get::<_, Option<&str>>(3)
+                        .map(|avatar| ctx.process_avatar_href(avatar, user_id).into_owned()),
+                    author: None,
+                    link: None,
+                }
+            })
+        }
+    })
+}
+
+fn render_page(target: HtmlTarget, info: PageInfo, ctx: &crate::BaseContext) -> String {
+    let url = target
+        .to_local_object_ref()
+        .to_local_uri(&ctx.host_url_apub);
+    let url = v_htmlescape::escape(url.as_str()).to_string();
+    let title = v_htmlescape::escape(&info.title).to_string();
+    let description = info
+        .description
+        .as_deref()
+        .map(|x| v_htmlescape::escape(x).to_string());
+
+    let mut output = String::new();
+
+    write!(
+        output,
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{0}</title><link rel="alternate" type="application/activity+json" href="{1}"><meta property="og:type" content="{2}"><meta property="og:title" content="{0}"><meta property="og:url" content="{1}"><meta property="og:site_name" content="{3}">"#,
+        title,
+        url,
+        info.og_type,
+        v_htmlescape::escape(&ctx.local_hostname),
+    )
+    .unwrap();
+
+    if let Some(description) = &description {
+        write!(
+            output,
+            r#"<meta property="og:description" content="{0}"><meta name="description" content="{0}">"#,
+            description
+        )
+        .unwrap();
+    }
+    if let Some(image) = &info.image {
+        write!(
+            output,
+            r#"<meta property="og:image" content="{}">"#,
+            v_htmlescape::escape(image)
+        )
+        .unwrap();
+    }
+
+    output.push_str("</head><body>");
+
+    match target {
+        HtmlTarget::Post(_) => {
+            write!(
+                output,
+                r#"<article class="h-entry"><h1><a class="p-name u-url" href="{}">{}</a></h1>"#,
+                url, title
+            )
+            .unwrap();
+            if let Some(author) = &info.author {
+                write!(
+                    output,
+                    r#"<p class="p-author h-card"><span class="p-name">{}</span></p>"#,
+                    v_htmlescape::escape(author)
+                )
+                .unwrap();
+            }
+            if let Some(link) = &info.link {
+                write!(
+                    output,
+                    r#"<p><a href="{0}">{0}</a></p>"#,
+                    v_htmlescape::escape(link)
+                )
+                .unwrap();
+            }
+            if let Some(description) = &description {
+                write!(output, r#"<p class="p-summary">{}</p>"#, description).unwrap();
+            }
+            output.push_str("</article>");
+        }
+        HtmlTarget::Community(_) | HtmlTarget::User(_) => {
+            output.push_str(r#"<div class="h-card">"#);
+            if let Some(image) = &info.image {
+                write!(
+                    output,
+                    r#"<img class="u-photo" src="{}" alt="">"#,
+                    v_htmlescape::escape(image)
+                )
+                .unwrap();
+            }
+            write!(
+                output,
+                r#"<h1><a class="p-name u-url" href="{}">{}</a></h1>"#,
+                url, title
+            )
+            .unwrap();
+            if let Some(description) = &description {
+                write!(output, r#"<p class="p-note">{}</p>"#, description).unwrap();
+            }
+            output.push_str("</div>");
+        }
+    }
+
+    output.push_str("</body></html>");
+
+    output
+}
+
+/// Responds to a browser request for an object, either by redirecting to the configured frontend
+/// or with a minimal page containing OpenGraph and microformats metadata
+pub async fn html_response(
+    target: HtmlTarget,
+    db: &tokio_postgres::Client,
+    ctx: &crate::BaseContext,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    if let Some(pattern) = &ctx.frontend_url_pattern {
+        let location = pattern
+            .replace("{kind}", target.kind())
+            .replace("{id}", &target.id().to_string());
+
+        return Ok(crate::common_response_builder()
+            .status(hyper::StatusCode::SEE_OTHER)
+            .header(hyper::header::LOCATION, location)
+            .header(hyper::header::VARY, "Accept")
+            .body(Default::default())?);
+    }
+
+    match fetch_page_info(target, db, ctx).await? {
+        None => Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such object",
+        )),
+        Some(info) => Ok(crate::common_response_builder()
+            .header(hyper::header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .header(hyper::header::VARY, "Accept")
+            .body(render_page(target, info, ctx).into())?),
+    }
+}
@@ -5,15 +5,54 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 mod communities;
+mod html;
 mod posts;
 
+lazy_static::lazy_static! {
+    static ref PROPERTY_VALUE_CONTEXT: activitystreams::base::AnyBase = activitystreams::base::AnyBase::from_arbitrary_json(serde_json::json!({
+        "schema": "http://schema.org#",
+        "PropertyValue": "schema:PropertyValue",
+        "value": "schema:value"
+    })).unwrap();
+    static ref MOVED_CONTEXT: activitystreams::base::AnyBase = activitystreams::base::AnyBase::from_arbitrary_json(serde_json::json!({
+        "alsoKnownAs": {"@id": "as:alsoKnownAs", "@type": "@id"},
+        "movedTo": {"@id": "as:movedTo", "@type": "@id"}
+    })).unwrap();
+}
+
 pub fn route_apub() -> crate::RouteNode<()> {
     crate::RouteNode::new()
+        .with_child(
+            "actor",
+            crate::RouteNode::new().with_handler_async(hyper::Method::GET, handler_actor_get),
+        )
         .with_child(
             "users",
             crate::RouteNode::new().with_child_parse::<UserLocalID, _>(
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::GET, handler_users_get)
+                    .with_child(
+                        "followers",
+                        crate::RouteNode::new()
+                            .with_handler_async(hyper::Method::GET, handler_users_followers_list)
+                            .with_child_parse::<UserLocalID, _>(
+                                crate::RouteNode::new()
+                                    .with_child(
+                                        "accept",
+                                        crate::RouteNode::new().with_handler_async(
+                                            hyper::Method::GET,
+                                            handler_users_followers_accept_get,
+                                        ),
+                                    )
+                                    .with_child(
+                                        "reject",
+                                        crate::RouteNode::new().with_handler_async(
+                                            hyper::Method::GET,
+                                            handler_users_followers_reject_get,
+                                        ),
+                                    ),
+                            ),
+                    )
                     .with_child(
                         "inbox",
                         crate::RouteNode::new()
@@ -51,12 +90,30 @@ pub fn route_apub() -> crate::RouteNode<()> {
                         crate::RouteNode::new()
                             .with_handler_async(hyper::Method::GET, handler_comments_delete_get),
                     )
+                    .with_child(
+                        "dislikes",
+                        crate::RouteNode::new().with_child_parse::<UserLocalID, _>(
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::GET,
+                                handler_comments_dislikes_get,
+                            ),
+                        ),
+                    )
                     .with_child(
                         "likes",
                         crate::RouteNode::new().with_child_parse::<UserLocalID, _>(
                             crate::RouteNode::new()
                                 .with_handler_async(hyper::Method::GET, handler_comments_likes_get),
                         ),
+                    )
+                    .with_child(
+                        "reactions",
+                        crate::RouteNode::new().with_child_parse::<i64, _>(
+                            crate::RouteNode::new().with_handler_async(
+                                hyper::Method::GET,
+                                handler_comments_reactions_get,
+                            ),
+                        ),
                     ),
             ),
         )
@@ -67,6 +124,13 @@ pub fn route_apub() -> crate::RouteNode<()> {
                     .with_handler_async(hyper::Method::GET, handler_comment_like_undos_get),
             ),
         )
+        .with_child(
+            "comment_reaction_undos",
+            crate::RouteNode::new().with_child_parse::<uuid::Uuid, _>(
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, handler_comment_reaction_undos_get),
+            ),
+        )
         .with_child("communities", communities::route_communities())
         .with_child(
             "community_follow_undos",
@@ -84,24 +148,81 @@ pub fn route_apub() -> crate::RouteNode<()> {
                     .with_handler_async(hyper::Method::GET, handler_post_like_undos_get),
             ),
         )
+        .with_child(
+            "post_reaction_undos",
+            crate::RouteNode::new().with_child_parse::<uuid::Uuid, _>(
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, handler_post_reaction_undos_get),
+            ),
+        )
 }
 
 pub fn route_inbox() -> crate::RouteNode<()> {
     crate::RouteNode::new().with_handler_async(hyper::Method::POST, handler_inbox_post)
 }
 
+async fn handler_actor_get(
+    _: (),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let actor_ap_id =
+        crate::apub_util::LocalObjectRef::InstanceActor.to_local_uri(&ctx.host_url_apub);
+
+    let mut info = activitystreams::actor::Application::new();
+    info.set_many_contexts(vec![
+        activitystreams::context(),
+        activitystreams::security(),
+    ])
+    .set_id(actor_ap_id.deref().clone())
+    .set_name(ctx.local_hostname.as_str());
+
+    let mut info = activitystreams::actor::ApActor::new(
+        crate::apub_util::LocalObjectRef::SharedInbox
+            .to_local_uri(&ctx.host_url_apub)
+            .into(),
+        info,
+    );
+    info.set_preferred_username(ctx.local_hostname.as_str());
+
+    let public_key = ctx.instance_actor_key.public_key_to_pem()?;
+
+    let public_key_ext = crate::apub_util::PublicKeyExtension {
+        public_key: Some(crate::apub_util::PublicKey {
+            id: crate::apub_util::get_instance_actor_pubkey_apub_id(&ctx.host_url_apub)
+                .as_str()
+                .into(),
+            owner: actor_ap_id.as_str().into(),
+            public_key_pem: std::str::from_utf8(&public_key)?.into(),
+            signature_algorithm: Some(crate::apub_util::SIGALG_RSA_SHA256.into()),
+        }),
+    };
+
+    // no stored timestamp for the instance actor, so only Cache-Control applies
+    crate::apub_util::cached_activity_response(&req, None, false, || {
+        Ok(serde_json::to_vec(&activitystreams_ext::Ext1::new(
+            info,
+            public_key_ext,
+        ))?)
+    })
+}
+
 async fn handler_users_get(
     params: (UserLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (user_id,) = params;
     let db = ctx.db_pool.get().await?;
 
+    if html::wants_html(&req) {
+        return html::html_response(html::HtmlTarget::User(user_id), &db, &ctx).await;
+    }
+
     match db
         .query_opt(
-            "SELECT username, local, public_key, description, description_html, avatar, is_bot FROM person WHERE id=$1",
-            &[&user_id.raw()],
+            "SELECT username, local, public_key, description, description_html, avatar, is_bot, display_name, profile_fields, deleted, also_known_as, moved_to, modified_at FROM person WHERE id=$1",
+            &[&user_id],
         )
         .await?
     {
@@ -119,6 +240,24 @@ async fn handler_users_get(
                 )));
             }
 
+            if row.get(9) {
+                let mut info = activitystreams::object::Tombstone::new();
+                info.set_former_type("Person".to_owned())
+                    .set_many_contexts(crate::apub_util::default_contexts())
+                    .set_id(
+                        crate::apub_util::LocalObjectRef::User(user_id)
+                            .to_local_uri(&ctx.host_url_apub)
+                            .into(),
+                    );
+
+                let body = serde_json::to_vec(&info)?;
+
+                let mut resp = crate::apub_util::activity_response(body);
+                *resp.status_mut() = hyper::StatusCode::GONE;
+
+                return Ok(resp);
+            }
+
             let username: String = row.get(0);
             let public_key: Option<&str> =
                 row.get::<_, Option<&[u8]>>(2)
@@ -139,21 +278,66 @@ async fn handler_users_get(
 
             let is_bot: bool = row.get(6);
 
-            fn format_user<T, K: serde::Serialize + activitystreams::base::AsBase<T> + activitystreams::object::AsObject<T> + activitystreams::markers::Actor>(mut info: K, user_id: UserLocalID, ctx: &crate::RouteContext, username: String, description: Option<String>, avatar: Option<&str>, public_key: Option<&str>) -> Result<Vec<u8>, crate::Error> {
+            let profile = UserProfile {
+                username,
+                display_name: row.get(7),
+                description,
+                avatar,
+                profile_fields: row.get::<_, postgres_types::Json<Vec<crate::types::ProfileField>>>(8).0,
+                also_known_as: row.get(10),
+                moved_to: row.get(11),
+            };
+
+            struct UserProfile<'a> {
+                username: String,
+                display_name: Option<&'a str>,
+                description: Option<String>,
+                avatar: Option<&'a str>,
+                profile_fields: Vec<crate::types::ProfileField<'a>>,
+                also_known_as: Vec<&'a str>,
+                moved_to: Option<&'a str>,
+            }
+
+            fn format_user<T, K: serde::Serialize + activitystreams::base::AsBase<T> + activitystreams::object::AsObject<T> + activitystreams::markers::Actor>(mut info: K, user_id: UserLocalID, ctx: &crate::RouteContext, profile: UserProfile, public_key: Option<&str>) -> Result<Vec<u8>, crate::Error> {
+                let UserProfile { username, display_name, description, avatar, profile_fields, also_known_as, moved_to } = profile;
+
                 let user_ap_id =
                     crate::apub_util::LocalObjectRef::User(user_id).to_local_uri(&ctx.host_url_apub);
 
-                info.set_many_contexts(vec![
+                let mut contexts = vec![
                     activitystreams::context(),
                     activitystreams::security(),
-                ]);
+                ];
+                if !profile_fields.is_empty() {
+                    contexts.push(PROPERTY_VALUE_CONTEXT.clone());
+                }
+                if !also_known_as.is_empty() || moved_to.is_some() {
+                    contexts.push(MOVED_CONTEXT.clone());
+                }
+
+                info.set_many_contexts(contexts);
                 info.set_id(user_ap_id.deref().clone())
-                    .set_name(username.as_ref());
+                    .set_name(display_name.unwrap_or(&username));
 
                 if let Some(description) = description {
                     info.set_summary(description);
                 }
 
+                if !profile_fields.is_empty() {
+                    let attachments: Result<Vec<_>, _> = profile_fields
+                        .iter()
+                        .map(|field| {
+                            activitystreams::base::AnyBase::from_arbitrary_json(serde_json::json!({
+                                "type": "PropertyValue",
+                                "name": field.name,
+                                "value": v_htmlescape::escape(&field.value).to_string(),
+                            }))
+                        })
+                        .collect();
+
+                    info.set_many_attachments(attachments?);
+                }
+
                 if let Some(avatar) = avatar {
                     let mut attachment = activitystreams::object::Image::new();
                     attachment.set_url(ctx.process_avatar_href(avatar, user_id).into_owned());
@@ -180,44 +364,38 @@ async fn handler_users_get(
                     crate::apub_util::LocalObjectRef::UserOutbox(user_id).to_local_uri(&ctx.host_url_apub)
                         .into(),
                 )
+                .set_followers(
+                    crate::apub_util::LocalObjectRef::UserFollowers(user_id).to_local_uri(&ctx.host_url_apub)
+                        .into(),
+                )
                 .set_endpoints(endpoints)
                 .set_preferred_username(username);
 
                 let key_id = format!("{}/users/{}#main-key", ctx.host_url_apub, user_id);
 
-                let body = if let Some(public_key) = public_key {
-                    let public_key_ext = crate::apub_util::PublicKeyExtension {
-                        public_key: Some(crate::apub_util::PublicKey {
-                            id: (&key_id).into(),
-                            owner: user_ap_id.as_str().into(),
-                            public_key_pem: public_key.into(),
-                            signature_algorithm: Some(crate::apub_util::SIGALG_RSA_SHA256.into()),
-                        }),
-                    };
+                let public_key_ext = crate::apub_util::PublicKeyExtension {
+                    public_key: public_key.map(|public_key| crate::apub_util::PublicKey {
+                        id: (&key_id).into(),
+                        owner: user_ap_id.as_str().into(),
+                        public_key_pem: public_key.into(),
+                        signature_algorithm: Some(crate::apub_util::SIGALG_RSA_SHA256.into()),
+                    }),
+                };
 
-                    let info = activitystreams_ext::Ext1::new(info, public_key_ext);
+                let moved_ext = crate::apub_util::MovedExtension::new(&also_known_as, moved_to)?;
 
-                    serde_json::to_vec(&info)
-                } else {
-                    serde_json::to_vec(&info)
-                }?;
+                let info = activitystreams_ext::Ext2::new(info, public_key_ext, moved_ext);
 
-                Ok(body)
+                Ok(serde_json::to_vec(&info)?)
             }
 
-            let body = if is_bot {
-                format_user(activitystreams::actor::Service::new(), user_id, &ctx, username, description, avatar, public_key)
-            } else {
-                format_user(activitystreams::actor::Person::new(), user_id, &ctx, username, description, avatar, public_key)
-            }?;
-
-            let mut resp = hyper::Response::new(body.into());
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static("application/activity+json"),
-            );
-
-            Ok(resp)
+            crate::apub_util::cached_activity_response(&req, Some(row.get(12)), false, || {
+                if is_bot {
+                    format_user(activitystreams::actor::Service::new(), user_id, &ctx, profile, public_key)
+                } else {
+                    format_user(activitystreams::actor::Person::new(), user_id, &ctx, profile, public_key)
+                }
+            })
         }
     }
 }
@@ -240,6 +418,122 @@ async fn inbox_common(
     Ok(crate::simple_response(hyper::StatusCode::ACCEPTED, ""))
 }
 
+async fn handler_users_followers_list(
+    params: (UserLocalID,),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id,) = params;
+    let db = ctx.db_pool.get().await?;
+
+    let row = db
+        .query_one(
+            "SELECT COUNT(*) FROM person_follow WHERE person=$1",
+            &[&user_id],
+        )
+        .await?;
+    let count: i64 = row.get(0);
+
+    let body = serde_json::to_vec(&serde_json::json!({
+        "@context": activitystreams::context(),
+        "type": "Collection",
+        "id": crate::apub_util::LocalObjectRef::UserFollowers(user_id).to_local_uri(&ctx.host_url_apub),
+        "totalItems": count,
+    }))?;
+
+    Ok(crate::apub_util::activity_response(body))
+}
+
+async fn handler_users_followers_accept_get(
+    params: (UserLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id, follower_id) = params;
+    let db = ctx.db_pool.get().await?;
+
+    let row = db.query_opt(
+        "SELECT person_follow.ap_id, person.ap_id FROM person_follow INNER JOIN person ON (person.id = person_follow.follower) WHERE person_follow.person=$1 AND person_follow.follower=$2",
+        &[&user_id, &follower_id],
+    ).await?;
+
+    match row {
+        None => Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such follow",
+        )),
+        Some(row) => {
+            let follow_ap_id: Option<&str> = row.get(0);
+            let follower_ap_id: Option<&str> = row.get(1);
+
+            let (follow_ap_id, follower_ap_id): (url::Url, url::Url) =
+                match (follow_ap_id, follower_ap_id) {
+                    (Some(follow_ap_id), Some(follower_ap_id)) => {
+                        (follow_ap_id.parse()?, follower_ap_id.parse()?)
+                    }
+                    _ => {
+                        return Err(crate::Error::InternalStr(format!(
+                            "Missing ap_id for follow ({} / {})",
+                            user_id, follower_id
+                        )))
+                    }
+                };
+
+            let body = crate::apub_util::user_follow_accept_to_ap(
+                user_id,
+                follower_id,
+                follower_ap_id,
+                activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id),
+                &ctx.host_url_apub,
+            )?;
+            let body = serde_json::to_vec(&body)?;
+
+            Ok(crate::apub_util::activity_response(body))
+        }
+    }
+}
+
+async fn handler_users_followers_reject_get(
+    params: (UserLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (user_id, follower_id) = params;
+    let db = ctx.db_pool.get().await?;
+
+    let row = db.query_opt(
+        "SELECT person_follow_reject.ap_id, person.ap_id FROM person_follow_reject INNER JOIN person ON (person.id = person_follow_reject.follower) WHERE person_follow_reject.person=$1 AND person_follow_reject.follower=$2",
+        &[&user_id, &follower_id],
+    ).await?;
+
+    match row {
+        None => Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such follow",
+        )),
+        Some(row) => {
+            let follow_ap_id: url::Url = row.get::<_, &str>(0).parse()?;
+            let follower_ap_id: url::Url = row
+                .get::<_, Option<&str>>(1)
+                .ok_or_else(|| {
+                    crate::Error::InternalStr(format!("Missing ap_id for user ({})", follower_id))
+                })?
+                .parse()?;
+
+            let body = crate::apub_util::user_follow_reject_to_ap(
+                user_id,
+                follower_id,
+                follower_ap_id,
+                activitystreams::base::AnyBase::from_xsd_any_uri(follow_ap_id),
+                &ctx.host_url_apub,
+            )?;
+            let body = serde_json::to_vec(&body)?;
+
+            Ok(crate::apub_util::activity_response(body))
+        }
+    }
+}
+
 async fn handler_users_inbox_post(
     _: (UserLocalID,),
     ctx: Arc<crate::RouteContext>,
@@ -266,11 +560,9 @@ async fn handler_users_outbox_get(
         "current": &page_ap_id
     });
 
-    let body = serde_json::to_vec(&collection)?.into();
+    let body = serde_json::to_vec(&collection)?;
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body)?)
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_users_outbox_page_get(
@@ -296,9 +588,9 @@ async fn handler_users_outbox_page_get(
         }
     };
 
-    let sql: &str = &format!("(SELECT TRUE, post.id, post.href, post.title, post.created, post.content_text, post.content_markdown, post.content_html, community.id, community.local, community.ap_id, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, community.ap_outbox, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, post.sensitive FROM post INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.author = $1 AND NOT post.deleted{}) UNION ALL (SELECT FALSE, reply.id, reply.content_text, reply.content_html, reply.created, parent_or_post_author.ap_id, reply.content_markdown, parent_reply.ap_id, post.id, post.local, post.ap_id, parent_reply.id, parent_reply.local, parent_or_post_author.id, parent_or_post_author.local, community.id, community.local, community.ap_id, reply.attachment_href, community.ap_outbox, community.ap_followers, NULL, NULL, NULL, reply.sensitive FROM reply INNER JOIN post ON (post.id = reply.post) INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN reply AS parent_reply ON (parent_reply.id = reply.parent) LEFT OUTER JOIN person AS parent_or_post_author ON (parent_or_post_author.id = COALESCE(parent_reply.author, post.author)) WHERE reply.author = $1 AND NOT reply.deleted{}) ORDER BY created DESC LIMIT $2", extra_conditions_posts, extra_conditions_comments);
+    let sql: &str = &format!("(SELECT TRUE, post.id, post.href, post.title, post.created, post.content_text, post.content_markdown, post.content_html, community.id, community.local, community.ap_id, NULL, NULL, NULL, NULL, NULL, NULL, NULL, NULL, community.ap_outbox, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, post.sensitive, (SELECT COALESCE(json_agg(json_build_object('url', url, 'media_type', media_type, 'alt_text', alt_text) ORDER BY position), '[]') FROM post_attachment WHERE post=post.id), (SELECT COALESCE(array_agg(community_flair.name ORDER BY community_flair.name), '{}') FROM post_flair INNER JOIN community_flair ON (community_flair.id = post_flair.flair) WHERE post_flair.post = post.id), post.language, post.sticky, post.locked, community.link_posts_as_notes FROM post INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.author = $1 AND NOT post.deleted AND NOT community.private{}) UNION ALL (SELECT FALSE, reply.id, reply.content_text, reply.content_html, reply.created, parent_or_post_author.ap_id, reply.content_markdown, parent_reply.ap_id, post.id, post.local, post.ap_id, parent_reply.id, parent_reply.local, parent_or_post_author.id, parent_or_post_author.local, community.id, community.local, community.ap_id, reply.attachment_href, community.ap_outbox, community.ap_followers, NULL, NULL, NULL, reply.sensitive, NULL, NULL, NULL, NULL, NULL, NULL FROM reply INNER JOIN post ON (post.id = reply.post) INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN reply AS parent_reply ON (parent_reply.id = reply.parent) LEFT OUTER JOIN person AS parent_or_post_author ON (parent_or_post_author.id = COALESCE(parent_reply.author, post.author)) WHERE reply.author = $1 AND NOT reply.deleted AND NOT community.private{}) ORDER BY created DESC LIMIT $2", extra_conditions_posts, extra_conditions_comments);
 
-    let rows = db.query(sql, &values[..]).await?;
+    let rows = crate::query::query(&db, sql, &values[..]).await?;
 
     let mut last_created = None;
 
@@ -375,8 +667,19 @@ async fn handler_users_outbox_page_get(
                     title: row.get(3),
                     created: &created,
                     community: community_id,
+                    community_private: false, // excluded above
+                    community_link_posts_as_notes: row.get(30),
                     poll,
                     sensitive: row.get(24),
+                    sticky: row.get(28),
+                    locked: row.get(29),
+                    attachments: Cow::Owned(
+                        row.get::<_, Option<postgres_types::Json<Vec<crate::PostAttachment>>>>(25)
+                            .map(|x| x.0)
+                            .unwrap_or_default(),
+                    ),
+                    flairs: Cow::Owned(row.get::<_, Option<Vec<String>>>(26).unwrap_or_default()),
+                    language: row.get(27),
                 };
 
                 let res = crate::apub_util::local_post_to_create_ap(
@@ -402,6 +705,7 @@ async fn handler_users_outbox_page_get(
                     content_markdown: row.get::<_, Option<_>>(6).map(Cow::Borrowed),
                     content_html: row.get::<_, Option<_>>(3).map(Cow::Borrowed),
                     created,
+                    updated: None,
                     ap_id: crate::APIDOrLocal::Local,
                     attachment_href: row.get::<_, Option<_>>(18).map(Cow::Borrowed),
                     sensitive: row.get(24),
@@ -441,6 +745,7 @@ async fn handler_users_outbox_page_get(
                     } else {
                         std::str::FromStr::from_str(row.get(17))?
                     },
+                    false, // excluded above
                     &ctx,
                 );
 
@@ -468,159 +773,70 @@ async fn handler_users_outbox_page_get(
         "next": next,
     });
 
-    let body = serde_json::to_vec(&info)?.into();
+    let body = serde_json::to_vec(&info)?;
 
-    Ok(hyper::Response::builder()
-        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-        .body(body)?)
+    Ok(crate::apub_util::activity_response(body))
 }
 
 async fn handler_comments_get(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (comment_id,) = params;
     let db = ctx.db_pool.get().await?;
 
-    match db
-        .query_opt(
-            "SELECT reply.author, reply.content_text, reply.post, reply.created, reply.local, reply.parent, post.local, post.ap_id, post.community, community.local, community.ap_id, reply_parent.local, reply_parent.ap_id, post_author.id, post_author.local, post_author.ap_id, reply_parent_author.id, reply_parent_author.local, reply_parent_author.ap_id, reply.deleted, reply.content_markdown, reply.content_html, reply.attachment_href, reply.sensitive FROM reply LEFT OUTER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN community ON (community.id = post.community) LEFT OUTER JOIN reply AS reply_parent ON (reply_parent.id = reply.parent) LEFT OUTER JOIN person AS reply_parent_author ON (reply_parent_author.id = reply_parent.author) WHERE reply.id=$1",
-            &[&comment_id],
-        )
-        .await?
-    {
-        None => Ok(crate::simple_response(
+    match crate::apub_util::get_local_comment_ap(comment_id, &db, &ctx).await? {
+        crate::apub_util::LocalCommentAP::NotFound => Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
             "No such comment",
         )),
-        Some(row) => {
-            let local: bool = row.get(4);
-
-            if !local {
-                return Err(crate::Error::UserError(crate::simple_response(
-                    hyper::StatusCode::BAD_REQUEST,
-                    "Requested comment is not owned by this instance",
-                )));
-            }
-
-            if row.get(19) {
-                let mut body = activitystreams::object::Tombstone::new();
-                body
-                    .set_former_type("Note".to_owned())
-                    .set_context(activitystreams::context())
-                    .set_id(crate::apub_util::LocalObjectRef::Comment(comment_id).to_local_uri(&ctx.host_url_apub).into());
-
-                let body = serde_json::to_vec(&body)?.into();
-
-                let mut resp = hyper::Response::new(body);
-                *resp.status_mut() = hyper::StatusCode::GONE;
-                resp.headers_mut().insert(
-                    hyper::header::CONTENT_TYPE,
-                    hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
+        crate::apub_util::LocalCommentAP::NotLocal => {
+            Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::BAD_REQUEST,
+                "Requested comment is not owned by this instance",
+            )))
+        }
+        crate::apub_util::LocalCommentAP::Deleted => {
+            let mut body = activitystreams::object::Tombstone::new();
+            body.set_former_type("Note".to_owned())
+                .set_many_contexts(crate::apub_util::default_contexts())
+                .set_id(
+                    crate::apub_util::LocalObjectRef::Comment(comment_id)
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
                 );
 
-                return Ok(resp);
-            }
-
-            let post_local_id = PostLocalID(row.get(2));
+            let body = serde_json::to_vec(&body)?;
 
-            let community_local_id = CommunityLocalID(row.get(8));
+            let mut resp = crate::apub_util::activity_response(body);
+            *resp.status_mut() = hyper::StatusCode::GONE;
 
-            let community_ap_id = if row.get(9) {
-                crate::apub_util::LocalObjectRef::Community(community_local_id).to_local_uri(&ctx.host_url_apub)
-            } else {
-                std::str::FromStr::from_str(row.get(10))?
-            };
-
-            let post_ap_id = if row.get(6) {
-                crate::apub_util::LocalObjectRef::Post(post_local_id).to_local_uri(&ctx.host_url_apub)
-            } else {
-                std::str::FromStr::from_str(row.get(7))?
-            };
-
-            let parent_local_id = row.get::<_, Option<_>>(5).map(CommentLocalID);
-
-            let content_text = row.get::<_, Option<_>>(1).map(Cow::Borrowed);
-            let content_markdown = row.get::<_, Option<_>>(20).map(Cow::Borrowed);
-            let content_html = row.get::<_, Option<_>>(21).map(Cow::Borrowed);
-
-            let attachment_href = row.get::<_, Option<_>>(22).map(Cow::Borrowed);
-
-            let info = crate::CommentInfo {
-                author: Some(UserLocalID(row.get(0))),
-                created: row.get(3),
-                content_text,
-                content_markdown,
-                content_html,
-                id: comment_id,
-                post: post_local_id,
-                parent: parent_local_id,
-                ap_id: crate::APIDOrLocal::Local,
-                attachment_href,
-                sensitive: row.get(23),
-            };
-
-            let parent_ap_id = match row.get(11) {
-                None => None,
-                Some(true) => Some(crate::apub_util::LocalObjectRef::Comment(parent_local_id.unwrap()).to_local_uri(&ctx.host_url_apub)),
-                Some(false) => row.get::<_, Option<&str>>(12).map(|x| x.parse()).transpose()?,
-            };
-
-            let post_or_parent_author_ap_id = match parent_local_id {
-                None => {
-                    // no parent comment, use post
-                    match row.get(14) {
-                        Some(post_author_local) => {
-                            if post_author_local {
-                                Some(crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(13))).to_local_uri(&ctx.host_url_apub))
-                            } else {
-                                Some(std::str::FromStr::from_str(row.get(15))?)
-                            }
-                        },
-                        None => None,
-                    }
-                },
-                Some(_) => {
-                    match row.get(17) {
-                        Some(parent_author_local) => {
-                            if parent_author_local {
-                                Some(crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(16))).to_local_uri(&ctx.host_url_apub))
-                            } else {
-                                Some(std::str::FromStr::from_str(row.get(18))?)
-                            }
-                        },
-                        None => None,
-                    }
-                },
-            };
-
-            let body = crate::apub_util::local_comment_to_ap(&info, &post_ap_id, parent_ap_id.map(From::from), post_or_parent_author_ap_id.map(From::from), community_ap_id.into(), &ctx)?;
-
-            let body = serde_json::to_vec(&body)?.into();
+            Ok(resp)
+        }
+        crate::apub_util::LocalCommentAP::Found {
+            object, community, ..
+        } => {
+            crate::apub_util::check_local_community_fetch(community, &req, &db, &ctx).await?;
 
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
+            let body = serde_json::to_vec(&object)?;
 
-            Ok(resp)
-        },
+            Ok(crate::apub_util::activity_response(body))
+        }
     }
 }
 
 async fn handler_comments_create_get(
     params: (CommentLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (comment_id,) = params;
     let db = ctx.db_pool.get().await?;
 
     match db
         .query_opt(
-            "SELECT reply.author, reply.content_text, reply.post, reply.created, reply.local, reply.parent, post.local, post.ap_id, post.community, community.local, community.ap_id, reply_parent.local, reply_parent.ap_id, post_author.id, post_author.local, post_author.ap_id, reply_parent_author.id, reply_parent_author.local, reply_parent_author.ap_id, reply.deleted, reply.content_markdown, reply.content_html, reply.attachment_href, reply.sensitive FROM reply LEFT OUTER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN community ON (community.id = post.community) LEFT OUTER JOIN reply AS reply_parent ON (reply_parent.id = reply.parent) LEFT OUTER JOIN person AS reply_parent_author ON (reply_parent_author.id = reply_parent.author) WHERE reply.id=$1",
+            "SELECT reply.author, reply.content_text, reply.post, reply.created, reply.local, reply.parent, post.local, post.ap_id, post.community, community.local, community.ap_id, reply_parent.local, reply_parent.ap_id, post_author.id, post_author.local, post_author.ap_id, reply_parent_author.id, reply_parent_author.local, reply_parent_author.ap_id, reply.deleted, reply.content_markdown, reply.content_html, reply.attachment_href, reply.sensitive FROM reply LEFT OUTER JOIN post ON (post.id = reply.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) LEFT OUTER JOIN community ON (community.id = post.community) LEFT OUTER JOIN reply AS reply_parent ON (reply_parent.id = reply.parent) LEFT OUTER JOIN person AS reply_parent_author ON (reply_parent_author.id = reply_parent.author) WHERE reply.id=$1 AND NOT reply.pending_approval",
             &[&comment_id],
         )
         .await?
@@ -650,6 +866,8 @@ async fn handler_comments_create_get(
 
             let community_local_id = CommunityLocalID(row.get(8));
 
+            let community_private = crate::apub_util::check_local_community_fetch(community_local_id, &req, &db, &ctx).await?;
+
             let community_ap_id = if row.get(9) {
                 crate::apub_util::LocalObjectRef::Community(community_local_id).to_local_uri(&ctx.host_url_apub)
             } else {
@@ -673,6 +891,7 @@ async fn handler_comments_create_get(
             let info = crate::CommentInfo {
                 author: Some(UserLocalID(row.get(0))),
                 created: row.get(3),
+                updated: None,
                 content_text,
                 content_markdown,
                 content_html,
@@ -718,17 +937,11 @@ async fn handler_comments_create_get(
                 },
             };
 
-            let body = crate::apub_util::local_comment_to_create_ap(&info, &post_ap_id, parent_ap_id.map(From::from), post_or_parent_author_ap_id.map(From::from), community_ap_id.into(), &ctx)?;
-
-            let body = serde_json::to_vec(&body)?.into();
+            let body = crate::apub_util::local_comment_to_create_ap(&info, &post_ap_id, parent_ap_id.map(From::from), post_or_parent_author_ap_id.map(From::from), community_ap_id.into(), community_private, &ctx)?;
 
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
+            let body = serde_json::to_vec(&body)?;
 
-            Ok(resp)
+            Ok(crate::apub_util::activity_response(body))
         },
     }
 }
@@ -777,19 +990,23 @@ async fn handler_comments_delete_get(
                 &ctx.host_url_apub,
             )?;
 
-            let body = serde_json::to_vec(&body)?.into();
+            let body = serde_json::to_vec(&body)?;
 
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
-
-            Ok(resp)
+            Ok(crate::apub_util::activity_response(body))
         }
     }
 }
 
+async fn handler_comments_dislikes_get(
+    params: (CommentLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id, user_id) = params;
+
+    get_comment_vote(comment_id, user_id, true, ctx).await
+}
+
 async fn handler_comments_likes_get(
     params: (CommentLocalID, UserLocalID),
     ctx: Arc<crate::RouteContext>,
@@ -797,12 +1014,21 @@ async fn handler_comments_likes_get(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (comment_id, user_id) = params;
 
+    get_comment_vote(comment_id, user_id, false, ctx).await
+}
+
+async fn get_comment_vote(
+    comment_id: CommentLocalID,
+    user_id: UserLocalID,
+    is_dislike: bool,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let db = ctx.db_pool.get().await?;
 
     let like_row = db
         .query_opt(
-            "SELECT local FROM reply_like WHERE reply=$1 AND person=$2",
-            &[&comment_id, &user_id.raw()],
+            "SELECT local FROM reply_like WHERE reply=$1 AND person=$2 AND is_dislike=$3",
+            &[&comment_id, &user_id, &is_dislike],
         )
         .await?;
     if let Some(like_row) = like_row {
@@ -832,28 +1058,99 @@ async fn handler_comments_likes_get(
                     .transpose()?
             };
 
-            let like = crate::apub_util::local_comment_like_to_ap(
+            let body = if is_dislike {
+                serde_json::to_vec(&crate::apub_util::local_comment_dislike_to_ap(
+                    comment_id,
+                    comment_ap_id,
+                    author_ap_id,
+                    user_id,
+                    &ctx.host_url_apub,
+                )?)?
+            } else {
+                serde_json::to_vec(&crate::apub_util::local_comment_like_to_ap(
+                    comment_id,
+                    comment_ap_id,
+                    author_ap_id,
+                    user_id,
+                    &ctx.host_url_apub,
+                )?)?
+            };
+
+            Ok(crate::apub_util::activity_response(body))
+        } else {
+            Ok(crate::simple_response(
+                hyper::StatusCode::BAD_REQUEST,
+                "Requested like is not owned by this instance",
+            ))
+        }
+    } else {
+        Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such like",
+        ))
+    }
+}
+
+async fn handler_comments_reactions_get(
+    params: (CommentLocalID, i64),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (comment_id, reaction_id) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let row = db
+        .query_opt(
+            "SELECT reply_reaction.local, reply_reaction.person, reply_reaction.content, reply.local, reply.ap_id, author.id, author.ap_id FROM reply_reaction INNER JOIN reply ON (reply.id = reply_reaction.reply) LEFT OUTER JOIN person AS author ON (author.id = reply.author) WHERE reply_reaction.reply=$1 AND reply_reaction.id=$2",
+            &[&comment_id, &reaction_id],
+        )
+        .await?;
+    if let Some(row) = row {
+        let local: bool = row.get(0);
+
+        if local {
+            let comment_local = row.get(3);
+            let comment_ap_id = if comment_local {
+                crate::apub_util::LocalObjectRef::Comment(comment_id)
+                    .to_local_uri(&ctx.host_url_apub)
+            } else {
+                std::str::FromStr::from_str(row.get(4))?
+            };
+
+            let author_ap_id = if comment_local {
+                Some(
+                    crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(5)))
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
+                )
+            } else {
+                row.get::<_, Option<&str>>(6)
+                    .map(|x| x.parse())
+                    .transpose()?
+            };
+
+            let body = serde_json::to_vec(&crate::apub_util::local_comment_reaction_to_ap(
                 comment_id,
+                reaction_id,
                 comment_ap_id,
                 author_ap_id,
-                user_id,
+                UserLocalID(row.get(1)),
+                row.get(2),
                 &ctx.host_url_apub,
-            )?;
-            let body = serde_json::to_vec(&like)?.into();
+            )?)?;
 
-            Ok(hyper::Response::builder()
-                .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                .body(body)?)
+            Ok(crate::apub_util::activity_response(body))
         } else {
             Ok(crate::simple_response(
                 hyper::StatusCode::BAD_REQUEST,
-                "Requested like is not owned by this instance",
+                "Requested reaction is not owned by this instance",
             ))
         }
     } else {
         Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
-            "No such like",
+            "No such reaction",
         ))
     }
 }
@@ -869,7 +1166,7 @@ async fn handler_comment_like_undos_get(
 
     let undo_row = db
         .query_opt(
-            "SELECT reply.id, local_reply_like_undo.person, reply_author.id, reply_author.ap_id, reply_author.local FROM local_reply_like_undo INNER JOIN reply ON (reply.id = local_reply_like_undo.reply) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) WHERE local_reply_like_undo.id=$1",
+            "SELECT reply.id, local_reply_like_undo.person, reply_author.id, reply_author.ap_id, reply_author.local, local_reply_like_undo.is_dislike FROM local_reply_like_undo INNER JOIN reply ON (reply.id = local_reply_like_undo.reply) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) WHERE local_reply_like_undo.id=$1",
             &[&undo_id],
         )
         .await?;
@@ -894,15 +1191,14 @@ async fn handler_comment_like_undos_get(
         let undo = crate::apub_util::local_comment_like_undo_to_ap(
             undo_id,
             comment_id,
+            undo_row.get(5),
             author_ap_id,
             user_id,
             &ctx.host_url_apub,
         )?;
-        let body = serde_json::to_vec(&undo)?.into();
+        let body = serde_json::to_vec(&undo)?;
 
-        Ok(hyper::Response::builder()
-            .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-            .body(body)?)
+        Ok(crate::apub_util::activity_response(body))
     } else {
         Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
@@ -911,6 +1207,58 @@ async fn handler_comment_like_undos_get(
     }
 }
 
+async fn handler_comment_reaction_undos_get(
+    params: (uuid::Uuid,),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (undo_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let undo_row = db
+        .query_opt(
+            "SELECT reply.id, local_reply_reaction_undo.person, local_reply_reaction_undo.reaction, reply_author.id, reply_author.ap_id, reply_author.local FROM local_reply_reaction_undo INNER JOIN reply ON (reply.id = local_reply_reaction_undo.reply) LEFT OUTER JOIN person AS reply_author ON (reply_author.id = reply.author) WHERE local_reply_reaction_undo.id=$1",
+            &[&undo_id],
+        )
+        .await?;
+
+    if let Some(undo_row) = undo_row {
+        let comment_id = CommentLocalID(undo_row.get(0));
+        let user_id = UserLocalID(undo_row.get(1));
+
+        let author_ap_id = match undo_row.get(5) {
+            None => None,
+            Some(true) => Some(
+                crate::apub_util::LocalObjectRef::User(UserLocalID(undo_row.get(3)))
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into(),
+            ),
+            Some(false) => undo_row
+                .get::<_, Option<&str>>(4)
+                .map(|x| x.parse())
+                .transpose()?,
+        };
+
+        let undo = crate::apub_util::local_comment_reaction_undo_to_ap(
+            undo_id,
+            comment_id,
+            undo_row.get(2),
+            author_ap_id,
+            user_id,
+            &ctx.host_url_apub,
+        )?;
+        let body = serde_json::to_vec(&undo)?;
+
+        Ok(crate::apub_util::activity_response(body))
+    } else {
+        Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such reaction undo",
+        ))
+    }
+}
+
 async fn handler_community_follow_undos_get(
     params: (uuid::Uuid,),
     ctx: Arc<crate::RouteContext>,
@@ -945,11 +1293,9 @@ async fn handler_community_follow_undos_get(
             user_id,
             &ctx.host_url_apub,
         )?;
-        let body = serde_json::to_vec(&undo)?.into();
+        let body = serde_json::to_vec(&undo)?;
 
-        Ok(hyper::Response::builder()
-            .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-            .body(body)?)
+        Ok(crate::apub_util::activity_response(body))
     } else {
         Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
@@ -978,7 +1324,7 @@ async fn handler_post_like_undos_get(
 
     let undo_row = db
         .query_opt(
-            "SELECT post.id, local_post_like_undo.person, post_author.id, post_author.ap_id, post_author.local FROM local_post_like_undo INNER JOIN post ON (post.id = local_post_like_undo.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE local_post_like_undo.id=$1",
+            "SELECT post.id, local_post_like_undo.person, post_author.id, post_author.ap_id, post_author.local, local_post_like_undo.is_dislike FROM local_post_like_undo INNER JOIN post ON (post.id = local_post_like_undo.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE local_post_like_undo.id=$1",
             &[&undo_id],
         )
         .await?;
@@ -1003,15 +1349,14 @@ async fn handler_post_like_undos_get(
         let undo = crate::apub_util::local_post_like_undo_to_ap(
             undo_id,
             post_id,
+            undo_row.get(5),
             author_ap_id,
             user_id,
             &ctx.host_url_apub,
         )?;
-        let body = serde_json::to_vec(&undo)?.into();
+        let body = serde_json::to_vec(&undo)?;
 
-        Ok(hyper::Response::builder()
-            .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-            .body(body)?)
+        Ok(crate::apub_util::activity_response(body))
     } else {
         Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
@@ -1019,3 +1364,55 @@ async fn handler_post_like_undos_get(
         ))
     }
 }
+
+async fn handler_post_reaction_undos_get(
+    params: (uuid::Uuid,),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (undo_id,) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let undo_row = db
+        .query_opt(
+            "SELECT post.id, local_post_reaction_undo.person, local_post_reaction_undo.reaction, post_author.id, post_author.ap_id, post_author.local FROM local_post_reaction_undo INNER JOIN post ON (post.id = local_post_reaction_undo.post) LEFT OUTER JOIN person AS post_author ON (post_author.id = post.author) WHERE local_post_reaction_undo.id=$1",
+            &[&undo_id],
+        )
+        .await?;
+
+    if let Some(undo_row) = undo_row {
+        let post_id = PostLocalID(undo_row.get(0));
+        let user_id = UserLocalID(undo_row.get(1));
+
+        let author_ap_id = match undo_row.get(5) {
+            None => None,
+            Some(true) => Some(
+                crate::apub_util::LocalObjectRef::User(UserLocalID(undo_row.get(3)))
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into(),
+            ),
+            Some(false) => undo_row
+                .get::<_, Option<&str>>(4)
+                .map(|x| x.parse())
+                .transpose()?,
+        };
+
+        let undo = crate::apub_util::local_post_reaction_undo_to_ap(
+            undo_id,
+            post_id,
+            undo_row.get(2),
+            author_ap_id,
+            user_id,
+            &ctx.host_url_apub,
+        )?;
+        let body = serde_json::to_vec(&undo)?;
+
+        Ok(crate::apub_util::activity_response(body))
+    } else {
+        Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such reaction undo",
+        ))
+    }
+}
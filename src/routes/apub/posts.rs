@@ -1,4 +1,4 @@
-use crate::types::{CommunityLocalID, PollLocalID, PollOptionLocalID, PostLocalID, UserLocalID};
+use crate::types::{CommunityLocalID, PollOptionLocalID, PostLocalID, UserLocalID};
 use activitystreams::prelude::*;
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -17,12 +17,26 @@ pub fn route_posts() -> crate::RouteNode<()> {
                 crate::RouteNode::new()
                     .with_handler_async(hyper::Method::GET, handler_posts_delete_get),
             )
+            .with_child(
+                "dislikes",
+                crate::RouteNode::new().with_child_parse::<UserLocalID, _>(
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::GET, handler_posts_dislikes_get),
+                ),
+            )
             .with_child(
                 "likes",
                 crate::RouteNode::new().with_child_parse::<UserLocalID, _>(
                     crate::RouteNode::new()
                         .with_handler_async(hyper::Method::GET, handler_posts_likes_get),
                 ),
+            )
+            .with_child(
+                "reactions",
+                crate::RouteNode::new().with_child_parse::<i64, _>(
+                    crate::RouteNode::new()
+                        .with_handler_async(hyper::Method::GET, handler_posts_reactions_get),
+                ),
             ),
     )
 }
@@ -30,154 +44,64 @@ pub fn route_posts() -> crate::RouteNode<()> {
 async fn handler_posts_get(
     params: (PostLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
     let db = ctx.db_pool.get().await?;
 
-    match db
-        .query_opt(
-            "SELECT post.author, post.href, post.title, post.created, post.community, post.local, post.deleted, post.had_href, post.content_text, post.content_markdown, post.content_html, community.ap_id, community.ap_outbox, community.local, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, poll.id, post.sensitive FROM post INNER JOIN community ON (post.community = community.id) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.id=$1",
-            &[&post_id.raw()],
-        )
-        .await?
-    {
-        None => Ok(crate::simple_response(
+    if super::html::wants_html(&req) {
+        return super::html::html_response(super::html::HtmlTarget::Post(post_id), &db, &ctx).await;
+    }
+
+    match crate::apub_util::get_local_post_ap(post_id, &db, &ctx).await? {
+        crate::apub_util::LocalPostAP::NotFound => Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
             "No such post",
         )),
-        Some(row) => {
-            let local: bool = row.get(5);
-
-            if !local {
-                return Err(crate::Error::UserError(crate::simple_response(
-                    hyper::StatusCode::BAD_REQUEST,
-                    "Requested post is not owned by this instance",
-                )));
-            }
-
-            if row.get(6) {
-                let had_href: Option<bool> = row.get(7);
-                let poll_id = row.get::<_, Option<_>>(18).map(PollLocalID);
-
-                let mut body = activitystreams::object::Tombstone::new();
-                body
-                    .set_former_type(
-                        (if poll_id.is_some() { "Question" } else { if had_href == Some(true) { "Page" } else { "Note" } }).to_owned()
-                    )
-                    .set_context(activitystreams::context())
-                    .set_id(crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub).into());
-
-                let body = serde_json::to_vec(&body)?.into();
-
-                let mut resp = hyper::Response::new(body);
-                *resp.status_mut() = hyper::StatusCode::GONE;
-                resp.headers_mut().insert(
-                    hyper::header::CONTENT_TYPE,
-                    hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
+        crate::apub_util::LocalPostAP::NotLocal => {
+            Err(crate::Error::UserError(crate::simple_response(
+                hyper::StatusCode::BAD_REQUEST,
+                "Requested post is not owned by this instance",
+            )))
+        }
+        crate::apub_util::LocalPostAP::Deleted { former_type } => {
+            let mut body = activitystreams::object::Tombstone::new();
+            body.set_former_type(former_type.to_owned())
+                .set_many_contexts(crate::apub_util::default_contexts())
+                .set_id(
+                    crate::apub_util::LocalObjectRef::Post(post_id)
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
                 );
 
-                return Ok(resp);
-            }
-
-            let community_local_id = CommunityLocalID(row.get(4));
-            let community_local: bool = row.get(13);
-
-            let community_ap_id = match row.get(11) {
-                Option::<&str>::Some(ap_id) => ap_id.parse()?,
-                None => {
-                    if community_local {
-                        crate::apub_util::LocalObjectRef::Community(community_local_id).to_local_uri(&ctx.host_url_apub)
-                    } else {
-                        return Err(crate::Error::InternalStrStatic("Missing community AP id"));
-                    }
-                }
-            };
-
-            let community_ap_outbox = match row.get(12) {
-                Option::<&str>::Some(ap_outbox) => Some(ap_outbox.parse()?),
-                None => {
-                    if community_local {
-                        Some(crate::apub_util::LocalObjectRef::CommunityOutbox(community_local_id).to_local_uri(&ctx.host_url_apub))
-                    } else {
-                        None
-                    }
-                }
-            };
-
-            let community_ap_followers = match row.get(14) {
-                Option::<&str>::Some(ap_followers) => Some(ap_followers.parse()?),
-                None => {
-                    if community_local {
-                        Some(crate::apub_util::LocalObjectRef::CommunityFollowers(community_local_id).to_local_uri(&ctx.host_url_apub))
-                    } else {
-                        None
-                    }
-                }
-            };
-
-            let closed_at: Option<chrono::DateTime<chrono::FixedOffset>>;
-
-            let poll = if let Some(multiple) = row.get(15) {
-                Some({
-                    let options: Vec<_> = row.get::<_, Vec<postgres_types::Json<(i64, &str, i64)>>>(16)
-                        .into_iter()
-                        .map(|x| x.0)
-                        .map(|(id, name, votes): (i64, &str, i64)| {
-                            crate::PollOption {
-                                id: PollOptionLocalID(id),
-                                name,
-                                votes: votes as u32,
-                            }
-                        })
-                        .collect();
-
-                    closed_at = row.get(17);
-
-                    Cow::Owned(crate::PollInfo {
-                        multiple,
-                        options: Cow::Owned(options),
-                        closed_at: closed_at.as_ref(),
-                    })
-                })
-            } else {
-                None
-            };
-
-            let post_info = crate::PostInfo {
-                author: Some(UserLocalID(row.get(0))),
-                community: community_local_id,
-                created: &row.get(3),
-                href: row.get(1),
-                content_text: row.get(8),
-                content_markdown: row.get(9),
-                content_html: row.get(10),
-                id: post_id,
-                title: row.get(2),
-                poll,
-                sensitive: row.get(19),
-            };
-
-            let body = crate::apub_util::post_to_ap(&post_info, community_ap_id.into(), community_ap_outbox.map(Into::into), community_ap_followers.map(Into::into), &ctx)?;
+            let body = serde_json::to_vec(&body)?;
 
-            let body = serde_json::to_vec(&body)?.into();
-
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
+            let mut resp = crate::apub_util::activity_response(body);
+            *resp.status_mut() = hyper::StatusCode::GONE;
 
             Ok(resp)
-        },
+        }
+        crate::apub_util::LocalPostAP::Found {
+            object,
+            community,
+            modified_at,
+            ..
+        } => {
+            let private =
+                crate::apub_util::check_local_community_fetch(community, &req, &db, &ctx).await?;
+
+            crate::apub_util::cached_activity_response(&req, modified_at, private, || {
+                Ok(serde_json::to_vec(&object)?)
+            })
+        }
     }
 }
 
 async fn handler_posts_create_get(
     params: (PostLocalID,),
     ctx: Arc<crate::RouteContext>,
-    _req: hyper::Request<hyper::Body>,
+    req: hyper::Request<hyper::Body>,
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id,) = params;
 
@@ -185,8 +109,8 @@ async fn handler_posts_create_get(
 
     match db
         .query_opt(
-            "SELECT post.author, post.href, post.title, post.created, post.community, post.local, post.deleted, post.content_text, post.content_markdown, post.content_html, community.ap_id, community.ap_outbox, community.local, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, post.sensitive FROM post INNER JOIN community ON (community.id = post.community) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.id=$1",
-            &[&post_id.raw()],
+            "SELECT post.author, post.href, post.title, post.created, post.community, post.local, post.deleted, post.content_text, post.content_markdown, post.content_html, community.ap_id, community.ap_outbox, community.local, community.ap_followers, poll.multiple, (SELECT array_agg(jsonb_build_array(id, name, (SELECT COUNT(*) FROM poll_vote WHERE poll_id = poll.id AND option_id = poll_option.id)) ORDER BY position ASC) FROM poll_option WHERE poll_id=poll.id), poll.closed_at, post.sensitive, (SELECT COALESCE(json_agg(json_build_object('url', url, 'media_type', media_type, 'alt_text', alt_text) ORDER BY position), '[]') FROM post_attachment WHERE post=post.id), (SELECT COALESCE(array_agg(community_flair.name ORDER BY community_flair.name), '{}') FROM post_flair INNER JOIN community_flair ON (community_flair.id = post_flair.flair) WHERE post_flair.post = post.id), post.language, post.sticky, post.locked, community.link_posts_as_notes FROM post INNER JOIN community ON (community.id = post.community) LEFT OUTER JOIN poll ON (poll.id = post.poll_id) WHERE post.id=$1",
+            &[&post_id],
         )
         .await?
     {
@@ -214,6 +138,14 @@ async fn handler_posts_create_get(
             let community_local_id = CommunityLocalID(row.get(4));
             let community_local: bool = row.get(12);
 
+            let community_private = crate::apub_util::check_local_community_fetch(
+                community_local_id,
+                &req,
+                &db,
+                &ctx,
+            )
+            .await?;
+
             let community_ap_id = match row.get(10) {
                 Option::<&str>::Some(ap_id) => ap_id.parse()?,
                 None => {
@@ -278,6 +210,8 @@ async fn handler_posts_create_get(
             let post_info = crate::PostInfo {
                 author: Some(UserLocalID(row.get(0))),
                 community: community_local_id,
+                community_private,
+                community_link_posts_as_notes: row.get(23),
                 created: &row.get(3),
                 href: row.get(1),
                 content_text: row.get(7),
@@ -287,19 +221,18 @@ async fn handler_posts_create_get(
                 title: row.get(2),
                 poll,
                 sensitive: row.get(17),
+                sticky: row.get(21),
+                locked: row.get(22),
+                attachments: Cow::Owned(row.get::<_, postgres_types::Json<Vec<crate::PostAttachment>>>(18).0),
+                flairs: Cow::Owned(row.get(19)),
+                language: row.get(20),
             };
 
             let body = crate::apub_util::local_post_to_create_ap(&post_info, community_ap_id.into(), community_ap_outbox.map(Into::into), community_ap_followers.map(Into::into), &ctx)?;
 
-            let body = serde_json::to_vec(&body)?.into();
-
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
+            let body = serde_json::to_vec(&body)?;
 
-            Ok(resp)
+            Ok(crate::apub_util::activity_response(body))
         },
     }
 }
@@ -316,7 +249,7 @@ async fn handler_posts_delete_get(
     match db
         .query_opt(
             "SELECT author, local, deleted FROM post WHERE id=$1",
-            &[&post_id.raw()],
+            &[&post_id],
         )
         .await?
     {
@@ -346,19 +279,23 @@ async fn handler_posts_delete_get(
             let body =
                 crate::apub_util::local_post_delete_to_ap(post_id, author, &ctx.host_url_apub)?;
 
-            let body = serde_json::to_vec(&body)?.into();
-
-            let mut resp = hyper::Response::new(body);
-            resp.headers_mut().insert(
-                hyper::header::CONTENT_TYPE,
-                hyper::header::HeaderValue::from_static(crate::apub_util::ACTIVITY_TYPE),
-            );
+            let body = serde_json::to_vec(&body)?;
 
-            Ok(resp)
+            Ok(crate::apub_util::activity_response(body))
         }
     }
 }
 
+async fn handler_posts_dislikes_get(
+    params: (PostLocalID, UserLocalID),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id, user_id) = params;
+
+    get_post_vote(post_id, user_id, true, ctx).await
+}
+
 async fn handler_posts_likes_get(
     params: (PostLocalID, UserLocalID),
     ctx: Arc<crate::RouteContext>,
@@ -366,12 +303,21 @@ async fn handler_posts_likes_get(
 ) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let (post_id, user_id) = params;
 
+    get_post_vote(post_id, user_id, false, ctx).await
+}
+
+async fn get_post_vote(
+    post_id: PostLocalID,
+    user_id: UserLocalID,
+    is_dislike: bool,
+    ctx: Arc<crate::RouteContext>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
     let db = ctx.db_pool.get().await?;
 
     let like_row = db
         .query_opt(
-            "SELECT local FROM post_like WHERE post=$1 AND person=$2",
-            &[&post_id.raw(), &user_id],
+            "SELECT local FROM post_like WHERE post=$1 AND person=$2 AND is_dislike=$3",
+            &[&post_id, &user_id, &is_dislike],
         )
         .await?;
     if let Some(like_row) = like_row {
@@ -381,7 +327,7 @@ async fn handler_posts_likes_get(
             let row = db
                 .query_one(
                     "SELECT post.local, post.ap_id, person.local, person.id, person.ap_id FROM post INNER JOIN person ON (person.id = post.author) WHERE post.id=$1",
-                    &[&post_id.raw()],
+                    &[&post_id],
                 )
                 .await?;
             let post_local = row.get(0);
@@ -403,28 +349,99 @@ async fn handler_posts_likes_get(
                     .transpose()?
             };
 
-            let like = crate::apub_util::local_post_like_to_ap(
+            let body = if is_dislike {
+                serde_json::to_vec(&crate::apub_util::local_post_dislike_to_ap(
+                    post_id,
+                    post_ap_id,
+                    author_ap_id,
+                    user_id,
+                    &ctx.host_url_apub,
+                )?)?
+            } else {
+                serde_json::to_vec(&crate::apub_util::local_post_like_to_ap(
+                    post_id,
+                    post_ap_id,
+                    author_ap_id,
+                    user_id,
+                    &ctx.host_url_apub,
+                )?)?
+            };
+
+            Ok(crate::apub_util::activity_response(body))
+        } else {
+            Ok(crate::simple_response(
+                hyper::StatusCode::BAD_REQUEST,
+                "Requested like is not owned by this instance",
+            ))
+        }
+    } else {
+        Ok(crate::simple_response(
+            hyper::StatusCode::NOT_FOUND,
+            "No such like",
+        ))
+    }
+}
+
+async fn handler_posts_reactions_get(
+    params: (PostLocalID, i64),
+    ctx: Arc<crate::RouteContext>,
+    _req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (post_id, reaction_id) = params;
+
+    let db = ctx.db_pool.get().await?;
+
+    let row = db
+        .query_opt(
+            "SELECT post_reaction.local, post_reaction.person, post_reaction.content, post.local, post.ap_id, person.local, person.id, person.ap_id FROM post_reaction INNER JOIN post ON (post.id = post_reaction.post) LEFT OUTER JOIN person ON (person.id = post.author) WHERE post_reaction.post=$1 AND post_reaction.id=$2",
+            &[&post_id, &reaction_id],
+        )
+        .await?;
+    if let Some(row) = row {
+        let local: bool = row.get(0);
+
+        if local {
+            let post_local = row.get(3);
+            let post_ap_id = if post_local {
+                crate::apub_util::LocalObjectRef::Post(post_id).to_local_uri(&ctx.host_url_apub)
+            } else {
+                std::str::FromStr::from_str(row.get(4))?
+            };
+
+            let author_ap_id = match row.get(5) {
+                None => None,
+                Some(true) => Some(
+                    crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(6)))
+                        .to_local_uri(&ctx.host_url_apub)
+                        .into(),
+                ),
+                Some(false) => row
+                    .get::<_, Option<&str>>(7)
+                    .map(|x| x.parse())
+                    .transpose()?,
+            };
+
+            let body = serde_json::to_vec(&crate::apub_util::local_post_reaction_to_ap(
                 post_id,
+                reaction_id,
                 post_ap_id,
                 author_ap_id,
-                user_id,
+                UserLocalID(row.get(1)),
+                row.get(2),
                 &ctx.host_url_apub,
-            )?;
-            let body = serde_json::to_vec(&like)?.into();
+            )?)?;
 
-            Ok(hyper::Response::builder()
-                .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-                .body(body)?)
+            Ok(crate::apub_util::activity_response(body))
         } else {
             Ok(crate::simple_response(
                 hyper::StatusCode::BAD_REQUEST,
-                "Requested like is not owned by this instance",
+                "Requested reaction is not owned by this instance",
             ))
         }
     } else {
         Ok(crate::simple_response(
             hyper::StatusCode::NOT_FOUND,
-            "No such like",
+            "No such reaction",
         ))
     }
 }
@@ -0,0 +1,402 @@
+use crate::lang;
+use crate::types::{CommunityLocalID, PostLocalID, UserLocalID};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::sync::Arc;
+
+const FEED_LIMIT: i64 = 30;
+
+#[derive(Clone, Copy)]
+enum FeedFormat {
+    Atom,
+    Rss,
+}
+
+impl FeedFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom",
+            FeedFormat::Rss => "rss",
+        }
+    }
+}
+
+/// Splits a path segment like `12.atom` into its ID and format
+fn parse_feed_segment<T: std::str::FromStr>(src: &str) -> Option<(T, FeedFormat)> {
+    let (id, ext) = src.rsplit_once('.')?;
+    let format = match ext {
+        "atom" => FeedFormat::Atom,
+        "rss" => FeedFormat::Rss,
+        _ => return None,
+    };
+
+    Some((id.parse().ok()?, format))
+}
+
+pub struct FeedEntry {
+    id: String,
+    link: String,
+    title: String,
+    author_name: Option<String>,
+    author_uri: Option<String>,
+    created: chrono::DateTime<chrono::FixedOffset>,
+    content_html: Option<String>,
+}
+
+pub struct FeedInfo {
+    pub id: String,
+    pub title: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+/// Fetches the newest posts matching `filter_sql`, which may refer to `$2` as `param`
+pub async fn fetch_feed_entries(
+    filter_sql: &str,
+    param: Option<&(dyn tokio_postgres::types::ToSql + Sync)>,
+    db: &crate::query::DbClient,
+    ctx: &crate::BaseContext,
+) -> Result<Vec<FeedEntry>, crate::Error> {
+    let mut values: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&FEED_LIMIT];
+    if let Some(param) = param {
+        values.push(param);
+    }
+
+    let sql: &str = &format!(
+        "SELECT post.id, post.author, post.href, post.content_text, post.title, post.created, post.content_html, post.ap_id, post.local, person.username, person.local, person.ap_id FROM community, post LEFT OUTER JOIN person ON (person.id = post.author) WHERE post.community = community.id AND post.approved=TRUE AND post.deleted=FALSE AND NOT community.deleted AND NOT community.private AND {} ORDER BY {} LIMIT $1",
+        filter_sql,
+        super::api::SortType::New.post_sort_sql(),
+    );
+
+    let rows = crate::query::query(db, sql, &values).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let post_id = PostLocalID(row.get(0));
+
+            let href_raw: Option<&str> = row.get(2);
+            let href = ctx.process_href_opt(href_raw.map(Cow::Borrowed), post_id);
+
+            let local_url = crate::apub_util::LocalObjectRef::Post(post_id)
+                .to_local_uri(&ctx.host_url_apub)
+                .to_string();
+
+            let id = if row.get(8) {
+                local_url.clone()
+            } else {
+                row.get::<_, Option<String>>(7)
+                    .unwrap_or_else(|| local_url.clone())
+            };
+
+            let author_uri = if row.get(10) {
+                Some(
+                    crate::apub_util::LocalObjectRef::User(UserLocalID(row.get(1)))
+                        .to_local_uri(&ctx.host_url_apub)
+                        .to_string(),
+                )
+            } else {
+                row.get(11)
+            };
+
+            let content = row
+                .get::<_, Option<&str>>(6)
+                .map(Cow::Borrowed)
+                .or_else(|| {
+                    let content_text: Option<&str> = row.get(3);
+                    content_text.map(|content_text| Cow::Owned(ammonia::clean_text(content_text)))
+                });
+
+            let link_content = href.map(|href| {
+                format!(
+                    r#"<p><a href="{0}">{0}</a></p>"#,
+                    ammonia::clean_text(&href)
+                )
+            });
+
+            let content_html = match (content, link_content) {
+                (None, None) => None,
+                (Some(content), None) => Some(content.into_owned()),
+                (None, Some(link_content)) => Some(link_content),
+                (Some(content), Some(link_content)) => Some(format!("{}{}", content, link_content)),
+            };
+
+            FeedEntry {
+                id,
+                link: local_url,
+                title: row.get(4),
+                author_name: row.get(9),
+                author_uri,
+                created: row.get(5),
+                content_html,
+            }
+        })
+        .collect())
+}
+
+pub fn render_atom(feed: FeedInfo) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let mut builder = atom_syndication::FeedBuilder::default();
+    builder.title(feed.title);
+    builder.id(feed.id);
+    builder.generator(atom_syndication::Generator {
+        value: "lotide".to_owned(),
+        uri: Some("https://sr.ht/~vpzom/lotide".to_owned()),
+        version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+    });
+
+    if let Some(entry) = feed.entries.first() {
+        builder.updated(entry.created);
+    }
+
+    for entry in feed.entries {
+        let mut entry_builder = atom_syndication::EntryBuilder::default();
+        entry_builder.title(entry.title);
+        entry_builder.id(entry.id);
+        entry_builder.updated(entry.created);
+        if let Some(author_name) = entry.author_name {
+            entry_builder.author(atom_syndication::Person {
+                name: author_name,
+                email: None,
+                uri: entry.author_uri,
+            });
+        }
+        entry_builder.published(entry.created);
+        entry_builder.link(
+            atom_syndication::LinkBuilder::default()
+                .href(entry.link)
+                .rel("self")
+                .build(),
+        );
+        entry_builder.content(atom_syndication::Content {
+            content_type: Some("html".to_owned()),
+            value: entry.content_html,
+            ..Default::default()
+        });
+
+        builder.entry(entry_builder.build());
+    }
+
+    let mut output = Vec::new();
+    builder.build().write_to(&mut output)?;
+
+    Ok(crate::common_response_builder()
+        .header(hyper::header::CONTENT_TYPE, "application/atom+xml")
+        .body(hyper::Body::from(output))?)
+}
+
+fn render_rss(feed: FeedInfo) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let mut output = String::new();
+
+    write!(
+        output,
+        r#"<?xml version="1.0" encoding="utf-8"?><rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel><title>{0}</title><link>{1}</link><description>{0}</description><generator>lotide {2}</generator>"#,
+        v_htmlescape::escape(&feed.title),
+        v_htmlescape::escape(&feed.id),
+        env!("CARGO_PKG_VERSION"),
+    )
+    .unwrap();
+
+    if let Some(entry) = feed.entries.first() {
+        write!(
+            output,
+            "<lastBuildDate>{}</lastBuildDate>",
+            entry.created.to_rfc2822()
+        )
+        .unwrap();
+    }
+
+    for entry in feed.entries {
+        write!(
+            output,
+            r#"<item><title>{}</title><link>{}</link><guid isPermaLink="false">{}</guid><pubDate>{}</pubDate>"#,
+            v_htmlescape::escape(&entry.title),
+            v_htmlescape::escape(&entry.link),
+            v_htmlescape::escape(&entry.id),
+            entry.created.to_rfc2822(),
+        )
+        .unwrap();
+
+        if let Some(author_name) = &entry.author_name {
+            write!(
+                output,
+                "<dc:creator>{}</dc:creator>",
+                v_htmlescape::escape(author_name)
+            )
+            .unwrap();
+        }
+        if let Some(content_html) = &entry.content_html {
+            write!(
+                output,
+                "<description>{}</description>",
+                v_htmlescape::escape(content_html)
+            )
+            .unwrap();
+        }
+
+        output.push_str("</item>");
+    }
+
+    output.push_str("</channel></rss>");
+
+    Ok(crate::common_response_builder()
+        .header(hyper::header::CONTENT_TYPE, "application/rss+xml")
+        .body(hyper::Body::from(output))?)
+}
+
+fn render_feed(
+    feed: FeedInfo,
+    format: FeedFormat,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    match format {
+        FeedFormat::Atom => render_atom(feed),
+        FeedFormat::Rss => render_rss(feed),
+    }
+}
+
+fn get_feed_url(path: &str, format: FeedFormat, ctx: &crate::RouteContext) -> String {
+    let mut url: url::Url = (*ctx.host_url_apub).clone();
+    url.set_path(&format!("/feeds/{}.{}", path, format.extension()));
+    url.into()
+}
+
+fn no_such_feed(lang: &crate::Translator) -> crate::Error {
+    crate::user_error(hyper::StatusCode::NOT_FOUND, lang, &lang::no_such_feed())
+}
+
+async fn route_feeds_community_get(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (segment,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+
+    let (community_id, format) =
+        parse_feed_segment::<CommunityLocalID>(&segment).ok_or_else(|| no_such_feed(&lang))?;
+
+    let db = ctx.get_db_read().await?;
+
+    let row = db
+        .query_opt(
+            "SELECT name FROM community WHERE id=$1 AND NOT deleted",
+            &[&community_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(
+                hyper::StatusCode::NOT_FOUND,
+                &lang,
+                &lang::no_such_community(),
+            )
+        })?;
+
+    let entries = fetch_feed_entries("community.id=$2", Some(&community_id), &db, &ctx).await?;
+
+    render_feed(
+        FeedInfo {
+            id: get_feed_url(&format!("community/{}", community_id), format, &ctx),
+            title: row.get(0),
+            entries,
+        },
+        format,
+    )
+}
+
+async fn route_feeds_user_get(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (segment,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+
+    let (user_id, format) =
+        parse_feed_segment::<UserLocalID>(&segment).ok_or_else(|| no_such_feed(&lang))?;
+
+    let db = ctx.get_db_read().await?;
+
+    let row = db
+        .query_opt(
+            "SELECT username FROM person WHERE id=$1 AND NOT suspended AND NOT deleted",
+            &[&user_id],
+        )
+        .await?
+        .ok_or_else(|| {
+            crate::user_error(hyper::StatusCode::NOT_FOUND, &lang, &lang::no_such_user())
+        })?;
+
+    let entries = fetch_feed_entries(
+        "post.author=$2 AND NOT community.unlisted",
+        Some(&user_id),
+        &db,
+        &ctx,
+    )
+    .await?;
+
+    render_feed(
+        FeedInfo {
+            id: get_feed_url(&format!("user/{}", user_id), format, &ctx),
+            title: row.get(0),
+            entries,
+        },
+        format,
+    )
+}
+
+async fn route_feeds_front_get(
+    params: (String,),
+    ctx: Arc<crate::RouteContext>,
+    req: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, crate::Error> {
+    let (segment,) = params;
+
+    let lang = crate::get_lang_for_req(&req);
+
+    let format = match segment.as_str() {
+        "front.atom" => FeedFormat::Atom,
+        "front.rss" => FeedFormat::Rss,
+        _ => return Err(no_such_feed(&lang)),
+    };
+
+    let db = ctx.get_db_read().await?;
+
+    // same filters as the aggregate post listing
+    let entries = fetch_feed_entries(
+        "NOT community.unlisted AND community.hide_posts_from_aggregates=FALSE",
+        None,
+        &db,
+        &ctx,
+    )
+    .await?;
+
+    render_feed(
+        FeedInfo {
+            id: get_feed_url("front", format, &ctx),
+            title: ctx.local_hostname.clone(),
+            entries,
+        },
+        format,
+    )
+}
+
+pub fn route_feeds() -> crate::RouteNode<()> {
+    crate::RouteNode::new()
+        .with_child(
+            "community",
+            crate::RouteNode::new().with_child_str(
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, route_feeds_community_get),
+            ),
+        )
+        .with_child(
+            "user",
+            crate::RouteNode::new().with_child_str(
+                crate::RouteNode::new()
+                    .with_handler_async(hyper::Method::GET, route_feeds_user_get),
+            ),
+        )
+        .with_child_str(
+            crate::RouteNode::new().with_handler_async(hyper::Method::GET, route_feeds_front_get),
+        )
+}
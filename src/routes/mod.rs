@@ -2,6 +2,7 @@ use crate::lang;
 
 mod api;
 mod apub;
+mod feeds;
 mod well_known;
 
 pub fn route_root() -> crate::RouteNode<()> {
@@ -15,5 +16,6 @@ pub fn route_root() -> crate::RouteNode<()> {
         })
         .with_child("apub", apub::route_apub())
         .with_child("api", api::route_api())
+        .with_child("feeds", feeds::route_feeds())
         .with_child(".well-known", well_known::route_well_known())
 }
@@ -26,6 +26,10 @@ async fn handler_nodeinfo_get(
             {
                 "rel": "http://nodeinfo.diaspora.software/ns/schema/2.0",
                 "href": format!("{}/unstable/nodeinfo/2.0", ctx.host_url_api),
+            },
+            {
+                "rel": "http://nodeinfo.diaspora.software/ns/schema/2.1",
+                "href": format!("{}/unstable/nodeinfo/2.1", ctx.host_url_api),
             }
         ]
     }))?
@@ -76,7 +80,10 @@ async fn handler_webfinger_get(
     let found: Option<(ActorLocalRef, Cow<'_, str>)> = match found_ref {
         Some(LocalRef::UserID(id)) => {
             let row = db
-                .query_opt("SELECT username FROM person WHERE id=$1 AND local", &[&id])
+                .query_opt(
+                    "SELECT username FROM person WHERE id=$1 AND local AND NOT deleted",
+                    &[&id],
+                )
                 .await?;
             row.map(|row| (ActorLocalRef::Person(id), Cow::Owned(row.get(0))))
         }
@@ -87,7 +94,7 @@ async fn handler_webfinger_get(
             row.map(|row| (ActorLocalRef::Community(id), Cow::Owned(row.get(0))))
         }
         Some(LocalRef::Name(name)) => {
-            let row = db.query_opt("(SELECT FALSE, id, username FROM person WHERE LOWER(username)=LOWER($1) AND local) UNION ALL (SELECT TRUE, id, name FROM community WHERE LOWER(name)=LOWER($1) AND local) LIMIT 1", &[&name]).await?;
+            let row = db.query_opt("(SELECT FALSE, id, username FROM person WHERE LOWER(username)=LOWER($1) AND local AND NOT deleted) UNION ALL (SELECT TRUE, id, name FROM community WHERE LOWER(name)=LOWER($1) AND local) LIMIT 1", &[&name]).await?;
             row.map(|row| {
                 let id = row.get(1);
                 (
@@ -1,7 +1,7 @@
 use crate::lang;
 use crate::types::{
     ActorLocalRef, CommentLocalID, CommunityLocalID, NotificationID, NotificationSubscriptionID,
-    PostLocalID, UserLocalID,
+    PostLocalID, UserLocalID, WebhookID,
 };
 
 use async_trait::async_trait;
@@ -9,10 +9,22 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::sync::Arc;
 
+// Pending tasks with a higher priority are run before any with a lower one, oldest first
+pub const PRIORITY_BACKGROUND: i16 = -10;
+pub const PRIORITY_NORMAL: i16 = 0;
+pub const PRIORITY_INTERACTIVE: i16 = 10;
+
 #[async_trait]
 pub trait TaskDef: Serialize + std::fmt::Debug + Sync {
     const KIND: &'static str;
     const MAX_ATTEMPTS: i16 = 8;
+    const PRIORITY: i16 = PRIORITY_NORMAL;
+
+    /// Remote host this task delivers to, used for queue stats and pausing delivery
+    fn destination_host(&self) -> Option<&str> {
+        None
+    }
+
     async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error>;
 }
 
@@ -26,8 +38,21 @@ pub struct DeliverToInbox<'a> {
 #[async_trait]
 impl<'a> TaskDef for DeliverToInbox<'a> {
     const KIND: &'static str = "deliver_to_inbox";
+    // deliveries queued one at a time are responses to something a user just did, fan-out to
+    // followers is queued separately in `deliver_to_follower_inboxes`
+    const PRIORITY: i16 = PRIORITY_INTERACTIVE;
+
+    fn destination_host(&self) -> Option<&str> {
+        crate::apub_util::compat::get_url_host_key(&self.inbox)
+    }
 
     async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        if self.inbox.scheme() != "https" && !ctx.dev_mode {
+            return Err(crate::Error::InternalStrStatic(
+                "Inbox URLs must be HTTPS in non-dev mode",
+            ));
+        }
+
         let db = ctx.db_pool.get().await?;
 
         let signing_info: Option<(_, _)> = match self.sign_as {
@@ -42,51 +67,78 @@ impl<'a> TaskDef for DeliverToInbox<'a> {
             ),
         };
 
-        let digest =
-            openssl::hash::hash(openssl::hash::MessageDigest::sha256(), self.object.as_ref())?;
-        let mut digest_header = "SHA-256=".to_owned();
-        base64::encode_config_buf(digest, base64::STANDARD, &mut digest_header);
+        let mut req = build_inbox_request(&self.inbox, self.object)?;
 
-        let inbox_uri = self.inbox.as_str().parse::<hyper::Uri>()?;
+        if let Some(ActorLocalRef::Community(community_id)) = self.sign_as {
+            if let Some(host) = crate::apub_util::compat::get_url_host_key(&self.inbox) {
+                let value = crate::apub_util::get_local_community_followers_sync_header(
+                    community_id,
+                    host,
+                    &db,
+                    &ctx.host_url_apub,
+                )
+                .await?;
 
-        let mut req = hyper::Request::post(&inbox_uri)
-            .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
-            .header("Digest", digest_header)
-            .body(self.object.into())?;
+                req.headers_mut()
+                    .insert("Collection-Synchronization", value);
+            }
+        }
 
-        req.headers_mut()
-            .entry(hyper::header::HOST)
-            .or_insert_with(|| {
-                let uri = inbox_uri;
-
-                let hostname = uri.host().expect("authority implies host");
-                if let Some(port) = uri.port() {
-                    let s = format!("{}:{}", hostname, port);
-                    hyper::header::HeaderValue::from_str(&s)
-                } else {
-                    hyper::header::HeaderValue::from_str(hostname)
-                }
-                .expect("uri host is valid header value")
-            });
-
-        if let Ok(path_and_query) = crate::get_path_and_query(&self.inbox) {
-            req.headers_mut()
-                .insert(hyper::header::DATE, crate::apub_util::now_http_date());
-
-            if let Some((privkey, key_id)) = signing_info {
-                let signature = hancock::Signature::create_legacy(
-                    key_id.as_str(),
-                    &hyper::Method::POST,
-                    &path_and_query,
-                    req.headers(),
-                    |src| crate::apub_util::do_sign(&privkey, &src),
-                )?;
-
-                req.headers_mut().insert("Signature", signature.to_header());
+        sign_inbox_request(
+            &mut req,
+            &self.inbox,
+            signing_info
+                .as_ref()
+                .map(|(privkey, key_id)| (privkey, key_id.as_str())),
+        )?;
+
+        let res = ctx.fetch(req).await?;
+
+        if res.status() == hyper::StatusCode::GONE {
+            // retrying won't help, but only a Gone actor ID is trusted to mean the actor was deleted
+            let rows = db.query(
+                "(SELECT ap_id FROM person WHERE NOT local AND NOT deleted AND ap_inbox=$1) UNION ALL (SELECT ap_id FROM community WHERE NOT local AND NOT deleted AND ap_inbox=$1)",
+                &[&self.inbox.as_str()],
+            ).await?;
+
+            let fetch_tasks: Vec<_> = rows
+                .iter()
+                .filter_map(|row| row.get::<_, Option<&str>>(0)?.parse().ok())
+                .map(|ap_id| FetchActor {
+                    actor_ap_id: Cow::Owned(ap_id),
+                })
+                .collect();
+
+            if !fetch_tasks.is_empty() {
+                ctx.enqueue_tasks(&fetch_tasks).await?;
             }
+
+            log::debug!("Inbox {} is gone, dropping delivery", self.inbox);
+
+            return Ok(());
         }
 
-        let res = crate::res_to_error(ctx.http_client.request(req).await?).await?;
+        if res.status() == hyper::StatusCode::UNAUTHORIZED {
+            // our copy of the recipient may be stale, e.g. after a key rotation or move
+            let rows = db.query(
+                "(SELECT ap_id FROM person WHERE NOT local AND (ap_inbox=$1 OR ap_shared_inbox=$1) AND (last_fetched_at IS NULL OR last_fetched_at < current_timestamp - INTERVAL '10 MINUTES') LIMIT 1) UNION ALL (SELECT ap_id FROM community WHERE NOT local AND (ap_inbox=$1 OR ap_shared_inbox=$1) AND (last_fetched_at IS NULL OR last_fetched_at < current_timestamp - INTERVAL '10 MINUTES') LIMIT 1)",
+                &[&self.inbox.as_str()],
+            ).await?;
+
+            let fetch_tasks: Vec<_> = rows
+                .iter()
+                .filter_map(|row| row.get::<_, Option<&str>>(0)?.parse().ok())
+                .map(|ap_id| FetchActor {
+                    actor_ap_id: Cow::Owned(ap_id),
+                })
+                .collect();
+
+            if !fetch_tasks.is_empty() {
+                ctx.enqueue_tasks(&fetch_tasks).await?;
+            }
+        }
+
+        let res = crate::res_to_error(res).await?;
 
         log::debug!("{:?}", res);
 
@@ -94,6 +146,105 @@ impl<'a> TaskDef for DeliverToInbox<'a> {
     }
 }
 
+/// Delivers an activity signed by the instance actor, as used for relay subscriptions
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeliverAsInstance<'a> {
+    pub inbox: Cow<'a, url::Url>,
+    pub object: String,
+}
+
+#[async_trait]
+impl<'a> TaskDef for DeliverAsInstance<'a> {
+    const KIND: &'static str = "deliver_as_instance";
+
+    fn destination_host(&self) -> Option<&str> {
+        crate::apub_util::compat::get_url_host_key(&self.inbox)
+    }
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        if self.inbox.scheme() != "https" && !ctx.dev_mode {
+            return Err(crate::Error::InternalStrStatic(
+                "Inbox URLs must be HTTPS in non-dev mode",
+            ));
+        }
+
+        let key_id = crate::apub_util::get_instance_actor_pubkey_apub_id(&ctx.host_url_apub);
+
+        let mut req = build_inbox_request(&self.inbox, self.object)?;
+        sign_inbox_request(
+            &mut req,
+            &self.inbox,
+            Some((&ctx.instance_actor_key, key_id.as_str())),
+        )?;
+
+        let res = ctx.fetch(req).await?;
+        let res = crate::res_to_error(res).await?;
+
+        log::debug!("{:?}", res);
+
+        Ok(())
+    }
+}
+
+fn build_inbox_request(
+    inbox: &url::Url,
+    object: String,
+) -> Result<hyper::Request<hyper::Body>, crate::Error> {
+    let digest = openssl::hash::hash(openssl::hash::MessageDigest::sha256(), object.as_ref())?;
+    let mut digest_header = "SHA-256=".to_owned();
+    base64::encode_config_buf(digest, base64::STANDARD, &mut digest_header);
+
+    let inbox_uri = inbox.as_str().parse::<hyper::Uri>()?;
+
+    let mut req = hyper::Request::post(&inbox_uri)
+        .header(hyper::header::CONTENT_TYPE, crate::apub_util::ACTIVITY_TYPE)
+        .header("Digest", digest_header)
+        .body(object.into())?;
+
+    req.headers_mut()
+        .entry(hyper::header::HOST)
+        .or_insert_with(|| {
+            let uri = inbox_uri;
+
+            let hostname = uri.host().expect("authority implies host");
+            if let Some(port) = uri.port() {
+                let s = format!("{}:{}", hostname, port);
+                hyper::header::HeaderValue::from_str(&s)
+            } else {
+                hyper::header::HeaderValue::from_str(hostname)
+            }
+            .expect("uri host is valid header value")
+        });
+
+    Ok(req)
+}
+
+/// Must be called after all other headers are set, since they are included in the signature
+fn sign_inbox_request(
+    req: &mut hyper::Request<hyper::Body>,
+    inbox: &url::Url,
+    signing_info: Option<(&openssl::pkey::PKey<openssl::pkey::Private>, &str)>,
+) -> Result<(), crate::Error> {
+    if let Ok(path_and_query) = crate::get_path_and_query(inbox) {
+        req.headers_mut()
+            .insert(hyper::header::DATE, crate::apub_util::now_http_date());
+
+        if let Some((privkey, key_id)) = signing_info {
+            let signature = hancock::Signature::create_legacy(
+                key_id,
+                &hyper::Method::POST,
+                &path_and_query,
+                req.headers(),
+                |src| crate::apub_util::do_sign(privkey, &src),
+            )?;
+
+            req.headers_mut().insert("Signature", signature.to_header());
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct DeliverToFollowers {
     pub actor: ActorLocalRef,
@@ -106,22 +257,78 @@ impl TaskDef for DeliverToFollowers {
     const KIND: &'static str = "deliver_to_followers";
 
     async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
         let community_id = match self.actor {
             ActorLocalRef::Community(id) => id,
-            ActorLocalRef::Person(_) => return Ok(()), // We don't have user followers at this point
-        };
+            ActorLocalRef::Person(user_id) => {
+                deliver_to_follower_inboxes(
+                    "person_follow",
+                    "person_follow.person",
+                    false,
+                    &user_id,
+                    self,
+                    &db,
+                )
+                .await?;
 
-        let db = ctx.db_pool.get().await?;
+                return Ok(());
+            }
+        };
 
-        db.execute(
-            "INSERT INTO task (kind, params, max_attempts, created_at) SELECT $1, json_build_object('sign_as', $2::JSON, 'object', $3::TEXT, 'inbox', inbox), $4, current_timestamp FROM (SELECT DISTINCT COALESCE(ap_shared_inbox, ap_inbox) AS inbox FROM community_follow, person WHERE person.id = community_follow.follower AND person.local = FALSE AND community = $5) AS result",
-            &[&DeliverToInbox::KIND, &postgres_types::Json(&if self.sign { Some(self.actor) } else { None }), &self.object, &DeliverToInbox::MAX_ATTEMPTS, &community_id],
-        ).await?;
+        deliver_to_follower_inboxes(
+            "community_follow",
+            "community_follow.community",
+            true,
+            &community_id,
+            self,
+            &db,
+        )
+        .await?;
 
         Ok(())
     }
 }
 
+/// Queues one delivery per distinct inbox in a single statement, so followers sharing an inbox
+/// only get one copy. Followers on blocked or dead hosts are skipped.
+async fn deliver_to_follower_inboxes(
+    follow_table: &str,
+    target_column: &str,
+    only_accepted: bool,
+    target: &(dyn postgres_types::ToSql + Sync),
+    def: DeliverToFollowers,
+    db: &tokio_postgres::Client,
+) -> Result<(), crate::Error> {
+    let sign_as = if def.sign { Some(def.actor) } else { None };
+
+    let sql: &str = &format!(
+        "INSERT INTO task (kind, params, max_attempts, created_at, destination_host, priority) SELECT $1, json_build_object('sign_as', $2::JSON, 'object', $3::TEXT, 'inbox', inbox), $4, current_timestamp, url_host_key(inbox), $6 FROM (SELECT DISTINCT (CASE WHEN COALESCE(remote_host.override_no_shared_inbox, remote_host.no_shared_inbox, FALSE) THEN person.ap_inbox ELSE COALESCE(person.ap_shared_inbox, person.ap_inbox) END) AS inbox FROM {0} INNER JOIN person ON (person.id = {0}.follower) LEFT OUTER JOIN remote_host ON (remote_host.host = url_host_key(person.ap_id)) WHERE person.local = FALSE AND {1} = $5{2} AND NOT COALESCE(remote_host.blocked, FALSE) AND NOT COALESCE(remote_host.dead, FALSE)) AS result WHERE inbox IS NOT NULL",
+        follow_table,
+        target_column,
+        if only_accepted {
+            format!(" AND {}.accepted", follow_table)
+        } else {
+            String::new()
+        },
+    );
+
+    db.execute(
+        sql,
+        &[
+            &DeliverToInbox::KIND,
+            &postgres_types::Json(&sign_as),
+            &def.object,
+            &DeliverToInbox::MAX_ATTEMPTS,
+            target,
+            &PRIORITY_NORMAL,
+        ],
+    )
+    .await?;
+
+    Ok(())
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct FetchActor<'a> {
     pub actor_ap_id: Cow<'a, url::Url>,
@@ -132,7 +339,435 @@ impl<'a> TaskDef for FetchActor<'a> {
     const KIND: &'static str = "fetch_actor";
 
     async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
-        crate::apub_util::fetch_actor(&self.actor_ap_id, ctx).await?;
+        match crate::apub_util::fetch_actor(&self.actor_ap_id, ctx.clone()).await {
+            Err(err) if crate::apub_util::is_remote_gone(&err) => {
+                crate::apub_util::ingest::ingest_remote_actor_delete(&self.actor_ap_id, &ctx)
+                    .await?;
+            }
+            res => {
+                res?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RefreshStaleActors {}
+
+const STALE_ACTOR_BATCH_SIZE: i64 = 50;
+
+#[async_trait]
+impl TaskDef for RefreshStaleActors {
+    const KIND: &'static str = "refresh_stale_actors";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let max_age = ctx.actor_refresh_age.as_secs_f64();
+
+        // last_fetched_at is bumped when queued so unreachable actors wait for the next window
+        let rows = db.query(
+            "WITH stale_person AS (UPDATE person SET last_fetched_at=current_timestamp WHERE id IN (SELECT id FROM person WHERE NOT local AND NOT deleted AND ap_id IS NOT NULL AND (last_fetched_at IS NULL OR last_fetched_at < current_timestamp - make_interval(secs => $1)) ORDER BY last_fetched_at ASC NULLS FIRST LIMIT $2) RETURNING ap_id), stale_community AS (UPDATE community SET last_fetched_at=current_timestamp WHERE id IN (SELECT id FROM community WHERE NOT local AND NOT deleted AND ap_id IS NOT NULL AND (last_fetched_at IS NULL OR last_fetched_at < current_timestamp - make_interval(secs => $1)) ORDER BY last_fetched_at ASC NULLS FIRST LIMIT $2) RETURNING ap_id) (SELECT ap_id FROM stale_person) UNION ALL (SELECT ap_id FROM stale_community)",
+            &[&max_age, &STALE_ACTOR_BATCH_SIZE],
+        ).await?;
+
+        let fetch_tasks: Vec<_> = rows
+            .iter()
+            .filter_map(|row| row.get::<_, &str>(0).parse().ok())
+            .map(|ap_id| FetchActor {
+                actor_ap_id: Cow::Owned(ap_id),
+            })
+            .collect();
+
+        if !fetch_tasks.is_empty() {
+            ctx.enqueue_tasks(&fetch_tasks).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RefreshObject<'a> {
+    pub object_ap_id: Cow<'a, url::Url>,
+}
+
+#[async_trait]
+impl<'a> TaskDef for RefreshObject<'a> {
+    const KIND: &'static str = "refresh_object";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        crate::apub_util::fetch_and_ingest(
+            &self.object_ap_id,
+            crate::apub_util::ingest::FoundFrom::Refresh,
+            ctx,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PruneLogins {}
+
+#[async_trait]
+impl TaskDef for PruneLogins {
+    const KIND: &'static str = "prune_logins";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let max_age = match ctx.login_max_age {
+            Some(max_age) => max_age.as_secs_f64(),
+            None => return Ok(()),
+        };
+
+        let db = ctx.db_pool.get().await?;
+
+        let count = db
+            .execute(
+                "DELETE FROM login WHERE created < current_timestamp - make_interval(secs => $1)",
+                &[&max_age],
+            )
+            .await?;
+
+        log::debug!("Pruned {} expired logins", count);
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct VacuumMedia {}
+
+const MEDIA_VACUUM_BATCH_SIZE: i64 = 200;
+const MEDIA_VACUUM_MAX_DELETES: usize = 100;
+
+#[async_trait]
+impl TaskDef for VacuumMedia {
+    const KIND: &'static str = "vacuum_media";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let media_storage = match &ctx.media_storage {
+            Some(media_storage) => media_storage,
+            None => return Ok(()),
+        };
+
+        let db = ctx.db_pool.get().await?;
+
+        let mut after: Option<i32> = None;
+        let mut deleted = 0;
+
+        while deleted < MEDIA_VACUUM_MAX_DELETES {
+            // recent uploads may not have been attached to anything yet
+            let rows = db.query(
+                "SELECT id, path, thumbnail_path FROM media WHERE created_at < current_timestamp - INTERVAL '1 DAY' AND ($1::INTEGER IS NULL OR id > $1) ORDER BY id ASC LIMIT $2",
+                &[&after, &MEDIA_VACUUM_BATCH_SIZE],
+            ).await?;
+
+            let last_id = match rows.last() {
+                Some(row) => row.get(0),
+                None => break,
+            };
+            after = Some(last_id);
+
+            let hrefs: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let id = crate::Pineapple::from_int(row.get(0));
+                    format!("local-media://{}", id.to_string())
+                })
+                .collect();
+
+            let used: std::collections::HashSet<String> = db.query(
+                "SELECT href FROM post WHERE href = ANY($1) UNION SELECT url FROM post_attachment WHERE url = ANY($1) UNION SELECT attachment_href FROM reply WHERE attachment_href = ANY($1) UNION SELECT avatar FROM person WHERE avatar = ANY($1)",
+                &[&hrefs],
+            ).await?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect();
+
+            for (row, href) in rows.iter().zip(hrefs.iter()) {
+                if used.contains(href) || deleted >= MEDIA_VACUUM_MAX_DELETES {
+                    continue;
+                }
+
+                let id: i32 = row.get(0);
+                let path: &str = row.get(1);
+                let thumbnail_path: Option<&str> = row.get(2);
+
+                media_storage.delete(path).await?;
+                if let Some(thumbnail_path) = thumbnail_path {
+                    media_storage.delete(thumbnail_path).await?;
+                }
+                db.execute("DELETE FROM media WHERE id=$1", &[&id]).await?;
+
+                deleted += 1;
+            }
+        }
+
+        log::debug!("Removed {} unused media files", deleted);
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GenerateMediaThumbnail {
+    pub media: i32,
+}
+
+const THUMBNAIL_SIZE: u32 = 320;
+const THUMBNAIL_MAX_SOURCE_BYTES: usize = 20 * 1024 * 1024;
+const BLURHASH_SOURCE_SIZE: u32 = 32;
+const BLURHASH_COMPONENTS_X: u32 = 4;
+const BLURHASH_COMPONENTS_Y: u32 = 3;
+
+#[async_trait]
+impl TaskDef for GenerateMediaThumbnail {
+    const KIND: &'static str = "generate_media_thumbnail";
+    const MAX_ATTEMPTS: i16 = 3;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        use futures::TryStreamExt;
+
+        let media_storage = match &ctx.media_storage {
+            Some(media_storage) => media_storage,
+            None => return Ok(()),
+        };
+
+        let db = ctx.db_pool.get().await?;
+
+        let row = db
+            .query_opt(
+                "SELECT path FROM media WHERE id=$1 AND thumbnail_path IS NULL",
+                &[&self.media],
+            )
+            .await?;
+        let path: String = match row {
+            Some(row) => row.get(0),
+            None => return Ok(()), // already generated, or deleted
+        };
+
+        let mut src = Vec::new();
+        let mut stream = media_storage.open(&path).await?;
+        while let Some(chunk) = stream.try_next().await? {
+            if src.len() + chunk.len() > THUMBNAIL_MAX_SOURCE_BYTES {
+                log::debug!("Media {} is too large to thumbnail", self.media);
+                return Ok(());
+            }
+
+            src.extend_from_slice(&chunk);
+        }
+
+        let result = tokio::task::spawn_blocking(move || {
+            let image = image::load_from_memory(&src)?;
+
+            let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+            let mut output = std::io::Cursor::new(Vec::new());
+            thumbnail.write_to(&mut output, image::ImageOutputFormat::Png)?;
+
+            let small = thumbnail
+                .thumbnail(BLURHASH_SOURCE_SIZE, BLURHASH_SOURCE_SIZE)
+                .to_rgba8();
+            let blurhash = blurhash::encode(
+                BLURHASH_COMPONENTS_X,
+                BLURHASH_COMPONENTS_Y,
+                small.width(),
+                small.height(),
+                small.as_raw(),
+            );
+
+            Result::<_, image::ImageError>::Ok((output.into_inner(), blurhash))
+        })
+        .await?;
+
+        let (thumbnail, blurhash) = match result {
+            Ok(value) => value,
+            Err(err) => {
+                // retrying won't help with an image we can't decode
+                log::debug!("Failed to thumbnail media {}: {:?}", self.media, err);
+                return Ok(());
+            }
+        };
+
+        let thumbnail_path = media_storage
+            .save(
+                futures::stream::once(futures::future::ready(Ok(bytes::Bytes::from(thumbnail)))),
+                "image/png",
+            )
+            .await?;
+
+        db.execute(
+            "UPDATE media SET thumbnail_path=$2, blurhash=$3 WHERE id=$1",
+            &[&self.media, &thumbnail_path, &blurhash],
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RetryFailedTasks {}
+
+#[async_trait]
+impl TaskDef for RetryFailedTasks {
+    const KIND: &'static str = "retry_failed_tasks";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let kinds = [DeliverToInbox::KIND, DeliverToWebhook::KIND];
+
+        // each run gives recent failed deliveries one more attempt
+        let count = db.execute(
+            "UPDATE task SET state='pending', attempts=max_attempts - 1, attempted_at=NULL WHERE state='failed' AND kind = ANY($1) AND created_at > current_timestamp - INTERVAL '3 DAYS'",
+            &[&&kinds[..]],
+        ).await?;
+
+        log::debug!("Requeued {} failed tasks", count);
+
+        Ok(())
+    }
+}
+
+/// Announces recent posts and comments in local communities which were never recorded as
+/// announced, e.g. because the process stopped before queueing the deliveries
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ReconcileAnnounces {}
+
+#[async_trait]
+impl TaskDef for ReconcileAnnounces {
+    const KIND: &'static str = "reconcile_announces";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        // newer objects may still be on their way through the normal path
+        let posts = db.query(
+            "SELECT post.id, post.community, post.local, post.ap_id FROM post INNER JOIN community ON (community.id = post.community) WHERE community.local AND post.announced_at IS NULL AND post.approved AND NOT post.pending_approval AND NOT post.deleted AND COALESCE(post.updated_local, post.created) BETWEEN current_timestamp - INTERVAL '1 DAY' AND current_timestamp - INTERVAL '5 MINUTES' ORDER BY post.created LIMIT 100",
+            &[],
+        ).await?;
+
+        for row in &posts {
+            let post = PostLocalID(row.get(0));
+            let post_ap_id: url::Url = if row.get(2) {
+                crate::apub_util::LocalObjectRef::Post(post)
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into()
+            } else {
+                match row.get::<_, Option<&str>>(3).map(str::parse).transpose()? {
+                    Some(ap_id) => ap_id,
+                    None => continue,
+                }
+            };
+
+            crate::apub_util::announce_community_post(
+                CommunityLocalID(row.get(1)),
+                post,
+                post_ap_id,
+                ctx.clone(),
+            )
+            .await?;
+        }
+
+        let comments = db.query(
+            "SELECT reply.id, post.community, reply.local, reply.ap_id FROM reply INNER JOIN post ON (post.id = reply.post) INNER JOIN community ON (community.id = post.community) WHERE community.local AND reply.announced_at IS NULL AND NOT reply.pending_approval AND NOT reply.deleted AND reply.created BETWEEN current_timestamp - INTERVAL '1 DAY' AND current_timestamp - INTERVAL '5 MINUTES' ORDER BY reply.created LIMIT 100",
+            &[],
+        ).await?;
+
+        for row in &comments {
+            let comment = CommentLocalID(row.get(0));
+            let comment_ap_id: url::Url = if row.get(2) {
+                crate::apub_util::LocalObjectRef::Comment(comment)
+                    .to_local_uri(&ctx.host_url_apub)
+                    .into()
+            } else {
+                match row.get::<_, Option<&str>>(3).map(str::parse).transpose()? {
+                    Some(ap_id) => ap_id,
+                    None => continue,
+                }
+            };
+
+            crate::apub_util::announce_community_comment(
+                CommunityLocalID(row.get(1)),
+                comment,
+                comment_ap_id,
+                ctx.clone(),
+            )
+            .await?;
+        }
+
+        log::debug!(
+            "Announced {} missed posts and {} missed comments",
+            posts.len(),
+            comments.len()
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RefreshCommunityActivity {}
+
+#[async_trait]
+impl TaskDef for RefreshCommunityActivity {
+    const KIND: &'static str = "refresh_community_activity";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let window = ctx.community_activity_window.as_secs_f64();
+
+        // unique participants count the most, so a few prolific accounts can't dominate the ranking
+        let count = db.execute(
+            "WITH activity AS (SELECT community, COUNT(*) FILTER (WHERE is_post) AS posts_count, COUNT(*) FILTER (WHERE NOT is_post) AS comments_count, COUNT(DISTINCT author) AS participants_count FROM ((SELECT post.community, TRUE AS is_post, post.author FROM post WHERE post.created > current_timestamp - make_interval(secs => $1) AND post.approved AND NOT post.deleted) UNION ALL (SELECT post.community, FALSE, reply.author FROM reply INNER JOIN post ON (post.id = reply.post) WHERE reply.created > current_timestamp - make_interval(secs => $1) AND NOT reply.deleted AND NOT reply.pending_approval)) AS item GROUP BY community), removed AS (DELETE FROM community_activity WHERE community NOT IN (SELECT community FROM activity)) INSERT INTO community_activity (community, posts_count, comments_count, participants_count, score, updated_at) SELECT community, posts_count, comments_count, participants_count, participants_count * 4 + posts_count * 2 + comments_count, current_timestamp FROM activity ON CONFLICT (community) DO UPDATE SET posts_count=excluded.posts_count, comments_count=excluded.comments_count, participants_count=excluded.participants_count, score=excluded.score, updated_at=excluded.updated_at",
+            &[&window],
+        ).await?;
+
+        log::debug!("Updated activity for {} communities", count);
+
+        Ok(())
+    }
+}
+
+/// Older posts are still ranked once, but their ranks change too slowly to be worth recomputing
+const HOT_RANK_WINDOW: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RecomputeHotRanks {}
+
+#[async_trait]
+impl TaskDef for RecomputeHotRanks {
+    const KIND: &'static str = "recompute_hot_ranks";
+    const MAX_ATTEMPTS: i16 = 1;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        // posts dated in the future are left unranked, since hot_rank can't handle a negative age
+        let count = db.execute(
+            "UPDATE post_score SET hot_rank = hot_rank(score, created) WHERE created < current_timestamp AND (hot_rank IS NULL OR created > current_timestamp - make_interval(secs => $1))",
+            &[&HOT_RANK_WINDOW.as_secs_f64()],
+        ).await?;
+
+        log::debug!("Recomputed hot rank for {} posts", count);
 
         Ok(())
     }
@@ -147,6 +782,7 @@ pub struct FetchCommunityFeatured {
 #[async_trait]
 impl TaskDef for FetchCommunityFeatured {
     const KIND: &'static str = "fetch_community_featured";
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
 
     async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
         use activitystreams::prelude::*;
@@ -195,6 +831,85 @@ impl TaskDef for FetchCommunityFeatured {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ProbeRemoteHost<'a> {
+    pub host: Cow<'a, str>,
+}
+
+#[async_trait]
+impl<'a> TaskDef for ProbeRemoteHost<'a> {
+    const KIND: &'static str = "probe_remote_host";
+    const MAX_ATTEMPTS: i16 = 3;
+    const PRIORITY: i16 = PRIORITY_BACKGROUND;
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        crate::apub_util::compat::probe_host(&self.host, &ctx).await
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SendCommunityAnnouncement {
+    pub announcement: i64,
+    pub after: Option<UserLocalID>,
+}
+
+const ANNOUNCEMENT_BATCH_SIZE: i64 = 100;
+const ANNOUNCEMENT_BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[async_trait]
+impl TaskDef for SendCommunityAnnouncement {
+    const KIND: &'static str = "send_community_announcement";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        // followers already notified by an earlier attempt are skipped, but still count towards the
+        // batch so that paging continues past them
+        let row = db.query_one(
+            "WITH batch AS (SELECT community_follow.follower, community_announcement.post FROM community_announcement INNER JOIN community_follow ON (community_follow.community = community_announcement.community) INNER JOIN person ON (person.id = community_follow.follower) WHERE community_announcement.id=$1 AND community_follow.accepted AND person.local AND person.notify_community_announcements AND ($2::BIGINT IS NULL OR community_follow.follower > $2) AND community_follow.follower IS DISTINCT FROM community_announcement.created_by ORDER BY community_follow.follower ASC LIMIT $3), inserted AS (INSERT INTO notification (kind, created_at, to_user, parent_post, announcement) SELECT 'community_announcement', current_timestamp, follower, post, $1 FROM batch ON CONFLICT (announcement, to_user) DO NOTHING RETURNING id, to_user) SELECT (SELECT COUNT(*) FROM batch), (SELECT MAX(follower) FROM batch), (SELECT COALESCE(array_agg(id ORDER BY to_user), '{}') FROM inserted), (SELECT COALESCE(array_agg(to_user ORDER BY to_user), '{}') FROM inserted)",
+            &[&self.announcement, &self.after, &ANNOUNCEMENT_BATCH_SIZE],
+        ).await?;
+
+        let batch_count: i64 = row.get(0);
+        let notifications: Vec<i64> = row.get(2);
+        let users: Vec<i64> = row.get(3);
+
+        let notification_tasks: Vec<_> = notifications
+            .into_iter()
+            .zip(users)
+            .map(|(notification, user)| {
+                let notification = NotificationID(notification);
+
+                ctx.send_stream_event(crate::StreamEvent::NewNotification {
+                    user: UserLocalID(user),
+                    notification,
+                });
+
+                SendNotification { notification }
+            })
+            .collect();
+
+        if !notification_tasks.is_empty() {
+            ctx.enqueue_tasks(&notification_tasks).await?;
+        }
+
+        if batch_count == ANNOUNCEMENT_BATCH_SIZE {
+            let last: i64 = row.get(1);
+
+            ctx.enqueue_task_delayed(
+                &SendCommunityAnnouncement {
+                    announcement: self.announcement,
+                    after: Some(UserLocalID(last)),
+                },
+                ANNOUNCEMENT_BATCH_INTERVAL,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct SendNotification {
     pub notification: NotificationID,
@@ -216,11 +931,20 @@ impl TaskDef for SendNotification {
                 reply_content: &'a str,
                 post_title: &'a str,
             },
+            CommunityAnnouncement {
+                href: crate::BaseURL,
+                post_title: &'a str,
+                community_name: &'a str,
+            },
+            NewLogin {
+                href: crate::BaseURL,
+                ip: String,
+            },
         }
 
         let db = ctx.db_pool.get().await?;
 
-        let row = db.query_one("SELECT notification.kind, notification.to_user, reply.id, reply.content_text, reply.content_markdown, reply.content_html, parent_post.title FROM notification LEFT OUTER JOIN reply ON (reply.id = notification.reply) LEFT OUTER JOIN post AS parent_post ON (parent_post.id = notification.parent_post) WHERE notification.id=$1", &[&self.notification]).await?;
+        let row = db.query_one("SELECT notification.kind, notification.to_user, reply.id, reply.content_text, reply.content_markdown, reply.content_html, parent_post.title, parent_post.id, community.name, login_attempt.ip FROM notification LEFT OUTER JOIN reply ON (reply.id = notification.reply) LEFT OUTER JOIN post AS parent_post ON (parent_post.id = notification.parent_post) LEFT OUTER JOIN community ON (community.id = parent_post.community) LEFT OUTER JOIN login_attempt ON (login_attempt.id = notification.login_attempt) WHERE notification.id=$1", &[&self.notification]).await?;
 
         let user = UserLocalID(row.get(1));
 
@@ -267,6 +991,31 @@ impl TaskDef for SendNotification {
                             ),
                             body: Cow::Borrowed(reply_content),
                         },
+                        NotificationSendInfo::CommunityAnnouncement {
+                            href,
+                            post_title,
+                            community_name,
+                        } => SendNotificationForSubscription {
+                            subscription: id,
+                            href: Cow::Owned(href.to_string()),
+                            title: Cow::Owned(
+                                lang.tr(&lang::notification_title_community_announcement(
+                                    *community_name,
+                                ))
+                                .into_owned(),
+                            ),
+                            body: Cow::Borrowed(post_title),
+                        },
+                        NotificationSendInfo::NewLogin { href, ip } => {
+                            SendNotificationForSubscription {
+                                subscription: id,
+                                href: Cow::Owned(href.to_string()),
+                                title: Cow::Owned(
+                                    lang.tr(&lang::notification_title_new_login()).into_owned(),
+                                ),
+                                body: Cow::Owned(ip.clone()),
+                            }
+                        }
                     }
                 })
                 .collect()
@@ -321,6 +1070,30 @@ impl TaskDef for SendNotification {
                     None
                 }
             }
+            "community_announcement" => {
+                let post_title: Option<&str> = row.get(6);
+                let community_name: Option<&str> = row.get(8);
+
+                if let (Some(post_title), Some(community_name)) = (post_title, community_name) {
+                    let id = PostLocalID(row.get(7));
+
+                    Some(build_content(NotificationSendInfo::CommunityAnnouncement {
+                        href: crate::apub_util::LocalObjectRef::Post(id)
+                            .to_local_uri(&ctx.host_url_apub),
+                        post_title,
+                        community_name,
+                    }))
+                } else {
+                    None
+                }
+            }
+            "new_login" => row.get::<_, Option<std::net::IpAddr>>(9).map(|ip| {
+                build_content(NotificationSendInfo::NewLogin {
+                    href: crate::apub_util::LocalObjectRef::User(user)
+                        .to_local_uri(&ctx.host_url_apub),
+                    ip: ip.to_string(),
+                })
+            }),
             _ => None,
         };
 
@@ -373,12 +1146,279 @@ impl<'a> TaskDef for SendNotificationForSubscription<'a> {
         let message = builder.build()?;
 
         let req = web_push::request_builder::build_request(message);
-        let res = ctx.http_client.request(req).await?;
+        let res = ctx.fetch(req).await?;
         let code = res.status();
-        let body = hyper::body::to_bytes(res.into_body()).await?;
+        let body = ctx.read_fetched_body(res).await?;
 
         web_push::request_builder::parse_response(code, body.to_vec())?;
 
         Ok(())
     }
 }
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SendPollResultsUpdate {
+    pub post: PostLocalID,
+}
+
+#[async_trait]
+impl TaskDef for SendPollResultsUpdate {
+    const KIND: &'static str = "send_poll_results_update";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        // clear first so that votes arriving from here on schedule another update
+        db.execute(
+            "UPDATE poll SET update_pending=FALSE WHERE id=(SELECT poll_id FROM post WHERE id=$1)",
+            &[&self.post],
+        )
+        .await?;
+
+        SendPostUpdate { post: self.post }.perform(ctx).await
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SendPostUpdate {
+    pub post: PostLocalID,
+}
+
+#[async_trait]
+impl TaskDef for SendPostUpdate {
+    const KIND: &'static str = "send_post_update";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let (object, author, community) =
+            match crate::apub_util::get_local_post_ap(self.post, &db, &ctx).await? {
+                crate::apub_util::LocalPostAP::Found {
+                    object,
+                    author,
+                    community,
+                    ..
+                } => (object, author, community),
+                _ => return Ok(()),
+            };
+
+        let row = db
+            .query_one(
+                "SELECT local, COALESCE(ap_shared_inbox, ap_inbox), private FROM community WHERE id=$1",
+                &[&community],
+            )
+            .await?;
+
+        let update = crate::apub_util::local_post_update_to_ap(
+            self.post,
+            author,
+            object,
+            if row.get(2) { Some(community) } else { None },
+            &ctx.host_url_apub,
+        )?;
+        let body = serde_json::to_string(&update)?;
+
+        if row.get(0) {
+            crate::apub_util::enqueue_forward_to_community_followers(community, body, ctx).await?;
+        } else if let Some(inbox) = row.get::<_, Option<&str>>(1) {
+            ctx.enqueue_task(&DeliverToInbox {
+                inbox: Cow::Owned(inbox.parse()?),
+                sign_as: Some(ActorLocalRef::Person(author)),
+                object: body,
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Federates a newly created local post, queued in the same transaction as the post itself
+#[derive(Deserialize, Serialize, Debug)]
+pub struct LocalPostCreated {
+    pub post: PostLocalID,
+}
+
+#[async_trait]
+impl TaskDef for LocalPostCreated {
+    const KIND: &'static str = "local_post_created";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let row = db
+            .query_opt(
+                "SELECT pending_approval, deleted FROM post WHERE id=$1",
+                &[&self.post],
+            )
+            .await?;
+
+        // held posts are sent once approved, and deleted ones not at all
+        match row {
+            Some(row) if !row.get::<_, bool>(0) && !row.get::<_, bool>(1) => {}
+            _ => return Ok(()),
+        }
+
+        crate::apub_util::enqueue_send_local_post_to_community(self.post, ctx).await
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AnnounceCommunityPost {
+    pub community: CommunityLocalID,
+    pub post: PostLocalID,
+    pub post_ap_id: url::Url,
+}
+
+#[async_trait]
+impl TaskDef for AnnounceCommunityPost {
+    const KIND: &'static str = "announce_community_post";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        crate::apub_util::announce_community_post(self.community, self.post, self.post_ap_id, ctx)
+            .await
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AnnounceCommunityComment {
+    pub community: CommunityLocalID,
+    pub comment: CommentLocalID,
+    pub comment_ap_id: url::Url,
+}
+
+#[async_trait]
+impl TaskDef for AnnounceCommunityComment {
+    const KIND: &'static str = "announce_community_comment";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        crate::apub_util::announce_community_comment(
+            self.community,
+            self.comment,
+            self.comment_ap_id,
+            ctx,
+        )
+        .await
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SendCommentUpdate {
+    pub comment: CommentLocalID,
+}
+
+#[async_trait]
+impl TaskDef for SendCommentUpdate {
+    const KIND: &'static str = "send_comment_update";
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let (object, author, community, community_private) =
+            match crate::apub_util::get_local_comment_ap(self.comment, &db, &ctx).await? {
+                crate::apub_util::LocalCommentAP::Found {
+                    object,
+                    author,
+                    community,
+                    community_private,
+                } => (object, author, community, community_private),
+                _ => return Ok(()),
+            };
+
+        let row = db
+            .query_one(
+                "SELECT local, COALESCE(ap_shared_inbox, ap_inbox) FROM community WHERE id=$1",
+                &[&community],
+            )
+            .await?;
+
+        let update = crate::apub_util::local_comment_update_to_ap(
+            self.comment,
+            author,
+            object,
+            if community_private {
+                Some(community)
+            } else {
+                None
+            },
+            &ctx.host_url_apub,
+        )?;
+        let body = serde_json::to_string(&update)?;
+
+        if row.get(0) {
+            crate::apub_util::enqueue_forward_to_community_followers(community, body, ctx).await?;
+        } else if let Some(inbox) = row.get::<_, Option<&str>>(1) {
+            ctx.enqueue_task(&DeliverToInbox {
+                inbox: Cow::Owned(inbox.parse()?),
+                sign_as: Some(ActorLocalRef::Person(author)),
+                object: body,
+            })
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct DeliverToWebhook {
+    pub webhook: WebhookID,
+    pub body: String,
+    /// Host of the webhook URL when queued, so deliveries share the per-host limits
+    #[serde(default)]
+    pub destination_host: Option<String>,
+}
+
+#[async_trait]
+impl TaskDef for DeliverToWebhook {
+    const KIND: &'static str = "deliver_to_webhook";
+
+    fn destination_host(&self) -> Option<&str> {
+        self.destination_host.as_deref()
+    }
+
+    async fn perform(self, ctx: Arc<crate::BaseContext>) -> Result<(), crate::Error> {
+        let db = ctx.db_pool.get().await?;
+
+        let row = db
+            .query_opt(
+                "SELECT url, secret FROM webhook WHERE id=$1",
+                &[&self.webhook],
+            )
+            .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(()), // webhook was removed
+        };
+
+        let url: &str = row.get(0);
+        let secret: &str = row.get(1);
+
+        // covering the time lets receivers reject old deliveries that are replayed
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+
+        let key = openssl::pkey::PKey::hmac(secret.as_bytes())?;
+        let mut signer = openssl::sign::Signer::new(openssl::hash::MessageDigest::sha256(), &key)?;
+        signer.update(timestamp.as_bytes())?;
+        signer.update(b".")?;
+        signer.update(self.body.as_bytes())?;
+        let signature = signer.sign_to_vec()?;
+
+        let mut signature_header = "sha256=".to_owned();
+        for byte in signature {
+            signature_header.push_str(&format!("{:02x}", byte));
+        }
+
+        let req = hyper::Request::post(url)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .header("X-Lotide-Timestamp", timestamp)
+            .header("X-Lotide-Signature", signature_header)
+            .body(self.body.into())?;
+
+        let res = ctx.fetch(req).await?;
+        crate::res_to_error(res).await?;
+
+        Ok(())
+    }
+}
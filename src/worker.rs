@@ -2,62 +2,297 @@ use std::sync::Arc;
 
 const TASK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
 
-pub fn start_worker(ctx: Arc<crate::BaseContext>, rx: tokio::sync::mpsc::Receiver<()>) {
-    crate::spawn_task(run_worker(ctx, rx));
+/// Tasks still marked as running after this long were claimed by a process that stopped
+const STALE_TASK_AGE_SECONDS: f64 = 300.0;
+
+/// How often each maintenance job runs, `None` if disabled
+pub struct MaintenanceIntervals {
+    pub actor_refresh: Option<std::time::Duration>,
+    pub login_prune: Option<std::time::Duration>,
+    pub media_vacuum: Option<std::time::Duration>,
+    pub failed_task_retry: Option<std::time::Duration>,
+    pub community_activity: Option<std::time::Duration>,
+    pub post_view_flush: Option<std::time::Duration>,
+    pub announce_reconcile: Option<std::time::Duration>,
+    pub hot_rank: Option<std::time::Duration>,
+}
+
+impl MaintenanceIntervals {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        fn minutes(value: u32) -> Option<std::time::Duration> {
+            if value == 0 {
+                None
+            } else {
+                Some(std::time::Duration::from_secs(u64::from(value) * 60))
+            }
+        }
+
+        Self {
+            actor_refresh: minutes(config.actor_refresh_interval_minutes),
+            login_prune: if config.login_max_age_days.is_some() {
+                minutes(config.login_prune_interval_minutes)
+            } else {
+                None
+            },
+            media_vacuum: minutes(config.media_vacuum_interval_minutes),
+            failed_task_retry: minutes(config.failed_task_retry_interval_minutes),
+            community_activity: minutes(config.community_activity_interval_minutes),
+            post_view_flush: minutes(config.post_view_flush_interval_minutes),
+            announce_reconcile: minutes(config.announce_reconcile_interval_minutes),
+            hot_rank: minutes(config.hot_rank_interval_minutes),
+        }
+    }
+}
+
+pub struct WorkerOptions {
+    /// How many tasks may run at once
+    pub concurrency: usize,
+    /// Limit for tasks delivering to a single remote host
+    pub host_concurrency: i64,
+    /// How often to check for tasks without being woken, e.g. ones queued by another process
+    pub poll_interval: std::time::Duration,
+}
+
+impl WorkerOptions {
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        Self {
+            concurrency: config.worker_concurrency.max(1),
+            host_concurrency: i64::from(config.worker_host_concurrency.max(1)),
+            poll_interval: std::time::Duration::from_secs(u64::from(
+                config.worker_poll_interval_seconds.max(1),
+            )),
+        }
+    }
+}
+
+pub fn start_worker(
+    ctx: Arc<crate::BaseContext>,
+    rx: tokio::sync::mpsc::Receiver<()>,
+    options: WorkerOptions,
+) {
+    crate::spawn_task(run_worker(ctx, rx, options));
+}
+
+pub fn start_scheduler(ctx: Arc<crate::BaseContext>, intervals: MaintenanceIntervals) {
+    schedule_task(
+        ctx.clone(),
+        intervals.actor_refresh,
+        crate::tasks::RefreshStaleActors {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.login_prune,
+        crate::tasks::PruneLogins {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.media_vacuum,
+        crate::tasks::VacuumMedia {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.failed_task_retry,
+        crate::tasks::RetryFailedTasks {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.community_activity,
+        crate::tasks::RefreshCommunityActivity {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.announce_reconcile,
+        crate::tasks::ReconcileAnnounces {},
+    );
+    schedule_task(
+        ctx.clone(),
+        intervals.hot_rank,
+        crate::tasks::RecomputeHotRanks {},
+    );
+    schedule_post_view_flush(ctx, intervals.post_view_flush);
+}
+
+fn schedule_task<T: crate::tasks::TaskDef + Send + 'static>(
+    ctx: Arc<crate::BaseContext>,
+    interval: Option<std::time::Duration>,
+    task: T,
+) {
+    let interval = match interval {
+        Some(interval) => interval,
+        None => return,
+    };
+
+    crate::spawn_task(async move {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            // a previous run that hasn't finished yet is left alone, and with several processes
+            // only one of them queues each run
+            if let Err(err) = ctx
+                .enqueue_periodic_task(&task, interval.period().mul_f64(0.9))
+                .await
+            {
+                log::error!("Failed to schedule {}: {:?}", T::KIND, err);
+            }
+        }
+    });
+}
+
+/// View counts live in this process's memory, so they are flushed directly rather than through the task queue
+fn schedule_post_view_flush(ctx: Arc<crate::BaseContext>, interval: Option<std::time::Duration>) {
+    let interval = match interval {
+        Some(interval) => interval,
+        None => return,
+    };
+
+    crate::spawn_task(async move {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = ctx.flush_post_views().await {
+                log::error!("Failed to flush post views: {:?}", err);
+            }
+        }
+    });
+}
+
+/// Marks the next runnable task as running.
+///
+/// Hosts already at their limit are skipped, counting tasks running in any process. The
+/// count and claim for a host are serialized with an advisory lock, so processes claiming
+/// at the same time can't both take the last slot.
+async fn claim_task(
+    db: &mut deadpool_postgres::Client,
+    host_concurrency: i64,
+) -> Result<Option<tokio_postgres::Row>, crate::Error> {
+    let trans = db.transaction().await?;
+
+    // hosts found to be full after locking, skipped so other pending tasks can still be claimed
+    let mut full_hosts: Vec<String> = Vec::new();
+
+    let task_id = loop {
+        let row = trans
+            .query_opt(
+                "SELECT id, destination_host FROM task \
+                    WHERE state='pending' \
+                    AND (attempted_at IS NULL OR attempted_at + (EXP(attempts) * INTERVAL '20 SECONDS') < current_timestamp) \
+                    AND (run_after IS NULL OR run_after < current_timestamp) \
+                    AND (destination_host IS NULL OR destination_host NOT IN (SELECT host FROM remote_host WHERE delivery_paused)) \
+                    AND (destination_host IS NULL OR (SELECT COUNT(*) FROM task AS running WHERE running.state='running' AND running.destination_host=task.destination_host) < $1) \
+                    AND (destination_host IS NULL OR NOT (destination_host = ANY($2))) \
+                    ORDER BY priority DESC, created_at \
+                    FOR UPDATE SKIP LOCKED LIMIT 1",
+                &[&host_concurrency, &full_hosts],
+            )
+            .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let task_id: i64 = row.get(0);
+        let destination_host: Option<&str> = row.get(1);
+
+        if let Some(destination_host) = destination_host {
+            trans
+                .execute(
+                    "SELECT pg_advisory_xact_lock(hashtext($1))",
+                    &[&destination_host],
+                )
+                .await?;
+
+            // another process may have claimed a task for this host since the first check
+            let running: i64 = trans
+                .query_one(
+                    "SELECT COUNT(*) FROM task WHERE state='running' AND destination_host=$1",
+                    &[&destination_host],
+                )
+                .await?
+                .get(0);
+
+            if running >= host_concurrency {
+                full_hosts.push(destination_host.to_owned());
+                continue;
+            }
+        }
+
+        break task_id;
+    };
+
+    let row = trans
+        .query_one(
+            "UPDATE task SET state='running', started_at=current_timestamp WHERE id=$1 RETURNING id, kind, params, destination_host, EXISTS(SELECT 1 FROM remote_host WHERE host=task.destination_host AND dead)",
+            &[&task_id],
+        )
+        .await?;
+
+    trans.commit().await?;
+
+    Ok(Some(row))
 }
 
 async fn run_worker(
     ctx: Arc<crate::BaseContext>,
     mut recv: tokio::sync::mpsc::Receiver<()>,
+    options: WorkerOptions,
 ) -> Result<(), crate::Error> {
-    let db = ctx.db_pool.get().await?;
+    let mut db = ctx.db_pool.get().await?;
 
-    // TODO allow disabling this so multiple workers can run
-    db.execute(
-        "UPDATE task SET state='pending', attempts = attempts + 1 WHERE state='running'",
-        &[],
-    )
-    .await?;
+    recover_stale_tasks(&db).await?;
+
+    let slots = Arc::new(tokio::sync::Semaphore::new(options.concurrency));
 
-    // TODO consider running tasks in parallel
     loop {
-        let row = db
-            .query_opt(
-                "UPDATE task SET state='running' WHERE id=(\
-                    SELECT id FROM task \
-                        WHERE state='pending' \
-                        AND (attempted_at IS NULL OR attempted_at + (EXP(attempts) * INTERVAL '20 SECONDS') < current_timestamp) \
-                        FOR UPDATE SKIP LOCKED LIMIT 1\
-                    ) RETURNING id, kind, params",
-                &[],
-            )
-            .await?;
+        let permit = slots
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| crate::Error::InternalStrStatic("Worker semaphore closed"))?;
+
+        let row = claim_task(&mut db, options.host_concurrency).await?;
 
         if let Some(row) = row {
             let task_id: i64 = row.get(0);
-            let kind: &str = row.get(1);
+            let kind: String = row.get(1);
             let params: serde_json::Value = row.get(2);
+            let destination_host: Option<String> = row.get(3);
+            let host_dead: bool = row.get(4);
 
-            let result =
-                tokio::time::timeout(TASK_TIMEOUT, perform_task(ctx.clone(), kind, params)).await;
-            let result = match result {
-                Err(_) => Err(crate::Error::InternalStrStatic("Timeout")),
-                Ok(res) => res,
-            };
-
-            if let Err(err) = result {
-                let err = format!("{:?}", err);
+            if host_dead {
+                // held until an admin retries the host
                 db.execute(
-                    "UPDATE task \
-                        SET state=(CASE WHEN attempts + 1 < max_attempts THEN 'pending'::lt_task_state ELSE 'failed'::lt_task_state END), attempts = attempts + 1, latest_error=$2, attempted_at=current_timestamp \
-                        WHERE id=$1",
-                    &[&task_id, &err],
-                ).await?;
-            } else {
-                db.execute("UPDATE task SET state='completed', completed_at=current_timestamp, attempts = attempts + 1 WHERE id=$1", &[&task_id]).await?;
+                    "UPDATE task SET state='failed', latest_error='Destination host is dead', attempted_at=current_timestamp WHERE id=$1",
+                    &[&task_id],
+                )
+                .await?;
+
+                continue;
             }
+
+            let ctx = ctx.clone();
+            crate::spawn_task(async move {
+                let res = run_task(ctx.clone(), task_id, &kind, params, destination_host).await;
+                std::mem::drop(permit);
+
+                // a host may have dropped below its limit
+                ctx.notify_worker()?;
+
+                res
+            });
         } else {
-            match tokio::time::timeout(std::time::Duration::from_secs(60), recv.recv()).await {
+            std::mem::drop(permit);
+
+            recover_stale_tasks(&db).await?;
+
+            // only tasks queued by this process wake it directly
+            match tokio::time::timeout(options.poll_interval, recv.recv()).await {
                 Err(tokio::time::error::Elapsed { .. }) => {}
                 Ok(recv_res) => recv_res.ok_or(crate::Error::InternalStrStatic(
                     "Worker trigger senders lost",
@@ -67,6 +302,66 @@ async fn run_worker(
     }
 }
 
+/// Returns tasks left running by a process that stopped to the queue
+async fn recover_stale_tasks(db: &tokio_postgres::Client) -> Result<(), crate::Error> {
+    db.execute(
+        "UPDATE task SET state='pending', attempts = attempts + 1 WHERE state='running' AND (started_at IS NULL OR started_at < current_timestamp - make_interval(secs => $1))",
+        &[&STALE_TASK_AGE_SECONDS],
+    )
+    .await?;
+
+    Ok(())
+}
+
+async fn run_task(
+    ctx: Arc<crate::BaseContext>,
+    task_id: i64,
+    kind: &str,
+    params: serde_json::Value,
+    destination_host: Option<String>,
+) -> Result<(), crate::Error> {
+    let result = tokio::time::timeout(TASK_TIMEOUT, perform_task(ctx.clone(), kind, params)).await;
+    let result = match result {
+        Err(_) => Err(crate::Error::InternalStrStatic("Timeout")),
+        Ok(res) => res,
+    };
+
+    let db = ctx.db_pool.get().await?;
+
+    if kind == crate::tasks::DeliverToInbox::KIND || kind == crate::tasks::DeliverAsInstance::KIND {
+        if let Some(host) = destination_host {
+            if result.is_ok() {
+                // last_delivery_at is only kept to the hour to avoid a write per delivery
+                db.execute(
+                    "UPDATE remote_host SET unreachable_since=NULL, consecutive_failures=0, dead=FALSE, last_delivery_at=current_timestamp WHERE host=$1 AND (unreachable_since IS NOT NULL OR last_delivery_at IS NULL OR last_delivery_at < current_timestamp - INTERVAL '1 HOUR')",
+                    &[&host],
+                )
+                .await?;
+            } else {
+                db.execute(
+                    "UPDATE remote_host SET unreachable_since=COALESCE(unreachable_since, current_timestamp), consecutive_failures=consecutive_failures + 1, dead=COALESCE(dead OR unreachable_since < current_timestamp - make_interval(secs => $2), FALSE) WHERE host=$1",
+                    &[&host, &ctx.host_dead_after.as_secs_f64()],
+                )
+                .await?;
+            }
+        }
+    }
+
+    if let Err(err) = result {
+        let err = format!("{:?}", err);
+        db.execute(
+            "UPDATE task \
+                SET state=(CASE WHEN attempts + 1 < max_attempts THEN 'pending'::lt_task_state ELSE 'failed'::lt_task_state END), attempts = attempts + 1, latest_error=$2, attempted_at=current_timestamp \
+                WHERE id=$1",
+            &[&task_id, &err],
+        ).await?;
+    } else {
+        db.execute("UPDATE task SET state='completed', completed_at=current_timestamp, attempts = attempts + 1 WHERE id=$1", &[&task_id]).await?;
+    }
+
+    Ok(())
+}
+
 async fn perform_task(
     ctx: Arc<crate::BaseContext>,
     kind: &str,
@@ -79,6 +374,10 @@ async fn perform_task(
             let def: crate::tasks::DeliverToInbox = serde_json::from_value(params)?;
             def.perform(ctx).await?;
         }
+        crate::tasks::DeliverAsInstance::KIND => {
+            let def: crate::tasks::DeliverAsInstance = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
         crate::tasks::DeliverToFollowers::KIND => {
             let def: crate::tasks::DeliverToFollowers = serde_json::from_value(params)?;
             def.perform(ctx).await?;
@@ -87,10 +386,62 @@ async fn perform_task(
             let def: crate::tasks::FetchActor = serde_json::from_value(params)?;
             def.perform(ctx).await?;
         }
+        crate::tasks::RefreshStaleActors::KIND => {
+            let def: crate::tasks::RefreshStaleActors = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::PruneLogins::KIND => {
+            let def: crate::tasks::PruneLogins = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::VacuumMedia::KIND => {
+            let def: crate::tasks::VacuumMedia = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::GenerateMediaThumbnail::KIND => {
+            let def: crate::tasks::GenerateMediaThumbnail = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::RetryFailedTasks::KIND => {
+            let def: crate::tasks::RetryFailedTasks = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::AnnounceCommunityPost::KIND => {
+            let def: crate::tasks::AnnounceCommunityPost = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::AnnounceCommunityComment::KIND => {
+            let def: crate::tasks::AnnounceCommunityComment = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::ReconcileAnnounces::KIND => {
+            let def: crate::tasks::ReconcileAnnounces = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::RefreshCommunityActivity::KIND => {
+            let def: crate::tasks::RefreshCommunityActivity = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::RecomputeHotRanks::KIND => {
+            let def: crate::tasks::RecomputeHotRanks = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
         crate::tasks::FetchCommunityFeatured::KIND => {
             let def: crate::tasks::FetchCommunityFeatured = serde_json::from_value(params)?;
             def.perform(ctx).await?;
         }
+        crate::tasks::RefreshObject::KIND => {
+            let def: crate::tasks::RefreshObject = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::ProbeRemoteHost::KIND => {
+            let def: crate::tasks::ProbeRemoteHost = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::SendCommunityAnnouncement::KIND => {
+            let def: crate::tasks::SendCommunityAnnouncement = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
         crate::tasks::SendNotification::KIND => {
             let def: crate::tasks::SendNotification = serde_json::from_value(params)?;
             def.perform(ctx).await?;
@@ -100,6 +451,26 @@ async fn perform_task(
                 serde_json::from_value(params)?;
             def.perform(ctx).await?;
         }
+        crate::tasks::DeliverToWebhook::KIND => {
+            let def: crate::tasks::DeliverToWebhook = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::SendPollResultsUpdate::KIND => {
+            let def: crate::tasks::SendPollResultsUpdate = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::SendPostUpdate::KIND => {
+            let def: crate::tasks::SendPostUpdate = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::LocalPostCreated::KIND => {
+            let def: crate::tasks::LocalPostCreated = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
+        crate::tasks::SendCommentUpdate::KIND => {
+            let def: crate::tasks::SendCommentUpdate = serde_json::from_value(params)?;
+            def.perform(ctx).await?;
+        }
         _ => {
             return Err(crate::Error::InternalStr(format!(
                 "Unrecognized task type: {}",
@@ -279,3 +279,35 @@ fn community_description_update(server1: &TestServer, server2: &TestServer) {
         );
     }
 }
+
+#[rstest]
+fn openapi_paths_routed(server1: &TestServer) {
+    let client = reqwest::blocking::Client::builder().build().unwrap();
+
+    let spec: serde_json::Value = client
+        .get(format!("{}/api/unstable/openapi.json", server1.host_url).deref())
+        .send()
+        .unwrap()
+        .error_for_status()
+        .unwrap()
+        .json()
+        .unwrap();
+
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        if path.contains('{') || item.get("get").is_none() {
+            continue;
+        }
+
+        let resp = client
+            .get(format!("{}{}", server1.host_url, path).deref())
+            .send()
+            .unwrap();
+
+        assert_ne!(
+            resp.status(),
+            reqwest::StatusCode::NOT_FOUND,
+            "{} is documented but not routed",
+            path
+        );
+    }
+}
@@ -41,6 +41,17 @@ macro_rules! id_wrapper {
                 self.0.to_sql_checked(ty, out)
             }
         }
+        impl<'a> postgres_types::FromSql<'a> for $ty {
+            fn from_sql(
+                ty: &postgres_types::Type,
+                raw: &'a [u8],
+            ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+                <i64 as postgres_types::FromSql>::from_sql(ty, raw).map(Self)
+            }
+            fn accepts(ty: &postgres_types::Type) -> bool {
+                <i64 as postgres_types::FromSql>::accepts(ty)
+            }
+        }
     };
 }
 
@@ -53,6 +64,10 @@ id_wrapper!(UserLocalID);
 id_wrapper!(NotificationID);
 id_wrapper!(NotificationSubscriptionID);
 id_wrapper!(FlagLocalID);
+id_wrapper!(FlairLocalID);
+id_wrapper!(WebhookID);
+id_wrapper!(AutomodRuleID);
+id_wrapper!(RelayID);
 
 #[derive(Serialize, Default, Clone, Copy)]
 pub struct Empty {}
@@ -85,6 +100,19 @@ pub struct JustContentText<'a> {
     pub content_text: Cow<'a, str>,
 }
 
+#[derive(Serialize)]
+pub struct RespReplyDraft<'a> {
+    pub content_text: Option<Cow<'a, str>>,
+    pub content_markdown: Option<Cow<'a, str>>,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ProfileField<'a> {
+    pub name: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+}
+
 #[derive(Serialize, Clone)]
 pub struct Content<'a> {
     pub content_text: Option<Cow<'a, str>>,
@@ -99,13 +127,57 @@ pub struct RespUserInfo<'a> {
     pub base: RespMinimalAuthorInfo<'a>,
 
     pub description: Content<'a>,
+    pub display_name: Option<Cow<'a, str>>,
+    pub profile_fields: Vec<ProfileField<'a>>,
+    pub post_count: i64,
+    pub comment_count: i64,
+    pub moved_to: Option<Cow<'a, str>>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suspended: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub your_note: Option<Option<JustContentText<'a>>>,
 }
 
+#[derive(Serialize)]
+pub struct RespAdminUserInfo<'a> {
+    #[serde(flatten)]
+    pub base: RespMinimalAuthorInfo<'a>,
+
+    pub created: String,
+    pub suspended: bool,
+    pub banned: bool,
+    pub post_count: i64,
+    pub comment_count: i64,
+}
+
+#[derive(Serialize)]
+pub struct RespUserInsightsCommunity<'a> {
+    pub community: RespMinimalCommunityInfo<'a>,
+    pub post_count: i64,
+    pub score: i64,
+}
+
+#[derive(Serialize)]
+pub struct RespUserInsightsWindow<'a> {
+    pub window: &'a str,
+    pub post_count: i64,
+    pub post_score: i64,
+    pub post_replies_count: i64,
+    pub comment_count: i64,
+    pub comment_score: i64,
+    pub comment_replies_count: i64,
+    pub top_communities: Vec<RespUserInsightsCommunity<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct RespUserInsights<'a> {
+    pub generated_at: String,
+    pub windows: Vec<RespUserInsightsWindow<'a>>,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -119,14 +191,33 @@ pub enum RespNotificationInfo<'a> {
         comment: RespPostCommentInfo<'a>,
         post: RespPostListPost<'a>,
     },
+    CommunityAnnouncement {
+        post: RespPostListPost<'a>,
+    },
+    NewLogin {
+        login: RespLoginAttempt<'a>,
+    },
 }
 
 #[derive(Serialize, Clone)]
 pub struct RespNotification<'a> {
+    pub id: NotificationID,
+
     #[serde(flatten)]
     pub info: RespNotificationInfo<'a>,
 
     pub unseen: bool,
+    pub read: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespLoginAttempt<'a> {
+    pub id: i64,
+    pub created: String,
+    pub success: bool,
+    pub ip: Option<String>,
+    pub user_agent: Option<Cow<'a, str>>,
 }
 
 #[derive(Serialize)]
@@ -172,13 +263,62 @@ pub struct RespMinimalAuthorInfo<'a> {
     pub avatar: Option<RespAvatarInfo<'a>>,
 }
 
+impl<'a> RespMinimalAuthorInfo<'a> {
+    pub fn into_owned(self) -> RespMinimalAuthorInfo<'static> {
+        RespMinimalAuthorInfo {
+            id: self.id,
+            username: Cow::Owned(self.username.into_owned()),
+            local: self.local,
+            host: Cow::Owned(self.host.into_owned()),
+            remote_url: self.remote_url.map(|x| Cow::Owned(x.into_owned())),
+            is_bot: self.is_bot,
+            avatar: self.avatar.map(|x| RespAvatarInfo {
+                url: Cow::Owned(x.url.into_owned()),
+            }),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct RespLoginUserInfo {
     pub id: UserLocalID,
     pub username: String,
     pub is_site_admin: bool,
     pub has_unread_notifications: bool,
+    pub unread_notifications_count: i64,
     pub has_pending_moderation_actions: bool,
+    pub preferred_languages: Vec<String>,
+    pub preferences: RespUserPreferences<'static>,
+}
+
+/// Client settings, stored as-is in `person.preferences`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct UserPreferences<'a> {
+    pub default_sort: Option<Cow<'a, str>>,
+    pub show_sensitive: bool,
+    pub timezone: Option<Cow<'a, str>>,
+    pub notify_post_replies: bool,
+    pub notify_comment_replies: bool,
+}
+
+impl<'a> Default for UserPreferences<'a> {
+    fn default() -> Self {
+        Self {
+            default_sort: None,
+            show_sensitive: false,
+            timezone: None,
+            notify_post_replies: true,
+            notify_comment_replies: true,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RespUserPreferences<'a> {
+    #[serde(flatten)]
+    pub base: UserPreferences<'a>,
+    pub notify_community_announcements: bool,
 }
 
 #[derive(Serialize)]
@@ -216,6 +356,54 @@ pub struct RespMinimalPostInfo<'a> {
     pub sensitive: bool,
 }
 
+/// Vote total for a post or comment. While `hidden_until` is set, this serializes as
+/// `"score": null` along with `score_hidden_until`.
+#[derive(Clone)]
+pub struct RespScore {
+    pub value: i64,
+    pub hidden_until: Option<String>,
+}
+
+impl serde::Serialize for RespScore {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("RespScore", 2)?;
+        match &self.hidden_until {
+            None => {
+                state.serialize_field("score", &self.value)?;
+                state.skip_field("score_hidden_until")?;
+            }
+            Some(hidden_until) => {
+                state.serialize_field("score", &Option::<i64>::None)?;
+                state.serialize_field("score_hidden_until", hidden_until)?;
+            }
+        }
+        state.end()
+    }
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub struct RespYourVote {
+    pub is_dislike: bool,
+}
+
+/// State after changing your vote
+#[derive(Serialize)]
+pub struct RespVoteResult {
+    #[serde(flatten)]
+    pub score: RespScore,
+    pub your_vote: Option<RespYourVote>,
+}
+
+#[derive(Serialize)]
+pub struct RespReactionInfo {
+    pub content: String,
+    pub count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub your_reaction: Option<bool>,
+}
+
 #[derive(Serialize, Clone)]
 pub struct RespPostListPost<'a> {
     pub id: PostLocalID,
@@ -233,11 +421,22 @@ pub struct RespPostListPost<'a> {
     pub replies_count_total: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub relevance: Option<f32>,
-    pub score: i64,
+    #[serde(flatten)]
+    pub score: RespScore,
     pub sticky: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub your_vote: Option<Option<Empty>>,
+    pub your_vote: Option<Option<RespYourVote>>,
     pub sensitive: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flairs: Option<Vec<RespFlairInfo<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<RespThumbnailInfo<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RespFlairInfo<'a> {
+    pub id: FlairLocalID,
+    pub name: Cow<'a, str>,
 }
 
 #[derive(Serialize, Clone)]
@@ -265,11 +464,13 @@ pub struct RespPostCommentInfo<'a> {
     pub content_markdown: Option<Cow<'a, str>>,
     pub created: String,
     pub deleted: bool,
+    pub edited_at: Option<String>,
     pub local: bool,
     pub replies: Option<RespList<'a, RespPostCommentInfo<'a>>>,
-    pub score: i64,
+    #[serde(flatten)]
+    pub score: RespScore,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub your_vote: Option<Option<Empty>>,
+    pub your_vote: Option<Option<RespYourVote>>,
 }
 
 impl<'a> RespPostCommentInfo<'a> {
@@ -289,17 +490,47 @@ pub enum RespThingInfo<'a> {
         base: RespMinimalCommentInfo<'a>,
         created: String,
         post: RespMinimalPostInfo<'a>,
+        #[serde(flatten)]
+        score: RespScore,
     },
 }
 
+#[derive(Serialize)]
+pub struct RespPostCreated {
+    pub id: PostLocalID,
+    /// Whether a recent post with the same link was returned instead of creating one
+    pub duplicate: bool,
+}
+
 #[derive(Serialize)]
 pub struct RespPostInfo<'a> {
     #[serde(flatten)]
     pub post: &'a RespPostListPost<'a>,
+    pub language: Option<Cow<'a, str>>,
+    pub canonical_href: Option<Cow<'a, str>>,
     pub approved: bool,
     pub rejected: bool,
     pub local: bool,
+    pub locked: bool,
+    pub views: i64,
     pub poll: Option<RespPollInfo<'a>>,
+    pub attachments: Vec<RespPostAttachment<'a>>,
+    pub reactions: Vec<RespReactionInfo>,
+}
+
+#[derive(Serialize)]
+pub struct RespPostAttachment<'a> {
+    pub url: Cow<'a, str>,
+    pub media_type: Option<&'a str>,
+    pub alt_text: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<RespThumbnailInfo<'a>>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespThumbnailInfo<'a> {
+    pub url: Cow<'a, str>,
+    pub blurhash: Cow<'a, str>,
 }
 
 #[derive(Serialize)]
@@ -336,6 +567,9 @@ pub struct RespCommunityInfo<'a> {
 
     pub description: Content<'a>,
     pub feeds: RespCommunityFeeds,
+    pub followers_count: i64,
+    pub posts_count: i64,
+    pub last_activity: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub you_are_moderator: Option<bool>,
@@ -344,11 +578,62 @@ pub struct RespCommunityInfo<'a> {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pending_moderation_actions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_dislikes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_scores_minutes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlisted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approve_follows: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members_only_posting: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Clone)]
 pub struct RespYourFollowInfo {
     pub accepted: bool,
+    pub state: FollowState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowState {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl FollowState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FollowState::Pending => "pending",
+            FollowState::Accepted => "accepted",
+            FollowState::Rejected => "rejected",
+        }
+    }
+}
+
+impl std::str::FromStr for FollowState {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "pending" => Ok(FollowState::Pending),
+            "accepted" => Ok(FollowState::Accepted),
+            "rejected" => Ok(FollowState::Rejected),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespCommunityFollowInfo<'a> {
+    pub community: RespMinimalCommunityInfo<'a>,
+    pub accepted: bool,
 }
 
 #[derive(Serialize)]
@@ -364,29 +649,135 @@ pub struct RespCommentInfo<'a> {
     pub base: RespPostCommentInfo<'a>,
     pub parent: Option<JustID<CommentLocalID>>,
     pub post: Option<RespMinimalPostInfo<'a>>,
+    pub reactions: Vec<RespReactionInfo>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespContentRevision<'a> {
+    pub id: i64,
+    pub created: String,
+    pub content_text: Option<Cow<'a, str>>,
+    pub content_markdown: Option<Cow<'a, str>>,
+    #[serde(rename = "content_html")]
+    pub content_html_safe: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RespCommentContext<'a> {
+    pub post: Option<RespMinimalPostInfo<'a>>,
+    pub ancestors: Vec<RespPostCommentInfo<'a>>,
+    pub comment: RespPostCommentInfo<'a>,
 }
 
 #[derive(Serialize, Clone)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum RespFlagDetails<'a> {
-    Post { post: RespPostListPost<'a> },
+    Post {
+        post: RespPostListPost<'a>,
+    },
+    Comment {
+        comment: RespMinimalCommentInfo<'a>,
+        post: RespMinimalPostInfo<'a>,
+        author: Option<RespMinimalAuthorInfo<'a>>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagCategory {
+    Spam,
+    Harassment,
+    Illegal,
+    Rule,
+    Other,
+}
+
+impl FlagCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagCategory::Spam => "spam",
+            FlagCategory::Harassment => "harassment",
+            FlagCategory::Illegal => "illegal",
+            FlagCategory::Rule => "rule",
+            FlagCategory::Other => "other",
+        }
+    }
+}
+
+impl std::str::FromStr for FlagCategory {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "spam" => Ok(FlagCategory::Spam),
+            "harassment" => Ok(FlagCategory::Harassment),
+            "illegal" => Ok(FlagCategory::Illegal),
+            "rule" => Ok(FlagCategory::Rule),
+            "other" => Ok(FlagCategory::Other),
+            _ => Err(()),
+        }
+    }
 }
 
 #[derive(Serialize, Clone)]
 pub struct RespFlagInfo<'a> {
     pub id: FlagLocalID,
 
-    pub flagger: RespMinimalAuthorInfo<'a>,
+    /// Absent for reports filed by automod rules
+    pub flagger: Option<RespMinimalAuthorInfo<'a>>,
+    pub automod_rule: Option<AutomodRuleID>,
 
     pub created_local: String,
 
     pub content: Option<JustContentText<'a>>,
 
+    pub category: Option<FlagCategory>,
+    pub rule: Option<i32>,
+
     #[serde(flatten)]
     pub details: RespFlagDetails<'a>,
 }
 
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModQueueReason {
+    /// Held by an automod rule
+    Held,
+    /// Reported by several users or by an automod rule
+    Reported,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum RespModQueueItemDetails<'a> {
+    Post {
+        post: RespMinimalPostInfo<'a>,
+    },
+    Comment {
+        comment: RespMinimalCommentInfo<'a>,
+        post: RespMinimalPostInfo<'a>,
+    },
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespModQueueItem<'a> {
+    pub reason: ModQueueReason,
+    pub flag_count: i64,
+    pub created: String,
+    pub author: Option<RespMinimalAuthorInfo<'a>>,
+
+    #[serde(flatten)]
+    pub details: RespModQueueItemDetails<'a>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespFlagCategoryStat {
+    pub category: Option<FlagCategory>,
+    pub rule: Option<i32>,
+    pub count: i64,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ActorLocalRef {
     Person(UserLocalID),
@@ -432,6 +823,10 @@ pub struct RespPollYourVote {
 #[derive(Serialize, Clone)]
 pub struct RespCommunityModlogEvent<'a> {
     pub time: String,
+    pub by: Option<RespMinimalAuthorInfo<'a>>,
+    pub reason: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub community: Option<RespMinimalCommunityInfo<'a>>,
     #[serde(flatten)]
     pub details: RespCommunityModlogEventDetails<'a>,
 }
@@ -442,11 +837,23 @@ pub struct RespCommunityModlogEvent<'a> {
 pub enum RespCommunityModlogEventDetails<'a> {
     RejectPost { post: RespMinimalPostInfo<'a> },
     ApprovePost { post: RespMinimalPostInfo<'a> },
+    StickyPost { post: RespMinimalPostInfo<'a> },
+    UnstickyPost { post: RespMinimalPostInfo<'a> },
+    LockPost { post: RespMinimalPostInfo<'a> },
+    UnlockPost { post: RespMinimalPostInfo<'a> },
+    AddModerator { user: RespMinimalAuthorInfo<'a> },
+    RemoveModerator { user: RespMinimalAuthorInfo<'a> },
+    MuteUser { user: RespMinimalAuthorInfo<'a> },
+    UnmuteUser { user: RespMinimalAuthorInfo<'a> },
+    EditCommunity,
+    BlockDomain { domain: Cow<'a, str> },
+    UnblockDomain { domain: Cow<'a, str> },
 }
 
 #[derive(Serialize, Clone)]
 pub struct RespSiteModlogEvent<'a> {
     pub time: String,
+    pub by: Option<RespMinimalAuthorInfo<'a>>,
     #[serde(flatten)]
     pub details: RespSiteModlogEventDetails<'a>,
 }
@@ -469,6 +876,50 @@ pub enum RespSiteModlogEventDetails<'a> {
     UnsuspendUser {
         user: RespMinimalAuthorInfo<'a>,
     },
+    MuteUser {
+        user: RespMinimalAuthorInfo<'a>,
+    },
+    UnmuteUser {
+        user: RespMinimalAuthorInfo<'a>,
+    },
+    PurgeUserContent {
+        user: RespMinimalAuthorInfo<'a>,
+    },
+    BanUser {
+        user: RespMinimalAuthorInfo<'a>,
+    },
+    UnbanUser {
+        user: RespMinimalAuthorInfo<'a>,
+    },
+    BlockDomain {
+        domain: Cow<'a, str>,
+    },
+    UnblockDomain {
+        domain: Cow<'a, str>,
+    },
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespMuteInfo<'a> {
+    pub user: RespMinimalAuthorInfo<'a>,
+    pub by: Option<RespMinimalAuthorInfo<'a>>,
+    pub created_at: String,
+    pub expires_at: String,
+    pub reason: Option<Cow<'a, str>>,
+}
+
+#[derive(Serialize)]
+pub struct RespError<'a> {
+    pub code: &'a str,
+    pub message: Cow<'a, str>,
+}
+
+#[derive(Serialize)]
+pub struct RespMutedError<'a> {
+    #[serde(flatten)]
+    pub base: RespError<'a>,
+    pub community: Option<CommunityLocalID>,
+    pub expires_at: Cow<'a, str>,
 }
 
 #[derive(Serialize, Clone)]
@@ -484,3 +935,290 @@ pub struct RespInvitationInfo<'a> {
     pub created_at: String,
     pub used: bool,
 }
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommunityArchiveAuthor<'a> {
+    pub username: Cow<'a, str>,
+    pub host: Cow<'a, str>,
+    pub remote_url: Option<Cow<'a, str>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommunityArchiveCommunity<'a> {
+    pub name: Cow<'a, str>,
+    pub remote_url: Option<Cow<'a, str>>,
+    pub description_text: Option<Cow<'a, str>>,
+    pub description_markdown: Option<Cow<'a, str>>,
+    pub description_html: Option<Cow<'a, str>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommunityArchivePost<'a> {
+    pub id: PostLocalID,
+    pub remote_url: Option<Cow<'a, str>>,
+    pub author: Option<CommunityArchiveAuthor<'a>>,
+    pub title: Cow<'a, str>,
+    pub href: Option<Cow<'a, str>>,
+    pub content_text: Option<Cow<'a, str>>,
+    pub content_markdown: Option<Cow<'a, str>>,
+    pub content_html: Option<Cow<'a, str>>,
+    pub created: Cow<'a, str>,
+    pub sensitive: bool,
+    pub sticky: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommunityArchiveComment<'a> {
+    pub id: CommentLocalID,
+    pub post: PostLocalID,
+    pub parent: Option<CommentLocalID>,
+    pub remote_url: Option<Cow<'a, str>>,
+    pub author: Option<CommunityArchiveAuthor<'a>>,
+    pub content_text: Option<Cow<'a, str>>,
+    pub content_markdown: Option<Cow<'a, str>>,
+    pub content_html: Option<Cow<'a, str>>,
+    pub created: Cow<'a, str>,
+    pub sensitive: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommunityArchive<'a> {
+    pub version: u32,
+    pub community: CommunityArchiveCommunity<'a>,
+    pub posts: Vec<CommunityArchivePost<'a>>,
+    pub comments: Vec<CommunityArchiveComment<'a>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct HostQuirks {
+    pub no_shared_inbox: bool,
+    pub inline_objects: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct HostQuirkOverrides {
+    pub no_shared_inbox: Option<bool>,
+    pub inline_objects: Option<bool>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespRemoteHostInfo<'a> {
+    pub host: Cow<'a, str>,
+    pub software_name: Option<Cow<'a, str>>,
+    pub software_version: Option<Cow<'a, str>>,
+    pub detected_quirks: HostQuirks,
+    pub quirk_overrides: HostQuirkOverrides,
+    pub delivery_paused: bool,
+    pub blocked: bool,
+    pub unreachable_since: Option<String>,
+    pub consecutive_failures: i32,
+    pub last_delivery_at: Option<String>,
+    pub dead: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespPeerInfo<'a> {
+    pub host: Cow<'a, str>,
+    pub users: i64,
+    pub communities: i64,
+    pub software_name: Option<Cow<'a, str>>,
+    pub last_delivery_at: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespTaskQueueKindCount<'a> {
+    pub kind: Cow<'a, str>,
+    pub pending: i64,
+    pub retrying: i64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespTaskQueueHostCount<'a> {
+    pub host: Cow<'a, str>,
+    pub pending: i64,
+    pub delivery_paused: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespTaskQueueFailure<'a> {
+    pub kind: Cow<'a, str>,
+    pub error: Option<Cow<'a, str>>,
+    pub count: i64,
+    pub latest_at: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespTaskQueueInfo<'a> {
+    pub pending_by_kind: Vec<RespTaskQueueKindCount<'a>>,
+    pub pending_by_host: Vec<RespTaskQueueHostCount<'a>>,
+    pub oldest_pending_created_at: Option<String>,
+    pub oldest_pending_age_seconds: Option<i64>,
+    pub recent_failures: Vec<RespTaskQueueFailure<'a>>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespInstanceStatsBucket {
+    pub start: String,
+    pub posts: i64,
+    pub comments: i64,
+    pub registrations: i64,
+    pub deliveries: i64,
+    pub failed_deliveries: i64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespInstanceStats {
+    pub buckets: Vec<RespInstanceStatsBucket>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespVoteStat {
+    pub likes: i64,
+    pub dislikes: i64,
+    /// Votes from accounts with no other posts, comments, or votes
+    pub no_history: i64,
+    pub anomalous: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespVoteHostStat<'a> {
+    pub host: Cow<'a, str>,
+    #[serde(flatten)]
+    pub stat: RespVoteStat,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespVoteWindowStat {
+    pub start: Option<String>,
+    #[serde(flatten)]
+    pub stat: RespVoteStat,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespVoteAnalytics<'a> {
+    pub window_minutes: u32,
+    pub by_host: Vec<RespVoteHostStat<'a>>,
+    pub by_window: Vec<RespVoteWindowStat>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    NewPost,
+    NewReport,
+    NewRegistration,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::NewPost => "new_post",
+            WebhookEvent::NewReport => "new_report",
+            WebhookEvent::NewRegistration => "new_registration",
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEvent {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "new_post" => Ok(WebhookEvent::NewPost),
+            "new_report" => Ok(WebhookEvent::NewReport),
+            "new_registration" => Ok(WebhookEvent::NewRegistration),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespRelayInfo<'a> {
+    pub id: RelayID,
+    pub ap_id: Cow<'a, str>,
+    pub accepted: bool,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespWebhookInfo<'a> {
+    pub id: WebhookID,
+    pub url: Cow<'a, str>,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomodRuleKind {
+    Keyword,
+    Regex,
+    LinkDomain,
+    MinAccountAge,
+    RateLimit,
+}
+
+impl AutomodRuleKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutomodRuleKind::Keyword => "keyword",
+            AutomodRuleKind::Regex => "regex",
+            AutomodRuleKind::LinkDomain => "link_domain",
+            AutomodRuleKind::MinAccountAge => "min_account_age",
+            AutomodRuleKind::RateLimit => "rate_limit",
+        }
+    }
+}
+
+impl std::str::FromStr for AutomodRuleKind {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "keyword" => Ok(AutomodRuleKind::Keyword),
+            "regex" => Ok(AutomodRuleKind::Regex),
+            "link_domain" => Ok(AutomodRuleKind::LinkDomain),
+            "min_account_age" => Ok(AutomodRuleKind::MinAccountAge),
+            "rate_limit" => Ok(AutomodRuleKind::RateLimit),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Ordered from least to most severe
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomodAction {
+    Report,
+    Hold,
+    Reject,
+}
+
+impl AutomodAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutomodAction::Report => "report",
+            AutomodAction::Hold => "hold",
+            AutomodAction::Reject => "reject",
+        }
+    }
+}
+
+impl std::str::FromStr for AutomodAction {
+    type Err = ();
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "report" => Ok(AutomodAction::Report),
+            "hold" => Ok(AutomodAction::Hold),
+            "reject" => Ok(AutomodAction::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RespAutomodRuleInfo<'a> {
+    pub id: AutomodRuleID,
+    pub kind: AutomodRuleKind,
+    pub value: Cow<'a, str>,
+    pub action: AutomodAction,
+    pub created_at: String,
+}